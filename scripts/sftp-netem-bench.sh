@@ -0,0 +1,40 @@
+#!/bin/bash
+# Benchmark sequential vs pipelined SFTP downloads against a local sshd with
+# added round-trip latency on loopback (tc netem, needs root for tc).
+#
+# Usage: sudo -E ./scripts/sftp-netem-bench.sh [delay_ms] [size_mb]
+#
+# SFTP_BENCH_USER (default: $SUDO_USER or $USER) must be able to log in to
+# 127.0.0.1 with SFTP_BENCH_KEY (default: ~/.ssh/id_ed25519) or SFTP_BENCH_PASSWORD.
+
+set -euo pipefail
+
+DELAY_MS="${1:-25}"
+SIZE_MB="${2:-64}"
+DEV="lo"
+
+export SFTP_BENCH_HOST="${SFTP_BENCH_HOST:-127.0.0.1}"
+export SFTP_BENCH_USER="${SFTP_BENCH_USER:-${SUDO_USER:-$USER}}"
+export SFTP_BENCH_FILE="${SFTP_BENCH_FILE:-/tmp/sftp-bench.bin}"
+if [[ -z "${SFTP_BENCH_PASSWORD:-}" ]]; then
+    export SFTP_BENCH_KEY="${SFTP_BENCH_KEY:-$(eval echo "~$SFTP_BENCH_USER")/.ssh/id_ed25519}"
+fi
+
+cleanup() {
+    tc qdisc del dev "$DEV" root netem 2>/dev/null || true
+}
+trap cleanup EXIT
+
+echo "Creating ${SIZE_MB} MB test file at $SFTP_BENCH_FILE"
+head -c "$((SIZE_MB * 1024 * 1024))" /dev/urandom > "$SFTP_BENCH_FILE"
+chmod 644 "$SFTP_BENCH_FILE"
+
+# Build first so compile time is not spent with the delay in place
+cd "$(dirname "$0")/../src-tauri"
+cargo test --release --lib --no-run
+
+# netem delays each direction, so halve it for the requested round trip
+echo "Adding ${DELAY_MS} ms round-trip delay on $DEV"
+tc qdisc add dev "$DEV" root netem delay "$((DELAY_MS / 2))ms"
+
+cargo test --release --lib sftp_pipeline_bench -- --ignored --nocapture
@@ -39,11 +39,11 @@ const MAGIC: &[u8; 10] = b"AEROVAULT2";
 /// Current format version
 const VERSION: u8 = 2;
 /// Default chunk size (64KB)
-const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+pub(crate) const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
 /// Nonce size for AES-GCM-SIV (96 bits)
-const NONCE_SIZE: usize = 12;
+pub(crate) const NONCE_SIZE: usize = 12;
 /// Authentication tag size (128 bits)
-const TAG_SIZE: usize = 16;
+pub(crate) const TAG_SIZE: usize = 16;
 /// Master key size (256 bits)
 const MASTER_KEY_SIZE: usize = 32;
 /// MAC key size (256 bits)
@@ -53,7 +53,7 @@ const SALT_SIZE: usize = 32;
 /// Wrapped key size (32-byte key + 8-byte AES-KW overhead = 40 bytes)
 const WRAPPED_KEY_SIZE: usize = 40;
 /// Header size (fixed)
-const HEADER_SIZE: usize = 512;
+pub(crate) const HEADER_SIZE: usize = 512;
 
 // Argon2id parameters (OWASP 2024 high security)
 const ARGON2_M_COST: u32 = 128 * 1024; // 128 MiB
@@ -339,7 +339,7 @@ pub struct UnlockedVaultV2 {
 // ============================================================================
 
 /// Derive encryption key from password using Argon2id (OWASP 2024 params)
-fn derive_key(password: &SecretString, salt: &[u8]) -> Result<SecretBox<Vec<u8>>, String> {
+pub(crate) fn derive_key(password: &SecretString, salt: &[u8]) -> Result<SecretBox<Vec<u8>>, String> {
     let params = Params::new(
         ARGON2_M_COST,
         ARGON2_T_COST,
@@ -418,7 +418,21 @@ fn derive_siv_key(master_key: &[u8]) -> [u8; 64] {
 }
 
 /// Encrypt filename using AES-SIV (deterministic, no IV needed)
-fn encrypt_filename(key: &[u8], filename: &str) -> Result<String, String> {
+pub(crate) fn encrypt_filename(key: &[u8], filename: &str) -> Result<String, String> {
+    let ciphertext = siv_encrypt_name(key, filename)?;
+    Ok(data_encoding::BASE64URL_NOPAD.encode(&ciphertext))
+}
+
+/// Decrypt filename using AES-SIV
+pub(crate) fn decrypt_filename(key: &[u8], encrypted: &str) -> Result<String, String> {
+    let ciphertext = data_encoding::BASE64URL_NOPAD
+        .decode(encrypted.as_bytes())
+        .map_err(|_| "Invalid base64")?;
+    siv_decrypt_name(key, &ciphertext)
+}
+
+/// AES-SIV ciphertext of a filename, for callers that pick their own encoding
+pub(crate) fn siv_encrypt_name(key: &[u8], filename: &str) -> Result<Vec<u8>, String> {
     // AES-SIV-AEAD needs 64 bytes key (two 32-byte keys for encryption and MAC)
     // Use HKDF to properly derive the full key from the 32-byte master key
     let mut full_key = derive_siv_key(key);
@@ -429,20 +443,16 @@ fn encrypt_filename(key: &[u8], filename: &str) -> Result<String, String> {
     // Use empty nonce for deterministic encryption
     let nonce = aes_siv::Nonce::default();
     let ciphertext = cipher.encrypt(&nonce, filename.as_bytes())
-        .map_err(|e| format!("Filename encryption failed: {:?}", e))?;
+        .map_err(|e| format!("Filename encryption failed: {:?}", e));
 
     // Zeroize the derived key after use
     full_key.zeroize();
 
-    Ok(data_encoding::BASE64URL_NOPAD.encode(&ciphertext))
+    ciphertext
 }
 
-/// Decrypt filename using AES-SIV
-fn decrypt_filename(key: &[u8], encrypted: &str) -> Result<String, String> {
-    let ciphertext = data_encoding::BASE64URL_NOPAD
-        .decode(encrypted.as_bytes())
-        .map_err(|_| "Invalid base64")?;
-
+/// Inverse of [`siv_encrypt_name`]
+pub(crate) fn siv_decrypt_name(key: &[u8], ciphertext: &[u8]) -> Result<String, String> {
     // Use HKDF to properly derive the full key
     let mut full_key = derive_siv_key(key);
 
@@ -450,13 +460,13 @@ fn decrypt_filename(key: &[u8], encrypted: &str) -> Result<String, String> {
         .map_err(|_| "Invalid SIV key")?;
 
     let nonce = aes_siv::Nonce::default();
-    let plaintext = cipher.decrypt(&nonce, ciphertext.as_ref())
-        .map_err(|_| "Filename decryption failed - wrong password?")?;
+    let plaintext = cipher.decrypt(&nonce, ciphertext)
+        .map_err(|_| "Filename decryption failed - wrong password?".to_string());
 
     // Zeroize the derived key after use
     full_key.zeroize();
 
-    String::from_utf8(plaintext)
+    String::from_utf8(plaintext?)
         .map_err(|_| "Invalid UTF-8 in filename".into())
 }
 
@@ -466,7 +476,7 @@ fn decrypt_filename(key: &[u8], encrypted: &str) -> Result<String, String> {
 
 /// Encrypt a chunk using AES-256-GCM-SIV with chunk index in AAD.
 /// GAP-D03: Chunk index bound to AAD prevents chunk reordering/duplication attacks.
-fn encrypt_chunk(key: &[u8], chunk: &[u8], chunk_index: u32) -> Result<Vec<u8>, String> {
+pub(crate) fn encrypt_chunk(key: &[u8], chunk: &[u8], chunk_index: u32) -> Result<Vec<u8>, String> {
     let cipher = Aes256GcmSiv::new_from_slice(key)
         .map_err(|_| "Invalid encryption key")?;
 
@@ -492,7 +502,7 @@ fn encrypt_chunk(key: &[u8], chunk: &[u8], chunk_index: u32) -> Result<Vec<u8>,
 
 /// Decrypt a chunk using AES-256-GCM-SIV with chunk index in AAD.
 /// GAP-D03: Chunk index verified via AAD — tampered index causes auth failure.
-fn decrypt_chunk(key: &[u8], encrypted: &[u8], chunk_index: u32) -> Result<Vec<u8>, String> {
+pub(crate) fn decrypt_chunk(key: &[u8], encrypted: &[u8], chunk_index: u32) -> Result<Vec<u8>, String> {
     if encrypted.len() < NONCE_SIZE + TAG_SIZE {
         return Err("Encrypted chunk too small".into());
     }
@@ -555,6 +565,129 @@ fn decrypt_chunk_cascade(aes_key: &[u8], chacha_key: &[u8], encrypted: &[u8], ch
     decrypt_chunk(aes_key, &aes_encrypted, chunk_index)
 }
 
+// ============================================================================
+// Ranged Access (remote vaults)
+// ============================================================================
+
+/// Per-chunk encryption overhead in the data section, excluding the 4-byte length prefix.
+/// Standard mode: GCM-SIV nonce + tag. Cascade mode adds the ChaCha20-Poly1305 nonce + tag.
+fn chunk_overhead(cascade_mode: bool) -> u64 {
+    let standard = (NONCE_SIZE + TAG_SIZE) as u64;
+    if cascade_mode { standard + 12 + 16 } else { standard }
+}
+
+/// A vault unlocked from its header and manifest bytes only.
+/// Used for remote vaults, where the data section is fetched on demand via ranged reads.
+pub(crate) struct VaultIndex {
+    pub header: VaultHeader,
+    pub master_key: SecretBox<Vec<u8>>,
+    chacha_key: [u8; 32],
+    pub manifest: VaultManifest,
+    /// Absolute offset of the data section in the vault file
+    pub data_start: u64,
+}
+
+impl VaultIndex {
+    /// Parse the manifest length from the first `HEADER_SIZE + 4` bytes of a vault (H9 bound applied).
+    pub(crate) fn manifest_len(prefix: &[u8]) -> Result<usize, String> {
+        if prefix.len() < HEADER_SIZE + 4 {
+            return Err("Invalid vault file: too small".into());
+        }
+        let manifest_len = u32::from_le_bytes([
+            prefix[HEADER_SIZE], prefix[HEADER_SIZE + 1], prefix[HEADER_SIZE + 2], prefix[HEADER_SIZE + 3]
+        ]) as usize;
+        validate_manifest_len(manifest_len)?;
+        Ok(manifest_len)
+    }
+
+    /// Unlock from raw header bytes and the encrypted manifest that follows them.
+    pub(crate) fn unlock(
+        header_buf: &[u8],
+        manifest_encrypted: &[u8],
+        password: &SecretString,
+    ) -> Result<Self, String> {
+        let header = VaultHeader::from_bytes(header_buf)?;
+
+        // Derive keys
+        let base_kek = derive_key(password, &header.salt)?;
+        let (mut kek_master, mut kek_mac) = derive_kek_pair(base_kek.expose_secret());
+
+        let master_key = unwrap_key(&kek_master, &header.wrapped_master_key)?;
+        let mac_key = unwrap_key(&kek_mac, &header.wrapped_mac_key)?;
+
+        kek_master.zeroize();
+        kek_mac.zeroize();
+
+        // Verify header MAC (constant-time comparison — H10 fix)
+        verify_header_mac(&header, mac_key.expose_secret())?;
+
+        let manifest_json = decrypt_filename(
+            master_key.expose_secret(),
+            &String::from_utf8_lossy(manifest_encrypted),
+        )?;
+
+        let mut manifest: VaultManifest = serde_json::from_str(&manifest_json)
+            .map_err(|e| format!("Failed to parse manifest: {}", e))?;
+
+        for entry in &mut manifest.entries {
+            entry.name = decrypt_filename(master_key.expose_secret(), &entry.encrypted_name)?;
+        }
+
+        let chacha_key = if header.flags.cascade_mode {
+            derive_chacha_key(master_key.expose_secret())
+        } else {
+            [0u8; 32]
+        };
+
+        Ok(Self {
+            header,
+            master_key,
+            chacha_key,
+            manifest,
+            data_start: HEADER_SIZE as u64 + 4 + manifest_encrypted.len() as u64,
+        })
+    }
+
+    /// Find an entry by its decrypted name
+    pub(crate) fn find_entry(&self, name: &str) -> Option<&ManifestEntry> {
+        self.manifest.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Absolute byte range `(offset, len)` covering all chunks of an entry.
+    /// Every chunk carries the same framing overhead, so the encrypted length
+    /// follows from the plaintext size and chunk count alone.
+    pub(crate) fn entry_range(&self, entry: &ManifestEntry) -> (u64, u64) {
+        if entry.is_dir || entry.chunk_count == 0 {
+            return (self.data_start + entry.offset, 0);
+        }
+        let framing = 4 + chunk_overhead(self.header.flags.cascade_mode);
+        (
+            self.data_start + entry.offset,
+            entry.chunk_count as u64 * framing + entry.size,
+        )
+    }
+
+    /// Largest encrypted chunk that can appear in this vault (sanity bound for length prefixes)
+    pub(crate) fn max_encrypted_chunk_len(&self) -> usize {
+        self.header.chunk_size as usize + chunk_overhead(self.header.flags.cascade_mode) as usize
+    }
+
+    /// Decrypt one data chunk (chunk index verified via AAD)
+    pub(crate) fn decrypt_chunk(&self, encrypted: &[u8], chunk_index: u32) -> Result<Vec<u8>, String> {
+        if self.header.flags.cascade_mode {
+            decrypt_chunk_cascade(self.master_key.expose_secret(), &self.chacha_key, encrypted, chunk_index)
+        } else {
+            decrypt_chunk(self.master_key.expose_secret(), encrypted, chunk_index)
+        }
+    }
+}
+
+impl Drop for VaultIndex {
+    fn drop(&mut self) {
+        self.chacha_key.zeroize();
+    }
+}
+
 // ============================================================================
 // Tauri Commands
 // ============================================================================
@@ -2293,6 +2426,452 @@ pub async fn vault_v2_compact(
     })
 }
 
+// ============================================================================
+// Vault Integrity Scrub
+// ============================================================================
+
+/// Health of a single manifest entry after a scrub
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryHealth {
+    /// All chunks authenticated and the stored length matches the manifest
+    Ok,
+    /// Chunk framing is intact but one or more chunks failed authentication
+    Damaged,
+    /// A chunk length prefix is invalid or runs past the end of the data section
+    Truncated,
+    /// The manifest offset points outside the data section
+    OutOfBounds,
+    /// The encrypted filename could not be decrypted
+    BadName,
+}
+
+/// Per-entry scrub result
+#[derive(Serialize)]
+pub struct EntryVerifyReport {
+    /// Decrypted name (encrypted name if it could not be decrypted)
+    pub name: String,
+    pub is_dir: bool,
+    pub health: EntryHealth,
+    /// Plaintext size recorded in the manifest
+    pub size: u64,
+    pub chunk_count: u32,
+    /// Indices of chunks that failed authentication or could not be read
+    pub corrupted_chunks: Vec<u32>,
+    /// Offset of the entry in the data section
+    pub offset: u64,
+    /// Bytes the entry occupies according to the chunk length prefixes
+    pub stored_len: u64,
+    /// Bytes the entry should occupy according to the manifest
+    pub expected_len: u64,
+}
+
+/// A byte range of the data section not referenced by any manifest entry
+#[derive(Serialize)]
+pub struct OrphanedRegion {
+    /// Offset in the data section
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// Result of writing intact entries into a new vault
+#[derive(Serialize)]
+pub struct SalvageResult {
+    pub path: String,
+    /// Number of entries (files and directories) copied
+    pub recovered: usize,
+    /// Names of entries left behind because they were not intact
+    pub skipped: Vec<String>,
+    /// The source header failed its MAC and was re-signed in the copy (`repair_header`)
+    pub header_repaired: bool,
+}
+
+/// Full scrub report returned by `vault_v2_verify`
+#[derive(Serialize)]
+pub struct VerifyReport {
+    /// Header HMAC matched (the scrub continues on mismatch as long as the keys unwrap)
+    pub header_valid: bool,
+    pub cascade_mode: bool,
+    /// Size of the data section in bytes
+    pub data_size: u64,
+    pub entries: Vec<EntryVerifyReport>,
+    pub healthy_count: usize,
+    pub damaged_count: usize,
+    /// Unreferenced data, e.g. left behind by deletions (reclaimable with `vault_v2_compact`)
+    pub orphaned_regions: Vec<OrphanedRegion>,
+    pub orphaned_bytes: u64,
+    /// Manifest entries whose offsets or sizes disagree with the data section
+    pub mismatches: Vec<String>,
+    pub salvage: Option<SalvageResult>,
+}
+
+/// Outcome of walking one entry's chunk stream
+struct EntryScan {
+    health: EntryHealth,
+    corrupted: Vec<u32>,
+    /// Bytes consumed in the data section
+    stored_len: u64,
+    /// Bytes of plaintext recovered from authenticated chunks
+    plaintext_len: u64,
+}
+
+/// Walk the chunk stream of one entry, authenticating every chunk.
+fn scrub_entry<R, F>(
+    reader: &mut R,
+    entry: &ManifestEntry,
+    data_start: u64,
+    data_size: u64,
+    max_chunk_len: usize,
+    decrypt: F,
+) -> Result<EntryScan, String>
+where
+    R: Read + std::io::Seek,
+    F: Fn(&[u8], u32) -> Result<Vec<u8>, String>,
+{
+    if entry.offset > data_size {
+        return Ok(EntryScan {
+            health: EntryHealth::OutOfBounds,
+            corrupted: (0..entry.chunk_count).collect(),
+            stored_len: 0,
+            plaintext_len: 0,
+        });
+    }
+
+    reader.seek(std::io::SeekFrom::Start(data_start + entry.offset))
+        .map_err(|e| format!("Failed to seek to entry data: {}", e))?;
+
+    let mut corrupted = Vec::new();
+    let mut pos = entry.offset;
+    let mut plaintext_len = 0u64;
+
+    for chunk_idx in 0..entry.chunk_count {
+        if pos + 4 > data_size {
+            corrupted.extend(chunk_idx..entry.chunk_count);
+            return Ok(EntryScan {
+                health: EntryHealth::Truncated,
+                corrupted,
+                stored_len: pos - entry.offset,
+                plaintext_len,
+            });
+        }
+        let mut len_buf = [0u8; 4];
+        reader.read_exact(&mut len_buf)
+            .map_err(|e| format!("Failed to read chunk length: {}", e))?;
+        let chunk_len = u32::from_le_bytes(len_buf) as usize;
+
+        // A bad length prefix desynchronizes the rest of the entry
+        if chunk_len == 0 || chunk_len > max_chunk_len || pos + 4 + chunk_len as u64 > data_size {
+            corrupted.extend(chunk_idx..entry.chunk_count);
+            return Ok(EntryScan {
+                health: EntryHealth::Truncated,
+                corrupted,
+                stored_len: pos - entry.offset,
+                plaintext_len,
+            });
+        }
+
+        let mut encrypted_chunk = vec![0u8; chunk_len];
+        reader.read_exact(&mut encrypted_chunk)
+            .map_err(|e| format!("Failed to read chunk data: {}", e))?;
+
+        match decrypt(&encrypted_chunk, chunk_idx) {
+            Ok(mut plaintext) => {
+                plaintext_len += plaintext.len() as u64;
+                plaintext.zeroize();
+            }
+            Err(_) => corrupted.push(chunk_idx),
+        }
+        pos += 4 + chunk_len as u64;
+    }
+
+    let health = if corrupted.is_empty() { EntryHealth::Ok } else { EntryHealth::Damaged };
+    Ok(EntryScan { health, corrupted, stored_len: pos - entry.offset, plaintext_len })
+}
+
+/// Scrub an AeroVault v2: authenticate every chunk of every entry and report
+/// per-entry health, unreferenced data regions and manifest/offset mismatches.
+///
+/// The vault itself is never modified. When `salvage_path` is given, every
+/// intact entry is copied (chunks verbatim, offsets rebuilt) into a new vault
+/// at that path, which opens with the same password. A header with a bad MAC
+/// is only re-signed in the salvaged copy when `repair_header` is set; without
+/// it the salvage is refused so a tampered header is never silently blessed.
+#[tauri::command]
+pub async fn vault_v2_verify(
+    vault_path: String,
+    password: String,
+    salvage_path: Option<String>,
+    repair_header: Option<bool>,
+) -> Result<VerifyReport, String> {
+    use std::io::Seek;
+
+    let pwd = SecretString::from(password);
+
+    if let Some(ref dest) = salvage_path {
+        if std::path::Path::new(dest).exists() {
+            return Err(format!("Salvage destination already exists: {}", dest));
+        }
+    }
+
+    let file = File::open(&vault_path)
+        .map_err(|e| format!("Failed to open vault: {}", e))?;
+    let file_size = file.metadata()
+        .map_err(|e| format!("Failed to stat vault: {}", e))?
+        .len();
+    let mut reader = BufReader::new(file);
+
+    // Read header
+    let mut header_buf = [0u8; HEADER_SIZE];
+    reader.read_exact(&mut header_buf)
+        .map_err(|e| format!("Failed to read header: {}", e))?;
+
+    let mut header = VaultHeader::from_bytes(&header_buf)?;
+    let cascade_mode = header.flags.cascade_mode;
+
+    // Derive keys (a wrong password fails here via the AES-KW integrity check)
+    let base_kek = derive_key(&pwd, &header.salt)?;
+    let (mut kek_master, mut kek_mac) = derive_kek_pair(base_kek.expose_secret());
+
+    let master_key = unwrap_key(&kek_master, &header.wrapped_master_key)?;
+    let mac_key = unwrap_key(&kek_mac, &header.wrapped_mac_key)?;
+
+    kek_master.zeroize();
+    kek_mac.zeroize();
+
+    // Header MAC mismatch is reported rather than fatal (constant-time comparison — H10 fix)
+    let header_valid = verify_header_mac(&header, mac_key.expose_secret()).is_ok();
+    let repair_header = repair_header.unwrap_or(false);
+    if salvage_path.is_some() && !header_valid && !repair_header {
+        return Err(
+            "Header integrity check failed. Verify without a salvage path to inspect the vault, \
+             or salvage with repair_header to re-sign the header in the copy".into()
+        );
+    }
+
+    let mut chacha_key = if cascade_mode {
+        derive_chacha_key(master_key.expose_secret())
+    } else {
+        [0u8; 32]
+    };
+
+    // Read manifest (bounded allocation — H9 fix). Without it nothing else can be located.
+    let (manifest_len, mut manifest_encrypted) = read_manifest_bounded(&mut reader)?;
+    let manifest_json = decrypt_filename(
+        master_key.expose_secret(),
+        &String::from_utf8_lossy(&manifest_encrypted),
+    ).map_err(|e| format!("Manifest is unreadable: {}", e))?;
+    manifest_encrypted.zeroize();
+
+    let mut manifest: VaultManifest = serde_json::from_str(&manifest_json)
+        .map_err(|e| format!("Failed to parse manifest: {}", e))?;
+
+    let data_start = HEADER_SIZE as u64 + 4 + manifest_len as u64;
+    let data_size = file_size.saturating_sub(data_start);
+    let framing = 4 + chunk_overhead(cascade_mode);
+    let max_chunk_len = header.chunk_size as usize + chunk_overhead(cascade_mode) as usize;
+
+    let decrypt = |encrypted: &[u8], chunk_idx: u32| -> Result<Vec<u8>, String> {
+        if cascade_mode {
+            decrypt_chunk_cascade(master_key.expose_secret(), &chacha_key, encrypted, chunk_idx)
+        } else {
+            decrypt_chunk(master_key.expose_secret(), encrypted, chunk_idx)
+        }
+    };
+
+    let mut entries = Vec::with_capacity(manifest.entries.len());
+    let mut mismatches = Vec::new();
+    // (offset, stored_len, entry index) of every file entry that lies inside the data section
+    let mut referenced: Vec<(u64, u64, usize)> = Vec::new();
+
+    for (idx, entry) in manifest.entries.iter_mut().enumerate() {
+        let name_result = decrypt_filename(master_key.expose_secret(), &entry.encrypted_name);
+        entry.name = name_result.clone().unwrap_or_else(|_| entry.encrypted_name.clone());
+
+        let expected_len = if entry.is_dir || entry.chunk_count == 0 {
+            0
+        } else {
+            entry.chunk_count as u64 * framing + entry.size
+        };
+
+        let (mut health, corrupted_chunks, stored_len) = if entry.is_dir || entry.chunk_count == 0 {
+            if !entry.is_dir && entry.size > 0 {
+                mismatches.push(format!(
+                    "'{}': manifest size is {} bytes but no chunks are recorded",
+                    entry.name, entry.size
+                ));
+            }
+            (EntryHealth::Ok, Vec::new(), 0)
+        } else {
+            let scan = scrub_entry(
+                &mut reader, entry, data_start, data_size, max_chunk_len, decrypt,
+            )?;
+            match scan.health {
+                EntryHealth::OutOfBounds => mismatches.push(format!(
+                    "'{}': offset {} is beyond the end of the data section ({} bytes)",
+                    entry.name, entry.offset, data_size
+                )),
+                EntryHealth::Ok if scan.plaintext_len != entry.size => mismatches.push(format!(
+                    "'{}': manifest size is {} bytes but chunks decrypt to {} bytes",
+                    entry.name, entry.size, scan.plaintext_len
+                )),
+                _ => {}
+            }
+            if scan.health != EntryHealth::OutOfBounds {
+                referenced.push((entry.offset, scan.stored_len, idx));
+            }
+            (scan.health, scan.corrupted, scan.stored_len)
+        };
+
+        if name_result.is_err() && health == EntryHealth::Ok {
+            health = EntryHealth::BadName;
+        }
+
+        entries.push(EntryVerifyReport {
+            name: entry.name.clone(),
+            is_dir: entry.is_dir,
+            health,
+            size: entry.size,
+            chunk_count: entry.chunk_count,
+            corrupted_chunks,
+            offset: entry.offset,
+            stored_len,
+            expected_len,
+        });
+    }
+
+    // Overlapping entries and unreferenced gaps in the data section
+    referenced.sort_unstable();
+    let mut orphaned_regions = Vec::new();
+    let mut cursor = 0u64;
+    let mut prev: Option<usize> = None;
+    for &(offset, len, idx) in &referenced {
+        if offset < cursor {
+            if let Some(p) = prev {
+                mismatches.push(format!(
+                    "'{}' (offset {}) overlaps '{}'",
+                    entries[idx].name, offset, entries[p].name
+                ));
+            }
+        } else if offset > cursor {
+            orphaned_regions.push(OrphanedRegion { offset: cursor, length: offset - cursor });
+        }
+        if offset + len >= cursor {
+            cursor = offset + len;
+            prev = Some(idx);
+        }
+    }
+    if data_size > cursor {
+        orphaned_regions.push(OrphanedRegion { offset: cursor, length: data_size - cursor });
+    }
+    let orphaned_bytes = orphaned_regions.iter().map(|r| r.length).sum();
+
+    let healthy_count = entries.iter().filter(|e| e.health == EntryHealth::Ok).count();
+    let damaged_count = entries.len() - healthy_count;
+
+    // Salvage intact entries into a new vault
+    let salvage = if let Some(dest) = salvage_path {
+        let mut salvaged = manifest.clone();
+        salvaged.entries.clear();
+        // (source offset, length) of every entry copied into the data section
+        let mut copies: Vec<(u64, u64)> = Vec::new();
+        let mut data_len = 0u64;
+        let mut skipped = Vec::new();
+
+        for (entry, report) in manifest.entries.iter().zip(&entries) {
+            if report.health != EntryHealth::Ok {
+                skipped.push(report.name.clone());
+                continue;
+            }
+            let mut kept = entry.clone();
+            if entry.is_dir || entry.chunk_count == 0 {
+                kept.offset = 0;
+            } else {
+                kept.offset = data_len;
+                copies.push((entry.offset, report.stored_len));
+                data_len += report.stored_len;
+            }
+            salvaged.entries.push(kept);
+        }
+
+        salvaged.modified = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let salvaged_json = serde_json::to_string(&salvaged)
+            .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+        let encrypted_manifest = encrypt_filename(master_key.expose_secret(), &salvaged_json)?;
+        let manifest_bytes = encrypted_manifest.as_bytes();
+
+        let header_repaired = !header_valid;
+        if header_repaired {
+            tracing::warn!("Vault {}: re-signing header with a bad MAC in salvage {}", vault_path, dest);
+            header.header_mac = header.compute_mac(mac_key.expose_secret());
+        }
+
+        let tmp_path = format!("{}.salvage.tmp", dest);
+        let write_result: Result<(), String> = (|| {
+            let tmp_file = File::create(&tmp_path)
+                .map_err(|e| format!("Failed to create salvage file: {}", e))?;
+            let mut writer = BufWriter::new(tmp_file);
+
+            writer.write_all(&header.to_bytes())
+                .map_err(|e| format!("Failed to write header: {}", e))?;
+            writer.write_all(&(manifest_bytes.len() as u32).to_le_bytes())
+                .map_err(|e| format!("Failed to write manifest length: {}", e))?;
+            writer.write_all(manifest_bytes)
+                .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+            // Chunks authenticated above and their AAD carries only the chunk
+            // index, so they are streamed across without re-encryption
+            for &(offset, len) in &copies {
+                reader.seek(std::io::SeekFrom::Start(data_start + offset))
+                    .map_err(|e| format!("Failed to seek to entry data: {}", e))?;
+                let copied = std::io::copy(&mut (&mut reader).take(len), &mut writer)
+                    .map_err(|e| format!("Failed to copy entry data: {}", e))?;
+                if copied != len {
+                    return Err("Vault data changed during salvage".into());
+                }
+            }
+
+            writer.flush()
+                .map_err(|e| format!("Failed to flush salvage file: {}", e))?;
+            writer.get_ref().sync_all()
+                .map_err(|e| format!("Failed to sync salvage file: {}", e))
+        })();
+
+        if let Err(e) = write_result {
+            let _ = std::fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+
+        std::fs::rename(&tmp_path, &dest).map_err(|e| {
+            let _ = std::fs::remove_file(&tmp_path);
+            format!("Failed to finalize salvage file: {}", e)
+        })?;
+
+        Some(SalvageResult {
+            path: dest,
+            recovered: salvaged.entries.len(),
+            skipped,
+            header_repaired,
+        })
+    } else {
+        None
+    };
+
+    chacha_key.zeroize();
+
+    Ok(VerifyReport {
+        header_valid,
+        cascade_mode,
+        data_size,
+        entries,
+        healthy_count,
+        damaged_count,
+        orphaned_regions,
+        orphaned_bytes,
+        mismatches,
+        salvage,
+    })
+}
+
 // ============================================================================
 // Vault Bidirectional Sync
 // ============================================================================
@@ -2730,3 +3309,100 @@ pub async fn vault_v2_sync_apply(
         errors,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "correct horse battery staple";
+
+    /// Create a vault holding `files` (name, content) in `dir` and return its path
+    async fn make_vault(dir: &std::path::Path, files: &[(&str, Vec<u8>)]) -> String {
+        let vault_path = dir.join("test.aerovault").to_string_lossy().to_string();
+        vault_v2_create(vault_path.clone(), PASSWORD.into(), None, false).await.unwrap();
+
+        let mut sources = Vec::new();
+        for (name, content) in files {
+            let source = dir.join(name);
+            std::fs::write(&source, content).unwrap();
+            sources.push(source.to_string_lossy().to_string());
+        }
+        vault_v2_add_files(vault_path.clone(), PASSWORD.into(), sources).await.unwrap();
+        vault_path
+    }
+
+    /// Offset of the data section in a vault file
+    fn data_start(bytes: &[u8]) -> usize {
+        let len = &bytes[HEADER_SIZE..HEADER_SIZE + 4];
+        HEADER_SIZE + 4 + u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize
+    }
+
+    fn content(len: usize, seed: u8) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8 ^ seed).collect()
+    }
+
+    #[tokio::test]
+    async fn test_verify_reports_and_salvages_around_a_corrupt_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = make_vault(dir.path(), &[
+            ("damaged.bin", content(3 * DEFAULT_CHUNK_SIZE, 1)),
+            ("intact.bin", content(1000, 2)),
+        ]).await;
+
+        // Flip one ciphertext byte in the second chunk of the first entry (offset 0)
+        let mut bytes = std::fs::read(&vault).unwrap();
+        let second_chunk = data_start(&bytes) + 4 + DEFAULT_CHUNK_SIZE + chunk_overhead(false) as usize;
+        bytes[second_chunk + 4 + 40] ^= 0x01;
+        std::fs::write(&vault, &bytes).unwrap();
+
+        let salvage = dir.path().join("salvaged.aerovault").to_string_lossy().to_string();
+        let report = vault_v2_verify(vault.clone(), PASSWORD.into(), Some(salvage.clone()), None).await.unwrap();
+        assert!(report.header_valid);
+        assert_eq!((report.healthy_count, report.damaged_count), (1, 1));
+
+        let damaged = report.entries.iter().find(|e| e.name == "damaged.bin").unwrap();
+        assert_eq!(damaged.health, EntryHealth::Damaged);
+        assert_eq!(damaged.corrupted_chunks, vec![1]);
+
+        let result = report.salvage.unwrap();
+        assert_eq!(result.recovered, 1);
+        assert_eq!(result.skipped, vec!["damaged.bin".to_string()]);
+        assert!(!result.header_repaired);
+        assert!(!std::path::Path::new(&format!("{}.salvage.tmp", salvage)).exists());
+
+        // The salvaged vault is clean and opens with the same password
+        let salvaged = vault_v2_verify(salvage, PASSWORD.into(), None, None).await.unwrap();
+        assert!(salvaged.header_valid);
+        assert_eq!((salvaged.healthy_count, salvaged.damaged_count), (1, 0));
+        assert_eq!(salvaged.entries[0].name, "intact.bin");
+        assert_eq!(salvaged.orphaned_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_verify_requires_explicit_header_repair() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = make_vault(dir.path(), &[("file.bin", content(5000, 3))]).await;
+
+        // Corrupt the stored header MAC
+        let mut bytes = std::fs::read(&vault).unwrap();
+        bytes[HEADER_SIZE - 1] ^= 0xff;
+        std::fs::write(&vault, &bytes).unwrap();
+
+        let report = vault_v2_verify(vault.clone(), PASSWORD.into(), None, None).await.unwrap();
+        assert!(!report.header_valid);
+        assert_eq!(report.healthy_count, 1);
+
+        // Salvaging a vault with a bad header needs explicit consent
+        let salvage = dir.path().join("salvaged.aerovault").to_string_lossy().to_string();
+        let err = vault_v2_verify(vault.clone(), PASSWORD.into(), Some(salvage.clone()), None).await.err().unwrap();
+        assert!(err.contains("Header integrity check failed"), "{}", err);
+        assert!(!std::path::Path::new(&salvage).exists());
+
+        let report = vault_v2_verify(vault, PASSWORD.into(), Some(salvage.clone()), Some(true)).await.unwrap();
+        assert!(report.salvage.unwrap().header_repaired);
+
+        let repaired = vault_v2_verify(salvage, PASSWORD.into(), None, None).await.unwrap();
+        assert!(repaired.header_valid);
+        assert_eq!(repaired.healthy_count, 1);
+    }
+}
@@ -3,7 +3,9 @@
 #![allow(dead_code)]
 
 use chrono::{DateTime, Utc};
+use crate::sync::FileInfo;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -201,6 +203,76 @@ pub fn save_cloud_config(config: &CloudConfig) -> Result<(), String> {
     Ok(())
 }
 
+/// Remote listing kept between syncs so providers with a change feed only
+/// fetch what changed since `change_token` instead of walking the whole tree
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RemoteSnapshot {
+    /// Connection the snapshot belongs to (see `remote_snapshot_key`)
+    pub key: String,
+    /// Provider change token the snapshot is current up to
+    pub change_token: String,
+    /// Relative path -> remote file info
+    pub files: HashMap<String, FileInfo>,
+    /// Provider item ID -> relative path ("" is the remote folder itself)
+    #[serde(default)]
+    pub ids: HashMap<String, String>,
+}
+
+/// Anything that changes which remote entries end up in the listing invalidates the snapshot
+pub fn remote_snapshot_key(config: &CloudConfig) -> String {
+    format!(
+        "{}|{}|{}|{}|{}",
+        config.protocol_type,
+        config.server_profile,
+        config.cloud_name,
+        config.remote_folder,
+        config.exclude_patterns.join(",")
+    )
+}
+
+fn get_snapshot_path() -> PathBuf {
+    get_config_path().with_file_name("cloud_remote_snapshot.json")
+}
+
+/// Load the remote snapshot if it was taken for the same connection
+pub fn load_remote_snapshot(key: &str) -> Option<RemoteSnapshot> {
+    let content = fs::read_to_string(get_snapshot_path()).ok()?;
+    match serde_json::from_str::<RemoteSnapshot>(&content) {
+        Ok(snapshot) if snapshot.key == key => Some(snapshot),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::warn!("Failed to parse remote snapshot: {}", e);
+            None
+        }
+    }
+}
+
+/// Save the remote snapshot, replacing any previous one
+pub fn save_remote_snapshot(snapshot: &RemoteSnapshot) -> Result<(), String> {
+    let path = get_snapshot_path();
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    let content = serde_json::to_string(snapshot)
+        .map_err(|e| format!("Failed to serialize remote snapshot: {}", e))?;
+
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write remote snapshot: {}", e))
+}
+
+/// Drop the remote snapshot so the next sync lists the whole tree
+pub fn clear_remote_snapshot() {
+    let path = get_snapshot_path();
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            tracing::warn!("Failed to remove remote snapshot: {}", e);
+        }
+    }
+}
+
 /// Ensure the local cloud folder exists
 pub fn ensure_cloud_folder(config: &CloudConfig) -> Result<PathBuf, String> {
     let path = &config.local_folder;
@@ -20,7 +20,7 @@ use tracing::info;
 /// Create and connect a provider for AeroCloud background sync.
 ///
 /// Dispatches based on `config.protocol_type`:
-/// - Direct auth (FTP, SFTP, WebDAV, S3, Azure, MEGA, Filen, Internxt, kDrive, Jottacloud):
+/// - Direct auth (FTP, SFTP, SMB, WebDAV, S3, Azure, Swift, B2, GCS, MEGA, Filen, Internxt, kDrive, Jottacloud):
 ///   loads credentials from vault, uses ProviderFactory
 /// - OAuth2 (Google Drive, Dropbox, OneDrive, Box, pCloud, Zoho):
 ///   reads client_id/secret from connection_params, auto-refreshes tokens
@@ -43,6 +43,10 @@ pub async fn create_cloud_provider(
         "kdrive" => create_via_factory(config, ProviderType::KDrive).await,
         "jottacloud" => create_via_factory(config, ProviderType::Jottacloud).await,
         "filelu" => create_via_factory(config, ProviderType::FileLu).await,
+        "swift" => create_via_factory(config, ProviderType::Swift).await,
+        "b2" => create_via_factory(config, ProviderType::B2).await,
+        "gcs" => create_via_factory(config, ProviderType::Gcs).await,
+        "smb" => create_via_factory(config, ProviderType::Smb).await,
 
         // --- OAuth2 providers: direct instantiation ---
         "googledrive" => create_google_drive(config).await,
@@ -137,10 +141,15 @@ fn get_param<'a>(config: &'a CloudConfig, key: &str) -> Result<&'a str, String>
         .ok_or_else(|| format!("Missing '{}' in connection_params", key))
 }
 
+/// Account the OAuth2 tokens were bound to when AeroCloud was set up
+fn oauth_account(config: &CloudConfig) -> Option<String> {
+    config.connection_params.get("account").and_then(|v| v.as_str()).map(str::to_string)
+}
+
 async fn create_google_drive(config: &CloudConfig) -> Result<Box<dyn StorageProvider>, String> {
     let client_id = get_param(config, "client_id")?;
     let client_secret = get_param(config, "client_secret")?;
-    let gc = GoogleDriveConfig::new(client_id, client_secret);
+    let gc = GoogleDriveConfig { account: oauth_account(config), ..GoogleDriveConfig::new(client_id, client_secret) };
     let mut p = GoogleDriveProvider::new(gc);
     p.connect().await.map_err(|e| format!("Google Drive: {}", e))?;
     info!("AeroCloud: connected to Google Drive");
@@ -150,7 +159,7 @@ async fn create_google_drive(config: &CloudConfig) -> Result<Box<dyn StorageProv
 async fn create_dropbox(config: &CloudConfig) -> Result<Box<dyn StorageProvider>, String> {
     let app_key = get_param(config, "client_id")?;
     let app_secret = get_param(config, "client_secret")?;
-    let dc = DropboxConfig::new(app_key, app_secret);
+    let dc = DropboxConfig { account: oauth_account(config), ..DropboxConfig::new(app_key, app_secret) };
     let mut p = DropboxProvider::new(dc);
     p.connect().await.map_err(|e| format!("Dropbox: {}", e))?;
     info!("AeroCloud: connected to Dropbox");
@@ -160,7 +169,7 @@ async fn create_dropbox(config: &CloudConfig) -> Result<Box<dyn StorageProvider>
 async fn create_onedrive(config: &CloudConfig) -> Result<Box<dyn StorageProvider>, String> {
     let client_id = get_param(config, "client_id")?;
     let client_secret = get_param(config, "client_secret")?;
-    let oc = OneDriveConfig::new(client_id, client_secret);
+    let oc = OneDriveConfig { account: oauth_account(config), ..OneDriveConfig::new(client_id, client_secret) };
     let mut p = OneDriveProvider::new(oc);
     p.connect().await.map_err(|e| format!("OneDrive: {}", e))?;
     info!("AeroCloud: connected to OneDrive");
@@ -170,7 +179,7 @@ async fn create_onedrive(config: &CloudConfig) -> Result<Box<dyn StorageProvider
 async fn create_box(config: &CloudConfig) -> Result<Box<dyn StorageProvider>, String> {
     let client_id = get_param(config, "client_id")?;
     let client_secret = get_param(config, "client_secret")?;
-    let bc = BoxConfig::new(client_id, client_secret);
+    let bc = BoxConfig { account: oauth_account(config), ..BoxConfig::new(client_id, client_secret) };
     let mut p = BoxProvider::new(bc);
     p.connect().await.map_err(|e| format!("Box: {}", e))?;
     info!("AeroCloud: connected to Box");
@@ -183,7 +192,7 @@ async fn create_pcloud(config: &CloudConfig) -> Result<Box<dyn StorageProvider>,
     let region = config.connection_params.get("region")
         .and_then(|v| v.as_str())
         .unwrap_or("us");
-    let pc = PCloudConfig::new(client_id, client_secret, region);
+    let pc = PCloudConfig { account: oauth_account(config), ..PCloudConfig::new(client_id, client_secret, region) };
     let mut p = PCloudProvider::new(pc);
     p.connect().await.map_err(|e| format!("pCloud: {}", e))?;
     info!("AeroCloud: connected to pCloud ({})", region);
@@ -196,7 +205,7 @@ async fn create_zoho(config: &CloudConfig) -> Result<Box<dyn StorageProvider>, S
     let region = config.connection_params.get("region")
         .and_then(|v| v.as_str())
         .unwrap_or("us");
-    let zc = ZohoWorkdriveConfig::new(client_id, client_secret, region);
+    let zc = ZohoWorkdriveConfig { account: oauth_account(config), ..ZohoWorkdriveConfig::new(client_id, client_secret, region) };
     let mut p = ZohoWorkdriveProvider::new(zc);
     p.connect().await.map_err(|e| format!("Zoho WorkDrive: {}", e))?;
     info!("AeroCloud: connected to Zoho WorkDrive ({})", region);
@@ -231,6 +240,7 @@ async fn create_fourshared(config: &CloudConfig) -> Result<Box<dyn StorageProvid
         consumer_secret: SecretString::from(consumer_secret.to_string()),
         access_token: SecretString::from(tokens.access_token),
         access_token_secret: SecretString::from(tokens.access_token_secret),
+        proxy: crate::providers::proxy::global(),
     };
 
     let mut p = FourSharedProvider::new(fs_config);
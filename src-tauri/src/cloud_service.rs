@@ -5,12 +5,15 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
-use crate::cloud_config::{CloudConfig, CloudSyncStatus, ConflictStrategy};
+use crate::cloud_config::{
+    load_remote_snapshot, remote_snapshot_key, save_remote_snapshot, clear_remote_snapshot,
+    CloudConfig, CloudSyncStatus, ConflictStrategy, RemoteSnapshot,
+};
 use crate::ftp::FtpManager;
-use crate::providers::{StorageProvider, RemoteEntry as ProviderRemoteEntry, ProviderError};
+use crate::providers::{StorageProvider, RemoteEntry as ProviderRemoteEntry, ProviderError, ChangeEntry, ProviderType};
 use crate::sync::{
-    build_comparison_results, CompareOptions, FileComparison, FileInfo, SyncAction, SyncDirection,
-    SyncStatus,
+    build_comparison_results, CompareOptions, FileComparison, FileInfo, SymlinkMode, SyncAction,
+    SyncDirection, SyncStatus,
 };
 // file_watcher module available for Phase 3A+ watcher integration
 use chrono::{DateTime, Utc};
@@ -183,6 +186,7 @@ impl CloudService {
             compare_checksum: false,
             exclude_patterns: config.exclude_patterns.clone(),
             direction: SyncDirection::Bidirectional,
+            symlink_mode: SymlinkMode::Skip,
         };
 
         let comparisons = build_comparison_results(local_files, remote_files, &options);
@@ -298,7 +302,7 @@ impl CloudService {
 
         // Get file listings
         let local_files = self.scan_local_folder(&config).await?;
-        let remote_files = self.scan_remote_folder_with_provider(provider, &config).await?;
+        let remote_files = self.scan_remote_changes_with_provider(provider, &config).await?;
 
         // Build comparison
         let options = CompareOptions {
@@ -307,6 +311,7 @@ impl CloudService {
             compare_checksum: false,
             exclude_patterns: config.exclude_patterns.clone(),
             direction: SyncDirection::Bidirectional,
+            symlink_mode: SymlinkMode::Skip,
         };
 
         let comparisons = build_comparison_results(local_files, remote_files, &options);
@@ -507,6 +512,9 @@ impl CloudService {
                         modified,
                         is_dir,
                         checksum: None,
+                        checksum_algorithm: None,
+                        checksum_weak: false,
+                        link_target: None,
                     },
                 );
 
@@ -575,6 +583,9 @@ impl CloudService {
                         }),
                         is_dir: entry.is_dir,
                         checksum: None,
+                        checksum_algorithm: None,
+                        checksum_weak: false,
+                        link_target: None,
                     },
                 );
 
@@ -722,6 +733,69 @@ impl CloudService {
         Ok(action)
     }
 
+    /// Remote listing for a sync run. Providers with a change feed replay the changes
+    /// since the last run on top of the stored snapshot instead of walking the whole tree.
+    async fn scan_remote_changes_with_provider<P: StorageProvider + ?Sized>(
+        &self,
+        provider: &mut P,
+        config: &CloudConfig,
+    ) -> Result<HashMap<String, FileInfo>, String> {
+        if !provider.supports_change_tracking() {
+            return self.scan_remote_folder_with_provider(provider, config).await;
+        }
+
+        let key = remote_snapshot_key(config);
+        if let Some(mut snapshot) = load_remote_snapshot(&key) {
+            match provider.list_changes(&snapshot.change_token).await {
+                Ok((changes, next_token)) => {
+                    // Dropbox paths are case-insensitive and may not match the configured folder's case
+                    let case_insensitive = provider.provider_type() == ProviderType::Dropbox;
+                    match apply_remote_changes(&mut snapshot, &changes, &config.remote_folder, &config.exclude_patterns, case_insensitive) {
+                        Some(rescan) => {
+                            tracing::info!("Applied {} remote changes ({} folders to rescan)", changes.len(), rescan.len());
+                            for relative in rescan {
+                                let path = remote_path(&config.remote_folder, &relative);
+                                Self::scan_remote_tree(provider, config, path, relative, &mut snapshot.files, &mut snapshot.ids).await;
+                            }
+                            snapshot.change_token = next_token;
+                            if let Err(e) = save_remote_snapshot(&snapshot) {
+                                tracing::warn!("{}", e);
+                            }
+                            return Ok(snapshot.files);
+                        }
+                        None => tracing::info!("Remote changes reference unknown items, listing the whole tree"),
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to list remote changes, listing the whole tree: {}", e),
+            }
+        }
+
+        // Take the token before listing so changes made during the scan are replayed next run
+        let token = provider.get_change_token().await;
+        let mut snapshot = RemoteSnapshot { key, ..Default::default() };
+        Self::scan_remote_tree(
+            provider, config, config.remote_folder.clone(), String::new(),
+            &mut snapshot.files, &mut snapshot.ids,
+        ).await;
+
+        match token {
+            // A truncated listing can't be patched reliably
+            Ok(token) if snapshot.files.len() < 100_000 => {
+                snapshot.change_token = token;
+                if let Err(e) = save_remote_snapshot(&snapshot) {
+                    tracing::warn!("{}", e);
+                }
+            }
+            Ok(_) => clear_remote_snapshot(),
+            Err(e) => {
+                tracing::warn!("Failed to get remote change token: {}", e);
+                clear_remote_snapshot();
+            }
+        }
+
+        Ok(snapshot.files)
+    }
+
     /// Scan remote folder using any StorageProvider (multi-protocol support)
     async fn scan_remote_folder_with_provider<P: StorageProvider + ?Sized>(
         &self,
@@ -729,10 +803,23 @@ impl CloudService {
         config: &CloudConfig,
     ) -> Result<HashMap<String, FileInfo>, String> {
         let mut files = HashMap::new();
-        let base_path = &config.remote_folder;
+        let mut ids = HashMap::new();
+        Self::scan_remote_tree(provider, config, config.remote_folder.clone(), String::new(), &mut files, &mut ids).await;
+        Ok(files)
+    }
 
+    /// Walk the remote tree below `base_path` (whose relative path is `base_prefix`),
+    /// adding entries to `files` and provider item IDs to `ids`
+    async fn scan_remote_tree<P: StorageProvider + ?Sized>(
+        provider: &mut P,
+        config: &CloudConfig,
+        base_path: String,
+        base_prefix: String,
+        files: &mut HashMap<String, FileInfo>,
+        ids: &mut HashMap<String, String>,
+    ) {
         // Stack-based recursive scan
-        let mut stack = vec![(base_path.clone(), String::new())];
+        let mut stack = vec![(base_path, base_prefix)];
 
         while let Some((current_path, relative_prefix)) = stack.pop() {
             // Navigate to directory
@@ -753,6 +840,15 @@ impl CloudService {
                     format!("{}/{}", relative_prefix, entry.name)
                 };
 
+                // IDs let change feeds that only report IDs find their place in the tree,
+                // including inside excluded folders
+                if let Some(parent_id) = entry.metadata.get("parentId") {
+                    ids.entry(parent_id.clone()).or_insert_with(|| relative_prefix.clone());
+                }
+                if let Some(id) = entry.metadata.get("id") {
+                    ids.insert(id.clone(), relative_path.clone());
+                }
+
                 // Check exclusions
                 if crate::sync::should_exclude(&relative_path, &config.exclude_patterns) {
                     continue;
@@ -761,7 +857,7 @@ impl CloudService {
                 // P1-6: Cap file index at 100K to prevent unbounded memory growth
                 if files.len() >= 100_000 {
                     tracing::warn!("Remote file index cap reached (100K), truncating scan");
-                    return Ok(files);
+                    return;
                 }
 
                 files.insert(
@@ -770,11 +866,12 @@ impl CloudService {
                         name: entry.name.clone(),
                         path: format!("{}/{}", current_path, entry.name),
                         size: entry.size,
-                        modified: entry.modified.and_then(|s| {
-                            DateTime::parse_from_rfc3339(&s).ok().map(|dt| dt.with_timezone(&Utc))
-                        }),
+                        modified: entry.modified.as_deref().and_then(parse_remote_time),
                         is_dir: entry.is_dir,
                         checksum: None,
+                        checksum_algorithm: None,
+                        checksum_weak: false,
+                        link_target: None,
                     },
                 );
 
@@ -786,8 +883,6 @@ impl CloudService {
                 }
             }
         }
-
-        Ok(files)
     }
 
     /// Process a single file comparison using any StorageProvider
@@ -920,3 +1015,356 @@ impl Default for CloudService {
         Self::new()
     }
 }
+
+/// Join the remote folder and a relative path the way the remote scan builds `FileInfo::path`
+fn remote_path(remote_folder: &str, relative: &str) -> String {
+    if relative.is_empty() {
+        remote_folder.to_string()
+    } else {
+        format!("{}/{}", remote_folder, relative)
+    }
+}
+
+fn parse_remote_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Where a change lands relative to the synced remote folder
+#[derive(Debug)]
+enum ChangeLocation {
+    /// Relative path inside the folder ("" is the folder itself)
+    Inside(String),
+    Outside,
+    /// ID-only change whose parent can't be placed yet
+    Unknown,
+}
+
+fn locate_change(snapshot: &RemoteSnapshot, change: &ChangeEntry, root: &str, case_insensitive: bool) -> ChangeLocation {
+    if let Some(path) = &change.path {
+        return match strip_root(path.trim_matches('/'), root, case_insensitive) {
+            Some(relative) if case_insensitive => ChangeLocation::Inside(match_snapshot_case(snapshot, relative)),
+            Some(relative) => ChangeLocation::Inside(relative.to_string()),
+            None => ChangeLocation::Outside,
+        };
+    }
+
+    let Some(parent_id) = &change.parent_id else {
+        // Parentless items (e.g. shared with the user) can't be inside the folder
+        return ChangeLocation::Outside;
+    };
+    match snapshot.ids.get(parent_id) {
+        Some(parent) if parent.is_empty() => ChangeLocation::Inside(change.name.clone()),
+        Some(parent) => ChangeLocation::Inside(format!("{}/{}", parent, change.name)),
+        // Every folder in the tree has a known ID, so an unknown parent lies outside it,
+        // as long as the folder's own ID is known
+        None if snapshot.ids.values().any(|p| p.is_empty()) => ChangeLocation::Outside,
+        None => ChangeLocation::Unknown,
+    }
+}
+
+/// `path` relative to `root` (both without surrounding slashes), or `None` when it
+/// lies outside. Components are compared lowercased for case-insensitive providers.
+fn strip_root<'a>(path: &'a str, root: &str, case_insensitive: bool) -> Option<&'a str> {
+    if root.is_empty() {
+        return Some(path);
+    }
+    let mut rest = path;
+    for component in root.split('/') {
+        let (head, tail) = rest.split_once('/').unwrap_or((rest, ""));
+        let matches = if case_insensitive {
+            head.to_lowercase() == component.to_lowercase()
+        } else {
+            head == component
+        };
+        if !matches {
+            return None;
+        }
+        rest = tail;
+    }
+    Some(rest)
+}
+
+/// Spell a case-insensitive path the way the snapshot already holds it, so a change
+/// reported in another case (Dropbox only guarantees the last component's case)
+/// updates the existing entry instead of adding a duplicate
+fn match_snapshot_case(snapshot: &RemoteSnapshot, relative: &str) -> String {
+    if relative.is_empty() || snapshot.files.contains_key(relative) {
+        return relative.to_string();
+    }
+    let lower = relative.to_lowercase();
+    if let Some(existing) = snapshot.files.keys().find(|p| p.to_lowercase() == lower) {
+        return existing.clone();
+    }
+    match relative.rsplit_once('/') {
+        Some((parent, name)) => format!("{}/{}", match_snapshot_case(snapshot, parent), name),
+        None => relative.to_string(),
+    }
+}
+
+fn is_under(path: &str, dir: &str) -> bool {
+    path == dir || path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn remove_subtree(snapshot: &mut RemoteSnapshot, relative: &str) {
+    snapshot.files.retain(|path, _| !is_under(path, relative));
+    snapshot.ids.retain(|_, path| !is_under(path, relative));
+}
+
+fn move_subtree(snapshot: &mut RemoteSnapshot, from: &str, to: &str, remote_folder: &str) {
+    let moved: Vec<String> = snapshot.files.keys().filter(|p| is_under(p, from)).cloned().collect();
+    for old in moved {
+        if let Some(mut info) = snapshot.files.remove(&old) {
+            let new = format!("{}{}", to, &old[from.len()..]);
+            info.path = remote_path(remote_folder, &new);
+            info.name = new.rsplit('/').next().unwrap_or(&new).to_string();
+            snapshot.files.insert(new, info);
+        }
+    }
+    for path in snapshot.ids.values_mut() {
+        if is_under(path, from) {
+            *path = format!("{}{}", to, &path[from.len()..]);
+        }
+    }
+}
+
+/// Replay provider changes on a remote snapshot. Returns the folders that appeared
+/// without their contents (moved in from elsewhere) and need a listing of their own,
+/// or `None` when a change can't be placed and the whole tree has to be listed.
+fn apply_remote_changes(
+    snapshot: &mut RemoteSnapshot,
+    changes: &[ChangeEntry],
+    remote_folder: &str,
+    exclude_patterns: &[String],
+    case_insensitive: bool,
+) -> Option<Vec<String>> {
+    let root = remote_folder.trim_matches('/');
+    let mut rescan: Vec<String> = Vec::new();
+
+    for change in changes {
+        let known = if change.file_id.is_empty() {
+            None
+        } else {
+            snapshot.ids.get(&change.file_id).cloned()
+        };
+
+        if change.removed {
+            // Deleted items often come without a parent, so look them up by ID first
+            let target = known.or_else(|| match locate_change(snapshot, change, root, case_insensitive) {
+                ChangeLocation::Inside(relative) => Some(relative),
+                _ => None,
+            });
+            if let Some(relative) = target.filter(|r| !r.is_empty()) {
+                remove_subtree(snapshot, &relative);
+            }
+            continue;
+        }
+
+        let relative = match locate_change(snapshot, change, root, case_insensitive) {
+            ChangeLocation::Inside(relative) => relative,
+            ChangeLocation::Outside => {
+                // Moved out of the synced folder
+                if let Some(old) = known.filter(|p| !p.is_empty()) {
+                    remove_subtree(snapshot, &old);
+                }
+                continue;
+            }
+            ChangeLocation::Unknown => return None,
+        };
+        if relative.is_empty() {
+            continue;
+        }
+
+        if let Some(old) = known.as_deref().filter(|old| !old.is_empty() && *old != relative) {
+            move_subtree(snapshot, old, &relative, remote_folder);
+        }
+        if !change.file_id.is_empty() {
+            snapshot.ids.insert(change.file_id.clone(), relative.clone());
+        }
+        if crate::sync::should_exclude(&relative, exclude_patterns) {
+            snapshot.files.remove(&relative);
+            continue;
+        }
+
+        let previous = snapshot.files.get(&relative);
+        let is_new_dir = change.is_dir && previous.is_none() && known.is_none();
+        let size = if change.is_dir {
+            0
+        } else {
+            change.size.or(previous.map(|f| f.size)).unwrap_or(0)
+        };
+        snapshot.files.insert(
+            relative.clone(),
+            FileInfo {
+                name: relative.rsplit('/').next().unwrap_or(&relative).to_string(),
+                path: remote_path(remote_folder, &relative),
+                size,
+                modified: change.timestamp.as_deref().and_then(parse_remote_time),
+                is_dir: change.is_dir,
+                checksum: None,
+                checksum_algorithm: None,
+                checksum_weak: false,
+                link_target: None,
+            },
+        );
+        if is_new_dir {
+            rescan.push(relative);
+        }
+    }
+
+    // A rescanned folder covers everything nested below it
+    rescan.sort();
+    let mut covered: Vec<String> = Vec::new();
+    for dir in rescan {
+        if !covered.iter().any(|parent| is_under(&dir, parent)) {
+            covered.push(dir);
+        }
+    }
+    Some(covered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(remote_folder: &str, relative: &str, size: u64, is_dir: bool) -> (String, FileInfo) {
+        (
+            relative.to_string(),
+            FileInfo {
+                name: relative.rsplit('/').next().unwrap().to_string(),
+                path: remote_path(remote_folder, relative),
+                size,
+                modified: None,
+                is_dir,
+                checksum: None,
+                checksum_algorithm: None,
+                checksum_weak: false,
+                link_target: None,
+            },
+        )
+    }
+
+    fn change(file_id: &str, name: &str) -> ChangeEntry {
+        ChangeEntry {
+            file_id: file_id.to_string(),
+            name: name.to_string(),
+            change_type: "modified".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_path_changes() {
+        let mut snapshot = RemoteSnapshot {
+            files: HashMap::from([
+                file("/Cloud", "docs", 0, true),
+                file("/Cloud", "docs/a.txt", 10, false),
+                file("/Cloud", "old", 0, true),
+                file("/Cloud", "old/b.txt", 20, false),
+            ]),
+            ..Default::default()
+        };
+
+        let changes = vec![
+            ChangeEntry { path: Some("/Cloud/docs/a.txt".into()), size: Some(15), ..change("id:a", "a.txt") },
+            ChangeEntry { path: Some("/Cloud/old".into()), removed: true, ..change("", "old") },
+            ChangeEntry { path: Some("/Cloud/new.tmp".into()), size: Some(1), ..change("id:t", "new.tmp") },
+            ChangeEntry { path: Some("/Elsewhere/c.txt".into()), size: Some(3), ..change("id:c", "c.txt") },
+            ChangeEntry { path: Some("/Cloud/pics".into()), is_dir: true, ..change("id:p", "pics") },
+        ];
+        let rescan = apply_remote_changes(&mut snapshot, &changes, "/Cloud", &["*.tmp".to_string()], false).unwrap();
+
+        assert_eq!(rescan, vec!["pics".to_string()]);
+        assert_eq!(snapshot.files["docs/a.txt"].size, 15);
+        assert_eq!(snapshot.files["docs/a.txt"].path, "/Cloud/docs/a.txt");
+        assert!(!snapshot.files.contains_key("old"));
+        assert!(!snapshot.files.contains_key("old/b.txt"));
+        assert!(!snapshot.files.contains_key("new.tmp"));
+        assert!(snapshot.files["pics"].is_dir);
+        assert_eq!(snapshot.files.len(), 3);
+    }
+
+    #[test]
+    fn test_apply_id_changes() {
+        let mut snapshot = RemoteSnapshot {
+            files: HashMap::from([
+                file("/", "docs", 0, true),
+                file("/", "docs/a.txt", 10, false),
+                file("/", "b.txt", 20, false),
+            ]),
+            ids: HashMap::from([
+                ("root".to_string(), String::new()),
+                ("d1".to_string(), "docs".to_string()),
+                ("f1".to_string(), "docs/a.txt".to_string()),
+                ("f2".to_string(), "b.txt".to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        let changes = vec![
+            // Folder renamed: children follow
+            ChangeEntry { parent_id: Some("root".into()), is_dir: true, ..change("d1", "papers") },
+            // New file in a known folder
+            ChangeEntry { parent_id: Some("d1".into()), size: Some(5), ..change("f3", "c.txt") },
+            // Deleted by ID only
+            ChangeEntry { removed: true, ..change("f2", "") },
+            // Somewhere else in the drive
+            ChangeEntry { parent_id: Some("other".into()), size: Some(1), ..change("f4", "x.txt") },
+        ];
+        let rescan = apply_remote_changes(&mut snapshot, &changes, "/", &[], false).unwrap();
+
+        assert!(rescan.is_empty());
+        let mut paths: Vec<&String> = snapshot.files.keys().collect();
+        paths.sort();
+        assert_eq!(paths, ["papers", "papers/a.txt", "papers/c.txt"]);
+        assert_eq!(snapshot.files["papers/a.txt"].path, "//papers/a.txt");
+        assert_eq!(snapshot.files["papers/a.txt"].size, 10);
+        assert_eq!(snapshot.ids["f1"], "papers/a.txt");
+        assert!(!snapshot.ids.contains_key("f2"));
+    }
+
+    #[test]
+    fn test_apply_dropbox_changes_ignore_case() {
+        // Folder configured as /aerocloud, Dropbox reports /AeroCloud and may
+        // spell parent folders differently from the listing
+        let mut snapshot = RemoteSnapshot {
+            files: HashMap::from([
+                file("/aerocloud", "Docs", 0, true),
+                file("/aerocloud", "Docs/a.txt", 10, false),
+                file("/aerocloud", "gone.txt", 5, false),
+            ]),
+            ..Default::default()
+        };
+
+        let changes = vec![
+            ChangeEntry { path: Some("/AeroCloud/docs/a.txt".into()), size: Some(15), ..change("id:a", "a.txt") },
+            ChangeEntry { path: Some("/AeroCloud/DOCS/New.txt".into()), size: Some(2), ..change("id:n", "New.txt") },
+            ChangeEntry { path: Some("/aerocloud/gone.txt".into()), removed: true, ..change("", "gone.txt") },
+            ChangeEntry { path: Some("/AeroCloudOld/x.txt".into()), size: Some(1), ..change("id:x", "x.txt") },
+        ];
+
+        // Case-sensitive matching drops every change as outside the folder
+        let mut strict = snapshot.clone();
+        apply_remote_changes(&mut strict, &changes[..2], "/aerocloud", &[], false).unwrap();
+        assert_eq!(strict.files.len(), 3);
+        assert_eq!(strict.files["Docs/a.txt"].size, 10);
+
+        apply_remote_changes(&mut snapshot, &changes, "/aerocloud", &[], true).unwrap();
+        let mut paths: Vec<&String> = snapshot.files.keys().collect();
+        paths.sort();
+        assert_eq!(paths, ["Docs", "Docs/New.txt", "Docs/a.txt"]);
+        assert_eq!(snapshot.files["Docs/a.txt"].size, 15);
+        assert_eq!(snapshot.files["Docs/New.txt"].path, "/aerocloud/Docs/New.txt");
+    }
+
+    #[test]
+    fn test_apply_unplaceable_change() {
+        // Without the folder's own ID an unknown parent may still be inside it
+        let mut snapshot = RemoteSnapshot::default();
+        let changes = vec![ChangeEntry { parent_id: Some("p".into()), ..change("f1", "a.txt") }];
+        assert!(apply_remote_changes(&mut snapshot, &changes, "/", &[], false).is_none());
+
+        snapshot.ids.insert("root".to_string(), String::new());
+        assert_eq!(apply_remote_changes(&mut snapshot, &changes, "/", &[], false), Some(vec![]));
+        assert!(snapshot.files.is_empty());
+    }
+}
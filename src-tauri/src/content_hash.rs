@@ -0,0 +1,307 @@
+// AeroFTP Content Hash Module
+// Local implementations of the hashes cloud providers report in their listings,
+// so sync can compare files exactly without downloading them.
+//
+// Besides MD5/SHA-1/SHA-256 this covers two provider-specific algorithms:
+// - Dropbox content_hash: SHA-256 over the SHA-256 digests of each 4 MiB block
+// - QuickXorHash (OneDrive): 160-bit XOR of the input bytes, each shifted 11 bits
+//   further than the previous one, with the length mixed into the last 64 bits
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+use crate::providers::HashAlgorithm;
+use crate::sync::FileInfo;
+
+/// Dropbox hashes content in blocks of this size (4 MiB)
+const DROPBOX_BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+/// QuickXorHash width in bits
+const QUICKXOR_WIDTH_BITS: usize = 160;
+
+/// QuickXorHash shift per input byte
+const QUICKXOR_SHIFT: usize = 11;
+
+/// Local files hashed concurrently when matching remote hashes
+const MAX_CONCURRENT_HASHES: usize = 8;
+
+/// Dropbox `content_hash`
+#[derive(Clone, Default)]
+pub struct DropboxContentHasher {
+    overall: Sha256,
+    block: Sha256,
+    block_pos: usize,
+}
+
+impl DropboxContentHasher {
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.block_pos == DROPBOX_BLOCK_SIZE {
+                let block = std::mem::take(&mut self.block);
+                self.overall.update(block.finalize());
+                self.block_pos = 0;
+            }
+            let n = data.len().min(DROPBOX_BLOCK_SIZE - self.block_pos);
+            self.block.update(&data[..n]);
+            self.block_pos += n;
+            data = &data[n..];
+        }
+    }
+
+    pub fn finalize(mut self) -> Vec<u8> {
+        if self.block_pos > 0 {
+            self.overall.update(self.block.finalize());
+        }
+        self.overall.finalize().to_vec()
+    }
+}
+
+/// OneDrive QuickXorHash
+#[derive(Clone)]
+pub struct QuickXorHasher {
+    state: [u8; QUICKXOR_WIDTH_BITS / 8],
+    /// Bit offset at which the next byte is XORed in
+    shift: usize,
+    length: u64,
+}
+
+impl Default for QuickXorHasher {
+    fn default() -> Self {
+        Self { state: [0; QUICKXOR_WIDTH_BITS / 8], shift: 0, length: 0 }
+    }
+}
+
+impl QuickXorHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        let width = self.state.len();
+        for &byte in data {
+            let index = self.shift / 8;
+            let bit = self.shift % 8;
+            self.state[index] ^= byte << bit;
+            if bit > 0 {
+                // The byte straddles two cells, wrapping around at 160 bits
+                self.state[(index + 1) % width] ^= byte >> (8 - bit);
+            }
+            self.shift = (self.shift + QUICKXOR_SHIFT) % QUICKXOR_WIDTH_BITS;
+        }
+        self.length += data.len() as u64;
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        let mut out = self.state;
+        let offset = out.len() - 8;
+        for (i, b) in self.length.to_le_bytes().iter().enumerate() {
+            out[offset + i] ^= b;
+        }
+        out.to_vec()
+    }
+}
+
+/// Streaming hasher for any `HashAlgorithm`
+#[derive(Clone)]
+pub enum ContentHasher {
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Dropbox(DropboxContentHasher),
+    QuickXor(QuickXorHasher),
+}
+
+impl ContentHasher {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Md5 => Self::Md5(Md5::new()),
+            HashAlgorithm::Sha1 => Self::Sha1(Sha1::new()),
+            HashAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            HashAlgorithm::DropboxContentHash => Self::Dropbox(DropboxContentHasher::default()),
+            HashAlgorithm::QuickXor => Self::QuickXor(QuickXorHasher::default()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Md5(h) => h.update(data),
+            Self::Sha1(h) => h.update(data),
+            Self::Sha256(h) => h.update(data),
+            Self::Dropbox(h) => h.update(data),
+            Self::QuickXor(h) => h.update(data),
+        }
+    }
+
+    /// Lowercase hex digest, the same form as `ContentHash::value`
+    pub fn finalize_hex(self) -> String {
+        match self {
+            Self::Md5(h) => hex::encode(h.finalize()),
+            Self::Sha1(h) => hex::encode(h.finalize()),
+            Self::Sha256(h) => hex::encode(h.finalize()),
+            Self::Dropbox(h) => hex::encode(h.finalize()),
+            Self::QuickXor(h) => hex::encode(h.finalize()),
+        }
+    }
+}
+
+/// Hash a local file with the given algorithm
+pub async fn hash_file(path: &Path, algorithm: HashAlgorithm) -> Option<String> {
+    let mut file = tokio::fs::File::open(path).await.ok()?;
+    let mut hasher = ContentHasher::new(algorithm);
+    let mut buf = vec![0u8; 65_536];
+    loop {
+        let n = file.read(&mut buf).await.ok()?;
+        if n == 0 { break; }
+        hasher.update(&buf[..n]);
+    }
+    Some(hasher.finalize_hex())
+}
+
+/// Fill in the checksums of local files whose remote counterpart carries a
+/// content hash, using the remote's algorithm. Pairs whose sizes differ are
+/// skipped since they cannot be identical anyway.
+pub async fn hash_local_files_like(local: &mut HashMap<String, FileInfo>, remote: &HashMap<String, FileInfo>) {
+    let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_HASHES));
+    let mut join_set = tokio::task::JoinSet::new();
+
+    for (relative, local_file) in local.iter() {
+        let Some(remote_file) = remote.get(relative) else { continue };
+        let Some(algorithm) = remote_file.checksum_algorithm else { continue };
+        if remote_file.checksum.is_none()
+            || local_file.is_dir
+            || local_file.link_target.is_some()
+            || local_file.size != remote_file.size
+        {
+            continue;
+        }
+        let sem = semaphore.clone();
+        let relative = relative.clone();
+        let path = local_file.path.clone();
+        join_set.spawn(async move {
+            let _permit = sem.acquire().await;
+            let checksum = hash_file(Path::new(&path), algorithm).await;
+            (relative, algorithm, checksum)
+        });
+    }
+
+    while let Some(result) = join_set.join_next().await {
+        if let Ok((relative, algorithm, Some(checksum))) = result {
+            if let Some(file) = local.get_mut(&relative) {
+                file.checksum = Some(checksum);
+                file.checksum_algorithm = Some(algorithm);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(algorithm: HashAlgorithm, data: &[u8]) -> String {
+        let mut hasher = ContentHasher::new(algorithm);
+        hasher.update(data);
+        hasher.finalize_hex()
+    }
+
+    /// Deterministic test input
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| ((i * 31 + 7) % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_standard_digests() {
+        assert_eq!(hash(HashAlgorithm::Md5, b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(hash(HashAlgorithm::Sha1, b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(
+            hash(HashAlgorithm::Sha256, b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_dropbox_content_hash_blocks() {
+        // No blocks at all: SHA-256 of the empty string
+        assert_eq!(
+            hash(HashAlgorithm::DropboxContentHash, b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        let data = pattern(DROPBOX_BLOCK_SIZE + 10);
+        let mut expected = Sha256::new();
+        expected.update(Sha256::digest(&data[..DROPBOX_BLOCK_SIZE]));
+        expected.update(Sha256::digest(&data[DROPBOX_BLOCK_SIZE..]));
+        let expected = hex::encode(expected.finalize());
+
+        // Chunking must not matter, including chunks straddling the block boundary
+        let mut hasher = ContentHasher::new(HashAlgorithm::DropboxContentHash);
+        for chunk in data.chunks(1_000_003) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize_hex(), expected);
+        assert_eq!(hash(HashAlgorithm::DropboxContentHash, &data), expected);
+    }
+
+    #[test]
+    fn test_quickxor_hash() {
+        // Expected values cross-checked with an independent bit-by-bit implementation
+        // of the QuickXorHash description in Microsoft's OneDrive documentation
+        assert_eq!(hash(HashAlgorithm::QuickXor, b""), "0000000000000000000000000000000000000000");
+        assert_eq!(hash(HashAlgorithm::QuickXor, &pattern(1)), "0700000000000000000000000100000000000000");
+        assert_eq!(hash(HashAlgorithm::QuickXor, &pattern(20)), "a52b4011093fb45104031c040c8110c200884ff8");
+        assert_eq!(hash(HashAlgorithm::QuickXor, &pattern(161)), "fd3e3b27942398d8d87c0cc9ec8fb9fc0f58e796");
+
+        let data = pattern(100_000);
+        let mut hasher = ContentHasher::new(HashAlgorithm::QuickXor);
+        for chunk in data.chunks(333) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize_hex(), "623d659d661e27090847e7843f2f33728730675f");
+    }
+
+    #[tokio::test]
+    async fn test_hash_local_files_like() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = |name: &str, size: u64, checksum: Option<&str>, algorithm: Option<HashAlgorithm>| FileInfo {
+            name: name.to_string(),
+            path: dir.path().join(name).to_string_lossy().into_owned(),
+            size,
+            modified: None,
+            is_dir: false,
+            checksum: checksum.map(str::to_string),
+            checksum_algorithm: algorithm,
+            checksum_weak: false,
+            link_target: None,
+        };
+        for name in ["same.txt", "grown.txt", "plain.txt"] {
+            std::fs::write(dir.path().join(name), b"abc").unwrap();
+        }
+
+        let md5 = Some(HashAlgorithm::Md5);
+        let mut local = HashMap::from([
+            ("same.txt".to_string(), file("same.txt", 3, None, None)),
+            ("grown.txt".to_string(), file("grown.txt", 3, None, None)),
+            ("plain.txt".to_string(), file("plain.txt", 3, None, None)),
+            ("photos".to_string(), FileInfo { is_dir: true, ..file("photos", 0, None, None) }),
+            ("current".to_string(), FileInfo { link_target: Some("same.txt".to_string()), ..file("current", 0, None, None) }),
+        ]);
+        let remote = HashMap::from([
+            ("same.txt".to_string(), file("same.txt", 3, Some("900150983cd24fb0d6963f7d28e17f72"), md5)),
+            // Sizes differ: cannot be identical, not worth hashing
+            ("grown.txt".to_string(), file("grown.txt", 4, Some("00"), md5)),
+            // Remote without a content hash
+            ("plain.txt".to_string(), file("plain.txt", 3, None, None)),
+            ("photos".to_string(), FileInfo { is_dir: true, ..file("photos", 0, Some("00"), md5) }),
+            ("current".to_string(), file("current", 0, Some("00"), md5)),
+        ]);
+
+        hash_local_files_like(&mut local, &remote).await;
+
+        assert_eq!(local["same.txt"].checksum.as_deref(), Some("900150983cd24fb0d6963f7d28e17f72"));
+        assert_eq!(local["same.txt"].checksum_algorithm, md5);
+        for name in ["grown.txt", "plain.txt", "photos", "current"] {
+            assert_eq!(local[name].checksum, None, "{}", name);
+            assert_eq!(local[name].checksum_algorithm, None, "{}", name);
+        }
+    }
+}
@@ -3,7 +3,7 @@
 //! This module provides an async wrapper around the suppaftp crate.
 
 use anyhow::{Context, Result};
-use suppaftp::tokio::AsyncFtpStream;
+use suppaftp::tokio::{AsyncFtpStream, AsyncNoTlsStream};
 use suppaftp::types::FileType;
 use thiserror::Error;
 use std::path::PathBuf;
@@ -41,6 +41,30 @@ pub struct RemoteFile {
     pub permissions: Option<String>,
 }
 
+/// Split `host`, `host:port`, `[v6]`, `[v6]:port` or a bare IPv6 address into
+/// host and port (21 when absent). IPv6 hosts are returned without brackets.
+fn parse_server_address(server: &str) -> Option<(String, u16)> {
+    let server = server.trim();
+    if let Some(rest) = server.strip_prefix('[') {
+        let (host, after) = rest.split_once(']')?;
+        let port = match after.strip_prefix(':') {
+            Some(port) => port.parse().ok()?,
+            None if after.is_empty() => 21,
+            None => return None,
+        };
+        return (!host.is_empty()).then(|| (host.to_string(), port));
+    }
+    if server.parse::<std::net::Ipv6Addr>().is_ok() {
+        return Some((server.to_string(), 21));
+    }
+    match server.split_once(':') {
+        Some((host, port)) if !host.is_empty() => Some((host.to_string(), port.parse().ok()?)),
+        Some(_) => None,
+        None if !server.is_empty() => Some((server.to_string(), 21)),
+        None => None,
+    }
+}
+
 pub struct FtpManager {
     stream: Option<AsyncFtpStream>,
     current_path: String,
@@ -65,17 +89,19 @@ impl FtpManager {
     pub async fn connect(&mut self, server: &str) -> Result<()> {
         info!("Connecting to FTP server: {}", server);
         
-        // Parse server address
-        let server_addr = if server.contains(':') {
-            server.to_string()
+        let (host, port) = parse_server_address(server)
+            .ok_or_else(|| FtpManagerError::ConnectionError(format!("Invalid server address: {}", server)))?;
+        let server_addr = if host.contains(':') {
+            format!("[{}]:{}", host, port)
         } else {
-            format!("{}:21", server)
+            format!("{}:{}", host, port)
         };
+        let proxy = crate::providers::proxy::global();
 
         // Connect with timeout
         let stream = tokio::time::timeout(
             Duration::from_secs(10),
-            AsyncFtpStream::connect(&server_addr)
+            crate::providers::ftp::open_ftp_stream::<AsyncNoTlsStream>(proxy.as_ref(), &host, port, true)
         )
         .await
         .context("Connection timeout")?
@@ -880,4 +906,22 @@ impl Default for FtpManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_server_address() {
+        assert_eq!(parse_server_address("ftp.example.com"), Some(("ftp.example.com".into(), 21)));
+        assert_eq!(parse_server_address("ftp.example.com:2121"), Some(("ftp.example.com".into(), 2121)));
+        assert_eq!(parse_server_address("192.0.2.1:21"), Some(("192.0.2.1".into(), 21)));
+        assert_eq!(parse_server_address("2001:db8::1"), Some(("2001:db8::1".into(), 21)));
+        assert_eq!(parse_server_address("[2001:db8::1]"), Some(("2001:db8::1".into(), 21)));
+        assert_eq!(parse_server_address("[2001:db8::1]:2121"), Some(("2001:db8::1".into(), 2121)));
+        assert_eq!(parse_server_address("[2001:db8::1]x"), None);
+        assert_eq!(parse_server_address("host:notaport"), None);
+        assert_eq!(parse_server_address(""), None);
+    }
+}
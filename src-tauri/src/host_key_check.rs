@@ -3,7 +3,8 @@
 //! Pre-check probe for host key verification before actual connection.
 //! Returns fingerprint + algorithm to frontend for user approval dialog.
 
-use russh::client::{self, Config, Handler};
+use crate::providers::ssh_tunnel::{self, SshEndpoint};
+use russh::client::{Config, Handler};
 use russh::keys::{self, known_hosts, HashAlg, PublicKey};
 use serde::Serialize;
 use std::collections::HashMap;
@@ -28,6 +29,10 @@ pub struct HostKeyInfo {
     pub algorithm: String,
     /// For "changed" status: line number in known_hosts
     pub changed_line: Option<usize>,
+    /// Server the key belongs to: the `~/.ssh/config` HostName of the target,
+    /// or a ProxyJump host whose key must be approved first
+    pub host: String,
+    pub port: u16,
 }
 
 /// Key for the PENDING_KEYS map
@@ -66,6 +71,8 @@ impl Handler for ProbeHandler {
                     fingerprint,
                     algorithm,
                     changed_line: None,
+                    host: self.host.clone(),
+                    port: self.port,
                 });
                 // Return true so the probe connection succeeds (we'll drop it immediately)
                 Ok(true)
@@ -81,6 +88,8 @@ impl Handler for ProbeHandler {
                     fingerprint,
                     algorithm,
                     changed_line: None,
+                    host: self.host.clone(),
+                    port: self.port,
                 });
                 // Store key for later acceptance
                 let key = pending_key(&self.host, self.port);
@@ -101,6 +110,8 @@ impl Handler for ProbeHandler {
                     fingerprint,
                     algorithm,
                     changed_line: Some(line),
+                    host: self.host.clone(),
+                    port: self.port,
                 });
                 // Store new key for potential acceptance
                 let key = pending_key(&self.host, self.port);
@@ -119,6 +130,8 @@ impl Handler for ProbeHandler {
                     fingerprint,
                     algorithm,
                     changed_line: None,
+                    host: self.host.clone(),
+                    port: self.port,
                 });
                 Ok(false)
             }
@@ -129,34 +142,49 @@ impl Handler for ProbeHandler {
 /// Probe a host's SSH key without authenticating.
 /// Returns the key status, fingerprint, and algorithm.
 #[tauri::command]
-pub async fn sftp_check_host_key(host: String, port: u16) -> Result<HostKeyInfo, String> {
+pub async fn sftp_check_host_key(
+    host: String,
+    port: u16,
+    proxy: Option<String>,
+    ssh_config: Option<bool>,
+) -> Result<HostKeyInfo, String> {
     let result = Arc::new(Mutex::new(None::<HostKeyInfo>));
-    let handler = ProbeHandler {
-        host: host.clone(),
-        port,
-        result: result.clone(),
-    };
 
     let config = Config {
         inactivity_timeout: Some(Duration::from_secs(10)),
         ..Default::default()
     };
 
-    let addr = format!("{}:{}", host, port);
+    // Follow ~/.ssh/config like the real connection does (unless the profile turned it
+    // off). Jump hosts are probed on the way: an unapproved jump host key is reported
+    // instead of the target's.
+    let proxy = crate::providers::proxy::from_setting(proxy.as_deref(), None).map_err(|e| e.to_string())?;
+    let endpoint = SshEndpoint::resolve(&host, port, "", ssh_config.unwrap_or(true), proxy)
+        .map_err(|e| e.to_string())?;
+    let make_handler = |h: &str, p: u16| ProbeHandler {
+        host: h.to_string(),
+        port: p,
+        result: result.clone(),
+    };
 
     // Probe connection — may fail for unknown/changed keys (handler returns false)
     // or succeed for known keys (we drop the handle immediately)
     let probe = tokio::time::timeout(
-        Duration::from_secs(10),
-        client::connect(Arc::new(config), &*addr, handler),
+        Duration::from_secs(if endpoint.route.jumps.is_empty() { 10 } else { 60 }),
+        ssh_tunnel::connect(Arc::new(config), &endpoint.host, endpoint.port, &endpoint.route, make_handler),
     )
     .await;
 
-    // For known keys, probe succeeds — drop the handle
-    if let Ok(Ok(_handle)) = probe {
-        drop(_handle);
-    }
-    // For unknown/changed keys, probe fails — that's expected
+    // For known keys, probe succeeds — drop the handles.
+    // For unknown/changed keys, probe fails — that's expected.
+    let probe_error = match probe {
+        Ok(Ok(handles)) => {
+            drop(handles);
+            None
+        }
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some("timed out".to_string()),
+    };
 
     // Retrieve captured result
     let info = result
@@ -166,16 +194,34 @@ pub async fn sftp_check_host_key(host: String, port: u16) -> Result<HostKeyInfo,
         .ok_or_else(|| {
             format!(
                 "Failed to retrieve host key from {}:{} — connection may have timed out",
-                host, port
+                endpoint.host, endpoint.port
             )
         })?;
 
+    // A trusted jump host was the last key seen: the target itself was never reached
+    if info.status == "known" && (info.host != endpoint.host || info.port != endpoint.port) {
+        return Err(format!(
+            "Could not reach {}:{} through jump host {}: {}",
+            endpoint.host, endpoint.port, info.host,
+            probe_error.unwrap_or_else(|| "unknown error".to_string())
+        ));
+    }
+
     Ok(info)
 }
 
+/// Map a profile host (possibly an ssh config alias) to the host:port its key is stored under
+fn resolve_key_host(host: &str, port: u16, ssh_config: Option<bool>) -> (String, u16) {
+    match SshEndpoint::resolve(host, port, "", ssh_config.unwrap_or(true), None) {
+        Ok(endpoint) => (endpoint.host, endpoint.port),
+        Err(_) => (host.to_string(), port),
+    }
+}
+
 /// Accept a pending host key and save it to ~/.ssh/known_hosts
 #[tauri::command]
-pub async fn sftp_accept_host_key(host: String, port: u16) -> Result<(), String> {
+pub async fn sftp_accept_host_key(host: String, port: u16, ssh_config: Option<bool>) -> Result<(), String> {
+    let (host, port) = resolve_key_host(&host, port, ssh_config);
     let key_id = pending_key(&host, port);
     let pubkey = {
         let mut map = PENDING_KEYS.lock().unwrap_or_else(|e| e.into_inner());
@@ -194,7 +240,13 @@ pub async fn sftp_accept_host_key(host: String, port: u16) -> Result<(), String>
 /// Remove a host key entry from ~/.ssh/known_hosts (for key-changed case).
 /// Uses the line number from the KeyChanged error for precise removal.
 #[tauri::command]
-pub async fn sftp_remove_host_key(host: String, port: u16, line: usize) -> Result<(), String> {
+pub async fn sftp_remove_host_key(
+    host: String,
+    port: u16,
+    line: usize,
+    ssh_config: Option<bool>,
+) -> Result<(), String> {
+    let (host, port) = resolve_key_host(&host, port, ssh_config);
     let known_hosts_path = dirs::home_dir()
         .ok_or("No home directory found")?
         .join(".ssh")
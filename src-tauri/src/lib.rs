@@ -21,6 +21,7 @@ mod file_watcher;
 mod sync_scheduler;
 mod transfer_pool;
 mod delta_sync;
+mod content_hash;
 mod cloud_service;
 mod cloud_provider_factory;
 mod providers;
@@ -33,7 +34,11 @@ mod profile_export;
 mod keystore_export;
 mod pty;
 mod ssh_shell;
+mod ssh_tunnels;
+mod ssh_batch;
+mod terminal_recording;
 mod host_key_check;
+mod tls_cert_check;
 mod ai_tools;
 mod context_intelligence;
 mod plugins;
@@ -60,8 +65,12 @@ mod cloud_filter_badge;
 use filesystem::validate_path;
 use ftp::{FtpManager, RemoteFile};
 use pty::{create_pty_state, spawn_shell, pty_write, pty_resize, pty_close};
-use ssh_shell::{create_ssh_shell_state, ssh_shell_open, ssh_shell_write, ssh_shell_resize, ssh_shell_close};
+use ssh_shell::{create_ssh_shell_state, ssh_shell_open, ssh_shell_write, ssh_shell_resize, ssh_shell_close, ssh_shared_connections};
+use ssh_tunnels::{create_ssh_tunnel_state, ssh_tunnel_list_saved, ssh_tunnel_save, ssh_tunnel_delete, ssh_tunnel_start, ssh_tunnel_stop, ssh_tunnel_status};
+use ssh_batch::{ssh_batch_run, ssh_batch_export};
+use terminal_recording::{create_terminal_replay_state, terminal_recording_get_settings, terminal_recording_set_settings, terminal_recordings_list, terminal_recording_delete, terminal_recording_to_vault, terminal_recording_replay, terminal_replay_stop};
 use host_key_check::{sftp_check_host_key, sftp_accept_host_key, sftp_remove_host_key};
+use tls_cert_check::{tls_check_certificate, tls_accept_certificate, tls_remove_certificate};
 
 /// Global transfer speed limits (bytes per second, 0 = unlimited)
 pub struct SpeedLimits {
@@ -4332,7 +4341,7 @@ fn rebuild_menu(app: AppHandle, labels: std::collections::HashMap<String, String
 // ============ Sync Commands ============
 
 use sync::{
-    CompareOptions, FileComparison, FileInfo, SyncIndex, SyncJournal,
+    CompareOptions, FileComparison, FileInfo, SymlinkMode, SyncIndex, SyncJournal,
     VerifyPolicy, VerifyResult, RetryPolicy, SyncErrorInfo,
     CanaryResult, CanarySummary, CanarySampleResult,
     build_comparison_results_with_index, should_exclude,
@@ -4370,9 +4379,14 @@ async fn compare_directories(
     // Run local and remote scans concurrently (F2 optimization)
     // Local scan runs on filesystem; remote scan holds FTP lock.
     // tokio::join! runs both futures on the same task but interleaves their I/O waits.
+    // FTP cannot create links, so they are only followed or skipped
+    let symlink_mode = match options.symlink_mode {
+        SymlinkMode::CopyAsLink => SymlinkMode::Skip,
+        mode => mode,
+    };
     let local_future = get_local_files_recursive(
         &local_path, &local_path, &options.exclude_patterns,
-        options.compare_checksum, Some(&state.cancel_flag),
+        options.compare_checksum, symlink_mode, Some(&state.cancel_flag),
     );
 
     let remote_future = async {
@@ -4421,6 +4435,18 @@ async fn compute_sha256(path: &std::path::Path) -> Option<String> {
     Some(format!("{:x}", hasher.finalize()))
 }
 
+/// Metadata of the directory or file a symlink points to, for `SymlinkMode::Follow`.
+/// `None` when the target is missing, outside `root`, or an ancestor of the link.
+async fn followed_link_metadata(link: &std::path::Path, root: &std::path::Path) -> Option<(PathBuf, std::fs::Metadata)> {
+    let target = tokio::fs::canonicalize(link).await.ok()?;
+    let parent = tokio::fs::canonicalize(link.parent()?).await.ok()?;
+    if !target.starts_with(root) || parent.starts_with(&target) {
+        return None;
+    }
+    let metadata = tokio::fs::metadata(&target).await.ok()?;
+    Some((target, metadata))
+}
+
 /// Scan local directory iteratively and build file info map.
 /// When `compare_checksum` is true, computes SHA-256 for each file.
 pub async fn get_local_files_recursive(
@@ -4428,6 +4454,7 @@ pub async fn get_local_files_recursive(
     _current_path: &str,
     exclude_patterns: &[String],
     compare_checksum: bool,
+    symlink_mode: SymlinkMode,
     cancel_flag: Option<&std::sync::atomic::AtomicBool>,
 ) -> Result<HashMap<String, FileInfo>, String> {
     let mut files = HashMap::new();
@@ -4436,6 +4463,9 @@ pub async fn get_local_files_recursive(
     if !base.exists() {
         return Ok(files);
     }
+    let canonical_base = tokio::fs::canonicalize(&base).await.unwrap_or_else(|_| base.clone());
+    // Directories already entered through a link, so link loops end
+    let mut followed_dirs = std::collections::HashSet::new();
 
     // Use a stack for iterative traversal instead of recursion
     let mut dirs_to_process = vec![base.clone()];
@@ -4469,14 +4499,33 @@ pub async fn get_local_files_recursive(
 
             // H22: Use symlink_metadata to avoid following symlinks outside sync root.
             // This returns metadata about the symlink itself, not its target.
-            let metadata = tokio::fs::symlink_metadata(&path).await.ok();
+            let mut metadata = tokio::fs::symlink_metadata(&path).await.ok();
+            let mut link_target = None;
 
-            // Skip symlinks entirely to prevent data exfiltration via malicious symlinks
             if metadata.as_ref().map(|m| m.file_type().is_symlink()).unwrap_or(false) {
-                continue;
+                match symlink_mode {
+                    // Skip symlinks entirely to prevent data exfiltration via malicious symlinks
+                    SymlinkMode::Skip => continue,
+                    SymlinkMode::CopyAsLink => {
+                        match tokio::fs::read_link(&path).await {
+                            Ok(target) => link_target = Some(target.to_string_lossy().to_string()),
+                            Err(_) => continue,
+                        }
+                    }
+                    // Only targets inside the sync root, each linked directory entered once
+                    SymlinkMode::Follow => match followed_link_metadata(&path, &canonical_base).await {
+                        Some((target, target_meta)) => {
+                            if target_meta.is_dir() && !followed_dirs.insert(target) {
+                                continue;
+                            }
+                            metadata = Some(target_meta);
+                        }
+                        None => continue,
+                    },
+                }
             }
 
-            let is_dir = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
+            let is_dir = link_target.is_none() && metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
 
             let modified = metadata.as_ref().and_then(|m| {
                 m.modified().ok().map(|t| {
@@ -4485,14 +4534,14 @@ pub async fn get_local_files_recursive(
                 })
             });
 
-            let size = if is_dir {
+            let size = if is_dir || link_target.is_some() {
                 0
             } else {
                 metadata.as_ref().map(|m| m.len()).unwrap_or(0)
             };
 
             // Compute SHA-256 checksum if requested (only for files, not directories)
-            let checksum = if compare_checksum && !is_dir {
+            let checksum = if compare_checksum && !is_dir && link_target.is_none() {
                 compute_sha256(&path).await
             } else {
                 None
@@ -4505,6 +4554,9 @@ pub async fn get_local_files_recursive(
                 modified,
                 is_dir,
                 checksum,
+                checksum_algorithm: None,
+                checksum_weak: false,
+                link_target,
             };
 
             // P2-1: Cap file index at 1M entries to prevent unbounded memory growth
@@ -4620,6 +4672,9 @@ pub async fn get_local_files_recursive_parallel(
                     modified,
                     is_dir: true,
                     checksum: None,
+                    checksum_algorithm: None,
+                    checksum_weak: false,
+                    link_target: None,
                 });
                 continue;
             }
@@ -4648,6 +4703,9 @@ pub async fn get_local_files_recursive_parallel(
                     modified,
                     is_dir: false,
                     checksum,
+                    checksum_algorithm: None,
+                    checksum_weak: false,
+                    link_target: None,
                 });
             }
         }
@@ -4665,6 +4723,9 @@ pub async fn get_local_files_recursive_parallel(
                 modified,
                 is_dir,
                 checksum: None,
+                checksum_algorithm: None,
+                checksum_weak: false,
+                link_target: None,
             });
         }
     }
@@ -4738,6 +4799,9 @@ async fn get_remote_files_recursive_with_progress(
                 }),
                 is_dir: entry.is_dir,
                 checksum: None,
+                checksum_algorithm: None,
+                checksum_weak: false,
+                link_target: None,
             };
 
             files.insert(relative_path, file_info);
@@ -4906,6 +4970,8 @@ fn get_transfer_optimization_hints(provider_type: Option<String>) -> Result<prov
             supports_resume_upload: true,
             supports_compression: true,
             supports_delta_sync: true,
+            pipeline_depth: providers::sftp_pipeline::DEFAULT_DEPTH,
+            pipeline_block_size: providers::sftp_pipeline::DEFAULT_BLOCK_SIZE,
             ..Default::default()
         },
         "s3" => providers::TransferOptimizationHints {
@@ -4926,6 +4992,28 @@ fn get_transfer_optimization_hints(provider_type: Option<String>) -> Result<prov
             supports_resume_download: true,
             ..Default::default()
         },
+        // Content hashes these providers report in their listings. Google Drive and
+        // OneDrive fall back to MD5 / SHA-1 and QuickXorHash for files without SHA-256.
+        "azure" | "gcs" | "swift" => providers::TransferOptimizationHints {
+            supports_server_checksum: true,
+            preferred_checksum_algo: Some(providers::HashAlgorithm::Md5.as_str().to_string()),
+            ..Default::default()
+        },
+        "googledrive" | "onedrive" => providers::TransferOptimizationHints {
+            supports_server_checksum: true,
+            preferred_checksum_algo: Some(providers::HashAlgorithm::Sha256.as_str().to_string()),
+            ..Default::default()
+        },
+        "dropbox" => providers::TransferOptimizationHints {
+            supports_server_checksum: true,
+            preferred_checksum_algo: Some(providers::HashAlgorithm::DropboxContentHash.as_str().to_string()),
+            ..Default::default()
+        },
+        "box" | "b2" => providers::TransferOptimizationHints {
+            supports_server_checksum: true,
+            preferred_checksum_algo: Some(providers::HashAlgorithm::Sha1.as_str().to_string()),
+            ..Default::default()
+        },
         _ => providers::TransferOptimizationHints::default(),
     };
     Ok(hints)
@@ -5083,6 +5171,7 @@ async fn sync_canary_run(
         &local_path,
         &exclude_patterns,
         false,
+        SymlinkMode::Skip,
         None,
     )
     .await?;
@@ -6997,6 +7086,12 @@ pub fn run() {
                 }
             }
 
+            // Forward SSH keyboard-interactive (2FA) prompts to the frontend
+            let prompt_handle = app.handle().clone();
+            providers::ssh_auth::set_prompt_emitter(Box::new(move |prompt| {
+                let _ = prompt_handle.emit("ssh_keyboard_interactive", prompt);
+            }));
+
             // Start mount watcher — emits 'volumes-changed' events instead of 5s polling
             filesystem::start_mount_watcher(app.handle().clone());
 
@@ -7282,6 +7377,10 @@ pub fn run() {
     let builder = builder.manage(create_pty_state());
     // Add SSH shell state for remote shell sessions
     let builder = builder.manage(create_ssh_shell_state());
+    // Add SSH tunnel state for port forwards
+    let builder = builder.manage(create_ssh_tunnel_state());
+    // Add replay state for terminal recordings
+    let builder = builder.manage(create_terminal_replay_state());
     let builder = builder.manage(cryptomator::CryptomatorState::new());
     // Master Password state for app-level security
     let builder = builder.manage(master_password::MasterPasswordState::new());
@@ -7476,12 +7575,15 @@ pub fn run() {
             aerovault_v2::vault_v2_delete_entries,
             aerovault_v2::vault_v2_add_files_to_dir,
             aerovault_v2::vault_v2_compact,
+            aerovault_v2::vault_v2_verify,
             aerovault_v2::vault_v2_sync_compare,
             aerovault_v2::vault_v2_sync_apply,
             // Remote Vault — open .aerovault on remote servers
             vault_remote::vault_v2_download_remote,
             vault_remote::vault_v2_upload_remote,
             vault_remote::vault_v2_cleanup_temp,
+            vault_remote::vault_v2_open_remote,
+            vault_remote::vault_v2_extract_entry_remote,
             // Cryptomator vault support
             cryptomator::cryptomator_unlock,
             cryptomator::cryptomator_lock,
@@ -7489,6 +7591,11 @@ pub fn run() {
             cryptomator::cryptomator_decrypt_file,
             cryptomator::cryptomator_encrypt_file,
             cryptomator::cryptomator_create,
+            cryptomator::cryptomator_rename,
+            cryptomator::cryptomator_delete,
+            cryptomator::cryptomator_change_password,
+            cryptomator::cryptomator_mount_remote,
+            cryptomator::cryptomator_unmount_remote,
             ai_stream::ai_chat_stream,
             ai::ollama_pull_model,
             ai::gemini_create_cache,
@@ -7498,6 +7605,8 @@ pub fn run() {
             ai::deepseek_fim_complete,
             // Multi-protocol provider commands
             provider_commands::provider_connect,
+            provider_commands::ssh_keyboard_interactive_respond,
+            provider_commands::set_global_proxy,
             provider_commands::provider_disconnect,
             provider_commands::provider_check_connection,
             provider_commands::provider_list_files,
@@ -7524,6 +7633,7 @@ pub fn run() {
             provider_commands::oauth2_connect,
             provider_commands::oauth2_full_auth,
             provider_commands::oauth2_has_tokens,
+            provider_commands::oauth2_list_accounts,
             provider_commands::oauth2_logout,
             // 4shared OAuth 1.0 commands
             provider_commands::fourshared_start_auth,
@@ -7599,6 +7709,7 @@ pub fn run() {
             session_commands::session_rename,
             session_commands::session_download,
             session_commands::session_upload,
+            session_commands::session_transfer,
             session_commands::session_create_share_link,
             spawn_shell,
             pty_write,
@@ -7608,10 +7719,30 @@ pub fn run() {
             ssh_shell_write,
             ssh_shell_resize,
             ssh_shell_close,
+            ssh_shared_connections,
+            ssh_tunnel_list_saved,
+            ssh_tunnel_save,
+            ssh_tunnel_delete,
+            ssh_tunnel_start,
+            ssh_tunnel_stop,
+            ssh_tunnel_status,
+            ssh_batch_run,
+            ssh_batch_export,
+            terminal_recording_get_settings,
+            terminal_recording_set_settings,
+            terminal_recordings_list,
+            terminal_recording_delete,
+            terminal_recording_to_vault,
+            terminal_recording_replay,
+            terminal_replay_stop,
             // Host key verification (TOFU UX)
             sftp_check_host_key,
             sftp_accept_host_key,
             sftp_remove_host_key,
+            // TLS certificate verification (TOFU UX)
+            tls_check_certificate,
+            tls_accept_certificate,
+            tls_remove_certificate,
             // Plugin system
             plugins::list_plugins,
             plugins::execute_plugin_tool,
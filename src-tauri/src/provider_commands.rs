@@ -77,14 +77,106 @@ pub struct ProviderConnectionParams {
     pub private_key_path: Option<String>,
     /// SFTP: Passphrase for encrypted private key
     pub key_passphrase: Option<String>,
-    /// SFTP: Connection timeout in seconds
+    /// SFTP: Additional private key files, tried in order
+    pub identity_files: Option<Vec<String>>,
+    /// SFTP: OpenSSH user certificate (defaults to `<key>-cert.pub`)
+    pub certificate_path: Option<String>,
+    /// SFTP: Offer ssh-agent identities (default true)
+    pub use_agent: Option<bool>,
+    /// SFTP: Allow keyboard-interactive / 2FA prompts (default true)
+    pub keyboard_interactive: Option<bool>,
+    /// SFTP: Resolve the host through ~/.ssh/config, including ProxyJump (default true)
+    pub ssh_config: Option<bool>,
+    /// SFTP: "auto" (SFTP, else rsync/scp), "sftp", "scp" or "rsync"
+    pub sftp_transfer_mode: Option<String>,
+    /// SFTP: READ/WRITE requests in flight per transfer (1-1024, default 64)
+    pub sftp_pipeline_depth: Option<u32>,
+    /// SFTP: Bytes per READ/WRITE request (1024-262144, default 32768)
+    pub sftp_block_size: Option<u32>,
+    /// SFTP: Metadata kept on transfers, e.g. "mode,times", "owner", "xattrs", "hardlinks" or "all"
+    pub sftp_preserve: Option<String>,
+    /// SFTP/SMB: Connection timeout in seconds
     pub timeout: Option<u64>,
     /// FTP/FTPS: TLS mode ("none", "explicit", "implicit", "explicit_if_available")
     pub tls_mode: Option<String>,
     /// FTP/FTPS: Accept invalid/self-signed certificates
     pub verify_cert: Option<bool>,
+    /// FTP/FTPS: Data connection mode ("passive", "epsv", "active")
+    pub ftp_data_mode: Option<String>,
+    /// FTP/FTPS: Local port range for active mode, e.g. "50000-50100"
+    pub ftp_active_ports: Option<String>,
+    /// FTP/FTPS: Address announced in PORT/EPRT when behind NAT
+    pub ftp_external_ip: Option<String>,
+    /// FTP/FTPS: Replace private PASV addresses with the server address (default true)
+    pub ftp_pasv_nat_workaround: Option<bool>,
+    /// FTPS/WebDAV: PEM bundle of additional trusted CA certificates
+    pub ca_cert: Option<String>,
+    /// FTPS/WebDAV: Credential vault account holding the client certificate (mutual TLS)
+    pub client_cert: Option<String>,
+    /// FTPS/WebDAV: Server certificate pin ("SHA256:<hex>" or "sha256/<base64 SPKI>")
+    pub cert_pin: Option<String>,
+    /// Swift: Keystone project (tenant) name
+    pub swift_project: Option<String>,
+    /// Swift: Keystone domain of user and project (default "Default")
+    pub swift_domain: Option<String>,
+    /// B2: Delete all versions instead of hiding files
+    pub b2_hard_delete: Option<bool>,
+    /// SMB: Share name (empty lists the server's shares)
+    pub smb_share: Option<String>,
+    /// SMB: NTLM domain or workgroup
+    pub smb_domain: Option<String>,
+    /// SMB: Require an encrypted session (SMB 3.x)
+    pub smb_encrypt: Option<bool>,
     /// Filen: Optional TOTP 2FA code
     pub two_factor_code: Option<String>,
+    /// Client-side encryption: password (enables the crypt overlay)
+    pub crypt_password: Option<String>,
+    /// Client-side encryption: optional second secret for key derivation
+    pub crypt_salt: Option<String>,
+    /// Client-side encryption: remote directory holding the encrypted tree
+    pub crypt_root: Option<String>,
+    /// Outbound proxy URL (`http://`, `socks5://`, `socks5h://`) or `none`; empty follows the global setting
+    pub proxy: Option<String>,
+    /// Comma-separated hosts that bypass `proxy`
+    pub no_proxy: Option<String>,
+}
+
+/// Add client-side encryption options to a provider `extra` map
+pub(crate) fn insert_crypt_options(
+    extra: &mut std::collections::HashMap<String, String>,
+    password: &Option<String>,
+    salt: &Option<String>,
+    root: &Option<String>,
+) {
+    let Some(password) = password.as_ref().filter(|p| !p.is_empty()) else { return };
+    extra.insert("crypt_password".to_string(), password.clone());
+    if let Some(salt) = salt.as_ref().filter(|s| !s.is_empty()) {
+        extra.insert("crypt_salt".to_string(), salt.clone());
+    }
+    if let Some(root) = root.as_ref().filter(|r| !r.is_empty()) {
+        extra.insert("crypt_root".to_string(), root.clone());
+    }
+}
+
+/// Layer the crypt overlay over an already connected provider (OAuth flows
+/// bypass `ProviderFactory`, which applies it for everything else)
+async fn apply_crypt_overlay(
+    provider: Box<dyn StorageProvider>,
+    password: &Option<String>,
+    salt: &Option<String>,
+    root: &Option<String>,
+) -> Result<Box<dyn StorageProvider>, String> {
+    let mut extra = std::collections::HashMap::new();
+    insert_crypt_options(&mut extra, password, salt, root);
+    match crate::providers::CryptConfig::from_extra(&extra) {
+        Some(crypt) => {
+            let mut wrapped = crate::providers::CryptProvider::new(provider, crypt);
+            wrapped.connect().await
+                .map_err(|e| format!("Encryption setup failed: {}", e))?;
+            Ok(Box::new(wrapped))
+        }
+        None => Ok(provider),
+    }
 }
 
 impl ProviderConnectionParams {
@@ -106,6 +198,10 @@ impl ProviderConnectionParams {
             "jottacloud" => ProviderType::Jottacloud,
             "drime" => ProviderType::DrimeCloud,
             "filelu" => ProviderType::FileLu,
+            "swift" => ProviderType::Swift,
+            "b2" => ProviderType::B2,
+            "gcs" => ProviderType::Gcs,
+            "smb" => ProviderType::Smb,
             other => return Err(format!("Unknown protocol: {}", other)),
         };
 
@@ -139,6 +235,27 @@ impl ProviderConnectionParams {
             if let Some(verify) = self.verify_cert {
                 extra.insert("verify_cert".to_string(), verify.to_string());
             }
+            if let Some(ref mode) = self.ftp_data_mode {
+                extra.insert("data_mode".to_string(), mode.clone());
+            }
+            if let Some(ref ports) = self.ftp_active_ports {
+                extra.insert("active_ports".to_string(), ports.clone());
+            }
+            if let Some(ref ip) = self.ftp_external_ip {
+                extra.insert("external_ip".to_string(), ip.clone());
+            }
+            if let Some(workaround) = self.ftp_pasv_nat_workaround {
+                extra.insert("pasv_nat_workaround".to_string(), workaround.to_string());
+            }
+        }
+
+        // Add TLS trust options (FTPS and WebDAV)
+        if matches!(provider_type, ProviderType::Ftp | ProviderType::Ftps | ProviderType::WebDav) {
+            for (key, value) in [("ca_cert", &self.ca_cert), ("client_cert", &self.client_cert), ("cert_pin", &self.cert_pin)] {
+                if let Some(value) = value {
+                    extra.insert(key.to_string(), value.clone());
+                }
+            }
         }
 
         // Add MEGA-specific options
@@ -162,6 +279,46 @@ impl ProviderConnectionParams {
             // account_name comes from username field
         }
 
+        // Add Swift/B2/GCS-specific options (bucket field holds the container/bucket name)
+        if matches!(provider_type, ProviderType::Swift | ProviderType::B2 | ProviderType::Gcs) {
+            let key = if provider_type == ProviderType::Swift { "container" } else { "bucket" };
+            match self.bucket {
+                Some(ref bucket) if !bucket.is_empty() => { extra.insert(key.to_string(), bucket.clone()); }
+                _ => return Err(format!("{} requires a {} name", provider_type, key)),
+            }
+        }
+        if provider_type == ProviderType::Swift {
+            if let Some(ref project) = self.swift_project {
+                extra.insert("project".to_string(), project.clone());
+            }
+            if let Some(ref domain) = self.swift_domain {
+                extra.insert("user_domain".to_string(), domain.clone());
+                extra.insert("project_domain".to_string(), domain.clone());
+            }
+            if let Some(ref region) = self.region {
+                extra.insert("region".to_string(), region.clone());
+            }
+        }
+        if provider_type == ProviderType::B2 && self.b2_hard_delete.unwrap_or(false) {
+            extra.insert("hard_delete".to_string(), "true".to_string());
+        }
+
+        // Add SMB-specific options
+        if provider_type == ProviderType::Smb {
+            if let Some(ref share) = self.smb_share {
+                extra.insert("share".to_string(), share.clone());
+            }
+            if let Some(ref domain) = self.smb_domain {
+                extra.insert("domain".to_string(), domain.clone());
+            }
+            if self.smb_encrypt.unwrap_or(false) {
+                extra.insert("encrypt".to_string(), "true".to_string());
+            }
+            if let Some(timeout) = self.timeout {
+                extra.insert("timeout".to_string(), timeout.to_string());
+            }
+        }
+
         // Add Filen/Internxt-specific options
         if provider_type == ProviderType::Filen || provider_type == ProviderType::Internxt {
             if let Some(ref code) = self.two_factor_code {
@@ -202,11 +359,65 @@ impl ProviderConnectionParams {
                     extra.insert("key_passphrase".to_string(), passphrase.clone());
                 }
             }
+            if let Some(ref identities) = self.identity_files {
+                let identities: Vec<&str> = identities.iter()
+                    .map(|p| p.trim())
+                    .filter(|p| !p.is_empty())
+                    .collect();
+                if !identities.is_empty() {
+                    extra.insert("identity_files".to_string(), identities.join("\n"));
+                }
+            }
+            if let Some(ref cert_path) = self.certificate_path {
+                if !cert_path.is_empty() {
+                    extra.insert("certificate_path".to_string(), cert_path.clone());
+                }
+            }
+            if let Some(use_agent) = self.use_agent {
+                extra.insert("use_agent".to_string(), use_agent.to_string());
+            }
+            if let Some(kbd) = self.keyboard_interactive {
+                extra.insert("keyboard_interactive".to_string(), kbd.to_string());
+            }
+            if let Some(use_config) = self.ssh_config {
+                extra.insert("ssh_config".to_string(), use_config.to_string());
+            }
+            if let Some(ref mode) = self.sftp_transfer_mode {
+                if !mode.is_empty() {
+                    extra.insert("transfer_mode".to_string(), mode.clone());
+                }
+            }
+            if let Some(depth) = self.sftp_pipeline_depth {
+                extra.insert("pipeline_depth".to_string(), depth.to_string());
+            }
+            if let Some(block_size) = self.sftp_block_size {
+                extra.insert("block_size".to_string(), block_size.to_string());
+            }
+            if let Some(ref preserve) = self.sftp_preserve {
+                if !preserve.is_empty() {
+                    extra.insert("preserve".to_string(), preserve.clone());
+                }
+            }
             if let Some(timeout) = self.timeout {
                 extra.insert("timeout".to_string(), timeout.to_string());
             }
         }
 
+        // Client-side encryption overlay (any provider)
+        insert_crypt_options(&mut extra, &self.crypt_password, &self.crypt_salt, &self.crypt_root);
+
+        // Outbound proxy (any provider)
+        if let Some(ref proxy) = self.proxy {
+            if !proxy.is_empty() {
+                extra.insert("proxy".to_string(), proxy.clone());
+            }
+        }
+        if let Some(ref no_proxy) = self.no_proxy {
+            if !no_proxy.is_empty() {
+                extra.insert("no_proxy".to_string(), no_proxy.clone());
+            }
+        }
+
         let host = if provider_type == ProviderType::Mega {
             "mega.nz".to_string()
         } else if provider_type == ProviderType::Internxt {
@@ -219,6 +430,10 @@ impl ProviderConnectionParams {
             "app.drime.cloud".to_string()
         } else if provider_type == ProviderType::FileLu {
             "filelu.com".to_string()
+        } else if provider_type == ProviderType::B2 {
+            "api.backblazeb2.com".to_string()
+        } else if provider_type == ProviderType::Gcs {
+            "storage.googleapis.com".to_string()
         } else if provider_type == ProviderType::Azure {
             // Azure constructs endpoint from account_name if server is empty
             if self.server.is_empty() {
@@ -294,6 +509,42 @@ pub async fn provider_connect(
     Ok(format!("Connected to {} via {}", display_name, protocol))
 }
 
+/// Answer the keyboard-interactive prompt emitted as `ssh_keyboard_interactive`
+/// while an SFTP connection authenticates. `responses: None` cancels the login.
+#[tauri::command]
+pub async fn ssh_keyboard_interactive_respond(
+    request_id: String,
+    responses: Option<Vec<String>>,
+) -> Result<(), String> {
+    crate::providers::ssh_auth::respond_to_prompt(&request_id, responses)
+}
+
+/// Set the proxy used by connections whose profile has no `proxy` of its own.
+/// `mode` is `"system"` (proxy environment variables, the default), `"none"`, or
+/// `"manual"` with `url` (`http://`, `socks5://` or `socks5h://`, credentials allowed).
+#[tauri::command]
+pub async fn set_global_proxy(
+    mode: String,
+    url: Option<String>,
+    no_proxy: Option<String>,
+) -> Result<(), String> {
+    use crate::providers::proxy::{self, GlobalProxy, ProxyConfig};
+
+    let policy = match mode.as_str() {
+        "system" => GlobalProxy::System,
+        "none" => GlobalProxy::Direct,
+        "manual" => {
+            let url = url.filter(|u| !u.trim().is_empty())
+                .ok_or_else(|| "A proxy URL is required in manual mode".to_string())?;
+            GlobalProxy::Manual(ProxyConfig::parse(&url, no_proxy.as_deref()).map_err(|e| e.to_string())?)
+        }
+        other => return Err(format!("Unknown proxy mode: {}", other)),
+    };
+    info!("Global proxy set to {}", mode);
+    proxy::set_global(policy);
+    Ok(())
+}
+
 /// Disconnect from the current provider
 #[tauri::command]
 pub async fn provider_disconnect(
@@ -441,6 +692,7 @@ pub async fn provider_download_file(
     state: State<'_, ProviderState>,
     remote_path: String,
     local_path: String,
+    link_target: Option<String>,
 ) -> Result<String, String> {
     let mut provider_lock = state.provider.lock().await;
     
@@ -459,6 +711,13 @@ pub async fn provider_download_file(
         let _ = tokio::fs::create_dir_all(parent).await;
     }
     
+    // Sync with copy-as-link: recreate the link, don't download its target
+    if let Some(target) = link_target {
+        create_local_symlink(&target, &local_path).await?;
+        info!("Link created: {} -> {}", local_path, target);
+        return Ok(format!("Linked: {} -> {}", filename, target));
+    }
+    
     provider.download(&remote_path, &local_path, None).await
         .map_err(|e| format!("Download failed: {}", e))?;
     
@@ -466,6 +725,27 @@ pub async fn provider_download_file(
     Ok(format!("Downloaded: {}", filename))
 }
 
+/// Replace the file or link at `link_path` with a symlink to `target`
+async fn create_local_symlink(target: &str, link_path: &str) -> Result<(), String> {
+    if let Ok(metadata) = tokio::fs::symlink_metadata(link_path).await {
+        if metadata.is_dir() {
+            return Err(format!("Cannot replace directory {} with a link", link_path));
+        }
+        tokio::fs::remove_file(link_path).await
+            .map_err(|e| format!("Failed to remove {}: {}", link_path, e))?;
+    }
+    #[cfg(unix)]
+    {
+        tokio::fs::symlink(target, link_path).await
+            .map_err(|e| format!("Failed to create link {}: {}", link_path, e))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = target;
+        Err("Symbolic links can only be recreated on Linux and macOS".to_string())
+    }
+}
+
 /// Download a folder recursively from the remote server (OAuth providers)
 #[tauri::command]
 pub async fn provider_download_folder(
@@ -816,6 +1096,7 @@ pub async fn provider_upload_file(
     state: State<'_, ProviderState>,
     local_path: String,
     remote_path: String,
+    link_target: Option<String>,
 ) -> Result<String, String> {
     let mut provider_lock = state.provider.lock().await;
     
@@ -829,6 +1110,21 @@ pub async fn provider_upload_file(
     
     info!("Uploading via provider: {} -> {}", local_path, remote_path);
     
+    // Sync with copy-as-link: replace the remote entry with the same link. Only an
+    // existing link is replaced; a file or directory at that path is left alone.
+    if let Some(target) = link_target {
+        if provider.read_link(&remote_path).await.is_ok() {
+            provider.delete(&remote_path).await
+                .map_err(|e| format!("Failed to replace link: {}", e))?;
+        } else if provider.exists(&remote_path).await.unwrap_or(false) {
+            return Err(format!("{} already exists and is not a link, not replacing it", remote_path));
+        }
+        provider.create_symlink(&target, &remote_path).await
+            .map_err(|e| format!("Failed to create link: {}", e))?;
+        info!("Link created: {} -> {}", remote_path, target);
+        return Ok(format!("Linked: {} -> {}", filename, target));
+    }
+    
     provider.upload(&local_path, &remote_path, None).await
         .map_err(|e| format!("Upload failed: {}", e))?;
     
@@ -1042,6 +1338,15 @@ pub struct OAuthConnectionParams {
     /// Region for multi-region providers (Zoho: "us", "eu", "in", "au", "jp", "ca", "sa")
     #[serde(default = "default_region")]
     pub region: String,
+    /// Account (email) of a saved profile; omit when signing in a new account
+    pub account: Option<String>,
+    /// Client-side encryption overlay (see `ProviderConnectionParams`)
+    pub crypt_password: Option<String>,
+    pub crypt_salt: Option<String>,
+    pub crypt_root: Option<String>,
+    /// Outbound proxy (see `ProviderConnectionParams`)
+    pub proxy: Option<String>,
+    pub no_proxy: Option<String>,
 }
 
 fn default_region() -> String {
@@ -1133,6 +1438,8 @@ pub async fn oauth2_complete_auth(
         }
         other => return Err(format!("Unknown OAuth2 provider: {}", other)),
     };
+    // Re-authorizing a saved account refreshes its own token slot
+    let config = config.with_account(params.account.as_deref());
 
     let manager = OAuth2Manager::new();
     manager.complete_auth_flow(&config, &code, &state).await
@@ -1160,24 +1467,26 @@ pub async fn oauth2_connect(
                           zoho_workdrive::ZohoWorkdriveConfig};
 
     info!("Connecting to OAuth2 provider: {}", params.provider);
+    let proxy = crate::providers::proxy::from_setting(params.proxy.as_deref(), params.no_proxy.as_deref())
+        .map_err(|e| e.to_string())?;
 
     let provider: Box<dyn StorageProvider> = match params.provider.to_lowercase().as_str() {
         "google_drive" | "googledrive" | "google" => {
-            let config = GoogleDriveConfig::new(&params.client_id, &params.client_secret);
+            let config = GoogleDriveConfig { proxy, account: params.account.clone(), ..GoogleDriveConfig::new(&params.client_id, &params.client_secret) };
             let mut p = GoogleDriveProvider::new(config);
             p.connect().await
                 .map_err(|e| format!("Google Drive connection failed: {}", e))?;
             Box::new(p)
         }
         "dropbox" => {
-            let config = DropboxConfig::new(&params.client_id, &params.client_secret);
+            let config = DropboxConfig { proxy, account: params.account.clone(), ..DropboxConfig::new(&params.client_id, &params.client_secret) };
             let mut p = DropboxProvider::new(config);
             p.connect().await
                 .map_err(|e| format!("Dropbox connection failed: {}", e))?;
             Box::new(p)
         }
         "onedrive" | "microsoft" => {
-            let config = OneDriveConfig::new(&params.client_id, &params.client_secret);
+            let config = OneDriveConfig { proxy, account: params.account.clone(), ..OneDriveConfig::new(&params.client_id, &params.client_secret) };
             let mut p = OneDriveProvider::new(config);
             p.connect().await
                 .map_err(|e| format!("OneDrive connection failed: {}", e))?;
@@ -1187,6 +1496,8 @@ pub async fn oauth2_connect(
             let config = BoxConfig {
                 client_id: params.client_id.clone(),
                 client_secret: params.client_secret.clone(),
+                proxy,
+                account: params.account.clone(),
             };
             let mut p = BoxProvider::new(config);
             p.connect().await
@@ -1198,6 +1509,8 @@ pub async fn oauth2_connect(
                 client_id: params.client_id.clone(),
                 client_secret: params.client_secret.clone(),
                 region: params.region.clone(),
+                proxy,
+                account: params.account.clone(),
             };
             let mut p = PCloudProvider::new(config);
             p.connect().await
@@ -1205,7 +1518,7 @@ pub async fn oauth2_connect(
             Box::new(p)
         }
         "zoho" | "zoho_workdrive" | "zohoworkdrive" => {
-            let config = ZohoWorkdriveConfig::new(&params.client_id, &params.client_secret, &params.region);
+            let config = ZohoWorkdriveConfig { proxy, account: params.account.clone(), ..ZohoWorkdriveConfig::new(&params.client_id, &params.client_secret, &params.region) };
             let mut p = ZohoWorkdriveProvider::new(config);
             p.connect().await
                 .map_err(|e| format!("Zoho WorkDrive connection failed: {}", e))?;
@@ -1213,7 +1526,8 @@ pub async fn oauth2_connect(
         }
         other => return Err(format!("Unknown OAuth2 provider: {}", other)),
     };
-    
+    let provider = apply_crypt_overlay(provider, &params.crypt_password, &params.crypt_salt, &params.crypt_root).await?;
+
     let display_name = provider.display_name();
     let account_email = provider.account_email();
 
@@ -1271,6 +1585,8 @@ pub async fn oauth2_full_auth(
         }
         other => return Err(format!("Unknown OAuth2 provider: {}", other)),
     };
+    // Re-authorizing a saved account refreshes its own token slot
+    let config = config.with_account(params.account.as_deref());
 
     // Create manager ONCE and keep it for the entire flow
     let manager = OAuth2Manager::new();
@@ -1318,49 +1634,55 @@ pub async fn oauth2_full_auth(
     Ok("Authentication successful! You can now connect.".to_string())
 }
 
-/// Check if OAuth2 tokens exist for a provider
+/// Map a provider name from the frontend to its OAuth2 provider
+fn parse_oauth_provider(provider: &str) -> Result<crate::providers::OAuthProvider, String> {
+    use crate::providers::OAuthProvider;
+
+    match provider.to_lowercase().as_str() {
+        "google_drive" | "googledrive" | "google" => Ok(OAuthProvider::Google),
+        "dropbox" => Ok(OAuthProvider::Dropbox),
+        "onedrive" | "microsoft" => Ok(OAuthProvider::OneDrive),
+        "box" => Ok(OAuthProvider::Box),
+        "pcloud" => Ok(OAuthProvider::PCloud),
+        "zoho" | "zoho_workdrive" | "zohoworkdrive" => Ok(OAuthProvider::ZohoWorkdrive),
+        other => Err(format!("Unknown OAuth2 provider: {}", other)),
+    }
+}
+
+/// Check if OAuth2 tokens exist for a provider account (`account: None` = a
+/// new sign-in waiting to be connected, or the only account of older profiles)
 #[tauri::command]
 pub async fn oauth2_has_tokens(
     provider: String,
+    account: Option<String>,
 ) -> Result<bool, String> {
-    use crate::providers::{OAuth2Manager, OAuthProvider};
-    
-    let oauth_provider = match provider.to_lowercase().as_str() {
-        "google_drive" | "googledrive" | "google" => OAuthProvider::Google,
-        "dropbox" => OAuthProvider::Dropbox,
-        "onedrive" | "microsoft" => OAuthProvider::OneDrive,
-        "box" => OAuthProvider::Box,
-        "pcloud" => OAuthProvider::PCloud,
-        "zoho" | "zoho_workdrive" | "zohoworkdrive" => OAuthProvider::ZohoWorkdrive,
-        other => return Err(format!("Unknown OAuth2 provider: {}", other)),
-    };
+    let oauth_provider = parse_oauth_provider(&provider)?;
+    let manager = crate::providers::OAuth2Manager::new();
+    Ok(manager.has_tokens(oauth_provider, account.as_deref()))
+}
 
-    let manager = OAuth2Manager::new();
-    Ok(manager.has_tokens(oauth_provider))
+/// List the accounts signed in for a provider
+#[tauri::command]
+pub async fn oauth2_list_accounts(
+    provider: String,
+) -> Result<Vec<String>, String> {
+    let oauth_provider = parse_oauth_provider(&provider)?;
+    Ok(crate::providers::OAuth2Manager::new().list_accounts(oauth_provider))
 }
 
-/// Clear OAuth2 tokens for a provider (logout)
+/// Clear OAuth2 tokens for a provider account (logout). Other accounts of the
+/// same provider stay signed in.
 #[tauri::command]
 pub async fn oauth2_logout(
     provider: String,
+    account: Option<String>,
 ) -> Result<(), String> {
-    use crate::providers::{OAuth2Manager, OAuthProvider};
-    
-    let oauth_provider = match provider.to_lowercase().as_str() {
-        "google_drive" | "googledrive" | "google" => OAuthProvider::Google,
-        "dropbox" => OAuthProvider::Dropbox,
-        "onedrive" | "microsoft" => OAuthProvider::OneDrive,
-        "box" => OAuthProvider::Box,
-        "pcloud" => OAuthProvider::PCloud,
-        "zoho" | "zoho_workdrive" | "zohoworkdrive" => OAuthProvider::ZohoWorkdrive,
-        other => return Err(format!("Unknown OAuth2 provider: {}", other)),
-    };
-
-    let manager = OAuth2Manager::new();
-    manager.clear_tokens(oauth_provider)
+    let oauth_provider = parse_oauth_provider(&provider)?;
+    let manager = crate::providers::OAuth2Manager::new();
+    manager.clear_tokens(oauth_provider, account.as_deref())
         .map_err(|e| format!("Failed to clear tokens: {}", e))?;
-    
-    info!("Logged out from {}", provider);
+
+    info!("Logged out from {} ({})", provider, account.as_deref().unwrap_or("unassigned"));
     Ok(())
 }
 
@@ -1738,18 +2060,34 @@ pub async fn provider_compare_directories(
     options: Option<crate::sync::CompareOptions>,
 ) -> Result<Vec<crate::sync::FileComparison>, String> {
     use std::collections::HashMap;
-    use crate::sync::{FileInfo, should_exclude, build_comparison_results_with_index, load_sync_index};
+    use crate::sync::{FileInfo, SymlinkMode, should_exclude, build_comparison_results_with_index, load_sync_index, resolve_link_path};
 
     let options = options.unwrap_or_default();
 
     info!("Provider compare: local={}, remote={}", local_path, remote_path);
 
+    // First check we're connected
+    let supports_symlinks = {
+        let provider_lock = state.provider.lock().await;
+        match provider_lock.as_ref() {
+            Some(provider) => provider.supports_symlinks(),
+            None => return Err("Not connected to any provider".to_string()),
+        }
+    };
+
+    // Links are only copied as links where the provider can create them
+    let symlink_mode = match options.symlink_mode {
+        SymlinkMode::CopyAsLink if !supports_symlinks => SymlinkMode::Skip,
+        mode => mode,
+    };
+
     let _ = app.emit("sync_scan_progress", serde_json::json!({
         "phase": "local", "files_found": 0,
     }));
 
-    // Get local files (reuse the same logic from lib.rs)
-    let local_files = crate::get_local_files_recursive(&local_path, &local_path, &options.exclude_patterns, options.compare_checksum, None)
+    // Get local files (reuse the same logic from lib.rs). Checksums are computed
+    // after the remote scan, with whatever algorithm the provider's listing uses.
+    let mut local_files = crate::get_local_files_recursive(&local_path, &local_path, &options.exclude_patterns, false, symlink_mode, None)
         .await
         .map_err(|e| format!("Failed to scan local directory: {}", e))?;
 
@@ -1760,14 +2098,8 @@ pub async fn provider_compare_directories(
     // Get remote files via provider - lock/unlock per directory to avoid blocking other operations
     let mut remote_files: HashMap<String, FileInfo> = HashMap::new();
     let mut dirs_to_process = vec![remote_path.clone()];
-
-    // First check we're connected
-    {
-        let provider_lock = state.provider.lock().await;
-        if provider_lock.is_none() {
-            return Err("Not connected to any provider".to_string());
-        }
-    }
+    // Directories already entered through a link, so link loops end
+    let mut followed_dirs = std::collections::HashSet::new();
 
     while let Some(current_dir) = dirs_to_process.pop() {
         // Lock provider only for this single list operation, then release
@@ -1800,6 +2132,37 @@ pub async fn provider_compare_directories(
                 continue;
             }
 
+            let sub_path = if current_dir.ends_with('/') {
+                format!("{}{}", current_dir, entry.name)
+            } else {
+                format!("{}/{}", current_dir, entry.name)
+            };
+
+            let mut link_target = None;
+            if entry.is_symlink {
+                match symlink_mode {
+                    SymlinkMode::Skip => continue,
+                    SymlinkMode::CopyAsLink => match &entry.link_target {
+                        Some(target) => link_target = Some(target.clone()),
+                        None => continue,
+                    },
+                    // Same rules as the local scan: stay under the sync root,
+                    // never descend into an ancestor, enter each directory once
+                    SymlinkMode::Follow => {
+                        let Some(target) = entry.link_target.as_deref().map(|t| resolve_link_path(&current_dir, t)) else {
+                            continue;
+                        };
+                        let root = remote_path.trim_end_matches('/');
+                        let inside = target == root || target.starts_with(&format!("{}/", root)) || root.is_empty();
+                        let ancestor = current_dir == target || current_dir.starts_with(&format!("{}/", target)) || target == "/";
+                        if !inside || (entry.is_dir && (ancestor || !followed_dirs.insert(target))) {
+                            continue;
+                        }
+                    }
+                }
+            }
+            let is_dir = entry.is_dir && link_target.is_none();
+
             let modified = entry.modified.and_then(|s| {
                 chrono::DateTime::parse_from_rfc3339(&s)
                     .map(|dt| dt.with_timezone(&chrono::Utc))
@@ -1816,23 +2179,22 @@ pub async fn provider_compare_directories(
                     })
             });
 
+            let content_hash = entry.content_hash.filter(|_| !is_dir && link_target.is_none());
             let file_info = FileInfo {
                 name: entry.name.clone(),
                 path: entry.path.clone(),
-                size: entry.size,
+                size: if link_target.is_some() { 0 } else { entry.size },
                 modified,
-                is_dir: entry.is_dir,
-                checksum: None,
+                is_dir,
+                checksum_algorithm: content_hash.as_ref().map(|h| h.algorithm),
+                checksum_weak: content_hash.as_ref().is_some_and(|h| h.weak),
+                checksum: content_hash.map(|h| h.value),
+                link_target,
             };
 
             remote_files.insert(relative_path, file_info);
 
-            if entry.is_dir {
-                let sub_path = if current_dir.ends_with('/') {
-                    format!("{}{}", current_dir, entry.name)
-                } else {
-                    format!("{}/{}", current_dir, entry.name)
-                };
+            if is_dir {
                 dirs_to_process.push(sub_path);
             }
         }
@@ -1843,6 +2205,12 @@ pub async fn provider_compare_directories(
         }));
     }
 
+    // Hash only the local files whose remote counterpart has a content hash;
+    // nothing is downloaded just to be hashed
+    if options.compare_checksum {
+        crate::content_hash::hash_local_files_like(&mut local_files, &remote_files).await;
+    }
+
     let _ = app.emit("sync_scan_progress", serde_json::json!({
         "phase": "comparing",
         "files_found": local_files.len() + remote_files.len(),
@@ -1862,6 +2230,13 @@ pub async fn provider_compare_directories(
 pub struct FourSharedAuthParams {
     pub consumer_key: String,
     pub consumer_secret: String,
+    /// Client-side encryption overlay (see `ProviderConnectionParams`)
+    pub crypt_password: Option<String>,
+    pub crypt_salt: Option<String>,
+    pub crypt_root: Option<String>,
+    /// Outbound proxy (see `ProviderConnectionParams`)
+    pub proxy: Option<String>,
+    pub no_proxy: Option<String>,
 }
 
 /// Result from starting 4shared OAuth flow
@@ -2255,21 +2630,24 @@ pub async fn fourshared_connect(
     let (access_token, access_token_secret) = load_fourshared_tokens()?;
 
     let config = FourSharedConfig {
-        consumer_key: params.consumer_key,
-        consumer_secret: params.consumer_secret.into(),
+        consumer_key: params.consumer_key.clone(),
+        consumer_secret: params.consumer_secret.clone().into(),
         access_token: access_token.into(),
         access_token_secret: access_token_secret.into(),
+        proxy: crate::providers::proxy::from_setting(params.proxy.as_deref(), params.no_proxy.as_deref())
+            .map_err(|e| e.to_string())?,
     };
 
     let mut provider = FourSharedProvider::new(config);
     provider.connect().await
         .map_err(|e| format!("4shared connection failed: {}", e))?;
+    let provider = apply_crypt_overlay(Box::new(provider), &params.crypt_password, &params.crypt_salt, &params.crypt_root).await?;
 
     let display_name = provider.display_name();
     let account_email = provider.account_email();
 
     let mut provider_lock = state.provider.lock().await;
-    *provider_lock = Some(Box::new(provider));
+    *provider_lock = Some(provider);
 
     info!("Connected to 4shared ({})", account_email.as_deref().unwrap_or("no email"));
     Ok(OAuth2ConnectResult { display_name, account_email })
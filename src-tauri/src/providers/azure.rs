@@ -22,7 +22,7 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tracing::{info, debug};
 
 use super::{
-    StorageProvider, ProviderType, ProviderError, RemoteEntry,
+    StorageProvider, ProviderType, ProviderError, RemoteEntry, ContentHash, HashAlgorithm,
     sanitize_api_error, HttpRetryConfig, send_with_retry,
 };
 use super::types::AzureConfig;
@@ -55,6 +55,8 @@ struct BlobItem {
     name: String,
     size: u64,
     last_modified: Option<String>,
+    /// Base64 Content-MD5 (absent for blobs uploaded in blocks without one)
+    content_md5: Option<String>,
     is_prefix: bool,  // virtual directory
 }
 
@@ -68,7 +70,7 @@ pub struct AzureProvider {
 
 impl AzureProvider {
     pub fn new(config: AzureConfig) -> Self {
-        let client = reqwest::Client::builder()
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .unwrap_or_else(|_| reqwest::Client::new());
@@ -218,6 +220,7 @@ impl AzureProvider {
             BlobProperties,
             BlobContentLength,
             BlobLastModified,
+            BlobContentMd5,
             NextMarker,
         }
 
@@ -225,6 +228,7 @@ impl AzureProvider {
         let mut current_name = String::new();
         let mut current_size: u64 = 0;
         let mut current_modified: Option<String> = None;
+        let mut current_md5: Option<String> = None;
         let mut in_blob = false;
         let mut in_prefix = false;
         let mut buf = Vec::new();
@@ -244,6 +248,7 @@ impl AzureProvider {
                             current_name.clear();
                             current_size = 0;
                             current_modified = None;
+                            current_md5 = None;
                         }
                         b"Name" if in_prefix => {
                             state = ParseState::BlobPrefixName;
@@ -260,6 +265,9 @@ impl AzureProvider {
                         b"Last-Modified" if in_blob => {
                             state = ParseState::BlobLastModified;
                         }
+                        b"Content-MD5" if in_blob => {
+                            state = ParseState::BlobContentMd5;
+                        }
                         b"NextMarker" => {
                             state = ParseState::NextMarker;
                         }
@@ -283,6 +291,11 @@ impl AzureProvider {
                                 current_modified = Some(text);
                             }
                         }
+                        ParseState::BlobContentMd5 => {
+                            if !text.is_empty() {
+                                current_md5 = Some(text);
+                            }
+                        }
                         ParseState::NextMarker => {
                             if !text.is_empty() {
                                 next_marker = Some(text);
@@ -303,6 +316,7 @@ impl AzureProvider {
                                         name: relative.to_string(),
                                         size: 0,
                                         last_modified: None,
+                                        content_md5: None,
                                         is_prefix: true,
                                     });
                                 }
@@ -319,6 +333,7 @@ impl AzureProvider {
                                         name: relative.to_string(),
                                         size: current_size,
                                         last_modified: current_modified.clone(),
+                                        content_md5: current_md5.take(),
                                         is_prefix: false,
                                     });
                                 }
@@ -338,7 +353,7 @@ impl AzureProvider {
                                 state = ParseState::Blob;
                             }
                         }
-                        b"Content-Length" | b"Last-Modified" => {
+                        b"Content-Length" | b"Last-Modified" | b"Content-MD5" => {
                             if in_blob {
                                 state = ParseState::BlobProperties;
                             }
@@ -634,6 +649,8 @@ impl StorageProvider for AzureProvider {
                 permissions: None, owner: None, group: None,
                 is_symlink: false, link_target: None, mime_type: None,
                 metadata: Default::default(),
+                content_hash: item.content_md5.as_deref()
+                    .and_then(|h| ContentHash::base64(HashAlgorithm::Md5, h)),
             }
         }).collect())
     }
@@ -917,6 +934,10 @@ impl StorageProvider for AzureProvider {
             .and_then(|v| v.to_str().ok())
             .map(|s| s.to_string());
 
+        let content_hash = resp.headers().get("Content-MD5")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| ContentHash::base64(HashAlgorithm::Md5, s));
+
         let name = std::path::Path::new(path)
             .file_name()
             .map(|n| n.to_string_lossy().to_string())
@@ -931,6 +952,7 @@ impl StorageProvider for AzureProvider {
             permissions: None, owner: None, group: None,
             is_symlink: false, link_target: None, mime_type,
             metadata: Default::default(),
+            content_hash,
         })
     }
 
@@ -953,6 +975,15 @@ impl StorageProvider for AzureProvider {
         Ok(format!("Azure Blob Storage: {}/{}", self.config.account_name, self.config.container))
     }
 
+    fn supports_checksum(&self) -> bool {
+        true
+    }
+
+    async fn checksum(&mut self, path: &str) -> Result<std::collections::HashMap<String, String>, ProviderError> {
+        let entry = self.stat(path).await?;
+        Ok(entry.content_hash.map(|h| h.to_checksums()).unwrap_or_default())
+    }
+
     fn supports_share_links(&self) -> bool {
         true
     }
@@ -1133,3 +1164,61 @@ impl AzureProvider {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_blob_list_content_md5() {
+        let provider = AzureProvider::new(AzureConfig {
+            account_name: "devstoreaccount1".to_string(),
+            access_key: secrecy::SecretString::from("a2V5".to_string()),
+            container: "backups".to_string(),
+            sas_token: None,
+            endpoint: None,
+            proxy: None,
+        });
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<EnumerationResults ServiceEndpoint="https://devstoreaccount1.blob.core.windows.net/" ContainerName="backups">
+  <Blobs>
+    <Blob>
+      <Name>abc.txt</Name>
+      <Properties>
+        <Last-Modified>Sun, 18 Oct 2026 10:00:00 GMT</Last-Modified>
+        <Content-Length>3</Content-Length>
+        <Content-MD5>kAFQmDzST7DWlj99KOF/cg==</Content-MD5>
+      </Properties>
+    </Blob>
+    <Blob>
+      <Name>blocks.bin</Name>
+      <Properties>
+        <Content-Length>10485760</Content-Length>
+        <Content-MD5 />
+      </Properties>
+    </Blob>
+    <BlobPrefix><Name>photos/</Name></BlobPrefix>
+  </Blobs>
+  <NextMarker>page2</NextMarker>
+</EnumerationResults>"#;
+
+        let (items, next_marker) = provider.parse_blob_list(xml);
+        assert_eq!(next_marker.as_deref(), Some("page2"));
+        assert_eq!(items.len(), 3);
+
+        assert_eq!(items[0].name, "abc.txt");
+        assert_eq!(items[0].size, 3);
+        let hash = items[0].content_md5.as_deref()
+            .and_then(|h| ContentHash::base64(HashAlgorithm::Md5, h))
+            .unwrap();
+        assert_eq!(hash.value, "900150983cd24fb0d6963f7d28e17f72");
+
+        // Blobs uploaded in blocks without a whole-file MD5
+        assert_eq!(items[1].name, "blocks.bin");
+        assert_eq!(items[1].content_md5, None);
+
+        assert!(items[2].is_prefix);
+        assert_eq!(items[2].name, "photos");
+        assert_eq!(items[2].content_md5, None);
+    }
+}
@@ -0,0 +1,1005 @@
+//! Backblaze B2 Provider
+//!
+//! Implements StorageProvider for Backblaze B2 using the native API (v2).
+//! Unlike the S3-compatible endpoint, the native API exposes file versions,
+//! hide markers (so deletes respect bucket lifecycle rules) and large files.
+//!
+//! - Files above 200 MB use the large-file API (start / upload_part / finish)
+//! - Server-side copy via `b2_copy_file`, or `b2_copy_part` above 5 GB
+//! - Delete hides the file unless `hard_delete` is set
+//!
+//! ## Limitations (documented)
+//! - B2-001: No storage quota API (B2 caps are account-level and not queryable)
+//! - B2-002: Directory markers use the B2 web UI convention (`<dir>/.bzEmpty`)
+
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use futures_util::StreamExt;
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use sha1::{Sha1, Digest};
+use std::collections::HashMap;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{info, debug, warn};
+
+use super::{
+    StorageProvider, ProviderType, ProviderError, RemoteEntry, FileVersion,
+    ContentHash, HashAlgorithm, sanitize_api_error, HttpRetryConfig, send_with_retry, read_full_async,
+};
+use super::types::B2Config;
+
+const AUTHORIZE_URL: &str = "https://api.backblazeb2.com/b2api/v2/b2_authorize_account";
+
+/// B2-003: Files above this size are uploaded as large files (200 MB)
+const LARGE_FILE_THRESHOLD: u64 = 200 * 1024 * 1024;
+
+/// B2-003: Largest object `b2_copy_file` can copy in one call (5 GB)
+const MAX_COPY_FILE_SIZE: u64 = 5 * 1000 * 1000 * 1000;
+
+/// Authorization tokens are valid for 24 hours; renew an hour early
+const TOKEN_LIFETIME: std::time::Duration = std::time::Duration::from_secs(23 * 3600);
+
+/// Maximum files per list call (B2 bills list calls per 1000 entries)
+const LIST_PAGE_SIZE: u32 = 1000;
+
+/// File the B2 web UI creates to keep an empty folder visible
+const FOLDER_MARKER: &str = ".bzEmpty";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthorizeResponse {
+    account_id: String,
+    authorization_token: String,
+    api_url: String,
+    download_url: String,
+    recommended_part_size: u64,
+    #[serde(default)]
+    allowed: Option<AllowedBucket>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AllowedBucket {
+    bucket_id: Option<String>,
+    bucket_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BucketList {
+    buckets: Vec<Bucket>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Bucket {
+    bucket_id: String,
+}
+
+/// File (version) as returned by the list and copy calls
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct B2File {
+    file_id: Option<String>,
+    file_name: String,
+    #[serde(default)]
+    content_length: u64,
+    content_sha1: Option<String>,
+    content_type: Option<String>,
+    /// "upload" | "folder" | "hide" | "start"
+    action: String,
+    #[serde(default)]
+    upload_timestamp: i64,
+    #[serde(default)]
+    file_info: HashMap<String, String>,
+}
+
+impl B2File {
+    /// Modification time: the client-supplied source mtime when present, else the upload time
+    fn modified(&self) -> Option<String> {
+        let millis = self.file_info.get("src_last_modified_millis")
+            .and_then(|m| m.parse::<i64>().ok())
+            .unwrap_or(self.upload_timestamp);
+        chrono::DateTime::from_timestamp_millis(millis).map(|t| t.to_rfc3339())
+    }
+
+    /// SHA-1 of the content, when B2 knows it (large files only via `large_file_sha1`)
+    fn sha1(&self) -> Option<String> {
+        self.content_sha1.as_deref()
+            .filter(|s| *s != "none")
+            .map(|s| s.trim_start_matches("unverified:").to_string())
+            .or_else(|| self.file_info.get("large_file_sha1").cloned())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileList {
+    files: Vec<B2File>,
+    next_file_name: Option<String>,
+    next_file_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadUrl {
+    upload_url: String,
+    authorization_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StartedFile {
+    file_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CopiedPart {
+    content_sha1: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct B2ErrorBody {
+    code: String,
+    message: String,
+}
+
+/// Map a failed B2 response to a ProviderError
+fn b2_error(status: u16, body: &str) -> ProviderError {
+    let (code, message) = match serde_json::from_str::<B2ErrorBody>(body) {
+        Ok(err) => (err.code, err.message),
+        Err(_) => (String::new(), sanitize_api_error(body)),
+    };
+    match (status, code.as_str()) {
+        (404, _) | (_, "not_found") | (_, "file_not_present") => ProviderError::NotFound(message),
+        (401, _) => ProviderError::AuthenticationFailed(message),
+        (403, _) => ProviderError::PermissionDenied(message),
+        _ => ProviderError::ServerError(format!("B2 error {} ({}): {}", status, code, message)),
+    }
+}
+
+/// Percent-encode a file name for download URLs and `X-Bz-File-Name`, keeping `/`
+fn encode_name(name: &str) -> String {
+    name.split('/')
+        .map(|part| urlencoding::encode(part).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Local file mtime in milliseconds, for the `src_last_modified_millis` file info
+fn local_mtime_millis(meta: &std::fs::Metadata) -> Option<i64> {
+    meta.modified().ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+}
+
+/// Authorized account session
+struct B2Session {
+    account_id: String,
+    token: String,
+    api_url: String,
+    download_url: String,
+    part_size: u64,
+    authorized_at: std::time::Instant,
+}
+
+/// Backblaze B2 Provider
+pub struct B2Provider {
+    config: B2Config,
+    client: reqwest::Client,
+    session: Option<B2Session>,
+    bucket_id: String,
+    current_prefix: String,
+}
+
+impl B2Provider {
+    pub fn new(config: B2Config) -> Self {
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
+            .connect_timeout(std::time::Duration::from_secs(30))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+
+        Self {
+            config,
+            client,
+            session: None,
+            bucket_id: String::new(),
+            current_prefix: String::new(),
+        }
+    }
+
+    fn session(&self) -> Result<&B2Session, ProviderError> {
+        self.session.as_ref().ok_or(ProviderError::NotConnected)
+    }
+
+    /// b2_authorize_account with the application key
+    async fn authorize(&mut self) -> Result<Option<AllowedBucket>, ProviderError> {
+        let basic = BASE64.encode(format!("{}:{}", self.config.key_id, self.config.application_key.expose_secret()));
+        let resp = self.client.get(AUTHORIZE_URL)
+            .header(AUTHORIZATION, format!("Basic {}", basic))
+            .send().await
+            .map_err(|e| ProviderError::ConnectionFailed(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(match b2_error(status, &body) {
+                ProviderError::ServerError(msg) => ProviderError::AuthenticationFailed(msg),
+                other => other,
+            });
+        }
+
+        let auth: AuthorizeResponse = resp.json().await
+            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+        self.session = Some(B2Session {
+            account_id: auth.account_id,
+            token: auth.authorization_token,
+            api_url: auth.api_url,
+            download_url: auth.download_url,
+            part_size: auth.recommended_part_size,
+            authorized_at: std::time::Instant::now(),
+        });
+        Ok(auth.allowed)
+    }
+
+    /// Renew the authorization token before it expires
+    async fn ensure_auth(&mut self) -> Result<(), ProviderError> {
+        let expired = self.session()?.authorized_at.elapsed() > TOKEN_LIFETIME;
+        if expired {
+            debug!("B2 authorization token expiring, re-authorizing");
+            self.authorize().await?;
+        }
+        Ok(())
+    }
+
+    /// Call a B2 API operation with a JSON body (retried on 429/5xx)
+    async fn api<T: DeserializeOwned>(&self, operation: &str, body: serde_json::Value) -> Result<T, ProviderError> {
+        let session = self.session()?;
+        let request = self.client.post(format!("{}/b2api/v2/{}", session.api_url, operation))
+            .header(AUTHORIZATION, &session.token)
+            .json(&body)
+            .build()
+            .map_err(|e| ProviderError::NetworkError(format!("Failed to build request: {}", e)))?;
+
+        let resp = send_with_retry(&self.client, request, &HttpRetryConfig::default())
+            .await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(b2_error(status, &body));
+        }
+
+        resp.json().await.map_err(|e| ProviderError::ParseError(e.to_string()))
+    }
+
+    fn resolve_name(&self, path: &str) -> String {
+        if path == "." || path.is_empty() {
+            self.current_prefix.clone()
+        } else if path.starts_with('/') {
+            path.trim_start_matches('/').to_string()
+        } else if self.current_prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}{}", self.current_prefix, path)
+        }
+    }
+
+    /// b2_list_file_names over all pages
+    async fn list_names(&self, prefix: &str, delimited: bool) -> Result<Vec<B2File>, ProviderError> {
+        let mut all = Vec::new();
+        let mut start: Option<String> = None;
+
+        loop {
+            let mut body = json!({
+                "bucketId": self.bucket_id,
+                "prefix": prefix,
+                "maxFileCount": LIST_PAGE_SIZE,
+            });
+            if delimited {
+                body["delimiter"] = json!("/");
+            }
+            if let Some(ref s) = start {
+                body["startFileName"] = json!(s);
+            }
+
+            let page: FileList = self.api("b2_list_file_names", body).await?;
+            all.extend(page.files);
+            match page.next_file_name {
+                Some(next) => start = Some(next),
+                None => break,
+            }
+        }
+
+        Ok(all)
+    }
+
+    /// Current version of a file (hidden files count as missing)
+    async fn latest_file(&self, name: &str) -> Result<B2File, ProviderError> {
+        let page: FileList = self.api("b2_list_file_names", json!({
+            "bucketId": self.bucket_id,
+            "startFileName": name,
+            "prefix": name,
+            "maxFileCount": 1,
+        })).await?;
+
+        page.files.into_iter()
+            .find(|f| f.file_name == name && f.action == "upload")
+            .ok_or_else(|| ProviderError::NotFound(name.to_string()))
+    }
+
+    /// Every stored version of `name` (uploads and hide markers), newest first
+    async fn file_versions(&self, name: &str) -> Result<Vec<B2File>, ProviderError> {
+        let mut all = Vec::new();
+        let mut start: Option<(String, String)> = None;
+
+        loop {
+            let mut body = json!({
+                "bucketId": self.bucket_id,
+                "prefix": name,
+                "startFileName": name,
+                "maxFileCount": LIST_PAGE_SIZE,
+            });
+            if let Some((ref file_name, ref file_id)) = start {
+                body["startFileName"] = json!(file_name);
+                body["startFileId"] = json!(file_id);
+            }
+
+            let page: FileList = self.api("b2_list_file_versions", body).await?;
+            all.extend(page.files.into_iter().filter(|f| f.file_name == name));
+            match (page.next_file_name, page.next_file_id) {
+                (Some(next_name), Some(next_id)) if next_name == name => start = Some((next_name, next_id)),
+                _ => break,
+            }
+        }
+
+        Ok(all)
+    }
+
+    /// Hide the file, or remove all its versions with `hard_delete`
+    async fn delete_name(&self, name: &str) -> Result<(), ProviderError> {
+        if !self.config.hard_delete {
+            let _: B2File = self.api("b2_hide_file", json!({
+                "bucketId": self.bucket_id,
+                "fileName": name,
+            })).await?;
+            return Ok(());
+        }
+
+        let versions = self.file_versions(name).await?;
+        if versions.is_empty() {
+            return Err(ProviderError::NotFound(name.to_string()));
+        }
+        for version in versions {
+            let Some(file_id) = version.file_id else { continue };
+            let _: serde_json::Value = self.api("b2_delete_file_version", json!({
+                "fileName": name,
+                "fileId": file_id,
+            })).await?;
+        }
+        Ok(())
+    }
+
+    /// Server-side copy of a file version to `dest`. Sources above 5 GB are
+    /// copied part by part into a large file.
+    async fn copy_version(&self, source_id: &str, source_size: u64, dest: &str) -> Result<(), ProviderError> {
+        if source_size <= MAX_COPY_FILE_SIZE {
+            let _: B2File = self.api("b2_copy_file", json!({
+                "sourceFileId": source_id,
+                "fileName": dest,
+                "metadataDirective": "COPY",
+            })).await?;
+            return Ok(());
+        }
+
+        let part_size = self.session()?.part_size.max(1);
+        let started: StartedFile = self.api("b2_start_large_file", json!({
+            "bucketId": self.bucket_id,
+            "fileName": dest,
+            "contentType": "b2/x-auto",
+        })).await?;
+
+        let result = async {
+            let mut part_sha1s = Vec::new();
+            let mut offset = 0u64;
+            while offset < source_size {
+                let end = (offset + part_size).min(source_size) - 1;
+                let part: CopiedPart = self.api("b2_copy_part", json!({
+                    "sourceFileId": source_id,
+                    "largeFileId": started.file_id,
+                    "partNumber": part_sha1s.len() + 1,
+                    "range": format!("bytes={}-{}", offset, end),
+                })).await?;
+                part_sha1s.push(part.content_sha1);
+                offset = end + 1;
+            }
+            let _: B2File = self.api("b2_finish_large_file", json!({
+                "fileId": started.file_id,
+                "partSha1Array": part_sha1s,
+            })).await?;
+            Ok::<(), ProviderError>(())
+        }.await;
+
+        if result.is_err() {
+            self.cancel_large_file(&started.file_id).await;
+        }
+        result
+    }
+
+    /// Best-effort cleanup of an unfinished large file
+    async fn cancel_large_file(&self, file_id: &str) {
+        let cancelled: Result<serde_json::Value, _> = self.api("b2_cancel_large_file", json!({ "fileId": file_id })).await;
+        if let Err(e) = cancelled {
+            warn!("Failed to cancel B2 large file {}: {}", file_id, e);
+        }
+    }
+
+    /// Stream a download URL to a local file
+    async fn download_url(&self, url: &str, local_path: &str, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        let resp = self.get_authorized(url).await?;
+        let total_size = resp.content_length().unwrap_or(0);
+
+        let mut stream = resp.bytes_stream();
+        let mut file = tokio::fs::File::create(local_path).await
+            .map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+
+        let mut bytes_received: u64 = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+            file.write_all(&chunk).await
+                .map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+            bytes_received += chunk.len() as u64;
+            if let Some(ref cb) = progress {
+                cb(bytes_received, total_size);
+            }
+        }
+        file.flush().await
+            .map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_authorized(&self, url: &str) -> Result<reqwest::Response, ProviderError> {
+        let request = self.client.get(url)
+            .header(AUTHORIZATION, &self.session()?.token)
+            .build()
+            .map_err(|e| ProviderError::NetworkError(format!("Failed to build request: {}", e)))?;
+        let resp = send_with_retry(&self.client, request, &HttpRetryConfig::default())
+            .await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(b2_error(status, &body));
+        }
+        Ok(resp)
+    }
+
+    fn file_url(&self, name: &str) -> Result<String, ProviderError> {
+        Ok(format!("{}/file/{}/{}", self.session()?.download_url, urlencoding::encode(&self.config.bucket), encode_name(name)))
+    }
+
+    /// Single-call upload (files up to `LARGE_FILE_THRESHOLD`)
+    async fn upload_single(&self, local_path: &str, name: &str, meta: &std::fs::Metadata, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        let file_len = meta.len();
+
+        // B2 requires the SHA-1 up front; hash first, then stream the file
+        let mut file = tokio::fs::File::open(local_path).await.map_err(ProviderError::IoError)?;
+        let mut hasher = Sha1::new();
+        let mut buf = vec![0u8; 1024 * 1024];
+        loop {
+            let n = file.read(&mut buf).await.map_err(ProviderError::IoError)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        let sha1 = hex::encode(hasher.finalize());
+
+        let target: UploadUrl = self.api("b2_get_upload_url", json!({ "bucketId": self.bucket_id })).await?;
+        let file = tokio::fs::File::open(local_path).await.map_err(ProviderError::IoError)?;
+        let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
+
+        let mut request = self.client.post(&target.upload_url)
+            .header(AUTHORIZATION, &target.authorization_token)
+            .header("X-Bz-File-Name", encode_name(name))
+            .header(CONTENT_TYPE, "b2/x-auto")
+            .header(CONTENT_LENGTH, file_len)
+            .header("X-Bz-Content-Sha1", &sha1);
+        if let Some(mtime) = local_mtime_millis(meta) {
+            request = request.header("X-Bz-Info-src_last_modified_millis", mtime.to_string());
+        }
+
+        // Streaming bodies are not retryable at this level — the caller can retry the entire upload
+        let resp = request.body(body).send().await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::TransferFailed(b2_error(status, &body).to_string()));
+        }
+
+        if let Some(ref cb) = progress {
+            cb(file_len, file_len);
+        }
+        Ok(())
+    }
+
+    /// B2-003: Large-file upload in parts of the account's recommended size,
+    /// each verified by SHA-1. The unfinished file is cancelled on failure.
+    async fn upload_large(&self, local_path: &str, name: &str, meta: &std::fs::Metadata, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        let file_len = meta.len();
+        let mut file_info = json!({});
+        if let Some(mtime) = local_mtime_millis(meta) {
+            file_info["src_last_modified_millis"] = json!(mtime.to_string());
+        }
+        let started: StartedFile = self.api("b2_start_large_file", json!({
+            "bucketId": self.bucket_id,
+            "fileName": name,
+            "contentType": "b2/x-auto",
+            "fileInfo": file_info,
+        })).await?;
+
+        let result = self.upload_parts(local_path, &started.file_id, file_len, progress).await;
+        if result.is_err() {
+            self.cancel_large_file(&started.file_id).await;
+        }
+        result
+    }
+
+    async fn upload_parts(&self, local_path: &str, file_id: &str, file_len: u64, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        let part_size = self.session()?.part_size as usize;
+        let target: UploadUrl = self.api("b2_get_upload_part_url", json!({ "fileId": file_id })).await?;
+        let mut file = tokio::fs::File::open(local_path).await.map_err(ProviderError::IoError)?;
+
+        let mut part_sha1s: Vec<String> = Vec::new();
+        let mut bytes_uploaded: u64 = 0;
+        loop {
+            let mut buf = vec![0u8; part_size];
+            let filled = read_full_async(&mut file, &mut buf).await?;
+            if filled == 0 {
+                break;
+            }
+            buf.truncate(filled);
+
+            let sha1 = hex::encode(Sha1::digest(&buf));
+            let request = self.client.post(&target.upload_url)
+                .header(AUTHORIZATION, &target.authorization_token)
+                .header("X-Bz-Part-Number", (part_sha1s.len() + 1).to_string())
+                .header(CONTENT_LENGTH, filled)
+                .header("X-Bz-Content-Sha1", &sha1)
+                .body(buf)
+                .build()
+                .map_err(|e| ProviderError::NetworkError(format!("Failed to build request: {}", e)))?;
+            let resp = send_with_retry(&self.client, request, &HttpRetryConfig::default())
+                .await
+                .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+            if !resp.status().is_success() {
+                let status = resp.status().as_u16();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(ProviderError::TransferFailed(
+                    format!("Part {} failed: {}", part_sha1s.len() + 1, b2_error(status, &body))
+                ));
+            }
+
+            part_sha1s.push(sha1);
+            bytes_uploaded += filled as u64;
+            if let Some(ref cb) = progress {
+                cb(bytes_uploaded, file_len);
+            }
+        }
+
+        let _: B2File = self.api("b2_finish_large_file", json!({
+            "fileId": file_id,
+            "partSha1Array": part_sha1s,
+        })).await?;
+        debug!("B2 large file upload complete: {} parts, {} bytes", part_sha1s.len(), file_len);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageProvider for B2Provider {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+
+    fn provider_type(&self) -> ProviderType { ProviderType::B2 }
+
+    fn display_name(&self) -> String {
+        format!("B2:{}", self.config.bucket)
+    }
+
+    fn is_connected(&self) -> bool { self.session.is_some() }
+
+    async fn connect(&mut self) -> Result<(), ProviderError> {
+        if self.config.bucket.is_empty() {
+            return Err(ProviderError::InvalidConfig("B2 bucket name is empty".to_string()));
+        }
+        info!("B2 connect: key='{}', bucket='{}'", self.config.key_id, self.config.bucket);
+
+        let allowed = self.authorize().await?;
+
+        // Keys restricted to one bucket report it and may not be allowed to list buckets
+        let restricted = allowed.and_then(|a| match (a.bucket_id, a.bucket_name) {
+            (Some(id), Some(name)) => Some((id, name)),
+            _ => None,
+        });
+        self.bucket_id = match restricted {
+            Some((id, name)) if name == self.config.bucket => id,
+            Some((_, name)) => {
+                self.session = None;
+                return Err(ProviderError::PermissionDenied(
+                    format!("Application key is restricted to bucket '{}'", name)
+                ));
+            }
+            None => {
+                let account_id = self.session()?.account_id.clone();
+                let list: BucketList = self.api("b2_list_buckets", json!({
+                    "accountId": account_id,
+                    "bucketName": self.config.bucket,
+                })).await?;
+                match list.buckets.into_iter().next() {
+                    Some(bucket) => bucket.bucket_id,
+                    None => {
+                        self.session = None;
+                        return Err(ProviderError::NotFound(format!("Bucket '{}'", self.config.bucket)));
+                    }
+                }
+            }
+        };
+
+        self.current_prefix = String::new();
+        info!("Connected to Backblaze B2 bucket {}", self.config.bucket);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ProviderError> {
+        self.session = None;
+        Ok(())
+    }
+
+    async fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
+        self.ensure_auth().await?;
+        let prefix = self.resolve_name(path);
+        let prefix = if prefix.is_empty() || prefix.ends_with('/') { prefix } else { format!("{}/", prefix) };
+
+        let files = self.list_names(&prefix, true).await?;
+        Ok(files.into_iter().filter_map(|file| {
+            let is_dir = file.action == "folder";
+            if !is_dir && file.action != "upload" {
+                return None;
+            }
+            let full = file.file_name.trim_end_matches('/');
+            let name = full.strip_prefix(&prefix).unwrap_or(full).to_string();
+            if name.is_empty() || name == FOLDER_MARKER {
+                return None;
+            }
+            Some(RemoteEntry {
+                path: format!("/{}", full),
+                name,
+                is_dir,
+                size: if is_dir { 0 } else { file.content_length },
+                modified: if is_dir { None } else { file.modified() },
+                permissions: None, owner: None, group: None,
+                is_symlink: false, link_target: None,
+                mime_type: file.content_type.clone().filter(|_| !is_dir),
+                metadata: Default::default(),
+                content_hash: file.sha1().filter(|_| !is_dir)
+                    .and_then(|h| ContentHash::hex(HashAlgorithm::Sha1, &h)),
+            })
+        }).collect())
+    }
+
+    async fn cd(&mut self, path: &str) -> Result<(), ProviderError> {
+        if path == ".." {
+            return self.cd_up().await;
+        }
+        if path == "/" {
+            self.current_prefix = String::new();
+            return Ok(());
+        }
+
+        let new_prefix = if path.starts_with('/') {
+            path.trim_start_matches('/').to_string()
+        } else if self.current_prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}{}/", self.current_prefix, path.trim_end_matches('/'))
+        };
+
+        self.current_prefix = if new_prefix.ends_with('/') || new_prefix.is_empty() {
+            new_prefix
+        } else {
+            format!("{}/", new_prefix)
+        };
+
+        Ok(())
+    }
+
+    async fn cd_up(&mut self) -> Result<(), ProviderError> {
+        let trimmed = self.current_prefix.trim_end_matches('/');
+        self.current_prefix = match trimmed.rfind('/') {
+            Some(pos) => format!("{}/", &trimmed[..pos]),
+            None => String::new(),
+        };
+        Ok(())
+    }
+
+    async fn pwd(&mut self) -> Result<String, ProviderError> {
+        if self.current_prefix.is_empty() {
+            Ok("/".to_string())
+        } else {
+            Ok(format!("/{}", self.current_prefix.trim_end_matches('/')))
+        }
+    }
+
+    async fn download(&mut self, remote_path: &str, local_path: &str, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        self.ensure_auth().await?;
+        let url = self.file_url(&self.resolve_name(remote_path))?;
+        self.download_url(&url, local_path, progress).await
+    }
+
+    async fn download_to_bytes(&mut self, remote_path: &str) -> Result<Vec<u8>, ProviderError> {
+        self.ensure_auth().await?;
+        let url = self.file_url(&self.resolve_name(remote_path))?;
+        let resp = self.get_authorized(&url).await?;
+        super::response_bytes_with_limit(resp, super::MAX_DOWNLOAD_TO_BYTES).await
+    }
+
+    /// - Files <= 200 MB: b2_upload_file (streaming)
+    /// - Files > 200 MB: large file in recommended-size parts
+    async fn upload(&mut self, local_path: &str, remote_path: &str, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        self.ensure_auth().await?;
+        let name = self.resolve_name(remote_path);
+        let meta = tokio::fs::metadata(local_path).await.map_err(ProviderError::IoError)?;
+
+        if meta.len() > LARGE_FILE_THRESHOLD {
+            self.upload_large(local_path, &name, &meta, progress).await
+        } else {
+            self.upload_single(local_path, &name, &meta, progress).await
+        }
+    }
+
+    /// B2-002: Create `<dir>/.bzEmpty` like the B2 web UI
+    async fn mkdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.ensure_auth().await?;
+        let name = format!("{}/{}", self.resolve_name(path).trim_end_matches('/'), FOLDER_MARKER);
+        let target: UploadUrl = self.api("b2_get_upload_url", json!({ "bucketId": self.bucket_id })).await?;
+
+        let resp = self.client.post(&target.upload_url)
+            .header(AUTHORIZATION, &target.authorization_token)
+            .header("X-Bz-File-Name", encode_name(&name))
+            .header(CONTENT_TYPE, "application/x-bz-empty")
+            .header(CONTENT_LENGTH, 0)
+            .header("X-Bz-Content-Sha1", hex::encode(Sha1::digest(b"")))
+            .send().await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(b2_error(status, &body));
+        }
+        Ok(())
+    }
+
+    async fn delete(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.ensure_auth().await?;
+        let name = self.resolve_name(path);
+        self.delete_name(&name).await
+    }
+
+    async fn rmdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.rmdir_recursive(path).await
+    }
+
+    async fn rmdir_recursive(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.ensure_auth().await?;
+        let prefix = format!("{}/", self.resolve_name(path).trim_end_matches('/'));
+        let files = self.list_names(&prefix, false).await?;
+
+        for file in files.iter().filter(|f| f.action == "upload") {
+            match self.delete_name(&file.file_name).await {
+                Ok(()) | Err(ProviderError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Rename via server-side copy, then delete (or hide) the source.
+    /// Directories are renamed by copying every file under the prefix.
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        self.ensure_auth().await?;
+        let from_name = self.resolve_name(from);
+        let to_name = self.resolve_name(to);
+
+        match self.latest_file(&from_name).await {
+            Ok(source) => {
+                let source_id = source.file_id.clone().unwrap_or_default();
+                self.copy_version(&source_id, source.content_length, &to_name).await?;
+                return self.delete_name(&from_name).await;
+            }
+            Err(ProviderError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        let prefix = format!("{}/", from_name.trim_end_matches('/'));
+        let to_prefix = format!("{}/", to_name.trim_end_matches('/'));
+        let files: Vec<B2File> = self.list_names(&prefix, false).await?
+            .into_iter()
+            .filter(|f| f.action == "upload")
+            .collect();
+        if files.is_empty() {
+            return Err(ProviderError::NotFound(from.to_string()));
+        }
+
+        // Copy everything first so a failure never leaves the directory half deleted
+        for file in &files {
+            let new_name = file.file_name.replacen(&prefix, &to_prefix, 1);
+            let source_id = file.file_id.clone().unwrap_or_default();
+            self.copy_version(&source_id, file.content_length, &new_name).await?;
+        }
+        for file in &files {
+            match self.delete_name(&file.file_name).await {
+                Ok(()) | Err(ProviderError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        info!("Renamed directory (copy+delete {} files) {} to {}", files.len(), from, to);
+        Ok(())
+    }
+
+    async fn stat(&mut self, path: &str) -> Result<RemoteEntry, ProviderError> {
+        self.ensure_auth().await?;
+        let name = self.resolve_name(path);
+        let file = self.latest_file(&name).await?;
+
+        let mut metadata = HashMap::new();
+        if let Some(ref id) = file.file_id {
+            metadata.insert("file_id".to_string(), id.clone());
+        }
+        Ok(RemoteEntry {
+            name: name.rsplit('/').next().unwrap_or(&name).to_string(),
+            path: format!("/{}", name),
+            is_dir: false,
+            size: file.content_length,
+            modified: file.modified(),
+            permissions: None, owner: None, group: None,
+            is_symlink: false, link_target: None,
+            content_hash: file.sha1().and_then(|h| ContentHash::hex(HashAlgorithm::Sha1, &h)),
+            mime_type: file.content_type,
+            metadata,
+        })
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool, ProviderError> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(ProviderError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn size(&mut self, path: &str) -> Result<u64, ProviderError> {
+        Ok(self.stat(path).await?.size)
+    }
+
+    async fn keep_alive(&mut self) -> Result<(), ProviderError> {
+        self.ensure_auth().await
+    }
+
+    async fn server_info(&mut self) -> Result<String, ProviderError> {
+        let api_url = self.session()?.api_url.clone();
+        Ok(format!("Backblaze B2: {} ({})", self.config.bucket, api_url))
+    }
+
+    fn supports_server_copy(&self) -> bool { true }
+
+    async fn server_copy(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        self.ensure_auth().await?;
+        let from_name = self.resolve_name(from);
+        let to_name = self.resolve_name(to);
+
+        let source = self.latest_file(&from_name).await?;
+        let source_id = source.file_id.clone().unwrap_or_default();
+        self.copy_version(&source_id, source.content_length, &to_name).await
+    }
+
+    fn supports_checksum(&self) -> bool { true }
+
+    async fn checksum(&mut self, path: &str) -> Result<HashMap<String, String>, ProviderError> {
+        self.ensure_auth().await?;
+        let file = self.latest_file(&self.resolve_name(path)).await?;
+        let mut checksums = HashMap::new();
+        if let Some(sha1) = file.sha1() {
+            checksums.insert("sha1".to_string(), sha1);
+        }
+        Ok(checksums)
+    }
+
+    fn transfer_optimization_hints(&self) -> super::TransferOptimizationHints {
+        super::TransferOptimizationHints {
+            supports_multipart: true,
+            multipart_threshold: LARGE_FILE_THRESHOLD,
+            multipart_part_size: self.session.as_ref().map(|s| s.part_size).unwrap_or(100 * 1024 * 1024),
+            supports_server_checksum: true,
+            preferred_checksum_algo: Some("sha1".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn supports_versions(&self) -> bool { true }
+
+    /// Uploaded versions, newest first. The version id is the B2 file ID.
+    async fn list_versions(&mut self, path: &str) -> Result<Vec<FileVersion>, ProviderError> {
+        self.ensure_auth().await?;
+        let name = self.resolve_name(path);
+        Ok(self.file_versions(&name).await?
+            .into_iter()
+            .filter(|f| f.action == "upload")
+            .filter_map(|f| Some(FileVersion {
+                modified: f.modified(),
+                size: f.content_length,
+                id: f.file_id?,
+                modified_by: None,
+            }))
+            .collect())
+    }
+
+    async fn download_version(&mut self, _path: &str, version_id: &str, local_path: &str) -> Result<(), ProviderError> {
+        self.ensure_auth().await?;
+        let url = format!("{}/b2api/v2/b2_download_file_by_id?fileId={}",
+            self.session()?.download_url, urlencoding::encode(version_id));
+        self.download_url(&url, local_path, None).await
+    }
+
+    /// Copy the old version to the same name, making it the newest version
+    async fn restore_version(&mut self, path: &str, version_id: &str) -> Result<(), ProviderError> {
+        self.ensure_auth().await?;
+        let name = self.resolve_name(path);
+        let version: B2File = self.api("b2_get_file_info", json!({ "fileId": version_id })).await?;
+        if version.file_name != name {
+            return Err(ProviderError::InvalidPath(format!("Version '{}' does not belong to '{}'", version_id, path)));
+        }
+        self.copy_version(version_id, version.content_length, &name).await?;
+        info!("Restored '{}' to version '{}'", name, version_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_listing_parse() {
+        let list: FileList = serde_json::from_str(r#"{
+            "files": [
+                {"action": "folder", "fileName": "photos/", "contentLength": 0, "uploadTimestamp": 0},
+                {"action": "upload", "fileId": "4_z1", "fileName": "big.iso", "contentLength": 6442450944,
+                 "contentSha1": "none", "contentType": "application/octet-stream",
+                 "fileInfo": {"large_file_sha1": "da39a3ee5e6b4b0d3255bfef95601890afd80709", "src_last_modified_millis": "1700000000000"},
+                 "uploadTimestamp": 1710000000000}
+            ],
+            "nextFileName": null
+        }"#).unwrap();
+
+        assert_eq!(list.files.len(), 2);
+        let big = &list.files[1];
+        assert_eq!(big.sha1().as_deref(), Some("da39a3ee5e6b4b0d3255bfef95601890afd80709"));
+        assert!(big.modified().unwrap().starts_with("2023-11-14T22:13:20"));
+        assert!(list.next_file_name.is_none());
+    }
+
+    #[test]
+    fn test_error_mapping() {
+        let body = r#"{"status": 400, "code": "file_not_present", "message": "File not present: a.txt"}"#;
+        assert!(matches!(b2_error(400, body), ProviderError::NotFound(_)));
+        assert!(matches!(b2_error(401, r#"{"code": "bad_auth_token", "message": "x"}"#), ProviderError::AuthenticationFailed(_)));
+        assert!(matches!(b2_error(500, "<html>"), ProviderError::ServerError(_)));
+    }
+}
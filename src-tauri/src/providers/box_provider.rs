@@ -11,7 +11,7 @@ use tracing::info;
 
 use super::{
     StorageProvider, ProviderType, ProviderError, RemoteEntry, StorageInfo, FileVersion,
-    sanitize_api_error,
+    ContentHash, HashAlgorithm, sanitize_api_error,
     oauth2::{OAuth2Manager, OAuthConfig},
 };
 use super::types::BoxConfig;
@@ -31,6 +31,8 @@ struct BoxItem {
     size: Option<u64>,
     modified_at: Option<String>,
     created_at: Option<String>,
+    /// SHA-1 of the file contents (files only)
+    sha1: Option<String>,
 }
 
 /// Box folder items response
@@ -114,13 +116,14 @@ pub struct BoxProvider {
 
 impl BoxProvider {
     pub fn new(config: BoxConfig) -> Self {
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
         Self {
             config,
             oauth_manager: OAuth2Manager::new(),
-            client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
-                .build()
-                .unwrap_or_default(),
+            client,
             connected: false,
             current_path: "/".to_string(),
             current_folder_id: "0".to_string(),
@@ -133,9 +136,14 @@ impl BoxProvider {
         }
     }
 
+    fn oauth_config(&self) -> OAuthConfig {
+        OAuthConfig::box_cloud(&self.config.client_id, &self.config.client_secret)
+            .with_account(self.config.account.as_deref())
+    }
+
     /// Get access token from OAuth manager (returns SecretString for memory zeroization)
     async fn get_token(&self) -> Result<secrecy::SecretString, ProviderError> {
-        let config = OAuthConfig::box_cloud(&self.config.client_id, &self.config.client_secret);
+        let config = self.oauth_config();
         self.oauth_manager.get_valid_token(&config).await
             .map_err(|e| ProviderError::AuthenticationFailed(format!("Box token error: {}", e)))
     }
@@ -376,6 +384,7 @@ impl StorageProvider for BoxProvider {
         self.connected = true;
         self.current_path = "/".to_string();
         self.current_folder_id = "0".to_string();
+        self.config.account = self.oauth_manager.bind_account(&self.oauth_config(), self.account_email.as_deref());
         info!("Connected to Box");
         Ok(())
     }
@@ -408,7 +417,7 @@ impl StorageProvider for BoxProvider {
         loop {
             let token = self.get_token().await?;
             let url = format!(
-                "{}/folders/{}/items?fields=name,type,id,size,modified_at&limit={}&offset={}",
+                "{}/folders/{}/items?fields=name,type,id,size,modified_at,sha1&limit={}&offset={}",
                 API_BASE, folder_id, PAGE_LIMIT, offset
             );
 
@@ -459,8 +468,10 @@ impl StorageProvider for BoxProvider {
                 metadata: {
                     let mut m = HashMap::new();
                     m.insert("id".to_string(), item.id);
+                    m.insert("parentId".to_string(), folder_id.clone());
                     m
                 },
+                content_hash: item.sha1.as_deref().and_then(|h| ContentHash::hex(HashAlgorithm::Sha1, h)),
             }
         }).collect::<Vec<_>>();
 
@@ -776,7 +787,7 @@ impl StorageProvider for BoxProvider {
         // Try file first
         if let Ok(file_id) = self.resolve_file_id(path).await {
             let token = self.get_token().await?;
-            let resp = self.client.get(format!("{}/files/{}?fields=name,type,size,modified_at", API_BASE, file_id))
+            let resp = self.client.get(format!("{}/files/{}?fields=name,type,size,modified_at,sha1", API_BASE, file_id))
                 .header(AUTHORIZATION, Self::bearer_header(&token)?)
                 .send().await
                 .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
@@ -793,6 +804,7 @@ impl StorageProvider for BoxProvider {
                 permissions: None, owner: None, group: None,
                 is_symlink: false, link_target: None, mime_type: None,
                 metadata: Default::default(),
+                content_hash: item.sha1.as_deref().and_then(|h| ContentHash::hex(HashAlgorithm::Sha1, h)),
             });
         }
 
@@ -816,6 +828,7 @@ impl StorageProvider for BoxProvider {
             permissions: None, owner: None, group: None,
             is_symlink: false, link_target: None, mime_type: None,
             metadata: Default::default(),
+            content_hash: None,
         })
     }
 
@@ -895,6 +908,13 @@ impl StorageProvider for BoxProvider {
         Ok(())
     }
 
+    fn supports_checksum(&self) -> bool { true }
+
+    async fn checksum(&mut self, path: &str) -> Result<HashMap<String, String>, ProviderError> {
+        let entry = self.stat(path).await?;
+        Ok(entry.content_hash.map(|h| h.to_checksums()).unwrap_or_default())
+    }
+
     fn supports_server_copy(&self) -> bool { true }
 
     async fn server_copy(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
@@ -1069,7 +1089,157 @@ impl StorageProvider for BoxProvider {
                     m.insert("id".to_string(), item.id);
                     m
                 },
+                content_hash: None,
             }
         }).collect())
     }
+
+    fn supports_change_tracking(&self) -> bool { true }
+
+    async fn get_change_token(&mut self) -> Result<String, ProviderError> {
+        let token = self.get_token().await?;
+        let resp = self.client.get(format!("{}/events?stream_position=now&stream_type=changes", API_BASE))
+            .header(AUTHORIZATION, Self::bearer_header(&token)?)
+            .send().await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::ServerError(format!("Box API error {}: {}", status, sanitize_api_error(&body))));
+        }
+
+        let events: BoxEventCollection = resp.json().await
+            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+        Ok(stream_position(&events.next_stream_position))
+    }
+
+    async fn list_changes(&mut self, page_token: &str) -> Result<(Vec<super::ChangeEntry>, String), ProviderError> {
+        let mut changes = Vec::new();
+        let mut position = page_token.to_string();
+
+        loop {
+            let token = self.get_token().await?;
+            let url = format!(
+                "{}/events?stream_position={}&stream_type=changes&limit=500",
+                API_BASE, urlencoding::encode(&position)
+            );
+            let resp = self.client.get(&url)
+                .header(AUTHORIZATION, Self::bearer_header(&token)?)
+                .send().await
+                .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(ProviderError::ServerError(format!("Box API error {}: {}", status, sanitize_api_error(&body))));
+            }
+
+            let events: BoxEventCollection = resp.json().await
+                .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+
+            for event in events.entries {
+                let Some(source) = event.source else { continue };
+                if source.item_type != "file" && source.item_type != "folder" {
+                    continue;
+                }
+                changes.push(event_to_change(&event.event_type, source));
+            }
+
+            position = stream_position(&events.next_stream_position);
+            if events.chunk_size == 0 {
+                return Ok((changes, position));
+            }
+        }
+    }
+}
+
+/// Box events page (`/events`)
+#[derive(Debug, Deserialize)]
+struct BoxEventCollection {
+    #[serde(default)]
+    chunk_size: u64,
+    /// Documented as a string but returned as a number on some accounts
+    next_stream_position: serde_json::Value,
+    #[serde(default)]
+    entries: Vec<BoxEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BoxEvent {
+    event_type: String,
+    source: Option<BoxEventSource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BoxEventSource {
+    #[serde(rename = "type", default)]
+    item_type: String,
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    name: String,
+    size: Option<u64>,
+    modified_at: Option<String>,
+    parent: Option<BoxEventParent>,
+    path_collection: Option<BoxPathCollection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BoxEventParent {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BoxPathCollection {
+    entries: Vec<BoxPathEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BoxPathEntry {
+    id: String,
+    #[serde(default)]
+    name: String,
+}
+
+fn stream_position(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Convert a Box event into a change entry. Trashed items are reported by ID only,
+/// since their path collection points into the trash.
+fn event_to_change(event_type: &str, source: BoxEventSource) -> super::ChangeEntry {
+    let removed = matches!(event_type, "ITEM_TRASH" | "ITEM_DELETE" | "ITEM_REMOVE");
+    let in_root = source.path_collection.as_ref()
+        .map(|pc| pc.entries.first().map(|e| e.id.as_str()) == Some("0"))
+        .unwrap_or(false);
+    let path = match &source.path_collection {
+        Some(pc) if in_root && !removed => {
+            let mut path = String::new();
+            for entry in pc.entries.iter().skip(1) {
+                path.push('/');
+                path.push_str(&entry.name);
+            }
+            path.push('/');
+            path.push_str(&source.name);
+            Some(path)
+        }
+        _ => None,
+    };
+    let is_dir = source.item_type == "folder";
+    super::ChangeEntry {
+        change_type: if removed { "deleted" } else { "modified" }.to_string(),
+        mime_type: None,
+        timestamp: source.modified_at,
+        removed,
+        path,
+        parent_id: source.parent.map(|p| p.id),
+        is_dir,
+        size: if is_dir || removed { None } else { source.size },
+        file_id: source.id,
+        name: source.name,
+    }
 }
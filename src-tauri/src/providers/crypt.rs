@@ -0,0 +1,755 @@
+//! Client-side Encryption Overlay (rclone-crypt style)
+//!
+//! Wraps any StorageProvider and encrypts transparently under `crypt_root`:
+//! - Names: every path segment with AES-SIV (deterministic, so paths map
+//!   directly without lookups), lowercase Base32hex-encoded so names that
+//!   differ only in case stay distinct on case-insensitive backends
+//! - Contents: per-file key (HKDF from a random 32-byte file salt) and
+//!   64 KiB AES-256-GCM-SIV chunks, reusing the AeroVault v2 primitives
+//!
+//! File format:
+//! ```text
+//! "AEROCRYPT1" | file_salt (32) | chunk 0 | chunk 1 | ... | chunk N (final)
+//! chunk = nonce (12) | ciphertext | tag (16), AAD = chunk index (u32 LE)
+//! ```
+//! The last chunk's index carries `FINAL_CHUNK_FLAG` so truncation at a chunk
+//! boundary is detected. Chunks are fixed-size, so ranged reads map directly
+//! onto ranged reads of the inner file.
+//!
+//! Keys: Argon2id(password, SHA-256(domain || crypt_salt)) → HKDF-SHA256 into
+//! separate filename and content keys. Entries that do not decrypt (files not
+//! written through the overlay) are hidden from listings.
+
+use async_trait::async_trait;
+use hkdf::Hkdf;
+use rand::{rngs::OsRng, RngCore};
+use secrecy::{zeroize::Zeroize, ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use tracing::info;
+
+use super::{read_full, CryptConfig, ProviderError, ProviderType, RemoteEntry, StorageProvider};
+use crate::aerovault_v2::{
+    decrypt_chunk, derive_key, encrypt_chunk, siv_decrypt_name, siv_encrypt_name,
+    DEFAULT_CHUNK_SIZE, NONCE_SIZE, TAG_SIZE,
+};
+
+/// Magic bytes identifying an encrypted file (includes format version)
+const MAGIC: &[u8; 10] = b"AEROCRYPT1";
+/// Random per-file salt used to derive the file key
+const FILE_SALT_SIZE: usize = 32;
+/// Header: magic + file salt
+const HEADER_SIZE: usize = MAGIC.len() + FILE_SALT_SIZE;
+/// Per-chunk overhead: nonce + GCM-SIV tag
+const CHUNK_OVERHEAD: usize = NONCE_SIZE + TAG_SIZE;
+/// Encrypted size of a full chunk
+const ENCRYPTED_CHUNK_SIZE: usize = DEFAULT_CHUNK_SIZE + CHUNK_OVERHEAD;
+/// Set on the AAD index of the last chunk of a file
+const FINAL_CHUNK_FLAG: u32 = 0x8000_0000;
+
+/// Filename and content keys derived from the crypt password
+struct CryptKeys {
+    name_key: [u8; 32],
+    content_key: [u8; 32],
+}
+
+impl Drop for CryptKeys {
+    fn drop(&mut self) {
+        self.name_key.zeroize();
+        self.content_key.zeroize();
+    }
+}
+
+impl CryptKeys {
+    fn derive(password: &SecretString, salt: &SecretString) -> Result<Self, String> {
+        let mut hasher = Sha256::new();
+        hasher.update(b"AeroFTP crypt v1");
+        hasher.update(salt.expose_secret().as_bytes());
+        let kdf_salt = hasher.finalize();
+
+        let root = derive_key(password, &kdf_salt)?;
+        let hk = Hkdf::<Sha256>::new(None, root.expose_secret());
+
+        let mut keys = CryptKeys { name_key: [0u8; 32], content_key: [0u8; 32] };
+        // SAFETY: 32 bytes is always a valid HKDF-SHA256 output length
+        hk.expand(b"AeroFTP crypt v1 filename key", &mut keys.name_key)
+            .expect("32 bytes is valid HKDF-SHA256 output length");
+        hk.expand(b"AeroFTP crypt v1 content key", &mut keys.content_key)
+            .expect("32 bytes is valid HKDF-SHA256 output length");
+        Ok(keys)
+    }
+
+    fn file_key(&self, file_salt: &[u8]) -> [u8; 32] {
+        let hk = Hkdf::<Sha256>::new(Some(file_salt), &self.content_key);
+        let mut key = [0u8; 32];
+        hk.expand(b"AeroFTP crypt v1 file key", &mut key)
+            .expect("32 bytes is valid HKDF-SHA256 output length");
+        key
+    }
+
+    /// Encrypted form of one path segment
+    fn encrypt_name(&self, name: &str) -> Result<String, String> {
+        let ciphertext = siv_encrypt_name(&self.name_key, name)?;
+        Ok(data_encoding::BASE32HEX_NOPAD.encode(&ciphertext).to_ascii_lowercase())
+    }
+
+    /// Inverse of `encrypt_name`; accepts names whose case the backend changed
+    fn decrypt_name(&self, encrypted: &str) -> Result<String, String> {
+        let ciphertext = data_encoding::BASE32HEX_NOPAD
+            .decode(encrypted.to_ascii_uppercase().as_bytes())
+            .map_err(|_| "Invalid name encoding".to_string())?;
+        siv_decrypt_name(&self.name_key, &ciphertext)
+    }
+
+    /// Parse the file header and derive the file key
+    fn open_header(&self, header: &[u8]) -> Result<[u8; 32], String> {
+        if header.len() < HEADER_SIZE || &header[..MAGIC.len()] != MAGIC {
+            return Err("Not an encrypted file (bad header)".to_string());
+        }
+        Ok(self.file_key(&header[MAGIC.len()..HEADER_SIZE]))
+    }
+}
+
+/// AAD index for chunk `index`, flagged when it is the last one
+fn chunk_aad_index(index: u64, last: bool) -> Result<u32, String> {
+    if index >= FINAL_CHUNK_FLAG as u64 {
+        return Err("File too large for the crypt format".to_string());
+    }
+    Ok(if last { index as u32 | FINAL_CHUNK_FLAG } else { index as u32 })
+}
+
+/// Encrypted size of a file of `size` cleartext bytes
+fn encrypted_size(size: u64) -> u64 {
+    let chunks = size.div_ceil(DEFAULT_CHUNK_SIZE as u64).max(1);
+    HEADER_SIZE as u64 + chunks * CHUNK_OVERHEAD as u64 + size
+}
+
+/// Cleartext size of an encrypted file of `size` bytes
+fn decrypted_size(size: u64) -> u64 {
+    let body = size.saturating_sub(HEADER_SIZE as u64);
+    let full_chunks = body / ENCRYPTED_CHUNK_SIZE as u64;
+    let remainder = body % ENCRYPTED_CHUNK_SIZE as u64;
+    full_chunks * DEFAULT_CHUNK_SIZE as u64 + remainder.saturating_sub(CHUNK_OVERHEAD as u64)
+}
+
+fn encrypt_stream<R: Read, W: Write>(keys: &CryptKeys, reader: &mut R, writer: &mut W) -> Result<(), String> {
+    let mut file_salt = [0u8; FILE_SALT_SIZE];
+    OsRng.fill_bytes(&mut file_salt);
+    writer.write_all(MAGIC).and_then(|_| writer.write_all(&file_salt))
+        .map_err(|e| format!("Write header: {}", e))?;
+    let mut file_key = keys.file_key(&file_salt);
+
+    // Read one chunk ahead so the last chunk can be flagged
+    let mut current = vec![0u8; DEFAULT_CHUNK_SIZE];
+    let mut next = vec![0u8; DEFAULT_CHUNK_SIZE];
+    let mut len = read_full(reader, &mut current).map_err(|e| format!("Read failed: {}", e))?;
+    let mut index: u64 = 0;
+    let result = loop {
+        let next_len = if len == DEFAULT_CHUNK_SIZE {
+            match read_full(reader, &mut next) {
+                Ok(n) => n,
+                Err(e) => break Err(format!("Read failed: {}", e)),
+            }
+        } else {
+            0
+        };
+        let last = next_len == 0;
+
+        let encrypted = chunk_aad_index(index, last)
+            .and_then(|aad| encrypt_chunk(&file_key, &current[..len], aad));
+        match encrypted {
+            Ok(chunk) => {
+                if let Err(e) = writer.write_all(&chunk) {
+                    break Err(format!("Write chunk: {}", e));
+                }
+            }
+            Err(e) => break Err(e),
+        }
+
+        if last {
+            break Ok(());
+        }
+        std::mem::swap(&mut current, &mut next);
+        len = next_len;
+        index += 1;
+    };
+
+    file_key.zeroize();
+    current.zeroize();
+    next.zeroize();
+    result
+}
+
+fn decrypt_stream<R: Read, W: Write>(keys: &CryptKeys, reader: &mut R, writer: &mut W) -> Result<(), String> {
+    let mut header = [0u8; HEADER_SIZE];
+    let n = read_full(reader, &mut header).map_err(|e| format!("Read failed: {}", e))?;
+    let mut file_key = keys.open_header(&header[..n])?;
+
+    let mut current = vec![0u8; ENCRYPTED_CHUNK_SIZE];
+    let mut next = vec![0u8; ENCRYPTED_CHUNK_SIZE];
+    let mut len = read_full(reader, &mut current).map_err(|e| format!("Read failed: {}", e))?;
+    let mut index: u64 = 0;
+    let result = loop {
+        if len == 0 {
+            break Err("Encrypted file is truncated".to_string());
+        }
+        let next_len = if len == ENCRYPTED_CHUNK_SIZE {
+            match read_full(reader, &mut next) {
+                Ok(n) => n,
+                Err(e) => break Err(format!("Read failed: {}", e)),
+            }
+        } else {
+            0
+        };
+        let last = next_len == 0;
+
+        let decrypted = chunk_aad_index(index, last)
+            .and_then(|aad| decrypt_chunk(&file_key, &current[..len], aad));
+        match decrypted {
+            Ok(mut plain) => {
+                let written = writer.write_all(&plain);
+                plain.zeroize();
+                if let Err(e) = written {
+                    break Err(format!("Write failed: {}", e));
+                }
+            }
+            Err(e) => break Err(format!("Chunk {}: {}", index, e)),
+        }
+
+        if last {
+            break Ok(());
+        }
+        std::mem::swap(&mut current, &mut next);
+        len = next_len;
+        index += 1;
+    };
+
+    file_key.zeroize();
+    result
+}
+
+fn crypt_error(e: String) -> ProviderError {
+    ProviderError::Other(format!("Crypt: {}", e))
+}
+
+fn temp_path() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("aeroftp_crypt_{}", uuid::Uuid::new_v4()))
+}
+
+/// Encrypting overlay over another storage provider
+pub struct CryptProvider {
+    inner: Box<dyn StorageProvider>,
+    config: CryptConfig,
+    /// Derived on connect (Argon2id is deliberately slow)
+    keys: Option<CryptKeys>,
+    /// Encrypted root on the inner provider (no trailing slash, "" for "/")
+    root: String,
+    /// Current cleartext directory
+    current_dir: String,
+}
+
+impl CryptProvider {
+    pub fn new(inner: Box<dyn StorageProvider>, config: CryptConfig) -> Self {
+        let root = config.root.trim_end_matches('/').to_string();
+        Self {
+            inner,
+            config,
+            keys: None,
+            root,
+            current_dir: "/".to_string(),
+        }
+    }
+
+    /// Wrap `inner` when the profile enables client-side encryption
+    pub fn wrap_if_configured(inner: Box<dyn StorageProvider>, extra: &HashMap<String, String>) -> Box<dyn StorageProvider> {
+        match CryptConfig::from_extra(extra) {
+            Some(crypt) => Box::new(Self::new(inner, crypt)),
+            None => inner,
+        }
+    }
+
+    fn keys(&self) -> Result<&CryptKeys, ProviderError> {
+        self.keys.as_ref().ok_or(ProviderError::NotConnected)
+    }
+
+    /// Resolve `path` against the current directory into normalized segments
+    fn segments(&self, path: &str) -> Vec<String> {
+        let joined = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("{}/{}", self.current_dir, path)
+        };
+
+        let mut parts: Vec<String> = Vec::new();
+        for part in joined.split('/') {
+            match part {
+                "" | "." => {}
+                ".." => { parts.pop(); }
+                p => parts.push(p.to_string()),
+            }
+        }
+        parts
+    }
+
+    fn clear_path(segments: &[String]) -> String {
+        format!("/{}", segments.join("/"))
+    }
+
+    /// Encrypted inner path for a cleartext path
+    fn inner_path(&self, path: &str) -> Result<String, ProviderError> {
+        let keys = self.keys()?;
+        let mut out = self.root.clone();
+        for segment in self.segments(path) {
+            out.push('/');
+            out.push_str(&keys.encrypt_name(&segment).map_err(crypt_error)?);
+        }
+        if out.is_empty() {
+            out.push('/');
+        }
+        Ok(out)
+    }
+
+    /// Map an inner entry back to cleartext; `None` if the name doesn't decrypt
+    fn clear_entry(&self, parent: &str, mut entry: RemoteEntry) -> Option<RemoteEntry> {
+        let keys = self.keys.as_ref()?;
+        let name = keys.decrypt_name(&entry.name).ok()?;
+        entry.path = if parent == "/" { format!("/{}", name) } else { format!("{}/{}", parent, name) };
+        entry.name = name;
+        if !entry.is_dir {
+            entry.size = decrypted_size(entry.size);
+        }
+        // Provider hashes describe ciphertext and would mislead comparisons
+        entry.mime_type = None;
+        entry.metadata.clear();
+        entry.content_hash = None;
+        Some(entry)
+    }
+}
+
+#[async_trait]
+impl StorageProvider for CryptProvider {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn provider_type(&self) -> ProviderType {
+        self.inner.provider_type()
+    }
+
+    fn display_name(&self) -> String {
+        format!("{} (encrypted)", self.inner.display_name())
+    }
+
+    fn account_email(&self) -> Option<String> {
+        self.inner.account_email()
+    }
+
+    async fn connect(&mut self) -> Result<(), ProviderError> {
+        if self.keys.is_none() {
+            let password = SecretString::from(self.config.password.expose_secret().to_string());
+            let salt = SecretString::from(self.config.salt.expose_secret().to_string());
+            let keys = tokio::task::spawn_blocking(move || CryptKeys::derive(&password, &salt))
+                .await
+                .map_err(|e| ProviderError::Other(format!("Key derivation task failed: {}", e)))?
+                .map_err(crypt_error)?;
+            self.keys = Some(keys);
+        }
+        if !self.inner.is_connected() {
+            self.inner.connect().await?;
+        }
+        info!("Crypt overlay active on {} at {}", self.inner.display_name(), self.config.root);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ProviderError> {
+        self.inner.disconnect().await
+    }
+
+    fn is_connected(&self) -> bool {
+        self.keys.is_some() && self.inner.is_connected()
+    }
+
+    async fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
+        let clear = Self::clear_path(&self.segments(path));
+        let inner_path = self.inner_path(&clear)?;
+        let entries = self.inner.list(&inner_path).await?;
+        Ok(entries.into_iter().filter_map(|e| self.clear_entry(&clear, e)).collect())
+    }
+
+    async fn pwd(&mut self) -> Result<String, ProviderError> {
+        Ok(self.current_dir.clone())
+    }
+
+    async fn cd(&mut self, path: &str) -> Result<(), ProviderError> {
+        let clear = Self::clear_path(&self.segments(path));
+        let inner_path = self.inner_path(&clear)?;
+        self.inner.cd(&inner_path).await?;
+        self.current_dir = clear;
+        Ok(())
+    }
+
+    async fn cd_up(&mut self) -> Result<(), ProviderError> {
+        self.cd("..").await
+    }
+
+    async fn download(
+        &mut self,
+        remote_path: &str,
+        local_path: &str,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        let inner_path = self.inner_path(remote_path)?;
+        let tmp = temp_path();
+        if let Err(e) = self.inner.download(&inner_path, &tmp.to_string_lossy(), on_progress).await {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e);
+        }
+
+        let keys = self.keys()?;
+        let result = std::fs::File::open(&tmp)
+            .and_then(|input| std::fs::File::create(local_path).map(|output| (input, output)))
+            .map_err(ProviderError::from)
+            .and_then(|(input, output)| {
+                let mut output = std::io::BufWriter::new(output);
+                decrypt_stream(keys, &mut std::io::BufReader::new(input), &mut output).map_err(crypt_error)?;
+                output.flush().map_err(ProviderError::from)
+            });
+        let _ = std::fs::remove_file(&tmp);
+        if result.is_err() {
+            let _ = std::fs::remove_file(local_path);
+        }
+        result
+    }
+
+    async fn download_to_bytes(&mut self, remote_path: &str) -> Result<Vec<u8>, ProviderError> {
+        let inner_path = self.inner_path(remote_path)?;
+        let encrypted = self.inner.download_to_bytes(&inner_path).await?;
+        let mut cleartext = Vec::with_capacity(decrypted_size(encrypted.len() as u64) as usize);
+        decrypt_stream(self.keys()?, &mut encrypted.as_slice(), &mut cleartext).map_err(crypt_error)?;
+        Ok(cleartext)
+    }
+
+    async fn upload(
+        &mut self,
+        local_path: &str,
+        remote_path: &str,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        let inner_path = self.inner_path(remote_path)?;
+        let keys = self.keys()?;
+
+        let tmp = temp_path();
+        let encrypted = std::fs::File::open(local_path)
+            .and_then(|input| std::fs::File::create(&tmp).map(|output| (input, output)))
+            .map_err(ProviderError::from)
+            .and_then(|(input, output)| {
+                let mut output = std::io::BufWriter::new(output);
+                encrypt_stream(keys, &mut std::io::BufReader::new(input), &mut output).map_err(crypt_error)?;
+                output.flush().map_err(ProviderError::from)
+            });
+        if let Err(e) = encrypted {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e);
+        }
+
+        let result = self.inner.upload(&tmp.to_string_lossy(), &inner_path, on_progress).await;
+        let _ = std::fs::remove_file(&tmp);
+        result
+    }
+
+    async fn mkdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        let inner_path = self.inner_path(path)?;
+        self.inner.mkdir(&inner_path).await
+    }
+
+    async fn delete(&mut self, path: &str) -> Result<(), ProviderError> {
+        let inner_path = self.inner_path(path)?;
+        self.inner.delete(&inner_path).await
+    }
+
+    async fn rmdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        let inner_path = self.inner_path(path)?;
+        self.inner.rmdir(&inner_path).await
+    }
+
+    async fn rmdir_recursive(&mut self, path: &str) -> Result<(), ProviderError> {
+        let inner_path = self.inner_path(path)?;
+        self.inner.rmdir_recursive(&inner_path).await
+    }
+
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        let inner_from = self.inner_path(from)?;
+        let inner_to = self.inner_path(to)?;
+        self.inner.rename(&inner_from, &inner_to).await
+    }
+
+    async fn stat(&mut self, path: &str) -> Result<RemoteEntry, ProviderError> {
+        let segments = self.segments(path);
+        let clear = Self::clear_path(&segments);
+        let inner_path = self.inner_path(&clear)?;
+        let entry = self.inner.stat(&inner_path).await?;
+        if segments.is_empty() {
+            return Ok(RemoteEntry::directory("/".to_string(), "/".to_string()));
+        }
+
+        let parent = Self::clear_path(&segments[..segments.len() - 1]);
+        let mut entry = entry;
+        entry.name = inner_path.rsplit('/').next().unwrap_or_default().to_string();
+        self.clear_entry(&parent, entry)
+            .ok_or_else(|| crypt_error(format!("Cannot decrypt name of {}", clear)))
+    }
+
+    async fn size(&mut self, path: &str) -> Result<u64, ProviderError> {
+        let inner_path = self.inner_path(path)?;
+        Ok(decrypted_size(self.inner.size(&inner_path).await?))
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool, ProviderError> {
+        let inner_path = self.inner_path(path)?;
+        self.inner.exists(&inner_path).await
+    }
+
+    async fn keep_alive(&mut self) -> Result<(), ProviderError> {
+        self.inner.keep_alive().await
+    }
+
+    async fn server_info(&mut self) -> Result<String, ProviderError> {
+        let inner_info = self.inner.server_info().await?;
+        Ok(format!("{} (client-side encryption at {})", inner_info, self.config.root))
+    }
+
+    fn supports_server_copy(&self) -> bool {
+        self.inner.supports_server_copy()
+    }
+
+    /// File keys come from the per-file salt, so ciphertext can be copied as-is
+    async fn server_copy(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        let inner_from = self.inner_path(from)?;
+        let inner_to = self.inner_path(to)?;
+        self.inner.server_copy(&inner_from, &inner_to).await
+    }
+
+    async fn storage_info(&mut self) -> Result<super::StorageInfo, ProviderError> {
+        self.inner.storage_info().await
+    }
+
+    /// Fixed-size chunks map a cleartext range onto one inner range. The
+    /// ciphertext size tells which chunk is the last one, so a file cut at a
+    /// chunk boundary fails here just as it does in `download`.
+    async fn read_range(&mut self, path: &str, offset: u64, len: u64) -> Result<Vec<u8>, ProviderError> {
+        let inner_path = self.inner_path(path)?;
+        let inner_size = self.inner.size(&inner_path).await?;
+        if inner_size < (HEADER_SIZE + CHUNK_OVERHEAD) as u64 {
+            return Err(crypt_error("Encrypted file is truncated".to_string()));
+        }
+        let clear_size = decrypted_size(inner_size);
+        if len == 0 || offset >= clear_size {
+            return Ok(Vec::new());
+        }
+        let len = len.min(clear_size - offset);
+        let final_chunk = (inner_size - HEADER_SIZE as u64).div_ceil(ENCRYPTED_CHUNK_SIZE as u64) - 1;
+
+        let header = self.inner.read_range(&inner_path, 0, HEADER_SIZE as u64).await?;
+        let mut file_key = self.keys()?.open_header(&header).map_err(crypt_error)?;
+
+        let chunk = DEFAULT_CHUNK_SIZE as u64;
+        let first = offset / chunk;
+        let last = (offset + len - 1) / chunk;
+        let encrypted = self.inner.read_range(
+            &inner_path,
+            HEADER_SIZE as u64 + first * ENCRYPTED_CHUNK_SIZE as u64,
+            (last - first + 1) * ENCRYPTED_CHUNK_SIZE as u64,
+        ).await;
+
+        let result = encrypted.and_then(|encrypted| {
+            let mut cleartext = Vec::new();
+            for (i, block) in encrypted.chunks(ENCRYPTED_CHUNK_SIZE).enumerate() {
+                let index = first + i as u64;
+                let plain = chunk_aad_index(index, index == final_chunk)
+                    .and_then(|aad| decrypt_chunk(&file_key, block, aad))
+                    .map_err(|e| crypt_error(format!("Chunk {}: {}", index, e)))?;
+                cleartext.extend_from_slice(&plain);
+            }
+            let start = (offset - first * chunk) as usize;
+            let end = start + len as usize;
+            if end > cleartext.len() {
+                return Err(crypt_error("Encrypted file is truncated".to_string()));
+            }
+            Ok(cleartext[start..end].to_vec())
+        });
+        file_key.zeroize();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::memory::MemoryProvider;
+
+    fn test_keys() -> CryptKeys {
+        CryptKeys { name_key: [7u8; 32], content_key: [9u8; 32] }
+    }
+
+    fn roundtrip(size: usize) {
+        let keys = test_keys();
+        let data: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+        let mut encrypted = Vec::new();
+        encrypt_stream(&keys, &mut data.as_slice(), &mut encrypted).unwrap();
+        assert_eq!(encrypted.len() as u64, encrypted_size(size as u64));
+        assert_eq!(decrypted_size(encrypted.len() as u64), size as u64);
+
+        let mut decrypted = Vec::new();
+        decrypt_stream(&keys, &mut encrypted.as_slice(), &mut decrypted).unwrap();
+        assert_eq!(decrypted, data);
+    }
+
+    #[test]
+    fn test_content_roundtrip_sizes() {
+        for size in [0, 1, DEFAULT_CHUNK_SIZE - 1, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_SIZE + 1, 3 * DEFAULT_CHUNK_SIZE] {
+            roundtrip(size);
+        }
+    }
+
+    #[test]
+    fn test_truncation_at_chunk_boundary_detected() {
+        let keys = test_keys();
+        let data = vec![1u8; 2 * DEFAULT_CHUNK_SIZE];
+        let mut encrypted = Vec::new();
+        encrypt_stream(&keys, &mut data.as_slice(), &mut encrypted).unwrap();
+
+        encrypted.truncate(HEADER_SIZE + ENCRYPTED_CHUNK_SIZE);
+        let mut out = Vec::new();
+        assert!(decrypt_stream(&keys, &mut encrypted.as_slice(), &mut out).is_err());
+    }
+
+    #[test]
+    fn test_wrong_key_rejected() {
+        let mut encrypted = Vec::new();
+        encrypt_stream(&test_keys(), &mut &b"secret"[..], &mut encrypted).unwrap();
+
+        let other = CryptKeys { name_key: [7u8; 32], content_key: [1u8; 32] };
+        let mut out = Vec::new();
+        assert!(decrypt_stream(&other, &mut encrypted.as_slice(), &mut out).is_err());
+    }
+
+    #[test]
+    fn test_bad_header_rejected() {
+        let mut out = Vec::new();
+        assert!(decrypt_stream(&test_keys(), &mut &b"plain text file"[..], &mut out).is_err());
+    }
+
+    /// Overlay rooted at `/vault` on an in-memory provider, with fixed keys
+    /// so tests skip Argon2
+    fn memory_overlay() -> CryptProvider {
+        let config = CryptConfig {
+            password: SecretString::from("unused".to_string()),
+            salt: SecretString::from(String::new()),
+            root: "/vault".to_string(),
+        };
+        let mut inner = MemoryProvider::default();
+        inner.dirs.insert("/vault".to_string());
+        let mut provider = CryptProvider::new(Box::new(inner), config);
+        provider.keys = Some(test_keys());
+        provider
+    }
+
+    fn memory(provider: &mut CryptProvider) -> &mut MemoryProvider {
+        provider.inner.as_any_mut().downcast_mut::<MemoryProvider>().unwrap()
+    }
+
+    fn content(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i % 251) as u8).collect()
+    }
+
+    async fn put(provider: &mut CryptProvider, path: &str, data: &[u8]) {
+        let dir = tempfile::tempdir().unwrap();
+        let local = dir.path().join("plain");
+        std::fs::write(&local, data).unwrap();
+        provider.upload(&local.to_string_lossy(), path, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_provider_roundtrip() {
+        let mut provider = memory_overlay();
+        let data = content(2 * DEFAULT_CHUNK_SIZE + 100);
+        provider.mkdir("/docs").await.unwrap();
+        put(&mut provider, "/docs/report.bin", &data).await;
+
+        let root = provider.list("/").await.unwrap();
+        assert_eq!(root.len(), 1);
+        assert!(root[0].is_dir);
+        assert_eq!(root[0].name, "docs");
+        let docs = provider.list("/docs").await.unwrap();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].path, "/docs/report.bin");
+        assert_eq!(docs[0].size, data.len() as u64);
+        let inner = memory(&mut provider);
+        assert!(inner.files.keys().all(|p| !p.contains("report.bin")));
+        assert!(!inner.dirs.contains("/vault/docs"));
+
+        assert_eq!(provider.download_to_bytes("/docs/report.bin").await.unwrap(), data);
+        let dir = tempfile::tempdir().unwrap();
+        let local = dir.path().join("out.bin").to_string_lossy().to_string();
+        provider.download("/docs/report.bin", &local, None).await.unwrap();
+        assert_eq!(std::fs::read(&local).unwrap(), data);
+
+        provider.rename("/docs/report.bin", "/docs/final.bin").await.unwrap();
+        assert!(!provider.exists("/docs/report.bin").await.unwrap());
+        assert_eq!(provider.stat("/docs/final.bin").await.unwrap().size, data.len() as u64);
+        assert_eq!(provider.download_to_bytes("/docs/final.bin").await.unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_read_range() {
+        let mut provider = memory_overlay();
+        let data = content(2 * DEFAULT_CHUNK_SIZE + 100);
+        put(&mut provider, "/f.bin", &data).await;
+
+        let chunk = DEFAULT_CHUNK_SIZE as u64;
+        for (offset, len) in [(0, 10), (chunk - 5, 10), (2 * chunk, 100), (2 * chunk + 50, 1000)] {
+            let start = offset as usize;
+            let end = (start + len as usize).min(data.len());
+            let range = provider.read_range("/f.bin", offset, len).await.unwrap();
+            assert_eq!(range, data[start..end], "offset {} len {}", offset, len);
+        }
+        assert!(provider.read_range("/f.bin", data.len() as u64, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_range_detects_truncation() {
+        let mut provider = memory_overlay();
+        put(&mut provider, "/f.bin", &content(2 * DEFAULT_CHUNK_SIZE)).await;
+
+        // Drop the final chunk: what is left still decrypts chunk by chunk,
+        // but its last chunk was not written as the final one
+        let inner = memory(&mut provider);
+        let encrypted = inner.files.values_mut().next().unwrap();
+        encrypted.truncate(HEADER_SIZE + ENCRYPTED_CHUNK_SIZE);
+
+        assert!(provider.read_range("/f.bin", 0, 10).await.is_err());
+        assert!(provider.download_to_bytes("/f.bin").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_names_differing_in_case_stay_distinct() {
+        let mut provider = memory_overlay();
+        put(&mut provider, "/Readme.md", b"first").await;
+        put(&mut provider, "/README.md", b"second").await;
+
+        let folded: std::collections::HashSet<String> =
+            memory(&mut provider).files.keys().map(|p| p.to_lowercase()).collect();
+        assert_eq!(folded.len(), 2);
+
+        // A backend that reports names in another case still maps back
+        let inner = memory(&mut provider);
+        let files: Vec<(String, Vec<u8>)> = inner.files.drain().collect();
+        for (path, data) in files {
+            inner.files.insert(path.to_uppercase().replace("/VAULT/", "/vault/"), data);
+        }
+        let mut names: Vec<String> = provider.list("/").await.unwrap().into_iter().map(|e| e.name).collect();
+        names.sort();
+        assert_eq!(names, ["README.md", "Readme.md"]);
+    }
+}
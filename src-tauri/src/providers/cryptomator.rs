@@ -0,0 +1,838 @@
+//! Cryptomator Vault Provider
+//!
+//! Wraps any connected StorageProvider and exposes the cleartext contents of a
+//! Cryptomator (vault format 8) vault stored on it, so a vault on Google Drive,
+//! WebDAV, S3, etc. can be browsed and synced like any other remote.
+//! Crypto is shared with the local vault support in `crate::cryptomator`.
+//!
+//! Physical layout on the inner provider:
+//! ```text
+//! <vault>/masterkey.cryptomator, <vault>/vault.cryptomator
+//! <vault>/d/XX/YYYY/<name>.c9r              file
+//! <vault>/d/XX/YYYY/<name>.c9r/dir.c9r      directory (holds the child dir ID)
+//! <vault>/d/XX/YYYY/<hash>.c9s/name.c9s     long name + contents.c9r or dir.c9r
+//! ```
+//! Directory IDs are resolved lazily and cached by cleartext path.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::io::Cursor;
+use tracing::info;
+
+use super::{ProviderError, ProviderType, RemoteEntry, StorageProvider};
+use crate::cryptomator::{
+    cleartext_size, shortened_name, MasterKeys,
+    CHUNK_CIPHERTEXT_SIZE, CHUNK_CLEARTEXT_SIZE, FILE_HEADER_SIZE,
+};
+
+/// Maximum number of cached directory IDs to prevent unbounded memory growth.
+const DIR_CACHE_MAX: usize = 10_000;
+
+/// An encrypted node (`.c9r` or `.c9s`) inside a physical vault directory
+#[derive(Debug, Clone)]
+struct Node {
+    /// Cleartext name
+    name: String,
+    /// Inner path of the node itself
+    path: String,
+    /// Full ciphertext name (`<base64url>.c9r`), also stored in `name.c9s` when shortened
+    encrypted_name: String,
+    is_dir: bool,
+    shortened: bool,
+    /// Ciphertext size of the contents (files only)
+    size: u64,
+    modified: Option<String>,
+}
+
+impl Node {
+    /// Inner path of the encrypted file contents
+    fn contents_path(&self) -> String {
+        if self.shortened {
+            format!("{}/contents.c9r", self.path)
+        } else {
+            self.path.clone()
+        }
+    }
+
+    /// Inner path of the file holding a directory's ID
+    fn dir_id_path(&self) -> String {
+        format!("{}/dir.c9r", self.path)
+    }
+}
+
+/// Split a normalized cleartext path into (parent, name)
+fn split_parent(path: &str) -> (String, String) {
+    match path.rfind('/') {
+        Some(0) => ("/".to_string(), path[1..].to_string()),
+        Some(idx) => (path[..idx].to_string(), path[idx + 1..].to_string()),
+        None => ("/".to_string(), path.to_string()),
+    }
+}
+
+fn crypto_error(e: String) -> ProviderError {
+    ProviderError::Other(format!("Cryptomator: {}", e))
+}
+
+fn temp_path() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("aeroftp_cryptomator_{}", uuid::Uuid::new_v4()))
+}
+
+/// Cryptomator vault layered over another storage provider
+pub struct CryptomatorProvider {
+    inner: Option<Box<dyn StorageProvider>>,
+    keys: MasterKeys,
+    /// Vault root on the inner provider (no trailing slash)
+    vault_root: String,
+    shortening_threshold: usize,
+    /// Current cleartext directory
+    current_dir: String,
+    /// Cleartext directory path → directory ID
+    dir_ids: HashMap<String, String>,
+    /// Directory ID → inner path of its physical directory
+    physical_dirs: HashMap<String, String>,
+}
+
+impl CryptomatorProvider {
+    /// Read and unlock the vault configuration stored at `vault_root` on `inner`
+    pub(crate) async fn load_keys(
+        inner: &mut Box<dyn StorageProvider>,
+        vault_root: &str,
+        password: &str,
+    ) -> Result<(MasterKeys, usize), ProviderError> {
+        let root = vault_root.trim_end_matches('/');
+        let masterkey = inner.download_to_bytes(&format!("{}/masterkey.cryptomator", root)).await
+            .map_err(|e| ProviderError::NotFound(format!("masterkey.cryptomator: {}", e)))?;
+        let jwt = inner.download_to_bytes(&format!("{}/vault.cryptomator", root)).await
+            .map_err(|e| ProviderError::NotFound(format!("vault.cryptomator: {}", e)))?;
+
+        let masterkey = String::from_utf8(masterkey)
+            .map_err(|_| ProviderError::ParseError("masterkey.cryptomator is not UTF-8".to_string()))?;
+        let jwt = String::from_utf8(jwt)
+            .map_err(|_| ProviderError::ParseError("vault.cryptomator is not UTF-8".to_string()))?;
+
+        let (keys, config) = MasterKeys::unlock(&masterkey, &jwt, password)
+            .map_err(ProviderError::AuthenticationFailed)?;
+        Ok((keys, config.shortening_threshold as usize))
+    }
+
+    pub(crate) fn new(
+        inner: Box<dyn StorageProvider>,
+        vault_root: &str,
+        keys: MasterKeys,
+        shortening_threshold: usize,
+    ) -> Self {
+        let root = vault_root.trim_end_matches('/');
+        info!("Cryptomator vault opened at {} on {}", root, inner.display_name());
+        Self {
+            inner: Some(inner),
+            keys,
+            vault_root: root.to_string(),
+            shortening_threshold,
+            current_dir: "/".to_string(),
+            dir_ids: HashMap::new(),
+            physical_dirs: HashMap::new(),
+        }
+    }
+
+    /// Detach the wrapped provider, leaving this vault locked
+    pub fn take_inner(&mut self) -> Option<Box<dyn StorageProvider>> {
+        self.dir_ids.clear();
+        self.physical_dirs.clear();
+        self.inner.take()
+    }
+
+    fn inner(&mut self) -> Result<&mut Box<dyn StorageProvider>, ProviderError> {
+        self.inner.as_mut().ok_or(ProviderError::NotConnected)
+    }
+
+    /// Resolve `path` against the current directory into an absolute cleartext path
+    fn normalize(&self, path: &str) -> String {
+        let joined = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("{}/{}", self.current_dir, path)
+        };
+
+        let mut parts: Vec<&str> = Vec::new();
+        for part in joined.split('/') {
+            match part {
+                "" | "." => {}
+                ".." => { parts.pop(); }
+                p => parts.push(p),
+            }
+        }
+        format!("/{}", parts.join("/"))
+    }
+
+    fn inner_path(&self, relative: &str) -> String {
+        format!("{}/{}", self.vault_root, relative)
+    }
+
+    /// Inner path of the physical directory holding the entries of `dir_id`
+    async fn physical_dir(&mut self, dir_id: &str) -> Result<String, ProviderError> {
+        if let Some(path) = self.physical_dirs.get(dir_id) {
+            return Ok(path.clone());
+        }
+
+        let path = self.inner_path(&self.keys.dir_path(dir_id).map_err(crypto_error)?);
+        let mut resolved = path.clone();
+        if !self.inner()?.exists(&path).await? {
+            let legacy = self.inner_path(&self.keys.legacy_dir_path(dir_id).map_err(crypto_error)?);
+            if self.inner()?.exists(&legacy).await? {
+                resolved = legacy;
+            }
+        }
+
+        if self.physical_dirs.len() >= DIR_CACHE_MAX {
+            self.physical_dirs.clear();
+        }
+        self.physical_dirs.insert(dir_id.to_string(), resolved.clone());
+        Ok(resolved)
+    }
+
+    /// List and decrypt the nodes of a directory
+    async fn list_nodes(&mut self, dir_id: &str) -> Result<Vec<Node>, ProviderError> {
+        let physical = self.physical_dir(dir_id).await?;
+        let entries = self.inner()?.list(&physical).await?;
+
+        let mut nodes = Vec::new();
+        for entry in entries {
+            let path = format!("{}/{}", physical, entry.name);
+
+            if entry.name.ends_with(".c9r") {
+                // Skip entries we can't decrypt (e.g. the dirid.c9r backup)
+                let Ok(name) = self.keys.decrypt_name(dir_id, &entry.name) else { continue };
+                nodes.push(Node {
+                    name,
+                    path,
+                    encrypted_name: entry.name,
+                    is_dir: entry.is_dir,
+                    shortened: false,
+                    size: entry.size,
+                    modified: entry.modified,
+                });
+            } else if entry.name.ends_with(".c9s") && entry.is_dir {
+                let long_name = match self.inner()?.download_to_bytes(&format!("{}/name.c9s", path)).await {
+                    Ok(bytes) => String::from_utf8_lossy(&bytes).trim().to_string(),
+                    Err(_) => continue,
+                };
+                let Ok(name) = self.keys.decrypt_name(dir_id, &long_name) else { continue };
+
+                let children = self.inner()?.list(&path).await?;
+                let is_dir = children.iter().any(|c| c.name == "dir.c9r");
+                let size = children.iter()
+                    .find(|c| c.name == "contents.c9r")
+                    .map(|c| c.size)
+                    .unwrap_or(0);
+
+                nodes.push(Node {
+                    name,
+                    path,
+                    encrypted_name: long_name,
+                    is_dir,
+                    shortened: true,
+                    size,
+                    modified: entry.modified,
+                });
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    async fn find_node(&mut self, dir_id: &str, name: &str) -> Result<Option<Node>, ProviderError> {
+        Ok(self.list_nodes(dir_id).await?.into_iter().find(|n| n.name == name))
+    }
+
+    async fn read_dir_id(&mut self, node: &Node) -> Result<String, ProviderError> {
+        let bytes = self.inner()?.download_to_bytes(&node.dir_id_path()).await?;
+        Ok(String::from_utf8_lossy(&bytes).trim().to_string())
+    }
+
+    fn cache_dir_id(&mut self, path: String, dir_id: String) {
+        if self.dir_ids.len() >= DIR_CACHE_MAX {
+            self.dir_ids.clear();
+        }
+        self.dir_ids.insert(path, dir_id);
+    }
+
+    /// Drop cached IDs for `path` and everything below it
+    fn forget_dir(&mut self, path: &str) {
+        let prefix = format!("{}/", path);
+        self.dir_ids.retain(|p, _| p != path && !p.starts_with(&prefix));
+    }
+
+    /// Resolve a cleartext directory path to its directory ID
+    async fn resolve_dir(&mut self, path: &str) -> Result<String, ProviderError> {
+        let path = self.normalize(path);
+        if let Some(id) = self.dir_ids.get(&path) {
+            return Ok(id.clone());
+        }
+
+        let mut dir_id = String::new();
+        let mut current = String::new();
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            current = format!("{}/{}", current, component);
+            if let Some(id) = self.dir_ids.get(&current) {
+                dir_id = id.clone();
+                continue;
+            }
+
+            let node = self.find_node(&dir_id, component).await?
+                .filter(|n| n.is_dir)
+                .ok_or_else(|| ProviderError::NotFound(current.clone()))?;
+            dir_id = self.read_dir_id(&node).await?;
+            self.cache_dir_id(current.clone(), dir_id.clone());
+        }
+
+        Ok(dir_id)
+    }
+
+    /// Find the node for a cleartext path
+    async fn locate(&mut self, path: &str) -> Result<Node, ProviderError> {
+        let path = self.normalize(path);
+        if path == "/" {
+            return Err(ProviderError::InvalidPath("The vault root is not an entry".to_string()));
+        }
+        let (parent, name) = split_parent(&path);
+        let parent_id = self.resolve_dir(&parent).await?;
+        self.find_node(&parent_id, &name).await?
+            .ok_or(ProviderError::NotFound(path))
+    }
+
+    /// Compute the node a new entry called `name` would occupy in `dir_id`
+    async fn new_node(&mut self, dir_id: &str, name: &str, is_dir: bool) -> Result<Node, ProviderError> {
+        if name.is_empty() || name.contains('/') {
+            return Err(ProviderError::InvalidPath(name.to_string()));
+        }
+
+        let physical = self.physical_dir(dir_id).await?;
+        let encrypted_name = self.keys.encrypt_name(dir_id, name).map_err(crypto_error)?;
+        let shortened = encrypted_name.len() > self.shortening_threshold;
+        let node_name = if shortened { shortened_name(&encrypted_name) } else { encrypted_name.clone() };
+
+        Ok(Node {
+            name: name.to_string(),
+            path: format!("{}/{}", physical, node_name),
+            encrypted_name,
+            is_dir,
+            shortened,
+            size: 0,
+            modified: None,
+        })
+    }
+
+    /// Write `name.c9s` for a shortened node
+    async fn write_long_name(&mut self, node: &Node) -> Result<(), ProviderError> {
+        let name_path = format!("{}/name.c9s", node.path);
+        self.upload_bytes(&name_path, node.encrypted_name.as_bytes()).await
+    }
+
+    async fn upload_bytes(&mut self, remote_path: &str, data: &[u8]) -> Result<(), ProviderError> {
+        let tmp = temp_path();
+        std::fs::write(&tmp, data)?;
+        let result = self.inner()?.upload(&tmp.to_string_lossy(), remote_path, None).await;
+        let _ = std::fs::remove_file(&tmp);
+        result
+    }
+
+    fn to_entry(&self, parent: &str, node: &Node) -> RemoteEntry {
+        let path = if parent == "/" {
+            format!("/{}", node.name)
+        } else {
+            format!("{}/{}", parent, node.name)
+        };
+        let mut entry = if node.is_dir {
+            RemoteEntry::directory(node.name.clone(), path)
+        } else {
+            RemoteEntry::file(node.name.clone(), path, cleartext_size(node.size))
+        };
+        entry.modified = node.modified.clone();
+        entry
+    }
+
+    /// Collect a directory ID and the IDs of all directories below it
+    async fn collect_dir_ids(&mut self, dir_id: String) -> Result<Vec<String>, ProviderError> {
+        let mut all = Vec::new();
+        let mut pending = vec![dir_id];
+        while let Some(id) = pending.pop() {
+            for node in self.list_nodes(&id).await? {
+                if node.is_dir {
+                    pending.push(self.read_dir_id(&node).await?);
+                }
+            }
+            all.push(id);
+        }
+        Ok(all)
+    }
+
+    /// Remove a directory node together with the physical directories of the given IDs
+    async fn remove_dir_node(&mut self, path: &str, node: &Node, dir_ids: Vec<String>) -> Result<(), ProviderError> {
+        for id in dir_ids {
+            let physical = self.physical_dir(&id).await?;
+            self.inner()?.rmdir_recursive(&physical).await?;
+            self.physical_dirs.remove(&id);
+        }
+        self.inner()?.rmdir_recursive(&node.path).await?;
+        self.forget_dir(path);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageProvider for CryptomatorProvider {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn provider_type(&self) -> ProviderType {
+        self.inner.as_ref().map(|p| p.provider_type()).unwrap_or(ProviderType::Cryptomator)
+    }
+
+    fn display_name(&self) -> String {
+        match &self.inner {
+            Some(inner) => format!("Cryptomator ({})", inner.display_name()),
+            None => "Cryptomator (locked)".to_string(),
+        }
+    }
+
+    fn account_email(&self) -> Option<String> {
+        self.inner.as_ref().and_then(|p| p.account_email())
+    }
+
+    async fn connect(&mut self) -> Result<(), ProviderError> {
+        let inner = self.inner()?;
+        if !inner.is_connected() {
+            inner.connect().await?;
+        }
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ProviderError> {
+        self.dir_ids.clear();
+        self.physical_dirs.clear();
+        self.inner()?.disconnect().await
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.as_ref().map(|p| p.is_connected()).unwrap_or(false)
+    }
+
+    async fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
+        let path = self.normalize(path);
+        let dir_id = self.resolve_dir(&path).await?;
+        let nodes = self.list_nodes(&dir_id).await?;
+        Ok(nodes.iter().map(|n| self.to_entry(&path, n)).collect())
+    }
+
+    async fn pwd(&mut self) -> Result<String, ProviderError> {
+        Ok(self.current_dir.clone())
+    }
+
+    async fn cd(&mut self, path: &str) -> Result<(), ProviderError> {
+        let path = self.normalize(path);
+        self.resolve_dir(&path).await?;
+        self.current_dir = path;
+        Ok(())
+    }
+
+    async fn cd_up(&mut self) -> Result<(), ProviderError> {
+        let (parent, _) = split_parent(&self.current_dir);
+        self.current_dir = parent;
+        Ok(())
+    }
+
+    async fn download(
+        &mut self,
+        remote_path: &str,
+        local_path: &str,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        let node = self.locate(remote_path).await?;
+        if node.is_dir {
+            return Err(ProviderError::InvalidPath(format!("{} is a directory", remote_path)));
+        }
+
+        let tmp = temp_path();
+        let tmp_str = tmp.to_string_lossy().to_string();
+        let result = self.inner()?.download(&node.contents_path(), &tmp_str, on_progress).await;
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e);
+        }
+
+        let decrypted = std::fs::File::open(&tmp)
+            .and_then(|input| std::fs::File::create(local_path).map(|output| (input, output)))
+            .map_err(ProviderError::from)
+            .and_then(|(input, mut output)| {
+                self.keys.decrypt_content(&mut std::io::BufReader::new(input), &mut output)
+                    .map_err(crypto_error)
+            });
+        let _ = std::fs::remove_file(&tmp);
+        if decrypted.is_err() {
+            let _ = std::fs::remove_file(local_path);
+        }
+        decrypted
+    }
+
+    async fn download_to_bytes(&mut self, remote_path: &str) -> Result<Vec<u8>, ProviderError> {
+        let node = self.locate(remote_path).await?;
+        if node.is_dir {
+            return Err(ProviderError::InvalidPath(format!("{} is a directory", remote_path)));
+        }
+
+        let encrypted = self.inner()?.download_to_bytes(&node.contents_path()).await?;
+        let mut cleartext = Vec::with_capacity(cleartext_size(encrypted.len() as u64) as usize);
+        self.keys.decrypt_content(&mut Cursor::new(encrypted), &mut cleartext)
+            .map_err(crypto_error)?;
+        Ok(cleartext)
+    }
+
+    async fn upload(
+        &mut self,
+        local_path: &str,
+        remote_path: &str,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        let path = self.normalize(remote_path);
+        let (parent, name) = split_parent(&path);
+        let parent_id = self.resolve_dir(&parent).await?;
+
+        let node = match self.find_node(&parent_id, &name).await? {
+            Some(existing) if existing.is_dir => {
+                return Err(ProviderError::AlreadyExists(format!("{} is a directory", path)));
+            }
+            Some(existing) => existing,
+            None => {
+                let node = self.new_node(&parent_id, &name, false).await?;
+                if node.shortened {
+                    self.inner()?.mkdir(&node.path).await?;
+                    self.write_long_name(&node).await?;
+                }
+                node
+            }
+        };
+
+        let tmp = temp_path();
+        let encrypted = std::fs::File::open(local_path)
+            .and_then(|input| std::fs::File::create(&tmp).map(|output| (input, output)))
+            .map_err(ProviderError::from)
+            .and_then(|(input, output)| {
+                let mut output = std::io::BufWriter::new(output);
+                self.keys.encrypt_content(&mut std::io::BufReader::new(input), &mut output)
+                    .map_err(crypto_error)?;
+                std::io::Write::flush(&mut output).map_err(ProviderError::from)
+            });
+        if let Err(e) = encrypted {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e);
+        }
+
+        let result = self.inner()?
+            .upload(&tmp.to_string_lossy(), &node.contents_path(), on_progress).await;
+        let _ = std::fs::remove_file(&tmp);
+        result
+    }
+
+    async fn mkdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        let path = self.normalize(path);
+        let (parent, name) = split_parent(&path);
+        let parent_id = self.resolve_dir(&parent).await?;
+        if self.find_node(&parent_id, &name).await?.is_some() {
+            return Err(ProviderError::AlreadyExists(path));
+        }
+
+        // Node first, then the physical directory for the new ID
+        let node = self.new_node(&parent_id, &name, true).await?;
+        let dir_id = uuid::Uuid::new_v4().to_string();
+        self.inner()?.mkdir(&node.path).await?;
+        if node.shortened {
+            self.write_long_name(&node).await?;
+        }
+        self.upload_bytes(&node.dir_id_path(), dir_id.as_bytes()).await?;
+
+        let physical = self.inner_path(&self.keys.dir_path(&dir_id).map_err(crypto_error)?);
+        if let Some((prefix, _)) = physical.rsplit_once('/') {
+            // d/XX is shared between directories and may already exist
+            let _ = self.inner()?.mkdir(prefix).await;
+        }
+        self.inner()?.mkdir(&physical).await?;
+
+        self.physical_dirs.insert(dir_id.clone(), physical);
+        self.cache_dir_id(path, dir_id);
+        Ok(())
+    }
+
+    async fn delete(&mut self, path: &str) -> Result<(), ProviderError> {
+        let node = self.locate(path).await?;
+        if node.is_dir {
+            return Err(ProviderError::InvalidPath(format!("{} is a directory", path)));
+        }
+        if node.shortened {
+            self.inner()?.rmdir_recursive(&node.path).await
+        } else {
+            self.inner()?.delete(&node.path).await
+        }
+    }
+
+    async fn rmdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        let path = self.normalize(path);
+        let node = self.locate(&path).await?;
+        if !node.is_dir {
+            return Err(ProviderError::InvalidPath(format!("{} is not a directory", path)));
+        }
+        let dir_id = self.read_dir_id(&node).await?;
+        if !self.list_nodes(&dir_id).await?.is_empty() {
+            return Err(ProviderError::DirectoryNotEmpty(path));
+        }
+        self.remove_dir_node(&path, &node, vec![dir_id]).await
+    }
+
+    async fn rmdir_recursive(&mut self, path: &str) -> Result<(), ProviderError> {
+        let path = self.normalize(path);
+        let node = self.locate(&path).await?;
+        if !node.is_dir {
+            return Err(ProviderError::InvalidPath(format!("{} is not a directory", path)));
+        }
+        let dir_id = self.read_dir_id(&node).await?;
+        let dir_ids = self.collect_dir_ids(dir_id).await?;
+        self.remove_dir_node(&path, &node, dir_ids).await
+    }
+
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        let from = self.normalize(from);
+        let to = self.normalize(to);
+        let node = self.locate(&from).await?;
+        if node.is_dir && (to == from || to.starts_with(&format!("{}/", from))) {
+            return Err(ProviderError::InvalidPath(format!("Cannot move {} into itself", from)));
+        }
+
+        let (to_parent, to_name) = split_parent(&to);
+        let to_parent_id = self.resolve_dir(&to_parent).await?;
+        if self.find_node(&to_parent_id, &to_name).await?.is_some() {
+            return Err(ProviderError::AlreadyExists(to));
+        }
+
+        // Only the node name changes: a directory keeps its ID, so its contents stay put
+        let target = self.new_node(&to_parent_id, &to_name, node.is_dir).await?;
+        if node.is_dir || node.shortened == target.shortened {
+            self.inner()?.rename(&node.path, &target.path).await?;
+            if target.shortened {
+                self.write_long_name(&target).await?;
+            } else if node.shortened {
+                self.inner()?.delete(&format!("{}/name.c9s", target.path)).await?;
+            }
+        } else if target.shortened {
+            self.inner()?.mkdir(&target.path).await?;
+            self.inner()?.rename(&node.path, &target.contents_path()).await?;
+            self.write_long_name(&target).await?;
+        } else {
+            self.inner()?.rename(&node.contents_path(), &target.path).await?;
+            self.inner()?.rmdir_recursive(&node.path).await?;
+        }
+
+        self.forget_dir(&from);
+        Ok(())
+    }
+
+    async fn stat(&mut self, path: &str) -> Result<RemoteEntry, ProviderError> {
+        let path = self.normalize(path);
+        if path == "/" {
+            return Ok(RemoteEntry::directory("/".to_string(), "/".to_string()));
+        }
+        let node = self.locate(&path).await?;
+        let (parent, _) = split_parent(&path);
+        Ok(self.to_entry(&parent, &node))
+    }
+
+    async fn size(&mut self, path: &str) -> Result<u64, ProviderError> {
+        Ok(self.stat(path).await?.size)
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool, ProviderError> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(ProviderError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn keep_alive(&mut self) -> Result<(), ProviderError> {
+        self.inner()?.keep_alive().await
+    }
+
+    async fn server_info(&mut self) -> Result<String, ProviderError> {
+        let root = self.vault_root.clone();
+        let inner_info = self.inner()?.server_info().await?;
+        Ok(format!("Cryptomator vault (format 8) at {} on {}", root, inner_info))
+    }
+
+    /// Ranged reads map onto whole encrypted chunks of the inner file
+    async fn read_range(&mut self, path: &str, offset: u64, len: u64) -> Result<Vec<u8>, ProviderError> {
+        let node = self.locate(path).await?;
+        if node.is_dir {
+            return Err(ProviderError::InvalidPath(format!("{} is a directory", path)));
+        }
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+
+        let contents = node.contents_path();
+        let header_bytes = self.inner()?.read_range(&contents, 0, FILE_HEADER_SIZE as u64).await?;
+        let header = self.keys.decrypt_file_header(&header_bytes).map_err(crypto_error)?;
+
+        let clear_chunk = CHUNK_CLEARTEXT_SIZE as u64;
+        let cipher_chunk = CHUNK_CIPHERTEXT_SIZE as u64;
+        let first = offset / clear_chunk;
+        let last = (offset + len - 1) / clear_chunk;
+        let encrypted = self.inner()?.read_range(
+            &contents,
+            FILE_HEADER_SIZE as u64 + first * cipher_chunk,
+            (last - first + 1) * cipher_chunk,
+        ).await?;
+
+        let mut cleartext = Vec::new();
+        for (i, chunk) in encrypted.chunks(CHUNK_CIPHERTEXT_SIZE).enumerate() {
+            cleartext.extend(header.decrypt_chunk(first + i as u64, chunk).map_err(crypto_error)?);
+        }
+
+        let start = ((offset - first * clear_chunk) as usize).min(cleartext.len());
+        let end = (start + len as usize).min(cleartext.len());
+        Ok(cleartext[start..end].to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::memory::MemoryProvider;
+
+    const PASSWORD: &str = "correct horse battery";
+
+    fn copy_tree(local: &std::path::Path, remote: &str, memory: &mut MemoryProvider) {
+        memory.dirs.insert(remote.to_string());
+        for entry in std::fs::read_dir(local).unwrap() {
+            let entry = entry.unwrap();
+            let path = format!("{}/{}", remote, entry.file_name().to_string_lossy());
+            if entry.file_type().unwrap().is_dir() {
+                copy_tree(&entry.path(), &path, memory);
+            } else {
+                memory.files.insert(path, std::fs::read(entry.path()).unwrap());
+            }
+        }
+    }
+
+    /// Create a vault locally, copy it to `/vault` on an in-memory provider and open it there
+    async fn open_vault() -> CryptomatorProvider {
+        let dir = tempfile::tempdir().unwrap();
+        let local = dir.path().join("vault");
+        crate::cryptomator::cryptomator_create(local.to_string_lossy().to_string(), PASSWORD.to_string())
+            .await
+            .unwrap();
+
+        let mut memory = MemoryProvider::default();
+        copy_tree(&local, "/vault", &mut memory);
+        let mut inner: Box<dyn StorageProvider> = Box::new(memory);
+        let (keys, threshold) = CryptomatorProvider::load_keys(&mut inner, "/vault", PASSWORD).await.unwrap();
+        CryptomatorProvider::new(inner, "/vault", keys, threshold)
+    }
+
+    async fn put(vault: &mut CryptomatorProvider, path: &str, data: &[u8]) {
+        let dir = tempfile::tempdir().unwrap();
+        let local = dir.path().join("plain");
+        std::fs::write(&local, data).unwrap();
+        vault.upload(&local.to_string_lossy(), path, None).await.unwrap();
+    }
+
+    fn names(entries: Vec<RemoteEntry>) -> Vec<String> {
+        let mut names: Vec<String> = entries.into_iter().map(|e| e.name).collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_provider_upload_list_download() {
+        let mut vault = open_vault().await;
+        let data: Vec<u8> = (0..70_000).map(|i| (i % 251) as u8).collect();
+        vault.mkdir("/docs").await.unwrap();
+        put(&mut vault, "/docs/report.bin", &data).await;
+
+        assert_eq!(names(vault.list("/").await.unwrap()), ["docs"]);
+        let docs = vault.list("/docs").await.unwrap();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].path, "/docs/report.bin");
+        assert_eq!(docs[0].size, data.len() as u64);
+
+        assert_eq!(vault.download_to_bytes("/docs/report.bin").await.unwrap(), data);
+        let dir = tempfile::tempdir().unwrap();
+        let local = dir.path().join("out.bin").to_string_lossy().to_string();
+        vault.download("/docs/report.bin", &local, None).await.unwrap();
+        assert_eq!(std::fs::read(&local).unwrap(), data);
+
+        let offset = CHUNK_CLEARTEXT_SIZE as u64 - 10;
+        let range = vault.read_range("/docs/report.bin", offset, 100).await.unwrap();
+        assert_eq!(range, data[offset as usize..offset as usize + 100]);
+    }
+
+    #[tokio::test]
+    async fn test_provider_rename() {
+        let mut vault = open_vault().await;
+        vault.mkdir("/a").await.unwrap();
+        vault.mkdir("/b").await.unwrap();
+        put(&mut vault, "/a/f.txt", b"hello").await;
+
+        // Directories move with their contents
+        vault.rename("/a", "/b/a").await.unwrap();
+        assert_eq!(names(vault.list("/").await.unwrap()), ["b"]);
+        assert_eq!(vault.download_to_bytes("/b/a/f.txt").await.unwrap(), b"hello");
+
+        // Into and back out of a shortened (.c9s) name
+        let long = format!("/b/a/{}", "x".repeat(200));
+        vault.rename("/b/a/f.txt", &long).await.unwrap();
+        assert_eq!(vault.download_to_bytes(&long).await.unwrap(), b"hello");
+        vault.rename(&long, "/b/a/g.txt").await.unwrap();
+        assert_eq!(names(vault.list("/b/a").await.unwrap()), ["g.txt"]);
+        assert_eq!(vault.download_to_bytes("/b/a/g.txt").await.unwrap(), b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_provider_rename_rejects_own_subtree() {
+        let mut vault = open_vault().await;
+        vault.mkdir("/a").await.unwrap();
+        vault.mkdir("/a/b").await.unwrap();
+
+        assert!(matches!(vault.rename("/a", "/a/b/a").await, Err(ProviderError::InvalidPath(_))));
+        assert!(matches!(vault.rename("/a", "/a").await, Err(ProviderError::InvalidPath(_))));
+        assert_eq!(names(vault.list("/a").await.unwrap()), ["b"]);
+    }
+
+    #[test]
+    fn test_split_parent() {
+        assert_eq!(split_parent("/a"), ("/".to_string(), "a".to_string()));
+        assert_eq!(split_parent("/a/b/c"), ("/a/b".to_string(), "c".to_string()));
+    }
+
+    #[test]
+    fn test_cleartext_size() {
+        assert_eq!(cleartext_size(68), 0);
+        assert_eq!(cleartext_size(68 + 28), 0);
+        assert_eq!(cleartext_size(68 + 28 + 100), 100);
+        assert_eq!(cleartext_size(68 + 32796), 32768);
+        assert_eq!(cleartext_size(68 + 32796 + 28 + 1), 32769);
+    }
+
+    #[test]
+    fn test_node_paths() {
+        let node = Node {
+            name: "a".to_string(),
+            path: "/v/d/AB/CD/x.c9s".to_string(),
+            encrypted_name: "x.c9r".to_string(),
+            is_dir: false,
+            shortened: true,
+            size: 0,
+            modified: None,
+        };
+        assert_eq!(node.contents_path(), "/v/d/AB/CD/x.c9s/contents.c9r");
+        assert_eq!(node.dir_id_path(), "/v/d/AB/CD/x.c9s/dir.c9r");
+    }
+}
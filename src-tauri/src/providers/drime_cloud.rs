@@ -216,7 +216,7 @@ impl DrimeCloudProvider {
     pub fn new(config: DrimeCloudConfig) -> Self {
         let mut default_headers = reqwest::header::HeaderMap::new();
         default_headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
-        let client = reqwest::Client::builder()
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
             .timeout(std::time::Duration::from_secs(300))
             .connect_timeout(std::time::Duration::from_secs(30))
             .default_headers(default_headers)
@@ -885,6 +885,7 @@ impl StorageProvider for DrimeCloudProvider {
                     link_target: None,
                     metadata: HashMap::new(),
                     mime_type: file.mime_type,
+                    content_hash: None,
                 });
             }
 
@@ -1269,6 +1270,7 @@ impl StorageProvider for DrimeCloudProvider {
                             link_target: None,
                             metadata: HashMap::new(),
                             mime_type: file.mime_type.clone(),
+                            content_hash: None,
                         });
                     }
                 }
@@ -1464,6 +1466,7 @@ impl StorageProvider for DrimeCloudProvider {
                     link_target: None,
                     metadata: HashMap::new(),
                     mime_type: file.mime_type,
+                    content_hash: None,
                 });
             }
 
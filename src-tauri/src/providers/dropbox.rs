@@ -11,7 +11,7 @@ use tracing::info;
 
 use super::{
     StorageProvider, ProviderType, ProviderError, RemoteEntry, ProviderConfig, StorageInfo, LockInfo,
-    sanitize_api_error,
+    ContentHash, HashAlgorithm, sanitize_api_error,
     oauth2::{OAuth2Manager, OAuthConfig, OAuthProvider},
 };
 
@@ -33,6 +33,8 @@ struct DropboxMetadata {
     size: u64,
     client_modified: Option<String>,
     server_modified: Option<String>,
+    /// Dropbox content hash (files only)
+    content_hash: Option<String>,
 }
 
 /// List folder response
@@ -48,6 +50,10 @@ struct ListFolderResult {
 pub struct DropboxConfig {
     pub app_key: String,
     pub app_secret: String,
+    /// Outbound proxy (`None` connects directly)
+    pub proxy: Option<super::proxy::ProxyConfig>,
+    /// Account whose tokens to use (email); `None` takes a fresh sign-in
+    pub account: Option<String>,
 }
 
 impl DropboxConfig {
@@ -55,6 +61,8 @@ impl DropboxConfig {
         Self {
             app_key: app_key.to_string(),
             app_secret: app_secret.to_string(),
+            account: None,
+            proxy: super::proxy::global(),
         }
     }
 
@@ -67,7 +75,10 @@ impl DropboxConfig {
             .or_else(|| config.extra.get("client_secret"))
             .ok_or_else(|| ProviderError::Other("Missing app_secret".to_string()))?;
 
-        Ok(Self::new(app_key, app_secret))
+        Ok(Self {
+            proxy: super::proxy::from_extra(&config.extra)?,
+            ..Self::new(app_key, app_secret)
+        })
     }
 }
 
@@ -84,13 +95,14 @@ pub struct DropboxProvider {
 
 impl DropboxProvider {
     pub fn new(config: DropboxConfig) -> Self {
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
         Self {
             config,
             oauth_manager: OAuth2Manager::new(),
-            client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
-                .build()
-                .unwrap_or_default(),
+            client,
             connected: false,
             current_path: "".to_string(), // Dropbox root is ""
             account_email: None,
@@ -100,6 +112,7 @@ impl DropboxProvider {
     /// Get OAuth config
     fn oauth_config(&self) -> OAuthConfig {
         OAuthConfig::dropbox(&self.config.app_key, &self.config.app_secret)
+            .with_account(self.config.account.as_deref())
     }
 
     /// Get authorization header
@@ -112,7 +125,7 @@ impl DropboxProvider {
 
     /// Check if authenticated
     pub fn is_authenticated(&self) -> bool {
-        self.oauth_manager.has_tokens(OAuthProvider::Dropbox)
+        self.oauth_manager.has_tokens(OAuthProvider::Dropbox, self.config.account.as_deref())
     }
 
     /// Start OAuth flow (called via oauth2_start_auth command)
@@ -157,6 +170,8 @@ impl DropboxProvider {
             link_target: None,
             mime_type: None,
             metadata: HashMap::new(),
+            content_hash: meta.content_hash.as_deref()
+                .and_then(|h| ContentHash::hex(HashAlgorithm::DropboxContentHash, h)),
         }
     }
 
@@ -360,6 +375,7 @@ impl StorageProvider for DropboxProvider {
         self.connected = true;
         self.current_path = "".to_string();
 
+        self.config.account = self.oauth_manager.bind_account(&self.oauth_config(), self.account_email.as_deref());
         info!("Connected to Dropbox");
         Ok(())
     }
@@ -764,6 +780,15 @@ impl StorageProvider for DropboxProvider {
         Ok("Dropbox API v2".to_string())
     }
 
+    fn supports_checksum(&self) -> bool {
+        true
+    }
+
+    async fn checksum(&mut self, path: &str) -> Result<HashMap<String, String>, ProviderError> {
+        let entry = self.stat(path).await?;
+        Ok(entry.content_hash.map(|h| h.to_checksums()).unwrap_or_default())
+    }
+
     fn supports_server_copy(&self) -> bool {
         true
     }
@@ -1319,4 +1344,56 @@ impl StorageProvider for DropboxProvider {
         info!("Unlocked file: {}", path);
         Ok(())
     }
+
+    fn supports_change_tracking(&self) -> bool {
+        true
+    }
+
+    async fn get_change_token(&mut self) -> Result<String, ProviderError> {
+        #[derive(Deserialize)]
+        struct LatestCursor {
+            cursor: String,
+        }
+
+        let body = serde_json::json!({
+            "path": "",
+            "recursive": true,
+            "include_deleted": true,
+            "include_mounted_folders": true
+        });
+        let result: LatestCursor = self.rpc_call("files/list_folder/get_latest_cursor", &body).await?;
+        Ok(result.cursor)
+    }
+
+    async fn list_changes(&mut self, page_token: &str) -> Result<(Vec<super::ChangeEntry>, String), ProviderError> {
+        let mut changes = Vec::new();
+        let mut cursor = page_token.to_string();
+
+        // An expired cursor ("reset") comes back as an API error; callers list again
+        loop {
+            let body = serde_json::json!({ "cursor": cursor });
+            let result: ListFolderResult = self.rpc_call("files/list_folder/continue", &body).await?;
+
+            for meta in &result.entries {
+                let removed = meta.tag == "deleted";
+                changes.push(super::ChangeEntry {
+                    file_id: meta.id.clone().unwrap_or_default(),
+                    name: meta.name.clone(),
+                    change_type: if removed { "deleted" } else { "modified" }.to_string(),
+                    mime_type: None,
+                    timestamp: meta.server_modified.clone(),
+                    removed,
+                    path: meta.path_display.clone().or_else(|| meta.path_lower.clone()),
+                    parent_id: None,
+                    is_dir: meta.tag == "folder",
+                    size: (meta.tag == "file").then_some(meta.size),
+                });
+            }
+
+            cursor = result.cursor;
+            if !result.has_more {
+                return Ok((changes, cursor));
+            }
+        }
+    }
 }
@@ -239,7 +239,7 @@ pub struct FileLuProvider {
 
 impl FileLuProvider {
     pub fn new(config: FileLuConfig) -> Self {
-        let client = reqwest::Client::builder()
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
             .timeout(std::time::Duration::from_secs(300))
             .connect_timeout(std::time::Duration::from_secs(30))
             .build()
@@ -482,6 +482,7 @@ impl FileLuProvider {
                 link_target: None,
                 mime_type: None,
                 metadata,
+                content_hash: None,
             });
         }
 
@@ -522,6 +523,7 @@ impl FileLuProvider {
                 link_target: None,
                 mime_type: mime,
                 metadata,
+                content_hash: None,
             });
         }
 
@@ -1272,6 +1274,7 @@ impl StorageProvider for FileLuProvider {
                 link_target: None,
                 mime_type: None,
                 metadata: HashMap::new(),
+                content_hash: None,
             })
         } else {
             let mime = Path::new(&name)
@@ -1291,6 +1294,7 @@ impl StorageProvider for FileLuProvider {
                 link_target: None,
                 mime_type: mime,
                 metadata: HashMap::new(),
+                content_hash: None,
             })
         }
     }
@@ -211,7 +211,7 @@ const FILE_KEY_CACHE_MAX_ENTRIES: usize = 10_000;
 
 impl FilenProvider {
     pub fn new(config: FilenConfig) -> Self {
-        let client = reqwest::Client::builder()
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .unwrap_or_else(|_| reqwest::Client::new());
@@ -769,6 +769,7 @@ impl StorageProvider for FilenProvider {
                         m.insert("uuid".to_string(), folder.uuid);
                         m
                     },
+                    content_hash: None,
                 });
             } else {
                 filen_log(&format!("FAILED decrypt folder: uuid={}, encrypted_len={}",
@@ -811,6 +812,7 @@ impl StorageProvider for FilenProvider {
                             m.insert("chunks".to_string(), file.chunks.to_string());
                             m
                         },
+                        content_hash: None,
                     });
                 }
             } else {
@@ -196,7 +196,7 @@ pub struct FourSharedProvider {
 
 impl FourSharedProvider {
     pub fn new(config: FourSharedConfig) -> Self {
-        let client = reqwest::Client::builder()
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .unwrap_or_else(|_| reqwest::Client::new());
@@ -735,6 +735,7 @@ impl StorageProvider for FourSharedProvider {
                     link_target: None,
                     mime_type: None,
                     metadata: std::collections::HashMap::new(),
+                    content_hash: None,
                 });
             }
 
@@ -799,6 +800,7 @@ impl StorageProvider for FourSharedProvider {
                     // FS-011: Populate MIME type from API response
                     mime_type: f.mime_type.clone(),
                     metadata: std::collections::HashMap::new(),
+                    content_hash: None,
                 });
             }
 
@@ -1174,6 +1176,7 @@ impl StorageProvider for FourSharedProvider {
                     // FS-011: Populate MIME type from API response
                     mime_type: file.mime_type,
                     metadata: std::collections::HashMap::new(),
+                    content_hash: None,
                 });
             }
         }
@@ -1207,6 +1210,7 @@ impl StorageProvider for FourSharedProvider {
             link_target: None,
             mime_type: None,
             metadata: std::collections::HashMap::new(),
+            content_hash: None,
         })
     }
 
@@ -1320,6 +1324,7 @@ impl StorageProvider for FourSharedProvider {
                         }
                         m
                     },
+                    content_hash: None,
                 })
             })
             .collect::<Vec<_>>();
@@ -0,0 +1,797 @@
+//! Google Cloud Storage Provider
+//!
+//! Implements StorageProvider for Google Cloud Storage using the JSON API and a
+//! service account key. The key signs an RS256 JWT that is exchanged for an
+//! OAuth2 access token (no browser flow, suitable for unattended sync).
+//!
+//! - Files above 32 MB use resumable uploads in 16 MB chunks
+//! - Server-side copy via `rewriteTo` (continued until done for large objects)
+//! - Versions are object generations (requires Object Versioning on the bucket)
+//!
+//! ## Limitations (documented)
+//! - GCS-001: Only service account keys (no user OAuth, no workload identity)
+//! - GCS-002: No storage quota API (GCS buckets are unbounded)
+
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD}};
+use futures_util::StreamExt;
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, LOCATION};
+use rustls::pki_types::{PrivateKeyDer, pem::PemObject};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, debug};
+
+use super::{
+    StorageProvider, ProviderType, ProviderError, RemoteEntry, FileVersion,
+    ContentHash, HashAlgorithm, sanitize_api_error, HttpRetryConfig, send_with_retry, read_full_async,
+};
+use super::types::GcsConfig;
+
+const API_BASE: &str = "https://storage.googleapis.com/storage/v1";
+const UPLOAD_BASE: &str = "https://storage.googleapis.com/upload/storage/v1";
+
+/// OAuth2 scope for reading and writing objects
+const SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+
+/// GCS-003: Files above this size use a resumable upload (32 MB)
+const RESUMABLE_THRESHOLD: u64 = 32 * 1024 * 1024;
+
+/// GCS-003: Resumable chunk size, a multiple of 256 KiB as the API requires (16 MB)
+const CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Lifetime requested for the signed JWT assertion
+const ASSERTION_LIFETIME_SECS: i64 = 3600;
+
+/// Refresh the access token when it expires within this window
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 300;
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: i64,
+}
+
+/// Object resource (only the fields we use)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcsObject {
+    name: String,
+    /// int64 values are serialized as strings
+    size: Option<String>,
+    updated: Option<String>,
+    content_type: Option<String>,
+    md5_hash: Option<String>,
+    crc32c: Option<String>,
+    generation: Option<String>,
+}
+
+impl GcsObject {
+    fn size(&self) -> u64 {
+        self.size.as_deref().and_then(|s| s.parse().ok()).unwrap_or(0)
+    }
+
+    fn generation(&self) -> i64 {
+        self.generation.as_deref().and_then(|g| g.parse().ok()).unwrap_or(0)
+    }
+
+    /// md5 (absent for composite objects) and crc32c, hex-encoded
+    fn checksums(&self) -> HashMap<String, String> {
+        let mut checksums = HashMap::new();
+        if let Some(md5) = self.md5_hash.as_deref().and_then(|h| BASE64.decode(h).ok()) {
+            checksums.insert("md5".to_string(), hex::encode(md5));
+        }
+        if let Some(crc) = self.crc32c.as_deref().and_then(|h| BASE64.decode(h).ok()) {
+            checksums.insert("crc32c".to_string(), hex::encode(crc));
+        }
+        checksums
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectList {
+    #[serde(default)]
+    items: Vec<GcsObject>,
+    #[serde(default)]
+    prefixes: Vec<String>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RewriteResponse {
+    done: bool,
+    rewrite_token: Option<String>,
+}
+
+/// Sign an RS256 JWT with a PKCS#8 PEM RSA key
+fn sign_jwt(claims: &serde_json::Value, private_key_pem: &str) -> Result<String, ProviderError> {
+    let invalid = |msg: String| ProviderError::InvalidConfig(format!("Invalid service account key: {}", msg));
+
+    let key = match PrivateKeyDer::from_pem_slice(private_key_pem.as_bytes()).map_err(|e| invalid(e.to_string()))? {
+        PrivateKeyDer::Pkcs8(key) => key,
+        _ => return Err(invalid("expected a PKCS#8 private key".to_string())),
+    };
+    let key_pair = ring::signature::RsaKeyPair::from_pkcs8(key.secret_pkcs8_der())
+        .map_err(|e| invalid(e.to_string()))?;
+
+    let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"RS256","typ":"JWT"}"#);
+    let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+    let signing_input = format!("{}.{}", header, payload);
+
+    let mut signature = vec![0u8; key_pair.public().modulus_len()];
+    key_pair.sign(
+        &ring::signature::RSA_PKCS1_SHA256,
+        &ring::rand::SystemRandom::new(),
+        signing_input.as_bytes(),
+        &mut signature,
+    ).map_err(|_| ProviderError::Other("Failed to sign service account assertion".to_string()))?;
+
+    Ok(format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature)))
+}
+
+/// Map a failed JSON API response to a ProviderError
+fn gcs_error(status: u16, body: &str, context: &str) -> ProviderError {
+    let message = serde_json::from_str::<serde_json::Value>(body).ok()
+        .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| sanitize_api_error(body));
+    match status {
+        404 => ProviderError::NotFound(context.to_string()),
+        401 => ProviderError::AuthenticationFailed(message),
+        403 => ProviderError::PermissionDenied(message),
+        _ => ProviderError::ServerError(format!("{} failed (HTTP {}): {}", context, status, message)),
+    }
+}
+
+/// Google Cloud Storage Provider
+pub struct GcsProvider {
+    config: GcsConfig,
+    client: reqwest::Client,
+    connected: bool,
+    current_prefix: String,
+    access_token: String,
+    token_expires: chrono::DateTime<chrono::Utc>,
+}
+
+impl GcsProvider {
+    pub fn new(config: GcsConfig) -> Self {
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
+            .connect_timeout(std::time::Duration::from_secs(30))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+
+        Self {
+            config,
+            client,
+            connected: false,
+            current_prefix: String::new(),
+            access_token: String::new(),
+            token_expires: chrono::DateTime::<chrono::Utc>::MIN_UTC,
+        }
+    }
+
+    /// Exchange a signed service account assertion for an access token
+    async fn fetch_token(&mut self) -> Result<(), ProviderError> {
+        let now = chrono::Utc::now().timestamp();
+        let claims = serde_json::json!({
+            "iss": self.config.client_email,
+            "scope": SCOPE,
+            "aud": self.config.token_uri,
+            "iat": now,
+            "exp": now + ASSERTION_LIFETIME_SECS,
+        });
+        let assertion = sign_jwt(&claims, self.config.private_key.expose_secret())?;
+
+        let body = format!("grant_type={}&assertion={}",
+            urlencoding::encode("urn:ietf:params:oauth:grant-type:jwt-bearer"), assertion);
+        let resp = self.client.post(&self.config.token_uri)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(body)
+            .send().await
+            .map_err(|e| ProviderError::ConnectionFailed(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::AuthenticationFailed(
+                format!("Service account token request failed: {}", sanitize_api_error(&body))
+            ));
+        }
+
+        let token: TokenResponse = resp.json().await
+            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+        self.access_token = token.access_token;
+        self.token_expires = chrono::Utc::now() + chrono::Duration::seconds(token.expires_in);
+        debug!("GCS access token obtained for {}", self.config.client_email);
+        Ok(())
+    }
+
+    /// Refresh the access token shortly before it expires
+    async fn ensure_token(&mut self) -> Result<(), ProviderError> {
+        if !self.connected {
+            return Err(ProviderError::NotConnected);
+        }
+        if self.token_expires - chrono::Utc::now() < chrono::Duration::seconds(TOKEN_REFRESH_MARGIN_SECS) {
+            self.fetch_token().await?;
+        }
+        Ok(())
+    }
+
+    fn bucket_url(&self) -> String {
+        format!("{}/b/{}/o", API_BASE, urlencoding::encode(&self.config.bucket))
+    }
+
+    /// Object names are percent-encoded as a single path segment (including `/`)
+    fn object_url(&self, name: &str) -> String {
+        format!("{}/{}", self.bucket_url(), urlencoding::encode(name))
+    }
+
+    fn upload_url(&self, upload_type: &str, name: &str) -> String {
+        format!("{}/b/{}/o?uploadType={}&name={}",
+            UPLOAD_BASE, urlencoding::encode(&self.config.bucket), upload_type, urlencoding::encode(name))
+    }
+
+    /// Send an authorized request with retry for transient errors (429/5xx).
+    /// Only byte bodies can be retried; streaming uploads use `self.client` directly.
+    async fn send(&self, builder: reqwest::RequestBuilder) -> Result<reqwest::Response, ProviderError> {
+        let request = builder
+            .header(AUTHORIZATION, format!("Bearer {}", self.access_token))
+            .build()
+            .map_err(|e| ProviderError::NetworkError(format!("Failed to build request: {}", e)))?;
+        send_with_retry(&self.client, request, &HttpRetryConfig::default())
+            .await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))
+    }
+
+    /// Send and fail on any non-success status
+    async fn send_ok(&self, builder: reqwest::RequestBuilder, context: &str) -> Result<reqwest::Response, ProviderError> {
+        let resp = self.send(builder).await?;
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(gcs_error(status, &body, context));
+        }
+        Ok(resp)
+    }
+
+    fn resolve_name(&self, path: &str) -> String {
+        if path == "." || path.is_empty() {
+            self.current_prefix.clone()
+        } else if path.starts_with('/') {
+            path.trim_start_matches('/').to_string()
+        } else if self.current_prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}{}", self.current_prefix, path)
+        }
+    }
+
+    /// objects.list over all pages. Returns (objects, common prefixes).
+    async fn list_objects(&self, prefix: &str, delimited: bool, versions: bool) -> Result<(Vec<GcsObject>, Vec<String>), ProviderError> {
+        let mut objects = Vec::new();
+        let mut prefixes = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut url = format!("{}?prefix={}&maxResults=1000", self.bucket_url(), urlencoding::encode(prefix));
+            if delimited {
+                url.push_str("&delimiter=/");
+            }
+            if versions {
+                url.push_str("&versions=true");
+            }
+            if let Some(ref token) = page_token {
+                url.push_str(&format!("&pageToken={}", urlencoding::encode(token)));
+            }
+
+            let page: ObjectList = self.send_ok(self.client.get(&url), "List objects").await?
+                .json().await
+                .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+            objects.extend(page.items);
+            prefixes.extend(page.prefixes);
+
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => break,
+            }
+        }
+
+        Ok((objects, prefixes))
+    }
+
+    async fn object_metadata(&self, name: &str) -> Result<GcsObject, ProviderError> {
+        self.send_ok(self.client.get(self.object_url(name)), name).await?
+            .json().await
+            .map_err(|e| ProviderError::ParseError(e.to_string()))
+    }
+
+    /// Stream object content (optionally a specific generation) to a local file
+    async fn download_object(&self, name: &str, generation: Option<&str>, local_path: &str, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        let mut url = format!("{}?alt=media", self.object_url(name));
+        if let Some(generation) = generation {
+            url.push_str(&format!("&generation={}", urlencoding::encode(generation)));
+        }
+        let resp = self.send_ok(self.client.get(&url), name).await?;
+
+        let total_size = resp.content_length().unwrap_or(0);
+        let mut stream = resp.bytes_stream();
+        let mut file = tokio::fs::File::create(local_path).await
+            .map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+
+        let mut bytes_received: u64 = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+            file.write_all(&chunk).await
+                .map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+            bytes_received += chunk.len() as u64;
+            if let Some(ref cb) = progress {
+                cb(bytes_received, total_size);
+            }
+        }
+        file.flush().await
+            .map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Server-side copy via objects.rewrite, repeated until the service reports done
+    async fn rewrite(&self, src: &str, src_generation: Option<&str>, dest: &str) -> Result<(), ProviderError> {
+        let base = format!("{}/rewriteTo/b/{}/o/{}",
+            self.object_url(src), urlencoding::encode(&self.config.bucket), urlencoding::encode(dest));
+        let mut rewrite_token: Option<String> = None;
+
+        loop {
+            let mut params = Vec::new();
+            if let Some(generation) = src_generation {
+                params.push(format!("sourceGeneration={}", urlencoding::encode(generation)));
+            }
+            if let Some(ref token) = rewrite_token {
+                params.push(format!("rewriteToken={}", urlencoding::encode(token)));
+            }
+            let url = if params.is_empty() { base.clone() } else { format!("{}?{}", base, params.join("&")) };
+
+            let resp: RewriteResponse = self.send_ok(
+                self.client.post(&url).header(CONTENT_TYPE, "application/json").body("{}"),
+                src,
+            ).await?
+                .json().await
+                .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+
+            if resp.done {
+                return Ok(());
+            }
+            rewrite_token = resp.rewrite_token;
+            if rewrite_token.is_none() {
+                return Err(ProviderError::ServerError("Rewrite incomplete without continuation token".to_string()));
+            }
+            debug!("GCS rewrite of '{}' in progress, continuing", src);
+        }
+    }
+
+    /// Single-request media upload (files up to `RESUMABLE_THRESHOLD`)
+    async fn upload_simple(&self, local_path: &str, name: &str, file_len: u64, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        let file = tokio::fs::File::open(local_path).await.map_err(ProviderError::IoError)?;
+        let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
+
+        // Streaming bodies are not retryable at this level — the caller can retry the entire upload
+        let resp = self.client.post(self.upload_url("media", name))
+            .header(AUTHORIZATION, format!("Bearer {}", self.access_token))
+            .header(CONTENT_LENGTH, file_len)
+            .body(body)
+            .send().await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::TransferFailed(format!("Upload failed: {}", sanitize_api_error(&body))));
+        }
+
+        if let Some(ref cb) = progress {
+            cb(file_len, file_len);
+        }
+        Ok(())
+    }
+
+    /// GCS-003: Resumable upload session; each chunk is retried on transient errors
+    async fn upload_resumable(&self, local_path: &str, name: &str, file_len: u64, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        let resp = self.send_ok(
+            self.client.post(self.upload_url("resumable", name))
+                .header("X-Upload-Content-Length", file_len)
+                .header(CONTENT_LENGTH, 0),
+            "Start resumable upload",
+        ).await?;
+        let session_url = resp.headers().get(LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| ProviderError::ServerError("Resumable upload returned no session URL".to_string()))?;
+
+        let mut file = tokio::fs::File::open(local_path).await.map_err(ProviderError::IoError)?;
+        let mut offset: u64 = 0;
+
+        while offset < file_len {
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            let filled = read_full_async(&mut file, &mut buf).await?;
+            if filled == 0 {
+                return Err(ProviderError::TransferFailed("File shrank during upload".to_string()));
+            }
+            buf.truncate(filled);
+
+            let end = offset + filled as u64 - 1;
+            let resp = self.send(
+                self.client.put(&session_url)
+                    .header("Content-Range", format!("bytes {}-{}/{}", offset, end, file_len))
+                    .body(buf)
+            ).await?;
+
+            // 308 Resume Incomplete acknowledges an intermediate chunk
+            let status = resp.status().as_u16();
+            if status != 308 && !resp.status().is_success() {
+                let body = resp.text().await.unwrap_or_default();
+                return Err(ProviderError::TransferFailed(
+                    format!("Chunk at offset {} failed: {}", offset, sanitize_api_error(&body))
+                ));
+            }
+
+            offset = end + 1;
+            if let Some(ref cb) = progress {
+                cb(offset, file_len);
+            }
+        }
+
+        debug!("GCS resumable upload complete: {} ({} bytes)", name, file_len);
+        Ok(())
+    }
+
+    fn entry_from_object(obj: GcsObject) -> RemoteEntry {
+        let full = obj.name.trim_end_matches('/').to_string();
+        let mut metadata = HashMap::new();
+        if let Some(ref generation) = obj.generation {
+            metadata.insert("generation".to_string(), generation.clone());
+        }
+        // Composite objects carry only crc32c, which sync cannot compute cheaply
+        let content_hash = obj.md5_hash.as_deref()
+            .and_then(|h| ContentHash::base64(HashAlgorithm::Md5, h));
+        RemoteEntry {
+            name: full.rsplit('/').next().unwrap_or(&full).to_string(),
+            path: format!("/{}", full),
+            is_dir: false,
+            size: obj.size(),
+            modified: obj.updated,
+            permissions: None, owner: None, group: None,
+            is_symlink: false, link_target: None,
+            mime_type: obj.content_type,
+            metadata,
+            content_hash,
+        }
+    }
+}
+
+#[async_trait]
+impl StorageProvider for GcsProvider {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+
+    fn provider_type(&self) -> ProviderType { ProviderType::Gcs }
+
+    fn display_name(&self) -> String {
+        format!("GCS:{}", self.config.bucket)
+    }
+
+    fn account_email(&self) -> Option<String> {
+        Some(self.config.client_email.clone())
+    }
+
+    fn is_connected(&self) -> bool { self.connected }
+
+    async fn connect(&mut self) -> Result<(), ProviderError> {
+        if self.config.bucket.is_empty() {
+            return Err(ProviderError::InvalidConfig("GCS bucket name is empty".to_string()));
+        }
+        info!("GCS connect: account='{}', bucket='{}'", self.config.client_email, self.config.bucket);
+
+        self.fetch_token().await?;
+
+        // objects.list only needs object permissions (buckets.get would need more)
+        let url = format!("{}?maxResults=1", self.bucket_url());
+        self.send_ok(self.client.get(&url), &format!("Bucket '{}'", self.config.bucket)).await?;
+
+        self.connected = true;
+        self.current_prefix = String::new();
+        info!("Connected to Google Cloud Storage bucket {}", self.config.bucket);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ProviderError> {
+        self.connected = false;
+        self.access_token.clear();
+        Ok(())
+    }
+
+    async fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
+        self.ensure_token().await?;
+        let prefix = self.resolve_name(path);
+        let prefix = if prefix.is_empty() || prefix.ends_with('/') { prefix } else { format!("{}/", prefix) };
+
+        let (objects, prefixes) = self.list_objects(&prefix, true, false).await?;
+
+        let dirs = prefixes.into_iter().filter_map(|p| {
+            let full = p.trim_end_matches('/').to_string();
+            let name = full.strip_prefix(&prefix).unwrap_or(&full).to_string();
+            (!name.is_empty()).then(|| RemoteEntry::directory(name, format!("/{}", full)))
+        });
+        // The folder placeholder of the listed prefix itself ("dir/") is not a file
+        let files = objects.into_iter()
+            .filter(|o| o.name != prefix)
+            .map(Self::entry_from_object);
+
+        Ok(dirs.chain(files).collect())
+    }
+
+    async fn cd(&mut self, path: &str) -> Result<(), ProviderError> {
+        if path == ".." {
+            return self.cd_up().await;
+        }
+        if path == "/" {
+            self.current_prefix = String::new();
+            return Ok(());
+        }
+
+        let new_prefix = if path.starts_with('/') {
+            path.trim_start_matches('/').to_string()
+        } else if self.current_prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}{}/", self.current_prefix, path.trim_end_matches('/'))
+        };
+
+        self.current_prefix = if new_prefix.ends_with('/') || new_prefix.is_empty() {
+            new_prefix
+        } else {
+            format!("{}/", new_prefix)
+        };
+
+        Ok(())
+    }
+
+    async fn cd_up(&mut self) -> Result<(), ProviderError> {
+        let trimmed = self.current_prefix.trim_end_matches('/');
+        self.current_prefix = match trimmed.rfind('/') {
+            Some(pos) => format!("{}/", &trimmed[..pos]),
+            None => String::new(),
+        };
+        Ok(())
+    }
+
+    async fn pwd(&mut self) -> Result<String, ProviderError> {
+        if self.current_prefix.is_empty() {
+            Ok("/".to_string())
+        } else {
+            Ok(format!("/{}", self.current_prefix.trim_end_matches('/')))
+        }
+    }
+
+    async fn download(&mut self, remote_path: &str, local_path: &str, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_name(remote_path);
+        self.download_object(&name, None, local_path, progress).await
+    }
+
+    async fn download_to_bytes(&mut self, remote_path: &str) -> Result<Vec<u8>, ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_name(remote_path);
+        let url = format!("{}?alt=media", self.object_url(&name));
+        let resp = self.send_ok(self.client.get(&url), &name).await?;
+        super::response_bytes_with_limit(resp, super::MAX_DOWNLOAD_TO_BYTES).await
+    }
+
+    /// - Files <= 32 MB: single media upload (streaming)
+    /// - Files > 32 MB: resumable upload in 16 MB chunks
+    async fn upload(&mut self, local_path: &str, remote_path: &str, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_name(remote_path);
+        let file_len = tokio::fs::metadata(local_path).await
+            .map_err(ProviderError::IoError)?
+            .len();
+
+        if file_len > RESUMABLE_THRESHOLD {
+            self.upload_resumable(local_path, &name, file_len, progress).await
+        } else {
+            self.upload_simple(local_path, &name, file_len, progress).await
+        }
+    }
+
+    /// Create a zero-byte `dir/` placeholder, as the Cloud Console does
+    async fn mkdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let name = format!("{}/", self.resolve_name(path).trim_end_matches('/'));
+        self.send_ok(
+            self.client.post(self.upload_url("media", &name)).header(CONTENT_LENGTH, 0).body(Vec::new()),
+            "mkdir",
+        ).await?;
+        Ok(())
+    }
+
+    /// With Object Versioning enabled the object becomes a noncurrent version
+    async fn delete(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_name(path);
+        self.send_ok(self.client.delete(self.object_url(&name)), &name).await?;
+        Ok(())
+    }
+
+    async fn rmdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.rmdir_recursive(path).await
+    }
+
+    async fn rmdir_recursive(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let prefix = format!("{}/", self.resolve_name(path).trim_end_matches('/'));
+        let (objects, _) = self.list_objects(&prefix, false, false).await?;
+
+        for obj in objects {
+            match self.send_ok(self.client.delete(self.object_url(&obj.name)), &obj.name).await {
+                Ok(_) | Err(ProviderError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Rename via server-side rewrite + delete. Directories are renamed by
+    /// rewriting every object under the prefix (including the `dir/` placeholder).
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let from_name = self.resolve_name(from);
+        let to_name = self.resolve_name(to);
+
+        match self.object_metadata(&from_name).await {
+            Ok(_) => {
+                self.rewrite(&from_name, None, &to_name).await?;
+                self.send_ok(self.client.delete(self.object_url(&from_name)), &from_name).await?;
+                return Ok(());
+            }
+            Err(ProviderError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        let prefix = format!("{}/", from_name.trim_end_matches('/'));
+        let to_prefix = format!("{}/", to_name.trim_end_matches('/'));
+        let (objects, _) = self.list_objects(&prefix, false, false).await?;
+        if objects.is_empty() {
+            return Err(ProviderError::NotFound(from.to_string()));
+        }
+
+        // Copy everything first so a failure never leaves the directory half deleted
+        for obj in &objects {
+            let new_name = obj.name.replacen(&prefix, &to_prefix, 1);
+            self.rewrite(&obj.name, None, &new_name).await?;
+        }
+        for obj in &objects {
+            match self.send_ok(self.client.delete(self.object_url(&obj.name)), &obj.name).await {
+                Ok(_) | Err(ProviderError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        info!("Renamed directory (rewrite+delete {} objects) {} to {}", objects.len(), from, to);
+        Ok(())
+    }
+
+    async fn stat(&mut self, path: &str) -> Result<RemoteEntry, ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_name(path);
+        let obj = self.object_metadata(&name).await
+            .map_err(|e| match e {
+                ProviderError::NotFound(_) => ProviderError::NotFound(path.to_string()),
+                other => other,
+            })?;
+        Ok(Self::entry_from_object(obj))
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool, ProviderError> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(ProviderError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn size(&mut self, path: &str) -> Result<u64, ProviderError> {
+        Ok(self.stat(path).await?.size)
+    }
+
+    async fn keep_alive(&mut self) -> Result<(), ProviderError> {
+        self.ensure_token().await
+    }
+
+    async fn server_info(&mut self) -> Result<String, ProviderError> {
+        Ok(format!("Google Cloud Storage: gs://{} ({})", self.config.bucket, self.config.client_email))
+    }
+
+    fn supports_server_copy(&self) -> bool { true }
+
+    async fn server_copy(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let from_name = self.resolve_name(from);
+        let to_name = self.resolve_name(to);
+        self.rewrite(&from_name, None, &to_name).await
+    }
+
+    fn supports_checksum(&self) -> bool { true }
+
+    async fn checksum(&mut self, path: &str) -> Result<HashMap<String, String>, ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_name(path);
+        Ok(self.object_metadata(&name).await?.checksums())
+    }
+
+    fn transfer_optimization_hints(&self) -> super::TransferOptimizationHints {
+        super::TransferOptimizationHints {
+            supports_multipart: true,
+            multipart_threshold: RESUMABLE_THRESHOLD,
+            multipart_part_size: CHUNK_SIZE as u64,
+            supports_server_checksum: true,
+            preferred_checksum_algo: Some("md5".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn supports_versions(&self) -> bool { true }
+
+    /// Object generations, newest first. The version id is the generation number.
+    async fn list_versions(&mut self, path: &str) -> Result<Vec<FileVersion>, ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_name(path);
+        let (objects, _) = self.list_objects(&name, false, true).await?;
+
+        let mut generations: Vec<GcsObject> = objects.into_iter().filter(|o| o.name == name).collect();
+        generations.sort_by_key(|o| std::cmp::Reverse(o.generation()));
+        Ok(generations.into_iter()
+            .filter_map(|o| Some(FileVersion {
+                size: o.size(),
+                modified: o.updated,
+                id: o.generation?,
+                modified_by: None,
+            }))
+            .collect())
+    }
+
+    async fn download_version(&mut self, path: &str, version_id: &str, local_path: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_name(path);
+        self.download_object(&name, Some(version_id), local_path, None).await
+    }
+
+    /// Rewrite the old generation onto the live object name
+    async fn restore_version(&mut self, path: &str, version_id: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_name(path);
+        self.rewrite(&name, Some(version_id), &name).await?;
+        info!("Restored '{}' to generation '{}'", name, version_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_checksums() {
+        let obj: GcsObject = serde_json::from_str(r#"{
+            "name": "docs/a.txt", "size": "11", "generation": "1700000000000001",
+            "md5Hash": "XrY7u+Ae7tCTyyK7j1rNww==", "crc32c": "yZRlqg=="
+        }"#).unwrap();
+
+        assert_eq!(obj.size(), 11);
+        assert_eq!(obj.generation(), 1700000000000001);
+        let checksums = obj.checksums();
+        assert_eq!(checksums["md5"], "5eb63bbbe01eeed093cb22bb8f5acdc3");
+        assert_eq!(checksums["crc32c"], "c99465aa");
+    }
+
+    #[test]
+    fn test_jwt_rejects_invalid_key() {
+        let claims = serde_json::json!({"iss": "a@b"});
+        assert!(matches!(sign_jwt(&claims, "not a key"), Err(ProviderError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,46 @@
+//! Shell-style wildcard matching shared by providers: `~/.ssh/config` host
+//! patterns and `Include` globs, and client-side `find` on providers without
+//! a server-side search.
+
+/// Glob match supporting `*` (any run) and `?` (one character)
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+    pi == p.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.example.com", "a.example.com"));
+        assert!(wildcard_match("web-??", "web-01"));
+        assert!(!wildcard_match("web-??", "web-1"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("*.tar.*", "backup.tar.gz"));
+        assert!(!wildcard_match("*.txt", "notes.txt.bak"));
+    }
+}
@@ -11,7 +11,7 @@ use tracing::info;
 
 use super::{
     StorageProvider, ProviderType, ProviderError, RemoteEntry, ProviderConfig, StorageInfo,
-    sanitize_api_error,
+    ContentHash, HashAlgorithm, sanitize_api_error,
     oauth2::{OAuth2Manager, OAuthConfig, OAuthProvider},
 };
 
@@ -43,6 +43,9 @@ struct DriveFile {
     parents: Vec<String>,
     #[serde(default)]
     trashed: bool,
+    /// Binary files only; Google Workspace documents have no content hash
+    md5_checksum: Option<String>,
+    sha256_checksum: Option<String>,
 }
 
 /// Google Drive file list response
@@ -58,6 +61,10 @@ struct DriveFileList {
 pub struct GoogleDriveConfig {
     pub client_id: String,
     pub client_secret: String,
+    /// Outbound proxy (`None` connects directly)
+    pub proxy: Option<super::proxy::ProxyConfig>,
+    /// Account whose tokens to use (email); `None` takes a fresh sign-in
+    pub account: Option<String>,
 }
 
 impl GoogleDriveConfig {
@@ -65,6 +72,8 @@ impl GoogleDriveConfig {
         Self {
             client_id: client_id.to_string(),
             client_secret: client_secret.to_string(),
+            account: None,
+            proxy: super::proxy::global(),
         }
     }
 
@@ -75,7 +84,10 @@ impl GoogleDriveConfig {
         let client_secret = config.extra.get("client_secret")
             .ok_or_else(|| ProviderError::Other("Missing client_secret".to_string()))?;
 
-        Ok(Self::new(client_id, client_secret))
+        Ok(Self {
+            proxy: super::proxy::from_extra(&config.extra)?,
+            ..Self::new(client_id, client_secret)
+        })
     }
 }
 
@@ -97,13 +109,14 @@ pub struct GoogleDriveProvider {
 
 impl GoogleDriveProvider {
     pub fn new(config: GoogleDriveConfig) -> Self {
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
         Self {
             config,
             oauth_manager: OAuth2Manager::new(),
-            client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
-                .build()
-                .unwrap_or_default(),
+            client,
             connected: false,
             current_folder_id: "root".to_string(),
             current_path: "/".to_string(),
@@ -116,6 +129,7 @@ impl GoogleDriveProvider {
     /// Get OAuth config
     fn oauth_config(&self) -> OAuthConfig {
         OAuthConfig::google(&self.config.client_id, &self.config.client_secret)
+            .with_account(self.config.account.as_deref())
     }
 
     /// Get authorization header
@@ -128,7 +142,7 @@ impl GoogleDriveProvider {
 
     /// Check if authenticated
     pub fn is_authenticated(&self) -> bool {
-        self.oauth_manager.has_tokens(OAuthProvider::Google)
+        self.oauth_manager.has_tokens(OAuthProvider::Google, self.config.account.as_deref())
     }
 
     /// Start OAuth flow - returns URL to open (called via oauth2_start_auth command)
@@ -151,7 +165,7 @@ impl GoogleDriveProvider {
 
         loop {
             let mut url = format!(
-                "{}/files?q='{}'+in+parents+and+trashed=false&fields=files(id,name,mimeType,size,modifiedTime,parents),nextPageToken&pageSize=1000",
+                "{}/files?q='{}'+in+parents+and+trashed=false&fields=files(id,name,mimeType,size,modifiedTime,parents,md5Checksum,sha256Checksum),nextPageToken&pageSize=1000",
                 DRIVE_API_BASE, folder_id
             );
 
@@ -190,7 +204,7 @@ impl GoogleDriveProvider {
     #[allow(dead_code)]
     async fn get_file(&self, file_id: &str) -> Result<DriveFile, ProviderError> {
         let url = format!(
-            "{}/files/{}?fields=id,name,mimeType,size,modifiedTime,parents",
+            "{}/files/{}?fields=id,name,mimeType,size,modifiedTime,parents,md5Checksum,sha256Checksum",
             DRIVE_API_BASE, file_id
         );
 
@@ -218,7 +232,7 @@ impl GoogleDriveProvider {
         );
 
         let url = format!(
-            "{}/files?q={}&fields=files(id,name,mimeType,size,modifiedTime,parents)",
+            "{}/files?q={}&fields=files(id,name,mimeType,size,modifiedTime,parents,md5Checksum,sha256Checksum)",
             DRIVE_API_BASE, urlencoding::encode(&query)
         );
 
@@ -349,7 +363,7 @@ impl GoogleDriveProvider {
 
         loop {
             let mut url = format!(
-                "{}/files?q=trashed=true&fields=files(id,name,mimeType,size,modifiedTime,parents),nextPageToken&pageSize=1000",
+                "{}/files?q=trashed=true&fields=files(id,name,mimeType,size,modifiedTime,parents,md5Checksum,sha256Checksum),nextPageToken&pageSize=1000",
                 DRIVE_API_BASE
             );
 
@@ -484,6 +498,9 @@ impl GoogleDriveProvider {
 
         let mut metadata = HashMap::new();
         metadata.insert("id".to_string(), file.id.clone());
+        if let Some(parent) = file.parents.first() {
+            metadata.insert("parentId".to_string(), parent.clone());
+        }
         metadata.insert("mimeType".to_string(), file.mime_type.clone());
         if let Some((export_mime, ext)) = Self::workspace_export_info(&file.mime_type) {
             metadata.insert("exportMimeType".to_string(), export_mime.to_string());
@@ -504,6 +521,9 @@ impl GoogleDriveProvider {
             link_target: None,
             mime_type: Some(file.mime_type.clone()),
             metadata,
+            content_hash: file.sha256_checksum.as_deref()
+                .and_then(|h| ContentHash::hex(HashAlgorithm::Sha256, h))
+                .or_else(|| file.md5_checksum.as_deref().and_then(|h| ContentHash::hex(HashAlgorithm::Md5, h))),
         }
     }
 }
@@ -553,6 +573,7 @@ impl StorageProvider for GoogleDriveProvider {
             }
         }
 
+        self.config.account = self.oauth_manager.bind_account(&self.oauth_config(), self.account_email.as_deref());
         info!("Connected to Google Drive");
         Ok(())
     }
@@ -1121,6 +1142,15 @@ impl StorageProvider for GoogleDriveProvider {
         Ok(url)
     }
 
+    fn supports_checksum(&self) -> bool {
+        true
+    }
+
+    async fn checksum(&mut self, path: &str) -> Result<HashMap<String, String>, ProviderError> {
+        let entry = self.stat(path).await?;
+        Ok(entry.content_hash.map(|h| h.to_checksums()).unwrap_or_default())
+    }
+
     fn supports_server_copy(&self) -> bool {
         true
     }
@@ -1200,7 +1230,7 @@ impl StorageProvider for GoogleDriveProvider {
 
         loop {
             let mut url = format!(
-                "{}/files?q={}&fields=files(id,name,mimeType,size,modifiedTime,parents),nextPageToken&pageSize=200",
+                "{}/files?q={}&fields=files(id,name,mimeType,size,modifiedTime,parents,md5Checksum,sha256Checksum),nextPageToken&pageSize=200",
                 DRIVE_API_BASE, urlencoding::encode(&query)
             );
 
@@ -1593,7 +1623,7 @@ impl StorageProvider for GoogleDriveProvider {
 
         loop {
             let url = format!(
-                "{}/changes?pageToken={}&fields=changes(fileId,file(name,mimeType,trashed),removed,time),newStartPageToken,nextPageToken&pageSize=1000",
+                "{}/changes?pageToken={}&fields=changes(fileId,file(name,mimeType,trashed,size,modifiedTime,parents),removed,time),newStartPageToken,nextPageToken&pageSize=1000",
                 DRIVE_API_BASE, urlencoding::encode(&current_token)
             );
 
@@ -1615,6 +1645,10 @@ impl StorageProvider for GoogleDriveProvider {
                 name: Option<String>,
                 mime_type: Option<String>,
                 trashed: Option<bool>,
+                size: Option<String>,
+                modified_time: Option<String>,
+                #[serde(default)]
+                parents: Vec<String>,
             }
             #[derive(Deserialize)]
             #[serde(rename_all = "camelCase")]
@@ -1640,13 +1674,18 @@ impl StorageProvider for GoogleDriveProvider {
                     || change.file.as_ref().and_then(|f| f.trashed).unwrap_or(false);
                 let change_type = if removed { "deleted" } else { "modified" };
 
+                let file = change.file.as_ref();
                 all_changes.push(super::ChangeEntry {
                     file_id: change.file_id.clone().unwrap_or_default(),
-                    name: change.file.as_ref().and_then(|f| f.name.clone()).unwrap_or_default(),
+                    name: file.and_then(|f| f.name.clone()).unwrap_or_default(),
                     change_type: change_type.to_string(),
-                    mime_type: change.file.as_ref().and_then(|f| f.mime_type.clone()),
-                    timestamp: change.time.clone(),
+                    mime_type: file.and_then(|f| f.mime_type.clone()),
+                    timestamp: file.and_then(|f| f.modified_time.clone()).or_else(|| change.time.clone()),
                     removed,
+                    path: None,
+                    parent_id: file.and_then(|f| f.parents.first().cloned()),
+                    is_dir: file.and_then(|f| f.mime_type.as_deref()) == Some("application/vnd.google-apps.folder"),
+                    size: file.and_then(|f| f.size.as_deref()).and_then(|s| s.parse().ok()),
                 });
             }
 
@@ -361,7 +361,7 @@ const DIR_CACHE_MAX_ENTRIES: usize = 10_000;
 
 impl InternxtProvider {
     pub fn new(config: InternxtConfig) -> Self {
-        let client = reqwest::Client::builder()
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
             .timeout(std::time::Duration::from_secs(300))
             .connect_timeout(std::time::Duration::from_secs(30))
             .build()
@@ -1233,6 +1233,7 @@ impl StorageProvider for InternxtProvider {
                     link_target: None,
                     mime_type: None,
                     metadata: Default::default(),
+                    content_hash: None,
                 });
             }
 
@@ -1302,6 +1303,7 @@ impl StorageProvider for InternxtProvider {
                     link_target: None,
                     mime_type: None,
                     metadata: Default::default(),
+                    content_hash: None,
                 });
             }
 
@@ -1914,6 +1916,7 @@ impl StorageProvider for InternxtProvider {
                 link_target: None,
                 mime_type: None,
                 metadata: Default::default(),
+                content_hash: None,
             });
         }
 
@@ -2142,6 +2145,7 @@ impl InternxtProvider {
                         link_target: None,
                         mime_type: None,
                         metadata: Default::default(),
+                        content_hash: None,
                     });
                     page_count += 1;
                 }
@@ -2179,6 +2183,7 @@ impl InternxtProvider {
                         link_target: None,
                         mime_type: None,
                         metadata: Default::default(),
+                        content_hash: None,
                     });
                     page_count += 1;
                 }
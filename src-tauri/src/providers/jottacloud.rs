@@ -82,7 +82,7 @@ pub struct JottacloudProvider {
 
 impl JottacloudProvider {
     pub fn new(config: JottacloudConfig) -> Self {
-        let client = reqwest::Client::builder()
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
             .timeout(std::time::Duration::from_secs(300))
             .connect_timeout(std::time::Duration::from_secs(30))
             .build()
@@ -535,6 +535,7 @@ impl JottacloudProvider {
                                     link_target: None,
                                     metadata: HashMap::new(),
                                     mime_type: None,
+                                    content_hash: None,
                                 });
                             }
                             child_folder_depth = Some(depth);
@@ -599,6 +600,7 @@ impl JottacloudProvider {
                                 link_target: None,
                                 metadata: HashMap::new(),
                                 mime_type: None,
+                                content_hash: None,
                             });
                         }
                     }
@@ -639,6 +641,7 @@ impl JottacloudProvider {
                                     link_target: None,
                                     metadata,
                                     mime_type: if current_mime.is_empty() { None } else { Some(current_mime.clone()) },
+                                    content_hash: None,
                                 });
                             }
                         }
@@ -1104,6 +1107,7 @@ impl StorageProvider for JottacloudProvider {
                 link_target: None,
                 metadata: HashMap::new(),
                 mime_type: None,
+                content_hash: None,
             })
         } else {
             // Try to parse as file listing (parent folder containing the file)
@@ -1418,6 +1422,7 @@ impl JottacloudProvider {
                                     link_target: None,
                                     metadata: HashMap::new(),
                                     mime_type: None,
+                                    content_hash: None,
                                 });
                             }
                             child_folder_depth = Some(depth);
@@ -1464,6 +1469,7 @@ impl JottacloudProvider {
                                 link_target: None,
                                 metadata: HashMap::new(),
                                 mime_type: None,
+                                content_hash: None,
                             });
                         }
                     }
@@ -1495,6 +1501,7 @@ impl JottacloudProvider {
                                     link_target: None,
                                     metadata: HashMap::new(),
                                     mime_type: None,
+                                    content_hash: None,
                                 });
                             }
                         }
@@ -69,11 +69,27 @@ struct KDriveFile {
     size: Option<i64>,
     #[serde(rename = "last_modified_at")]
     last_modified: Option<i64>, // Unix timestamp
-    #[allow(dead_code)]
     #[serde(default)]
     path: Option<String>,
 }
 
+/// Cursor listing page (`/files/{id}/listing` and `/files/listing/continue`)
+#[derive(Debug, Deserialize)]
+struct ListingPayload {
+    #[serde(default)]
+    actions: Vec<ListingAction>,
+    cursor: Option<String>,
+    has_more: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListingAction {
+    action: String,
+    file_id: i64,
+    parent_id: Option<i64>,
+    file: Option<KDriveFile>,
+}
+
 #[derive(Debug, Deserialize)]
 struct DriveInfo {
     #[allow(dead_code)]
@@ -165,7 +181,7 @@ pub struct KDriveProvider {
 
 impl KDriveProvider {
     pub fn new(config: KDriveConfig) -> Self {
-        let client = reqwest::Client::builder()
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
             .timeout(std::time::Duration::from_secs(300))
             .connect_timeout(std::time::Duration::from_secs(30))
             .build()
@@ -203,6 +219,25 @@ impl KDriveProvider {
             ))
     }
 
+    /// Fetch one page of a cursor listing
+    async fn listing_page(&self, url: &str) -> Result<ListingPayload, ProviderError> {
+        let resp = self.get_with_retry(url).await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::ServerError(format!(
+                "Listing failed ({}): {}", status, sanitize_api_error(&body)
+            )));
+        }
+
+        let api_resp: ApiResponse<ListingPayload> = resp.json().await.map_err(|e| {
+            ProviderError::ServerError(format!("Parse listing response failed: {}", e))
+        })?;
+        api_resp.data
+            .ok_or_else(|| ProviderError::ServerError("Listing response without data".to_string()))
+    }
+
     /// KD-004: Send a GET request with automatic retry on 429/5xx
     async fn get_with_retry(&self, url: &str) -> Result<reqwest::Response, ProviderError> {
         let request = self.client.get(url)
@@ -623,8 +658,12 @@ impl StorageProvider for KDriveProvider {
                     group: None,
                     is_symlink: false,
                     link_target: None,
-                    metadata: HashMap::new(),
+                    metadata: HashMap::from([
+                        ("id".to_string(), file.id.to_string()),
+                        ("parentId".to_string(), folder_id.to_string()),
+                    ]),
                     mime_type: None,
+                    content_hash: None,
                 });
             }
 
@@ -937,6 +976,7 @@ impl StorageProvider for KDriveProvider {
             link_target: None,
             metadata: HashMap::new(),
             mime_type: None,
+            content_hash: None,
         })
     }
 
@@ -1083,6 +1123,7 @@ impl StorageProvider for KDriveProvider {
                     link_target: None,
                     metadata: HashMap::new(),
                     mime_type: None,
+                    content_hash: None,
                 });
             }
 
@@ -1283,6 +1324,64 @@ impl StorageProvider for KDriveProvider {
         Ok(())
     }
 
+    fn supports_change_tracking(&self) -> bool {
+        true
+    }
+
+    async fn get_change_token(&mut self) -> Result<String, ProviderError> {
+        // The listing cursor only turns into a change cursor once the snapshot listing is exhausted
+        let mut url = self.api_url_v3(&format!("/files/{}/listing?depth=unlimited&limit=1000", self.root_file_id));
+        loop {
+            let page = self.listing_page(&url).await?;
+            let cursor = page.cursor
+                .ok_or_else(|| ProviderError::ServerError("Listing response without cursor".to_string()))?;
+            if page.has_more != Some(true) {
+                return Ok(cursor);
+            }
+            url = self.api_url_v3(&format!("/files/listing/continue?cursor={}", urlencoding::encode(&cursor)));
+        }
+    }
+
+    async fn list_changes(&mut self, page_token: &str) -> Result<(Vec<super::ChangeEntry>, String), ProviderError> {
+        let mut changes = Vec::new();
+        let mut cursor = page_token.to_string();
+
+        loop {
+            let url = self.api_url_v3(&format!("/files/listing/continue?cursor={}&limit=1000", urlencoding::encode(&cursor)));
+            let page = self.listing_page(&url).await?;
+
+            for action in page.actions {
+                let removed = matches!(action.action.as_str(), "file_delete" | "file_trash" | "file_move_out");
+                let file = action.file;
+                let is_dir = file.as_ref().and_then(|f| f.file_type.as_deref()) == Some("dir");
+                changes.push(super::ChangeEntry {
+                    file_id: action.file_id.to_string(),
+                    name: file.as_ref().and_then(|f| f.name.clone()).unwrap_or_default(),
+                    change_type: if removed { "deleted" } else { "modified" }.to_string(),
+                    mime_type: None,
+                    timestamp: file.as_ref().and_then(|f| f.last_modified).map(|ts| {
+                        chrono::DateTime::from_timestamp(ts, 0)
+                            .unwrap_or_default()
+                            .format("%Y-%m-%d %H:%M:%S")
+                            .to_string()
+                    }),
+                    removed,
+                    path: if removed { None } else { file.as_ref().and_then(|f| f.path.clone()) },
+                    parent_id: action.parent_id.map(|id| id.to_string()),
+                    is_dir,
+                    size: if removed || is_dir { None } else { file.as_ref().and_then(|f| f.size).map(|s| s.max(0) as u64) },
+                });
+            }
+
+            if let Some(next) = page.cursor {
+                cursor = next;
+            }
+            if page.has_more != Some(true) {
+                return Ok((changes, cursor));
+            }
+        }
+    }
+
     // ─── KD-010: Trash management via kDrive trash API ────────────────
 
     // Note: StorageProvider trait does not define list_trash/restore_from_trash/permanent_delete
@@ -1,40 +1,507 @@
-//! MEGA Storage Provider - MEGAcmd Implementation
+//! MEGA Storage Provider - Native API Implementation
 //!
-//! Uses official MEGAcmd CLI for reliable MEGA.nz integration
-//! Requires MEGAcmd to be installed: https://mega.nz/cmd
-//! On Ubuntu/Debian: sudo apt install megacmd
-
+//! Talks to the MEGA JSON API directly, no MEGAcmd daemon required.
+//! Covers the password key derivation (v1 AES rounds / v2 PBKDF2-SHA512),
+//! RSA session id decryption, node-tree decryption and AES-128-CTR transfers
+//! with chunk MAC verification.
+
+use aes::Aes128;
+use aes::cipher::{
+    BlockDecrypt, BlockDecryptMut, BlockEncrypt, BlockEncryptMut, KeyInit, KeyIvInit, StreamCipher,
+};
+use aes::cipher::block_padding::NoPadding;
+use aes::cipher::generic_array::GenericArray;
 use async_trait::async_trait;
-use tokio::process::Command;
-use tokio::io::AsyncWriteExt;
+use base64::Engine;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use futures_util::StreamExt;
+use num_bigint::BigUint;
 use secrecy::ExposeSecret;
+use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 use super::{
     StorageProvider, ProviderError, ProviderType, RemoteEntry, MegaConfig, StorageInfo,
 };
 
-/// Timeout for all MEGAcmd commands (seconds).
-const MEGA_CMD_TIMEOUT_SECS: u64 = 60;
+/// MEGA API endpoint (commands are POSTed as a JSON array)
+const API_URL: &str = "https://g.api.mega.co.nz/cs";
+
+/// Timeout for a single API command (seconds).
+const API_TIMEOUT_SECS: u64 = 60;
+
+/// Maximum number of retries when the API answers EAGAIN.
+const MAX_RETRIES: usize = 5;
+
+/// Initial delay between EAGAIN retries, doubled each attempt (milliseconds).
+const RETRY_DELAY_MS: u64 = 500;
+
+/// Target size of one upload POST; pieces always end on a MEGA chunk boundary.
+const UPLOAD_PIECE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// The node tree is refetched on `list()` once it is older than this (seconds).
+const NODE_CACHE_TTL_SECS: u64 = 30;
+
+/// Path under which the rubbish bin is exposed
+const TRASH_PATH: &str = "/Rubbish";
+
+/// Password key seed for v1 (pre-2020) accounts
+const V1_KEY_SEED: [u8; 16] = [
+    0x93, 0xC4, 0x67, 0xE3, 0x7D, 0xB0, 0xC7, 0xA4,
+    0xD1, 0xBE, 0x3F, 0x81, 0x01, 0x52, 0xCB, 0x56,
+];
+
+/// PBKDF2 iterations for v2 accounts
+const V2_ITERATIONS: u32 = 100_000;
+
+// Node types as reported by the API
+const NODE_FILE: u8 = 0;
+const NODE_FOLDER: u8 = 1;
+const NODE_ROOT: u8 = 2;
+const NODE_RUBBISH: u8 = 4;
+
+// API error codes handled specially
+const EAGAIN: i64 = -3;
+const ESID: i64 = -15;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+type Aes128CbcEnc = cbc::Encryptor<Aes128>;
+type Aes128CbcDec = cbc::Decryptor<Aes128>;
+
+/// URL-safe base64 without padding; decoding tolerates padding MEGA sometimes appends
+const B64: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::URL_SAFE,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+fn b64_decode(s: &str) -> Result<Vec<u8>, ProviderError> {
+    let normalized: String = s.trim().chars().map(|c| match c {
+        '+' => '-',
+        '/' => '_',
+        c => c,
+    }).collect();
+    B64.decode(normalized.trim_end_matches('='))
+        .map_err(|e| ProviderError::ParseError(format!("Invalid base64 from MEGA: {}", e)))
+}
+
+fn to_key(bytes: &[u8]) -> Result<[u8; 16], ProviderError> {
+    bytes.get(..16)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| ProviderError::ParseError("MEGA key is too short".to_string()))
+}
+
+/// AES-128-ECB over whole blocks (trailing partial block is ignored)
+fn ecb_encrypt(key: &[u8; 16], data: &[u8]) -> Vec<u8> {
+    let cipher = Aes128::new(key.into());
+    let mut out = data.to_vec();
+    for block in out.chunks_exact_mut(16) {
+        cipher.encrypt_block(GenericArray::from_mut_slice(block));
+    }
+    out
+}
+
+fn ecb_decrypt(key: &[u8; 16], data: &[u8]) -> Vec<u8> {
+    let cipher = Aes128::new(key.into());
+    let mut out = data.to_vec();
+    for block in out.chunks_exact_mut(16) {
+        cipher.decrypt_block(GenericArray::from_mut_slice(block));
+    }
+    out
+}
+
+/// v1 password key: 65536 rounds of AES over the seed, keyed by each 16-byte
+/// block of the zero-padded password
+fn prepare_key_v1(password: &[u8]) -> [u8; 16] {
+    let mut padded = password.to_vec();
+    padded.resize(password.len().div_ceil(16) * 16, 0);
+    let ciphers: Vec<Aes128> = padded.chunks_exact(16)
+        .map(|k| Aes128::new(GenericArray::from_slice(k)))
+        .collect();
+
+    let mut block = GenericArray::from(V1_KEY_SEED);
+    for _ in 0..0x10000 {
+        for cipher in &ciphers {
+            cipher.encrypt_block(&mut block);
+        }
+    }
+    block.into()
+}
+
+/// v1 login hash: email folded into 16 bytes, encrypted 16384 times with the password key
+fn user_hash_v1(email: &str, password_key: &[u8; 16]) -> String {
+    let mut hash = [0u8; 16];
+    for (i, b) in email.bytes().enumerate() {
+        hash[i % 16] ^= b;
+    }
+    let cipher = Aes128::new(password_key.into());
+    let mut block = GenericArray::from(hash);
+    for _ in 0..0x4000 {
+        cipher.encrypt_block(&mut block);
+    }
+    let mut out = [0u8; 8];
+    out[..4].copy_from_slice(&block[..4]);
+    out[4..].copy_from_slice(&block[8..12]);
+    B64.encode(out)
+}
+
+/// v2 derivation: PBKDF2-SHA512 → (password key, login hash)
+fn derive_key_v2(password: &[u8], salt: &[u8]) -> ([u8; 16], String) {
+    let mut derived = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha512>(password, salt, V2_ITERATIONS, &mut derived);
+    let mut key = [0u8; 16];
+    key.copy_from_slice(&derived[..16]);
+    (key, B64.encode(&derived[16..]))
+}
+
+/// Parse `count` MPIs (16-bit big-endian bit length followed by the value)
+fn read_mpis(data: &[u8], count: usize) -> Result<Vec<BigUint>, ProviderError> {
+    let mut out = Vec::with_capacity(count);
+    let mut pos = 0;
+    for _ in 0..count {
+        let header = data.get(pos..pos + 2)
+            .ok_or_else(|| ProviderError::ParseError("Truncated MPI".to_string()))?;
+        let len = (u16::from_be_bytes([header[0], header[1]]) as usize).div_ceil(8);
+        let value = data.get(pos + 2..pos + 2 + len)
+            .ok_or_else(|| ProviderError::ParseError("Truncated MPI".to_string()))?;
+        out.push(BigUint::from_bytes_be(value));
+        pos += 2 + len;
+    }
+    Ok(out)
+}
+
+/// Decrypt `csid` with the account's RSA private key (p, q, d, u MPIs).
+/// The session id is the first 43 bytes of the plaintext.
+fn decrypt_session_id(private_key: &[u8], csid: &[u8]) -> Result<String, ProviderError> {
+    let key = read_mpis(private_key, 4)?;
+    let (p, q, d) = (&key[0], &key[1], &key[2]);
+    let ciphertext = read_mpis(csid, 1)?.remove(0);
+    let plain = ciphertext.modpow(d, &(p * q)).to_bytes_be();
+    if plain.len() < 43 {
+        return Err(ProviderError::AuthenticationFailed("MEGA returned an invalid session".to_string()));
+    }
+    Ok(B64.encode(&plain[..43]))
+}
+
+/// Split a 32-byte file key into (AES key, CTR nonce, meta MAC)
+fn unpack_file_key(k: &[u8]) -> ([u8; 16], [u8; 8], [u8; 8]) {
+    let mut key = [0u8; 16];
+    for i in 0..16 {
+        key[i] = k[i] ^ k[i + 16];
+    }
+    let mut nonce = [0u8; 8];
+    nonce.copy_from_slice(&k[16..24]);
+    let mut mac = [0u8; 8];
+    mac.copy_from_slice(&k[24..32]);
+    (key, nonce, mac)
+}
+
+fn pack_file_key(key: &[u8; 16], nonce: &[u8; 8], mac: &[u8; 8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[16..24].copy_from_slice(nonce);
+    out[24..32].copy_from_slice(mac);
+    for i in 0..16 {
+        out[i] = key[i] ^ out[i + 16];
+    }
+    out
+}
+
+/// Node attributes are `MEGA{json}`, zero-padded and AES-CBC encrypted with a zero IV
+fn encrypt_attrs(key: &[u8; 16], attrs: &Value) -> String {
+    let mut buf = format!("MEGA{}", attrs).into_bytes();
+    let len = buf.len();
+    buf.resize(len.div_ceil(16) * 16, 0);
+    let padded_len = buf.len();
+    let _ = Aes128CbcEnc::new(key.into(), &[0u8; 16].into())
+        .encrypt_padded_mut::<NoPadding>(&mut buf, padded_len);
+    B64.encode(&buf)
+}
+
+fn decrypt_attrs(key: &[u8; 16], data: &[u8]) -> Option<Value> {
+    if data.is_empty() || !data.len().is_multiple_of(16) {
+        return None;
+    }
+    let mut buf = data.to_vec();
+    let plain = Aes128CbcDec::new(key.into(), &[0u8; 16].into())
+        .decrypt_padded_mut::<NoPadding>(&mut buf)
+        .ok()?;
+    let end = plain.iter().rposition(|&b| b != 0).map(|i| i + 1).unwrap_or(0);
+    let json = plain[..end].strip_prefix(b"MEGA")?;
+    serde_json::from_slice(json).ok()
+}
+
+/// Size of MEGA chunk `index`: 128 KiB, 256 KiB, ... up to 1 MiB, then 1 MiB each
+fn chunk_size(index: u64) -> u64 {
+    (index.min(7) + 1) * 128 * 1024
+}
+
+/// Incremental CBC-MAC over MEGA chunks, condensed into the 8-byte meta MAC
+/// stored in the file key.
+struct ChunkMac {
+    cipher: Aes128,
+    nonce: [u8; 8],
+    chunk_mac: [u8; 16],
+    file_mac: [u8; 16],
+    pending: [u8; 16],
+    pending_len: usize,
+    chunk_left: u64,
+    chunk_index: u64,
+}
+
+impl ChunkMac {
+    fn new(key: &[u8; 16], nonce: &[u8; 8]) -> Self {
+        Self {
+            cipher: Aes128::new(key.into()),
+            nonce: *nonce,
+            chunk_mac: [0; 16],
+            file_mac: [0; 16],
+            pending: [0; 16],
+            pending_len: 0,
+            chunk_left: 0,
+            chunk_index: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.chunk_left == 0 {
+                self.chunk_mac[..8].copy_from_slice(&self.nonce);
+                self.chunk_mac[8..].copy_from_slice(&self.nonce);
+                self.chunk_left = chunk_size(self.chunk_index);
+            }
+            let take = (16 - self.pending_len)
+                .min(data.len())
+                .min(self.chunk_left as usize);
+            self.pending[self.pending_len..self.pending_len + take].copy_from_slice(&data[..take]);
+            self.pending_len += take;
+            self.chunk_left -= take as u64;
+            data = &data[take..];
+
+            if self.pending_len == 16 {
+                self.absorb_block();
+            }
+            if self.chunk_left == 0 {
+                self.finish_chunk();
+            }
+        }
+    }
+
+    fn absorb_block(&mut self) {
+        let mut block = GenericArray::from(self.chunk_mac);
+        for (b, p) in block.iter_mut().zip(self.pending.iter()) {
+            *b ^= p;
+        }
+        self.cipher.encrypt_block(&mut block);
+        self.chunk_mac = block.into();
+        self.pending = [0; 16];
+        self.pending_len = 0;
+    }
+
+    fn finish_chunk(&mut self) {
+        let mut block = GenericArray::from(self.file_mac);
+        for (b, m) in block.iter_mut().zip(self.chunk_mac.iter()) {
+            *b ^= m;
+        }
+        self.cipher.encrypt_block(&mut block);
+        self.file_mac = block.into();
+        self.chunk_index += 1;
+    }
+
+    fn finish(mut self) -> [u8; 8] {
+        if self.chunk_left > 0 {
+            if self.pending_len > 0 {
+                self.absorb_block();
+            }
+            self.finish_chunk();
+        }
+        let m = self.file_mac;
+        let mut out = [0u8; 8];
+        for i in 0..4 {
+            out[i] = m[i] ^ m[i + 4];
+            out[i + 4] = m[i + 8] ^ m[i + 12];
+        }
+        out
+    }
+}
+
+/// CTR IV: 8-byte nonce followed by a 64-bit block counter starting at 0
+fn ctr_cipher(key: &[u8; 16], nonce: &[u8; 8]) -> Aes128Ctr {
+    let mut iv = [0u8; 16];
+    iv[..8].copy_from_slice(nonce);
+    Aes128Ctr::new(key.into(), &iv.into())
+}
+
+/// Map a negative API result to a typed error
+fn mega_error(code: i64) -> ProviderError {
+    match code {
+        -2 => ProviderError::InvalidPath("MEGA rejected the request arguments (EARGS)".to_string()),
+        -9 => ProviderError::NotFound("MEGA object not found (ENOENT)".to_string()),
+        -11 => ProviderError::PermissionDenied("MEGA access denied (EACCESS)".to_string()),
+        -12 => ProviderError::AlreadyExists("MEGA object already exists (EEXIST)".to_string()),
+        -14 => ProviderError::ServerError("MEGA key decryption failed (EKEY)".to_string()),
+        ESID => ProviderError::AuthenticationFailed("MEGA session expired (ESID)".to_string()),
+        -16 => ProviderError::PermissionDenied("MEGA account or object is blocked (EBLOCKED)".to_string()),
+        -17 => ProviderError::ServerError("Storage or transfer quota exceeded (EOVERQUOTA)".to_string()),
+        -18 => ProviderError::ServerError("MEGA resource temporarily unavailable (ETEMPUNAVAIL)".to_string()),
+        -26 => ProviderError::AuthenticationFailed(
+            "Two-factor authentication is enabled on this MEGA account and is not supported yet".to_string()
+        ),
+        other => ProviderError::ServerError(format!("MEGA API error {}", other)),
+    }
+}
+
+/// Parse `https://mega.nz/file/<handle>#<key>` and the legacy `#!<handle>!<key>` form
+fn parse_file_link(link: &str) -> Result<(String, Vec<u8>), ProviderError> {
+    let link = link.trim();
+    let (handle, key) = if let Some(rest) = link.split("/file/").nth(1) {
+        rest.split_once('#').unwrap_or((rest, ""))
+    } else if let Some(rest) = link.split("#!").nth(1) {
+        rest.split_once('!').unwrap_or((rest, ""))
+    } else if link.contains("/folder/") || link.contains("#F!") {
+        return Err(ProviderError::NotSupported("Importing MEGA folder links is not supported".to_string()));
+    } else {
+        return Err(ProviderError::InvalidPath(format!("Not a MEGA file link: {}", link)));
+    };
+    let handle = handle.split(['/', '?']).next().unwrap_or_default().to_string();
+    let key = b64_decode(key.split('/').next().unwrap_or_default())?;
+    if handle.is_empty() || key.len() != 32 {
+        return Err(ProviderError::InvalidPath("MEGA link is missing its decryption key".to_string()));
+    }
+    Ok((handle, key))
+}
+
+/// Sleep long enough to keep `bytes` since `start` under `limit_kb` KB/s (0 = unlimited)
+async fn throttle(start: Instant, bytes: u64, limit_kb: u64) {
+    if limit_kb == 0 {
+        return;
+    }
+    let expected = Duration::from_secs_f64(bytes as f64 / (limit_kb as f64 * 1024.0));
+    let elapsed = start.elapsed();
+    if expected > elapsed {
+        tokio::time::sleep(expected - elapsed).await;
+    }
+}
+
+/// Decrypted node from the account tree
+#[derive(Debug, Clone)]
+struct MegaNode {
+    handle: String,
+    parent: String,
+    kind: u8,
+    name: String,
+    size: u64,
+    timestamp: i64,
+    /// Decrypted node key: 16 bytes for folders, 32 for files
+    key: Vec<u8>,
+}
+
+impl MegaNode {
+    fn is_dir(&self) -> bool {
+        self.kind != NODE_FILE
+    }
+
+    /// AES key used for attributes (and content, for files)
+    fn aes_key(&self) -> [u8; 16] {
+        if self.key.len() == 32 {
+            unpack_file_key(&self.key).0
+        } else {
+            to_key(&self.key).unwrap_or_default()
+        }
+    }
+}
+
+/// Logged-in session state
+struct MegaSession {
+    sid: String,
+    master_key: [u8; 16],
+    user_handle: String,
+    /// Keys of folders we share (outgoing shares / exported folders)
+    share_keys: HashMap<String, [u8; 16]>,
+}
+
+impl MegaSession {
+    /// Decrypt a raw node from `f` / `p` responses; nodes whose key we cannot
+    /// unwrap (e.g. incoming shares) are skipped.
+    fn decrypt_node(&self, raw: &Value) -> Option<MegaNode> {
+        let handle = raw["h"].as_str()?.to_string();
+        let kind = raw["t"].as_u64()? as u8;
+        let parent = raw["p"].as_str().unwrap_or_default().to_string();
+        let timestamp = raw["ts"].as_i64().unwrap_or(0);
+
+        if kind >= NODE_ROOT {
+            let name = match kind {
+                NODE_ROOT => "Cloud Drive",
+                NODE_RUBBISH => "Rubbish Bin",
+                _ => "Inbox",
+            };
+            return Some(MegaNode { handle, parent, kind, name: name.to_string(), size: 0, timestamp, key: Vec::new() });
+        }
+
+        let key = raw["k"].as_str()?.split('/').find_map(|part| {
+            let (owner, enc) = part.split_once(':')?;
+            let wrapping = if owner == self.user_handle {
+                self.master_key
+            } else {
+                *self.share_keys.get(owner)?
+            };
+            let enc = b64_decode(enc).ok()?;
+            let expected = if kind == NODE_FILE { 32 } else { 16 };
+            (enc.len() == expected).then(|| ecb_decrypt(&wrapping, &enc))
+        })?;
 
-/// Maximum number of automatic retries for transient errors.
-const MAX_RETRIES: usize = 2;
+        let mut node = MegaNode {
+            handle,
+            parent,
+            kind,
+            name: String::new(),
+            size: raw["s"].as_u64().unwrap_or(0),
+            timestamp,
+            key,
+        };
+        let attrs = decrypt_attrs(&node.aes_key(), &b64_decode(raw["a"].as_str()?).ok()?)?;
+        node.name = attrs["n"].as_str()?.to_string();
+        Some(node)
+    }
 
-/// Delay between retries (milliseconds).
-const RETRY_DELAY_MS: u64 = 2000;
+    fn wrap_key(&self, key: &[u8]) -> String {
+        B64.encode(ecb_encrypt(&self.master_key, key))
+    }
+}
 
 pub struct MegaProvider {
     config: MegaConfig,
+    client: reqwest::Client,
     connected: bool,
     current_path: String,
+    session: Option<MegaSession>,
+    nodes: HashMap<String, MegaNode>,
+    nodes_fetched: Option<Instant>,
+    seq: u64,
+    /// (upload, download) KB/s, 0 = unlimited
+    speed_limit: (u64, u64),
 }
 
 impl MegaProvider {
     pub fn new(config: MegaConfig) -> Self {
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
+            .connect_timeout(Duration::from_secs(30))
+            .read_timeout(Duration::from_secs(120))
+            .build()
+            .unwrap_or_default();
         Self {
             config,
+            client,
             connected: false,
             current_path: "/".to_string(),
+            session: None,
+            nodes: HashMap::new(),
+            nodes_fetched: None,
+            seq: rand::random::<u32>() as u64,
+            speed_limit: (0, 0),
         }
     }
 
@@ -43,228 +510,170 @@ impl MegaProvider {
         tracing::debug!(target: "mega", "{}", msg);
     }
 
-    /// Resolve MEGAcmd executable path (checks PATH and common install locations)
-    fn resolve_mega_cmd(cmd: &str) -> String {
-        #[cfg(windows)]
-        {
-            let program_files = std::env::var("ProgramFiles")
-                .unwrap_or_else(|_| r"C:\Program Files".to_string());
-            let local_appdata = std::env::var("LOCALAPPDATA").unwrap_or_default();
-            let candidates = [
-                format!(r"{}\MEGAcmd\{}.bat", program_files, cmd),
-                format!(r"{}\MEGAcmd\{}.exe", program_files, cmd),
-                format!(r"{}\MEGAcmd\{}.bat", local_appdata, cmd),
-            ];
-            for candidate in &candidates {
-                if std::path::Path::new(candidate).exists() {
-                    return candidate.clone();
-                }
-            }
-        }
-        #[cfg(target_os = "macos")]
-        {
-            // ARCH-05: Check macOS install locations
-            let candidates = [
-                format!("/Applications/MEGAcmd.app/Contents/MacOS/{}", cmd),
-                format!("/usr/local/bin/{}", cmd),
-                format!("/opt/homebrew/bin/{}", cmd),
-            ];
-            for candidate in &candidates {
-                if std::path::Path::new(candidate).exists() {
-                    return candidate.clone();
-                }
-            }
-        }
-        cmd.to_string()
-    }
-
-    /// Classify MEGAcmd stderr into typed ProviderError (ERR-01).
-    fn classify_mega_error(stderr: &str) -> ProviderError {
-        let lower = stderr.to_lowercase();
-        if lower.contains("not logged in") || lower.contains("not logged-in") || lower.contains("login required") {
-            ProviderError::AuthenticationFailed(format!("MEGAcmd: {}", stderr))
-        } else if lower.contains("not found") || lower.contains("no such file") || lower.contains("couldn't find") {
-            ProviderError::NotFound(stderr.to_string())
-        } else if lower.contains("over quota") || lower.contains("storage quota") || lower.contains("over storage") {
-            ProviderError::ServerError("Storage quota exceeded".to_string())
-        } else if lower.contains("permission denied") || lower.contains("access denied") {
-            ProviderError::PermissionDenied(stderr.to_string())
-        } else if lower.contains("already exists") {
-            ProviderError::AlreadyExists(stderr.to_string())
-        } else if lower.contains("not empty") {
-            ProviderError::DirectoryNotEmpty(stderr.to_string())
-        } else if lower.contains("temporarily unavailable") || lower.contains("try again") || lower.contains("server busy") {
-            ProviderError::ServerError(format!("Transient error: {}", stderr))
-        } else {
-            ProviderError::ServerError(stderr.to_string())
-        }
+    fn session(&self) -> Result<&MegaSession, ProviderError> {
+        self.session.as_ref().ok_or(ProviderError::NotConnected)
     }
 
-    /// Check if an error is transient and worth retrying (ERR-02).
-    fn is_transient_error(stderr: &str) -> bool {
-        let lower = stderr.to_lowercase();
-        lower.contains("temporarily unavailable")
-            || lower.contains("try again")
-            || lower.contains("server busy")
-            || lower.contains("connection reset")
-            || lower.contains("timed out")
-    }
-
-    /// Helper to run mega-* commands with timeout, error classification, and retry (ARCH-04, ERR-01, ERR-02).
-    async fn run_mega_cmd(&self, cmd: &str, args: &[&str]) -> Result<String, ProviderError> {
-        self.log_debug(&format!("[CMD] {} {:?}", cmd, args));
-        let resolved_cmd = Self::resolve_mega_cmd(cmd);
-
-        let mut last_err = ProviderError::Unknown("No attempts made".to_string());
+    /// Send one API command, retrying on EAGAIN with exponential backoff.
+    async fn api_raw(&mut self, cmd: &Value) -> Result<Value, ProviderError> {
+        let mut delay = RETRY_DELAY_MS;
 
         for attempt in 0..=MAX_RETRIES {
             if attempt > 0 {
-                tracing::debug!(target: "mega", "[RETRY] attempt {}/{} for {} {:?}", attempt, MAX_RETRIES, cmd, args);
-                tokio::time::sleep(std::time::Duration::from_millis(RETRY_DELAY_MS)).await;
+                tracing::debug!(target: "mega", "[RETRY] attempt {}/{} for {}", attempt, MAX_RETRIES, cmd["a"]);
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                delay *= 2;
             }
 
-            let output_future = Command::new(&resolved_cmd)
-                .args(args)
-                .output();
-
-            let output = match tokio::time::timeout(
-                std::time::Duration::from_secs(MEGA_CMD_TIMEOUT_SECS),
-                output_future,
-            ).await {
-                Ok(Ok(output)) => output,
-                Ok(Err(e)) => {
-                    let err = format!("Failed to execute {} (resolved: {}): {}", cmd, resolved_cmd, e);
-                    self.log_debug(&format!("[CMD ERROR] {}", err));
-                    return Err(ProviderError::ServerError(err));
-                }
-                Err(_) => {
-                    self.log_debug(&format!("[CMD TIMEOUT] {} exceeded {}s", cmd, MEGA_CMD_TIMEOUT_SECS));
-                    return Err(ProviderError::Timeout);
-                }
+            self.seq += 1;
+            // Session ids are URL-safe base64, so no escaping is needed
+            let url = match &self.session {
+                Some(session) => format!("{}?id={}&sid={}", API_URL, self.seq, session.sid),
+                None => format!("{}?id={}", API_URL, self.seq),
             };
-
-            if output.status.success() {
-                let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-                return Ok(stdout);
+            let resp = self.client.post(&url)
+                .timeout(Duration::from_secs(API_TIMEOUT_SECS))
+                .json(&[cmd])
+                .send().await.map_err(|e| {
+                if e.is_timeout() { ProviderError::Timeout } else { ProviderError::NetworkError(e.to_string()) }
+            })?;
+            if resp.status().is_server_error() {
+                continue;
             }
+            let body: Value = resp.json().await
+                .map_err(|e| ProviderError::ParseError(format!("Invalid MEGA API response: {}", e)))?;
 
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let err_msg = stderr.trim().to_string();
-            let final_err = if err_msg.is_empty() {
-                "Unknown MEGAcmd error".to_string()
-            } else {
-                err_msg
+            let result = match body {
+                Value::Array(mut items) if !items.is_empty() => items.swap_remove(0),
+                other => other,
             };
-            self.log_debug(&format!("[CMD FAILURE] {}", final_err));
-
-            // ERR-02: Retry on transient errors
-            if Self::is_transient_error(&final_err) && attempt < MAX_RETRIES {
-                last_err = Self::classify_mega_error(&final_err);
-                continue;
+            match result.as_i64() {
+                Some(EAGAIN) => continue,
+                Some(code) if code < 0 => return Err(mega_error(code)),
+                _ => return Ok(result),
             }
-
-            return Err(Self::classify_mega_error(&final_err));
         }
 
-        Err(last_err)
+        Err(ProviderError::ServerError("MEGA API is busy (EAGAIN), try again later".to_string()))
     }
 
-    /// Run a mega command with automatic re-auth on session expiry (ERR-04).
-    /// Use this for operational commands (not for login/logout themselves).
-    async fn run_mega_cmd_with_reauth(&mut self, cmd: &str, args: &[&str]) -> Result<String, ProviderError> {
-        match self.run_mega_cmd(cmd, args).await {
-            Ok(out) => Ok(out),
-            Err(ProviderError::AuthenticationFailed(_)) => {
+    /// Send an API command with automatic re-login on session expiry.
+    async fn api(&mut self, cmd: Value) -> Result<Value, ProviderError> {
+        match self.api_raw(&cmd).await {
+            Err(ProviderError::AuthenticationFailed(_)) if self.session.is_some() => {
                 tracing::info!(target: "mega", "[REAUTH] Session expired, re-authenticating...");
                 self.do_login().await?;
-                // Retry the original command once after re-auth
-                self.run_mega_cmd(cmd, args).await
+                self.api_raw(&cmd).await
             }
-            Err(e) => Err(e),
+            other => other,
         }
     }
 
-    /// Internal login logic — used by connect() and re-auth (ARCH-03/AUTH-01: password via stdin).
+    /// Log in with email/password and establish an API session.
     async fn do_login(&mut self) -> Result<(), ProviderError> {
-        let password = self.config.password.expose_secret().to_string();
-        let resolved_cmd = Self::resolve_mega_cmd("mega-login");
-
-        let login_future = async {
-            let mut child = Command::new(&resolved_cmd)
-                .arg(&self.config.email)
-                .stdin(std::process::Stdio::piped())
-                .stdout(std::process::Stdio::piped())
-                .stderr(std::process::Stdio::piped())
-                .spawn()
-                .map_err(|e| ProviderError::ConnectionFailed(
-                    format!("Failed to spawn mega-login: {}", e)
-                ))?;
-
-            if let Some(mut stdin) = child.stdin.take() {
-                stdin.write_all(password.as_bytes()).await.map_err(|e| {
-                    ProviderError::ConnectionFailed(format!("Failed to write password to stdin: {}", e))
-                })?;
-                stdin.write_all(b"\n").await.ok();
-                drop(stdin);
+        self.session = None;
+        let email = self.config.email.trim().to_lowercase();
+
+        let prelogin = self.api_raw(&json!({"a": "us0", "user": email})).await?;
+        let version = prelogin["v"].as_i64().unwrap_or(1);
+        let salt = match prelogin["s"].as_str() {
+            Some(s) if version >= 2 => b64_decode(s)?,
+            _ => Vec::new(),
+        };
+
+        // Key derivation is CPU-bound (65536 AES rounds or 100k PBKDF2 iterations)
+        let password = self.config.password.expose_secret().as_bytes().to_vec();
+        let email_for_hash = email.clone();
+        let (password_key, user_hash) = tokio::task::spawn_blocking(move || {
+            if version >= 2 {
+                derive_key_v2(&password, &salt)
+            } else {
+                let key = prepare_key_v1(&password);
+                let hash = user_hash_v1(&email_for_hash, &key);
+                (key, hash)
             }
+        }).await.map_err(|e| ProviderError::Other(format!("Key derivation failed: {}", e)))?;
 
-            let output = child.wait_with_output().await.map_err(|e| {
-                ProviderError::ConnectionFailed(format!("mega-login wait failed: {}", e))
+        let login = self.api_raw(&json!({"a": "us", "user": email, "uh": user_hash})).await
+            .map_err(|e| match e {
+                ProviderError::NotFound(_) => ProviderError::AuthenticationFailed("Invalid MEGA email or password".to_string()),
+                other => other,
             })?;
 
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                let err_msg = stderr.trim();
-                return Err(ProviderError::AuthenticationFailed(
-                    format!("MEGAcmd Login Failed: {}", if err_msg.is_empty() { "Unknown error" } else { err_msg })
-                ));
+        let encrypted_master = b64_decode(login["k"].as_str().unwrap_or_default())?;
+        let master_key = to_key(&ecb_decrypt(&password_key, &encrypted_master))?;
+
+        let sid = if let Some(csid) = login["csid"].as_str() {
+            let private_key = ecb_decrypt(&master_key, &b64_decode(login["privk"].as_str().unwrap_or_default())?);
+            let csid = b64_decode(csid)?;
+            tokio::task::spawn_blocking(move || decrypt_session_id(&private_key, &csid))
+                .await
+                .map_err(|e| ProviderError::Other(format!("Session decryption failed: {}", e)))??
+        } else if let Some(tsid) = login["tsid"].as_str() {
+            // Ephemeral accounts: the second half must be the first half under the master key
+            let raw = b64_decode(tsid)?;
+            if raw.len() < 32 || ecb_encrypt(&master_key, &raw[..16]) != raw[raw.len() - 16..] {
+                return Err(ProviderError::AuthenticationFailed("Invalid MEGA email or password".to_string()));
             }
-
-            Ok(())
+            tsid.to_string()
+        } else {
+            return Err(ProviderError::AuthenticationFailed("MEGA login returned no session".to_string()));
         };
 
-        match tokio::time::timeout(
-            std::time::Duration::from_secs(MEGA_CMD_TIMEOUT_SECS),
-            login_future,
-        ).await {
-            Ok(result) => result,
-            Err(_) => Err(ProviderError::Timeout),
-        }
-    }
-
-    /// Try to start MEGAcmd daemon if not running (ARCH-02).
-    async fn ensure_daemon_running(&self) -> Result<(), ProviderError> {
-        // Try mega-version as a connectivity check
-        match self.run_mega_cmd("mega-version", &[]).await {
-            Ok(_) => Ok(()),
-            Err(ProviderError::ServerError(ref e)) if e.contains("Failed to execute") => {
-                // Binary not found — daemon can't be started
-                Err(ProviderError::ConnectionFailed(
-                    "MEGAcmd is not installed. Install it from https://mega.nz/cmd".to_string()
-                ))
-            }
-            Err(_) => {
-                // Daemon might not be running — try to start it
-                tracing::info!(target: "mega", "[DAEMON] Attempting to start MEGAcmd daemon...");
-                let server_cmd = Self::resolve_mega_cmd("mega-cmd-server");
-                let _ = Command::new(&server_cmd)
-                    .stdin(std::process::Stdio::null())
-                    .stdout(std::process::Stdio::null())
-                    .stderr(std::process::Stdio::null())
-                    .spawn();
-
-                // Give daemon time to start
-                tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-
-                // Verify daemon is now accessible
-                match self.run_mega_cmd("mega-version", &[]).await {
-                    Ok(_) => Ok(()),
-                    Err(_) => Err(ProviderError::ConnectionFailed(
-                        "MEGAcmd daemon failed to start. Ensure MEGAcmd is installed: https://mega.nz/cmd".to_string()
-                    )),
+        self.session = Some(MegaSession {
+            sid,
+            master_key,
+            user_handle: String::new(),
+            share_keys: HashMap::new(),
+        });
+
+        let user = self.api_raw(&json!({"a": "ug"})).await?;
+        if let Some(session) = self.session.as_mut() {
+            session.user_handle = user["u"].as_str().unwrap_or_default().to_string();
+        }
+        Ok(())
+    }
+
+    /// Fetch and decrypt the whole node tree.
+    async fn refresh_nodes(&mut self) -> Result<(), ProviderError> {
+        let res = self.api(json!({"a": "f", "c": 1, "r": 1})).await?;
+        let session = self.session.as_mut().ok_or(ProviderError::NotConnected)?;
+
+        for share in res["ok"].as_array().into_iter().flatten() {
+            if let (Some(h), Some(k)) = (share["h"].as_str(), share["k"].as_str()) {
+                if let Ok(key) = b64_decode(k).and_then(|k| to_key(&ecb_decrypt(&session.master_key, &k))) {
+                    session.share_keys.insert(h.to_string(), key);
                 }
             }
         }
+
+        let raw_nodes = res["f"].as_array().cloned().unwrap_or_default();
+        let session = self.session()?;
+        let mut nodes = HashMap::with_capacity(raw_nodes.len());
+        let mut skipped = 0;
+        for raw in &raw_nodes {
+            match session.decrypt_node(raw) {
+                Some(node) => { nodes.insert(node.handle.clone(), node); }
+                None => skipped += 1,
+            }
+        }
+        if skipped > 0 {
+            self.log_debug(&format!("[NODES] Skipped {} nodes without a usable key", skipped));
+        }
+
+        self.nodes = nodes;
+        self.nodes_fetched = Some(Instant::now());
+        Ok(())
+    }
+
+    async fn refresh_if_stale(&mut self) -> Result<(), ProviderError> {
+        let stale = self.nodes_fetched
+            .map(|t| t.elapsed() > Duration::from_secs(NODE_CACHE_TTL_SECS))
+            .unwrap_or(true);
+        if stale {
+            self.refresh_nodes().await?;
+        }
+        Ok(())
     }
 
     /// Helper to resolve path relative to current_path
@@ -279,56 +688,243 @@ impl MegaProvider {
         }
     }
 
-    /// Parse a single mega-ls -l output line into a RemoteEntry (CQ-01: defensive parsing).
-    fn parse_ls_line(line: &str, parent_path: &str) -> Option<RemoteEntry> {
-        // Skip header and section lines
-        if line.contains("FLAGS") && line.contains("VERS") { return None; }
-        if line.starts_with('/') && line.ends_with(':') { return None; }
+    fn special_root(&self, kind: u8) -> Option<&MegaNode> {
+        self.nodes.values().find(|n| n.kind == kind)
+    }
+
+    fn children<'a>(&'a self, parent: &str) -> impl Iterator<Item = &'a MegaNode> + 'a {
+        let parent = parent.to_string();
+        self.nodes.values().filter(move |n| n.parent == parent)
+    }
 
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        // CQ-01: Require minimum 6 columns (flags, vers, size, date, time, name)
-        if parts.len() < 6 {
-            if !line.trim().is_empty() {
-                tracing::debug!(target: "mega", "[PARSE] Skipping unparseable line ({} columns): {:?}", parts.len(), line);
-            }
-            return None;
+    fn child(&self, parent: &str, name: &str) -> Option<&MegaNode> {
+        self.children(parent).find(|n| n.name == name)
+    }
+
+    /// Resolve an absolute path to a node of the Cloud Drive tree
+    fn find_node(&self, path: &str) -> Option<&MegaNode> {
+        let mut node = self.special_root(NODE_ROOT)?;
+        for segment in path.split('/').filter(|s| !s.is_empty() && *s != ".") {
+            node = self.child(&node.handle, segment)?;
         }
+        Some(node)
+    }
 
-        let flags = parts[0];
-        let size_str = parts[2];
-        let date_str = parts[3];
-        let time_str = parts[4];
+    fn node_at(&self, path: &str) -> Result<MegaNode, ProviderError> {
+        self.find_node(path)
+            .cloned()
+            .ok_or_else(|| ProviderError::NotFound(format!("Path not found: {}", path)))
+    }
 
-        let name = parts[5..].join(" ");
+    /// Split an absolute path into (parent folder node, file name)
+    fn parent_and_name(&self, path: &str) -> Result<(MegaNode, String), ProviderError> {
+        let trimmed = path.trim_end_matches('/');
+        let (parent, name) = trimmed.rsplit_once('/').unwrap_or(("", trimmed));
         if name.is_empty() {
-            tracing::debug!(target: "mega", "[PARSE] Skipping line with empty name: {:?}", line);
-            return None;
+            return Err(ProviderError::InvalidPath(format!("Invalid path: {}", path)));
         }
+        let parent = self.node_at(if parent.is_empty() { "/" } else { parent })?;
+        if !parent.is_dir() {
+            return Err(ProviderError::InvalidPath(format!("Not a directory: {}", parent.name)));
+        }
+        Ok((parent, name.to_string()))
+    }
 
-        let is_dir = flags.starts_with('d');
-        let size = size_str.parse::<u64>().unwrap_or(0);
-        let modified = format!("{} {}", date_str, time_str);
-
-        let full_path = if parent_path == "/" {
-            format!("/{}", name)
-        } else {
-            format!("{}/{}", parent_path.trim_end_matches('/'), name)
-        };
-
-        Some(RemoteEntry {
-            name,
-            path: full_path,
-            is_dir,
-            size,
-            modified: Some(modified),
+    fn to_entry(node: &MegaNode, path: String) -> RemoteEntry {
+        let mut metadata = HashMap::new();
+        metadata.insert("handle".to_string(), node.handle.clone());
+        RemoteEntry {
+            name: node.name.clone(),
+            path,
+            is_dir: node.is_dir(),
+            size: node.size,
+            modified: chrono::DateTime::from_timestamp(node.timestamp, 0)
+                .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string()),
             is_symlink: false,
             link_target: None,
             permissions: None,
             owner: None,
             group: None,
             mime_type: None,
-            metadata: Default::default(),
-        })
+            metadata,
+            content_hash: None,
+        }
+    }
+
+    fn join(parent: &str, name: &str) -> String {
+        format!("{}/{}", parent.trim_end_matches('/'), name)
+    }
+
+    /// Remove a node and everything below it from the cache
+    fn forget_subtree(&mut self, handle: &str) {
+        let mut stack = vec![handle.to_string()];
+        while let Some(h) = stack.pop() {
+            stack.extend(self.children(&h).map(|n| n.handle.clone()));
+            self.nodes.remove(&h);
+        }
+    }
+
+    /// Create nodes under `parent` ("p" command) and cache the ones returned.
+    async fn put_nodes(&mut self, parent: &str, nodes: Value) -> Result<Vec<MegaNode>, ProviderError> {
+        let res = self.api(json!({"a": "p", "t": parent, "n": nodes})).await?;
+        let session = self.session()?;
+        let created: Vec<MegaNode> = res["f"].as_array().into_iter().flatten()
+            .filter_map(|raw| session.decrypt_node(raw))
+            .collect();
+        for node in &created {
+            self.nodes.insert(node.handle.clone(), node.clone());
+        }
+        Ok(created)
+    }
+
+    async fn create_folder(&mut self, parent: &str, name: &str) -> Result<MegaNode, ProviderError> {
+        let key: [u8; 16] = rand::random();
+        let session = self.session()?;
+        let node = json!([{
+            "h": "xxxxxxxx",
+            "t": NODE_FOLDER,
+            "a": encrypt_attrs(&key, &json!({"n": name})),
+            "k": session.wrap_key(&key),
+        }]);
+        self.put_nodes(parent, node).await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ProviderError::ServerError("MEGA did not return the new folder".to_string()))
+    }
+
+    async fn set_name(&mut self, node: &MegaNode, name: &str) -> Result<(), ProviderError> {
+        let session = self.session()?;
+        let cmd = json!({
+            "a": "a",
+            "n": node.handle,
+            "attr": encrypt_attrs(&node.aes_key(), &json!({"n": name})),
+            "key": session.wrap_key(&node.key),
+        });
+        self.api(cmd).await?;
+        if let Some(cached) = self.nodes.get_mut(&node.handle) {
+            cached.name = name.to_string();
+        }
+        Ok(())
+    }
+
+    async fn move_handle(&mut self, handle: &str, target: &str) -> Result<(), ProviderError> {
+        self.api(json!({"a": "m", "n": handle, "t": target})).await?;
+        if let Some(cached) = self.nodes.get_mut(handle) {
+            cached.parent = target.to_string();
+        }
+        Ok(())
+    }
+
+    /// Move `node` to `dest`: into it when `dest` is an existing folder,
+    /// otherwise to its parent under the new name.
+    async fn move_node(&mut self, node: &MegaNode, dest: &str) -> Result<(), ProviderError> {
+        if let Some(folder) = self.find_node(dest).filter(|n| n.is_dir()).cloned() {
+            if folder.handle != node.parent {
+                self.move_handle(&node.handle, &folder.handle).await?;
+            }
+            return Ok(());
+        }
+        let (parent, name) = self.parent_and_name(dest)?;
+        if parent.handle != node.parent {
+            self.move_handle(&node.handle, &parent.handle).await?;
+        }
+        if name != node.name {
+            self.set_name(node, &name).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete_node(&mut self, node: &MegaNode) -> Result<(), ProviderError> {
+        self.api(json!({"a": "d", "n": node.handle})).await?;
+        self.forget_subtree(&node.handle);
+        Ok(())
+    }
+
+    /// Stream a file, decrypt it into `out` and verify its MAC.
+    async fn fetch_into<W: AsyncWrite + Unpin + Send>(
+        &mut self,
+        node: &MegaNode,
+        out: &mut W,
+        progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        let res = self.api(json!({"a": "g", "g": 1, "ssl": 2, "n": node.handle})).await?;
+        if let Some(code) = res["e"].as_i64().filter(|c| *c < 0) {
+            return Err(mega_error(code));
+        }
+        let url = res["g"].as_str()
+            .ok_or_else(|| ProviderError::ServerError("MEGA returned no download URL".to_string()))?
+            .to_string();
+        let total = res["s"].as_u64().unwrap_or(node.size);
+
+        let (key, nonce, expected_mac) = unpack_file_key(&node.key);
+        let mut cipher = ctr_cipher(&key, &nonce);
+        let mut mac = ChunkMac::new(&key, &nonce);
+
+        let resp = self.client.get(&url).send().await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Err(ProviderError::TransferFailed(format!("Download failed: HTTP {}", resp.status())));
+        }
+
+        let start = Instant::now();
+        let mut transferred: u64 = 0;
+        let mut stream = resp.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let mut buf = chunk.map_err(|e| ProviderError::TransferFailed(e.to_string()))?.to_vec();
+            cipher.apply_keystream(&mut buf);
+            mac.update(&buf);
+            out.write_all(&buf).await.map_err(ProviderError::IoError)?;
+            transferred += buf.len() as u64;
+            if let Some(ref cb) = progress {
+                cb(transferred, total);
+            }
+            throttle(start, transferred, self.speed_limit.1).await;
+        }
+        out.flush().await.map_err(ProviderError::IoError)?;
+
+        if transferred != total {
+            return Err(ProviderError::TransferFailed(format!(
+                "Download truncated: {} of {} bytes", transferred, total
+            )));
+        }
+        if mac.finish() != expected_mac {
+            return Err(ProviderError::TransferFailed(
+                "MEGA integrity check failed (MAC mismatch)".to_string()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Find the folder-link key for `node`, sharing it with the "EXP" pseudo-user
+    /// first if it is not already shared.
+    async fn ensure_folder_share(&mut self, node: &MegaNode) -> Result<[u8; 16], ProviderError> {
+        if let Some(key) = self.session()?.share_keys.get(&node.handle) {
+            return Ok(*key);
+        }
+        let share_key: [u8; 16] = rand::random();
+        let session = self.session()?;
+        let handle_auth = format!("{0}{0}", node.handle);
+        let cmd = json!({
+            "a": "s2",
+            "n": node.handle,
+            "s": [{"u": "EXP", "r": 0}],
+            "ok": session.wrap_key(&share_key),
+            "ha": session.wrap_key(handle_auth.as_bytes()),
+            "cr": [[node.handle], [node.handle], [0, 0, B64.encode(ecb_encrypt(&share_key, &node.key))]],
+        });
+        self.api(cmd).await?;
+        if let Some(session) = self.session.as_mut() {
+            session.share_keys.insert(node.handle.clone(), share_key);
+        }
+        Ok(share_key)
+    }
+
+    fn trash_entry(&self, name: &str) -> Result<MegaNode, ProviderError> {
+        let rubbish = self.special_root(NODE_RUBBISH)
+            .ok_or_else(|| ProviderError::NotFound("Rubbish bin not found".to_string()))?;
+        self.child(&rubbish.handle, name.trim_start_matches('/'))
+            .cloned()
+            .ok_or_else(|| ProviderError::NotFound(format!("Not in rubbish bin: {}", name)))
     }
 }
 
@@ -347,31 +943,11 @@ impl StorageProvider for MegaProvider {
     async fn connect(&mut self) -> Result<(), ProviderError> {
         self.current_path = "/".to_string();
 
-        // ARCH-01: Check MEGAcmd installation and ARCH-02: ensure daemon is running
-        self.ensure_daemon_running().await?;
-
-        tracing::info!("[MEGAcmd] Logging in as {}...", self.config.email);
-
-        // AUTH-03/AUTH-05: Check if already logged in as this user (exact match)
-        if let Ok(whoami) = self.run_mega_cmd("mega-whoami", &[]).await {
-            let whoami_trimmed = whoami.trim();
-            if whoami_trimmed == self.config.email || whoami_trimmed.ends_with(&format!(" {}", self.config.email)) {
-                tracing::info!("[MEGAcmd] Already logged in (Existing Session).");
-                self.connected = true;
-                return Ok(());
-            }
-            // If logged in as someone else, logout
-            tracing::info!("[MEGAcmd] Active session mismatch. Logging out...");
-            if let Err(e) = self.run_mega_cmd("mega-logout", &[]).await {
-                // AUTH-04: Log warning on logout failure
-                tracing::warn!("[MEGAcmd] Logout failed during session switch: {}", e);
-            }
-        }
-
-        // ARCH-03/AUTH-01: Password via stdin pipe (not CLI argument)
+        tracing::info!("[MEGA] Logging in as {}...", self.config.email);
         self.do_login().await?;
+        self.refresh_nodes().await?;
 
-        tracing::info!("[MEGAcmd] Login successful.");
+        tracing::info!("[MEGA] Login successful ({} nodes).", self.nodes.len());
         self.connected = true;
         Ok(())
     }
@@ -379,18 +955,16 @@ impl StorageProvider for MegaProvider {
     async fn disconnect(&mut self) -> Result<(), ProviderError> {
         self.current_path = "/".to_string();
 
-        let should_logout = self.config.logout_on_disconnect.unwrap_or(false);
-
-        if should_logout {
-            tracing::info!("[MEGAcmd] Disconnecting and killing session (logout_on_disconnect=true)");
-            if let Err(e) = self.run_mega_cmd("mega-logout", &[]).await {
-                tracing::warn!("[MEGAcmd] Logout on disconnect failed: {}", e);
+        if self.config.logout_on_disconnect.unwrap_or(false) && self.session.is_some() {
+            tracing::info!("[MEGA] Disconnecting and killing session (logout_on_disconnect=true)");
+            if let Err(e) = self.api_raw(&json!({"a": "sml"})).await {
+                tracing::warn!("[MEGA] Logout on disconnect failed: {}", e);
             }
-        } else {
-            tracing::info!("[MEGAcmd] Disconnecting but KEEPING session active (logout_on_disconnect=false)");
-            // AUTH-05/AUTH-22: MEGAcmd daemon handles session persistence internally.
         }
 
+        self.session = None;
+        self.nodes.clear();
+        self.nodes_fetched = None;
         self.connected = false;
         Ok(())
     }
@@ -401,23 +975,18 @@ impl StorageProvider for MegaProvider {
 
     async fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
         let target_path = self.resolve_path(path);
+        tracing::debug!("[MEGA] Listing path: {}", target_path);
 
-        tracing::debug!("[MEGAcmd] Listing path: {}", target_path);
-
-        let output = self.run_mega_cmd_with_reauth("mega-ls", &["-l", &target_path]).await
-            .map_err(|e| match e {
-                ProviderError::NotFound(_) => ProviderError::NotFound(format!("Path not found: {}", target_path)),
-                other => other,
-            })?;
-
-        let mut entries = Vec::new();
-
-        for line in output.lines() {
-            if let Some(entry) = Self::parse_ls_line(line, &target_path) {
-                entries.push(entry);
-            }
+        self.refresh_if_stale().await?;
+        let dir = self.node_at(&target_path)?;
+        if !dir.is_dir() {
+            return Err(ProviderError::InvalidPath(format!("Not a directory: {}", target_path)));
         }
 
+        let mut entries: Vec<RemoteEntry> = self.children(&dir.handle)
+            .map(|n| Self::to_entry(n, Self::join(&target_path, &n.name)))
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
         Ok(entries)
     }
 
@@ -440,16 +1009,13 @@ impl StorageProvider for MegaProvider {
             self.resolve_path(path)
         };
 
-        tracing::debug!("[MEGAcmd] CD verifying path: {}", new_path);
-
-        self.run_mega_cmd_with_reauth("mega-ls", &[&new_path]).await
-            .map_err(|e| match e {
-                ProviderError::NotFound(_) => ProviderError::NotFound(format!("Invalid directory: {}", new_path)),
-                other => other,
-            })?;
-
-        self.current_path = new_path;
-        Ok(())
+        match self.find_node(&new_path) {
+            Some(node) if node.is_dir() => {
+                self.current_path = new_path;
+                Ok(())
+            }
+            _ => Err(ProviderError::NotFound(format!("Invalid directory: {}", new_path))),
+        }
     }
 
     async fn cd_up(&mut self) -> Result<(), ProviderError> {
@@ -458,139 +1024,176 @@ impl StorageProvider for MegaProvider {
 
     async fn download(&mut self, r: &str, l: &str, progress: Option<Box<dyn Fn(u64,u64)+Send>>) -> Result<(), ProviderError> {
         let abs_remote = self.resolve_path(r);
-        self.log_debug(&format!("[MEGAcmd] Downloading '{}' to '{}'", abs_remote, l));
+        self.log_debug(&format!("[MEGA] Downloading '{}' to '{}'", abs_remote, l));
 
-        // XFER-08: Signal start
-        if let Some(ref cb) = progress {
-            cb(0, 0);
-        }
-
-        // XFER-03: Add --resume flag for resume support
-        match self.run_mega_cmd_with_reauth("mega-get", &["--resume", &abs_remote, l]).await {
-            Ok(out) => {
-                self.log_debug(&format!("[MEGAcmd] Download output: {}", out));
-                // XFER-02: Signal completion with file size
-                if let Some(ref cb) = progress {
-                    match std::fs::metadata(l) {
-                        Ok(meta) => cb(meta.len(), meta.len()),
-                        Err(_) => cb(1, 1),
-                    }
-                }
-                Ok(())
-            },
-            Err(e) => {
-                self.log_debug(&format!("[MEGAcmd] Download ERROR: {}", e));
-                Err(e)
-            }
+        let node = self.node_at(&abs_remote)?;
+        if node.is_dir() {
+            return Err(ProviderError::InvalidPath(format!("Is a directory: {}", abs_remote)));
         }
+
+        let mut file = tokio::fs::File::create(l).await.map_err(ProviderError::IoError)?;
+        let result = self.fetch_into(&node, &mut file, progress).await;
+        if result.is_err() {
+            drop(file);
+            let _ = tokio::fs::remove_file(l).await;
+        }
+        result
     }
 
     async fn download_to_bytes(&mut self, r: &str) -> Result<Vec<u8>, ProviderError> {
-        // XFER-01: Implement via temp file
         let abs_remote = self.resolve_path(r);
-        let file_name = Path::new(&abs_remote)
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| format!("mega_tmp_{}", uuid::Uuid::new_v4()));
-
-        let temp_dir = std::env::temp_dir();
-        let temp_path = temp_dir.join(format!("aeroftp_mega_{}", file_name));
-        let temp_str = temp_path.to_string_lossy().to_string();
+        let node = self.node_at(&abs_remote)?;
 
-        self.run_mega_cmd_with_reauth("mega-get", &[&abs_remote, &temp_str]).await
-            .map_err(|e| ProviderError::TransferFailed(format!("Download to bytes failed: {}", e)))?;
-
-        // H2: Check file size before reading to prevent OOM
+        // H2: Check file size before downloading to prevent OOM
         let limit = super::MAX_DOWNLOAD_TO_BYTES;
-        let metadata = tokio::fs::metadata(&temp_path).await.map_err(ProviderError::IoError)?;
-        if metadata.len() > limit {
-            let _ = tokio::fs::remove_file(&temp_path).await;
+        if node.size > limit {
             return Err(ProviderError::TransferFailed(format!(
                 "File too large for in-memory download ({:.1} MB). Use streaming download for files over {:.0} MB.",
-                metadata.len() as f64 / 1_048_576.0,
+                node.size as f64 / 1_048_576.0,
                 limit as f64 / 1_048_576.0,
             )));
         }
 
-        let bytes = tokio::fs::read(&temp_path).await.map_err(|e| {
-            ProviderError::IoError(e)
-        })?;
-
-        // Clean up temp file
-        if let Err(e) = tokio::fs::remove_file(&temp_path).await {
-            tracing::debug!(target: "mega", "[CLEANUP] Failed to remove temp file {:?}: {}", temp_path, e);
-        }
-
+        let mut bytes = Vec::with_capacity(node.size as usize);
+        self.fetch_into(&node, &mut bytes, None).await?;
         Ok(bytes)
     }
 
     async fn upload(&mut self, l: &str, r: &str, progress: Option<Box<dyn Fn(u64,u64)+Send>>) -> Result<(), ProviderError> {
-        // XFER-04/CQ-09: Resolve remote path
         let abs_remote = self.resolve_path(r);
+        let (parent, name) = self.parent_and_name(&abs_remote)?;
+        let existing = self.child(&parent.handle, &name).cloned();
+        if existing.as_ref().is_some_and(|n| n.is_dir()) {
+            return Err(ProviderError::AlreadyExists(format!("A folder named {} exists", name)));
+        }
+
+        let mut file = tokio::fs::File::open(l).await.map_err(ProviderError::IoError)?;
+        let size = file.metadata().await.map_err(ProviderError::IoError)?.len();
+
+        let res = self.api(json!({"a": "u", "s": size, "ssl": 2})).await?;
+        let url = res["p"].as_str()
+            .ok_or_else(|| ProviderError::ServerError("MEGA returned no upload URL".to_string()))?
+            .to_string();
+
+        let key: [u8; 16] = rand::random();
+        let nonce: [u8; 8] = rand::random();
+        let mut cipher = ctr_cipher(&key, &nonce);
+        let mut mac = ChunkMac::new(&key, &nonce);
 
-        // XFER-02: Signal start
         if let Some(ref cb) = progress {
-            cb(0, 0);
+            cb(0, size);
         }
 
-        self.run_mega_cmd_with_reauth("mega-put", &[l, &abs_remote]).await
-            .map_err(|e| ProviderError::TransferFailed(format!("Upload failed: {}", e)))?;
+        let start = Instant::now();
+        let mut offset: u64 = 0;
+        let mut chunk_index: u64 = 0;
+        let mut completion = String::new();
+        loop {
+            // Group whole MEGA chunks into one POST
+            let mut len: u64 = 0;
+            while len < UPLOAD_PIECE_SIZE && offset + len < size {
+                len += chunk_size(chunk_index);
+                chunk_index += 1;
+            }
+            let len = len.min(size - offset);
+
+            let mut buf = vec![0u8; len as usize];
+            file.read_exact(&mut buf).await.map_err(ProviderError::IoError)?;
+            mac.update(&buf);
+            cipher.apply_keystream(&mut buf);
+
+            let resp = self.client.post(format!("{}/{}", url, offset))
+                .body(buf)
+                .send().await
+                .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+            if !resp.status().is_success() {
+                return Err(ProviderError::TransferFailed(format!("Upload failed: HTTP {}", resp.status())));
+            }
+            let text = resp.text().await.map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+            if let Ok(code) = text.trim().parse::<i64>() {
+                return Err(ProviderError::TransferFailed(format!("Upload failed: {}", mega_error(code))));
+            }
+            if !text.trim().is_empty() {
+                completion = text.trim().to_string();
+            }
 
-        // XFER-02: Signal completion with local file size
-        if let Some(ref cb) = progress {
-            match std::fs::metadata(l) {
-                Ok(meta) => cb(meta.len(), meta.len()),
-                Err(_) => cb(1, 1),
+            offset += len;
+            if let Some(ref cb) = progress {
+                cb(offset, size);
             }
+            throttle(start, offset, self.speed_limit.0).await;
+            if offset >= size {
+                break;
+            }
+        }
+
+        if completion.is_empty() {
+            return Err(ProviderError::TransferFailed("MEGA did not confirm the upload".to_string()));
+        }
+
+        let file_key = pack_file_key(&key, &nonce, &mac.finish());
+        let session = self.session()?;
+        let mut node = json!({
+            "h": completion,
+            "t": NODE_FILE,
+            "a": encrypt_attrs(&key, &json!({"n": name})),
+            "k": session.wrap_key(&file_key),
+        });
+        // Replacing a file keeps the old one as a previous version
+        if let Some(old) = &existing {
+            node["ov"] = json!(old.handle);
+        }
+        self.put_nodes(&parent.handle, json!([node])).await?;
+        if let Some(old) = existing {
+            self.nodes.remove(&old.handle);
         }
 
         Ok(())
     }
 
     async fn mkdir(&mut self, p: &str) -> Result<(), ProviderError> {
-        // CQ-06: Resolve path
         let p = self.resolve_path(p);
-        self.run_mega_cmd_with_reauth("mega-mkdir", &[&p]).await?;
+        let (parent, name) = self.parent_and_name(&p)?;
+        if self.child(&parent.handle, &name).is_some() {
+            return Err(ProviderError::AlreadyExists(p));
+        }
+        self.create_folder(&parent.handle, &name).await?;
         Ok(())
     }
 
     async fn delete(&mut self, p: &str) -> Result<(), ProviderError> {
-        // CQ-07: Resolve path
+        // Permanent delete: bypasses rubbish bin. Soft delete available via move_to_trash().
         let p = self.resolve_path(p);
-        // Permanent delete: -f bypasses rubbish bin. Soft delete available via move_to_trash().
-        self.run_mega_cmd_with_reauth("mega-rm", &["-f", &p]).await?;
-        Ok(())
+        let node = self.node_at(&p)?;
+        self.delete_node(&node).await
     }
 
     async fn rmdir(&mut self, p: &str) -> Result<(), ProviderError> {
-        // CQ-08: -r for recursive, -f for permanent delete (bypasses rubbish bin).
         let p = self.resolve_path(p);
-        self.run_mega_cmd_with_reauth("mega-rm", &["-r", "-f", &p]).await?;
-        Ok(())
+        let node = self.node_at(&p)?;
+        if node.kind != NODE_FOLDER {
+            return Err(ProviderError::InvalidPath(format!("Not a directory: {}", p)));
+        }
+        self.delete_node(&node).await
     }
 
     async fn rmdir_recursive(&mut self, p: &str) -> Result<(), ProviderError> {
-        let p = self.resolve_path(p);
-        self.run_mega_cmd_with_reauth("mega-rm", &["-r", &p]).await?;
-        Ok(())
+        // Deleting a folder node removes its whole subtree server-side
+        self.rmdir(p).await
     }
 
     async fn rename(&mut self, f: &str, t: &str) -> Result<(), ProviderError> {
         let f = self.resolve_path(f);
         let t = self.resolve_path(t);
-        self.run_mega_cmd_with_reauth("mega-mv", &[&f, &t]).await?;
-        Ok(())
+        let node = self.node_at(&f)?;
+        self.move_node(&node, &t).await
     }
 
     async fn stat(&mut self, p: &str) -> Result<RemoteEntry, ProviderError> {
         let abs_path = self.resolve_path(p);
-        let target_name = Path::new(&abs_path)
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_default();
 
         // For root, return a synthetic directory entry
-        if abs_path == "/" || target_name.is_empty() {
+        if abs_path.trim_end_matches('/').is_empty() {
             return Ok(RemoteEntry {
                 name: "/".to_string(),
                 path: "/".to_string(),
@@ -604,31 +1207,12 @@ impl StorageProvider for MegaProvider {
                 group: None,
                 mime_type: None,
                 metadata: Default::default(),
+                content_hash: None,
             });
         }
 
-        // List the parent directory and find the matching entry by name
-        let parent = Path::new(&abs_path)
-            .parent()
-            .map(|pp| pp.to_string_lossy().to_string())
-            .unwrap_or_else(|| "/".to_string());
-        let parent = if parent.is_empty() { "/".to_string() } else { parent };
-
-        let output = self.run_mega_cmd_with_reauth("mega-ls", &["-l", &parent]).await
-            .map_err(|e| ProviderError::ServerError(format!("stat failed for '{}': {}", abs_path, e)))?;
-
-        for line in output.lines() {
-            if let Some(entry) = Self::parse_ls_line(line, &parent) {
-                if entry.name == target_name {
-                    return Ok(RemoteEntry {
-                        path: abs_path,
-                        ..entry
-                    });
-                }
-            }
-        }
-
-        Err(ProviderError::NotFound(format!("Path not found: {}", abs_path)))
+        let node = self.node_at(&abs_path)?;
+        Ok(Self::to_entry(&node, abs_path))
     }
 
     async fn size(&mut self, p: &str) -> Result<u64, ProviderError> {
@@ -638,17 +1222,13 @@ impl StorageProvider for MegaProvider {
 
     async fn exists(&mut self, p: &str) -> Result<bool, ProviderError> {
         let p = self.resolve_path(p);
-        match self.run_mega_cmd_with_reauth("mega-ls", &[&p]).await {
-            Ok(_) => Ok(true),
-            Err(ProviderError::NotFound(_)) => Ok(false),
-            Err(_) => Ok(false),
-        }
+        Ok(self.find_node(&p).is_some())
     }
 
     async fn keep_alive(&mut self) -> Result<(), ProviderError> { Ok(()) }
 
     async fn server_info(&mut self) -> Result<String, ProviderError> {
-        self.run_mega_cmd_with_reauth("mega-whoami", &[]).await
+        Ok(format!("MEGA ({}) - native API", self.config.email))
     }
 
     fn supports_server_copy(&self) -> bool { true }
@@ -656,7 +1236,30 @@ impl StorageProvider for MegaProvider {
     async fn server_copy(&mut self, f: &str, t: &str) -> Result<(), ProviderError> {
         let f = self.resolve_path(f);
         let t = self.resolve_path(t);
-        self.run_mega_cmd_with_reauth("mega-cp", &[&f, &t]).await?;
+        let source = self.node_at(&f)?;
+        let (dest_parent, dest_name) = match self.find_node(&t).filter(|n| n.is_dir()) {
+            Some(folder) => (folder.clone(), source.name.clone()),
+            None => self.parent_and_name(&t)?,
+        };
+
+        // Files are copied server-side by handle; folders are recreated level by level
+        let mut queue = vec![(source, dest_parent.handle, dest_name)];
+        while let Some((node, target, name)) = queue.pop() {
+            if node.is_dir() {
+                let folder = self.create_folder(&target, &name).await?;
+                queue.extend(self.children(&node.handle)
+                    .map(|c| (c.clone(), folder.handle.clone(), c.name.clone())));
+            } else {
+                let session = self.session()?;
+                let copy = json!([{
+                    "h": node.handle,
+                    "t": NODE_FILE,
+                    "a": encrypt_attrs(&node.aes_key(), &json!({"n": name})),
+                    "k": session.wrap_key(&node.key),
+                }]);
+                self.put_nodes(&target, copy).await?;
+            }
+        }
         Ok(())
     }
 
@@ -668,29 +1271,30 @@ impl StorageProvider for MegaProvider {
         expires_in_secs: Option<u64>,
     ) -> Result<String, ProviderError> {
         let abs_path = self.resolve_path(path);
+        let node = self.node_at(&abs_path)?;
 
-        // SHARE-01: MEGAcmd's mega-export does not support link expiry.
-        if let Some(secs) = expires_in_secs {
-            tracing::warn!(target: "mega", "Link expiry requested ({}s) but MEGAcmd mega-export does not support expiry. Creating permanent link.", secs);
-        }
-
-        let output = self.run_mega_cmd_with_reauth("mega-export", &["-a", &abs_path]).await?;
+        let (kind, link_key) = if node.is_dir() {
+            ("folder", self.ensure_folder_share(&node).await?.to_vec())
+        } else {
+            ("file", node.key.clone())
+        };
 
-        // mega-export output format: "Exported /path: https://mega.nz/..."
-        for line in output.lines() {
-            if let Some(url_start) = line.find("https://mega.nz/") {
-                return Ok(line[url_start..].trim().to_string());
-            }
+        let mut cmd = json!({"a": "l", "n": node.handle});
+        // SHARE-01: link expiry is a MEGA Pro feature; the API rejects it otherwise
+        if let Some(secs) = expires_in_secs {
+            cmd["ets"] = json!(chrono::Utc::now().timestamp() + secs as i64);
         }
+        let res = self.api(cmd).await?;
+        let public_handle = res.as_str()
+            .ok_or_else(|| ProviderError::ParseError(format!("Unexpected export response: {}", res)))?;
 
-        Err(ProviderError::ParseError(
-            format!("Could not parse export link from MEGAcmd output: {}", output.trim())
-        ))
+        Ok(format!("https://mega.nz/{}/{}#{}", kind, public_handle, B64.encode(link_key)))
     }
 
     async fn remove_share_link(&mut self, path: &str) -> Result<(), ProviderError> {
         let abs_path = self.resolve_path(path);
-        self.run_mega_cmd_with_reauth("mega-export", &["-d", &abs_path]).await?;
+        let node = self.node_at(&abs_path)?;
+        self.api(json!({"a": "l", "n": node.handle, "d": 1})).await?;
         Ok(())
     }
 
@@ -698,41 +1302,35 @@ impl StorageProvider for MegaProvider {
 
     async fn import_link(&mut self, link: &str, dest: &str) -> Result<(), ProviderError> {
         let abs_dest = self.resolve_path(dest);
-        self.run_mega_cmd_with_reauth("mega-import", &[link, &abs_dest]).await?;
+        let folder = self.node_at(&abs_dest)?;
+        if !folder.is_dir() {
+            return Err(ProviderError::InvalidPath(format!("Not a directory: {}", abs_dest)));
+        }
+
+        let (public_handle, key) = parse_file_link(link)?;
+        let info = self.api(json!({"a": "g", "p": public_handle})).await?;
+        let attrs = info["at"].as_str()
+            .ok_or_else(|| ProviderError::NotFound("MEGA link is no longer available".to_string()))?
+            .to_string();
+        if decrypt_attrs(&unpack_file_key(&key).0, &b64_decode(&attrs)?).is_none() {
+            return Err(ProviderError::InvalidPath("Wrong decryption key for MEGA link".to_string()));
+        }
+
+        let session = self.session()?;
+        let node = json!([{
+            "ph": public_handle,
+            "t": NODE_FILE,
+            "a": attrs,
+            "k": session.wrap_key(&key),
+        }]);
+        self.put_nodes(&folder.handle, node).await?;
         Ok(())
     }
 
     async fn storage_info(&mut self) -> Result<StorageInfo, ProviderError> {
-        let output = self.run_mega_cmd_with_reauth("mega-df", &[]).await?;
-
-        // CQ-05: Only parse the "Total" or "Cloud drive" line for storage info
-        let mut used: u64 = 0;
-        let mut total: u64 = 0;
-
-        for line in output.lines() {
-            let line = line.trim();
-            // Look for lines with "of" pattern containing byte counts
-            if let Some(colon_pos) = line.find(':') {
-                let label = line[..colon_pos].trim().to_lowercase();
-                let rest = line[colon_pos + 1..].trim();
-                let parts: Vec<&str> = rest.split_whitespace().collect();
-                // Format: "<used> of <total> ..."
-                if parts.len() >= 3 && parts[1] == "of" {
-                    if let (Ok(u), Ok(t)) = (parts[0].parse::<u64>(), parts[2].parse::<u64>()) {
-                        // Prefer "Total" line; fall back to "Cloud drive"
-                        if label.contains("total") {
-                            used = u;
-                            total = t;
-                            break;
-                        } else if label.contains("cloud drive") {
-                            used = u;
-                            total = t;
-                            // Don't break — a "Total" line may follow
-                        }
-                    }
-                }
-            }
-        }
+        let res = self.api(json!({"a": "uq", "strg": 1, "xfer": 1})).await?;
+        let used = res["cstrg"].as_u64().unwrap_or(0);
+        let total = res["mstrg"].as_u64().unwrap_or(0);
 
         Ok(StorageInfo {
             used,
@@ -743,102 +1341,47 @@ impl StorageProvider for MegaProvider {
 
     async fn disk_usage(&mut self, path: &str) -> Result<u64, ProviderError> {
         let abs_path = self.resolve_path(path);
-        let output = self.run_mega_cmd_with_reauth("mega-du", &[&abs_path]).await?;
-
-        // CQ-04: More specific line matching for disk usage output.
-        // mega-du output: "Total storage used: 123456789 bytes" or "/path: 123456789"
-        // Parse from last line that contains a numeric value.
-        for line in output.lines().rev() {
-            let line = line.trim();
-            if line.is_empty() { continue; }
-            // Try to find last numeric token
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if let Some(last) = parts.last() {
-                // Strip "bytes" suffix if present
-                let num_str = last.trim_end_matches("bytes").trim();
-                if let Ok(bytes) = num_str.parse::<u64>() {
-                    return Ok(bytes);
-                }
-                // Also try the raw last token
-                if let Ok(bytes) = last.parse::<u64>() {
-                    return Ok(bytes);
-                }
-            }
-        }
+        let root = self.node_at(&abs_path)?;
 
-        Err(ProviderError::ParseError(
-            format!("Could not parse disk usage from: {}", output.trim())
-        ))
+        let mut total = 0;
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            total += node.size;
+            stack.extend(self.children(&node.handle).cloned());
+        }
+        Ok(total)
     }
 
     fn supports_find(&self) -> bool { true }
 
     async fn find(&mut self, path: &str, pattern: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
         let abs_path = self.resolve_path(path);
-        let output = self.run_mega_cmd_with_reauth("mega-find", &[&abs_path, "--pattern", pattern]).await?;
+        self.refresh_if_stale().await?;
+        let root = self.node_at(&abs_path)?;
 
         let mut entries = Vec::new();
-        for line in output.lines() {
-            let line = line.trim();
-            if line.is_empty() { continue; }
-
-            // mega-find outputs full paths, one per line
-            // CQ-03: Metadata not available from mega-find — size/modified left as defaults
-            let name = Path::new(line)
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| line.to_string());
-
-            let is_dir = line.ends_with('/');
-            let clean_path = line.trim_end_matches('/').to_string();
-
-            entries.push(RemoteEntry {
-                name: name.trim_end_matches('/').to_string(),
-                path: clean_path,
-                is_dir,
-                size: 0,
-                modified: None,
-                is_symlink: false,
-                link_target: None,
-                permissions: None,
-                owner: None,
-                group: None,
-                mime_type: None,
-                metadata: Default::default(),
-            });
+        let mut stack = vec![(root.handle, abs_path)];
+        while let Some((handle, dir_path)) = stack.pop() {
+            for child in self.children(&handle) {
+                let child_path = Self::join(&dir_path, &child.name);
+                if super::glob::wildcard_match(pattern, &child.name) {
+                    entries.push(Self::to_entry(child, child_path.clone()));
+                }
+                if child.is_dir() {
+                    stack.push((child.handle.clone(), child_path));
+                }
+            }
         }
-
         Ok(entries)
     }
 
     async fn set_speed_limit(&mut self, upload_kb: u64, download_kb: u64) -> Result<(), ProviderError> {
-        let ul = upload_kb.to_string();
-        let dl = download_kb.to_string();
-        self.run_mega_cmd_with_reauth("mega-speedlimit", &["-u", &ul, "-d", &dl]).await?;
+        self.speed_limit = (upload_kb, download_kb);
         Ok(())
     }
 
     async fn get_speed_limit(&mut self) -> Result<(u64, u64), ProviderError> {
-        let output = self.run_mega_cmd_with_reauth("mega-speedlimit", &[]).await?;
-
-        let mut upload: u64 = 0;
-        let mut download: u64 = 0;
-
-        for line in output.lines() {
-            let line_lower = line.to_lowercase();
-            if let Some(eq_pos) = line.find('=') {
-                let val_str = line[eq_pos + 1..].trim();
-                let num_str = val_str.split_whitespace().next().unwrap_or("0");
-                let val = num_str.parse::<u64>().unwrap_or(0);
-                if line_lower.contains("upload") {
-                    upload = val;
-                } else if line_lower.contains("download") {
-                    download = val;
-                }
-            }
-        }
-
-        Ok((upload, download))
+        Ok(self.speed_limit)
     }
 }
 
@@ -847,54 +1390,169 @@ impl MegaProvider {
     /// Move a file or directory to the MEGA rubbish bin (soft delete).
     pub async fn move_to_trash(&mut self, path: &str) -> Result<(), ProviderError> {
         let p = self.resolve_path(path);
-        // mega-rm without -f moves to rubbish bin. -r handles directories.
-        self.run_mega_cmd_with_reauth("mega-rm", &["-r", &p]).await?;
-        Ok(())
+        let node = self.node_at(&p)?;
+        let rubbish = self.special_root(NODE_RUBBISH)
+            .map(|n| n.handle.clone())
+            .ok_or_else(|| ProviderError::NotFound("Rubbish bin not found".to_string()))?;
+        self.move_handle(&node.handle, &rubbish).await
     }
 
-    /// TRASH-02: List items in the MEGA rubbish bin via `mega-ls /Rubbish`.
+    /// TRASH-02: List items in the MEGA rubbish bin.
     pub async fn list_trash(&mut self) -> Result<Vec<RemoteEntry>, ProviderError> {
-        let output = self.run_mega_cmd_with_reauth("mega-ls", &["-l", "/Rubbish"]).await
-            .map_err(|e| match e {
-                ProviderError::NotFound(_) => {
-                    // Empty rubbish or path doesn't exist — return empty list
-                    ProviderError::NotFound("Rubbish bin empty".to_string())
-                }
-                other => other,
-            });
-
-        match output {
-            Ok(out) => {
-                let mut entries = Vec::new();
-                for line in out.lines() {
-                    if let Some(entry) = Self::parse_ls_line(line, "/Rubbish") {
-                        entries.push(entry);
-                    }
-                }
-                Ok(entries)
-            }
-            Err(ProviderError::NotFound(_)) => Ok(Vec::new()),
-            Err(e) => Err(e),
-        }
+        self.refresh_if_stale().await?;
+        let Some(rubbish) = self.special_root(NODE_RUBBISH) else {
+            return Ok(Vec::new());
+        };
+        Ok(self.children(&rubbish.handle)
+            .map(|n| Self::to_entry(n, Self::join(TRASH_PATH, &n.name)))
+            .collect())
     }
 
     /// TRASH-03: Restore an item from rubbish bin to a destination path.
     pub async fn restore_from_trash(&mut self, filename: &str, dest: &str) -> Result<(), ProviderError> {
-        let rubbish_path = format!("/Rubbish/{}", filename.trim_start_matches('/'));
+        let node = self.trash_entry(filename)?;
         let abs_dest = self.resolve_path(dest);
-        self.run_mega_cmd_with_reauth("mega-mv", &[&rubbish_path, &abs_dest]).await?;
-        Ok(())
+        self.move_node(&node, &abs_dest).await
     }
 
     /// TRASH-04: Permanently delete an item from the rubbish bin.
     pub async fn permanent_delete_from_trash(&mut self, filename: &str) -> Result<(), ProviderError> {
-        let rubbish_path = format!("/Rubbish/{}", filename.trim_start_matches('/'));
-        // Use -f flag for permanent deletion
-        self.run_mega_cmd_with_reauth("mega-rm", &["-f", &rubbish_path]).await?;
-        Ok(())
+        let node = self.trash_entry(filename)?;
+        self.delete_node(&node).await
     }
 
-    // TODO: SHARE-02: User-to-user sharing via `mega-share` command
+    // TODO: SHARE-02: User-to-user sharing (needs RSA key exchange with the contact)
     // TODO: AUTH-07/QUOTA-01: Transfer quota tracking
     // TODO: QUOTA-02: Pro status detection
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_key_roundtrip() {
+        let key: [u8; 16] = rand::random();
+        let nonce: [u8; 8] = rand::random();
+        let mac: [u8; 8] = rand::random();
+        let packed = pack_file_key(&key, &nonce, &mac);
+        assert_eq!(unpack_file_key(&packed), (key, nonce, mac));
+    }
+
+    #[test]
+    fn test_attrs_roundtrip() {
+        let key: [u8; 16] = rand::random();
+        let encrypted = encrypt_attrs(&key, &json!({"n": "Report Q3.pdf"}));
+        let attrs = decrypt_attrs(&key, &b64_decode(&encrypted).unwrap()).unwrap();
+        assert_eq!(attrs["n"], "Report Q3.pdf");
+        assert!(decrypt_attrs(&[0u8; 16], &b64_decode(&encrypted).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_chunk_boundaries() {
+        let sizes: Vec<u64> = (0..10).map(chunk_size).collect();
+        assert_eq!(sizes[0], 128 * 1024);
+        assert_eq!(sizes[7], 1024 * 1024);
+        assert_eq!(sizes[9], 1024 * 1024);
+    }
+
+    #[test]
+    fn test_chunk_mac_is_split_independent() {
+        let key: [u8; 16] = rand::random();
+        let nonce: [u8; 8] = rand::random();
+        let data: Vec<u8> = (0..700_001u32).map(|i| (i % 251) as u8).collect();
+
+        let mut whole = ChunkMac::new(&key, &nonce);
+        whole.update(&data);
+        let mut pieces = ChunkMac::new(&key, &nonce);
+        for piece in data.chunks(4093) {
+            pieces.update(piece);
+        }
+        assert_eq!(whole.finish(), pieces.finish());
+        assert_eq!(ChunkMac::new(&key, &nonce).finish(), [0u8; 8]);
+    }
+
+    #[test]
+    fn test_read_mpis() {
+        // 17-bit value 0x010001 followed by 8-bit value 0xFF
+        let data = [0x00, 0x11, 0x01, 0x00, 0x01, 0x00, 0x08, 0xFF];
+        let mpis = read_mpis(&data, 2).unwrap();
+        assert_eq!(mpis[0], BigUint::from(65537u32));
+        assert_eq!(mpis[1], BigUint::from(255u32));
+        assert!(read_mpis(&data, 3).is_err());
+    }
+
+    // Known-answer vectors below were computed with an independent Python
+    // implementation (AES from the `cryptography` package, hashlib PBKDF2)
+
+    #[test]
+    fn test_password_key_vectors() {
+        let key = prepare_key_v1(b"correct horse battery staple");
+        assert_eq!(hex::encode(key), "57d978ef82e99ea50ab00045e8614d7b");
+        assert_eq!(user_hash_v1("user@example.com", &key), "sSgbS6ks0bE");
+
+        // Single-block password
+        let key = prepare_key_v1(b"short");
+        assert_eq!(hex::encode(key), "5a2d5e01dbf15a0abea32e5b93dab913");
+        assert_eq!(user_hash_v1("user@example.com", &key), "I_LPm1oRu98");
+
+        let salt: Vec<u8> = (0..32).collect();
+        let (key, hash) = derive_key_v2(b"correct horse battery staple", &salt);
+        assert_eq!(hex::encode(key), "88aa99bab648e0a15a6dcfd127cb7d9f");
+        assert_eq!(hash, "F9Gj-gyqbKnMl-KglgC7wA");
+    }
+
+    #[test]
+    fn test_chunk_mac_vectors() {
+        let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let nonce: [u8; 8] = core::array::from_fn(|i| 0xa0 + i as u8);
+
+        // Spans the 128 KiB and 256 KiB chunks and ends in a partial block
+        let data: Vec<u8> = (0..300_001u32).map(|i| (i % 251) as u8).collect();
+        let mut mac = ChunkMac::new(&key, &nonce);
+        mac.update(&data);
+        assert_eq!(hex::encode(mac.finish()), "3db3e71a0fc8789c");
+
+        let mut mac = ChunkMac::new(&key, &nonce);
+        mac.update(b"abc");
+        assert_eq!(hex::encode(mac.finish()), "2f76cba9ac32428f");
+    }
+
+    #[test]
+    fn test_decrypt_session_id() {
+        let mpi = |hex: &str| {
+            let value = BigUint::parse_bytes(hex.as_bytes(), 16).unwrap();
+            let mut out = (value.bits() as u16).to_be_bytes().to_vec();
+            out.extend(value.to_bytes_be());
+            out
+        };
+        // 512-bit test key: p, q, d, u
+        let private_key = [
+            mpi("9adb19d9ad97561cba29ef5e8f0191a19b25822b3f5fd2fed7c3fe9f4fd823f5"),
+            mpi("8ba87e7d472f2bcee1030888012f611bd2cea0d0a2e45d7dcffb454e5a2fc42d"),
+            mpi("3a9c8bef2e2c126f5368aeb1b4f69b573bc185a654ef49ca18cd0253fccf2ab9\
+                 72a28b8863894ee6f874e965600c049e5e6acb524a0a97ddcb6f4f694395ac31"),
+            mpi("1237f0064b3ed66584e89f02cb9cb93c591cb306f71bae47546f14bf349cd6c6"),
+        ].concat();
+        // Bytes 0x01..=0x3c encrypted with e = 65537
+        let csid = mpi("4d7581ace3e0659f2e08e5d836f29452be7fed301d82bf3d90a96ea7fb61a49f\
+                        73ee813818035a3c4912b88eba48c6388438d67cb9d52ae6ef203816e7c2ba73");
+
+        assert_eq!(
+            decrypt_session_id(&private_key, &csid).unwrap(),
+            "AQIDBAUGBwgJCgsMDQ4PEBESExQVFhcYGRobHB0eHyAhIiMkJSYnKCkqKw"
+        );
+        assert!(decrypt_session_id(&private_key[..10], &csid).is_err());
+    }
+
+    #[test]
+    fn test_parse_file_link() {
+        let key = B64.encode([7u8; 32]);
+        let (handle, parsed) = parse_file_link(&format!("https://mega.nz/file/AbCdEfGh#{}", key)).unwrap();
+        assert_eq!(handle, "AbCdEfGh");
+        assert_eq!(parsed, vec![7u8; 32]);
+        let (handle, _) = parse_file_link(&format!("https://mega.nz/#!AbCdEfGh!{}", key)).unwrap();
+        assert_eq!(handle, "AbCdEfGh");
+        assert!(matches!(parse_file_link("https://mega.nz/folder/AbCd#xyz"), Err(ProviderError::NotSupported(_))));
+    }
+}
@@ -0,0 +1,225 @@
+//! In-memory StorageProvider for tests
+//!
+//! Files live in a path → bytes map and directories in a set. Uploads create
+//! missing parent directories the way object stores do; everything else
+//! behaves like a plain filesystem (renames move whole trees, `rmdir` needs an
+//! empty directory). Tests reach the maps through `as_any_mut` to inspect or
+//! tamper with the stored bytes.
+
+use async_trait::async_trait;
+use std::collections::{BTreeSet, HashMap};
+
+use super::{ProviderError, ProviderType, RemoteEntry, StorageProvider};
+
+#[derive(Default)]
+pub(crate) struct MemoryProvider {
+    pub files: HashMap<String, Vec<u8>>,
+    pub dirs: BTreeSet<String>,
+}
+
+/// Absolute path without empty segments or a trailing slash ("/" for the root)
+fn normalize(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty() && *s != ".").collect();
+    format!("/{}", segments.join("/"))
+}
+
+fn parent_of(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) => &path[..i],
+    }
+}
+
+fn name_of(path: &str) -> String {
+    path.rsplit('/').next().unwrap_or_default().to_string()
+}
+
+/// Whether `path` is `dir` itself or lies below it
+fn is_within(path: &str, dir: &str) -> bool {
+    dir == "/" || path == dir || path.starts_with(&format!("{}/", dir))
+}
+
+impl MemoryProvider {
+    fn is_dir(&self, path: &str) -> bool {
+        path == "/" || self.dirs.contains(path)
+    }
+
+    fn add_parents(&mut self, path: &str) {
+        let mut parent = parent_of(path);
+        while parent != "/" {
+            self.dirs.insert(parent.to_string());
+            parent = parent_of(parent);
+        }
+    }
+
+    fn has_children(&self, dir: &str) -> bool {
+        self.files.keys().chain(self.dirs.iter()).any(|p| p != dir && is_within(p, dir))
+    }
+
+    fn file(&self, path: &str) -> Result<&Vec<u8>, ProviderError> {
+        self.files.get(&normalize(path)).ok_or_else(|| ProviderError::NotFound(path.to_string()))
+    }
+}
+
+#[async_trait]
+impl StorageProvider for MemoryProvider {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+    fn provider_type(&self) -> ProviderType { ProviderType::Ftp }
+    fn display_name(&self) -> String { "memory".into() }
+    async fn connect(&mut self) -> Result<(), ProviderError> { Ok(()) }
+    async fn disconnect(&mut self) -> Result<(), ProviderError> { Ok(()) }
+    fn is_connected(&self) -> bool { true }
+
+    async fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
+        let dir = normalize(path);
+        if !self.is_dir(&dir) {
+            return Err(ProviderError::NotFound(path.to_string()));
+        }
+        let dirs = self.dirs.iter()
+            .filter(|p| *p != "/" && parent_of(p) == dir)
+            .map(|p| RemoteEntry::directory(name_of(p), p.clone()));
+        let files = self.files.iter()
+            .filter(|(p, _)| parent_of(p) == dir)
+            .map(|(p, data)| RemoteEntry::file(name_of(p), p.clone(), data.len() as u64));
+        Ok(dirs.chain(files).collect())
+    }
+
+    async fn pwd(&mut self) -> Result<String, ProviderError> { Ok("/".into()) }
+
+    async fn cd(&mut self, path: &str) -> Result<(), ProviderError> {
+        if self.is_dir(&normalize(path)) { Ok(()) } else { Err(ProviderError::NotFound(path.to_string())) }
+    }
+
+    async fn cd_up(&mut self) -> Result<(), ProviderError> { Ok(()) }
+
+    async fn download(
+        &mut self,
+        remote_path: &str,
+        local_path: &str,
+        _on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        std::fs::write(local_path, self.file(remote_path)?)?;
+        Ok(())
+    }
+
+    async fn download_to_bytes(&mut self, remote_path: &str) -> Result<Vec<u8>, ProviderError> {
+        self.file(remote_path).cloned()
+    }
+
+    async fn upload(
+        &mut self,
+        local_path: &str,
+        remote_path: &str,
+        _on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        let path = normalize(remote_path);
+        if self.is_dir(&path) {
+            return Err(ProviderError::AlreadyExists(remote_path.to_string()));
+        }
+        let data = std::fs::read(local_path)?;
+        self.add_parents(&path);
+        self.files.insert(path, data);
+        Ok(())
+    }
+
+    async fn mkdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        let dir = normalize(path);
+        if self.is_dir(&dir) || self.files.contains_key(&dir) {
+            return Err(ProviderError::AlreadyExists(path.to_string()));
+        }
+        if !self.is_dir(parent_of(&dir)) {
+            return Err(ProviderError::NotFound(parent_of(&dir).to_string()));
+        }
+        self.dirs.insert(dir);
+        Ok(())
+    }
+
+    async fn delete(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.files.remove(&normalize(path))
+            .map(|_| ())
+            .ok_or_else(|| ProviderError::NotFound(path.to_string()))
+    }
+
+    async fn rmdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        let dir = normalize(path);
+        if !self.dirs.contains(&dir) {
+            return Err(ProviderError::NotFound(path.to_string()));
+        }
+        if self.has_children(&dir) {
+            return Err(ProviderError::DirectoryNotEmpty(path.to_string()));
+        }
+        self.dirs.remove(&dir);
+        Ok(())
+    }
+
+    async fn rmdir_recursive(&mut self, path: &str) -> Result<(), ProviderError> {
+        let dir = normalize(path);
+        if !self.dirs.contains(&dir) {
+            return Err(ProviderError::NotFound(path.to_string()));
+        }
+        self.files.retain(|p, _| !is_within(p, &dir));
+        self.dirs.retain(|p| !is_within(p, &dir));
+        Ok(())
+    }
+
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        let (from, to) = (normalize(from), normalize(to));
+        if self.is_dir(&to) || self.files.contains_key(&to) {
+            return Err(ProviderError::AlreadyExists(to));
+        }
+        if !self.is_dir(parent_of(&to)) {
+            return Err(ProviderError::NotFound(parent_of(&to).to_string()));
+        }
+        if let Some(data) = self.files.remove(&from) {
+            self.files.insert(to, data);
+            return Ok(());
+        }
+        if !self.dirs.contains(&from) {
+            return Err(ProviderError::NotFound(from));
+        }
+        if is_within(&to, &from) {
+            return Err(ProviderError::InvalidPath(format!("Cannot move {} into itself", from)));
+        }
+
+        let moved = |p: &str| format!("{}{}", to, &p[from.len()..]);
+        let files: Vec<String> = self.files.keys().filter(|p| is_within(p, &from)).cloned().collect();
+        for path in files {
+            let data = self.files.remove(&path).unwrap_or_default();
+            self.files.insert(moved(&path), data);
+        }
+        let dirs: Vec<String> = self.dirs.iter().filter(|p| is_within(p, &from)).cloned().collect();
+        for path in dirs {
+            self.dirs.remove(&path);
+            self.dirs.insert(moved(&path));
+        }
+        Ok(())
+    }
+
+    async fn stat(&mut self, path: &str) -> Result<RemoteEntry, ProviderError> {
+        let path = normalize(path);
+        if self.is_dir(&path) {
+            return Ok(RemoteEntry::directory(name_of(&path), path));
+        }
+        let size = self.file(&path)?.len() as u64;
+        Ok(RemoteEntry::file(name_of(&path), path, size))
+    }
+
+    async fn size(&mut self, path: &str) -> Result<u64, ProviderError> {
+        Ok(self.file(path)?.len() as u64)
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool, ProviderError> {
+        let path = normalize(path);
+        Ok(self.is_dir(&path) || self.files.contains_key(&path))
+    }
+
+    async fn keep_alive(&mut self) -> Result<(), ProviderError> { Ok(()) }
+    async fn server_info(&mut self) -> Result<String, ProviderError> { Ok("memory".into()) }
+
+    async fn read_range(&mut self, path: &str, offset: u64, len: u64) -> Result<Vec<u8>, ProviderError> {
+        let data = self.file(path)?;
+        let start = (offset as usize).min(data.len());
+        let end = (offset.saturating_add(len) as usize).min(data.len());
+        Ok(data[start..end].to_vec())
+    }
+}
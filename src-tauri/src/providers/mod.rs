@@ -22,6 +22,14 @@
 pub mod types;
 pub mod ftp;
 pub mod sftp;
+pub mod ssh_auth;
+pub mod ssh_config;
+pub mod ssh_pool;
+pub mod ssh_exec;
+pub mod scp;
+pub mod rsync;
+pub mod sftp_pipeline;
+pub mod ssh_tunnel;
 pub mod webdav;
 pub mod s3;
 pub mod oauth2;
@@ -41,7 +49,19 @@ pub mod kdrive;
 pub mod jottacloud;
 pub mod drime_cloud;
 pub mod filelu;
+pub mod swift;
+pub mod b2;
+pub mod gcs;
+pub mod ntlm;
+pub mod smb;
+pub mod cryptomator;
+pub mod crypt;
 pub mod http_retry;
+pub mod glob;
+pub mod proxy;
+pub mod tls;
+#[cfg(test)]
+pub(crate) mod memory;
 
 pub use types::*;
 // GAP-A01: retry infrastructure ready — integration into providers deferred to v2.5.0
@@ -66,6 +86,12 @@ pub use kdrive::KDriveProvider;
 pub use jottacloud::JottacloudProvider;
 pub use drime_cloud::DrimeCloudProvider;
 pub use filelu::FileLuProvider;
+pub use swift::SwiftProvider;
+pub use b2::B2Provider;
+pub use gcs::GcsProvider;
+pub use smb::SmbProvider;
+pub use cryptomator::CryptomatorProvider;
+pub use crypt::CryptProvider;
 pub use oauth2::{OAuth2Manager, OAuthConfig, OAuthProvider};
 
 use async_trait::async_trait;
@@ -134,6 +160,38 @@ pub fn sanitize_api_error(body: &str) -> String {
     }
 }
 
+/// Fill `buf` from `reader`, returning fewer bytes only at end of stream
+pub(crate) fn read_full<R: std::io::Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// [`read_full`] for async readers, e.g. the next part of a file being uploaded
+pub(crate) async fn read_full_async<R: tokio::io::AsyncRead + Unpin>(
+    reader: &mut R,
+    buf: &mut [u8],
+) -> Result<usize, ProviderError> {
+    use tokio::io::AsyncReadExt;
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = reader.read(&mut buf[filled..]).await
+            .map_err(|e| ProviderError::TransferFailed(format!("File read error: {}", e)))?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
 /// Transfer optimization hints — per-provider capability advertisement
 #[derive(Debug, Clone, Serialize)]
 pub struct TransferOptimizationHints {
@@ -147,6 +205,10 @@ pub struct TransferOptimizationHints {
     pub preferred_checksum_algo: Option<String>,
     pub supports_compression: bool,
     pub supports_delta_sync: bool,
+    /// Read/write requests kept in flight per transfer (1 = one at a time)
+    pub pipeline_depth: u32,
+    /// Bytes per pipelined request (0 = not applicable)
+    pub pipeline_block_size: u32,
 }
 
 impl Default for TransferOptimizationHints {
@@ -162,6 +224,8 @@ impl Default for TransferOptimizationHints {
             preferred_checksum_algo: None,
             supports_compression: false,
             supports_delta_sync: false,
+            pipeline_depth: 1,
+            pipeline_block_size: 0,
         }
     }
 }
@@ -275,6 +339,51 @@ pub trait StorageProvider: Send + Sync {
     fn supports_symlinks(&self) -> bool {
         false
     }
+
+    /// Read the target of a symbolic link (not resolved)
+    async fn read_link(&mut self, _path: &str) -> Result<String, ProviderError> {
+        Err(ProviderError::NotSupported("read_link".to_string()))
+    }
+
+    /// Create a symbolic link at `link_path` pointing to `target`
+    async fn create_symlink(&mut self, _target: &str, _link_path: &str) -> Result<(), ProviderError> {
+        Err(ProviderError::NotSupported("create_symlink".to_string()))
+    }
+
+    /// Check if provider supports hard links
+    fn supports_hardlinks(&self) -> bool {
+        false
+    }
+
+    /// Create `link_path` as another name for the existing file `existing`
+    async fn create_hardlink(&mut self, _existing: &str, _link_path: &str) -> Result<(), ProviderError> {
+        Err(ProviderError::NotSupported("create_hardlink".to_string()))
+    }
+
+    /// Get mode, ownership and times of a file (symlinks are followed)
+    async fn get_attributes(&mut self, _path: &str) -> Result<PosixAttributes, ProviderError> {
+        Err(ProviderError::NotSupported("get_attributes".to_string()))
+    }
+
+    /// Set the given mode, ownership and times; `None` fields are left as they are
+    async fn set_attributes(&mut self, _path: &str, _attrs: &PosixAttributes) -> Result<(), ProviderError> {
+        Err(ProviderError::NotSupported("set_attributes".to_string()))
+    }
+
+    /// Check if provider supports extended attributes
+    fn supports_xattrs(&self) -> bool {
+        false
+    }
+
+    /// Get the `user.*` extended attributes of a file
+    async fn get_xattrs(&mut self, _path: &str) -> Result<Xattrs, ProviderError> {
+        Err(ProviderError::NotSupported("get_xattrs".to_string()))
+    }
+
+    /// Set extended attributes on a file (existing ones not listed are kept)
+    async fn set_xattrs(&mut self, _path: &str, _xattrs: &Xattrs) -> Result<(), ProviderError> {
+        Err(ProviderError::NotSupported("set_xattrs".to_string()))
+    }
     
     /// Check if provider supports server-side copy
     fn supports_server_copy(&self) -> bool {
@@ -505,8 +614,14 @@ pub trait StorageProvider: Send + Sync {
 pub struct ProviderFactory;
 
 impl ProviderFactory {
-    /// Create a new provider instance based on configuration
+    /// Create a new provider instance based on configuration.
+    /// Profiles with a `crypt_password` get the client-side encryption overlay.
     pub fn create(config: &ProviderConfig) -> Result<Box<dyn StorageProvider>, ProviderError> {
+        let provider = Self::create_base(config)?;
+        Ok(CryptProvider::wrap_if_configured(provider, &config.extra))
+    }
+
+    fn create_base(config: &ProviderConfig) -> Result<Box<dyn StorageProvider>, ProviderError> {
         match config.provider_type {
             ProviderType::Ftp | ProviderType::Ftps => {
                 let ftp_config = FtpConfig::from_provider_config(config)?;
@@ -576,6 +691,28 @@ impl ProviderFactory {
                 let filelu_config = FileLuConfig::from_provider_config(config)?;
                 Ok(Box::new(FileLuProvider::new(filelu_config)))
             }
+            ProviderType::Swift => {
+                let swift_config = SwiftConfig::from_provider_config(config)?;
+                Ok(Box::new(SwiftProvider::new(swift_config)))
+            }
+            ProviderType::B2 => {
+                let b2_config = B2Config::from_provider_config(config)?;
+                Ok(Box::new(B2Provider::new(b2_config)))
+            }
+            ProviderType::Gcs => {
+                let gcs_config = GcsConfig::from_provider_config(config)?;
+                Ok(Box::new(GcsProvider::new(gcs_config)))
+            }
+            ProviderType::Smb => {
+                let smb_config = SmbConfig::from_provider_config(config)?;
+                Ok(Box::new(SmbProvider::new(smb_config)))
+            }
+            ProviderType::Cryptomator => {
+                // Vaults are layered over an existing connection by cryptomator_mount_remote
+                Err(ProviderError::NotSupported(
+                    "Cryptomator vaults are opened on top of an existing connection".to_string()
+                ))
+            }
         }
     }
     
@@ -603,6 +740,10 @@ impl ProviderFactory {
             ProviderType::KDrive,
             ProviderType::DrimeCloud,
             ProviderType::FileLu,
+            ProviderType::Swift,
+            ProviderType::B2,
+            ProviderType::Gcs,
+            ProviderType::Smb,
         ]
     }
 }
@@ -0,0 +1,321 @@
+//! NTLMv2 authentication (MS-NLMP) wrapped in SPNEGO (RFC 4178)
+//!
+//! Used by the SMB provider for SESSION_SETUP. Only the pieces a client needs:
+//! building NEGOTIATE / AUTHENTICATE messages, parsing the server CHALLENGE and
+//! deriving the session key. No key exchange (RC4) is negotiated, so the
+//! exported session key is the NTLMv2 session base key.
+
+use hmac::{Hmac, Mac};
+use md4::{Digest, Md4};
+use md5::Md5;
+
+use super::ProviderError;
+
+type HmacMd5 = Hmac<Md5>;
+
+const SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+
+const NEGOTIATE_UNICODE: u32 = 0x0000_0001;
+const REQUEST_TARGET: u32 = 0x0000_0004;
+const NEGOTIATE_SIGN: u32 = 0x0000_0010;
+const NEGOTIATE_NTLM: u32 = 0x0000_0200;
+const NEGOTIATE_ANONYMOUS: u32 = 0x0000_0800;
+const NEGOTIATE_ALWAYS_SIGN: u32 = 0x0000_8000;
+const NEGOTIATE_EXTENDED_SESSIONSECURITY: u32 = 0x0008_0000;
+const NEGOTIATE_TARGET_INFO: u32 = 0x0080_0000;
+const NEGOTIATE_128: u32 = 0x2000_0000;
+const NEGOTIATE_56: u32 = 0x8000_0000;
+
+const CLIENT_FLAGS: u32 = NEGOTIATE_UNICODE | REQUEST_TARGET | NEGOTIATE_SIGN | NEGOTIATE_NTLM
+    | NEGOTIATE_ALWAYS_SIGN | NEGOTIATE_EXTENDED_SESSIONSECURITY | NEGOTIATE_TARGET_INFO
+    | NEGOTIATE_128 | NEGOTIATE_56;
+
+/// AV_PAIR id of the server timestamp in the CHALLENGE target info
+const MSV_AV_TIMESTAMP: u16 = 7;
+
+/// SPNEGO mechanism OID 1.3.6.1.5.5.2
+const SPNEGO_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x02];
+/// NTLMSSP mechanism OID 1.3.6.1.4.1.311.2.2.10
+const NTLMSSP_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x02, 0x0a];
+
+/// Parsed CHALLENGE_MESSAGE
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    pub flags: u32,
+    pub server_challenge: [u8; 8],
+    pub target_info: Vec<u8>,
+}
+
+impl Challenge {
+    /// Server time (FILETIME) from the target info, if the server sent one
+    pub fn timestamp(&self) -> Option<u64> {
+        let mut pos = 0;
+        while pos + 4 <= self.target_info.len() {
+            let id = u16::from_le_bytes([self.target_info[pos], self.target_info[pos + 1]]);
+            let len = u16::from_le_bytes([self.target_info[pos + 2], self.target_info[pos + 3]]) as usize;
+            let value = self.target_info.get(pos + 4..pos + 4 + len)?;
+            match id {
+                0 => return None,
+                MSV_AV_TIMESTAMP if len == 8 => return Some(u64::from_le_bytes(value.try_into().ok()?)),
+                _ => pos += 4 + len,
+            }
+        }
+        None
+    }
+}
+
+/// Credentials for one authentication attempt. An empty user name requests an
+/// anonymous session.
+pub struct Credentials<'a> {
+    pub user: &'a str,
+    pub domain: &'a str,
+    pub password: &'a str,
+}
+
+fn utf16(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect()
+}
+
+fn hmac_md5(key: &[u8], parts: &[&[u8]]) -> [u8; 16] {
+    let mut mac = HmacMd5::new_from_slice(key).expect("HMAC accepts any key length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// NT one-way function: MD4 of the UTF-16LE password
+pub fn nt_hash(password: &str) -> [u8; 16] {
+    Md4::digest(utf16(password)).into()
+}
+
+/// NTOWFv2: HMAC-MD5 keyed by the NT hash over upper-case user + domain
+pub fn ntowf_v2(password: &str, user: &str, domain: &str) -> [u8; 16] {
+    let identity = format!("{}{}", user.to_uppercase(), domain);
+    hmac_md5(&nt_hash(password), &[&utf16(&identity)])
+}
+
+/// NEGOTIATE_MESSAGE (no domain or workstation supplied)
+pub fn negotiate_message() -> Vec<u8> {
+    let mut msg = Vec::with_capacity(32);
+    msg.extend_from_slice(SIGNATURE);
+    msg.extend_from_slice(&1u32.to_le_bytes());
+    msg.extend_from_slice(&CLIENT_FLAGS.to_le_bytes());
+    msg.extend_from_slice(&[0u8; 16]);
+    msg
+}
+
+/// Parse a CHALLENGE_MESSAGE. Trailing bytes (e.g. an SPNEGO mechListMIC) are ignored.
+pub fn parse_challenge(msg: &[u8]) -> Result<Challenge, ProviderError> {
+    let invalid = |what: &str| ProviderError::AuthenticationFailed(format!("Invalid NTLM challenge: {}", what));
+    if msg.len() < 48 || &msg[..8] != SIGNATURE {
+        return Err(invalid("bad signature"));
+    }
+    if u32::from_le_bytes(msg[8..12].try_into().unwrap()) != 2 {
+        return Err(invalid("unexpected message type"));
+    }
+    let flags = u32::from_le_bytes(msg[20..24].try_into().unwrap());
+    let server_challenge: [u8; 8] = msg[24..32].try_into().unwrap();
+    let info_len = u16::from_le_bytes([msg[40], msg[41]]) as usize;
+    let info_offset = u32::from_le_bytes(msg[44..48].try_into().unwrap()) as usize;
+    let target_info = msg.get(info_offset..info_offset + info_len)
+        .ok_or_else(|| invalid("target info out of bounds"))?
+        .to_vec();
+    Ok(Challenge { flags, server_challenge, target_info })
+}
+
+/// NTLMv2 response and session base key for the given client challenge and time
+pub fn ntlmv2_response(
+    response_key: &[u8; 16],
+    challenge: &Challenge,
+    client_challenge: &[u8; 8],
+    timestamp: u64,
+) -> (Vec<u8>, [u8; 16]) {
+    let mut blob = Vec::with_capacity(32 + challenge.target_info.len());
+    blob.extend_from_slice(&[0x01, 0x01, 0, 0, 0, 0, 0, 0]);
+    blob.extend_from_slice(&timestamp.to_le_bytes());
+    blob.extend_from_slice(client_challenge);
+    blob.extend_from_slice(&[0u8; 4]);
+    blob.extend_from_slice(&challenge.target_info);
+    blob.extend_from_slice(&[0u8; 4]);
+
+    let proof = hmac_md5(response_key, &[&challenge.server_challenge, &blob]);
+    let session_base_key = hmac_md5(response_key, &[&proof]);
+
+    let mut response = proof.to_vec();
+    response.extend_from_slice(&blob);
+    (response, session_base_key)
+}
+
+/// LMv2 response: HMAC over both challenges, followed by the client challenge
+pub fn lmv2_response(response_key: &[u8; 16], challenge: &Challenge, client_challenge: &[u8; 8]) -> Vec<u8> {
+    let mut response = hmac_md5(response_key, &[&challenge.server_challenge, client_challenge]).to_vec();
+    response.extend_from_slice(client_challenge);
+    response
+}
+
+/// Build the AUTHENTICATE_MESSAGE. Returns the message and the session key
+/// (`None` for anonymous sessions, which cannot sign).
+pub fn authenticate_message(
+    creds: &Credentials<'_>,
+    challenge: &Challenge,
+    client_challenge: &[u8; 8],
+    now: u64,
+) -> (Vec<u8>, Option<[u8; 16]>) {
+    let anonymous = creds.user.is_empty();
+    let (lm, nt, session_key) = if anonymous {
+        (vec![0u8], Vec::new(), None)
+    } else {
+        let key = ntowf_v2(creds.password, creds.user, creds.domain);
+        // Servers that send a timestamp expect it echoed and no LMv2 response
+        let (lm, timestamp) = match challenge.timestamp() {
+            Some(ts) => (vec![0u8; 24], ts),
+            None => (lmv2_response(&key, challenge, client_challenge), now),
+        };
+        let (nt, session_key) = ntlmv2_response(&key, challenge, client_challenge, timestamp);
+        (lm, nt, Some(session_key))
+    };
+
+    let mut flags = CLIENT_FLAGS & (challenge.flags | NEGOTIATE_UNICODE);
+    if anonymous {
+        flags |= NEGOTIATE_ANONYMOUS;
+    }
+
+    let domain = utf16(creds.domain);
+    let user = utf16(creds.user);
+    let fields: [&[u8]; 6] = [&lm, &nt, &domain, &user, &[], &[]];
+
+    const HEADER_LEN: usize = 64;
+    let mut msg = Vec::with_capacity(HEADER_LEN + fields.iter().map(|f| f.len()).sum::<usize>());
+    msg.extend_from_slice(SIGNATURE);
+    msg.extend_from_slice(&3u32.to_le_bytes());
+    let mut offset = HEADER_LEN;
+    for field in fields {
+        msg.extend_from_slice(&(field.len() as u16).to_le_bytes());
+        msg.extend_from_slice(&(field.len() as u16).to_le_bytes());
+        msg.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += field.len();
+    }
+    msg.extend_from_slice(&flags.to_le_bytes());
+    for field in fields {
+        msg.extend_from_slice(field);
+    }
+    (msg, session_key)
+}
+
+/// Current time as a Windows FILETIME (100 ns intervals since 1601-01-01)
+pub fn filetime_now() -> u64 {
+    const UNIX_EPOCH_AS_FILETIME: u64 = 116_444_736_000_000_000;
+    let since_unix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    UNIX_EPOCH_AS_FILETIME + since_unix.as_nanos() as u64 / 100
+}
+
+// ============ SPNEGO ============
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// NegTokenInit offering NTLMSSP, carrying the NEGOTIATE_MESSAGE
+pub fn spnego_init(mech_token: &[u8]) -> Vec<u8> {
+    let mech_types = der(0xa0, &der(0x30, &der(0x06, NTLMSSP_OID)));
+    let token = der(0xa2, &der(0x04, mech_token));
+    let neg_token_init = der(0xa0, &der(0x30, &[mech_types, token].concat()));
+    der(0x60, &[der(0x06, SPNEGO_OID), neg_token_init].concat())
+}
+
+/// NegTokenResp carrying the AUTHENTICATE_MESSAGE
+pub fn spnego_response(response_token: &[u8]) -> Vec<u8> {
+    der(0xa1, &der(0x30, &der(0xa2, &der(0x04, response_token))))
+}
+
+/// Locate the NTLMSSP message inside a server SPNEGO token
+pub fn extract_ntlmssp(blob: &[u8]) -> Option<&[u8]> {
+    blob.windows(SIGNATURE.len())
+        .position(|w| w == SIGNATURE)
+        .map(|pos| &blob[pos..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // MS-NLMP 4.2.4 inputs: user "User", domain "Domain", password "Password"
+    const SERVER_CHALLENGE: [u8; 8] = [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef];
+    const CLIENT_CHALLENGE: [u8; 8] = [0xaa; 8];
+
+    fn challenge() -> Challenge {
+        Challenge {
+            flags: CLIENT_FLAGS,
+            server_challenge: SERVER_CHALLENGE,
+            // MsvAvNbDomainName "Domain", MsvAvNbComputerName "Server", MsvAvEOL
+            target_info: hex::decode(
+                "02000c0044006f006d00610069006e0001000c0053006500720076006500720000000000"
+            ).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_ntlmv2_keys() {
+        assert_eq!(hex::encode(nt_hash("Password")), "a4f49c406510bdcab6824ee7c30fd852");
+        let key = ntowf_v2("Password", "User", "Domain");
+        assert_eq!(hex::encode(key), "0c868a403bfd7a93a3001ef22ef02e3f");
+        assert_eq!(
+            hex::encode(&lmv2_response(&key, &challenge(), &CLIENT_CHALLENGE)[..16]),
+            "86c35097ac9cec102554764a57cccc19"
+        );
+
+        // NTProofStr and SessionBaseKey from MS-NLMP 4.2.4.2.2
+        let (response, session_key) = ntlmv2_response(&key, &challenge(), &CLIENT_CHALLENGE, 0);
+        assert_eq!(hex::encode(&response[..16]), "68cd0ab851e51c96aabc927bebef6a1c");
+        assert_eq!(hex::encode(session_key), "8de40ccadbc14a82f15cb0ad0de95ca3");
+    }
+
+    #[test]
+    fn test_challenge_roundtrip() {
+        let mut msg = SIGNATURE.to_vec();
+        msg.extend_from_slice(&2u32.to_le_bytes());
+        msg.extend_from_slice(&[0u8; 8]);
+        msg.extend_from_slice(&CLIENT_FLAGS.to_le_bytes());
+        msg.extend_from_slice(&SERVER_CHALLENGE);
+        msg.extend_from_slice(&[0u8; 8]);
+        let info = [7u8, 0, 8, 0, 1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 0];
+        msg.extend_from_slice(&(info.len() as u16).to_le_bytes());
+        msg.extend_from_slice(&(info.len() as u16).to_le_bytes());
+        msg.extend_from_slice(&48u32.to_le_bytes());
+        msg.extend_from_slice(&info);
+
+        let wrapped = [spnego_response(&[0xde, 0xad]), msg.clone()].concat();
+        let parsed = parse_challenge(extract_ntlmssp(&wrapped).unwrap()).unwrap();
+        assert_eq!(parsed.server_challenge, SERVER_CHALLENGE);
+        assert_eq!(parsed.timestamp(), Some(0x0807_0605_0403_0201));
+
+        let creds = Credentials { user: "User", domain: "Domain", password: "Password" };
+        let (auth, key) = authenticate_message(&creds, &parsed, &CLIENT_CHALLENGE, 0);
+        assert!(key.is_some());
+        // Timestamp present: LM response is 24 zero bytes at the start of the payload
+        assert_eq!(u16::from_le_bytes([auth[12], auth[13]]), 24);
+        assert_eq!(&auth[64..88], &[0u8; 24]);
+    }
+
+    #[test]
+    fn test_spnego_init_encoding() {
+        let token = spnego_init(&negotiate_message());
+        assert_eq!(token[0], 0x60);
+        assert_eq!(token[1] as usize, token.len() - 2);
+        assert_eq!(extract_ntlmssp(&token).unwrap(), negotiate_message().as_slice());
+    }
+}
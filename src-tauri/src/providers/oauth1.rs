@@ -190,7 +190,9 @@ pub async fn request_token(
 
     let body = format!("oauth_callback={}", percent_encode(callback_url));
 
-    let client = reqwest::Client::new();
+    let client = super::proxy::http_client_builder(super::proxy::global().as_ref())
+        .build()
+        .map_err(|e| format!("HTTP client init failed: {}", e))?;
     let resp = client
         .post(request_token_url)
         .header("Authorization", &auth)
@@ -241,7 +243,9 @@ pub async fn access_token(
         authorization_header("POST", access_token_url, &creds, &extra_with_verifier)
     };
 
-    let client = reqwest::Client::new();
+    let client = super::proxy::http_client_builder(super::proxy::global().as_ref())
+        .build()
+        .map_err(|e| format!("HTTP client init failed: {}", e))?;
     let resp = client
         .post(access_token_url)
         .header("Authorization", &auth)
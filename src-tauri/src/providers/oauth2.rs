@@ -46,7 +46,7 @@ impl<'c> oauth2::AsyncHttpClient<'c> for OAuth2HttpClient {
 
     fn call(&'c self, request: oauth2::HttpRequest) -> Self::Future {
         Box::pin(async move {
-            let client = reqwest::Client::builder()
+            let client = super::proxy::http_client_builder(super::proxy::global().as_ref())
                 .redirect(reqwest::redirect::Policy::none())
                 .build()
                 .map_err(|e| oauth2::HttpClientError::Other(e.to_string()))?;
@@ -123,6 +123,9 @@ pub struct OAuthConfig {
     pub redirect_uri: String,
     /// Extra query parameters for the authorization URL (e.g., token_access_type=offline for Dropbox)
     pub extra_auth_params: Vec<(String, String)>,
+    /// Account the tokens belong to (email or login). `None` is the provider's
+    /// unassigned slot, where a new sign-in lands until its account is known.
+    pub account: Option<String>,
 }
 
 impl OAuthConfig {
@@ -142,6 +145,7 @@ impl OAuthConfig {
             extra_auth_params: vec![
                 ("access_type".to_string(), "offline".to_string()),
             ],
+            account: None,
         }
     }
 
@@ -171,6 +175,7 @@ impl OAuthConfig {
             extra_auth_params: vec![
                 ("token_access_type".to_string(), "offline".to_string()),
             ],
+            account: None,
         }
     }
 
@@ -195,6 +200,7 @@ impl OAuthConfig {
             ],
             redirect_uri: format!("http://localhost:{}/callback", port),
             extra_auth_params: vec![],
+            account: None,
         }
     }
 
@@ -214,6 +220,7 @@ impl OAuthConfig {
             scopes: vec![],
             redirect_uri: format!("http://127.0.0.1:{}/callback", port),
             extra_auth_params: vec![],
+            account: None,
         }
     }
 
@@ -239,6 +246,7 @@ impl OAuthConfig {
             scopes: vec![],
             redirect_uri: format!("http://127.0.0.1:{}/callback", port),
             extra_auth_params: vec![],
+            account: None,
         }
     }
 
@@ -286,6 +294,7 @@ impl OAuthConfig {
                 ("access_type".to_string(), "offline".to_string()),
                 ("prompt".to_string(), "consent".to_string()),
             ],
+            account: None,
         }
     }
 
@@ -293,6 +302,45 @@ impl OAuthConfig {
     pub fn zoho(client_id: &str, client_secret: &str, region: &str) -> Self {
         Self::zoho_with_port(client_id, client_secret, 0, region)
     }
+
+    /// Use the tokens of a specific account (`None` = unassigned slot)
+    pub fn with_account(mut self, account: Option<&str>) -> Self {
+        self.account = account.map(normalize_account).filter(|a| !a.is_empty());
+        self
+    }
+}
+
+/// Account ids are matched case-insensitively (emails)
+fn normalize_account(account: &str) -> String {
+    account.trim().to_lowercase()
+}
+
+/// Credential store key for a provider's tokens: `oauth_google` for the
+/// unassigned slot, `oauth_google:alice@example.com` for a bound account
+fn token_key(provider: OAuthProvider, account: Option<&str>) -> String {
+    let base = format!("oauth_{:?}", provider).to_lowercase();
+    match account {
+        Some(account) => format!("{}:{}", base, normalize_account(account)),
+        None => base,
+    }
+}
+
+/// Slot a profile reads tokens from. A profile that names its account only
+/// ever uses that account's slot, so signing in a second account can't take
+/// over its tokens. A profile that names none (a fresh sign-in, or a profile
+/// saved before accounts were tracked) uses the unassigned slot, falling back
+/// to the provider's only bound account.
+fn resolve_slot(provider: OAuthProvider, account: Option<&str>, stored: impl Fn(&str) -> bool, bound: &[String]) -> String {
+    if let Some(account) = account {
+        return token_key(provider, Some(account));
+    }
+    let unassigned = token_key(provider, None);
+    if !stored(&unassigned) {
+        if let [only] = bound {
+            return token_key(provider, Some(only));
+        }
+    }
+    unassigned
 }
 
 /// Stored OAuth2 tokens
@@ -414,8 +462,8 @@ impl OAuth2Manager {
             scopes: config.scopes.clone(),
         };
         
-        // Store in keyring
-        self.store_tokens(config.provider, &tokens)?;
+        // Store in keyring (unassigned slot unless re-authorizing a known account)
+        self.store_tokens(config.provider, config.account.as_deref(), &tokens)?;
         
         info!("OAuth2 tokens obtained for {:?}", config.provider);
         
@@ -450,8 +498,9 @@ impl OAuth2Manager {
             scopes: config.scopes.clone(),
         };
         
-        // Update keyring
-        self.store_tokens(config.provider, &tokens)?;
+        // Update keyring, in the slot the refresh token came from
+        let key = self.resolve_key(config.provider, config.account.as_deref());
+        self.store_tokens_at(&key, &tokens)?;
         
         info!("OAuth2 tokens refreshed for {:?}", config.provider);
         
@@ -465,7 +514,7 @@ impl OAuth2Manager {
         config: &OAuthConfig,
     ) -> Result<SecretString, ProviderError> {
         let _guard = self.refresh_guard.lock().await;
-        let mut tokens = self.load_tokens(config.provider)?;
+        let mut tokens = self.load_tokens(config.provider, config.account.as_deref())?;
 
         if tokens.is_expired() {
             if let Some(ref refresh_token) = tokens.refresh_token {
@@ -492,27 +541,30 @@ impl OAuth2Manager {
         Ok(token_dir)
     }
 
+    /// Store tokens for an account (`None` = unassigned slot)
+    pub fn store_tokens(&self, provider: OAuthProvider, account: Option<&str>, tokens: &StoredTokens) -> Result<(), ProviderError> {
+        self.store_tokens_at(&token_key(provider, account), tokens)
+    }
+
     /// Store tokens in secure credential store (OS keyring or encrypted vault)
-    pub fn store_tokens(&self, provider: OAuthProvider, tokens: &StoredTokens) -> Result<(), ProviderError> {
+    fn store_tokens_at(&self, key: &str, tokens: &StoredTokens) -> Result<(), ProviderError> {
         let json = serde_json::to_string_pretty(tokens)
             .map_err(|e| ProviderError::Other(format!("Failed to serialize tokens: {}", e)))?;
 
-        let account = format!("oauth_{:?}", provider).to_lowercase();
-
         // Store in universal vault
         if let Some(store) = crate::credential_store::CredentialStore::from_cache() {
-            store.store(&account, &json)
+            store.store(key, &json)
                 .map_err(|e| ProviderError::Other(format!("Failed to store tokens: {}", e)))?;
-            info!("Tokens stored in credential vault for {:?}", provider);
+            info!("Tokens stored in credential vault for {}", key);
             return Ok(());
         }
 
         // Vault not open — try auto-initializing vault first
         if crate::credential_store::CredentialStore::init().is_ok() {
             if let Some(store) = crate::credential_store::CredentialStore::from_cache() {
-                store.store(&account, &json)
+                store.store(key, &json)
                     .map_err(|e| ProviderError::Other(format!("Failed to store tokens: {}", e)))?;
-                info!("Tokens stored in auto-initialized vault for {:?}", provider);
+                info!("Tokens stored in auto-initialized vault for {}", key);
                 return Ok(());
             }
         }
@@ -520,34 +572,62 @@ impl OAuth2Manager {
         // Vault requires master password — store in memory only (never on disk unencrypted)
         if let Ok(mut cache) = MEMORY_TOKEN_CACHE.lock() {
             let map = cache.get_or_insert_with(HashMap::new);
-            map.insert(account, json);
+            map.insert(key.to_string(), json);
         }
 
-        info!("Tokens stored in memory for {:?} (vault locked)", provider);
+        info!("Tokens stored in memory for {} (vault locked)", key);
         Ok(())
     }
 
-    /// Load tokens from credential vault or legacy file
-    pub fn load_tokens(&self, provider: OAuthProvider) -> Result<StoredTokens, ProviderError> {
-        let account = format!("oauth_{:?}", provider).to_lowercase();
-
-        // Try vault first
+    /// Raw token JSON from the vault or the in-memory cache
+    fn stored_json(key: &str) -> Option<String> {
         if let Some(store) = crate::credential_store::CredentialStore::from_cache() {
-            if let Ok(json) = store.get(&account) {
-                return serde_json::from_str(&json)
-                    .map_err(|e| ProviderError::Other(format!("Failed to parse tokens: {}", e)));
+            if let Ok(json) = store.get(key) {
+                return Some(json);
             }
         }
+        MEMORY_TOKEN_CACHE.lock().ok()
+            .and_then(|cache| cache.as_ref().and_then(|map| map.get(key).cloned()))
+    }
 
-        // Fallback: try in-memory cache
+    /// Accounts with tokens bound to them for this provider
+    pub fn list_accounts(&self, provider: OAuthProvider) -> Vec<String> {
+        let prefix = format!("{}:", token_key(provider, None));
+        let mut keys: Vec<String> = crate::credential_store::CredentialStore::from_cache()
+            .and_then(|store| store.list_accounts().ok())
+            .unwrap_or_default();
         if let Ok(cache) = MEMORY_TOKEN_CACHE.lock() {
             if let Some(map) = cache.as_ref() {
-                if let Some(json) = map.get(&account) {
-                    return serde_json::from_str(json)
-                        .map_err(|e| ProviderError::Other(format!("Failed to parse tokens: {}", e)));
-                }
+                keys.extend(map.keys().cloned());
             }
         }
+        let mut accounts: Vec<String> = keys.iter()
+            .filter_map(|k| k.strip_prefix(&prefix).map(str::to_string))
+            .collect();
+        accounts.sort();
+        accounts.dedup();
+        accounts
+    }
+
+    /// Slot to read tokens from, see [`resolve_slot`]
+    fn resolve_key(&self, provider: OAuthProvider, account: Option<&str>) -> String {
+        let bound = if account.is_none() { self.list_accounts(provider) } else { Vec::new() };
+        resolve_slot(provider, account, |key| Self::stored_json(key).is_some(), &bound)
+    }
+
+    /// Load tokens for an account from credential vault or legacy file
+    pub fn load_tokens(&self, provider: OAuthProvider, account: Option<&str>) -> Result<StoredTokens, ProviderError> {
+        let key = self.resolve_key(provider, account);
+
+        if let Some(json) = Self::stored_json(&key) {
+            return serde_json::from_str(&json)
+                .map_err(|e| ProviderError::Other(format!("Failed to parse tokens: {}", e)));
+        }
+
+        // Legacy plaintext files only ever held the unassigned slot
+        if key != token_key(provider, None) {
+            return Err(ProviderError::AuthenticationFailed(format!("No stored tokens for {}", key)));
+        }
 
         // Legacy: try plaintext file — migrate to vault immediately, then delete the file
         let legacy_path = Self::token_dir()?.join(format!("oauth2_{:?}.json", provider).to_lowercase());
@@ -565,7 +645,7 @@ impl OAuth2Manager {
         // Migrate to vault
         let mut migrated = false;
         if let Some(store) = crate::credential_store::CredentialStore::from_cache() {
-            if store.store(&account, &json).is_ok() {
+            if store.store(&key, &json).is_ok() {
                 migrated = true;
                 info!("Legacy tokens for {:?} migrated to credential vault", provider);
             }
@@ -574,7 +654,7 @@ impl OAuth2Manager {
             // Try auto-init vault
             if crate::credential_store::CredentialStore::init().is_ok() {
                 if let Some(store) = crate::credential_store::CredentialStore::from_cache() {
-                    if store.store(&account, &json).is_ok() {
+                    if store.store(&key, &json).is_ok() {
                         migrated = true;
                         info!("Legacy tokens for {:?} migrated to auto-initialized vault", provider);
                     }
@@ -602,46 +682,81 @@ impl OAuth2Manager {
         Ok(tokens)
     }
 
-    /// Delete tokens from credential vault, memory cache, and legacy files
-    pub fn delete_tokens(&self, provider: OAuthProvider) -> Result<(), ProviderError> {
-        let account = format!("oauth_{:?}", provider).to_lowercase();
+    /// File the tokens a provider connected with under the account it reported.
+    /// Tokens read from the unassigned slot (or from a slot named after a
+    /// different account) move to `account`'s slot, which leaves the unassigned
+    /// slot free for the next account to sign in. Providers call this at the
+    /// end of `connect()`. Returns the account the tokens are now stored under;
+    /// the caller keeps it for later refreshes.
+    pub fn bind_account(&self, config: &OAuthConfig, account: Option<&str>) -> Option<String> {
+        let Some(account) = account.map(normalize_account).filter(|a| !a.is_empty()) else {
+            return config.account.clone();
+        };
+        let source = self.resolve_key(config.provider, config.account.as_deref());
+        let target = token_key(config.provider, Some(&account));
+        if source == target {
+            return Some(account);
+        }
 
-        // Delete from vault
-        if let Some(store) = crate::credential_store::CredentialStore::from_cache() {
-            let _ = store.delete(&account);
+        let moved = Self::stored_json(&source)
+            .and_then(|json| serde_json::from_str::<StoredTokens>(&json).ok())
+            .map(|tokens| self.store_tokens_at(&target, &tokens));
+        match moved {
+            Some(Ok(())) => {
+                self.delete_key(&source);
+                info!("OAuth tokens for {:?} bound to account {}", config.provider, account);
+                Some(account)
+            }
+            Some(Err(e)) => {
+                warn!("Could not bind {:?} tokens to {}: {}", config.provider, account, e);
+                config.account.clone()
+            }
+            None => config.account.clone(),
         }
+    }
 
-        // Delete from in-memory cache
+    fn delete_key(&self, key: &str) {
+        if let Some(store) = crate::credential_store::CredentialStore::from_cache() {
+            let _ = store.delete(key);
+        }
         if let Ok(mut cache) = MEMORY_TOKEN_CACHE.lock() {
             if let Some(map) = cache.as_mut() {
-                map.remove(&account);
+                map.remove(key);
             }
         }
+    }
 
-        // Delete legacy .json file if exists
-        let json_path = Self::token_dir()?.join(format!("oauth2_{:?}.json", provider).to_lowercase());
-        if json_path.exists() {
-            let _ = crate::credential_store::secure_delete(&json_path);
-        }
+    /// Delete an account's tokens (`None` = unassigned slot and legacy files)
+    pub fn delete_tokens(&self, provider: OAuthProvider, account: Option<&str>) -> Result<(), ProviderError> {
+        // Delete from vault and in-memory cache
+        self.delete_key(&token_key(provider, account));
+
+        if account.is_none() {
+            // Delete legacy .json file if exists
+            let json_path = Self::token_dir()?.join(format!("oauth2_{:?}.json", provider).to_lowercase());
+            if json_path.exists() {
+                let _ = crate::credential_store::secure_delete(&json_path);
+            }
 
-        // Delete legacy .enc file if exists
-        let enc_path = Self::token_dir()?.join(format!("oauth2_{:?}.enc", provider).to_lowercase());
-        if enc_path.exists() {
-            let _ = crate::credential_store::secure_delete(&enc_path);
+            // Delete legacy .enc file if exists
+            let enc_path = Self::token_dir()?.join(format!("oauth2_{:?}.enc", provider).to_lowercase());
+            if enc_path.exists() {
+                let _ = crate::credential_store::secure_delete(&enc_path);
+            }
         }
 
-        info!("Tokens deleted for {:?}", provider);
+        info!("Tokens deleted for {:?} ({})", provider, account.unwrap_or("unassigned"));
         Ok(())
     }
 
     /// Alias for delete_tokens
-    pub fn clear_tokens(&self, provider: OAuthProvider) -> Result<(), ProviderError> {
-        self.delete_tokens(provider)
+    pub fn clear_tokens(&self, provider: OAuthProvider, account: Option<&str>) -> Result<(), ProviderError> {
+        self.delete_tokens(provider, account)
     }
 
-    /// Check if tokens exist for provider
-    pub fn has_tokens(&self, provider: OAuthProvider) -> bool {
-        self.load_tokens(provider).is_ok()
+    /// Check if tokens exist for an account (or are waiting in the unassigned slot)
+    pub fn has_tokens(&self, provider: OAuthProvider, account: Option<&str>) -> bool {
+        self.load_tokens(provider, account).is_ok()
     }
 
     /// Create OAuth2 client from config (v5 builder API)
@@ -1016,6 +1131,55 @@ mod tests {
         assert_eq!(state, "xyz789");
     }
     
+    #[test]
+    fn test_token_keys_per_account() {
+        assert_eq!(token_key(OAuthProvider::Google, None), "oauth_google");
+        assert_eq!(
+            token_key(OAuthProvider::Google, Some(" Alice@Example.com ")),
+            "oauth_google:alice@example.com"
+        );
+        let config = OAuthConfig::dropbox("id", "secret").with_account(Some("Bob@Example.com"));
+        assert_eq!(config.account.as_deref(), Some("bob@example.com"));
+        assert!(OAuthConfig::dropbox("id", "secret").with_account(Some("  ")).account.is_none());
+    }
+
+    #[test]
+    fn test_two_bound_accounts_resolve_to_their_own_tokens() {
+        let provider = OAuthProvider::Dropbox;
+        let mut slots: HashMap<String, &str> = HashMap::new();
+
+        // First sign-in lands unassigned; the profile naming no account finds it
+        slots.insert(token_key(provider, None), "token-alice");
+        let key = resolve_slot(provider, None, |k| slots.contains_key(k), &[]);
+        assert_eq!(slots[&key], "token-alice");
+
+        // Bound to alice, it stays reachable for profiles saved before accounts were tracked
+        let tokens = slots.remove(&key).unwrap();
+        slots.insert(token_key(provider, Some("alice@example.com")), tokens);
+        let bound = vec!["alice@example.com".to_string()];
+        let key = resolve_slot(provider, None, |k| slots.contains_key(k), &bound);
+        assert_eq!(slots[&key], "token-alice");
+
+        // A second account signs in and binds
+        slots.insert(token_key(provider, None), "token-bob");
+        let key = resolve_slot(provider, None, |k| slots.contains_key(k), &bound);
+        assert_eq!(slots[&key], "token-bob");
+        let tokens = slots.remove(&key).unwrap();
+        slots.insert(token_key(provider, Some("Bob@Example.com")), tokens);
+        let bound = vec!["alice@example.com".to_string(), "bob@example.com".to_string()];
+
+        // Each profile resolves to its own account, the empty unassigned slot is never used
+        let alice = resolve_slot(provider, Some("alice@example.com"), |k| slots.contains_key(k), &bound);
+        let bob = resolve_slot(provider, Some("BOB@example.com"), |k| slots.contains_key(k), &bound);
+        assert_eq!(slots.get(&alice), Some(&"token-alice"));
+        assert_eq!(slots.get(&bob), Some(&"token-bob"));
+
+        // A profile for an account without tokens doesn't borrow another sign-in's
+        slots.insert(token_key(provider, None), "token-carol");
+        let dave = resolve_slot(provider, Some("dave@example.com"), |k| slots.contains_key(k), &bound);
+        assert!(!slots.contains_key(&dave));
+    }
+
     #[test]
     fn test_oauth_config_google() {
         let config = OAuthConfig::google("client_id", "client_secret");
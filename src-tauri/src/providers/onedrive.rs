@@ -11,7 +11,7 @@ use tracing::info;
 
 use super::{
     StorageProvider, ProviderType, ProviderError, RemoteEntry, ProviderConfig, StorageInfo,
-    sanitize_api_error,
+    ContentHash, HashAlgorithm, sanitize_api_error,
     oauth2::{OAuth2Manager, OAuthConfig, OAuthProvider},
 };
 
@@ -24,6 +24,7 @@ const GRAPH_API_BASE: &str = "https://graph.microsoft.com/v1.0";
 #[allow(dead_code)]
 struct DriveItem {
     id: String,
+    #[serde(default)]
     name: String,
     #[serde(default)]
     size: u64,
@@ -36,6 +37,12 @@ struct DriveItem {
     parent_reference: Option<ParentReference>,
     #[serde(rename = "@microsoft.graph.downloadUrl")]
     download_url: Option<String>,
+    /// Present on items removed since the last delta round
+    #[serde(default)]
+    deleted: Option<serde_json::Value>,
+    /// Present only on the drive root
+    #[serde(default)]
+    root: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -49,6 +56,25 @@ struct FolderFacet {
 #[serde(rename_all = "camelCase")]
 struct FileFacet {
     mime_type: Option<String>,
+    #[serde(default)]
+    hashes: Option<FileHashes>,
+}
+
+/// Personal accounts report SHA-1/SHA-256, business accounts only QuickXorHash
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct FileHashes {
+    quick_xor_hash: Option<String>,
+    sha1_hash: Option<String>,
+    sha256_hash: Option<String>,
+}
+
+impl FileHashes {
+    fn best(&self) -> Option<ContentHash> {
+        self.sha256_hash.as_deref().and_then(|h| ContentHash::hex(HashAlgorithm::Sha256, h))
+            .or_else(|| self.sha1_hash.as_deref().and_then(|h| ContentHash::hex(HashAlgorithm::Sha1, h)))
+            .or_else(|| self.quick_xor_hash.as_deref().and_then(|h| ContentHash::base64(HashAlgorithm::QuickXor, h)))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -68,11 +94,25 @@ struct ChildrenResponse {
     next_link: Option<String>,
 }
 
+/// Delta query response: pages via `nextLink`, ends with a `deltaLink` for the next round
+#[derive(Debug, Deserialize)]
+struct DeltaResponse {
+    value: Vec<DriveItem>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+    #[serde(rename = "@odata.deltaLink")]
+    delta_link: Option<String>,
+}
+
 /// OneDrive provider configuration
 #[derive(Debug, Clone)]
 pub struct OneDriveConfig {
     pub client_id: String,
     pub client_secret: String,
+    /// Outbound proxy (`None` connects directly)
+    pub proxy: Option<super::proxy::ProxyConfig>,
+    /// Account whose tokens to use (email); `None` takes a fresh sign-in
+    pub account: Option<String>,
 }
 
 impl OneDriveConfig {
@@ -80,6 +120,8 @@ impl OneDriveConfig {
         Self {
             client_id: client_id.to_string(),
             client_secret: client_secret.to_string(),
+            account: None,
+            proxy: super::proxy::global(),
         }
     }
 
@@ -90,7 +132,10 @@ impl OneDriveConfig {
         let client_secret = config.extra.get("client_secret")
             .ok_or_else(|| ProviderError::Other("Missing client_secret".to_string()))?;
 
-        Ok(Self::new(client_id, client_secret))
+        Ok(Self {
+            proxy: super::proxy::from_extra(&config.extra)?,
+            ..Self::new(client_id, client_secret)
+        })
     }
 }
 
@@ -110,13 +155,14 @@ pub struct OneDriveProvider {
 
 impl OneDriveProvider {
     pub fn new(config: OneDriveConfig) -> Self {
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
         Self {
             config,
             oauth_manager: OAuth2Manager::new(),
-            client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
-                .build()
-                .unwrap_or_default(),
+            client,
             connected: false,
             current_path: "/".to_string(),
             current_item_id: "root".to_string(),
@@ -128,6 +174,7 @@ impl OneDriveProvider {
     /// Get OAuth config
     fn oauth_config(&self) -> OAuthConfig {
         OAuthConfig::onedrive(&self.config.client_id, &self.config.client_secret)
+            .with_account(self.config.account.as_deref())
     }
 
     /// Get authorization header
@@ -140,7 +187,7 @@ impl OneDriveProvider {
 
     /// Check if authenticated
     pub fn is_authenticated(&self) -> bool {
-        self.oauth_manager.has_tokens(OAuthProvider::OneDrive)
+        self.oauth_manager.has_tokens(OAuthProvider::OneDrive, self.config.account.as_deref())
     }
 
     /// Start OAuth flow (called via oauth2_start_auth command)
@@ -186,6 +233,9 @@ impl OneDriveProvider {
 
         let mut metadata = HashMap::new();
         metadata.insert("id".to_string(), item.id.clone());
+        if let Some(parent_id) = item.parent_reference.as_ref().and_then(|p| p.id.clone()) {
+            metadata.insert("parentId".to_string(), parent_id);
+        }
         if let Some(ref url) = item.download_url {
             metadata.insert("downloadUrl".to_string(), url.clone());
         }
@@ -203,6 +253,7 @@ impl OneDriveProvider {
             link_target: None,
             mime_type: item.file.as_ref().and_then(|f| f.mime_type.clone()),
             metadata,
+            content_hash: item.file.as_ref().and_then(|f| f.hashes.as_ref()).and_then(FileHashes::best),
         }
     }
 
@@ -281,6 +332,24 @@ impl OneDriveProvider {
         Ok(all_items)
     }
 
+    /// Fetch one page of a delta query
+    async fn delta_page(&self, url: &str) -> Result<DeltaResponse, ProviderError> {
+        let response = self.client
+            .get(url)
+            .header(AUTHORIZATION, self.auth_header().await?)
+            .send()
+            .await
+            .map_err(|e| ProviderError::ConnectionFailed(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(ProviderError::Other(format!("Delta query failed: {}", sanitize_api_error(&text))));
+        }
+
+        response.json().await
+            .map_err(|e| ProviderError::Other(format!("Parse error: {}", e)))
+    }
+
     /// Evict oldest half of cache when it exceeds the maximum size
     fn trim_cache_if_needed(&mut self) {
         const MAX_CACHE_SIZE: usize = 10_000;
@@ -387,6 +456,7 @@ impl StorageProvider for OneDriveProvider {
         self.current_path = "/".to_string();
         self.current_item_id = "root".to_string();
 
+        self.config.account = self.oauth_manager.bind_account(&self.oauth_config(), self.account_email.as_deref());
         info!("Connected to OneDrive");
         Ok(())
     }
@@ -835,6 +905,15 @@ impl StorageProvider for OneDriveProvider {
         Ok(url)
     }
 
+    fn supports_checksum(&self) -> bool {
+        true
+    }
+
+    async fn checksum(&mut self, path: &str) -> Result<HashMap<String, String>, ProviderError> {
+        let entry = self.stat(path).await?;
+        Ok(entry.content_hash.map(|h| h.to_checksums()).unwrap_or_default())
+    }
+
     fn supports_server_copy(&self) -> bool {
         true
     }
@@ -1295,4 +1374,85 @@ impl StorageProvider for OneDriveProvider {
         info!("Resumable upload completed: {}", remote_path);
         Ok(())
     }
+
+    fn supports_change_tracking(&self) -> bool {
+        true
+    }
+
+    async fn get_change_token(&mut self) -> Result<String, ProviderError> {
+        let mut url = format!("{}/me/drive/root/delta?token=latest", GRAPH_API_BASE);
+
+        loop {
+            let result = self.delta_page(&url).await?;
+            if let Some(delta) = result.delta_link {
+                return Ok(delta);
+            }
+            url = result.next_link
+                .ok_or_else(|| ProviderError::Other("Delta response without next or delta link".to_string()))?;
+        }
+    }
+
+    async fn list_changes(&mut self, page_token: &str) -> Result<(Vec<super::ChangeEntry>, String), ProviderError> {
+        // Tokens are the deltaLink URLs returned by Graph; never follow anything else with our bearer token
+        if !page_token.starts_with(GRAPH_API_BASE) {
+            return Err(ProviderError::InvalidConfig("Invalid OneDrive change token".to_string()));
+        }
+
+        let mut changes = Vec::new();
+        let mut url = page_token.to_string();
+
+        loop {
+            let result = self.delta_page(&url).await?;
+
+            // Delta omits parentReference.path, so items are tracked by ID
+            for item in result.value {
+                let removed = item.deleted.is_some();
+                changes.push(super::ChangeEntry {
+                    change_type: if removed { "deleted" } else { "modified" }.to_string(),
+                    mime_type: item.file.as_ref().and_then(|f| f.mime_type.clone()),
+                    timestamp: item.last_modified_date_time.clone(),
+                    removed,
+                    path: item.root.as_ref().map(|_| "/".to_string()),
+                    parent_id: item.parent_reference.and_then(|p| p.id),
+                    is_dir: item.folder.is_some() || item.root.is_some(),
+                    size: item.file.as_ref().filter(|_| !removed).map(|_| item.size),
+                    file_id: item.id,
+                    name: item.name,
+                });
+            }
+
+            if let Some(delta) = result.delta_link {
+                return Ok((changes, delta));
+            }
+            url = result.next_link
+                .ok_or_else(|| ProviderError::Other("Delta response without next or delta link".to_string()))?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_hashes_best() {
+        let hashes = |json: &str| serde_json::from_str::<FileHashes>(json).unwrap().best();
+
+        // Personal accounts: the strongest digest wins
+        let best = hashes(r#"{"quickXorHash":"pStAEQk/tFEEAxwEDIEQwgCIT/g=","sha1Hash":"A9993E364706816ABA3E25717850C26C9CD0D89D","sha256Hash":"BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD"}"#).unwrap();
+        assert_eq!(best.algorithm, HashAlgorithm::Sha256);
+        assert_eq!(best.value, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        let best = hashes(r#"{"quickXorHash":"pStAEQk/tFEEAxwEDIEQwgCIT/g=","sha1Hash":"A9993E364706816ABA3E25717850C26C9CD0D89D"}"#).unwrap();
+        assert_eq!(best.algorithm, HashAlgorithm::Sha1);
+
+        // Business accounts: base64 QuickXorHash only
+        let best = hashes(r#"{"quickXorHash":"pStAEQk/tFEEAxwEDIEQwgCIT/g="}"#).unwrap();
+        assert_eq!(best.algorithm, HashAlgorithm::QuickXor);
+        assert_eq!(best.value, "a52b4011093fb45104031c040c8110c200884ff8");
+
+        // Unusable values fall through to the next algorithm
+        let best = hashes(r#"{"quickXorHash":"pStAEQk/tFEEAxwEDIEQwgCIT/g=","sha256Hash":""}"#).unwrap();
+        assert_eq!(best.algorithm, HashAlgorithm::QuickXor);
+        assert!(hashes("{}").is_none());
+    }
 }
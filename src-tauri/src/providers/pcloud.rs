@@ -158,7 +158,7 @@ pub struct PCloudProvider {
 
 impl PCloudProvider {
     pub fn new(config: PCloudConfig) -> Self {
-        let client = reqwest::Client::builder()
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .unwrap_or_else(|_| reqwest::Client::new());
@@ -172,10 +172,15 @@ impl PCloudProvider {
         }
     }
 
+    fn oauth_config(&self) -> OAuthConfig {
+        OAuthConfig::pcloud(&self.config.client_id, &self.config.client_secret, &self.config.region)
+            .with_account(self.config.account.as_deref())
+    }
+
     /// Get Authorization header with Bearer token (token never exposed in URL)
     async fn auth_header(&self) -> Result<String, ProviderError> {
         use secrecy::ExposeSecret;
-        let config = OAuthConfig::pcloud(&self.config.client_id, &self.config.client_secret, &self.config.region);
+        let config = self.oauth_config();
         let secret = self.oauth_manager.get_valid_token(&config).await
             .map_err(|e| ProviderError::AuthenticationFailed(format!("pCloud token error: {}", e)))?;
         Ok(format!("Bearer {}", secret.expose_secret()))
@@ -231,6 +236,7 @@ impl PCloudProvider {
                         // PA-012: Populate MIME type from pCloud contenttype field
                         mime_type: item.contenttype.clone(),
                         metadata: Default::default(),
+                        content_hash: None,
                     });
                 }
 
@@ -241,6 +247,23 @@ impl PCloudProvider {
         }
     }
 
+    /// pCloud file and folder IDs share a number space only per type, so prefix them
+    /// the way the API does in `id` fields ("f123" / "d456")
+    fn item_id(fileid: Option<u64>, folderid: Option<u64>) -> Option<String> {
+        fileid.map(|id| format!("f{}", id)).or_else(|| folderid.map(|id| format!("d{}", id)))
+    }
+
+    fn id_metadata(fileid: Option<u64>, folderid: Option<u64>, parentfolderid: Option<u64>) -> HashMap<String, String> {
+        let mut metadata = HashMap::new();
+        if let Some(id) = Self::item_id(fileid, folderid) {
+            metadata.insert("id".to_string(), id);
+        }
+        if let Some(parent) = parentfolderid {
+            metadata.insert("parentId".to_string(), format!("d{}", parent));
+        }
+        metadata
+    }
+
     /// Check pCloud API response for errors (PA-014: sanitized messages)
     fn check_response(resp: &PCloudResponse) -> Result<(), ProviderError> {
         if resp.result != 0 {
@@ -289,6 +312,7 @@ impl StorageProvider for PCloudProvider {
 
         self.connected = true;
         self.current_path = "/".to_string();
+        self.config.account = self.oauth_manager.bind_account(&self.oauth_config(), self.account_email.as_deref());
         info!("Connected to pCloud ({})", self.config.region);
         Ok(())
     }
@@ -335,7 +359,8 @@ impl StorageProvider for PCloudProvider {
                 is_symlink: false, link_target: None,
                 // PA-012: Populate MIME type from pCloud contenttype field
                 mime_type: item.contenttype,
-                metadata: Default::default(),
+                metadata: Self::id_metadata(item.fileid, item.folderid, item.parentfolderid),
+                content_hash: None,
             }
         }).collect())
     }
@@ -653,6 +678,7 @@ impl StorageProvider for PCloudProvider {
                 is_symlink: false, link_target: None,
                 mime_type: None,
                 metadata: Default::default(),
+                content_hash: None,
             });
         }
 
@@ -670,6 +696,7 @@ impl StorageProvider for PCloudProvider {
             // PA-012: Populate MIME type from pCloud contenttype field
             mime_type: meta.contenttype,
             metadata: Default::default(),
+            content_hash: None,
         })
     }
 
@@ -1001,4 +1028,90 @@ impl StorageProvider for PCloudProvider {
         self.collect_matching(&metadata, &resolved, &pattern_lower, &mut results);
         Ok(results)
     }
+
+    fn supports_change_tracking(&self) -> bool { true }
+
+    async fn get_change_token(&mut self) -> Result<String, ProviderError> {
+        // `last=0` returns no entries, only the current diff ID
+        let url = format!("{}/diff?last=0", self.config.api_base());
+        let auth = self.auth_header().await?;
+        let resp: PCloudDiffResponse = self.get_with_retry(&url, &auth).await?
+            .json().await
+            .map_err(|e| ProviderError::ParseError(sanitize_api_error(&e.to_string())))?;
+
+        if resp.result != 0 {
+            return Err(ProviderError::Other(
+                sanitize_api_error(&resp.error.unwrap_or_else(|| "Diff failed".to_string()))
+            ));
+        }
+        Ok(resp.diffid.unwrap_or(0).to_string())
+    }
+
+    async fn list_changes(&mut self, page_token: &str) -> Result<(Vec<super::ChangeEntry>, String), ProviderError> {
+        const DIFF_LIMIT: usize = 5000;
+        let mut changes = Vec::new();
+        let mut diffid: u64 = page_token.parse()
+            .map_err(|_| ProviderError::InvalidConfig("Invalid pCloud change token".to_string()))?;
+
+        loop {
+            let url = format!("{}/diff?diffid={}&limit={}", self.config.api_base(), diffid, DIFF_LIMIT);
+            let auth = self.auth_header().await?;
+            let resp: PCloudDiffResponse = self.get_with_retry(&url, &auth).await?
+                .json().await
+                .map_err(|e| ProviderError::ParseError(sanitize_api_error(&e.to_string())))?;
+
+            if resp.result != 0 {
+                return Err(ProviderError::Other(
+                    sanitize_api_error(&resp.error.unwrap_or_else(|| "Diff failed".to_string()))
+                ));
+            }
+
+            let page_len = resp.entries.len();
+            for entry in resp.entries {
+                diffid = diffid.max(entry.diffid);
+                let removed = match entry.event.as_str() {
+                    "deletefile" | "deletefolder" => true,
+                    "createfile" | "modifyfile" | "createfolder" | "modifyfolder" => false,
+                    // Share and account events don't touch the tree
+                    _ => continue,
+                };
+                let Some(meta) = entry.metadata else { continue };
+                let Some(file_id) = Self::item_id(meta.fileid, meta.folderid) else { continue };
+                changes.push(super::ChangeEntry {
+                    file_id,
+                    change_type: if removed { "deleted" } else { "modified" }.to_string(),
+                    mime_type: meta.contenttype,
+                    timestamp: meta.modified,
+                    removed,
+                    path: meta.path,
+                    parent_id: meta.parentfolderid.map(|id| format!("d{}", id)),
+                    is_dir: meta.isfolder,
+                    size: (!meta.isfolder && !removed).then_some(meta.size),
+                    name: meta.name,
+                });
+            }
+
+            if page_len < DIFF_LIMIT {
+                return Ok((changes, diffid.to_string()));
+            }
+        }
+    }
+}
+
+/// pCloud `diff` response
+#[derive(Debug, Deserialize)]
+struct PCloudDiffResponse {
+    result: u32,
+    #[serde(default)]
+    error: Option<String>,
+    diffid: Option<u64>,
+    #[serde(default)]
+    entries: Vec<PCloudDiffEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PCloudDiffEntry {
+    event: String,
+    diffid: u64,
+    metadata: Option<PCloudMetadata>,
 }
@@ -0,0 +1,1319 @@
+//! Client for the rsync wire protocol over an SSH exec channel
+//!
+//! Speaks protocol 30 to `rsync --server` (rsync 3.0 and later), so hosts that
+//! refuse the `sftp` subsystem can still be listed and transferred to. As with
+//! rsync itself, downloads send block checksums of the existing local copy and
+//! uploads are matched against the server's block checksums, so only changed
+//! data crosses the wire.
+//!
+//! Each session transfers one file or one directory listing. Block checksums
+//! are MD5 (the protocol 30 default); compression, ownership and recursion
+//! are never negotiated.
+
+use super::sftp::escape_glob;
+use super::ssh_pool::{shell_quote, SshConnection};
+use super::ProviderError;
+use md5::{Digest, Md5};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+const PROTOCOL_VERSION: i32 = 30;
+
+/// Multiplexed frames carry `MPLEX_BASE + code` in the top byte of their header
+const MPLEX_BASE: u8 = 7;
+const MSG_DATA: u8 = 0;
+const MSG_ERROR_XFER: u8 = 1;
+const MSG_INFO: u8 = 2;
+const MSG_ERROR: u8 = 3;
+const MSG_WARNING: u8 = 4;
+const MSG_ERROR_SOCKET: u8 = 5;
+const MSG_LOG: u8 = 6;
+const MSG_ERROR_UTF8: u8 = 8;
+const MSG_ERROR_EXIT: u8 = 86;
+
+/// Largest payload written per outgoing frame
+const MAX_FRAME: usize = 32 * 1024;
+/// Literal data is sent in tokens of at most this size
+const CHUNK_SIZE: usize = 32 * 1024;
+
+const NDX_DONE: i32 = -1;
+
+const ITEM_BASIS_TYPE_FOLLOWS: u16 = 1 << 11;
+const ITEM_XNAME_FOLLOWS: u16 = 1 << 12;
+const ITEM_TRANSFER: u16 = 1 << 15;
+
+const XMIT_TOP_DIR: u16 = 1 << 0;
+const XMIT_SAME_MODE: u16 = 1 << 1;
+const XMIT_EXTENDED_FLAGS: u16 = 1 << 2;
+const XMIT_SAME_NAME: u16 = 1 << 5;
+const XMIT_LONG_NAME: u16 = 1 << 6;
+const XMIT_SAME_TIME: u16 = 1 << 7;
+const XMIT_IO_ERROR_ENDLIST: u16 = 1 << 12;
+const XMIT_MOD_NSEC: u16 = 1 << 13;
+
+/// Compatibility flag: the checksum seed is hashed before the block data
+const CF_CHKSUM_SEED_FIX: u32 = 1 << 5;
+
+const BLOCK_SIZE: u64 = 700;
+const MAX_BLOCK_SIZE: u64 = 1 << 17;
+/// MD5 digest length, used in full for block checksums
+const SUM_LENGTH: usize = 16;
+
+/// Uploads above this size are sent as literal data instead of being
+/// matched in memory against the server's block checksums
+const MAX_DELTA_SIZE: u64 = 256 * 1024 * 1024;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+/// File list entry as sent by the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RsyncEntry {
+    /// Path relative to the listed directory (`.` is the directory itself)
+    pub name: String,
+    pub size: u64,
+    /// Seconds since the epoch
+    pub mtime: i64,
+    /// `st_mode`, including the file type bits
+    pub mode: u32,
+    pub link_target: Option<String>,
+}
+
+impl RsyncEntry {
+    pub fn is_dir(&self) -> bool {
+        self.mode & S_IFMT == S_IFDIR
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode & S_IFMT == S_IFLNK
+    }
+}
+
+/// How much of a transfer was reused from the existing copy
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DeltaStats {
+    /// Bytes sent as literal data
+    pub literal: u64,
+    /// Bytes copied from matching blocks of the existing copy
+    pub matched: u64,
+}
+
+fn protocol_error(message: impl std::fmt::Display) -> ProviderError {
+    ProviderError::TransferFailed(format!("rsync: {}", message))
+}
+
+/// Encode rsync's variable-length integers: the leading one bits of the first
+/// byte count the extra bytes, its remaining bits hold the most significant byte
+fn encode_var(out: &mut Vec<u8>, value: u64, min_bytes: usize, size: usize) {
+    let mut b = [0u8; 9];
+    b[1..=size].copy_from_slice(&value.to_le_bytes()[..size]);
+    let mut cnt = size;
+    while cnt > min_bytes && b[cnt] == 0 {
+        cnt -= 1;
+    }
+    let bit = 1u32 << (7 + min_bytes - cnt);
+    if b[cnt] as u32 >= bit {
+        cnt += 1;
+        b[0] = !(bit - 1) as u8;
+    } else if cnt > min_bytes {
+        b[0] = (b[cnt] as u32 | !(bit * 2 - 1)) as u8;
+    } else {
+        b[0] = b[cnt];
+    }
+    out.extend_from_slice(&b[..cnt]);
+}
+
+/// Bytes following the first `min_bytes` of a variable-length integer
+fn var_extra_bytes(prefix: u8) -> usize {
+    (prefix.leading_ones() as usize).min(6)
+}
+
+/// Decode a complete variable-length integer (`min_bytes` plus its extra bytes)
+fn decode_var(bytes: &[u8], min_bytes: usize) -> u64 {
+    let extra = bytes.len() - min_bytes;
+    let mut b = [0u8; 9];
+    b[..bytes.len() - 1].copy_from_slice(&bytes[1..]);
+    b[min_bytes - 1 + extra] = bytes[0] & ((1u16 << (8 - extra)) - 1) as u8;
+    u64::from_le_bytes(b[..8].try_into().unwrap_or_default())
+}
+
+/// File indexes are sent as differences from the previous index of the same sign
+#[derive(Debug, Clone, Copy)]
+struct NdxState {
+    prev_positive: i32,
+    prev_negative: i32,
+}
+
+impl Default for NdxState {
+    fn default() -> Self {
+        Self { prev_positive: -1, prev_negative: 1 }
+    }
+}
+
+impl NdxState {
+    fn encode(&mut self, out: &mut Vec<u8>, ndx: i32) {
+        let (value, diff) = if ndx >= 0 {
+            let diff = ndx - self.prev_positive;
+            self.prev_positive = ndx;
+            (ndx, diff)
+        } else if ndx == NDX_DONE {
+            out.push(0);
+            return;
+        } else {
+            out.push(0xFF);
+            let value = -ndx;
+            let diff = value - self.prev_negative;
+            self.prev_negative = value;
+            (value, diff)
+        };
+        if diff > 0 && diff < 0xFE {
+            out.push(diff as u8);
+        } else if diff < 0 || diff > 0x7FFF {
+            out.extend_from_slice(&[0xFE, (value >> 24) as u8 | 0x80, value as u8, (value >> 8) as u8, (value >> 16) as u8]);
+        } else {
+            out.extend_from_slice(&[0xFE, (diff >> 8) as u8, diff as u8]);
+        }
+    }
+}
+
+/// Sizes of the block checksums describing one basis file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct SumHead {
+    count: i32,
+    blength: i32,
+    s2length: i32,
+    remainder: i32,
+}
+
+impl SumHead {
+    /// rsync's block size: about the square root of the file length, rounded
+    /// to a multiple of 8 and clamped to 700 bytes .. 128 KiB
+    fn for_size(len: u64) -> Self {
+        let blength = if len <= BLOCK_SIZE * BLOCK_SIZE {
+            BLOCK_SIZE
+        } else {
+            let mut c = 1u64;
+            let mut l = len >> 2;
+            while l != 0 {
+                c <<= 1;
+                l >>= 2;
+            }
+            if c >= MAX_BLOCK_SIZE {
+                MAX_BLOCK_SIZE
+            } else {
+                let mut blength = 0u64;
+                while c >= 8 {
+                    blength |= c;
+                    if len < blength * blength {
+                        blength &= !c;
+                    }
+                    c >>= 1;
+                }
+                blength.max(BLOCK_SIZE)
+            }
+        };
+        Self {
+            count: len.div_ceil(blength) as i32,
+            blength: blength as i32,
+            s2length: SUM_LENGTH as i32,
+            remainder: (len % blength) as i32,
+        }
+    }
+
+    fn block_len(&self, index: usize) -> usize {
+        if index + 1 == self.count as usize && self.remainder != 0 {
+            self.remainder as usize
+        } else {
+            self.blength as usize
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BlockSum {
+    weak: u32,
+    /// First `s2length` bytes of the seeded MD5
+    strong: Vec<u8>,
+}
+
+/// rsync's rolling checksum (bytes are summed as signed chars)
+fn weak_parts(data: &[u8]) -> (u32, u32) {
+    let (mut s1, mut s2) = (0u32, 0u32);
+    for &byte in data {
+        s1 = s1.wrapping_add(byte as i8 as u32);
+        s2 = s2.wrapping_add(s1);
+    }
+    (s1, s2)
+}
+
+fn weak_checksum(data: &[u8]) -> u32 {
+    let (s1, s2) = weak_parts(data);
+    (s1 & 0xFFFF) | (s2 << 16)
+}
+
+/// Block MD5 mixed with the session's checksum seed
+#[derive(Debug, Clone, Copy)]
+struct StrongSum {
+    seed: i32,
+    seed_first: bool,
+}
+
+impl StrongSum {
+    fn block(&self, data: &[u8]) -> [u8; SUM_LENGTH] {
+        let mut md5 = Md5::new();
+        let seed = (self.seed != 0).then(|| self.seed.to_le_bytes());
+        if let (Some(seed), true) = (seed, self.seed_first) {
+            md5.update(seed);
+        }
+        md5.update(data);
+        if let (Some(seed), false) = (seed, self.seed_first) {
+            md5.update(seed);
+        }
+        md5.finalize().into()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(Range<usize>),
+    Block(usize),
+}
+
+/// Find the blocks of the basis file (described by `sums`) inside `data`
+fn match_blocks(data: &[u8], head: &SumHead, sums: &[BlockSum], strong_sum: &StrongSum) -> Vec<Token> {
+    let len = data.len();
+    let blength = head.blength as usize;
+    let mut tokens = Vec::new();
+    if sums.is_empty() || blength == 0 || len == 0 {
+        if len > 0 {
+            tokens.push(Token::Literal(0..len));
+        }
+        return tokens;
+    }
+
+    let mut table: HashMap<u32, Vec<usize>> = HashMap::new();
+    let mut tags = vec![false; 1 << 16];
+    for (index, sum) in sums.iter().enumerate() {
+        table.entry(sum.weak).or_default().push(index);
+        tags[(sum.weak & 0xFFFF) as usize] = true;
+    }
+
+    let (mut offset, mut literal_start) = (0usize, 0usize);
+    let mut k = blength.min(len);
+    let (mut s1, mut s2) = weak_parts(&data[..k]);
+    while k > 0 {
+        let weak = (s1 & 0xFFFF) | (s2 << 16);
+        let mut found = None;
+        if tags[(weak & 0xFFFF) as usize] {
+            if let Some(candidates) = table.get(&weak) {
+                let mut strong = None;
+                for &index in candidates {
+                    if head.block_len(index) != k {
+                        continue;
+                    }
+                    let strong = strong.get_or_insert_with(|| strong_sum.block(&data[offset..offset + k]));
+                    if strong[..sums[index].strong.len()] == sums[index].strong[..] {
+                        found = Some(index);
+                        break;
+                    }
+                }
+            }
+        }
+        if let Some(index) = found {
+            if literal_start < offset {
+                tokens.push(Token::Literal(literal_start..offset));
+            }
+            tokens.push(Token::Block(index));
+            offset += k;
+            literal_start = offset;
+            k = blength.min(len - offset);
+            (s1, s2) = weak_parts(&data[offset..offset + k]);
+            continue;
+        }
+
+        // Roll the window one byte forward (it shrinks at the end of the data)
+        let out = data[offset] as i8 as u32;
+        s1 = s1.wrapping_sub(out);
+        s2 = s2.wrapping_sub((k as u32).wrapping_mul(out));
+        if offset + k < len {
+            s1 = s1.wrapping_add(data[offset + k] as i8 as u32);
+            s2 = s2.wrapping_add(s1);
+        } else {
+            k -= 1;
+        }
+        offset += 1;
+    }
+    if literal_start < len {
+        tokens.push(Token::Literal(literal_start..len));
+    }
+    tokens
+}
+
+/// Item flags with the optional fields that follow them
+#[derive(Debug, Default, Clone)]
+struct ItemAttrs {
+    flags: u16,
+    basis_type: u8,
+    xname: Vec<u8>,
+}
+
+/// Buffered, multiplexed byte stream to the remote rsync
+struct Wire<S> {
+    stream: BufReader<S>,
+    multiplexed: bool,
+    /// Payload left in the current incoming data frame
+    data_left: usize,
+    out: Vec<u8>,
+    ndx_in: NdxState,
+    ndx_out: NdxState,
+    /// Error messages from the server, reported if the session fails
+    errors: Vec<String>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Wire<S> {
+    fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+            multiplexed: false,
+            data_left: 0,
+            out: Vec::new(),
+            ndx_in: NdxState::default(),
+            ndx_out: NdxState::default(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Error for a failed session, preferring what the server reported
+    fn failure(&self, message: &str) -> ProviderError {
+        if self.errors.is_empty() {
+            return protocol_error(message);
+        }
+        let detail = self.errors.join("; ");
+        if detail.contains("No such file") {
+            ProviderError::NotFound(detail)
+        } else if detail.contains("Permission denied") {
+            ProviderError::PermissionDenied(detail)
+        } else {
+            protocol_error(detail)
+        }
+    }
+
+    fn io_failure(&self, error: std::io::Error) -> ProviderError {
+        if error.kind() == std::io::ErrorKind::UnexpectedEof {
+            self.failure("connection closed unexpectedly")
+        } else {
+            protocol_error(error)
+        }
+    }
+
+    async fn flush(&mut self) -> Result<(), ProviderError> {
+        if self.out.is_empty() {
+            return Ok(());
+        }
+        let out = std::mem::take(&mut self.out);
+        let result = async {
+            if self.multiplexed {
+                for frame in out.chunks(MAX_FRAME) {
+                    let header = (((MPLEX_BASE + MSG_DATA) as u32) << 24) | frame.len() as u32;
+                    self.stream.write_all(&header.to_le_bytes()).await?;
+                    self.stream.write_all(frame).await?;
+                }
+            } else {
+                self.stream.write_all(&out).await?;
+            }
+            self.stream.flush().await
+        }.await;
+        self.out = out;
+        self.out.clear();
+        result.map_err(|e| self.io_failure(e))
+    }
+
+    async fn flush_if_full(&mut self) -> Result<(), ProviderError> {
+        if self.out.len() >= 4 * MAX_FRAME {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<(), ProviderError> {
+        self.flush().await?;
+        self.stream.shutdown().await.map_err(protocol_error)
+    }
+
+    async fn read_raw(&mut self, buf: &mut [u8]) -> Result<(), ProviderError> {
+        match self.stream.read_exact(buf).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(self.io_failure(e)),
+        }
+    }
+
+    /// Read frame headers until a data frame starts, handling server messages
+    async fn next_data_frame(&mut self) -> Result<(), ProviderError> {
+        while self.data_left == 0 {
+            let mut header = [0u8; 4];
+            self.read_raw(&mut header).await?;
+            let header = u32::from_le_bytes(header);
+            let (tag, len) = ((header >> 24) as u8, (header & 0xFF_FFFF) as usize);
+            if tag < MPLEX_BASE {
+                return Err(protocol_error(format!("invalid frame header {:#010x}", header)));
+            }
+            if tag - MPLEX_BASE == MSG_DATA {
+                self.data_left = len;
+                continue;
+            }
+            let mut payload = vec![0u8; len];
+            self.read_raw(&mut payload).await?;
+            let text = String::from_utf8_lossy(&payload).trim_end().to_string();
+            match tag - MPLEX_BASE {
+                MSG_ERROR_XFER | MSG_ERROR | MSG_ERROR_SOCKET | MSG_ERROR_UTF8 => {
+                    tracing::warn!("rsync: {}", text);
+                    self.errors.push(text);
+                }
+                MSG_WARNING => tracing::warn!("rsync: {}", text),
+                MSG_INFO | MSG_LOG => tracing::debug!("rsync: {}", text),
+                MSG_ERROR_EXIT => return Err(self.failure("server exited with an error")),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), ProviderError> {
+        // Anything still buffered may be what the server is waiting for
+        self.flush().await?;
+        if !self.multiplexed {
+            return self.read_raw(buf).await;
+        }
+        let mut filled = 0;
+        while filled < buf.len() {
+            self.next_data_frame().await?;
+            let n = self.data_left.min(buf.len() - filled);
+            self.read_raw(&mut buf[filled..filled + n]).await?;
+            self.data_left -= n;
+            filled += n;
+        }
+        Ok(())
+    }
+
+    async fn read_u8(&mut self) -> Result<u8, ProviderError> {
+        let mut b = [0u8; 1];
+        self.read_bytes(&mut b).await?;
+        Ok(b[0])
+    }
+
+    async fn read_u16(&mut self) -> Result<u16, ProviderError> {
+        let mut b = [0u8; 2];
+        self.read_bytes(&mut b).await?;
+        Ok(u16::from_le_bytes(b))
+    }
+
+    async fn read_i32(&mut self) -> Result<i32, ProviderError> {
+        let mut b = [0u8; 4];
+        self.read_bytes(&mut b).await?;
+        Ok(i32::from_le_bytes(b))
+    }
+
+    async fn read_var(&mut self, min_bytes: usize, size: usize) -> Result<u64, ProviderError> {
+        let mut b = [0u8; 9];
+        self.read_bytes(&mut b[..min_bytes]).await?;
+        let extra = var_extra_bytes(b[0]);
+        if min_bytes - 1 + extra > size {
+            return Err(protocol_error(format!("invalid variable-length integer prefix {:#04x}", b[0])));
+        }
+        self.read_bytes(&mut b[min_bytes..min_bytes + extra]).await?;
+        Ok(decode_var(&b[..min_bytes + extra], min_bytes))
+    }
+
+    async fn read_varint(&mut self) -> Result<i32, ProviderError> {
+        Ok(self.read_var(1, 4).await? as u32 as i32)
+    }
+
+    async fn read_varlong(&mut self, min_bytes: usize) -> Result<i64, ProviderError> {
+        Ok(self.read_var(min_bytes, 8).await? as i64)
+    }
+
+    async fn read_ndx(&mut self) -> Result<i32, ProviderError> {
+        let first = self.read_u8().await?;
+        if first == 0 {
+            return Ok(NDX_DONE);
+        }
+        let (negative, b) = if first == 0xFF { (true, self.read_u8().await?) } else { (false, first) };
+        let prev = if negative { self.ndx_in.prev_negative } else { self.ndx_in.prev_positive };
+        let value = if b == 0xFE {
+            let mut b = [0u8; 2];
+            self.read_bytes(&mut b).await?;
+            if b[0] & 0x80 != 0 {
+                let mut rest = [0u8; 2];
+                self.read_bytes(&mut rest).await?;
+                i32::from_le_bytes([b[1], rest[0], rest[1], b[0] & 0x7F])
+            } else {
+                (((b[0] as i32) << 8) | b[1] as i32) + prev
+            }
+        } else {
+            b as i32 + prev
+        };
+        if negative {
+            self.ndx_in.prev_negative = value;
+            Ok(-value)
+        } else {
+            self.ndx_in.prev_positive = value;
+            Ok(value)
+        }
+    }
+
+    async fn read_item_attrs(&mut self) -> Result<ItemAttrs, ProviderError> {
+        let mut attrs = ItemAttrs { flags: self.read_u16().await?, ..Default::default() };
+        if attrs.flags & ITEM_BASIS_TYPE_FOLLOWS != 0 {
+            attrs.basis_type = self.read_u8().await?;
+        }
+        if attrs.flags & ITEM_XNAME_FOLLOWS != 0 {
+            let mut len = self.read_u8().await? as usize;
+            if len & 0x80 != 0 {
+                len = ((len & 0x7F) << 8) | self.read_u8().await? as usize;
+            }
+            attrs.xname = vec![0u8; len];
+            self.read_bytes(&mut attrs.xname).await?;
+        }
+        Ok(attrs)
+    }
+
+    async fn read_sum_head(&mut self) -> Result<SumHead, ProviderError> {
+        let head = SumHead {
+            count: self.read_i32().await?,
+            blength: self.read_i32().await?,
+            s2length: self.read_i32().await?,
+            remainder: self.read_i32().await?,
+        };
+        if head.count < 0
+            || !(0..=MAX_BLOCK_SIZE as i32).contains(&head.blength)
+            || !(0..=SUM_LENGTH as i32).contains(&head.s2length)
+            || !(0..=head.blength).contains(&head.remainder)
+        {
+            return Err(protocol_error(format!("invalid checksum header {:?}", head)));
+        }
+        Ok(head)
+    }
+
+    async fn read_sums(&mut self) -> Result<(SumHead, Vec<BlockSum>), ProviderError> {
+        let head = self.read_sum_head().await?;
+        let mut sums = Vec::with_capacity((head.count as usize).min(1 << 20));
+        for _ in 0..head.count {
+            let weak = self.read_i32().await? as u32;
+            let mut strong = vec![0u8; head.s2length as usize];
+            self.read_bytes(&mut strong).await?;
+            sums.push(BlockSum { weak, strong });
+        }
+        Ok((head, sums))
+    }
+
+    /// Read file list entries until the terminating zero flags byte
+    async fn read_file_list(&mut self, links: bool) -> Result<Vec<RsyncEntry>, ProviderError> {
+        let mut entries = Vec::new();
+        let (mut name, mut mode, mut mtime) = (Vec::<u8>::new(), 0u32, 0i64);
+        loop {
+            let mut flags = self.read_u8().await? as u16;
+            if flags == 0 {
+                break;
+            }
+            if flags & XMIT_EXTENDED_FLAGS != 0 {
+                flags |= (self.read_u8().await? as u16) << 8;
+            }
+            if flags == XMIT_EXTENDED_FLAGS | XMIT_IO_ERROR_ENDLIST {
+                self.read_varint().await?;
+                break;
+            }
+            let keep = if flags & XMIT_SAME_NAME != 0 { self.read_u8().await? as usize } else { 0 };
+            let len = if flags & XMIT_LONG_NAME != 0 {
+                self.read_varint().await? as usize
+            } else {
+                self.read_u8().await? as usize
+            };
+            if keep > name.len() || len > 64 * 1024 {
+                return Err(protocol_error("invalid file name in file list"));
+            }
+            name.truncate(keep);
+            let start = name.len();
+            name.resize(start + len, 0);
+            self.read_bytes(&mut name[start..]).await?;
+
+            let size = self.read_varlong(3).await? as u64;
+            if flags & XMIT_SAME_TIME == 0 {
+                mtime = self.read_varlong(4).await?;
+            }
+            if flags & XMIT_MOD_NSEC != 0 {
+                self.read_varint().await?;
+            }
+            if flags & XMIT_SAME_MODE == 0 {
+                mode = self.read_i32().await? as u32;
+            }
+            let mut link_target = None;
+            if links && mode & S_IFMT == S_IFLNK {
+                let len = self.read_varint().await? as usize;
+                if len > 64 * 1024 {
+                    return Err(protocol_error("invalid symlink target in file list"));
+                }
+                let mut target = vec![0u8; len];
+                self.read_bytes(&mut target).await?;
+                link_target = Some(String::from_utf8_lossy(&target).to_string());
+            }
+            entries.push(RsyncEntry {
+                name: String::from_utf8_lossy(&name).to_string(),
+                size,
+                mtime,
+                mode,
+                link_target,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn put_u8(&mut self, value: u8) {
+        self.out.push(value);
+    }
+
+    fn put_u16(&mut self, value: u16) {
+        self.out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_i32(&mut self, value: i32) {
+        self.out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_ndx(&mut self, ndx: i32) {
+        self.ndx_out.encode(&mut self.out, ndx);
+    }
+
+    fn put_item_attrs(&mut self, attrs: &ItemAttrs) {
+        self.put_u16(attrs.flags);
+        if attrs.flags & ITEM_BASIS_TYPE_FOLLOWS != 0 {
+            self.put_u8(attrs.basis_type);
+        }
+        if attrs.flags & ITEM_XNAME_FOLLOWS != 0 {
+            if attrs.xname.len() > 0x7F {
+                self.put_u8((attrs.xname.len() >> 8) as u8 | 0x80);
+            }
+            self.put_u8(attrs.xname.len() as u8);
+            self.out.extend_from_slice(&attrs.xname);
+        }
+    }
+
+    fn put_sum_head(&mut self, head: &SumHead) {
+        for value in [head.count, head.blength, head.s2length, head.remainder] {
+            self.put_i32(value);
+        }
+    }
+
+    fn put_literal(&mut self, data: &[u8]) {
+        for chunk in data.chunks(CHUNK_SIZE) {
+            self.put_i32(chunk.len() as i32);
+            self.out.extend_from_slice(chunk);
+        }
+    }
+}
+
+/// Append a single-entry file list (a regular file, nothing inherited from a previous entry)
+fn encode_file_entry(out: &mut Vec<u8>, entry: &RsyncEntry) {
+    let name = entry.name.as_bytes();
+    let mut flags = if entry.is_dir() { 0 } else { XMIT_TOP_DIR };
+    if name.len() > 0xFF {
+        flags |= XMIT_LONG_NAME;
+    }
+    if flags & 0xFF00 != 0 || flags == 0 {
+        flags |= XMIT_EXTENDED_FLAGS;
+        out.extend_from_slice(&flags.to_le_bytes());
+    } else {
+        out.push(flags as u8);
+    }
+    if flags & XMIT_LONG_NAME != 0 {
+        encode_var(out, name.len() as u64, 1, 4);
+    } else {
+        out.push(name.len() as u8);
+    }
+    out.extend_from_slice(name);
+    encode_var(out, entry.size, 3, 8);
+    encode_var(out, entry.mtime as u64, 4, 8);
+    out.extend_from_slice(&(entry.mode as i32).to_le_bytes());
+}
+
+/// Where a downloaded file is assembled
+struct ReceiveTarget {
+    /// Existing local copy the server's block references point into
+    basis: Option<tokio::fs::File>,
+    out: tokio::fs::File,
+    size: u64,
+    stats: DeltaStats,
+}
+
+/// One `rsync --server` process at the other end of an exec channel
+struct Session<S> {
+    wire: Wire<S>,
+    strong_sum: StrongSum,
+    on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    /// Exchange protocol versions and the checksum seed; everything after is multiplexed
+    async fn start(stream: S, on_progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<Self, ProviderError> {
+        let mut wire = Wire::new(stream);
+        wire.put_i32(PROTOCOL_VERSION);
+        let remote = wire.read_i32().await
+            .map_err(|_| protocol_error("server did not answer (is rsync installed?)"))?;
+        if remote < PROTOCOL_VERSION {
+            return Err(ProviderError::NotSupported(format!(
+                "rsync 3.0 or later is required on the server (it speaks protocol {})", remote
+            )));
+        }
+        if remote > 100 {
+            return Err(protocol_error("unexpected output from the server (does the login shell print a banner?)"));
+        }
+        let compat_flags = wire.read_varint().await? as u32;
+        let seed = wire.read_i32().await?;
+        wire.multiplexed = true;
+        Ok(Self {
+            wire,
+            strong_sum: StrongSum { seed, seed_first: compat_flags & CF_CHKSUM_SEED_FIX != 0 },
+            on_progress,
+        })
+    }
+
+    fn progress(&self, done: u64, total: u64) {
+        if let Some(ref progress) = self.on_progress {
+            progress(done, total);
+        }
+    }
+
+    /// Generator side: ask for file `ndx`, describing the blocks of `basis`
+    async fn request_file(&mut self, ndx: i32, basis: Option<&mut tokio::fs::File>, basis_len: u64) -> Result<(), ProviderError> {
+        let head = if basis.is_some() && basis_len > 0 { SumHead::for_size(basis_len) } else { SumHead::default() };
+        self.wire.put_ndx(ndx);
+        self.wire.put_u16(ITEM_TRANSFER);
+        self.wire.put_sum_head(&head);
+        if let Some(basis) = basis.filter(|_| head.count > 0) {
+            basis.seek(std::io::SeekFrom::Start(0)).await.map_err(protocol_error)?;
+            let mut block = vec![0u8; head.blength as usize];
+            for index in 0..head.count as usize {
+                let block = &mut block[..head.block_len(index)];
+                basis.read_exact(block).await
+                    .map_err(|e| ProviderError::TransferFailed(format!("Read error: {}", e)))?;
+                self.wire.put_i32(weak_checksum(block) as i32);
+                self.wire.out.extend_from_slice(&self.strong_sum.block(block));
+                self.wire.flush_if_full().await?;
+            }
+        }
+        self.wire.put_ndx(NDX_DONE);
+        self.wire.flush().await
+    }
+
+    /// Receiver side: rebuild one file from literal data and basis blocks
+    async fn receive_data(&mut self, target: &mut ReceiveTarget) -> Result<(), ProviderError> {
+        let head = self.wire.read_sum_head().await?;
+        let write_error = |e: std::io::Error| ProviderError::TransferFailed(format!("Write error: {}", e));
+        let mut md5 = Md5::new();
+        let mut buffer = vec![0u8; CHUNK_SIZE.max(head.blength as usize)];
+        let mut received = 0u64;
+        loop {
+            let token = self.wire.read_i32().await?;
+            if token == 0 {
+                break;
+            }
+            if token > 0 {
+                let mut left = token as usize;
+                while left > 0 {
+                    let n = left.min(buffer.len());
+                    self.wire.read_bytes(&mut buffer[..n]).await?;
+                    target.out.write_all(&buffer[..n]).await.map_err(write_error)?;
+                    md5.update(&buffer[..n]);
+                    left -= n;
+                }
+                target.stats.literal += token as u64;
+                received += token as u64;
+            } else {
+                let index = (-(token as i64) - 1) as usize;
+                let basis = target.basis.as_mut()
+                    .filter(|_| index < head.count as usize)
+                    .ok_or_else(|| protocol_error(format!("invalid block reference {}", index)))?;
+                let len = head.block_len(index);
+                basis.seek(std::io::SeekFrom::Start(index as u64 * head.blength as u64)).await.map_err(protocol_error)?;
+                basis.read_exact(&mut buffer[..len]).await
+                    .map_err(|e| ProviderError::TransferFailed(format!("Read error: {}", e)))?;
+                target.out.write_all(&buffer[..len]).await.map_err(write_error)?;
+                md5.update(&buffer[..len]);
+                target.stats.matched += len as u64;
+                received += len as u64;
+            }
+            self.progress(received, target.size);
+        }
+        let mut expected = [0u8; SUM_LENGTH];
+        self.wire.read_bytes(&mut expected).await?;
+        if md5.finalize()[..] != expected[..] {
+            return Err(ProviderError::TransferFailed("rsync: checksum mismatch after transfer".to_string()));
+        }
+        target.out.flush().await.map_err(write_error)
+    }
+
+    /// Receiver side of the phase handshake, then the server's statistics
+    /// and the final goodbye. Only file 0 is ever requested.
+    async fn run_receiver(&mut self, mut target: Option<&mut ReceiveTarget>) -> Result<(), ProviderError> {
+        let mut phase = 0;
+        loop {
+            let ndx = self.wire.read_ndx().await?;
+            if ndx == NDX_DONE {
+                phase += 1;
+                if phase > 2 {
+                    break;
+                }
+                if phase == 1 {
+                    // No redo requests: end both generator phases at once
+                    self.wire.put_ndx(NDX_DONE);
+                    self.wire.put_ndx(NDX_DONE);
+                }
+                continue;
+            }
+            let attrs = self.wire.read_item_attrs().await?;
+            if attrs.flags & ITEM_TRANSFER == 0 {
+                continue;
+            }
+            match target.as_deref_mut() {
+                Some(target) if ndx == 0 => self.receive_data(target).await?,
+                _ => return Err(protocol_error(format!("unexpected file index {}", ndx))),
+            }
+        }
+        for _ in 0..5 {
+            self.wire.read_varlong(3).await?;
+        }
+        self.wire.put_ndx(NDX_DONE);
+        self.wire.close().await
+    }
+
+    /// Sender side: answer one request with delta tokens and the file's MD5
+    async fn send_data(
+        &mut self,
+        file: &mut tokio::fs::File,
+        size: u64,
+        head: &SumHead,
+        sums: &[BlockSum],
+    ) -> Result<DeltaStats, ProviderError> {
+        let read_error = |e: std::io::Error| ProviderError::TransferFailed(format!("Read error: {}", e));
+        file.seek(std::io::SeekFrom::Start(0)).await.map_err(read_error)?;
+        let mut md5 = Md5::new();
+        let mut stats = DeltaStats::default();
+
+        if !sums.is_empty() && size <= MAX_DELTA_SIZE {
+            let mut data = Vec::with_capacity(size as usize);
+            file.read_to_end(&mut data).await.map_err(read_error)?;
+            for token in match_blocks(&data, head, sums, &self.strong_sum) {
+                match token {
+                    Token::Literal(range) => {
+                        self.wire.put_literal(&data[range.clone()]);
+                        stats.literal += range.len() as u64;
+                    }
+                    Token::Block(index) => {
+                        self.wire.put_i32(-(index as i32) - 1);
+                        stats.matched += head.block_len(index) as u64;
+                    }
+                }
+                self.progress(stats.literal + stats.matched, size);
+                self.wire.flush_if_full().await?;
+            }
+            md5.update(&data);
+        } else {
+            let mut buffer = vec![0u8; CHUNK_SIZE];
+            loop {
+                let n = file.read(&mut buffer).await.map_err(read_error)?;
+                if n == 0 {
+                    break;
+                }
+                self.wire.put_literal(&buffer[..n]);
+                md5.update(&buffer[..n]);
+                stats.literal += n as u64;
+                self.progress(stats.literal, size);
+                self.wire.flush_if_full().await?;
+            }
+        }
+        self.wire.put_i32(0);
+        self.wire.out.extend_from_slice(&md5.finalize());
+        self.wire.flush().await?;
+        Ok(stats)
+    }
+}
+
+/// `rsync --server` command line; the server expands wildcards in source paths
+fn server_command(sender: bool, flags: &str, path: &str) -> String {
+    if sender {
+        format!("rsync --server --sender {} . {}", flags, shell_quote(&escape_glob(path)))
+    } else {
+        format!("rsync --server {} . {}", flags, shell_quote(path))
+    }
+}
+
+async fn fetch_file_list<S>(stream: S) -> Result<Vec<RsyncEntry>, ProviderError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut session = Session::start(stream, None).await?;
+    session.wire.put_i32(0); // no filter rules
+    let entries = session.wire.read_file_list(true).await?;
+    if entries.is_empty() {
+        // The server exits right after an empty file list
+        return Err(session.wire.failure("no such file or directory"));
+    }
+    session.run_receiver(None).await?;
+    Ok(entries)
+}
+
+/// List the entries of a remote directory
+pub async fn list(conn: &SshConnection, dir: &str) -> Result<Vec<RsyncEntry>, ProviderError> {
+    let dir = format!("{}/", dir.trim_end_matches('/'));
+    let channel = conn.open_exec(&server_command(true, "-lde.C", &dir)).await?;
+    let entries = fetch_file_list(channel.into_stream()).await?;
+    Ok(entries.into_iter().filter(|e| e.name != ".").collect())
+}
+
+/// Attributes of a single remote path (symlinks are not followed)
+pub async fn stat(conn: &SshConnection, path: &str) -> Result<RsyncEntry, ProviderError> {
+    let channel = conn.open_exec(&server_command(true, "-lde.C", path)).await?;
+    let mut entries = fetch_file_list(channel.into_stream()).await?;
+    Ok(entries.swap_remove(0))
+}
+
+/// Downloads are written next to the target and renamed into place once complete
+pub(super) fn partial_path(local_path: &Path) -> PathBuf {
+    let mut name = local_path.as_os_str().to_os_string();
+    name.push(".aeroftp-partial");
+    PathBuf::from(name)
+}
+
+async fn receive_file<S>(
+    stream: S,
+    local_path: &Path,
+    on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+) -> Result<DeltaStats, ProviderError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut session = Session::start(stream, on_progress).await?;
+    session.wire.put_i32(0); // no filter rules
+    let entries = session.wire.read_file_list(false).await?;
+    let entry = match entries.as_slice() {
+        [] => return Err(session.wire.failure("no such file")),
+        [entry] if !entry.is_dir() => entry.clone(),
+        _ => return Err(ProviderError::InvalidPath(format!("{} is a directory", entries[0].name))),
+    };
+
+    let mut basis = match tokio::fs::File::open(local_path).await {
+        Ok(file) if file.metadata().await.map(|m| m.is_file()).unwrap_or(false) => Some(file),
+        _ => None,
+    };
+    let basis_len = match &basis {
+        Some(file) => file.metadata().await.map(|m| m.len()).unwrap_or(0),
+        None => 0,
+    };
+
+    let partial = partial_path(local_path);
+    let out = tokio::fs::File::create(&partial).await
+        .map_err(|e| ProviderError::TransferFailed(format!("Failed to create local file: {}", e)))?;
+    let mut target = ReceiveTarget { basis: None, out, size: entry.size, stats: DeltaStats::default() };
+
+    let result = async {
+        session.request_file(0, basis.as_mut(), basis_len).await?;
+        target.basis = basis.take();
+        session.run_receiver(Some(&mut target)).await
+    }.await;
+    let ReceiveTarget { out, stats, .. } = target;
+    if let Err(e) = result {
+        drop(out);
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(e);
+    }
+
+    if let Ok(secs) = u64::try_from(entry.mtime) {
+        let out = out.into_std().await;
+        let _ = out.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+    }
+    tokio::fs::rename(&partial, local_path).await
+        .map_err(|e| ProviderError::TransferFailed(format!("Failed to move download into place: {}", e)))?;
+    Ok(stats)
+}
+
+/// Download a remote file, reusing blocks of the existing local copy
+pub async fn download(
+    conn: &SshConnection,
+    remote_path: &str,
+    local_path: &Path,
+    on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+) -> Result<DeltaStats, ProviderError> {
+    let channel = conn.open_exec(&server_command(true, "-e.C", remote_path)).await?;
+    receive_file(channel.into_stream(), local_path, on_progress).await
+}
+
+async fn send_file<S>(
+    stream: S,
+    local_path: &Path,
+    name: &str,
+    on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+) -> Result<DeltaStats, ProviderError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut file = tokio::fs::File::open(local_path).await
+        .map_err(|e| ProviderError::TransferFailed(format!("Failed to open local file: {}", e)))?;
+    let metadata = file.metadata().await
+        .map_err(|e| ProviderError::TransferFailed(format!("Failed to read local file: {}", e)))?;
+    #[cfg(unix)]
+    let permissions = std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o7777;
+    #[cfg(not(unix))]
+    let permissions = 0o644;
+    let entry = RsyncEntry {
+        name: name.to_string(),
+        size: metadata.len(),
+        mtime: metadata.modified().ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0),
+        mode: S_IFREG | permissions,
+        link_target: None,
+    };
+
+    let mut session = Session::start(stream, on_progress).await?;
+    encode_file_entry(&mut session.wire.out, &entry);
+    session.wire.put_u8(0); // end of file list
+
+    let mut stats = DeltaStats::default();
+    let mut phase = 0;
+    loop {
+        let ndx = session.wire.read_ndx().await?;
+        if ndx == NDX_DONE {
+            phase += 1;
+            if phase > 2 {
+                break;
+            }
+            session.wire.put_ndx(NDX_DONE);
+            continue;
+        }
+        let attrs = session.wire.read_item_attrs().await?;
+        if attrs.flags & ITEM_TRANSFER == 0 {
+            continue;
+        }
+        if ndx != 0 {
+            return Err(protocol_error(format!("unexpected file index {}", ndx)));
+        }
+        let (head, sums) = session.wire.read_sums().await?;
+        session.wire.put_ndx(ndx);
+        session.wire.put_item_attrs(&attrs);
+        session.wire.put_sum_head(&head);
+        stats = session.send_data(&mut file, entry.size, &head, &sums).await?;
+    }
+    session.wire.put_ndx(NDX_DONE);
+    if session.wire.read_ndx().await? != NDX_DONE {
+        return Err(protocol_error("invalid packet at end of run"));
+    }
+    session.wire.close().await?;
+    Ok(stats)
+}
+
+/// Upload a local file, sending only the blocks the server's copy lacks
+pub async fn upload(
+    conn: &SshConnection,
+    local_path: &Path,
+    remote_path: &str,
+    on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+) -> Result<DeltaStats, ProviderError> {
+    let name = remote_path.rsplit('/').next().filter(|n| !n.is_empty())
+        .ok_or_else(|| ProviderError::InvalidPath(format!("Invalid upload path: {}", remote_path)))?;
+    // -I: transfer even when size and mtime already match
+    let channel = conn.open_exec(&server_command(false, "-tIe.C", remote_path)).await?;
+    send_file(channel.into_stream(), local_path, name, on_progress).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn var(value: u64, min_bytes: usize, size: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_var(&mut out, value, min_bytes, size);
+        out
+    }
+
+    #[tokio::test]
+    async fn test_variable_length_integers() {
+        assert_eq!(var(0x7F, 1, 4), [0x7F]);
+        assert_eq!(var(0x80, 1, 4), [0x80, 0x80]);
+        assert_eq!(var(0x3FFF, 1, 4), [0xBF, 0xFF]);
+        assert_eq!(var(5, 3, 8), [0x00, 0x05, 0x00]);
+
+        let values: [(u64, usize, usize); 6] = [
+            (0, 1, 4),
+            (0xFFFF_FFFF, 1, 4),
+            (1_700_000_000, 4, 8),
+            (-86_400i64 as u64, 4, 8),
+            (5 * 1024 * 1024 * 1024, 3, 8),
+            (u64::MAX >> 1, 3, 8),
+        ];
+        let mut bytes = Vec::new();
+        for (value, min_bytes, size) in values {
+            encode_var(&mut bytes, value, min_bytes, size);
+        }
+        let mut wire = Wire::new(Cursor::new(bytes));
+        for (value, min_bytes, size) in values {
+            assert_eq!(wire.read_var(min_bytes, size).await.unwrap(), value);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ndx_round_trip() {
+        let sequence = [0, 1, 2, 300, NDX_DONE, 70_000, 5, -2, NDX_DONE, 6];
+        let mut state = NdxState::default();
+        let mut bytes = Vec::new();
+        for ndx in sequence {
+            state.encode(&mut bytes, ndx);
+        }
+        assert_eq!(&bytes[..3], [1, 1, 1]);
+
+        let mut wire = Wire::new(Cursor::new(bytes));
+        for ndx in sequence {
+            assert_eq!(wire.read_ndx().await.unwrap(), ndx);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_list_round_trip() {
+        let entry = RsyncEntry {
+            name: "report.pdf".to_string(),
+            size: 123_456,
+            mtime: 1_700_000_000,
+            mode: S_IFREG | 0o640,
+            link_target: None,
+        };
+        let mut bytes = Vec::new();
+        encode_file_entry(&mut bytes, &entry);
+        bytes.push(0);
+        assert_eq!(bytes[0] as u16, XMIT_TOP_DIR);
+
+        let mut wire = Wire::new(Cursor::new(bytes));
+        assert_eq!(wire.read_file_list(true).await.unwrap(), vec![entry]);
+    }
+
+    #[test]
+    fn test_block_matching_rebuilds_file() {
+        let basis: Vec<u8> = (0..20_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        let mut data = basis.clone();
+        data.splice(1_000..1_000, b"inserted bytes".iter().copied());
+        data[9_000] ^= 0xFF;
+        data.truncate(data.len() - 300);
+
+        let strong_sum = StrongSum { seed: 12345, seed_first: true };
+        let head = SumHead::for_size(basis.len() as u64);
+        assert_eq!((head.count, head.blength, head.remainder), (29, 700, 400));
+        let sums: Vec<BlockSum> = basis.chunks(head.blength as usize)
+            .map(|block| BlockSum { weak: weak_checksum(block), strong: strong_sum.block(block).to_vec() })
+            .collect();
+
+        let tokens = match_blocks(&data, &head, &sums, &strong_sum);
+        let mut rebuilt = Vec::new();
+        let mut matched = 0;
+        for token in tokens {
+            match token {
+                Token::Literal(range) => rebuilt.extend_from_slice(&data[range]),
+                Token::Block(index) => {
+                    let start = index * head.blength as usize;
+                    rebuilt.extend_from_slice(&basis[start..start + head.block_len(index)]);
+                    matched += 1;
+                }
+            }
+        }
+        assert_eq!(rebuilt, data);
+        assert!(matched >= 24, "only {} blocks reused", matched);
+    }
+
+    /// Run `command` as a local process speaking over stdin/stdout, the way the
+    /// same command runs on an SSH exec channel
+    fn local_server(command: &str) -> (tokio::process::Child, impl AsyncRead + AsyncWrite + Unpin) {
+        let mut child = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let stream = tokio::io::join(child.stdout.take().unwrap(), child.stdin.take().unwrap());
+        (child, stream)
+    }
+
+    async fn wait_success(mut child: tokio::process::Child) {
+        let status = child.wait().await.unwrap();
+        assert!(status.success(), "rsync --server exited with {}", status);
+    }
+
+    /// Upload, listing and download against a real `rsync --server`, first in
+    /// full and then as deltas against the previous copy. Needs rsync 3.0+ on
+    /// PATH: `cargo test rsync_server_round_trip -- --ignored --nocapture`
+    #[tokio::test]
+    #[ignore]
+    async fn rsync_server_round_trip() {
+        if std::process::Command::new("rsync").arg("--version").output().is_err() {
+            eprintln!("rsync not installed, skipping");
+            return;
+        }
+        let tmp = tempfile::tempdir().unwrap();
+        let remote_dir = tmp.path().join("remote");
+        std::fs::create_dir(&remote_dir).unwrap();
+        let remote = remote_dir.join("data.bin");
+        let remote_str = remote.to_str().unwrap();
+        let local = tmp.path().join("local.bin");
+
+        let original: Vec<u8> = (0..300_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        let mut edited = original.clone();
+        edited.splice(50_000..50_000, b"a few inserted bytes".iter().copied());
+        edited[200_000] ^= 0xFF;
+
+        // Upload without a copy on the server: everything is literal
+        std::fs::write(&local, &original).unwrap();
+        let (child, stream) = local_server(&server_command(false, "-tIe.C", remote_str));
+        let stats = send_file(stream, &local, "data.bin", None).await.unwrap();
+        wait_success(child).await;
+        assert_eq!(std::fs::read(&remote).unwrap(), original);
+        assert_eq!(stats, DeltaStats { literal: original.len() as u64, matched: 0 });
+
+        // Upload an edited copy: only the changed blocks are sent
+        std::fs::write(&local, &edited).unwrap();
+        let (child, stream) = local_server(&server_command(false, "-tIe.C", remote_str));
+        let stats = send_file(stream, &local, "data.bin", None).await.unwrap();
+        wait_success(child).await;
+        assert_eq!(std::fs::read(&remote).unwrap(), edited);
+        assert_eq!(stats.literal + stats.matched, edited.len() as u64);
+        assert!(stats.literal < 5_000, "{:?}", stats);
+
+        // Listing
+        let (child, stream) = local_server(&server_command(true, "-lde.C", &format!("{}/", remote_dir.display())));
+        let entries = fetch_file_list(stream).await.unwrap();
+        wait_success(child).await;
+        let entry = entries.iter().find(|e| e.name == "data.bin").unwrap();
+        assert_eq!(entry.size, edited.len() as u64);
+        assert!(!entry.is_dir());
+
+        // Download without a local copy
+        let download = tmp.path().join("download.bin");
+        let (child, stream) = local_server(&server_command(true, "-e.C", remote_str));
+        let stats = receive_file(stream, &download, None).await.unwrap();
+        wait_success(child).await;
+        assert_eq!(std::fs::read(&download).unwrap(), edited);
+        assert_eq!(stats, DeltaStats { literal: edited.len() as u64, matched: 0 });
+
+        // Download a server-side edit over the local copy: only the changes come back
+        std::fs::write(&remote, &original).unwrap();
+        let (child, stream) = local_server(&server_command(true, "-e.C", remote_str));
+        let stats = receive_file(stream, &download, None).await.unwrap();
+        wait_success(child).await;
+        assert_eq!(std::fs::read(&download).unwrap(), original);
+        assert_eq!(stats.literal + stats.matched, original.len() as u64);
+        assert!(stats.literal < 5_000, "{:?}", stats);
+        assert!(!partial_path(&download).exists());
+    }
+}
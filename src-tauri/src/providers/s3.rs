@@ -19,9 +19,19 @@ use std::collections::HashMap;
 
 use super::{
     StorageProvider, ProviderError, ProviderType, RemoteEntry, S3Config, FileVersion,
-    sanitize_api_error,
+    ContentHash, HashAlgorithm, sanitize_api_error,
 };
 
+/// Single-part uploads have the object's MD5 as ETag; multipart ETags end in `-<parts>`.
+/// SSE-KMS, SSE-C and many S3-compatible stores return 32 hex digits that are no MD5,
+/// so the hash is weak: sync trusts a match but falls back to size and time on a mismatch.
+fn etag_md5(etag: &str) -> Option<ContentHash> {
+    if etag.len() != 32 {
+        return None;
+    }
+    ContentHash::hex(HashAlgorithm::Md5, etag).map(ContentHash::weak)
+}
+
 /// S3 Storage Provider
 pub struct S3Provider {
     config: S3Config,
@@ -33,7 +43,7 @@ pub struct S3Provider {
 impl S3Provider {
     /// Create a new S3 provider with the given configuration
     pub fn new(config: S3Config) -> Result<Self, ProviderError> {
-        let client = Client::builder()
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .map_err(|e| ProviderError::ConnectionFailed(format!("HTTP client init failed: {e}")))?;
@@ -545,6 +555,7 @@ impl S3Provider {
                                             let etag = c_etag.as_ref()
                                                 .map(|s| s.trim_matches('"').to_string());
 
+                                            let content_hash = etag.as_deref().and_then(etag_md5);
                                             let mut metadata = HashMap::new();
                                             if let Some(etag) = etag {
                                                 metadata.insert("etag".to_string(), etag);
@@ -566,6 +577,7 @@ impl S3Provider {
                                                 link_target: None,
                                                 mime_type: None,
                                                 metadata,
+                                                content_hash,
                                             });
                                         }
                                     }
@@ -1476,6 +1488,7 @@ impl StorageProvider for S3Provider {
                 let name = key.rsplit('/').next().unwrap_or(key).to_string();
                 let is_dir = key.ends_with('/') && size == 0;
                 
+                let content_hash = if is_dir { None } else { etag.as_deref().and_then(etag_md5) };
                 let mut metadata = HashMap::new();
                 if let Some(etag) = etag {
                     metadata.insert("etag".to_string(), etag);
@@ -1494,6 +1507,7 @@ impl StorageProvider for S3Provider {
                     link_target: None,
                     mime_type: content_type,
                     metadata,
+                    content_hash,
                 })
             }
             StatusCode::NOT_FOUND => {
@@ -1774,6 +1788,7 @@ impl StorageProvider for S3Provider {
                                                 link_target: None,
                                                 mime_type: None,
                                                 metadata: HashMap::new(),
+                                                content_hash: None,
                                             });
                                         }
                                     }
@@ -1880,6 +1895,15 @@ impl StorageProvider for S3Provider {
         }
     }
 
+    fn supports_checksum(&self) -> bool {
+        true
+    }
+
+    async fn checksum(&mut self, path: &str) -> Result<HashMap<String, String>, ProviderError> {
+        let entry = self.stat(path).await?;
+        Ok(entry.content_hash.map(|h| h.to_checksums()).unwrap_or_default())
+    }
+
     fn transfer_optimization_hints(&self) -> super::TransferOptimizationHints {
         super::TransferOptimizationHints {
             supports_multipart: true,
@@ -2188,6 +2212,7 @@ mod tests {
             bucket: "test-bucket".to_string(),
             prefix: None,
             path_style: true,
+            proxy: None,
         }).expect("Failed to create S3Provider");
 
         assert_eq!(
@@ -2196,6 +2221,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_etag_md5() {
+        let hash = etag_md5("900150983CD24FB0D6963F7D28E17F72").unwrap();
+        assert_eq!(hash.algorithm, HashAlgorithm::Md5);
+        assert_eq!(hash.value, "900150983cd24fb0d6963f7d28e17f72");
+        // Could be an SSE-KMS ETag: a mismatch must not count
+        assert!(hash.weak);
+        assert!(etag_md5("900150983cd24fb0d6963f7d28e17f72-3").is_none());
+        assert!(etag_md5("").is_none());
+    }
+
     #[test]
     fn test_build_url_virtual_hosted() {
         let provider = S3Provider::new(S3Config {
@@ -2206,6 +2242,7 @@ mod tests {
             bucket: "my-bucket".to_string(),
             prefix: None,
             path_style: false,
+            proxy: None,
         }).expect("Failed to create S3Provider");
 
         assert_eq!(
@@ -0,0 +1,288 @@
+//! SCP transfers (the rcp source/sink protocol) over an SSH exec channel
+//!
+//! Used when a server refuses the `sftp` subsystem but still runs `scp -t`
+//! (sink, for uploads) and `scp -f` (source, for downloads). Only single
+//! files are transferred; directories are walked by the caller.
+//!
+//! Every control line and data block is acknowledged with a single byte:
+//! `0` for OK, `1` followed by a message line for a warning, `2` followed by
+//! a message line for a fatal error.
+
+use super::ssh_pool::{shell_quote, SshConnection};
+use super::ProviderError;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Longest control line accepted from the server
+const MAX_CONTROL_LINE: usize = 64 * 1024;
+
+const CHUNK_SIZE: usize = 32 * 1024;
+
+/// File attributes carried by the `T` and `C` control lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScpFileInfo {
+    pub size: u64,
+    pub mode: u32,
+    /// Seconds since the epoch, when the source sent a `T` line
+    pub mtime: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Control {
+    /// `C<mode> <size> <name>`
+    File { mode: u32, size: u64 },
+    /// `T<mtime> 0 <atime> 0`
+    Time { mtime: i64 },
+    /// `D<mode> 0 <name>` / `E` (only with `-r`, which we never request)
+    Directory,
+    EndDirectory,
+}
+
+fn protocol_error(message: impl std::fmt::Display) -> ProviderError {
+    ProviderError::TransferFailed(format!("SCP: {}", message))
+}
+
+fn parse_control(line: &str) -> Result<Control, ProviderError> {
+    let (kind, rest) = line.split_at(line.chars().next().map(char::len_utf8).unwrap_or(0));
+    let mut fields = rest.splitn(3, ' ');
+    let mut number = |what: &str| fields.next().ok_or_else(|| protocol_error(format!("missing {} in {:?}", what, line)));
+    match kind {
+        "C" => {
+            let mode = u32::from_str_radix(number("mode")?, 8).map_err(|_| protocol_error(format!("bad mode in {:?}", line)))?;
+            let size = number("size")?.parse().map_err(|_| protocol_error(format!("bad size in {:?}", line)))?;
+            number("name")?;
+            Ok(Control::File { mode: mode & 0o7777, size })
+        }
+        "T" => {
+            let mtime = number("mtime")?.parse().map_err(|_| protocol_error(format!("bad time in {:?}", line)))?;
+            Ok(Control::Time { mtime })
+        }
+        "D" => Ok(Control::Directory),
+        "E" => Ok(Control::EndDirectory),
+        _ => Err(protocol_error(format!("unexpected control line {:?}", line))),
+    }
+}
+
+async fn read_line<R: AsyncRead + Unpin>(reader: &mut BufReader<R>) -> Result<String, ProviderError> {
+    let mut line = Vec::new();
+    (&mut *reader).take(MAX_CONTROL_LINE as u64).read_until(b'\n', &mut line).await
+        .map_err(protocol_error)?;
+    if line.last() != Some(&b'\n') {
+        return Err(protocol_error("connection closed unexpectedly"));
+    }
+    line.pop();
+    Ok(String::from_utf8_lossy(&line).to_string())
+}
+
+/// Read an acknowledgement; warnings are logged, errors returned
+async fn read_ack<R: AsyncRead + Unpin>(reader: &mut BufReader<R>) -> Result<(), ProviderError> {
+    let code = reader.read_u8().await.map_err(|_| protocol_error("connection closed unexpectedly"))?;
+    match code {
+        0 => Ok(()),
+        1 => {
+            tracing::warn!("SCP: {}", read_line(reader).await?);
+            Ok(())
+        }
+        2 => Err(remote_error(&read_line(reader).await?)),
+        other => Err(protocol_error(format!("unexpected response byte {:#04x}", other))),
+    }
+}
+
+/// Map the remote `scp` error text to the closest provider error
+fn remote_error(message: &str) -> ProviderError {
+    let message = message.strip_prefix("scp: ").unwrap_or(message).to_string();
+    if message.contains("No such file") {
+        ProviderError::NotFound(message)
+    } else if message.contains("Permission denied") {
+        ProviderError::PermissionDenied(message)
+    } else {
+        ProviderError::TransferFailed(message)
+    }
+}
+
+async fn send<W: AsyncWrite + Unpin>(writer: &mut W, data: &[u8]) -> Result<(), ProviderError> {
+    writer.write_all(data).await.map_err(protocol_error)?;
+    writer.flush().await.map_err(protocol_error)
+}
+
+/// Sink side of the protocol: receive one file from an `scp -f` source into `out`
+async fn receive<S, W>(
+    stream: S,
+    out: &mut W,
+    on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+) -> Result<ScpFileInfo, ProviderError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = BufReader::new(stream);
+    send(reader.get_mut(), &[0]).await?;
+
+    let mut mtime = None;
+    let (mode, size) = loop {
+        let code = reader.read_u8().await.map_err(|_| protocol_error("connection closed unexpectedly"))?;
+        match code {
+            1 => tracing::warn!("SCP: {}", read_line(&mut reader).await?),
+            2 => return Err(remote_error(&read_line(&mut reader).await?)),
+            _ => {
+                let line = format!("{}{}", code as char, read_line(&mut reader).await?);
+                match parse_control(&line)? {
+                    Control::Time { mtime: t } => mtime = Some(t),
+                    Control::File { mode, size } => break (mode, size),
+                    Control::Directory | Control::EndDirectory => {
+                        return Err(protocol_error("source sent a directory"));
+                    }
+                }
+                send(reader.get_mut(), &[0]).await?;
+            }
+        }
+    };
+    send(reader.get_mut(), &[0]).await?;
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut received = 0u64;
+    while received < size {
+        let want = (size - received).min(CHUNK_SIZE as u64) as usize;
+        let n = reader.read(&mut buffer[..want]).await.map_err(protocol_error)?;
+        if n == 0 {
+            return Err(protocol_error("connection closed during transfer"));
+        }
+        out.write_all(&buffer[..n]).await
+            .map_err(|e| ProviderError::TransferFailed(format!("Write error: {}", e)))?;
+        received += n as u64;
+        if let Some(ref progress) = on_progress {
+            progress(received, size);
+        }
+    }
+    out.flush().await.map_err(|e| ProviderError::TransferFailed(format!("Flush error: {}", e)))?;
+
+    read_ack(&mut reader).await?;
+    send(reader.get_mut(), &[0]).await?;
+    Ok(ScpFileInfo { size, mode, mtime })
+}
+
+/// Source side of the protocol: send one file to an `scp -t` sink
+async fn transmit<S, R>(
+    stream: S,
+    name: &str,
+    input: &mut R,
+    info: ScpFileInfo,
+    on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+) -> Result<(), ProviderError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+{
+    if name.is_empty() || name.contains(['/', '\n']) {
+        return Err(ProviderError::InvalidPath(format!("Invalid file name for SCP: {:?}", name)));
+    }
+    let mut reader = BufReader::new(stream);
+    read_ack(&mut reader).await?;
+
+    if let Some(mtime) = info.mtime {
+        send(reader.get_mut(), format!("T{} 0 {} 0\n", mtime, mtime).as_bytes()).await?;
+        read_ack(&mut reader).await?;
+    }
+    send(reader.get_mut(), format!("C{:04o} {} {}\n", info.mode & 0o7777, info.size, name).as_bytes()).await?;
+    read_ack(&mut reader).await?;
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut sent = 0u64;
+    while sent < info.size {
+        let want = (info.size - sent).min(CHUNK_SIZE as u64) as usize;
+        let n = input.read(&mut buffer[..want]).await
+            .map_err(|e| ProviderError::TransferFailed(format!("Read error: {}", e)))?;
+        if n == 0 {
+            return Err(ProviderError::TransferFailed("Local file shrank during upload".to_string()));
+        }
+        reader.get_mut().write_all(&buffer[..n]).await.map_err(protocol_error)?;
+        sent += n as u64;
+        if let Some(ref progress) = on_progress {
+            progress(sent, info.size);
+        }
+    }
+    send(reader.get_mut(), &[0]).await?;
+    read_ack(&mut reader).await?;
+    reader.get_mut().shutdown().await.map_err(protocol_error)
+}
+
+/// Download `remote_path` into `out` with `scp -f`
+pub async fn download<W: AsyncWrite + Unpin>(
+    conn: &SshConnection,
+    remote_path: &str,
+    out: &mut W,
+    on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+) -> Result<ScpFileInfo, ProviderError> {
+    let channel = conn.open_exec(&format!("scp -p -f {}", shell_quote(remote_path))).await?;
+    receive(channel.into_stream(), out, on_progress).await
+}
+
+/// Upload `info.size` bytes from `input` to `remote_path` with `scp -t`
+pub async fn upload<R: AsyncRead + Unpin>(
+    conn: &SshConnection,
+    remote_path: &str,
+    input: &mut R,
+    info: ScpFileInfo,
+    on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+) -> Result<(), ProviderError> {
+    let (dir, name) = match remote_path.rsplit_once('/') {
+        Some(("", name)) => ("/", name),
+        Some((dir, name)) => (dir, name),
+        None => (".", remote_path),
+    };
+    let flags = if info.mtime.is_some() { "-p -t" } else { "-t" };
+    let channel = conn.open_exec(&format!("scp {} {}", flags, shell_quote(dir))).await?;
+    transmit(channel.into_stream(), name, input, info, on_progress).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_control() {
+        assert_eq!(parse_control("C0644 1234 report final.pdf").unwrap(), Control::File { mode: 0o644, size: 1234 });
+        assert_eq!(parse_control("T1700000000 0 1700000001 0").unwrap(), Control::Time { mtime: 1700000000 });
+        assert_eq!(parse_control("E").unwrap(), Control::EndDirectory);
+        assert!(parse_control("C0644 big name").is_err());
+        assert!(parse_control("X").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_receive_from_source() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let source = tokio::spawn(async move {
+            let mut ack = [0u8; 1];
+            server.read_exact(&mut ack).await.unwrap();
+            server.write_all(b"T1700000000 0 1700000000 0\n").await.unwrap();
+            server.read_exact(&mut ack).await.unwrap();
+            server.write_all(b"C0600 5 a.txt\n").await.unwrap();
+            server.read_exact(&mut ack).await.unwrap();
+            server.write_all(b"hello\0").await.unwrap();
+            server.read_exact(&mut ack).await.unwrap();
+            assert_eq!(ack, [0]);
+        });
+
+        let mut out = Vec::new();
+        let info = receive(client, &mut out, None).await.unwrap();
+        source.await.unwrap();
+        assert_eq!(out, b"hello");
+        assert_eq!(info, ScpFileInfo { size: 5, mode: 0o600, mtime: Some(1700000000) });
+    }
+
+    #[tokio::test]
+    async fn test_transmit_reports_sink_error() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let sink = tokio::spawn(async move {
+            server.write_all(&[0]).await.unwrap();
+            let mut line = vec![0u8; "C0644 3 b.txt\n".len()];
+            server.read_exact(&mut line).await.unwrap();
+            assert_eq!(line, b"C0644 3 b.txt\n");
+            server.write_all(b"\x02scp: /srv/b.txt: Permission denied\n").await.unwrap();
+        });
+
+        let info = ScpFileInfo { size: 3, mode: 0o644, mtime: None };
+        let result = transmit(client, "b.txt", &mut &b"abc"[..], info, None).await;
+        sink.await.unwrap();
+        assert!(matches!(result, Err(ProviderError::PermissionDenied(_))));
+    }
+}
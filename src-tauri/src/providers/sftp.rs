@@ -1,90 +1,67 @@
 //! SFTP Provider Implementation
 //!
 //! This module provides SFTP (SSH File Transfer Protocol) support using the russh crate.
-//! Supports both password and SSH key-based authentication.
+//! Supports password, SSH key, certificate, ssh-agent and keyboard-interactive
+//! authentication (see `ssh_auth`). Host aliases from `~/.ssh/config` and
+//! ProxyJump/ProxyCommand routes are honored (see `ssh_config`, `ssh_tunnel`).
 //!
 //! Status: v1.3.0
 
-use super::{ProviderError, ProviderType, RemoteEntry, SftpConfig, StorageProvider};
+use super::ssh_auth::SshAuthOptions;
+use super::sftp_pipeline::Pipeline;
+use super::ssh_exec::{self, ExecTransport};
+use super::ssh_pool::{self, shell_quote, ExecOutput, SshConnection};
+use super::ssh_tunnel::SshEndpoint;
+use super::{
+    PosixAttributes, ProviderError, ProviderType, RemoteEntry, SftpConfig, SshTransferMode, StorageProvider, Xattrs,
+};
 use async_trait::async_trait;
-use russh::client::{self, Config, Handle, Handler};
-use russh::keys::{self, known_hosts, PrivateKeyWithHashAlg, PublicKey};
-use russh::client::AuthResult;
+use russh::client::Config;
 use russh::{compression, Preferred};
 use russh_sftp::client::SftpSession;
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-/// SSH Client Handler for server key verification
-struct SshHandler {
-    /// The host being connected to (for known_hosts lookup)
-    host: String,
-    /// The port being connected to
-    port: u16,
-}
+/// How long the first remote command may take before exec is considered
+/// unavailable (sftp-only accounts often hang instead of refusing)
+const EXEC_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
-impl SshHandler {
-    fn new(host: &str, port: u16) -> Self {
-        Self {
-            host: host.to_string(),
-            port,
-        }
-    }
-}
+/// Maximum number of results returned by `find`
+const FIND_MAX_RESULTS: usize = 500;
 
-impl Handler for SshHandler {
-    type Error = russh::Error;
+/// Output cap for the remote `find` (NUL-separated records)
+const FIND_MAX_OUTPUT: usize = 4 * 1024 * 1024;
 
-    async fn check_server_key(
-        &mut self,
-        server_public_key: &PublicKey,
-    ) -> Result<bool, Self::Error> {
-        // Use russh's built-in known_hosts verification
-        match known_hosts::check_known_hosts(&self.host, self.port, server_public_key) {
-            Ok(true) => {
-                tracing::info!("SFTP: Host key verified for {}", self.host);
-                Ok(true)
-            }
-            Ok(false) => {
-                // SEC-P1-06: Host not in known_hosts — reject here.
-                // Frontend must call sftp_check_host_key + sftp_accept_host_key first.
-                tracing::warn!(
-                    "SFTP: Host key for {} not pre-approved via TOFU dialog — rejecting",
-                    self.host
-                );
-                Ok(false)
-            }
-            Err(keys::Error::KeyChanged { line }) => {
-                tracing::error!(
-                    "SFTP: REJECTING connection to {} - host key changed at known_hosts line {} (possible MITM attack)",
-                    self.host, line
-                );
-                Ok(false)
-            }
-            Err(e) => {
-                // SEC: Reject on unknown errors — do not silently accept.
-                // Only TOFU (Ok(false)) should auto-accept; other errors may indicate
-                // corrupted known_hosts or key format issues.
-                tracing::error!(
-                    "SFTP: REJECTING connection to {} - known_hosts verification error: {}",
-                    self.host, e
-                );
-                Ok(false)
-            }
-        }
-    }
-}
+/// Output cap for `getfattr` (values are base64-encoded)
+const XATTR_MAX_OUTPUT: usize = 1024 * 1024;
+
+/// Checksum commands run on the server in order of preference; `{path}` is
+/// replaced by the quoted path
+const CHECKSUM_COMMANDS: &[(&str, &str)] = &[
+    ("sha256", "sha256sum -- {path} 2>/dev/null || shasum -a 256 -- {path}"),
+    ("blake3", "b3sum -- {path}"),
+];
 
 /// SFTP Provider
 ///
 /// Provides secure file transfer over SSH using the SFTP protocol.
 pub struct SftpProvider {
     config: SftpConfig,
-    /// SSH connection handle
-    ssh_handle: Option<Handle<SshHandler>>,
+    /// SSH connection, shared with shells and tunnels to the same user@host:port
+    ssh: Option<Arc<SshConnection>>,
     /// SFTP session for file operations
     sftp: Option<SftpSession>,
+    /// scp/rsync over exec channels, used instead of `sftp` when the
+    /// server refuses the subsystem or the profile asks for it
+    transport: Option<ExecTransport>,
+    /// Second SFTP channel with many READ/WRITE requests in flight, opened on
+    /// the first transfer
+    pipeline: Option<Pipeline>,
+    /// The server refused the extra channel (e.g. `MaxSessions 1`);
+    /// transfers use `sftp` one request at a time
+    pipeline_refused: bool,
     /// Current working directory
     current_dir: String,
     /// Home directory (resolved on connect)
@@ -95,19 +72,30 @@ pub struct SftpProvider {
     upload_limit_bps: u64,
     /// SSH compression enabled (zlib@openssh.com)
     compression_enabled: bool,
+    /// Whether the account may run commands (None = not probed yet).
+    /// Restricted shells and `internal-sftp` accounts fall back to SFTP-only operations.
+    exec_available: Option<bool>,
+    /// Local (device, inode) → remote path of files uploaded in this session,
+    /// for `preserve.hardlinks`
+    uploaded_inodes: HashMap<(u64, u64), String>,
 }
 
 impl SftpProvider {
     pub fn new(config: SftpConfig) -> Self {
         Self {
             config,
-            ssh_handle: None,
+            ssh: None,
             sftp: None,
+            transport: None,
+            pipeline: None,
+            pipeline_refused: false,
             current_dir: "/".to_string(),
             home_dir: "/".to_string(),
             download_limit_bps: 0,
             upload_limit_bps: 0,
             compression_enabled: false,
+            exec_available: None,
+            uploaded_inodes: HashMap::new(),
         }
     }
 
@@ -137,6 +125,279 @@ impl SftpProvider {
         self.sftp.as_ref().ok_or(ProviderError::NotConnected)
     }
 
+    /// Connected over SFTP or over the scp/rsync fallback
+    fn ensure_connected(&self) -> Result<(), ProviderError> {
+        if self.sftp.is_none() && self.transport.is_none() {
+            return Err(ProviderError::NotConnected);
+        }
+        Ok(())
+    }
+
+    /// Pipelined transfer session, or `None` to transfer one request at a time
+    async fn pipeline(&mut self) -> Option<&Pipeline> {
+        if self.pipeline.is_none() && !self.pipeline_refused && self.config.pipeline_depth > 1 {
+            let ssh = self.ssh.clone()?;
+            match ssh.open_raw_sftp(self.config.timeout_secs.max(120)).await {
+                Ok(raw) => {
+                    let pipeline = Pipeline::new(raw, self.config.pipeline_depth, self.config.pipeline_block_size).await;
+                    self.pipeline = Some(pipeline);
+                }
+                Err(e) => {
+                    tracing::warn!("SFTP: {}, transferring without pipelining", e);
+                    self.pipeline_refused = true;
+                }
+            }
+        }
+        self.pipeline.as_ref()
+    }
+
+    /// Copy the contents of `full_path` into `local_path`
+    async fn download_contents(
+        &mut self,
+        full_path: &str,
+        local_path: &str,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        if let Some(transport) = &self.transport {
+            return transport.download(full_path, local_path, on_progress).await;
+        }
+        let sftp = self.get_sftp()?;
+
+        // Get file size
+        let metadata = sftp.metadata(full_path).await
+            .map_err(|e| ProviderError::NotFound(format!("File not found: {}", e)))?;
+        let total_size = metadata.size.unwrap_or(0);
+
+        let limit_bps = self.download_limit_bps;
+        if let Some(pipeline) = self.pipeline().await {
+            let mut local_file = tokio::fs::File::create(local_path).await
+                .map_err(|e| ProviderError::TransferFailed(format!("Failed to create local file: {}", e)))?;
+            let transferred = pipeline.download(full_path, &mut local_file, total_size, limit_bps, on_progress).await?;
+            tracing::info!("SFTP: Download complete: {} bytes", transferred);
+            return Ok(());
+        }
+        let sftp = self.get_sftp()?;
+
+        // Open remote file
+        let mut remote_file = sftp.open(full_path).await
+            .map_err(|e| ProviderError::TransferFailed(format!("Failed to open remote file: {}", e)))?;
+
+        // Create local file
+        let mut local_file = tokio::fs::File::create(local_path).await
+            .map_err(|e| ProviderError::TransferFailed(format!("Failed to create local file: {}", e)))?;
+
+        // Read and write in chunks with optional rate limiting
+        let mut buffer = vec![0u8; 32768]; // 32KB chunks
+        let mut transferred: u64 = 0;
+        let start = std::time::Instant::now();
+
+        loop {
+            let bytes_read = remote_file.read(&mut buffer).await
+                .map_err(|e| ProviderError::TransferFailed(format!("Read error: {}", e)))?;
+
+            if bytes_read == 0 {
+                break;
+            }
+
+            local_file.write_all(&buffer[..bytes_read]).await
+                .map_err(|e| ProviderError::TransferFailed(format!("Write error: {}", e)))?;
+
+            transferred += bytes_read as u64;
+
+            if let Some(ref progress) = on_progress {
+                progress(transferred, total_size);
+            }
+
+            // Apply bandwidth throttling
+            if self.download_limit_bps > 0 {
+                let expected = std::time::Duration::from_secs_f64(transferred as f64 / self.download_limit_bps as f64);
+                let elapsed = start.elapsed();
+                if expected > elapsed {
+                    tokio::time::sleep(expected - elapsed).await;
+                }
+            }
+        }
+
+        local_file.flush().await
+            .map_err(|e| ProviderError::TransferFailed(format!("Flush error: {}", e)))?;
+
+        tracing::info!("SFTP: Download complete: {} bytes", transferred);
+        Ok(())
+    }
+
+    /// Copy the contents of `local_path` into `full_path`
+    async fn upload_contents(
+        &mut self,
+        local_path: &str,
+        full_path: &str,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        if let Some(transport) = &self.transport {
+            return transport.upload(local_path, &full_path, on_progress).await;
+        }
+        self.get_sftp()?;
+
+        // Open local file
+        let mut local_file = tokio::fs::File::open(local_path).await
+            .map_err(|e| ProviderError::TransferFailed(format!("Failed to open local file: {}", e)))?;
+
+        let total_size = local_file.metadata().await
+            .map(|m| m.len())
+            .unwrap_or(0);
+
+        let limit_bps = self.upload_limit_bps;
+        if let Some(pipeline) = self.pipeline().await {
+            let transferred = pipeline.upload(full_path, &mut local_file, total_size, limit_bps, on_progress).await?;
+            tracing::info!("SFTP: Upload complete: {} bytes", transferred);
+            return Ok(());
+        }
+        let sftp = self.get_sftp()?;
+
+        // Create remote file
+        let mut remote_file = sftp.create(full_path).await
+            .map_err(|e| ProviderError::TransferFailed(format!("Failed to create remote file: {}", e)))?;
+
+        // Read and write in chunks with optional rate limiting
+        let mut buffer = vec![0u8; 32768]; // 32KB chunks
+        let mut transferred: u64 = 0;
+        let start = std::time::Instant::now();
+
+        loop {
+            let bytes_read = local_file.read(&mut buffer).await
+                .map_err(|e| ProviderError::TransferFailed(format!("Read error: {}", e)))?;
+
+            if bytes_read == 0 {
+                break;
+            }
+
+            remote_file.write_all(&buffer[..bytes_read]).await
+                .map_err(|e| ProviderError::TransferFailed(format!("Write error: {}", e)))?;
+
+            transferred += bytes_read as u64;
+
+            if let Some(ref progress) = on_progress {
+                progress(transferred, total_size);
+            }
+
+            // Apply bandwidth throttling
+            if self.upload_limit_bps > 0 {
+                let expected = std::time::Duration::from_secs_f64(transferred as f64 / self.upload_limit_bps as f64);
+                let elapsed = start.elapsed();
+                if expected > elapsed {
+                    tokio::time::sleep(expected - elapsed).await;
+                }
+            }
+        }
+
+        remote_file.shutdown().await
+            .map_err(|e| ProviderError::TransferFailed(format!("Shutdown error: {}", e)))?;
+
+        tracing::info!("SFTP: Upload complete: {} bytes", transferred);
+        Ok(())
+    }
+
+    /// Copy local mode/times/owner and xattrs to an uploaded file, as configured.
+    /// Failures only log a warning: the contents are already in place.
+    async fn preserve_upload(&mut self, local_path: &str, full_path: &str) {
+        let preserve = self.config.preserve;
+        if preserve.attributes() {
+            let result = match local_attributes(Path::new(local_path)) {
+                Ok(attrs) => self.set_attributes(full_path, &preserve.filter(&attrs)).await,
+                Err(e) => Err(ProviderError::IoError(e)),
+            };
+            if let Err(e) = result {
+                tracing::warn!("SFTP: Could not preserve attributes of {}: {}", full_path, e);
+            }
+        }
+        if preserve.xattrs {
+            let result = match local_xattrs(Path::new(local_path)) {
+                Ok(xattrs) => self.set_xattrs(full_path, &xattrs).await,
+                Err(e) => Err(ProviderError::IoError(e)),
+            };
+            if let Err(e) = result {
+                tracing::warn!("SFTP: Could not preserve extended attributes of {}: {}", full_path, e);
+            }
+        }
+    }
+
+    /// Copy remote xattrs and mode/times/owner to a downloaded file, as configured
+    async fn preserve_download(&mut self, full_path: &str, local_path: &str) {
+        let preserve = self.config.preserve;
+        // Before the mode, which may make the file read-only
+        if preserve.xattrs {
+            let result = match self.get_xattrs(full_path).await {
+                Ok(xattrs) => set_local_xattrs(Path::new(local_path), &xattrs).map_err(ProviderError::IoError),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::warn!("SFTP: Could not preserve extended attributes of {}: {}", local_path, e);
+            }
+        }
+        if preserve.attributes() {
+            let result = match self.get_attributes(full_path).await {
+                Ok(attrs) => set_local_attributes(Path::new(local_path), &preserve.filter(&attrs)).map_err(ProviderError::IoError),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::warn!("SFTP: Could not preserve attributes of {}: {}", local_path, e);
+            }
+        }
+    }
+
+    /// With `preserve.hardlinks`, create `full_path` as a hard link to an earlier
+    /// upload of the same local inode instead of sending the contents again.
+    /// Returns false when the file still has to be uploaded.
+    async fn upload_as_hardlink(&mut self, local_path: &str, full_path: &str) -> bool {
+        if !self.config.preserve.hardlinks {
+            return false;
+        }
+        let Some(inode) = linked_inode(Path::new(local_path)) else {
+            return false;
+        };
+        let existing = match self.uploaded_inodes.get(&inode) {
+            Some(existing) if existing != full_path => existing.clone(),
+            _ => return false,
+        };
+
+        // Link under a temporary name first: the current file is only replaced
+        // once the link exists
+        let temp_path = format!("{}.aeroftp-link", full_path);
+        let result = match self.create_hardlink(&existing, &temp_path).await {
+            Ok(()) => self.replace_with(&temp_path, full_path).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => {
+                tracing::info!("SFTP: Linked {} to {} (same local inode)", full_path, existing);
+                true
+            }
+            Err(e) => {
+                tracing::debug!("SFTP: Hard link to {} failed ({}), uploading a copy", existing, e);
+                let _ = self.delete(&temp_path).await;
+                false
+            }
+        }
+    }
+
+    /// Move `temp_path` over `full_path`. Plain SFTP renames refuse to overwrite,
+    /// so an existing file is removed right before the rename.
+    async fn replace_with(&mut self, temp_path: &str, full_path: &str) -> Result<(), ProviderError> {
+        if matches!(self.exists(full_path).await, Ok(true)) {
+            self.delete(full_path).await?;
+        }
+        self.rename(temp_path, full_path).await
+    }
+
+    /// Remember a completed upload so later files sharing its inode become links
+    fn record_uploaded_inode(&mut self, local_path: &str, full_path: &str) {
+        if !self.config.preserve.hardlinks {
+            return;
+        }
+        if let Some(inode) = linked_inode(Path::new(local_path)) {
+            self.uploaded_inodes.entry(inode).or_insert_with(|| full_path.to_string());
+        }
+    }
+
     /// Get mutable SFTP session or error if not connected
     #[allow(dead_code)]
     fn get_sftp_mut(&mut self) -> Result<&mut SftpSession, ProviderError> {
@@ -172,49 +433,292 @@ impl SftpProvider {
             link_target: None,
             mime_type: None,
             metadata: Default::default(),
+            content_hash: None,
         }
     }
 
-    /// Authenticate using SSH private key
-    async fn authenticate_with_key(&self, handle: &mut Handle<SshHandler>) -> Result<bool, ProviderError> {
-        let key_path = self.config.private_key_path.as_ref()
-            .ok_or_else(|| ProviderError::AuthenticationFailed("No private key path specified".to_string()))?;
+    /// Check once per connection whether the account may run commands
+    async fn probe_exec(&mut self) -> bool {
+        if let Some(available) = self.exec_available {
+            return available;
+        }
+        let Some(ssh) = self.ssh.clone() else { return false };
+        let probe = tokio::time::timeout(EXEC_PROBE_TIMEOUT, ssh.exec_output("echo aeroftp-exec", 64)).await;
+        let available = matches!(&probe, Ok(Ok(out)) if out.success() && out.stdout.starts_with(b"aeroftp-exec"));
+        if !available {
+            tracing::info!("SFTP: Remote commands unavailable on {}, using SFTP-only fallbacks", self.config.host);
+        }
+        self.exec_available = Some(available);
+        available
+    }
 
-        // Expand ~ in path (cross-platform: uses PathBuf for correct separator)
-        let expanded_path = if let Some(stripped) = key_path.strip_prefix("~/") {
-            if let Some(home) = dirs::home_dir() {
-                home.join(stripped).to_string_lossy().to_string()
-            } else {
-                key_path.clone()
+    /// Run `command` on the server over an exec channel of the shared SSH
+    /// connection. Returns `None` when the account cannot run commands.
+    async fn exec(&mut self, command: &str, max_output: usize) -> Option<ExecOutput> {
+        if !self.probe_exec().await {
+            return None;
+        }
+        let ssh = self.ssh.clone()?;
+        match ssh.exec_output(command, max_output).await {
+            Ok(output) => Some(output),
+            Err(e) => {
+                tracing::debug!("SFTP: Remote command failed: {}", e);
+                None
             }
-        } else {
-            key_path.clone()
-        };
+        }
+    }
+
+    /// Case-insensitive name search by walking directories over SFTP
+    async fn find_via_sftp(&self, root: String, pattern: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
+        let sftp = self.get_sftp()?;
+        let pattern_lower = pattern.to_lowercase();
+        let mut results = Vec::new();
+        let mut dirs_to_scan = vec![root];
+
+        while let Some(dir) = dirs_to_scan.pop() {
+            let entries = match sftp.read_dir(&dir).await {
+                Ok(e) => e,
+                Err(_) => continue, // Skip inaccessible directories
+            };
+
+            for entry in entries {
+                let name = entry.file_name();
+                if name == "." || name == ".." {
+                    continue;
+                }
+
+                let entry_path = if dir == "/" {
+                    format!("/{}", name)
+                } else {
+                    format!("{}/{}", dir.trim_end_matches('/'), name)
+                };
+
+                let remote_entry = self.metadata_to_entry(name.clone(), entry_path.clone(), &entry.metadata());
+
+                if remote_entry.is_dir {
+                    dirs_to_scan.push(entry_path.clone());
+                }
+
+                if name.to_lowercase().contains(&pattern_lower) {
+                    results.push(remote_entry);
+                    if results.len() >= FIND_MAX_RESULTS {
+                        return Ok(results);
+                    }
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Build the authentication chain from the connection config and the
+    /// endpoint resolved from `~/.ssh/config` (profile keys are tried first)
+    fn auth_options(&self, endpoint: &SshEndpoint) -> SshAuthOptions {
+        let mut identity_files: Vec<String> = self.config.private_key_path.iter().cloned().collect();
+        identity_files.extend(self.config.identity_files.iter().cloned());
+        for file in &endpoint.identity_files {
+            if !identity_files.contains(file) {
+                identity_files.push(file.clone());
+            }
+        }
+
+        SshAuthOptions {
+            username: endpoint.username.clone(),
+            password: self.config.password.clone(),
+            identity_files,
+            key_passphrase: self.config.key_passphrase.clone(),
+            certificate_path: self.config.certificate_path.clone().or_else(|| endpoint.certificate_path.clone()),
+            use_agent: self.config.use_agent && !endpoint.identities_only,
+            keyboard_interactive: self.config.keyboard_interactive,
+            label: format!("{}@{}", endpoint.username, endpoint.host),
+        }
+    }
+}
+
+/// Mode, ownership and times of a local file (symlinks are followed)
+fn local_attributes(path: &Path) -> std::io::Result<PosixAttributes> {
+    let metadata = std::fs::metadata(path)?;
+    let epoch = |time: std::io::Result<std::time::SystemTime>| {
+        time.ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as u32)
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Ok(PosixAttributes {
+            mode: Some(metadata.mode() & 0o7777),
+            uid: Some(metadata.uid()),
+            gid: Some(metadata.gid()),
+            atime: epoch(metadata.accessed()),
+            mtime: epoch(metadata.modified()),
+        })
+    }
+    #[cfg(not(unix))]
+    Ok(PosixAttributes {
+        atime: epoch(metadata.accessed()),
+        mtime: epoch(metadata.modified()),
+        ..Default::default()
+    })
+}
+
+/// Apply `attrs` to a local file: times first (needs write access), then owner and mode
+fn set_local_attributes(path: &Path, attrs: &PosixAttributes) -> std::io::Result<()> {
+    let time = |secs: u32| std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs as u64);
+    if attrs.atime.is_some() || attrs.mtime.is_some() {
+        let mut times = std::fs::FileTimes::new();
+        if let Some(atime) = attrs.atime {
+            times = times.set_accessed(time(atime));
+        }
+        if let Some(mtime) = attrs.mtime {
+            times = times.set_modified(time(mtime));
+        }
+        std::fs::File::options().write(true).open(path)?.set_times(times)?;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if attrs.uid.is_some() || attrs.gid.is_some() {
+            std::os::unix::fs::chown(path, attrs.uid, attrs.gid)?;
+        }
+        if let Some(mode) = attrs.mode {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode & 0o7777))?;
+        }
+    }
+    Ok(())
+}
+
+/// `user.*` extended attributes of a local file
+#[cfg(unix)]
+fn local_xattrs(path: &Path) -> std::io::Result<Xattrs> {
+    let mut xattrs = Xattrs::new();
+    for name in xattr::list(path)? {
+        let name = name.to_string_lossy().to_string();
+        if !name.starts_with("user.") {
+            continue;
+        }
+        if let Some(value) = xattr::get(path, &name)? {
+            xattrs.insert(name, value);
+        }
+    }
+    Ok(xattrs)
+}
+
+#[cfg(not(unix))]
+fn local_xattrs(_path: &Path) -> std::io::Result<Xattrs> {
+    Ok(Xattrs::new())
+}
 
-        tracing::info!("SFTP: Loading private key from {}", expanded_path);
+#[cfg(unix)]
+fn set_local_xattrs(path: &Path, xattrs: &Xattrs) -> std::io::Result<()> {
+    for (name, value) in xattrs {
+        xattr::set(path, name, value)?;
+    }
+    Ok(())
+}
 
-        // Load and parse the key using russh's built-in key loading
-        use secrecy::ExposeSecret;
-        let passphrase_str = self.config.key_passphrase.as_ref().map(|s| s.expose_secret().to_string());
-        let key_pair = keys::load_secret_key(&expanded_path, passphrase_str.as_deref())
-            .map_err(|e| ProviderError::AuthenticationFailed(format!("Failed to load key: {}", e)))?;
+#[cfg(not(unix))]
+fn set_local_xattrs(_path: &Path, _xattrs: &Xattrs) -> std::io::Result<()> {
+    Ok(())
+}
 
-        // Wrap in PrivateKeyWithHashAlg (required by russh 0.54+)
-        let key_with_hash = PrivateKeyWithHashAlg::new(Arc::new(key_pair), None);
+/// (device, inode) of a local file with more than one name
+#[cfg(unix)]
+fn linked_inode(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::metadata(path).ok()?;
+    (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
 
-        // Authenticate
-        let auth_result = handle.authenticate_publickey(&self.config.username, key_with_hash).await
-            .map_err(|e| ProviderError::AuthenticationFailed(format!("Key authentication failed: {}", e)))?;
+#[cfg(not(unix))]
+fn linked_inode(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
 
-        match auth_result {
-            AuthResult::Success => Ok(true),
-            AuthResult::Failure { .. } => Ok(false),
+/// Sort: directories first, then alphabetically
+fn sort_entries(entries: &mut [RemoteEntry]) {
+    entries.sort_by(|a, b| {
+        match (a.is_dir, b.is_dir) {
+            (true, false) => std::cmp::Ordering::Less,
+            (false, true) => std::cmp::Ordering::Greater,
+            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
         }
+    });
+}
+
+/// Escape glob metacharacters so `find -iname` matches `pattern` literally
+pub(super) fn escape_glob(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Parse GNU `find -printf '%y\t%s\t%T@\t%m\t%p\0'` records. An incomplete
+/// trailing record (output cap reached) is dropped.
+pub(super) fn parse_find_output(output: &[u8], limit: usize) -> Vec<RemoteEntry> {
+    let mut records: Vec<&[u8]> = output.split(|b| *b == 0).collect();
+    records.pop(); // after the last NUL: empty, or a truncated record
+    records.into_iter()
+        .filter_map(|record| {
+            let record = String::from_utf8_lossy(record);
+            let mut fields = record.splitn(5, '\t');
+            let (kind, size, mtime, mode, path) =
+                (fields.next()?, fields.next()?, fields.next()?, fields.next()?, fields.next()?);
+            let is_dir = kind == "d";
+            let modified = mtime.split('.').next()
+                .and_then(|secs| secs.parse::<i64>().ok())
+                .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string());
+            Some(RemoteEntry {
+                name: path.rsplit('/').next().unwrap_or(path).to_string(),
+                path: path.to_string(),
+                is_dir,
+                size: if is_dir { 0 } else { size.parse().unwrap_or(0) },
+                modified,
+                permissions: u32::from_str_radix(mode, 8).ok().map(|m| format_permissions(m, is_dir)),
+                owner: None,
+                group: None,
+                is_symlink: kind == "l",
+                link_target: None,
+                mime_type: None,
+                metadata: Default::default(),
+                content_hash: None,
+            })
+        })
+        .take(limit)
+        .collect()
+}
+
+/// Hex digest from `sha256sum`/`shasum`/`b3sum` output (`<digest>  <path>`)
+fn parse_checksum_output(output: &ExecOutput) -> Option<String> {
+    if !output.success() {
+        return None;
     }
+    let line = String::from_utf8_lossy(&output.stdout);
+    // A leading backslash marks an escaped file name, not part of the digest
+    let digest = line.split_whitespace().next()?.trim_start_matches('\\').to_lowercase();
+    (digest.len() == 64 && digest.bytes().all(|b| b.is_ascii_hexdigit())).then_some(digest)
+}
+
+/// Total from `du -s` output (`<size>\t<path>`); du exits non-zero when it
+/// could not read some subdirectories but still prints a total
+fn parse_du_output(output: &ExecOutput) -> Option<u64> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .last()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
 }
 
 /// Format Unix permissions as rwx string
-fn format_permissions(mode: u32, is_dir: bool) -> String {
+pub(super) fn format_permissions(mode: u32, is_dir: bool) -> String {
     let user = format!(
         "{}{}{}",
         if mode & 0o400 != 0 { 'r' } else { '-' },
@@ -273,53 +777,39 @@ impl StorageProvider for SftpProvider {
             ..Default::default()
         };
 
-        // Connect to SSH server
-        let addr = format!("{}:{}", self.config.host, self.config.port);
-        let mut handle = client::connect(Arc::new(config), &addr, SshHandler::new(&self.config.host, self.config.port)).await
-            .map_err(|e| ProviderError::ConnectionFailed(format!("SSH connection failed: {}", e)))?;
-
-        tracing::info!("SFTP: SSH connection established, authenticating...");
-
-        // Authenticate
-        let authenticated = if self.config.private_key_path.is_some() {
-            // Try key-based authentication
-            self.authenticate_with_key(&mut handle).await?
-        } else if let Some(password) = &self.config.password {
-            // Password authentication
-            use secrecy::ExposeSecret;
-            let result = handle.authenticate_password(&self.config.username, password.expose_secret()).await
-                .map_err(|e| ProviderError::AuthenticationFailed(format!("Password auth failed: {}", e)))?;
-            matches!(result, AuthResult::Success)
-        } else {
-            return Err(ProviderError::AuthenticationFailed(
-                "No authentication method provided (need password or private key)".to_string()
-            ));
+        // Resolve ~/.ssh/config aliases, then connect directly or through ProxyJump/ProxyCommand
+        // (or reuse the connection of a shell/tunnel already open to the same user@host:port)
+        let endpoint = SshEndpoint::resolve(
+            &self.config.host, self.config.port, &self.config.username, self.config.use_ssh_config,
+            self.config.proxy.clone(),
+        )?;
+        let ssh = ssh_pool::connect(&endpoint, &self.auth_options(&endpoint), config).await?;
+
+        tracing::info!("SFTP: Authenticated, opening SFTP channel...");
+
+        // Open SFTP subsystem channel; restricted servers may only allow scp or rsync
+        let sftp = match self.config.transfer_mode {
+            SshTransferMode::Sftp => Some(ssh.open_sftp().await?),
+            SshTransferMode::Auto => match ssh.open_sftp().await {
+                Ok(sftp) => Some(sftp),
+                Err(e) => {
+                    tracing::warn!("SFTP: {}, falling back to scp/rsync", e);
+                    None
+                }
+            },
+            SshTransferMode::Scp | SshTransferMode::Rsync => None,
         };
 
-        if !authenticated {
-            return Err(ProviderError::AuthenticationFailed(
-                "Authentication rejected by server".to_string()
-            ));
-        }
-
-        tracing::info!("SFTP: Authenticated successfully, opening SFTP channel...");
-
-        // Open SFTP subsystem channel
-        let channel = handle.channel_open_session().await
-            .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to open session channel: {}", e)))?;
-
-        channel.request_subsystem(true, "sftp").await
-            .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to request SFTP subsystem: {}", e)))?;
-
-        // Create SFTP session from channel
-        let sftp = SftpSession::new(channel.into_stream()).await
-            .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to create SFTP session: {}", e)))?;
-
         // Get home directory (canonicalize ".")
-        let home = sftp.canonicalize(".").await
-            .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to get home directory: {}", e)))?;
-
-        self.home_dir = home;
+        if let Some(sftp) = &sftp {
+            self.home_dir = sftp.canonicalize(".").await
+                .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to get home directory: {}", e)))?;
+        } else {
+            let transport = ExecTransport::open(ssh.clone(), self.config.transfer_mode).await?;
+            tracing::info!("SFTP: Using {} over SSH exec channels", transport.transfer().name());
+            self.home_dir = transport.home().await?;
+            self.transport = Some(transport);
+        }
 
         // Set initial directory
         if let Some(initial) = &self.config.initial_path {
@@ -328,8 +818,12 @@ impl StorageProvider for SftpProvider {
             self.current_dir = self.home_dir.clone();
         }
 
-        self.ssh_handle = Some(handle);
+        self.ssh = Some(ssh);
         self.sftp = Some(sftp);
+        self.exec_available = None;
+        self.uploaded_inodes.clear();
+        // Probe now so supports_checksum()/supports_xattrs() answer for this server
+        self.probe_exec().await;
 
         tracing::info!("SFTP: Connected successfully to {} (home: {})", self.config.host, self.home_dir);
         Ok(())
@@ -343,10 +837,13 @@ impl StorageProvider for SftpProvider {
             let _ = sftp.close().await;
         }
 
-        // Close SSH handle
-        if let Some(handle) = self.ssh_handle.take() {
-            let _ = handle.disconnect(russh::Disconnect::ByApplication, "", "en").await;
-        }
+        // Release the SSH connection; it closes once shells and tunnels on it are gone too
+        self.pipeline = None;
+        self.pipeline_refused = false;
+        self.transport = None;
+        self.ssh = None;
+        self.exec_available = None;
+        self.uploaded_inodes.clear();
 
         self.current_dir = "/".to_string();
         self.home_dir = "/".to_string();
@@ -356,15 +853,21 @@ impl StorageProvider for SftpProvider {
     }
 
     fn is_connected(&self) -> bool {
-        self.sftp.is_some()
+        self.sftp.is_some() || self.transport.is_some()
     }
 
     async fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
-        let sftp = self.get_sftp()?;
         let full_path = self.normalize_path(path);
 
         tracing::debug!("SFTP: Listing directory: {}", full_path);
 
+        if let Some(transport) = &self.transport {
+            let mut result = transport.list(&full_path).await?;
+            sort_entries(&mut result);
+            return Ok(result);
+        }
+
+        let sftp = self.get_sftp()?;
         let entries = sftp.read_dir(&full_path).await
             .map_err(|e| ProviderError::NotFound(format!("Failed to list directory: {}", e)))?;
 
@@ -413,14 +916,7 @@ impl StorageProvider for SftpProvider {
             result.push(remote_entry);
         }
 
-        // Sort: directories first, then alphabetically
-        result.sort_by(|a, b| {
-            match (a.is_dir, b.is_dir) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-            }
-        });
+        sort_entries(&mut result);
 
         tracing::debug!("SFTP: Listed {} entries", result.len());
         Ok(result)
@@ -430,99 +926,64 @@ impl StorageProvider for SftpProvider {
         Ok(self.current_dir.clone())
     }
 
-    async fn cd(&mut self, path: &str) -> Result<(), ProviderError> {
-        let sftp = self.get_sftp()?;
-        let full_path = self.normalize_path(path);
-
-        // Verify the directory exists
-        let metadata = sftp.metadata(&full_path).await
-            .map_err(|e| ProviderError::NotFound(format!("Directory not found: {}", e)))?;
-
-        if let Some(perms) = metadata.permissions {
-            if (perms & 0o40000) == 0 {
-                return Err(ProviderError::InvalidPath(format!("{} is not a directory", full_path)));
-            }
-        }
-
-        self.current_dir = full_path;
-        tracing::debug!("SFTP: Changed directory to {}", self.current_dir);
-        Ok(())
-    }
-
-    async fn cd_up(&mut self) -> Result<(), ProviderError> {
-        self.cd("..").await
-    }
-
-    async fn download(
-        &mut self,
-        remote_path: &str,
-        local_path: &str,
-        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
-    ) -> Result<(), ProviderError> {
-        let sftp = self.get_sftp()?;
-        let full_path = self.normalize_path(remote_path);
-
-        tracing::info!("SFTP: Downloading {} to {}", full_path, local_path);
-
-        // Get file size
-        let metadata = sftp.metadata(&full_path).await
-            .map_err(|e| ProviderError::NotFound(format!("File not found: {}", e)))?;
-        let total_size = metadata.size.unwrap_or(0);
-
-        // Open remote file
-        let mut remote_file = sftp.open(&full_path).await
-            .map_err(|e| ProviderError::TransferFailed(format!("Failed to open remote file: {}", e)))?;
-
-        // Create local file
-        let mut local_file = tokio::fs::File::create(local_path).await
-            .map_err(|e| ProviderError::TransferFailed(format!("Failed to create local file: {}", e)))?;
-
-        // Read and write in chunks with optional rate limiting
-        let mut buffer = vec![0u8; 32768]; // 32KB chunks
-        let mut transferred: u64 = 0;
-        let start = std::time::Instant::now();
-
-        loop {
-            let bytes_read = remote_file.read(&mut buffer).await
-                .map_err(|e| ProviderError::TransferFailed(format!("Read error: {}", e)))?;
-
-            if bytes_read == 0 {
-                break;
-            }
-
-            local_file.write_all(&buffer[..bytes_read]).await
-                .map_err(|e| ProviderError::TransferFailed(format!("Write error: {}", e)))?;
-
-            transferred += bytes_read as u64;
+    async fn cd(&mut self, path: &str) -> Result<(), ProviderError> {
+        let full_path = self.normalize_path(path);
 
-            if let Some(ref progress) = on_progress {
-                progress(transferred, total_size);
+        if let Some(transport) = &self.transport {
+            let entry = transport.stat(&full_path).await?;
+            if !entry.is_dir && !entry.is_symlink {
+                return Err(ProviderError::InvalidPath(format!("{} is not a directory", full_path)));
             }
+            self.current_dir = full_path;
+            return Ok(());
+        }
 
-            // Apply bandwidth throttling
-            if self.download_limit_bps > 0 {
-                let expected = std::time::Duration::from_secs_f64(transferred as f64 / self.download_limit_bps as f64);
-                let elapsed = start.elapsed();
-                if expected > elapsed {
-                    tokio::time::sleep(expected - elapsed).await;
-                }
+        let sftp = self.get_sftp()?;
+        // Verify the directory exists
+        let metadata = sftp.metadata(&full_path).await
+            .map_err(|e| ProviderError::NotFound(format!("Directory not found: {}", e)))?;
+
+        if let Some(perms) = metadata.permissions {
+            if (perms & 0o40000) == 0 {
+                return Err(ProviderError::InvalidPath(format!("{} is not a directory", full_path)));
             }
         }
 
-        local_file.flush().await
-            .map_err(|e| ProviderError::TransferFailed(format!("Flush error: {}", e)))?;
+        self.current_dir = full_path;
+        tracing::debug!("SFTP: Changed directory to {}", self.current_dir);
+        Ok(())
+    }
 
-        tracing::info!("SFTP: Download complete: {} bytes", transferred);
+    async fn cd_up(&mut self) -> Result<(), ProviderError> {
+        self.cd("..").await
+    }
+
+    async fn download(
+        &mut self,
+        remote_path: &str,
+        local_path: &str,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        let full_path = self.normalize_path(remote_path);
+
+        tracing::info!("SFTP: Downloading {} to {}", full_path, local_path);
+
+        self.download_contents(&full_path, local_path, on_progress).await?;
+        self.preserve_download(&full_path, local_path).await;
         Ok(())
     }
 
     async fn download_to_bytes(&mut self, remote_path: &str) -> Result<Vec<u8>, ProviderError> {
-        let sftp = self.get_sftp()?;
         let full_path = self.normalize_path(remote_path);
         let limit = super::MAX_DOWNLOAD_TO_BYTES;
 
         tracing::debug!("SFTP: Reading file to bytes: {}", full_path);
 
+        if let Some(transport) = &self.transport {
+            return transport.read(&full_path, limit).await;
+        }
+        let sftp = self.get_sftp()?;
+
         // H2: Check file size before reading to prevent OOM
         if let Ok(metadata) = sftp.metadata(&full_path).await {
             if metadata.size.unwrap_or(0) > limit {
@@ -553,68 +1014,29 @@ impl StorageProvider for SftpProvider {
         remote_path: &str,
         on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
     ) -> Result<(), ProviderError> {
-        let sftp = self.get_sftp()?;
         let full_path = self.normalize_path(remote_path);
 
         tracing::info!("SFTP: Uploading {} to {}", local_path, full_path);
 
-        // Open local file
-        let mut local_file = tokio::fs::File::open(local_path).await
-            .map_err(|e| ProviderError::TransferFailed(format!("Failed to open local file: {}", e)))?;
-
-        let total_size = local_file.metadata().await
-            .map(|m| m.len())
-            .unwrap_or(0);
-
-        // Create remote file
-        let mut remote_file = sftp.create(&full_path).await
-            .map_err(|e| ProviderError::TransferFailed(format!("Failed to create remote file: {}", e)))?;
-
-        // Read and write in chunks with optional rate limiting
-        let mut buffer = vec![0u8; 32768]; // 32KB chunks
-        let mut transferred: u64 = 0;
-        let start = std::time::Instant::now();
-
-        loop {
-            let bytes_read = local_file.read(&mut buffer).await
-                .map_err(|e| ProviderError::TransferFailed(format!("Read error: {}", e)))?;
-
-            if bytes_read == 0 {
-                break;
-            }
-
-            remote_file.write_all(&buffer[..bytes_read]).await
-                .map_err(|e| ProviderError::TransferFailed(format!("Write error: {}", e)))?;
-
-            transferred += bytes_read as u64;
-
-            if let Some(ref progress) = on_progress {
-                progress(transferred, total_size);
-            }
-
-            // Apply bandwidth throttling
-            if self.upload_limit_bps > 0 {
-                let expected = std::time::Duration::from_secs_f64(transferred as f64 / self.upload_limit_bps as f64);
-                let elapsed = start.elapsed();
-                if expected > elapsed {
-                    tokio::time::sleep(expected - elapsed).await;
-                }
-            }
+        if self.upload_as_hardlink(local_path, &full_path).await {
+            return Ok(());
         }
-
-        remote_file.shutdown().await
-            .map_err(|e| ProviderError::TransferFailed(format!("Shutdown error: {}", e)))?;
-
-        tracing::info!("SFTP: Upload complete: {} bytes", transferred);
+        self.upload_contents(local_path, &full_path, on_progress).await?;
+        self.preserve_upload(local_path, &full_path).await;
+        self.record_uploaded_inode(local_path, &full_path);
         Ok(())
     }
 
     async fn mkdir(&mut self, path: &str) -> Result<(), ProviderError> {
-        let sftp = self.get_sftp()?;
         let full_path = self.normalize_path(path);
 
         tracing::info!("SFTP: Creating directory: {}", full_path);
 
+        if let Some(transport) = &self.transport {
+            return transport.mkdir(&full_path).await;
+        }
+        let sftp = self.get_sftp()?;
+
         sftp.create_dir(&full_path).await
             .map_err(|e| ProviderError::ServerError(format!("Failed to create directory: {}", e)))?;
 
@@ -622,11 +1044,15 @@ impl StorageProvider for SftpProvider {
     }
 
     async fn delete(&mut self, path: &str) -> Result<(), ProviderError> {
-        let sftp = self.get_sftp()?;
         let full_path = self.normalize_path(path);
 
         tracing::info!("SFTP: Deleting file: {}", full_path);
 
+        if let Some(transport) = &self.transport {
+            return transport.delete(&full_path).await;
+        }
+        let sftp = self.get_sftp()?;
+
         sftp.remove_file(&full_path).await
             .map_err(|e| ProviderError::ServerError(format!("Failed to delete file: {}", e)))?;
 
@@ -634,11 +1060,15 @@ impl StorageProvider for SftpProvider {
     }
 
     async fn rmdir(&mut self, path: &str) -> Result<(), ProviderError> {
-        let sftp = self.get_sftp()?;
         let full_path = self.normalize_path(path);
 
         tracing::info!("SFTP: Removing directory: {}", full_path);
 
+        if let Some(transport) = &self.transport {
+            return transport.rmdir(&full_path).await;
+        }
+        let sftp = self.get_sftp()?;
+
         sftp.remove_dir(&full_path).await
             .map_err(|e| ProviderError::ServerError(format!("Failed to remove directory: {}", e)))?;
 
@@ -670,12 +1100,16 @@ impl StorageProvider for SftpProvider {
     }
 
     async fn rename(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
-        let sftp = self.get_sftp()?;
         let from_path = self.normalize_path(from);
         let to_path = self.normalize_path(to);
 
         tracing::info!("SFTP: Renaming {} to {}", from_path, to_path);
 
+        if let Some(transport) = &self.transport {
+            return transport.rename(&from_path, &to_path).await;
+        }
+        let sftp = self.get_sftp()?;
+
         sftp.rename(&from_path, &to_path).await
             .map_err(|e| ProviderError::ServerError(format!("Failed to rename: {}", e)))?;
 
@@ -683,8 +1117,11 @@ impl StorageProvider for SftpProvider {
     }
 
     async fn stat(&mut self, path: &str) -> Result<RemoteEntry, ProviderError> {
-        let sftp = self.get_sftp()?;
         let full_path = self.normalize_path(path);
+        if let Some(transport) = &self.transport {
+            return transport.stat(&full_path).await;
+        }
+        let sftp = self.get_sftp()?;
 
         let metadata = sftp.metadata(&full_path).await
             .map_err(|e| ProviderError::NotFound(format!("File not found: {}", e)))?;
@@ -712,8 +1149,11 @@ impl StorageProvider for SftpProvider {
     }
 
     async fn size(&mut self, path: &str) -> Result<u64, ProviderError> {
-        let sftp = self.get_sftp()?;
         let full_path = self.normalize_path(path);
+        if let Some(transport) = &self.transport {
+            return Ok(transport.stat(&full_path).await?.size);
+        }
+        let sftp = self.get_sftp()?;
 
         let metadata = sftp.metadata(&full_path).await
             .map_err(|e| ProviderError::NotFound(format!("File not found: {}", e)))?;
@@ -722,8 +1162,11 @@ impl StorageProvider for SftpProvider {
     }
 
     async fn exists(&mut self, path: &str) -> Result<bool, ProviderError> {
-        let sftp = self.get_sftp()?;
         let full_path = self.normalize_path(path);
+        if let Some(transport) = &self.transport {
+            return transport.exists(&full_path).await;
+        }
+        let sftp = self.get_sftp()?;
 
         match sftp.try_exists(&full_path).await {
             Ok(exists) => Ok(exists),
@@ -734,8 +1177,13 @@ impl StorageProvider for SftpProvider {
     async fn keep_alive(&mut self) -> Result<(), ProviderError> {
         // SFTP over SSH is a persistent connection
         // Just check if we're still connected
-        if self.sftp.is_none() {
-            return Err(ProviderError::NotConnected);
+        self.ensure_connected()?;
+
+        // The shared SSH connection may have been dropped under us
+        if let Some(ssh) = &self.ssh {
+            if ssh.is_closed().await {
+                return Err(ProviderError::NotConnected);
+            }
         }
 
         // Optionally do a simple operation to verify connection
@@ -749,9 +1197,10 @@ impl StorageProvider for SftpProvider {
     }
 
     async fn server_info(&mut self) -> Result<String, ProviderError> {
+        let protocol = self.transport.as_ref().map_or("SFTP", |t| t.transfer().name());
         Ok(format!(
-            "SFTP Server: {}:{} (user: {}, home: {})",
-            self.config.host, self.config.port, self.config.username, self.home_dir
+            "SFTP Server: {}:{} (user: {}, home: {}, transfers: {})",
+            self.config.host, self.config.port, self.config.username, self.home_dir, protocol
         ))
     }
 
@@ -760,12 +1209,17 @@ impl StorageProvider for SftpProvider {
     }
 
     async fn chmod(&mut self, path: &str, mode: u32) -> Result<(), ProviderError> {
-        let sftp = self.get_sftp()?;
         let full_path = self.normalize_path(path);
 
         tracing::info!("SFTP: chmod {} to {:o}", full_path, mode);
 
-        let attrs = russh_sftp::protocol::FileAttributes { permissions: Some(mode), ..Default::default() };
+        if let Some(transport) = &self.transport {
+            return transport.chmod(&full_path, mode).await;
+        }
+        let sftp = self.get_sftp()?;
+
+        // empty(), not default(): default() also carries size 0 and zeroed owner and times
+        let attrs = russh_sftp::protocol::FileAttributes { permissions: Some(mode), ..russh_sftp::protocol::FileAttributes::empty() };
 
         sftp.set_metadata(&full_path, attrs).await
             .map_err(|e| ProviderError::ServerError(format!("Failed to chmod: {}", e)))?;
@@ -777,54 +1231,212 @@ impl StorageProvider for SftpProvider {
         true // SFTP supports symlinks
     }
 
+    async fn read_link(&mut self, path: &str) -> Result<String, ProviderError> {
+        let full_path = self.normalize_path(path);
+        if let Some(transport) = &self.transport {
+            return transport.read_link(&full_path).await;
+        }
+        let sftp = self.get_sftp()?;
+
+        sftp.read_link(&full_path).await
+            .map_err(|e| ProviderError::NotFound(format!("Failed to read link: {}", e)))
+    }
+
+    async fn create_symlink(&mut self, target: &str, link_path: &str) -> Result<(), ProviderError> {
+        let full_path = self.normalize_path(link_path);
+
+        tracing::info!("SFTP: Creating symlink {} -> {}", full_path, target);
+
+        if let Some(transport) = &self.transport {
+            return transport.symlink(target, &full_path).await;
+        }
+        let sftp = self.get_sftp()?;
+
+        // OpenSSH's sftp-server reads SSH_FXP_SYMLINK arguments in the reverse of the
+        // draft's order (target first); russh-sftp sends them as given
+        sftp.symlink(target, &full_path).await
+            .map_err(|e| ProviderError::ServerError(format!("Failed to create symlink: {}", e)))
+    }
+
+    fn supports_hardlinks(&self) -> bool {
+        true // hardlink@openssh.com, or `ln` over exec
+    }
+
+    async fn create_hardlink(&mut self, existing: &str, link_path: &str) -> Result<(), ProviderError> {
+        let existing = self.normalize_path(existing);
+        let full_path = self.normalize_path(link_path);
+
+        tracing::info!("SFTP: Creating hard link {} => {}", full_path, existing);
+
+        if let Some(transport) = &self.transport {
+            return transport.hardlink(&existing, &full_path).await;
+        }
+        let sftp = self.get_sftp()?;
+
+        let supported = sftp.hardlink(&existing, &full_path).await
+            .map_err(|e| ProviderError::ServerError(format!("Failed to create hard link: {}", e)))?;
+        if !supported {
+            return Err(ProviderError::NotSupported("hardlink@openssh.com".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn get_attributes(&mut self, path: &str) -> Result<PosixAttributes, ProviderError> {
+        let full_path = self.normalize_path(path);
+        if let Some(transport) = &self.transport {
+            return transport.get_attributes(&full_path).await;
+        }
+        let sftp = self.get_sftp()?;
+
+        let metadata = sftp.metadata(&full_path).await
+            .map_err(|e| ProviderError::NotFound(format!("File not found: {}", e)))?;
+
+        Ok(PosixAttributes {
+            mode: metadata.permissions.map(|p| p & 0o7777),
+            uid: metadata.uid,
+            gid: metadata.gid,
+            atime: metadata.atime,
+            mtime: metadata.mtime,
+        })
+    }
+
+    async fn set_attributes(&mut self, path: &str, attrs: &PosixAttributes) -> Result<(), ProviderError> {
+        let full_path = self.normalize_path(path);
+        if attrs.is_empty() {
+            return Ok(());
+        }
+        if let Some(transport) = &self.transport {
+            return transport.set_attributes(&full_path, attrs).await;
+        }
+        let sftp = self.get_sftp()?;
+
+        // SETSTAT sends uid with gid and atime with mtime; fill in the missing half
+        // from the current values rather than zeroing it
+        let mut attrs = *attrs;
+        if attrs.uid.is_some() != attrs.gid.is_some() || attrs.atime.is_some() != attrs.mtime.is_some() {
+            let current = sftp.metadata(&full_path).await
+                .map_err(|e| ProviderError::NotFound(format!("File not found: {}", e)))?;
+            if attrs.uid.is_some() || attrs.gid.is_some() {
+                attrs.uid = attrs.uid.or(current.uid);
+                attrs.gid = attrs.gid.or(current.gid);
+            }
+            if attrs.atime.is_some() || attrs.mtime.is_some() {
+                attrs.atime = attrs.atime.or(current.atime);
+                attrs.mtime = attrs.mtime.or(current.mtime);
+            }
+        }
+
+        let metadata = russh_sftp::protocol::FileAttributes {
+            permissions: attrs.mode,
+            uid: attrs.uid,
+            gid: attrs.gid,
+            atime: attrs.atime,
+            mtime: attrs.mtime,
+            ..russh_sftp::protocol::FileAttributes::empty()
+        };
+        sftp.set_metadata(&full_path, metadata).await
+            .map_err(|e| ProviderError::ServerError(format!("Failed to set attributes: {}", e)))
+    }
+
+    fn supports_xattrs(&self) -> bool {
+        // OpenSSH has no SFTP extension for them; getfattr/setfattr run over exec
+        self.exec_available == Some(true)
+    }
+
+    async fn get_xattrs(&mut self, path: &str) -> Result<Xattrs, ProviderError> {
+        let full_path = self.normalize_path(path);
+        if let Some(transport) = &self.transport {
+            return transport.get_xattrs(&full_path).await;
+        }
+        self.ensure_connected()?;
+
+        match self.exec(&ssh_exec::getfattr_command(&full_path), XATTR_MAX_OUTPUT).await {
+            Some(output) if output.success() => Ok(ssh_exec::parse_getfattr_output(&output.stdout)),
+            Some(output) => Err(ssh_exec::command_error(&output)),
+            None => Err(ProviderError::NotSupported("get_xattrs".to_string())),
+        }
+    }
+
+    async fn set_xattrs(&mut self, path: &str, xattrs: &Xattrs) -> Result<(), ProviderError> {
+        let full_path = self.normalize_path(path);
+        if let Some(transport) = &self.transport {
+            return transport.set_xattrs(&full_path, xattrs).await;
+        }
+        self.ensure_connected()?;
+
+        let Some(command) = ssh_exec::setfattr_command(&full_path, xattrs) else {
+            return Ok(());
+        };
+        match self.exec(&command, 64 * 1024).await {
+            Some(output) if output.success() => Ok(()),
+            Some(output) => Err(ssh_exec::command_error(&output)),
+            None => Err(ProviderError::NotSupported("set_xattrs".to_string())),
+        }
+    }
+
     fn supports_find(&self) -> bool {
         true
     }
 
     async fn find(&mut self, path: &str, pattern: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
-        let sftp = self.get_sftp()?;
+        self.ensure_connected()?;
         let root = self.normalize_path(path);
-        let pattern_lower = pattern.to_lowercase();
-        let mut results = Vec::new();
-        let mut dirs_to_scan = vec![root];
 
-        while let Some(dir) = dirs_to_scan.pop() {
-            let entries = match sftp.read_dir(&dir).await {
-                Ok(e) => e,
-                Err(_) => continue, // Skip inaccessible directories
-            };
+        // GNU find on the server; BSD find has no -printf and fails without output
+        let command = format!(
+            "find {} -mindepth 1 -iname {} -printf '%y\\t%s\\t%T@\\t%m\\t%p\\0' 2>/dev/null",
+            shell_quote(&root),
+            shell_quote(&format!("*{}*", escape_glob(pattern))),
+        );
+        if let Some(output) = self.exec(&command, FIND_MAX_OUTPUT).await {
+            if output.success() || !output.stdout.is_empty() {
+                return Ok(parse_find_output(&output.stdout, FIND_MAX_RESULTS));
+            }
+        }
 
-            for entry in entries {
-                let name = entry.file_name();
-                if name == "." || name == ".." {
-                    continue;
-                }
+        self.find_via_sftp(root, pattern).await
+    }
 
-                let entry_path = if dir == "/" {
-                    format!("/{}", name)
-                } else {
-                    format!("{}/{}", dir.trim_end_matches('/'), name)
-                };
+    async fn disk_usage(&mut self, path: &str) -> Result<u64, ProviderError> {
+        self.ensure_connected()?;
+        let quoted = shell_quote(&self.normalize_path(path));
 
-                let remote_entry = self.metadata_to_entry(name.clone(), entry_path.clone(), &entry.metadata());
+        // GNU du reports apparent bytes; BSD and busybox du only KiB blocks
+        for (command, unit) in [(format!("du -sb -- {}", quoted), 1), (format!("du -sk -- {}", quoted), 1024)] {
+            let Some(output) = self.exec(&command, 4096).await else {
+                break;
+            };
+            if let Some(size) = parse_du_output(&output) {
+                return Ok(size * unit);
+            }
+        }
+        Err(ProviderError::NotSupported("disk_usage".to_string()))
+    }
 
-                if remote_entry.is_dir {
-                    dirs_to_scan.push(entry_path.clone());
-                }
+    fn supports_checksum(&self) -> bool {
+        self.exec_available == Some(true)
+    }
 
-                if name.to_lowercase().contains(&pattern_lower) {
-                    results.push(remote_entry);
-                    if results.len() >= 500 {
-                        return Ok(results);
-                    }
-                }
+    /// Digest from the first checksum command the server has, so each file is read once
+    async fn checksum(&mut self, path: &str) -> Result<HashMap<String, String>, ProviderError> {
+        self.ensure_connected()?;
+        let quoted = shell_quote(&self.normalize_path(path));
+
+        for (algo, template) in CHECKSUM_COMMANDS {
+            let Some(output) = self.exec(&template.replace("{path}", &quoted), 4096).await else {
+                return Err(ProviderError::NotSupported("checksum (remote commands unavailable)".to_string()));
+            };
+            if let Some(digest) = parse_checksum_output(&output) {
+                return Ok(HashMap::from([(algo.to_string(), digest)]));
             }
         }
-
-        Ok(results)
+        Err(ProviderError::NotSupported("checksum (no sha256sum or b3sum on server)".to_string()))
     }
 
     async fn storage_info(&mut self) -> Result<super::StorageInfo, ProviderError> {
+        if self.transport.is_some() {
+            return Err(ProviderError::NotSupported("storage_info over scp/rsync".to_string()));
+        }
         let sftp = self.get_sftp()?;
         let path = self.normalize_path(".");
 
@@ -851,11 +1463,17 @@ impl StorageProvider for SftpProvider {
     }
 
     fn transfer_optimization_hints(&self) -> super::TransferOptimizationHints {
+        // scp cannot resume; rsync resumes implicitly through its delta transfer
+        let sftp = self.transport.is_none();
         super::TransferOptimizationHints {
-            supports_resume_download: true,
-            supports_resume_upload: true,
+            supports_resume_download: sftp,
+            supports_resume_upload: sftp,
             supports_compression: true,
             supports_delta_sync: true,
+            supports_server_checksum: self.exec_available == Some(true),
+            preferred_checksum_algo: Some("sha256".to_string()),
+            pipeline_depth: if sftp && !self.pipeline_refused { self.config.pipeline_depth } else { 1 },
+            pipeline_block_size: if sftp { self.config.pipeline_block_size } else { 0 },
             ..Default::default()
         }
     }
@@ -865,9 +1483,21 @@ impl StorageProvider for SftpProvider {
     }
 
     async fn read_range(&mut self, path: &str, offset: u64, len: u64) -> Result<Vec<u8>, ProviderError> {
+        let full_path = self.normalize_path(path);
+
+        // GAP-A03: Cap read_range allocation to prevent attacker-controlled OOM
+        const MAX_READ_RANGE: u64 = 100 * 1024 * 1024; // 100 MB
+        if len > MAX_READ_RANGE {
+            return Err(ProviderError::Other(
+                format!("Read range size {} exceeds maximum {} bytes", len, MAX_READ_RANGE)
+            ));
+        }
+
+        if let Some(transport) = &self.transport {
+            return transport.read_range(&full_path, offset, len).await;
+        }
         let sftp = self.sftp.as_ref()
             .ok_or_else(|| ProviderError::NotConnected)?;
-        let full_path = self.normalize_path(path);
 
         let mut file = sftp.open(&full_path).await
             .map_err(|e| ProviderError::ServerError(format!("Failed to open file for range read: {}", e)))?;
@@ -877,14 +1507,6 @@ impl StorageProvider for SftpProvider {
         file.seek(std::io::SeekFrom::Start(offset)).await
             .map_err(|e| ProviderError::ServerError(format!("Failed to seek: {}", e)))?;
 
-        // GAP-A03: Cap read_range allocation to prevent attacker-controlled OOM
-        const MAX_READ_RANGE: u64 = 100 * 1024 * 1024; // 100 MB
-        if len > MAX_READ_RANGE {
-            return Err(ProviderError::Other(
-                format!("Read range size {} exceeds maximum {} bytes", len, MAX_READ_RANGE)
-            ));
-        }
-
         // Read exact len bytes
         let mut buf = vec![0u8; len as usize];
         let mut total_read = 0usize;
@@ -902,6 +1524,7 @@ impl StorageProvider for SftpProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::providers::SftpPreserve;
 
     #[test]
     fn test_sftp_provider_creation() {
@@ -912,8 +1535,18 @@ mod tests {
             password: Some(secrecy::SecretString::from("testpass".to_string())),
             private_key_path: None,
             key_passphrase: None,
+            identity_files: Vec::new(),
+            certificate_path: None,
+            use_agent: true,
+            keyboard_interactive: true,
+            use_ssh_config: false,
             initial_path: None,
             timeout_secs: 30,
+            proxy: None,
+            transfer_mode: SshTransferMode::Auto,
+            pipeline_depth: 64,
+            pipeline_block_size: 32768,
+            preserve: SftpPreserve::default(),
         };
 
         let provider = SftpProvider::new(config);
@@ -921,6 +1554,22 @@ mod tests {
         assert!(!provider.is_connected());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_local_attributes_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("release.tar");
+        std::fs::write(&path, b"contents").unwrap();
+        let attrs = PosixAttributes { mode: Some(0o640), atime: Some(1_600_000_000), mtime: Some(1_700_000_000), ..Default::default() };
+        set_local_attributes(&path, &attrs).unwrap();
+
+        let read = local_attributes(&path).unwrap();
+        assert_eq!((read.mode, read.atime, read.mtime), (attrs.mode, attrs.atime, attrs.mtime));
+        assert_eq!(linked_inode(&path), None);
+        std::fs::hard_link(&path, dir.path().join("again.tar")).unwrap();
+        assert!(linked_inode(&path).is_some());
+    }
+
     #[test]
     fn test_normalize_path() {
         let config = SftpConfig {
@@ -930,8 +1579,18 @@ mod tests {
             password: None,
             private_key_path: None,
             key_passphrase: None,
+            identity_files: Vec::new(),
+            certificate_path: None,
+            use_agent: true,
+            keyboard_interactive: true,
+            use_ssh_config: false,
             initial_path: None,
             timeout_secs: 30,
+            proxy: None,
+            transfer_mode: SshTransferMode::Auto,
+            pipeline_depth: 64,
+            pipeline_block_size: 32768,
+            preserve: SftpPreserve::default(),
         };
 
         let mut provider = SftpProvider::new(config);
@@ -946,6 +1605,35 @@ mod tests {
         assert_eq!(provider.normalize_path("~/documents"), "/home/user/documents");
     }
 
+    #[test]
+    fn test_parse_find_output() {
+        let output = b"f\t1234\t1700000000.5\t644\t/srv/a b.txt\0d\t4096\t1700000000.0\t755\t/srv/dir\0f\t9\t17";
+        let entries = parse_find_output(output, FIND_MAX_RESULTS);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "a b.txt");
+        assert_eq!(entries[0].size, 1234);
+        assert_eq!(entries[0].permissions.as_deref(), Some("-rw-r--r--"));
+        assert_eq!(entries[0].modified.as_deref(), Some("2023-11-14 22:13:20"));
+        assert!(entries[1].is_dir);
+        assert_eq!(entries[1].size, 0);
+        assert_eq!(parse_find_output(output, 1).len(), 1);
+        assert_eq!(escape_glob("a*b[1]"), "a\\*b\\[1\\]");
+    }
+
+    #[test]
+    fn test_parse_checksum_and_du_output() {
+        let digest = "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855";
+        let ok = |stdout: &str| ExecOutput { exit_status: Some(0), stdout: stdout.as_bytes().to_vec(), stderr: Vec::new() };
+        assert_eq!(parse_checksum_output(&ok(&format!("\\{}  /a\\nb\n", digest))), Some(digest.to_lowercase()));
+        assert_eq!(parse_checksum_output(&ok("sha256sum: /a: No such file\n")), None);
+        let missing = ExecOutput { exit_status: Some(127), ..Default::default() };
+        assert_eq!(parse_checksum_output(&missing), None);
+
+        let partial = ExecOutput { exit_status: Some(1), stdout: b"52428800\t/srv\n".to_vec(), stderr: Vec::new() };
+        assert_eq!(parse_du_output(&partial), Some(52428800));
+        assert_eq!(parse_du_output(&ExecOutput::default()), None);
+    }
+
     #[test]
     fn test_format_permissions() {
         assert_eq!(format_permissions(0o755, true), "drwxr-xr-x");
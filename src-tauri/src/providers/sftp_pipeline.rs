@@ -0,0 +1,477 @@
+//! Pipelined SFTP transfers
+//!
+//! russh-sftp's `File` sends one READ or WRITE and waits for the reply before
+//! sending the next, so a transfer moves at most one block per round trip.
+//! Like OpenSSH's `sftp -R <requests> -B <buffer>`, this keeps up to `depth`
+//! requests of `block_size` bytes in flight on a raw SFTP session and writes
+//! the replies out in offset order.
+
+use super::{read_full_async, ProviderError};
+use futures_util::stream::{FuturesOrdered, FuturesUnordered, StreamExt};
+use russh_sftp::client::error::Error as SftpError;
+use russh_sftp::client::RawSftpSession;
+use russh_sftp::protocol::{FileAttributes, OpenFlags, StatusCode};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+/// Same defaults as OpenSSH's `sftp` (`-B 32768 -R 64`)
+pub const DEFAULT_BLOCK_SIZE: u32 = 32 * 1024;
+pub const DEFAULT_DEPTH: u32 = 64;
+
+pub const MIN_BLOCK_SIZE: u32 = 1024;
+/// OpenSSH's sftp-server rejects packets over 256 KiB
+pub const MAX_BLOCK_SIZE: u32 = 256 * 1024;
+pub const MAX_DEPTH: u32 = 1024;
+
+/// Raw SFTP session used only for bulk reads and writes
+pub struct Pipeline {
+    raw: RawSftpSession,
+    depth: usize,
+    read_size: u32,
+    write_size: u32,
+}
+
+fn is_eof(error: &SftpError) -> bool {
+    matches!(error, SftpError::Status(status) if status.status_code == StatusCode::Eof)
+}
+
+fn sftp_error(context: &str, error: SftpError) -> ProviderError {
+    match &error {
+        SftpError::Status(status) if status.status_code == StatusCode::NoSuchFile => {
+            ProviderError::NotFound(format!("{}: {}", context, error))
+        }
+        SftpError::Status(status) if status.status_code == StatusCode::PermissionDenied => {
+            ProviderError::PermissionDenied(format!("{}: {}", context, error))
+        }
+        _ => ProviderError::TransferFailed(format!("{}: {}", context, error)),
+    }
+}
+
+/// Sleep until `transferred` bytes fit within `limit_bps` (0 = unlimited)
+async fn throttle(start: Instant, transferred: u64, limit_bps: u64) {
+    if limit_bps > 0 {
+        let expected = Duration::from_secs_f64(transferred as f64 / limit_bps as f64);
+        let elapsed = start.elapsed();
+        if expected > elapsed {
+            tokio::time::sleep(expected - elapsed).await;
+        }
+    }
+}
+
+/// Fill up to `size` bytes from `input`; shorter only at end of input
+async fn read_block<R: AsyncRead + Unpin>(input: &mut R, size: usize) -> Result<Vec<u8>, ProviderError> {
+    let mut block = vec![0u8; size];
+    let filled = read_full_async(input, &mut block).await?;
+    block.truncate(filled);
+    Ok(block)
+}
+
+impl Pipeline {
+    /// Wrap an initialized session; block sizes are lowered to the server's
+    /// `limits@openssh.com` when it advertises them
+    pub async fn new(raw: RawSftpSession, depth: u32, block_size: u32) -> Self {
+        let block_size = block_size.clamp(MIN_BLOCK_SIZE, MAX_BLOCK_SIZE);
+        let (mut read_size, mut write_size) = (block_size, block_size);
+        if let Ok(limits) = raw.limits().await {
+            if limits.max_read_len > 0 {
+                read_size = read_size.min(limits.max_read_len.min(u64::from(u32::MAX)) as u32);
+            }
+            if limits.max_write_len > 0 {
+                write_size = write_size.min(limits.max_write_len.min(u64::from(u32::MAX)) as u32);
+            }
+        }
+        Self {
+            raw,
+            depth: depth.clamp(1, MAX_DEPTH) as usize,
+            read_size,
+            write_size,
+        }
+    }
+
+    /// Download `path` into `out`; `total` is the expected size (reading continues past it until EOF)
+    pub async fn download<W: AsyncWrite + Unpin + Send>(
+        &self,
+        path: &str,
+        out: &mut W,
+        total: u64,
+        limit_bps: u64,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<u64, ProviderError> {
+        let handle = self.raw.open(path, OpenFlags::READ, FileAttributes::empty()).await
+            .map_err(|e| sftp_error("Failed to open remote file", e))?
+            .handle;
+        let result = self.read_blocks(&handle, out, total, limit_bps, on_progress).await;
+        if let Err(e) = self.raw.close(handle).await {
+            tracing::warn!("SFTP: Failed to close {}: {}", path, e);
+        }
+        result
+    }
+
+    async fn read_blocks<W: AsyncWrite + Unpin + Send>(
+        &self,
+        handle: &str,
+        out: &mut W,
+        total: u64,
+        limit_bps: u64,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<u64, ProviderError> {
+        let start = Instant::now();
+        let mut pending = FuturesOrdered::new();
+        let mut next_offset = 0u64;
+        let mut written = 0u64;
+        let mut eof = false;
+
+        loop {
+            // Past the expected size, probe one block at a time for growth or EOF
+            while !eof && pending.len() < self.depth && (next_offset < total || pending.is_empty()) {
+                let (offset, len, handle) = (next_offset, self.read_size, handle.to_string());
+                pending.push_back(async move { (offset, len, self.raw.read(handle, offset, len).await) });
+                next_offset += u64::from(len);
+            }
+            let Some((offset, len, reply)) = pending.next().await else {
+                break;
+            };
+            if eof {
+                // Replies to requests issued beyond the end of the file
+                continue;
+            }
+            let mut data = match reply {
+                Ok(data) => data.data,
+                Err(e) if is_eof(&e) => {
+                    eof = true;
+                    continue;
+                }
+                Err(e) => return Err(sftp_error("Read error", e)),
+            };
+            // Servers may return less than asked; fetch the rest before the next block
+            while (data.len() as u64) < u64::from(len) {
+                let missing = len - data.len() as u32;
+                match self.raw.read(handle, offset + data.len() as u64, missing).await {
+                    Ok(more) if !more.data.is_empty() => data.extend_from_slice(&more.data),
+                    Ok(_) => break,
+                    Err(e) if is_eof(&e) => {
+                        eof = true;
+                        break;
+                    }
+                    Err(e) => return Err(sftp_error("Read error", e)),
+                }
+            }
+
+            out.write_all(&data).await
+                .map_err(|e| ProviderError::TransferFailed(format!("Write error: {}", e)))?;
+            written += data.len() as u64;
+            if let Some(ref progress) = on_progress {
+                progress(written, total.max(written));
+            }
+            throttle(start, written, limit_bps).await;
+        }
+
+        out.flush().await
+            .map_err(|e| ProviderError::TransferFailed(format!("Flush error: {}", e)))?;
+        Ok(written)
+    }
+
+    /// Upload everything from `input` to `path`, replacing it
+    pub async fn upload<R: AsyncRead + Unpin + Send>(
+        &self,
+        path: &str,
+        input: &mut R,
+        total: u64,
+        limit_bps: u64,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<u64, ProviderError> {
+        let flags = OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE;
+        let handle = self.raw.open(path, flags, FileAttributes::empty()).await
+            .map_err(|e| sftp_error("Failed to create remote file", e))?
+            .handle;
+        let result = self.write_blocks(&handle, input, total, limit_bps, on_progress).await;
+        // The server may only report a failed write (e.g. disk full) on close
+        let closed = self.raw.close(handle).await;
+        let written = result?;
+        closed.map_err(|e| sftp_error("Close error", e))?;
+        Ok(written)
+    }
+
+    async fn write_blocks<R: AsyncRead + Unpin + Send>(
+        &self,
+        handle: &str,
+        input: &mut R,
+        total: u64,
+        limit_bps: u64,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<u64, ProviderError> {
+        let start = Instant::now();
+        let mut pending = FuturesUnordered::new();
+        let mut next_offset = 0u64;
+        let mut acked = 0u64;
+        let mut done = false;
+
+        loop {
+            while !done && pending.len() < self.depth {
+                let block = read_block(input, self.write_size as usize).await?;
+                if block.is_empty() {
+                    done = true;
+                    break;
+                }
+                let (offset, len, handle) = (next_offset, block.len() as u64, handle.to_string());
+                pending.push(async move { self.raw.write(handle, offset, block).await.map(|_| len) });
+                next_offset += len;
+            }
+            match pending.next().await {
+                Some(Ok(len)) => {
+                    acked += len;
+                    if let Some(ref progress) = on_progress {
+                        progress(acked, total.max(acked));
+                    }
+                    throttle(start, acked, limit_bps).await;
+                }
+                Some(Err(e)) => return Err(sftp_error("Write error", e)),
+                None => break,
+            }
+        }
+        Ok(acked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, DuplexStream};
+
+    /// Minimal SFTP v3 server over an in-memory stream: one file per path,
+    /// reads capped at `MAX_READ` bytes, and replies to each batch of requests
+    /// sent back in reverse order
+    const MAX_READ: usize = 20_000;
+
+    struct Packet {
+        kind: u8,
+        body: Vec<u8>,
+        pos: usize,
+    }
+
+    impl Packet {
+        fn u32(&mut self) -> u32 {
+            let value = u32::from_be_bytes(self.body[self.pos..self.pos + 4].try_into().unwrap());
+            self.pos += 4;
+            value
+        }
+
+        fn u64(&mut self) -> u64 {
+            (u64::from(self.u32()) << 32) | u64::from(self.u32())
+        }
+
+        fn bytes(&mut self) -> Vec<u8> {
+            let len = self.u32() as usize;
+            self.pos += len;
+            self.body[self.pos - len..self.pos].to_vec()
+        }
+    }
+
+    fn reply(kind: u8, id: u32, payload: &[u8]) -> Vec<u8> {
+        let mut packet = ((payload.len() + 5) as u32).to_be_bytes().to_vec();
+        packet.push(kind);
+        packet.extend_from_slice(&id.to_be_bytes());
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    fn string(data: &[u8]) -> Vec<u8> {
+        let mut out = (data.len() as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(data);
+        out
+    }
+
+    fn status(id: u32, code: u32) -> Vec<u8> {
+        let mut payload = code.to_be_bytes().to_vec();
+        payload.extend(string(b""));
+        payload.extend(string(b""));
+        reply(101, id, &payload)
+    }
+
+    async fn serve(mut stream: DuplexStream, files: Arc<Mutex<HashMap<String, Vec<u8>>>>) {
+        let mut handles: Vec<String> = Vec::new();
+        let mut queued: Vec<Vec<u8>> = Vec::new();
+        loop {
+            let mut len = [0u8; 4];
+            let readable = if queued.is_empty() {
+                stream.read_exact(&mut len).await.is_ok()
+            } else {
+                // Flush queued replies (newest first) whenever the client pauses
+                match tokio::time::timeout(Duration::from_millis(5), stream.read_exact(&mut len)).await {
+                    Ok(result) => result.is_ok(),
+                    Err(_) => {
+                        for packet in queued.drain(..).rev() {
+                            stream.write_all(&packet).await.unwrap();
+                        }
+                        continue;
+                    }
+                }
+            };
+            if !readable {
+                return;
+            }
+            let mut body = vec![0u8; u32::from_be_bytes(len) as usize];
+            stream.read_exact(&mut body).await.unwrap();
+            let mut packet = Packet { kind: body[0], body, pos: 1 };
+            if packet.kind == 1 {
+                stream.write_all(&[0, 0, 0, 5, 2, 0, 0, 0, 3]).await.unwrap();
+                continue;
+            }
+            let id = packet.u32();
+            let response = match packet.kind {
+                // OPEN
+                3 => {
+                    let path = String::from_utf8(packet.bytes()).unwrap();
+                    let flags = packet.u32();
+                    let mut files = files.lock().unwrap();
+                    if flags & 0x10 != 0 {
+                        files.insert(path.clone(), Vec::new());
+                    }
+                    if files.contains_key(&path) {
+                        handles.push(path);
+                        reply(102, id, &string((handles.len() - 1).to_string().as_bytes()))
+                    } else {
+                        status(id, 2)
+                    }
+                }
+                // READ
+                5 => {
+                    let path = &handles[String::from_utf8(packet.bytes()).unwrap().parse::<usize>().unwrap()];
+                    let (offset, len) = (packet.u64() as usize, packet.u32() as usize);
+                    let files = files.lock().unwrap();
+                    let data = &files[path];
+                    if offset >= data.len() {
+                        status(id, 1)
+                    } else {
+                        let end = data.len().min(offset + len.min(MAX_READ));
+                        reply(103, id, &string(&data[offset..end]))
+                    }
+                }
+                // WRITE
+                6 => {
+                    let path = handles[String::from_utf8(packet.bytes()).unwrap().parse::<usize>().unwrap()].clone();
+                    let offset = packet.u64() as usize;
+                    let data = packet.bytes();
+                    let mut files = files.lock().unwrap();
+                    let file = files.get_mut(&path).unwrap();
+                    if file.len() < offset + data.len() {
+                        file.resize(offset + data.len(), 0);
+                    }
+                    file[offset..offset + data.len()].copy_from_slice(&data);
+                    status(id, 0)
+                }
+                // CLOSE
+                4 => status(id, 0),
+                // EXTENDED (limits@openssh.com) and anything else
+                _ => status(id, 8),
+            };
+            queued.push(response);
+        }
+    }
+
+    async fn session(files: &Arc<Mutex<HashMap<String, Vec<u8>>>>, depth: u32) -> Pipeline {
+        let (client, server) = tokio::io::duplex(1024 * 1024);
+        tokio::spawn(serve(server, files.clone()));
+        let raw = RawSftpSession::new(client);
+        raw.init().await.unwrap();
+        Pipeline::new(raw, depth, DEFAULT_BLOCK_SIZE).await
+    }
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn test_download_reorders_and_completes_short_reads() {
+        let content = sample(300_001);
+        let files = Arc::new(Mutex::new(HashMap::from([("/data.bin".to_string(), content.clone())])));
+        let pipeline = session(&files, 8).await;
+
+        let progress = Arc::new(Mutex::new(0u64));
+        let seen = progress.clone();
+        let mut out = Vec::new();
+        let written = pipeline.download("/data.bin", &mut out, content.len() as u64, 0,
+            Some(Box::new(move |done, _| *seen.lock().unwrap() = done))).await.unwrap();
+        assert_eq!(written, content.len() as u64);
+        assert_eq!(out, content);
+        assert_eq!(*progress.lock().unwrap(), content.len() as u64);
+
+        // A stale size still reads to EOF
+        let mut out = Vec::new();
+        pipeline.download("/data.bin", &mut out, 1000, 0, None).await.unwrap();
+        assert_eq!(out, content);
+    }
+
+    #[tokio::test]
+    async fn test_upload_writes_out_of_order_acks() {
+        let content = sample(200_000);
+        let files = Arc::new(Mutex::new(HashMap::new()));
+        let pipeline = session(&files, 16).await;
+
+        let written = pipeline.upload("/up.bin", &mut &content[..], content.len() as u64, 0, None).await.unwrap();
+        assert_eq!(written, content.len() as u64);
+        assert_eq!(files.lock().unwrap()["/up.bin"], content);
+    }
+
+    #[tokio::test]
+    async fn test_missing_file_is_not_found() {
+        let files = Arc::new(Mutex::new(HashMap::new()));
+        let pipeline = session(&files, 4).await;
+        let result = pipeline.download("/missing", &mut Vec::new(), 0, 0, None).await;
+        assert!(matches!(result, Err(ProviderError::NotFound(_))));
+    }
+
+    /// Sequential vs pipelined download against a real server. Run with
+    /// `scripts/sftp-netem-bench.sh`, which adds latency to loopback with
+    /// `tc netem`, or directly:
+    /// `SFTP_BENCH_HOST=127.0.0.1 SFTP_BENCH_USER=me SFTP_BENCH_KEY=~/.ssh/id_ed25519
+    ///  SFTP_BENCH_FILE=/tmp/bench.bin cargo test --release sftp_pipeline_bench -- --ignored --nocapture`
+    #[tokio::test]
+    #[ignore]
+    async fn sftp_pipeline_bench() {
+        use crate::providers::sftp::SftpProvider;
+        use crate::providers::{ProviderConfig, ProviderType, SftpConfig, StorageProvider};
+
+        let env = |name: &str| std::env::var(name).ok();
+        let Some(host) = env("SFTP_BENCH_HOST") else {
+            eprintln!("SFTP_BENCH_HOST not set, skipping");
+            return;
+        };
+        let remote = env("SFTP_BENCH_FILE").unwrap_or_else(|| "/tmp/sftp-bench.bin".to_string());
+        let local = std::env::temp_dir().join("sftp-bench.download");
+
+        for depth in [1, DEFAULT_DEPTH, 256] {
+            let mut extra = HashMap::new();
+            if let Some(key) = env("SFTP_BENCH_KEY") {
+                extra.insert("private_key_path".to_string(), key);
+            }
+            extra.insert("ssh_config".to_string(), "false".to_string());
+            extra.insert("transfer_mode".to_string(), "sftp".to_string());
+            extra.insert("pipeline_depth".to_string(), depth.to_string());
+            let config = ProviderConfig {
+                name: "bench".to_string(),
+                provider_type: ProviderType::Sftp,
+                host: host.clone(),
+                port: env("SFTP_BENCH_PORT").and_then(|p| p.parse().ok()),
+                username: env("SFTP_BENCH_USER"),
+                password: env("SFTP_BENCH_PASSWORD"),
+                initial_path: None,
+                extra,
+            };
+            let mut provider = SftpProvider::new(SftpConfig::from_provider_config(&config).unwrap());
+            provider.connect().await.unwrap();
+            let started = Instant::now();
+            provider.download(&remote, local.to_str().unwrap(), None).await.unwrap();
+            let elapsed = started.elapsed();
+            let size = std::fs::metadata(&local).unwrap().len();
+            eprintln!(
+                "depth {:>4}: {} bytes in {:.2}s ({:.1} MB/s)",
+                depth, size, elapsed.as_secs_f64(), size as f64 / 1_048_576.0 / elapsed.as_secs_f64(),
+            );
+            provider.disconnect().await.unwrap();
+        }
+        let _ = std::fs::remove_file(local);
+    }
+}
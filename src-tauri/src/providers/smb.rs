@@ -0,0 +1,2120 @@
+//! SMB2/3 Provider
+//!
+//! Implements StorageProvider for Windows shares, Samba and NAS devices over
+//! direct TCP (port 445), in pure Rust:
+//!
+//! - Dialects 2.0.2, 2.1, 3.0, 3.0.2 and 3.1.1 (with SHA-512 pre-authentication integrity)
+//! - NTLMv2 authentication wrapped in SPNEGO (see `ntlm`); an empty user name
+//!   opens an anonymous session
+//! - Message signing (HMAC-SHA256 for 2.x, AES-CMAC for 3.x) on every
+//!   authenticated session, encryption (AES-128-GCM / AES-128-CCM) when the
+//!   server, the share or the profile asks for it
+//! - Share enumeration through `NetrShareEnum` (DCE/RPC on the `srvsvc` pipe)
+//!   when the profile has no share: `/` lists the shares, `/<share>/...` their contents
+//! - Server-side copy with `FSCTL_SRV_COPYCHUNK_WRITE`
+//!
+//! ## Limitations (documented)
+//! - SMB-001: NTLM only (no Kerberos)
+//! - SMB-002: DFS referrals are not followed
+//! - SMB-003: One request in flight per connection (no compounding or multi-channel)
+
+use aes::Aes128;
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{AeadInPlace, KeyInit};
+use aes_gcm::Aes128Gcm;
+use async_trait::async_trait;
+use ccm::consts::{U11, U16};
+use ccm::Ccm;
+use cmac::Cmac;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashMap;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, info, warn};
+
+use super::ntlm;
+use super::types::SmbConfig;
+use super::{ProviderError, ProviderType, RemoteEntry, StorageInfo, StorageProvider};
+
+type Aes128Ccm = Ccm<Aes128, U16, U11>;
+
+const SMB2_MAGIC: &[u8; 4] = b"\xfeSMB";
+const TRANSFORM_MAGIC: &[u8; 4] = b"\xfdSMB";
+const HEADER_LEN: usize = 64;
+const TRANSFORM_HEADER_LEN: usize = 52;
+
+const DIALECT_202: u16 = 0x0202;
+const DIALECT_210: u16 = 0x0210;
+const DIALECT_300: u16 = 0x0300;
+const DIALECT_302: u16 = 0x0302;
+const DIALECT_311: u16 = 0x0311;
+const DIALECTS: [u16; 5] = [DIALECT_202, DIALECT_210, DIALECT_300, DIALECT_302, DIALECT_311];
+
+// Commands
+const NEGOTIATE: u16 = 0x00;
+const SESSION_SETUP: u16 = 0x01;
+const LOGOFF: u16 = 0x02;
+const TREE_CONNECT: u16 = 0x03;
+const TREE_DISCONNECT: u16 = 0x04;
+const CREATE: u16 = 0x05;
+const CLOSE: u16 = 0x06;
+const READ: u16 = 0x08;
+const WRITE: u16 = 0x09;
+const IOCTL: u16 = 0x0b;
+const ECHO: u16 = 0x0d;
+const QUERY_DIRECTORY: u16 = 0x0e;
+const QUERY_INFO: u16 = 0x10;
+const SET_INFO: u16 = 0x11;
+
+// NTSTATUS values
+const STATUS_SUCCESS: u32 = 0x0000_0000;
+const STATUS_PENDING: u32 = 0x0000_0103;
+const STATUS_BUFFER_OVERFLOW: u32 = 0x8000_0005;
+const STATUS_NO_MORE_FILES: u32 = 0x8000_0006;
+const STATUS_NO_SUCH_FILE: u32 = 0xC000_000F;
+const STATUS_END_OF_FILE: u32 = 0xC000_0011;
+const STATUS_MORE_PROCESSING_REQUIRED: u32 = 0xC000_0016;
+const STATUS_ACCESS_DENIED: u32 = 0xC000_0022;
+const STATUS_OBJECT_NAME_INVALID: u32 = 0xC000_0033;
+const STATUS_OBJECT_NAME_NOT_FOUND: u32 = 0xC000_0034;
+const STATUS_OBJECT_NAME_COLLISION: u32 = 0xC000_0035;
+const STATUS_OBJECT_PATH_NOT_FOUND: u32 = 0xC000_003A;
+const STATUS_SHARING_VIOLATION: u32 = 0xC000_0043;
+const STATUS_DELETE_PENDING: u32 = 0xC000_0056;
+const STATUS_LOGON_FAILURE: u32 = 0xC000_006D;
+const STATUS_ACCOUNT_RESTRICTION: u32 = 0xC000_006E;
+const STATUS_PASSWORD_EXPIRED: u32 = 0xC000_0071;
+const STATUS_ACCOUNT_DISABLED: u32 = 0xC000_0072;
+const STATUS_DISK_FULL: u32 = 0xC000_007F;
+const STATUS_FILE_IS_A_DIRECTORY: u32 = 0xC000_00BA;
+const STATUS_BAD_NETWORK_NAME: u32 = 0xC000_00CC;
+const STATUS_DIRECTORY_NOT_EMPTY: u32 = 0xC000_0101;
+const STATUS_NOT_A_DIRECTORY: u32 = 0xC000_0103;
+const STATUS_USER_SESSION_DELETED: u32 = 0xC000_0203;
+const STATUS_ACCOUNT_LOCKED_OUT: u32 = 0xC000_0234;
+const STATUS_NETWORK_SESSION_EXPIRED: u32 = 0xC000_035C;
+
+// Header flags
+const FLAGS_ASYNC_COMMAND: u32 = 0x0000_0002;
+const FLAGS_SIGNED: u32 = 0x0000_0008;
+
+// Negotiation
+const SIGNING_ENABLED: u16 = 0x0001;
+const SIGNING_REQUIRED: u16 = 0x0002;
+const CAP_LARGE_MTU: u32 = 0x0000_0004;
+const CAP_ENCRYPTION: u32 = 0x0000_0040;
+const CTX_PREAUTH_INTEGRITY: u16 = 0x0001;
+const CTX_ENCRYPTION: u16 = 0x0002;
+const HASH_SHA512: u16 = 0x0001;
+const CIPHER_AES128_CCM: u16 = 0x0001;
+const CIPHER_AES128_GCM: u16 = 0x0002;
+
+// Session and share flags
+const SESSION_FLAG_IS_GUEST: u16 = 0x0001;
+const SESSION_FLAG_IS_NULL: u16 = 0x0002;
+const SESSION_FLAG_ENCRYPT_DATA: u16 = 0x0004;
+const SHARE_TYPE_DISK: u8 = 0x01;
+const SHAREFLAG_ENCRYPT_DATA: u32 = 0x0000_8000;
+
+// Access masks
+const FILE_READ_DATA: u32 = 0x0000_0001;
+const FILE_LIST_DIRECTORY: u32 = 0x0000_0001;
+const FILE_WRITE_DATA: u32 = 0x0000_0002;
+const FILE_READ_ATTRIBUTES: u32 = 0x0000_0080;
+const DELETE: u32 = 0x0001_0000;
+const SYNCHRONIZE: u32 = 0x0010_0000;
+
+// CREATE parameters
+const IMPERSONATION: u32 = 0x0000_0002;
+const SHARE_ALL: u32 = 0x0000_0007;
+const FILE_OPEN: u32 = 0x0000_0001;
+const FILE_CREATE: u32 = 0x0000_0002;
+const FILE_OPEN_IF: u32 = 0x0000_0003;
+const FILE_OVERWRITE_IF: u32 = 0x0000_0005;
+const FILE_DIRECTORY_FILE: u32 = 0x0000_0001;
+const FILE_NON_DIRECTORY_FILE: u32 = 0x0000_0040;
+const FILE_ATTRIBUTE_READONLY: u32 = 0x0000_0001;
+const FILE_ATTRIBUTE_HIDDEN: u32 = 0x0000_0002;
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x0000_0010;
+const FILE_ATTRIBUTE_NORMAL: u32 = 0x0000_0080;
+
+// Information classes
+const INFO_FILE: u8 = 0x01;
+const INFO_FILESYSTEM: u8 = 0x02;
+const FILE_DIRECTORY_INFORMATION: u8 = 0x01;
+const FILE_RENAME_INFORMATION: u8 = 0x0a;
+const FILE_DISPOSITION_INFORMATION: u8 = 0x0d;
+const FILE_FS_FULL_SIZE_INFORMATION: u8 = 0x07;
+const RESTART_SCANS: u8 = 0x01;
+
+// IOCTL
+const IOCTL_IS_FSCTL: u32 = 0x0000_0001;
+const FSCTL_SRV_REQUEST_RESUME_KEY: u32 = 0x0014_0078;
+const FSCTL_SRV_COPYCHUNK_WRITE: u32 = 0x0014_80F2;
+
+/// Credits asked for with every request
+const CREDIT_REQUEST: u16 = 64;
+
+/// Upper bound for a single READ/WRITE (servers may advertise 8 MB)
+const MAX_IO_SIZE: u32 = 1024 * 1024;
+
+/// Buffer size for QUERY_DIRECTORY responses
+const DIRECTORY_BUFFER: u32 = 64 * 1024;
+
+/// Server-side copy: bytes per chunk and chunks per FSCTL (Windows limits)
+const COPYCHUNK_SIZE: u32 = 1024 * 1024;
+const COPYCHUNK_MAX_CHUNKS: usize = 16;
+
+/// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01
+const FILETIME_UNIX_OFFSET: i64 = 11_644_473_600;
+
+// ============ Encoding helpers ============
+
+trait PutLe {
+    fn put_u16(&mut self, v: u16);
+    fn put_u32(&mut self, v: u32);
+    fn put_u64(&mut self, v: u64);
+}
+
+impl PutLe for Vec<u8> {
+    fn put_u16(&mut self, v: u16) { self.extend_from_slice(&v.to_le_bytes()); }
+    fn put_u32(&mut self, v: u32) { self.extend_from_slice(&v.to_le_bytes()); }
+    fn put_u64(&mut self, v: u64) { self.extend_from_slice(&v.to_le_bytes()); }
+}
+
+fn truncated(what: &str) -> ProviderError {
+    ProviderError::ParseError(format!("Truncated SMB {}", what))
+}
+
+fn le16(buf: &[u8], pos: usize) -> Result<u16, ProviderError> {
+    buf.get(pos..pos + 2).map(|b| u16::from_le_bytes([b[0], b[1]])).ok_or_else(|| truncated("message"))
+}
+
+fn le32(buf: &[u8], pos: usize) -> Result<u32, ProviderError> {
+    buf.get(pos..pos + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap())).ok_or_else(|| truncated("message"))
+}
+
+fn le64(buf: &[u8], pos: usize) -> Result<u64, ProviderError> {
+    buf.get(pos..pos + 8).map(|b| u64::from_le_bytes(b.try_into().unwrap())).ok_or_else(|| truncated("message"))
+}
+
+fn align8(n: usize) -> usize {
+    (n + 7) & !7
+}
+
+fn utf16(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect()
+}
+
+fn from_utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// FILETIME to the "YYYY-MM-DD HH:MM:SS" format used by the other providers
+fn format_filetime(filetime: u64) -> Option<String> {
+    if filetime == 0 {
+        return None;
+    }
+    let secs = (filetime / 10_000_000) as i64 - FILETIME_UNIX_OFFSET;
+    chrono::DateTime::from_timestamp(secs, 0).map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+// ============ Crypto ============
+
+/// SP800-108 counter-mode KDF with HMAC-SHA256, as used by SMB 3.x
+fn kdf(key: &[u8; 16], label: &[u8], context: &[u8]) -> [u8; 16] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(&1u32.to_be_bytes());
+    mac.update(label);
+    mac.update(&[0]);
+    mac.update(context);
+    mac.update(&128u32.to_be_bytes());
+    let out = mac.finalize().into_bytes();
+    out[..16].try_into().unwrap()
+}
+
+fn preauth_update(hash: &[u8; 64], msg: &[u8]) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    hasher.update(hash);
+    hasher.update(msg);
+    let mut out = [0u8; 64];
+    out.copy_from_slice(&hasher.finalize());
+    out
+}
+
+/// Message signature: AES-CMAC for SMB 3.x, truncated HMAC-SHA256 for 2.x
+fn sign_message(dialect: u16, key: &[u8; 16], msg: &[u8]) -> [u8; 16] {
+    if dialect >= DIALECT_300 {
+        let mut mac = <Cmac<Aes128> as Mac>::new_from_slice(key).expect("AES-128 key is 16 bytes");
+        mac.update(msg);
+        mac.finalize().into_bytes().into()
+    } else {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(msg);
+        mac.finalize().into_bytes()[..16].try_into().unwrap()
+    }
+}
+
+fn nonce_len(cipher: u16) -> usize {
+    if cipher == CIPHER_AES128_GCM { 12 } else { 11 }
+}
+
+fn aead_seal(cipher: u16, key: &[u8; 16], nonce: &[u8], aad: &[u8], data: &mut [u8]) -> Result<[u8; 16], ProviderError> {
+    let key = GenericArray::from_slice(key);
+    let tag = match cipher {
+        CIPHER_AES128_GCM => Aes128Gcm::new(key).encrypt_in_place_detached(GenericArray::from_slice(nonce), aad, data),
+        _ => Aes128Ccm::new(key).encrypt_in_place_detached(GenericArray::from_slice(nonce), aad, data),
+    }.map_err(|_| ProviderError::Other("SMB encryption failed".to_string()))?;
+    Ok(tag.into())
+}
+
+fn aead_open(cipher: u16, key: &[u8; 16], nonce: &[u8], aad: &[u8], data: &mut [u8], tag: &[u8]) -> Result<(), ProviderError> {
+    let key = GenericArray::from_slice(key);
+    let tag = GenericArray::from_slice(tag);
+    match cipher {
+        CIPHER_AES128_GCM => Aes128Gcm::new(key).decrypt_in_place_detached(GenericArray::from_slice(nonce), aad, data, tag),
+        _ => Aes128Ccm::new(key).decrypt_in_place_detached(GenericArray::from_slice(nonce), aad, data, tag),
+    }.map_err(|_| ProviderError::ConnectionFailed("SMB message failed decryption (possible tampering)".to_string()))
+}
+
+/// Wrap a message in an SMB2 TRANSFORM_HEADER and encrypt it
+fn seal(cipher: u16, key: &[u8; 16], session_id: u64, msg: &[u8]) -> Result<Vec<u8>, ProviderError> {
+    let mut header = [0u8; TRANSFORM_HEADER_LEN];
+    header[..4].copy_from_slice(TRANSFORM_MAGIC);
+    rand::thread_rng().fill_bytes(&mut header[20..20 + nonce_len(cipher)]);
+    header[36..40].copy_from_slice(&(msg.len() as u32).to_le_bytes());
+    header[42..44].copy_from_slice(&1u16.to_le_bytes()); // Encrypted
+    header[44..52].copy_from_slice(&session_id.to_le_bytes());
+
+    let mut data = msg.to_vec();
+    let tag = aead_seal(cipher, key, &header[20..20 + nonce_len(cipher)], &header[20..], &mut data)?;
+    header[4..20].copy_from_slice(&tag);
+    Ok([header.as_slice(), &data].concat())
+}
+
+/// Decrypt a TRANSFORM_HEADER frame back into the SMB2 message
+fn unseal(cipher: u16, key: &[u8; 16], frame: &[u8]) -> Result<Vec<u8>, ProviderError> {
+    if frame.len() < TRANSFORM_HEADER_LEN {
+        return Err(truncated("transform header"));
+    }
+    let size = le32(frame, 36)? as usize;
+    let mut data = frame[TRANSFORM_HEADER_LEN..].to_vec();
+    if data.len() != size {
+        return Err(ProviderError::ParseError("SMB transform size mismatch".to_string()));
+    }
+    aead_open(cipher, key, &frame[20..20 + nonce_len(cipher)], &frame[20..TRANSFORM_HEADER_LEN], &mut data, &frame[4..20])?;
+    Ok(data)
+}
+
+/// Map an NTSTATUS to a provider error
+fn status_error(status: u32, what: &str) -> ProviderError {
+    match status {
+        STATUS_NO_SUCH_FILE | STATUS_OBJECT_NAME_NOT_FOUND | STATUS_OBJECT_PATH_NOT_FOUND
+        | STATUS_BAD_NETWORK_NAME | STATUS_DELETE_PENDING => ProviderError::NotFound(what.to_string()),
+        STATUS_ACCESS_DENIED | STATUS_SHARING_VIOLATION => ProviderError::PermissionDenied(what.to_string()),
+        STATUS_OBJECT_NAME_COLLISION => ProviderError::AlreadyExists(what.to_string()),
+        STATUS_DIRECTORY_NOT_EMPTY => ProviderError::DirectoryNotEmpty(what.to_string()),
+        STATUS_OBJECT_NAME_INVALID | STATUS_FILE_IS_A_DIRECTORY | STATUS_NOT_A_DIRECTORY => {
+            ProviderError::InvalidPath(what.to_string())
+        }
+        STATUS_LOGON_FAILURE => ProviderError::AuthenticationFailed("Wrong user name or password".to_string()),
+        STATUS_ACCOUNT_RESTRICTION | STATUS_PASSWORD_EXPIRED | STATUS_ACCOUNT_DISABLED | STATUS_ACCOUNT_LOCKED_OUT => {
+            ProviderError::AuthenticationFailed(format!("Account cannot log on (NTSTATUS 0x{:08X})", status))
+        }
+        STATUS_USER_SESSION_DELETED | STATUS_NETWORK_SESSION_EXPIRED => ProviderError::NotConnected,
+        STATUS_DISK_FULL => ProviderError::TransferFailed("Disk full".to_string()),
+        _ => ProviderError::ServerError(format!("{}: NTSTATUS 0x{:08X}", what, status)),
+    }
+}
+
+// ============ Connection ============
+
+/// A parsed SMB2 response. Offsets inside bodies are relative to the header start.
+struct Smb2Response {
+    status: u32,
+    flags: u32,
+    message_id: u64,
+    tree_id: u32,
+    session_id: u64,
+    msg: Vec<u8>,
+}
+
+impl Smb2Response {
+    fn parse(msg: Vec<u8>) -> Result<Self, ProviderError> {
+        if msg.len() < HEADER_LEN || &msg[..4] != SMB2_MAGIC {
+            return Err(ProviderError::ParseError("Not an SMB2 message".to_string()));
+        }
+        Ok(Self {
+            status: le32(&msg, 8)?,
+            flags: le32(&msg, 16)?,
+            message_id: le64(&msg, 24)?,
+            tree_id: le32(&msg, 36)?,
+            session_id: le64(&msg, 40)?,
+            msg,
+        })
+    }
+
+    fn credits_granted(&self) -> u16 {
+        u16::from_le_bytes([self.msg[14], self.msg[15]])
+    }
+
+    fn u8(&self, off: usize) -> Result<u8, ProviderError> {
+        self.msg.get(HEADER_LEN + off).copied().ok_or_else(|| truncated("response"))
+    }
+
+    fn u16(&self, off: usize) -> Result<u16, ProviderError> {
+        le16(&self.msg, HEADER_LEN + off)
+    }
+
+    fn u32(&self, off: usize) -> Result<u32, ProviderError> {
+        le32(&self.msg, HEADER_LEN + off)
+    }
+
+    fn u64(&self, off: usize) -> Result<u64, ProviderError> {
+        le64(&self.msg, HEADER_LEN + off)
+    }
+
+    /// Buffer at `offset` (from the header start) of `len` bytes
+    fn slice(&self, offset: usize, len: usize) -> Result<&[u8], ProviderError> {
+        self.msg.get(offset..offset + len).ok_or_else(|| truncated("response buffer"))
+    }
+
+    fn ok(self, what: &str) -> Result<Self, ProviderError> {
+        match self.status {
+            STATUS_SUCCESS => Ok(self),
+            status => Err(status_error(status, what)),
+        }
+    }
+}
+
+/// An open file or directory
+struct SmbHandle {
+    file_id: [u8; 16],
+    attributes: u32,
+    size: u64,
+    last_write: u64,
+}
+
+/// One row of a FileDirectoryInformation listing
+#[derive(Debug)]
+struct DirEntry {
+    name: String,
+    attributes: u32,
+    size: u64,
+    last_write: u64,
+}
+
+/// Parse a chain of FILE_DIRECTORY_INFORMATION records
+fn parse_directory_info(buf: &[u8]) -> Result<Vec<DirEntry>, ProviderError> {
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    loop {
+        let next = le32(buf, pos)? as usize;
+        let name_len = le32(buf, pos + 60)? as usize;
+        let name = buf.get(pos + 64..pos + 64 + name_len).ok_or_else(|| truncated("directory entry"))?;
+        entries.push(DirEntry {
+            name: from_utf16(name),
+            attributes: le32(buf, pos + 56)?,
+            size: le64(buf, pos + 40)?,
+            last_write: le64(buf, pos + 24)?,
+        });
+        if next == 0 {
+            return Ok(entries);
+        }
+        pos += next;
+    }
+}
+
+/// Keys established for the session
+struct SessionKeys {
+    signing: [u8; 16],
+    /// Client-to-server and server-to-client cipher keys (SMB 3.x)
+    encryption: Option<([u8; 16], [u8; 16])>,
+}
+
+/// One TCP connection with an authenticated session
+struct Smb2Connection {
+    stream: TcpStream,
+    timeout: Duration,
+    dialect: u16,
+    server_security_mode: u16,
+    capabilities: u32,
+    /// Cipher chosen during negotiation (0 = none available)
+    cipher: u16,
+    max_transact: u32,
+    max_read: u32,
+    max_write: u32,
+    message_id: u64,
+    credits: u32,
+    session_id: u64,
+    keys: Option<SessionKeys>,
+    encrypt: bool,
+    preauth_hash: [u8; 64],
+}
+
+impl Smb2Connection {
+    async fn open(config: &SmbConfig) -> Result<Self, ProviderError> {
+        let timeout = Duration::from_secs(config.timeout_secs);
+        let stream = tokio::time::timeout(timeout, super::proxy::connect_tcp(config.proxy.as_ref(), &config.host, config.port))
+            .await
+            .map_err(|_| ProviderError::Timeout)?
+            .map_err(|e| ProviderError::ConnectionFailed(format!("{}:{}: {}", config.host, config.port, e)))?;
+        let _ = stream.set_nodelay(true);
+
+        let mut conn = Self {
+            stream,
+            timeout,
+            dialect: 0,
+            server_security_mode: 0,
+            capabilities: 0,
+            cipher: 0,
+            max_transact: 65536,
+            max_read: 65536,
+            max_write: 65536,
+            message_id: 0,
+            credits: 1,
+            session_id: 0,
+            keys: None,
+            encrypt: false,
+            preauth_hash: [0u8; 64],
+        };
+        conn.negotiate().await?;
+        Ok(conn)
+    }
+
+    fn dialect_name(&self) -> &'static str {
+        match self.dialect {
+            DIALECT_202 => "2.0.2",
+            DIALECT_210 => "2.1",
+            DIALECT_300 => "3.0",
+            DIALECT_302 => "3.0.2",
+            _ => "3.1.1",
+        }
+    }
+
+    fn is_signing(&self) -> bool {
+        self.keys.is_some()
+    }
+
+    fn is_encrypting(&self) -> bool {
+        self.encrypt
+    }
+
+    /// Largest READ/WRITE payload that fits the server limits and our credits
+    fn io_size(&self, server_max: u32) -> u32 {
+        let size = server_max.clamp(4096, MAX_IO_SIZE);
+        if self.dialect == DIALECT_202 || self.capabilities & CAP_LARGE_MTU == 0 {
+            size.min(65536)
+        } else {
+            size.min(self.credits.max(1).saturating_mul(65536))
+        }
+    }
+
+    fn credit_charge(&self, payload: usize) -> u16 {
+        if self.dialect == DIALECT_202 {
+            0
+        } else {
+            (payload.max(1).saturating_sub(1) / 65536 + 1) as u16
+        }
+    }
+
+    /// Build (and sign) a request. Returns its message id and bytes.
+    fn build(&mut self, command: u16, tree_id: u32, body: &[u8], payload: usize) -> (u64, Vec<u8>) {
+        let charge = self.credit_charge(payload);
+        let message_id = self.message_id;
+        self.message_id += u64::from(charge.max(1));
+        self.credits = self.credits.saturating_sub(u32::from(charge.max(1)));
+
+        let mut msg = Vec::with_capacity(HEADER_LEN + body.len());
+        msg.extend_from_slice(SMB2_MAGIC);
+        msg.put_u16(HEADER_LEN as u16);
+        msg.put_u16(charge);
+        msg.put_u32(0); // ChannelSequence
+        msg.put_u16(command);
+        msg.put_u16(CREDIT_REQUEST);
+        msg.put_u32(0); // Flags
+        msg.put_u32(0); // NextCommand
+        msg.put_u64(message_id);
+        msg.put_u32(0); // Reserved (ProcessId)
+        msg.put_u32(tree_id);
+        msg.put_u64(self.session_id);
+        msg.extend_from_slice(&[0u8; 16]);
+        msg.extend_from_slice(body);
+
+        // Encrypted messages are authenticated by the cipher instead
+        if let Some(keys) = self.keys.as_ref().filter(|_| !self.encrypt && command != SESSION_SETUP) {
+            msg[16..20].copy_from_slice(&FLAGS_SIGNED.to_le_bytes());
+            let signature = sign_message(self.dialect, &keys.signing, &msg);
+            msg[48..64].copy_from_slice(&signature);
+        }
+        (message_id, msg)
+    }
+
+    /// Check the signature of a signed response
+    fn verify(&self, msg: &[u8]) -> Result<(), ProviderError> {
+        let Some(keys) = self.keys.as_ref() else { return Ok(()) };
+        let mut unsigned = msg.to_vec();
+        unsigned[48..64].fill(0);
+        let expected = sign_message(self.dialect, &keys.signing, &unsigned);
+        if bool::from(expected[..].ct_eq(&msg[48..64])) {
+            Ok(())
+        } else {
+            Err(ProviderError::ConnectionFailed("SMB response signature mismatch (possible tampering)".to_string()))
+        }
+    }
+
+    /// Once a session is established, every response must be sealed (when
+    /// encrypting) or carry a valid signature. Session setup responses are
+    /// checked by `session_setup` itself.
+    fn authenticate_response(&self, resp: &Smb2Response, sealed: bool) -> Result<(), ProviderError> {
+        if self.encrypt {
+            return if sealed {
+                Ok(())
+            } else {
+                Err(ProviderError::ConnectionFailed("Unencrypted SMB response on an encrypted session (possible tampering)".to_string()))
+            };
+        }
+        if sealed || self.keys.is_none() || le16(&resp.msg, 12)? == SESSION_SETUP {
+            return Ok(());
+        }
+        if resp.flags & FLAGS_SIGNED == 0 {
+            return Err(ProviderError::ConnectionFailed("Unsigned SMB response on a signed session (possible tampering)".to_string()));
+        }
+        self.verify(&resp.msg)
+    }
+
+    async fn write_frame(&mut self, frame: &[u8]) -> Result<(), ProviderError> {
+        let mut out = Vec::with_capacity(4 + frame.len());
+        out.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        out.extend_from_slice(frame);
+        self.stream.write_all(&out).await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))
+    }
+
+    async fn read_frame(&mut self) -> Result<Vec<u8>, ProviderError> {
+        let mut len = [0u8; 4];
+        self.stream.read_exact(&mut len).await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+        if len[0] != 0 {
+            return Err(ProviderError::ParseError(format!("Unexpected NetBIOS message type 0x{:02x}", len[0])));
+        }
+        let mut frame = vec![0u8; u32::from_be_bytes(len) as usize];
+        self.stream.read_exact(&mut frame).await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+        Ok(frame)
+    }
+
+    /// Send a built request and wait for its final response (interim STATUS_PENDING
+    /// replies and unsolicited messages such as oplock breaks are skipped)
+    async fn exchange(&mut self, message_id: u64, msg: Vec<u8>) -> Result<Smb2Response, ProviderError> {
+        let frame = match (&self.keys, self.encrypt) {
+            (Some(SessionKeys { encryption: Some((c2s, _)), .. }), true) => seal(self.cipher, c2s, self.session_id, &msg)?,
+            _ => msg,
+        };
+        let timeout = self.timeout;
+        let exchange = async {
+            self.write_frame(&frame).await?;
+            loop {
+                let frame = self.read_frame().await?;
+                let (msg, sealed) = if frame.starts_with(TRANSFORM_MAGIC) {
+                    let s2c = match &self.keys {
+                        Some(SessionKeys { encryption: Some((_, s2c)), .. }) => *s2c,
+                        _ => return Err(ProviderError::ParseError("Encrypted SMB message without session keys".to_string())),
+                    };
+                    (unseal(self.cipher, &s2c, &frame)?, true)
+                } else {
+                    (frame, false)
+                };
+                let resp = Smb2Response::parse(msg)?;
+                self.credits = self.credits.saturating_add(u32::from(resp.credits_granted()));
+                if resp.message_id != message_id {
+                    debug!("SMB: skipping unsolicited message (id {})", resp.message_id);
+                    continue;
+                }
+                // Interim responses are never signed
+                if resp.status == STATUS_PENDING && resp.flags & FLAGS_ASYNC_COMMAND != 0 {
+                    continue;
+                }
+                self.authenticate_response(&resp, sealed)?;
+                return Ok(resp);
+            }
+        };
+        tokio::time::timeout(timeout, exchange).await.map_err(|_| ProviderError::Timeout)?
+    }
+
+    async fn request(&mut self, command: u16, tree_id: u32, body: &[u8], payload: usize) -> Result<Smb2Response, ProviderError> {
+        let (message_id, msg) = self.build(command, tree_id, body, payload);
+        self.exchange(message_id, msg).await
+    }
+
+    async fn negotiate(&mut self) -> Result<(), ProviderError> {
+        let mut client_guid = [0u8; 16];
+        let mut salt = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut client_guid);
+        rand::thread_rng().fill_bytes(&mut salt);
+
+        let contexts_offset = align8(HEADER_LEN + 36 + DIALECTS.len() * 2);
+        let mut body = Vec::with_capacity(128);
+        body.put_u16(36);
+        body.put_u16(DIALECTS.len() as u16);
+        body.put_u16(SIGNING_ENABLED);
+        body.put_u16(0);
+        body.put_u32(CAP_LARGE_MTU | CAP_ENCRYPTION);
+        body.extend_from_slice(&client_guid);
+        body.put_u32(contexts_offset as u32);
+        body.put_u16(2); // NegotiateContextCount
+        body.put_u16(0);
+        for dialect in DIALECTS {
+            body.put_u16(dialect);
+        }
+        body.resize(contexts_offset - HEADER_LEN, 0);
+        body.put_u16(CTX_PREAUTH_INTEGRITY);
+        body.put_u16(6 + salt.len() as u16);
+        body.put_u32(0);
+        body.put_u16(1);
+        body.put_u16(salt.len() as u16);
+        body.put_u16(HASH_SHA512);
+        body.extend_from_slice(&salt);
+        body.resize(align8(body.len()), 0);
+        body.put_u16(CTX_ENCRYPTION);
+        body.put_u16(6);
+        body.put_u32(0);
+        body.put_u16(2);
+        body.put_u16(CIPHER_AES128_GCM);
+        body.put_u16(CIPHER_AES128_CCM);
+
+        let (message_id, request) = self.build(NEGOTIATE, 0, &body, 0);
+        let hash = preauth_update(&[0u8; 64], &request);
+        let resp = self.exchange(message_id, request).await?.ok("Negotiate")?;
+
+        self.dialect = resp.u16(4)?;
+        if !DIALECTS.contains(&self.dialect) {
+            return Err(ProviderError::ConnectionFailed(format!("Server chose unsupported SMB dialect 0x{:04x}", self.dialect)));
+        }
+        self.server_security_mode = resp.u16(2)?;
+        self.capabilities = resp.u32(24)?;
+        self.max_transact = resp.u32(28)?;
+        self.max_read = resp.u32(32)?;
+        self.max_write = resp.u32(36)?;
+
+        if self.dialect == DIALECT_311 {
+            self.preauth_hash = preauth_update(&hash, &resp.msg);
+            let count = resp.u16(6)?;
+            let mut pos = resp.u32(60)? as usize;
+            for _ in 0..count {
+                let ctx_type = le16(&resp.msg, pos)?;
+                let len = le16(&resp.msg, pos + 2)? as usize;
+                if ctx_type == CTX_ENCRYPTION && len >= 4 {
+                    self.cipher = le16(&resp.msg, pos + 10)?;
+                }
+                pos = align8(pos + 8 + len);
+            }
+        } else if self.dialect >= DIALECT_300 && self.capabilities & CAP_ENCRYPTION != 0 {
+            self.cipher = CIPHER_AES128_CCM;
+        }
+
+        debug!("SMB: negotiated dialect {} (cipher {})", self.dialect_name(), self.cipher);
+        Ok(())
+    }
+
+    async fn session_setup_round(&mut self, token: &[u8], hash: &mut [u8; 64]) -> Result<Smb2Response, ProviderError> {
+        let mut body = Vec::with_capacity(24 + token.len());
+        body.put_u16(25);
+        body.push(0); // Flags
+        body.push(SIGNING_ENABLED as u8);
+        body.put_u32(0); // Capabilities
+        body.put_u32(0); // Channel
+        body.put_u16((HEADER_LEN + 24) as u16);
+        body.put_u16(token.len() as u16);
+        body.put_u64(0); // PreviousSessionId
+        body.extend_from_slice(token);
+
+        let (message_id, request) = self.build(SESSION_SETUP, 0, &body, 0);
+        *hash = preauth_update(hash, &request);
+        self.exchange(message_id, request).await
+    }
+
+    /// Security blob of a SESSION_SETUP response
+    fn security_buffer(resp: &Smb2Response) -> Result<&[u8], ProviderError> {
+        resp.slice(resp.u16(4)? as usize, resp.u16(6)? as usize)
+    }
+
+    /// NTLMv2 session setup, then key derivation for signing and encryption
+    async fn session_setup(&mut self, config: &SmbConfig) -> Result<(), ProviderError> {
+        let mut hash = self.preauth_hash;
+
+        let resp = self.session_setup_round(&ntlm::spnego_init(&ntlm::negotiate_message()), &mut hash).await?;
+        if resp.status != STATUS_MORE_PROCESSING_REQUIRED {
+            return Err(status_error(resp.status, "Session setup"));
+        }
+        self.session_id = resp.session_id;
+        hash = preauth_update(&hash, &resp.msg);
+
+        let challenge = ntlm::extract_ntlmssp(Self::security_buffer(&resp)?)
+            .ok_or_else(|| ProviderError::AuthenticationFailed("Server did not offer NTLM".to_string()))
+            .and_then(ntlm::parse_challenge)?;
+        let mut client_challenge = [0u8; 8];
+        rand::thread_rng().fill_bytes(&mut client_challenge);
+        let creds = ntlm::Credentials {
+            user: &config.username,
+            domain: &config.domain,
+            password: config.password.expose_secret(),
+        };
+        let (auth, session_key) = ntlm::authenticate_message(&creds, &challenge, &client_challenge, ntlm::filetime_now());
+
+        let resp = self.session_setup_round(&ntlm::spnego_response(&auth), &mut hash).await?;
+        if resp.status != STATUS_SUCCESS {
+            return Err(status_error(resp.status, "Session setup"));
+        }
+        let session_flags = resp.u16(2)?;
+
+        match session_key.filter(|_| session_flags & (SESSION_FLAG_IS_GUEST | SESSION_FLAG_IS_NULL) == 0) {
+            Some(session_key) => {
+                self.keys = Some(self.derive_keys(&session_key, &hash));
+                // 3.1.1 and signing-required servers always sign the final response
+                let must_sign = self.dialect == DIALECT_311 || self.server_security_mode & SIGNING_REQUIRED != 0;
+                if resp.flags & FLAGS_SIGNED != 0 {
+                    self.verify(&resp.msg)?;
+                } else if must_sign {
+                    return Err(ProviderError::AuthenticationFailed("Server did not sign the session setup response".to_string()));
+                }
+            }
+            None => {
+                if !config.username.is_empty() {
+                    warn!("SMB: server logged '{}' on as guest", config.username);
+                }
+                if self.server_security_mode & SIGNING_REQUIRED != 0 || config.require_encryption {
+                    return Err(ProviderError::AuthenticationFailed(
+                        "Guest and anonymous sessions cannot sign or encrypt, which this server requires".to_string()
+                    ));
+                }
+            }
+        }
+
+        if session_flags & SESSION_FLAG_ENCRYPT_DATA != 0 || config.require_encryption {
+            self.enable_encryption()?;
+        }
+        Ok(())
+    }
+
+    fn derive_keys(&self, session_key: &[u8; 16], preauth_hash: &[u8; 64]) -> SessionKeys {
+        let (signing, encryption) = match self.dialect {
+            DIALECT_311 => (
+                kdf(session_key, b"SMBSigningKey\0", preauth_hash),
+                (kdf(session_key, b"SMBC2SCipherKey\0", preauth_hash), kdf(session_key, b"SMBS2CCipherKey\0", preauth_hash)),
+            ),
+            DIALECT_300 | DIALECT_302 => (
+                kdf(session_key, b"SMB2AESCMAC\0", b"SmbSign\0"),
+                (kdf(session_key, b"SMB2AESCCM\0", b"ServerIn \0"), kdf(session_key, b"SMB2AESCCM\0", b"ServerOut\0")),
+            ),
+            _ => return SessionKeys { signing: *session_key, encryption: None },
+        };
+        SessionKeys { signing, encryption: Some(encryption).filter(|_| self.cipher != 0) }
+    }
+
+    fn enable_encryption(&mut self) -> Result<(), ProviderError> {
+        match &self.keys {
+            Some(SessionKeys { encryption: Some(_), .. }) => {
+                self.encrypt = true;
+                Ok(())
+            }
+            _ => Err(ProviderError::NotSupported(format!(
+                "SMB encryption is not available (dialect {}, authenticated session required)", self.dialect_name()
+            ))),
+        }
+    }
+
+    /// Connect to `\\server\share`. Returns the tree id and the share type.
+    async fn tree_connect(&mut self, host: &str, share: &str) -> Result<(u32, u8), ProviderError> {
+        let path = utf16(&format!("\\\\{}\\{}", host.trim_start_matches('[').trim_end_matches(']'), share));
+        let mut body = Vec::with_capacity(8 + path.len());
+        body.put_u16(9);
+        body.put_u16(0);
+        body.put_u16((HEADER_LEN + 8) as u16);
+        body.put_u16(path.len() as u16);
+        body.extend_from_slice(&path);
+
+        let resp = self.request(TREE_CONNECT, 0, &body, 0).await?
+            .ok(&format!("Share '{}'", share))?;
+        let share_type = resp.u8(2)?;
+        if resp.u32(4)? & SHAREFLAG_ENCRYPT_DATA != 0 && !self.encrypt {
+            debug!("SMB: share '{}' requires encryption", share);
+            self.enable_encryption()?;
+        }
+        Ok((resp.tree_id, share_type))
+    }
+
+    async fn tree_disconnect(&mut self, tree_id: u32) -> Result<(), ProviderError> {
+        self.request(TREE_DISCONNECT, tree_id, &[4, 0, 0, 0], 0).await?.ok("Tree disconnect")?;
+        Ok(())
+    }
+
+    async fn logoff(&mut self) -> Result<(), ProviderError> {
+        self.request(LOGOFF, 0, &[4, 0, 0, 0], 0).await?.ok("Logoff")?;
+        Ok(())
+    }
+
+    async fn echo(&mut self) -> Result<(), ProviderError> {
+        self.request(ECHO, 0, &[4, 0, 0, 0], 0).await?.ok("Echo")?;
+        Ok(())
+    }
+
+    async fn create(
+        &mut self,
+        tree_id: u32,
+        path: &str,
+        access: u32,
+        disposition: u32,
+        options: u32,
+        attributes: u32,
+    ) -> Result<SmbHandle, ProviderError> {
+        let name = utf16(path);
+        let mut body = Vec::with_capacity(56 + name.len().max(1));
+        body.put_u16(57);
+        body.push(0); // SecurityFlags
+        body.push(0); // RequestedOplockLevel: none
+        body.put_u32(IMPERSONATION);
+        body.put_u64(0); // SmbCreateFlags
+        body.put_u64(0); // Reserved
+        body.put_u32(access);
+        body.put_u32(attributes);
+        body.put_u32(SHARE_ALL);
+        body.put_u32(disposition);
+        body.put_u32(options);
+        body.put_u16((HEADER_LEN + 56) as u16);
+        body.put_u16(name.len() as u16);
+        body.put_u32(0); // CreateContextsOffset
+        body.put_u32(0); // CreateContextsLength
+        if name.is_empty() {
+            body.push(0);
+        } else {
+            body.extend_from_slice(&name);
+        }
+
+        let what = if path.is_empty() { "\\" } else { path };
+        let resp = self.request(CREATE, tree_id, &body, 0).await?.ok(what)?;
+        Ok(SmbHandle {
+            file_id: resp.slice(HEADER_LEN + 64, 16)?.try_into().unwrap(),
+            attributes: resp.u32(56)?,
+            size: resp.u64(48)?,
+            last_write: resp.u64(24)?,
+        })
+    }
+
+    async fn close(&mut self, tree_id: u32, file_id: &[u8; 16]) {
+        let mut body = Vec::with_capacity(24);
+        body.put_u16(24);
+        body.put_u16(0);
+        body.put_u32(0);
+        body.extend_from_slice(file_id);
+        if let Err(e) = self.request(CLOSE, tree_id, &body, 0).await.and_then(|r| r.ok("Close")) {
+            debug!("SMB: close failed: {}", e);
+        }
+    }
+
+    /// Read up to `len` bytes; an empty result means end of file
+    async fn read(&mut self, tree_id: u32, file_id: &[u8; 16], offset: u64, len: u32) -> Result<Vec<u8>, ProviderError> {
+        let mut body = Vec::with_capacity(49);
+        body.put_u16(49);
+        body.push(0x50); // Padding: data right after the response header
+        body.push(0);
+        body.put_u32(len);
+        body.put_u64(offset);
+        body.extend_from_slice(file_id);
+        body.put_u32(0); // MinimumCount
+        body.put_u32(0); // Channel
+        body.put_u32(0); // RemainingBytes
+        body.put_u16(0);
+        body.put_u16(0);
+        body.push(0);
+
+        let resp = self.request(READ, tree_id, &body, len as usize).await?;
+        match resp.status {
+            STATUS_END_OF_FILE => return Ok(Vec::new()),
+            // Named pipes: the message continues in the next read
+            STATUS_SUCCESS | STATUS_BUFFER_OVERFLOW => {}
+            status => return Err(status_error(status, "Read")),
+        }
+        let data_offset = resp.u8(2)? as usize;
+        let data_len = resp.u32(4)? as usize;
+        Ok(resp.slice(data_offset, data_len)?.to_vec())
+    }
+
+    /// Write `data` at `offset`; returns the number of bytes written
+    async fn write(&mut self, tree_id: u32, file_id: &[u8; 16], offset: u64, data: &[u8]) -> Result<usize, ProviderError> {
+        let mut body = Vec::with_capacity(48 + data.len());
+        body.put_u16(49);
+        body.put_u16((HEADER_LEN + 48) as u16);
+        body.put_u32(data.len() as u32);
+        body.put_u64(offset);
+        body.extend_from_slice(file_id);
+        body.put_u32(0); // Channel
+        body.put_u32(0); // RemainingBytes
+        body.put_u16(0);
+        body.put_u16(0);
+        body.put_u32(0); // Flags
+        body.extend_from_slice(data);
+
+        let resp = self.request(WRITE, tree_id, &body, data.len()).await?.ok("Write")?;
+        Ok(resp.u32(4)? as usize)
+    }
+
+    /// Write all of `data`, retrying short writes
+    async fn write_all(&mut self, tree_id: u32, file_id: &[u8; 16], offset: u64, data: &[u8]) -> Result<(), ProviderError> {
+        let mut done = 0;
+        while done < data.len() {
+            let written = self.write(tree_id, file_id, offset + done as u64, &data[done..]).await?;
+            if written == 0 {
+                return Err(ProviderError::TransferFailed("Server accepted no data".to_string()));
+            }
+            done += written;
+        }
+        Ok(())
+    }
+
+    /// One QUERY_DIRECTORY round; `None` once the listing is exhausted
+    async fn query_directory(&mut self, tree_id: u32, file_id: &[u8; 16], restart: bool) -> Result<Option<Vec<DirEntry>>, ProviderError> {
+        let pattern = utf16("*");
+        let out_len = DIRECTORY_BUFFER.min(self.max_transact);
+        let mut body = Vec::with_capacity(32 + pattern.len());
+        body.put_u16(33);
+        body.push(FILE_DIRECTORY_INFORMATION);
+        body.push(if restart { RESTART_SCANS } else { 0 });
+        body.put_u32(0); // FileIndex
+        body.extend_from_slice(file_id);
+        body.put_u16((HEADER_LEN + 32) as u16);
+        body.put_u16(pattern.len() as u16);
+        body.put_u32(out_len);
+        body.extend_from_slice(&pattern);
+
+        let resp = self.request(QUERY_DIRECTORY, tree_id, &body, out_len as usize).await?;
+        if resp.status == STATUS_NO_MORE_FILES {
+            return Ok(None);
+        }
+        let resp = resp.ok("List directory")?;
+        let buf = resp.slice(resp.u16(2)? as usize, resp.u32(4)? as usize)?;
+        parse_directory_info(buf).map(Some)
+    }
+
+    async fn query_info(&mut self, tree_id: u32, file_id: &[u8; 16], info_type: u8, class: u8, out_len: u32) -> Result<Vec<u8>, ProviderError> {
+        let mut body = Vec::with_capacity(41);
+        body.put_u16(41);
+        body.push(info_type);
+        body.push(class);
+        body.put_u32(out_len);
+        body.put_u16(0); // InputBufferOffset
+        body.put_u16(0);
+        body.put_u32(0); // InputBufferLength
+        body.put_u32(0); // AdditionalInformation
+        body.put_u32(0); // Flags
+        body.extend_from_slice(file_id);
+        body.push(0);
+
+        let resp = self.request(QUERY_INFO, tree_id, &body, out_len as usize).await?.ok("Query info")?;
+        Ok(resp.slice(resp.u16(2)? as usize, resp.u32(4)? as usize)?.to_vec())
+    }
+
+    async fn set_info(&mut self, tree_id: u32, file_id: &[u8; 16], class: u8, data: &[u8], what: &str) -> Result<(), ProviderError> {
+        let mut body = Vec::with_capacity(32 + data.len());
+        body.put_u16(33);
+        body.push(INFO_FILE);
+        body.push(class);
+        body.put_u32(data.len() as u32);
+        body.put_u16((HEADER_LEN + 32) as u16);
+        body.put_u16(0);
+        body.put_u32(0); // AdditionalInformation
+        body.extend_from_slice(file_id);
+        body.extend_from_slice(data);
+
+        self.request(SET_INFO, tree_id, &body, 0).await?.ok(what)?;
+        Ok(())
+    }
+
+    /// FSCTL on an open file; returns the output buffer
+    async fn fsctl(&mut self, tree_id: u32, file_id: &[u8; 16], code: u32, input: &[u8], max_output: u32) -> Result<Vec<u8>, ProviderError> {
+        let mut body = Vec::with_capacity(56 + input.len().max(1));
+        body.put_u16(57);
+        body.put_u16(0);
+        body.put_u32(code);
+        body.extend_from_slice(file_id);
+        body.put_u32(if input.is_empty() { 0 } else { (HEADER_LEN + 56) as u32 });
+        body.put_u32(input.len() as u32);
+        body.put_u32(0); // MaxInputResponse
+        body.put_u32(0); // OutputOffset
+        body.put_u32(0); // OutputCount
+        body.put_u32(max_output);
+        body.put_u32(IOCTL_IS_FSCTL);
+        body.put_u32(0);
+        if input.is_empty() {
+            body.push(0);
+        } else {
+            body.extend_from_slice(input);
+        }
+
+        let resp = self.request(IOCTL, tree_id, &body, input.len().max(max_output as usize)).await?
+            .ok(&format!("FSCTL 0x{:08X}", code))?;
+        Ok(resp.slice(resp.u32(32)? as usize, resp.u32(36)? as usize)?.to_vec())
+    }
+
+    /// Copy `src` into `dst` on the server with FSCTL_SRV_COPYCHUNK_WRITE
+    async fn copy_chunks(&mut self, tree_id: u32, src: &SmbHandle, dst: &SmbHandle) -> Result<(), ProviderError> {
+        let resume = self.fsctl(tree_id, &src.file_id, FSCTL_SRV_REQUEST_RESUME_KEY, &[], 32).await?;
+        let resume_key = resume.get(..24).ok_or_else(|| truncated("resume key"))?.to_vec();
+
+        let mut offset = 0u64;
+        while offset < src.size {
+            let mut chunks = Vec::new();
+            let mut next = offset;
+            while next < src.size && chunks.len() < COPYCHUNK_MAX_CHUNKS {
+                let len = (src.size - next).min(u64::from(COPYCHUNK_SIZE)) as u32;
+                chunks.push((next, len));
+                next += u64::from(len);
+            }
+
+            let mut input = resume_key.clone();
+            input.put_u32(chunks.len() as u32);
+            input.put_u32(0);
+            for (chunk_offset, len) in chunks {
+                input.put_u64(chunk_offset); // SourceOffset
+                input.put_u64(chunk_offset); // TargetOffset
+                input.put_u32(len);
+                input.put_u32(0);
+            }
+
+            let output = self.fsctl(tree_id, &dst.file_id, FSCTL_SRV_COPYCHUNK_WRITE, &input, 12).await?;
+            let written = le32(&output, 8)?;
+            if written == 0 {
+                return Err(ProviderError::TransferFailed("Server-side copy made no progress".to_string()));
+            }
+            offset += u64::from(written);
+        }
+        Ok(())
+    }
+}
+
+// ============ Share enumeration (MS-SRVS over DCE/RPC) ============
+
+mod srvsvc {
+    use super::{le16, le32, from_utf16, PutLe, ProviderError};
+
+    const PTYPE_REQUEST: u8 = 0;
+    const PTYPE_RESPONSE: u8 = 2;
+    const PTYPE_FAULT: u8 = 3;
+    const PTYPE_BIND: u8 = 11;
+    const PTYPE_BIND_ACK: u8 = 12;
+    const PFC_FIRST_LAST: u8 = 0x03;
+    const PFC_LAST_FRAG: u8 = 0x02;
+
+    /// Fragment size offered in the bind (a common default)
+    pub const MAX_FRAG: u16 = 4280;
+
+    /// NetrShareEnum
+    const OPNUM_SHARE_ENUM: u16 = 15;
+
+    /// srvsvc interface 4b324fc8-1670-01d3-1278-5a47bf6ee188 v3.0
+    const SRVSVC_UUID: [u8; 16] = [
+        0xc8, 0x4f, 0x32, 0x4b, 0x70, 0x16, 0xd3, 0x01, 0x12, 0x78, 0x5a, 0x47, 0xbf, 0x6e, 0xe1, 0x88,
+    ];
+    /// NDR transfer syntax 8a885d04-1ceb-11c9-9fe8-08002b104860 v2
+    const NDR_UUID: [u8; 16] = [
+        0x04, 0x5d, 0x88, 0x8a, 0xeb, 0x1c, 0xc9, 0x11, 0x9f, 0xe8, 0x08, 0x00, 0x2b, 0x10, 0x48, 0x60,
+    ];
+
+    pub const STYPE_DISKTREE: u32 = 0x0000_0000;
+    pub const STYPE_SPECIAL: u32 = 0x8000_0000;
+
+    #[derive(Debug)]
+    pub struct ShareInfo {
+        pub name: String,
+        pub share_type: u32,
+        pub remark: String,
+    }
+
+    fn pdu_header(ptype: u8, call_id: u32, body_len: usize) -> Vec<u8> {
+        let mut pdu = vec![5, 0, ptype, PFC_FIRST_LAST, 0x10, 0, 0, 0];
+        pdu.put_u16((16 + body_len) as u16);
+        pdu.put_u16(0); // auth_length
+        pdu.put_u32(call_id);
+        pdu
+    }
+
+    pub fn bind() -> Vec<u8> {
+        let mut body = Vec::with_capacity(56);
+        body.put_u16(MAX_FRAG);
+        body.put_u16(MAX_FRAG);
+        body.put_u32(0); // assoc_group_id
+        body.extend_from_slice(&[1, 0, 0, 0]); // one context
+        body.put_u16(0); // context id
+        body.extend_from_slice(&[1, 0]); // one transfer syntax
+        body.extend_from_slice(&SRVSVC_UUID);
+        body.put_u16(3);
+        body.put_u16(0);
+        body.extend_from_slice(&NDR_UUID);
+        body.put_u32(2);
+        [pdu_header(PTYPE_BIND, 1, body.len()), body].concat()
+    }
+
+    /// NetrShareEnum request at info level 1
+    pub fn share_enum(server: &str) -> Vec<u8> {
+        let name: Vec<u16> = format!("\\\\{}", server).encode_utf16().chain(std::iter::once(0)).collect();
+        let mut stub = Vec::new();
+        stub.put_u32(0x0002_0000); // ServerName referent id
+        stub.put_u32(name.len() as u32); // max count
+        stub.put_u32(0); // offset
+        stub.put_u32(name.len() as u32); // actual count
+        for unit in &name {
+            stub.put_u16(*unit);
+        }
+        stub.resize((stub.len() + 3) & !3, 0);
+        stub.put_u32(1); // Level
+        stub.put_u32(1); // union switch
+        stub.put_u32(0x0002_0004); // SHARE_INFO_1_CONTAINER referent id
+        stub.put_u32(0); // EntriesRead
+        stub.put_u32(0); // Buffer (null)
+        stub.put_u32(u32::MAX); // PreferedMaximumLength
+        stub.put_u32(0); // ResumeHandle (null)
+
+        let mut body = Vec::with_capacity(8 + stub.len());
+        body.put_u32(stub.len() as u32); // alloc_hint
+        body.put_u16(0); // context id
+        body.put_u16(OPNUM_SHARE_ENUM);
+        body.extend_from_slice(&stub);
+        [pdu_header(PTYPE_REQUEST, 2, body.len()), body].concat()
+    }
+
+    /// Outcome of feeding received pipe data
+    pub enum Reply {
+        /// More fragments are needed
+        Incomplete,
+        BindAck,
+        Stub(Vec<u8>),
+    }
+
+    /// Consume complete PDUs from `buf`, appending response stubs to `stub`
+    pub fn feed(buf: &mut Vec<u8>, stub: &mut Vec<u8>) -> Result<Reply, ProviderError> {
+        while buf.len() >= 16 {
+            let frag_len = le16(buf, 8)? as usize;
+            if frag_len < 16 {
+                return Err(ProviderError::ParseError("Invalid DCE/RPC fragment".to_string()));
+            }
+            if buf.len() < frag_len {
+                break;
+            }
+            let frag: Vec<u8> = buf.drain(..frag_len).collect();
+            match frag[2] {
+                PTYPE_BIND_ACK => return Ok(Reply::BindAck),
+                PTYPE_RESPONSE if frag_len >= 24 => {
+                    stub.extend_from_slice(&frag[24..]);
+                    if frag[3] & PFC_LAST_FRAG != 0 {
+                        return Ok(Reply::Stub(std::mem::take(stub)));
+                    }
+                }
+                PTYPE_FAULT => {
+                    return Err(ProviderError::ServerError(format!("DCE/RPC fault 0x{:08X}", le32(&frag, 24).unwrap_or(0))));
+                }
+                other => {
+                    return Err(ProviderError::ServerError(format!("Unexpected DCE/RPC packet type {}", other)));
+                }
+            }
+        }
+        Ok(Reply::Incomplete)
+    }
+
+    struct NdrReader<'a> {
+        buf: &'a [u8],
+        pos: usize,
+    }
+
+    impl NdrReader<'_> {
+        fn u32(&mut self) -> Result<u32, ProviderError> {
+            self.pos = (self.pos + 3) & !3;
+            let v = le32(self.buf, self.pos)?;
+            self.pos += 4;
+            Ok(v)
+        }
+
+        /// Conformant varying UTF-16 string
+        fn string(&mut self) -> Result<String, ProviderError> {
+            let _max = self.u32()?;
+            let _offset = self.u32()?;
+            let count = self.u32()? as usize;
+            let bytes = self.buf.get(self.pos..self.pos + count * 2)
+                .ok_or_else(|| ProviderError::ParseError("Truncated NDR string".to_string()))?;
+            self.pos += count * 2;
+            Ok(from_utf16(bytes).trim_end_matches('\0').to_string())
+        }
+    }
+
+    /// Parse the NetrShareEnum response stub
+    pub fn parse_share_enum(stub: &[u8]) -> Result<Vec<ShareInfo>, ProviderError> {
+        if stub.len() < 4 {
+            return Err(ProviderError::ParseError("Truncated NetrShareEnum response".to_string()));
+        }
+        match le32(stub, stub.len() - 4)? {
+            0 => {}
+            5 => return Err(ProviderError::PermissionDenied("Listing shares".to_string())),
+            code => return Err(ProviderError::ServerError(format!("NetrShareEnum failed: WERROR 0x{:08X}", code))),
+        }
+
+        let mut r = NdrReader { buf: stub, pos: 0 };
+        let _level = r.u32()?;
+        let _switch = r.u32()?;
+        if r.u32()? == 0 {
+            return Ok(Vec::new());
+        }
+        let count = r.u32()? as usize;
+        if r.u32()? == 0 || count > stub.len() / 12 {
+            return Ok(Vec::new());
+        }
+        let _max = r.u32()?;
+
+        let mut rows = Vec::with_capacity(count);
+        for _ in 0..count {
+            rows.push((r.u32()?, r.u32()?, r.u32()?));
+        }
+        let mut shares = Vec::with_capacity(count);
+        for (name_ptr, share_type, remark_ptr) in rows {
+            let name = if name_ptr != 0 { r.string()? } else { String::new() };
+            let remark = if remark_ptr != 0 { r.string()? } else { String::new() };
+            shares.push(ShareInfo { name, share_type, remark });
+        }
+        Ok(shares)
+    }
+}
+
+// ============ Provider ============
+
+/// SMB2/3 Provider
+pub struct SmbProvider {
+    config: SmbConfig,
+    conn: Option<Smb2Connection>,
+    /// Tree ids of connected shares (lower-case share name)
+    trees: HashMap<String, u32>,
+    /// `IPC$` tree used for share enumeration
+    ipc_tree: Option<u32>,
+    current_dir: String,
+}
+
+impl SmbProvider {
+    pub fn new(config: SmbConfig) -> Self {
+        Self {
+            config,
+            conn: None,
+            trees: HashMap::new(),
+            ipc_tree: None,
+            current_dir: "/".to_string(),
+        }
+    }
+
+    fn conn(&mut self) -> Result<&mut Smb2Connection, ProviderError> {
+        self.conn.as_mut().ok_or(ProviderError::NotConnected)
+    }
+
+    /// Absolute, normalized path (`.` and `..` resolved)
+    fn normalize_path(&self, path: &str) -> String {
+        let joined = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("{}/{}", self.current_dir.trim_end_matches('/'), path)
+        };
+        let mut parts: Vec<&str> = Vec::new();
+        for part in joined.split(['/', '\\']) {
+            match part {
+                "" | "." => {}
+                ".." => { parts.pop(); }
+                p => parts.push(p),
+            }
+        }
+        format!("/{}", parts.join("/"))
+    }
+
+    /// Split a path into share name and backslash path inside the share
+    fn locate(&self, path: &str) -> Result<(String, String), ProviderError> {
+        let abs = self.normalize_path(path);
+        let parts: Vec<&str> = abs.split('/').filter(|p| !p.is_empty()).collect();
+        match &self.config.share {
+            Some(share) => Ok((share.clone(), parts.join("\\"))),
+            None => match parts.split_first() {
+                Some((share, rest)) => Ok((share.to_string(), rest.join("\\"))),
+                None => Err(ProviderError::InvalidPath("/ lists the server's shares; open a share first".to_string())),
+            },
+        }
+    }
+
+    /// Whether `path` is the share list (no share configured and at `/`)
+    fn is_share_list(&self, path: &str) -> bool {
+        self.config.share.is_none() && self.normalize_path(path) == "/"
+    }
+
+    /// Tree id of a disk share, connecting on first use
+    async fn tree_for(&mut self, share: &str) -> Result<u32, ProviderError> {
+        let key = share.to_lowercase();
+        if let Some(tree_id) = self.trees.get(&key) {
+            return Ok(*tree_id);
+        }
+        let conn = self.conn.as_mut().ok_or(ProviderError::NotConnected)?;
+        let (tree_id, share_type) = conn.tree_connect(&self.config.host, share).await?;
+        if share_type != SHARE_TYPE_DISK {
+            let _ = conn.tree_disconnect(tree_id).await;
+            return Err(ProviderError::NotSupported(format!("'{}' is not a disk share", share)));
+        }
+        self.trees.insert(key, tree_id);
+        Ok(tree_id)
+    }
+
+    async fn open(
+        &mut self,
+        path: &str,
+        access: u32,
+        disposition: u32,
+        options: u32,
+        attributes: u32,
+    ) -> Result<(u32, SmbHandle), ProviderError> {
+        let (share, inner) = self.locate(path)?;
+        let tree_id = self.tree_for(&share).await?;
+        let handle = self.conn()?.create(tree_id, &inner, access, disposition, options, attributes).await?;
+        Ok((tree_id, handle))
+    }
+
+    fn entry(&self, name: String, path: String, attributes: u32, size: u64, last_write: u64) -> RemoteEntry {
+        let is_dir = attributes & FILE_ATTRIBUTE_DIRECTORY != 0;
+        let mut metadata = HashMap::new();
+        if attributes & FILE_ATTRIBUTE_HIDDEN != 0 {
+            metadata.insert("hidden".to_string(), "true".to_string());
+        }
+        RemoteEntry {
+            name,
+            path,
+            is_dir,
+            size: if is_dir { 0 } else { size },
+            modified: format_filetime(last_write),
+            permissions: Some(if attributes & FILE_ATTRIBUTE_READONLY != 0 { "r--" } else { "rw-" }.to_string()),
+            owner: None,
+            group: None,
+            is_symlink: false,
+            link_target: None,
+            mime_type: None,
+            metadata,
+            content_hash: None,
+        }
+    }
+
+    /// Disk shares of the server (hidden administrative shares excluded)
+    async fn list_shares(&mut self) -> Result<Vec<RemoteEntry>, ProviderError> {
+        let host = self.config.host.clone();
+        let conn = self.conn.as_mut().ok_or(ProviderError::NotConnected)?;
+        let tree_id = match self.ipc_tree {
+            Some(tree_id) => tree_id,
+            None => {
+                let (tree_id, _) = conn.tree_connect(&host, "IPC$").await?;
+                self.ipc_tree = Some(tree_id);
+                tree_id
+            }
+        };
+
+        let pipe = conn.create(tree_id, "srvsvc", FILE_READ_DATA | FILE_WRITE_DATA | FILE_READ_ATTRIBUTES | SYNCHRONIZE, FILE_OPEN, 0, 0).await?;
+        let result = async {
+            Self::rpc_call(conn, tree_id, &pipe.file_id, &srvsvc::bind()).await?;
+            let stub = Self::rpc_call(conn, tree_id, &pipe.file_id, &srvsvc::share_enum(&host)).await?;
+            srvsvc::parse_share_enum(&stub)
+        }.await;
+        conn.close(tree_id, &pipe.file_id).await;
+
+        let mut entries: Vec<RemoteEntry> = result?.into_iter()
+            .filter(|s| s.share_type & 0xFFFF == srvsvc::STYPE_DISKTREE && s.share_type & srvsvc::STYPE_SPECIAL == 0)
+            .map(|s| {
+                let mut entry = RemoteEntry::directory(s.name.clone(), format!("/{}", s.name));
+                if !s.remark.is_empty() {
+                    entry.metadata.insert("comment".to_string(), s.remark);
+                }
+                entry
+            })
+            .collect();
+        entries.sort_by_key(|e| e.name.to_lowercase());
+        Ok(entries)
+    }
+
+    /// Write one DCE/RPC PDU to the pipe and read until its reply is complete
+    async fn rpc_call(conn: &mut Smb2Connection, tree_id: u32, file_id: &[u8; 16], pdu: &[u8]) -> Result<Vec<u8>, ProviderError> {
+        conn.write_all(tree_id, file_id, 0, pdu).await?;
+        let mut buf = Vec::new();
+        let mut stub = Vec::new();
+        loop {
+            let data = conn.read(tree_id, file_id, 0, u32::from(srvsvc::MAX_FRAG)).await?;
+            if data.is_empty() {
+                return Err(ProviderError::ParseError("srvsvc pipe closed".to_string()));
+            }
+            buf.extend_from_slice(&data);
+            match srvsvc::feed(&mut buf, &mut stub)? {
+                srvsvc::Reply::Incomplete => continue,
+                srvsvc::Reply::BindAck => return Ok(Vec::new()),
+                srvsvc::Reply::Stub(stub) => return Ok(stub),
+            }
+        }
+    }
+
+    /// Read a remote file from `offset` into a local file
+    async fn download_from(
+        &mut self,
+        remote_path: &str,
+        local_path: &str,
+        offset: u64,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        let (tree_id, handle) = self.open(remote_path, FILE_READ_DATA | FILE_READ_ATTRIBUTES | SYNCHRONIZE, FILE_OPEN, FILE_NON_DIRECTORY_FILE, 0).await?;
+        let conn = self.conn()?;
+        let result = async {
+            let mut file = if offset > 0 {
+                let mut file = tokio::fs::OpenOptions::new().write(true).open(local_path).await?;
+                file.seek(std::io::SeekFrom::Start(offset)).await?;
+                file
+            } else {
+                tokio::fs::File::create(local_path).await?
+            };
+
+            let total = handle.size;
+            let mut pos = offset;
+            while pos < total {
+                let len = conn.io_size(conn.max_read).min((total - pos).min(u64::from(u32::MAX)) as u32);
+                let data = conn.read(tree_id, &handle.file_id, pos, len).await?;
+                if data.is_empty() {
+                    break;
+                }
+                file.write_all(&data).await?;
+                pos += data.len() as u64;
+                if let Some(ref cb) = on_progress {
+                    cb(pos, total);
+                }
+            }
+            file.flush().await?;
+            Ok::<(), ProviderError>(())
+        }.await;
+        conn.close(tree_id, &handle.file_id).await;
+        result
+    }
+
+    /// Write a local file from `offset` into a remote file
+    async fn upload_from(
+        &mut self,
+        local_path: &str,
+        remote_path: &str,
+        offset: u64,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        let mut file = tokio::fs::File::open(local_path).await?;
+        let total = file.metadata().await?.len();
+        if offset > 0 {
+            file.seek(std::io::SeekFrom::Start(offset)).await?;
+        }
+
+        let disposition = if offset > 0 { FILE_OPEN_IF } else { FILE_OVERWRITE_IF };
+        let (tree_id, handle) = self.open(remote_path, FILE_WRITE_DATA | FILE_READ_ATTRIBUTES | SYNCHRONIZE, disposition, FILE_NON_DIRECTORY_FILE, FILE_ATTRIBUTE_NORMAL).await?;
+        let conn = self.conn()?;
+        let result = async {
+            let mut buf = vec![0u8; conn.io_size(conn.max_write) as usize];
+            let mut pos = offset;
+            loop {
+                let chunk = buf.len().min(conn.io_size(conn.max_write) as usize);
+                let n = file.read(&mut buf[..chunk]).await?;
+                if n == 0 {
+                    break;
+                }
+                conn.write_all(tree_id, &handle.file_id, pos, &buf[..n]).await?;
+                pos += n as u64;
+                if let Some(ref cb) = on_progress {
+                    cb(pos, total);
+                }
+            }
+            Ok::<(), ProviderError>(())
+        }.await;
+        conn.close(tree_id, &handle.file_id).await;
+        result
+    }
+
+    /// Mark a file or directory for deletion
+    async fn remove(&mut self, path: &str, directory: bool) -> Result<(), ProviderError> {
+        let (_, inner) = self.locate(path)?;
+        if inner.is_empty() {
+            return Err(ProviderError::InvalidPath("Cannot delete a share root".to_string()));
+        }
+        let options = if directory { FILE_DIRECTORY_FILE } else { FILE_NON_DIRECTORY_FILE };
+        let (tree_id, handle) = self.open(path, DELETE | FILE_READ_ATTRIBUTES | SYNCHRONIZE, FILE_OPEN, options, 0).await?;
+        let conn = self.conn()?;
+        let result = conn.set_info(tree_id, &handle.file_id, FILE_DISPOSITION_INFORMATION, &[1], &inner).await;
+        conn.close(tree_id, &handle.file_id).await;
+        result
+    }
+}
+
+#[async_trait]
+impl StorageProvider for SmbProvider {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+
+    fn provider_type(&self) -> ProviderType { ProviderType::Smb }
+
+    fn display_name(&self) -> String {
+        match &self.config.share {
+            Some(share) => format!("smb://{}/{}", self.config.host, share),
+            None => format!("smb://{}", self.config.host),
+        }
+    }
+
+    fn account_email(&self) -> Option<String> {
+        if self.config.username.is_empty() {
+            None
+        } else if self.config.domain.is_empty() {
+            Some(self.config.username.clone())
+        } else {
+            Some(format!("{}\\{}", self.config.domain, self.config.username))
+        }
+    }
+
+    fn is_connected(&self) -> bool { self.conn.is_some() }
+
+    async fn connect(&mut self) -> Result<(), ProviderError> {
+        info!("SMB connect: {}:{} share={:?}", self.config.host, self.config.port, self.config.share);
+
+        let mut conn = Smb2Connection::open(&self.config).await?;
+        conn.session_setup(&self.config).await?;
+        info!("Connected to SMB {} server {} (signing: {}, encryption: {})",
+            conn.dialect_name(), self.config.host, conn.is_signing(), conn.is_encrypting());
+
+        self.conn = Some(conn);
+        self.trees.clear();
+        self.ipc_tree = None;
+        self.current_dir = "/".to_string();
+
+        if let Some(share) = self.config.share.clone() {
+            self.tree_for(&share).await?;
+        }
+        if let Some(initial) = self.config.initial_path.clone().filter(|p| !p.is_empty() && p != "/") {
+            self.cd(&initial).await?;
+        }
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ProviderError> {
+        if let Some(mut conn) = self.conn.take() {
+            for tree_id in self.trees.drain().map(|(_, id)| id).chain(self.ipc_tree.take()) {
+                let _ = conn.tree_disconnect(tree_id).await;
+            }
+            let _ = conn.logoff().await;
+        }
+        Ok(())
+    }
+
+    async fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
+        if self.is_share_list(path) {
+            return self.list_shares().await;
+        }
+        let base = self.normalize_path(path);
+        let (tree_id, dir) = self.open(&base, FILE_LIST_DIRECTORY | FILE_READ_ATTRIBUTES | SYNCHRONIZE, FILE_OPEN, FILE_DIRECTORY_FILE, 0).await?;
+
+        let conn = self.conn()?;
+        let mut rows = Vec::new();
+        let mut restart = true;
+        let result = loop {
+            match conn.query_directory(tree_id, &dir.file_id, restart).await {
+                Ok(Some(batch)) => rows.extend(batch),
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+            restart = false;
+        };
+        conn.close(tree_id, &dir.file_id).await;
+        result?;
+
+        let prefix = base.trim_end_matches('/');
+        Ok(rows.into_iter()
+            .filter(|row| row.name != "." && row.name != "..")
+            .map(|row| {
+                let path = format!("{}/{}", prefix, row.name);
+                self.entry(row.name, path, row.attributes, row.size, row.last_write)
+            })
+            .collect())
+    }
+
+    async fn pwd(&mut self) -> Result<String, ProviderError> {
+        Ok(self.current_dir.clone())
+    }
+
+    async fn cd(&mut self, path: &str) -> Result<(), ProviderError> {
+        let target = self.normalize_path(path);
+        if !self.is_share_list(&target) {
+            let entry = self.stat(&target).await?;
+            if !entry.is_dir {
+                return Err(ProviderError::InvalidPath(format!("Not a directory: {}", target)));
+            }
+        }
+        self.current_dir = target;
+        Ok(())
+    }
+
+    async fn cd_up(&mut self) -> Result<(), ProviderError> {
+        self.current_dir = self.normalize_path("..");
+        Ok(())
+    }
+
+    async fn download(
+        &mut self,
+        remote_path: &str,
+        local_path: &str,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        self.download_from(remote_path, local_path, 0, on_progress).await
+    }
+
+    async fn download_to_bytes(&mut self, remote_path: &str) -> Result<Vec<u8>, ProviderError> {
+        let size = self.size(remote_path).await?;
+        if size > super::MAX_DOWNLOAD_TO_BYTES {
+            return Err(ProviderError::TransferFailed(format!(
+                "File too large for in-memory download ({:.1} MB). Use streaming download instead.",
+                size as f64 / 1_048_576.0,
+            )));
+        }
+        self.read_range(remote_path, 0, size).await
+    }
+
+    async fn upload(
+        &mut self,
+        local_path: &str,
+        remote_path: &str,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        self.upload_from(local_path, remote_path, 0, on_progress).await
+    }
+
+    async fn mkdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        let (tree_id, handle) = self.open(path, FILE_READ_ATTRIBUTES | SYNCHRONIZE, FILE_CREATE, FILE_DIRECTORY_FILE, FILE_ATTRIBUTE_DIRECTORY).await?;
+        self.conn()?.close(tree_id, &handle.file_id).await;
+        Ok(())
+    }
+
+    async fn delete(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.remove(path, false).await
+    }
+
+    async fn rmdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.remove(path, true).await
+    }
+
+    async fn rmdir_recursive(&mut self, path: &str) -> Result<(), ProviderError> {
+        // Breadth-first walk, then remove directories deepest first
+        let mut dirs = vec![self.normalize_path(path)];
+        let mut i = 0;
+        while i < dirs.len() {
+            let dir = dirs[i].clone();
+            for entry in self.list(&dir).await? {
+                if entry.is_dir {
+                    dirs.push(entry.path);
+                } else {
+                    self.remove(&entry.path, false).await?;
+                }
+            }
+            i += 1;
+        }
+        for dir in dirs.iter().rev() {
+            self.remove(dir, true).await?;
+        }
+        Ok(())
+    }
+
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        let (from_share, from_inner) = self.locate(from)?;
+        let (to_share, to_inner) = self.locate(to)?;
+        if !from_share.eq_ignore_ascii_case(&to_share) {
+            return Err(ProviderError::NotSupported("Moving between SMB shares".to_string()));
+        }
+        if from_inner.is_empty() || to_inner.is_empty() {
+            return Err(ProviderError::InvalidPath("Cannot rename a share root".to_string()));
+        }
+
+        let name = utf16(&to_inner);
+        let mut info = Vec::with_capacity(20 + name.len());
+        info.push(0); // ReplaceIfExists
+        info.extend_from_slice(&[0u8; 7]);
+        info.put_u64(0); // RootDirectory
+        info.put_u32(name.len() as u32);
+        info.extend_from_slice(&name);
+
+        let (tree_id, handle) = self.open(from, DELETE | FILE_READ_ATTRIBUTES | SYNCHRONIZE, FILE_OPEN, 0, 0).await?;
+        let conn = self.conn()?;
+        let result = conn.set_info(tree_id, &handle.file_id, FILE_RENAME_INFORMATION, &info, &to_inner).await;
+        conn.close(tree_id, &handle.file_id).await;
+        result
+    }
+
+    async fn stat(&mut self, path: &str) -> Result<RemoteEntry, ProviderError> {
+        let abs = self.normalize_path(path);
+        if self.is_share_list(&abs) {
+            return Ok(RemoteEntry::directory("/".to_string(), "/".to_string()));
+        }
+        let (tree_id, handle) = self.open(&abs, FILE_READ_ATTRIBUTES | SYNCHRONIZE, FILE_OPEN, 0, 0).await?;
+        self.conn()?.close(tree_id, &handle.file_id).await;
+
+        let name = abs.rsplit('/').next().filter(|n| !n.is_empty()).unwrap_or("/").to_string();
+        Ok(self.entry(name, abs, handle.attributes, handle.size, handle.last_write))
+    }
+
+    async fn size(&mut self, path: &str) -> Result<u64, ProviderError> {
+        Ok(self.stat(path).await?.size)
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool, ProviderError> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(ProviderError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn keep_alive(&mut self) -> Result<(), ProviderError> {
+        self.conn()?.echo().await
+    }
+
+    async fn server_info(&mut self) -> Result<String, ProviderError> {
+        let host = self.config.host.clone();
+        let port = self.config.port;
+        let conn = self.conn()?;
+        let security = if conn.is_encrypting() {
+            "encrypted"
+        } else if conn.is_signing() {
+            "signed"
+        } else {
+            "unsigned"
+        };
+        Ok(format!("SMB {} on {}:{} ({})", conn.dialect_name(), host, port, security))
+    }
+
+    /// Size and free space of the share holding the current directory
+    async fn storage_info(&mut self) -> Result<StorageInfo, ProviderError> {
+        let cwd = self.current_dir.clone();
+        let (share, _) = self.locate(&cwd)
+            .map_err(|_| ProviderError::NotSupported("storage_info outside a share".to_string()))?;
+        let (tree_id, root) = self.open(&format!("/{}", if self.config.share.is_some() { "" } else { share.as_str() }),
+            FILE_READ_ATTRIBUTES | SYNCHRONIZE, FILE_OPEN, FILE_DIRECTORY_FILE, 0).await?;
+        let conn = self.conn()?;
+        let info = conn.query_info(tree_id, &root.file_id, INFO_FILESYSTEM, FILE_FS_FULL_SIZE_INFORMATION, 32).await;
+        conn.close(tree_id, &root.file_id).await;
+        let info = info?;
+
+        let unit = le32(&info, 24)? as u64 * le32(&info, 28)? as u64;
+        let total = le64(&info, 0)?.saturating_mul(unit);
+        let free = le64(&info, 8)?.saturating_mul(unit);
+        Ok(StorageInfo { used: total.saturating_sub(free), total, free })
+    }
+
+    fn supports_server_copy(&self) -> bool { true }
+
+    async fn server_copy(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        let (from_share, _) = self.locate(from)?;
+        let (to_share, _) = self.locate(to)?;
+        if !from_share.eq_ignore_ascii_case(&to_share) {
+            return Err(ProviderError::NotSupported("Server-side copy between SMB shares".to_string()));
+        }
+
+        let (tree_id, src) = self.open(from, FILE_READ_DATA | FILE_READ_ATTRIBUTES | SYNCHRONIZE, FILE_OPEN, FILE_NON_DIRECTORY_FILE, 0).await?;
+        let dst = match self.open(to, FILE_READ_DATA | FILE_WRITE_DATA | FILE_READ_ATTRIBUTES | SYNCHRONIZE, FILE_OVERWRITE_IF, FILE_NON_DIRECTORY_FILE, FILE_ATTRIBUTE_NORMAL).await {
+            Ok((_, dst)) => dst,
+            Err(e) => {
+                self.conn()?.close(tree_id, &src.file_id).await;
+                return Err(e);
+            }
+        };
+        let conn = self.conn()?;
+        let result = conn.copy_chunks(tree_id, &src, &dst).await;
+        conn.close(tree_id, &dst.file_id).await;
+        conn.close(tree_id, &src.file_id).await;
+        result
+    }
+
+    fn supports_resume(&self) -> bool { true }
+
+    async fn resume_download(
+        &mut self,
+        remote_path: &str,
+        local_path: &str,
+        offset: u64,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        self.download_from(remote_path, local_path, offset, on_progress).await
+    }
+
+    async fn resume_upload(
+        &mut self,
+        local_path: &str,
+        remote_path: &str,
+        offset: u64,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        self.upload_from(local_path, remote_path, offset, on_progress).await
+    }
+
+    fn transfer_optimization_hints(&self) -> super::TransferOptimizationHints {
+        super::TransferOptimizationHints {
+            supports_resume_download: true,
+            supports_resume_upload: true,
+            ..Default::default()
+        }
+    }
+
+    async fn read_range(&mut self, path: &str, offset: u64, len: u64) -> Result<Vec<u8>, ProviderError> {
+        let (tree_id, handle) = self.open(path, FILE_READ_DATA | FILE_READ_ATTRIBUTES | SYNCHRONIZE, FILE_OPEN, FILE_NON_DIRECTORY_FILE, 0).await?;
+        let conn = self.conn()?;
+        let end = offset.saturating_add(len).min(handle.size);
+        let mut out = Vec::with_capacity(end.saturating_sub(offset) as usize);
+        let mut pos = offset;
+        let result = loop {
+            if pos >= end {
+                break Ok(());
+            }
+            let chunk = conn.io_size(conn.max_read).min((end - pos) as u32);
+            match conn.read(tree_id, &handle.file_id, pos, chunk).await {
+                Ok(data) if data.is_empty() => break Ok(()),
+                Ok(data) => {
+                    pos += data.len() as u64;
+                    out.extend_from_slice(&data);
+                }
+                Err(e) => break Err(e),
+            }
+        };
+        conn.close(tree_id, &handle.file_id).await;
+        result.map(|_| out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transform_roundtrip() {
+        let key = [0x11u8; 16];
+        let msg = b"\xfeSMB hello from the client".to_vec();
+        for cipher in [CIPHER_AES128_GCM, CIPHER_AES128_CCM] {
+            let mut frame = seal(cipher, &key, 0x42, &msg).unwrap();
+            assert_eq!(&frame[..4], TRANSFORM_MAGIC);
+            assert_eq!(le64(&frame, 44).unwrap(), 0x42);
+            assert_eq!(unseal(cipher, &key, &frame).unwrap(), msg);
+
+            let last = frame.len() - 1;
+            frame[last] ^= 1;
+            assert!(unseal(cipher, &key, &frame).is_err());
+        }
+    }
+
+    #[test]
+    fn test_kdf_and_signing() {
+        // Test vectors published by Microsoft for SMB 3.0 ("Encryption in SMB 3.0:
+        // A protocol perspective") and SMB 3.1.1 ("SMB 3.1.1 Encryption in Windows 10")
+        let session_key: [u8; 16] = hex::decode("b4546771b515f766a86735532dd6c4f0").unwrap().try_into().unwrap();
+        assert_eq!(hex::encode(kdf(&session_key, b"SMB2AESCMAC\0", b"SmbSign\0")), "f773cd23c18fd1e08ee510cada7cf852");
+        assert_eq!(hex::encode(kdf(&session_key, b"SMB2AESCCM\0", b"ServerIn \0")), "261b72350558f2e9dcf613070383edbf");
+        assert_eq!(hex::encode(kdf(&session_key, b"SMB2AESCCM\0", b"ServerOut\0")), "8fe2b57ec34d2db5b1a9727f526bbdb5");
+
+        let session_key: [u8; 16] = hex::decode("270e1ba896585eeb7af3472d3b4c75a7").unwrap().try_into().unwrap();
+        let preauth_hash = hex::decode(
+            "0dd13628cc3ed218ef9df9772d436d0887ab9814bfae63a80aa845f36909db79\
+             28622dddad522d9751640a459762c5a9d6bb084cbb3ce6bdadef5d5bce3c6c01",
+        )
+        .unwrap();
+        assert_eq!(hex::encode(kdf(&session_key, b"SMBSigningKey\0", &preauth_hash)), "73fe7a9a77bef0bde49c650d8ccb5f76");
+        assert_eq!(hex::encode(kdf(&session_key, b"SMBC2SCipherKey\0", &preauth_hash)), "629bcbc54422a0f572b97f45989b6073");
+        assert_eq!(hex::encode(kdf(&session_key, b"SMBS2CCipherKey\0", &preauth_hash)), "e2af0dcefac68da71a0dfbd0d1350d74");
+
+        let signing = kdf(&session_key, b"SMBSigningKey\0", &preauth_hash);
+        let msg = vec![0xabu8; 80];
+        assert_ne!(sign_message(DIALECT_311, &signing, &msg), sign_message(DIALECT_210, &signing, &msg));
+    }
+
+    async fn signed_connection() -> Smb2Connection {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        Smb2Connection {
+            stream,
+            timeout: Duration::from_secs(5),
+            dialect: DIALECT_311,
+            server_security_mode: 0,
+            capabilities: 0,
+            cipher: CIPHER_AES128_GCM,
+            max_transact: 65536,
+            max_read: 65536,
+            max_write: 65536,
+            message_id: 0,
+            credits: 1,
+            session_id: 0x42,
+            keys: Some(SessionKeys { signing: [7u8; 16], encryption: Some(([1u8; 16], [2u8; 16])) }),
+            encrypt: false,
+            preauth_hash: [0u8; 64],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_responses_must_be_signed_or_sealed() {
+        let mut conn = signed_connection().await;
+        let (_, signed) = conn.build(ECHO, 0, &[4, 0, 0, 0], 0);
+        let resp = Smb2Response::parse(signed.clone()).unwrap();
+        assert!(conn.authenticate_response(&resp, false).is_ok());
+
+        // Clearing the flag must not bypass verification
+        let mut stripped = signed.clone();
+        stripped[16..20].fill(0);
+        stripped[48..64].fill(0);
+        assert!(conn.authenticate_response(&Smb2Response::parse(stripped).unwrap(), false).is_err());
+
+        let mut tampered = signed.clone();
+        tampered[HEADER_LEN] ^= 1;
+        assert!(conn.authenticate_response(&Smb2Response::parse(tampered).unwrap(), false).is_err());
+
+        // Session setup responses are checked during authentication instead
+        let (_, setup) = conn.build(SESSION_SETUP, 0, &[9, 0], 0);
+        assert!(conn.authenticate_response(&Smb2Response::parse(setup).unwrap(), false).is_ok());
+
+        // Once encrypting, plaintext frames are rejected even when signed
+        conn.encrypt = true;
+        assert!(conn.authenticate_response(&resp, false).is_err());
+        assert!(conn.authenticate_response(&resp, true).is_ok());
+    }
+
+    #[test]
+    fn test_directory_info_parsing() {
+        let mut buf = Vec::new();
+        for (i, name) in ["report.pdf", "Photos"].iter().enumerate() {
+            let encoded = utf16(name);
+            let len = align8(64 + encoded.len());
+            let mut record = vec![0u8; len];
+            let next = if i == 0 { len as u32 } else { 0 };
+            record[0..4].copy_from_slice(&next.to_le_bytes());
+            record[24..32].copy_from_slice(&132_000_000_000_000_000u64.to_le_bytes());
+            record[40..48].copy_from_slice(&(1234u64 * (1 - i as u64)).to_le_bytes());
+            let attrs = if i == 0 { FILE_ATTRIBUTE_NORMAL } else { FILE_ATTRIBUTE_DIRECTORY };
+            record[56..60].copy_from_slice(&attrs.to_le_bytes());
+            record[60..64].copy_from_slice(&(encoded.len() as u32).to_le_bytes());
+            record[64..64 + encoded.len()].copy_from_slice(&encoded);
+            buf.extend_from_slice(&record);
+        }
+
+        let entries = parse_directory_info(&buf).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "report.pdf");
+        assert_eq!(entries[0].size, 1234);
+        assert_eq!(entries[1].attributes & FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_DIRECTORY);
+        assert_eq!(format_filetime(132_000_000_000_000_000).as_deref(), Some("2019-04-17 18:40:00"));
+    }
+
+    #[test]
+    fn test_share_enum_parsing() {
+        let mut stub = Vec::new();
+        stub.put_u32(1); // level
+        stub.put_u32(1); // switch
+        stub.put_u32(0x20000); // container
+        stub.put_u32(2); // count
+        stub.put_u32(0x20004); // array
+        stub.put_u32(2); // max count
+        for (i, share_type) in [srvsvc::STYPE_DISKTREE, 3 | srvsvc::STYPE_SPECIAL].iter().enumerate() {
+            stub.put_u32(0x20008 + i as u32 * 8);
+            stub.put_u32(*share_type);
+            stub.put_u32(0x2000c + i as u32 * 8);
+        }
+        for s in ["data", "Team files", "IPC$", "IPC Service"] {
+            let units: Vec<u16> = s.encode_utf16().chain(std::iter::once(0)).collect();
+            stub.resize((stub.len() + 3) & !3, 0);
+            stub.put_u32(units.len() as u32);
+            stub.put_u32(0);
+            stub.put_u32(units.len() as u32);
+            for unit in units {
+                stub.put_u16(unit);
+            }
+        }
+        stub.resize((stub.len() + 3) & !3, 0);
+        stub.put_u32(2); // total entries
+        stub.put_u32(0); // resume handle
+        stub.put_u32(0); // WERROR_OK
+
+        let shares = srvsvc::parse_share_enum(&stub).unwrap();
+        assert_eq!(shares.len(), 2);
+        assert_eq!(shares[0].name, "data");
+        assert_eq!(shares[0].remark, "Team files");
+        assert_eq!(shares[1].name, "IPC$");
+
+        let request = srvsvc::share_enum("nas");
+        assert_eq!(le16(&request, 8).unwrap() as usize, request.len());
+        assert_eq!(srvsvc::bind().len(), 72);
+    }
+
+    /// Round trip against a real Samba server, plain and encrypted. For example:
+    /// `docker run -d -p 4445:445 dperson/samba -u "test;secret" -s "public;/share;yes;no;no;test"`
+    /// `SMB_TEST_HOST=127.0.0.1 SMB_TEST_PORT=4445 SMB_TEST_SHARE=public SMB_TEST_USER=test
+    ///  SMB_TEST_PASSWORD=secret cargo test samba_round_trip -- --ignored --nocapture`
+    #[tokio::test]
+    #[ignore]
+    async fn samba_round_trip() {
+        use crate::providers::ProviderConfig;
+
+        let env = |name: &str| std::env::var(name).ok();
+        let Some(host) = env("SMB_TEST_HOST") else {
+            eprintln!("SMB_TEST_HOST not set, skipping");
+            return;
+        };
+        let share = env("SMB_TEST_SHARE").unwrap_or_else(|| "public".to_string());
+        let config = |share: Option<&str>, encrypt: bool| {
+            let mut extra = HashMap::new();
+            if let Some(share) = share {
+                extra.insert("share".to_string(), share.to_string());
+            }
+            extra.insert("encrypt".to_string(), encrypt.to_string());
+            let config = ProviderConfig {
+                name: "samba".to_string(),
+                provider_type: ProviderType::Smb,
+                host: host.clone(),
+                port: env("SMB_TEST_PORT").and_then(|p| p.parse().ok()),
+                username: env("SMB_TEST_USER"),
+                password: env("SMB_TEST_PASSWORD"),
+                initial_path: None,
+                extra,
+            };
+            SmbConfig::from_provider_config(&config).unwrap()
+        };
+
+        // Share enumeration over srvsvc
+        let mut provider = SmbProvider::new(config(None, false));
+        provider.connect().await.unwrap();
+        let shares = provider.list("/").await.unwrap();
+        assert!(shares.iter().any(|s| s.is_dir && s.name.eq_ignore_ascii_case(&share)), "{:?}", shares);
+        provider.disconnect().await.unwrap();
+
+        let tmp = tempfile::tempdir().unwrap();
+        let local = tmp.path().join("upload.bin");
+        let data: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&local, &data).unwrap();
+
+        for encrypt in [false, true] {
+            let mut provider = SmbProvider::new(config(Some(&share), encrypt));
+            provider.connect().await.unwrap();
+            let dir = format!("/aeroftp-test-{}", encrypt);
+            let _ = provider.rmdir_recursive(&dir).await;
+
+            provider.mkdir(&dir).await.unwrap();
+            let file = format!("{}/data.bin", dir);
+            provider.upload(local.to_str().unwrap(), &file, None).await.unwrap();
+            assert_eq!(provider.stat(&file).await.unwrap().size, data.len() as u64);
+            assert_eq!(provider.download_to_bytes(&file).await.unwrap(), data);
+
+            let renamed = format!("{}/renamed.bin", dir);
+            provider.rename(&file, &renamed).await.unwrap();
+            let names: Vec<String> = provider.list(&dir).await.unwrap().into_iter().map(|e| e.name).collect();
+            assert_eq!(names, ["renamed.bin"]);
+
+            provider.delete(&renamed).await.unwrap();
+            provider.rmdir(&dir).await.unwrap();
+            assert!(!provider.exists(&dir).await.unwrap());
+            provider.disconnect().await.unwrap();
+        }
+    }
+}
@@ -0,0 +1,469 @@
+//! SSH Authentication
+//!
+//! Shared authentication chain for SSH-based connections. Methods are tried in
+//! this order, restricted to what the server advertises:
+//!
+//! 1. Configured identity files, each with its OpenSSH user certificate (`<key>-cert.pub`) when present
+//! 2. Configured password
+//! 3. ssh-agent identities (`SSH_AUTH_SOCK`, or the OpenSSH named pipe on Windows)
+//! 4. Default identity files (`~/.ssh/id_*`), only when no key or password is configured
+//! 5. keyboard-interactive (2FA/TOTP prompts are surfaced to the UI)
+//!
+//! The profile's own credentials go first: every rejected key counts against
+//! the server's `MaxAuthTries`, and an agent holding many keys could otherwise
+//! lock the user out before the configured key or password is ever offered.
+//!
+//! Servers that require several methods (e.g. a CA-signed certificate followed by
+//! a TOTP prompt) answer with partial success, and the chain continues with the
+//! methods the server still asks for.
+
+use super::ProviderError;
+use russh::client::{AuthResult, Handle, Handler, KeyboardInteractiveAuthResponse};
+use russh::keys::agent::client::AgentClient;
+use russh::keys::{self, Certificate, HashAlg, PrivateKey, PrivateKeyWithHashAlg};
+use russh::{MethodKind, MethodSet};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Identity files tried when none are configured and no password is set
+const DEFAULT_IDENTITIES: &[&str] = &["~/.ssh/id_ed25519", "~/.ssh/id_ecdsa", "~/.ssh/id_rsa"];
+
+/// How long a keyboard-interactive prompt waits for the user
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// Upper bound on keyboard-interactive rounds, so a misbehaving server cannot loop forever
+const MAX_PROMPT_ROUNDS: usize = 8;
+
+/// Credentials and preferences for one SSH login
+pub struct SshAuthOptions {
+    pub username: String,
+    pub password: Option<SecretString>,
+    /// Private key files, tried in order
+    pub identity_files: Vec<String>,
+    /// Passphrase for encrypted private keys
+    pub key_passphrase: Option<SecretString>,
+    /// Explicit OpenSSH certificate (otherwise `<key>-cert.pub` is picked up)
+    pub certificate_path: Option<String>,
+    /// Offer identities held by the running ssh-agent
+    pub use_agent: bool,
+    /// Allow keyboard-interactive, prompting the user for anything but the password
+    pub keyboard_interactive: bool,
+    /// Shown in keyboard-interactive prompts, e.g. "user@host"
+    pub label: String,
+}
+
+/// A single keyboard-interactive question
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyboardInteractiveField {
+    pub prompt: String,
+    /// Whether the answer may be shown while typing (false for secrets)
+    pub echo: bool,
+}
+
+/// Emitted to the frontend when the server asks keyboard-interactive questions.
+/// Answered through `ssh_keyboard_interactive_respond`.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyboardInteractivePrompt {
+    pub request_id: String,
+    /// "user@host" of the connection asking
+    pub label: String,
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<KeyboardInteractiveField>,
+}
+
+type PromptEmitter = Box<dyn Fn(&KeyboardInteractivePrompt) + Send + Sync>;
+type PromptReply = oneshot::Sender<Option<Vec<String>>>;
+
+/// Delivers prompts to the UI (registered once at app startup)
+static PROMPT_EMITTER: OnceLock<PromptEmitter> = OnceLock::new();
+
+/// Prompts awaiting an answer (request_id → reply channel; `None` cancels)
+static PENDING_PROMPTS: LazyLock<Mutex<HashMap<String, PromptReply>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Register the function that forwards keyboard-interactive prompts to the UI
+pub fn set_prompt_emitter(emitter: PromptEmitter) {
+    let _ = PROMPT_EMITTER.set(emitter);
+}
+
+/// Answer (or cancel with `None`) a pending keyboard-interactive prompt
+pub fn respond_to_prompt(request_id: &str, responses: Option<Vec<String>>) -> Result<(), String> {
+    let sender = PENDING_PROMPTS.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(request_id)
+        .ok_or_else(|| format!("No pending authentication prompt with id {}", request_id))?;
+    sender.send(responses)
+        .map_err(|_| "Authentication prompt is no longer waiting for an answer".to_string())
+}
+
+/// Forward a prompt to the UI and wait for the user's answers
+async fn ask_user(prompt: KeyboardInteractivePrompt) -> Result<Vec<String>, ProviderError> {
+    let emitter = PROMPT_EMITTER.get().ok_or_else(|| ProviderError::AuthenticationFailed(
+        "Server requested interactive authentication but no prompt handler is available".to_string()
+    ))?;
+
+    let request_id = prompt.request_id.clone();
+    let (tx, rx) = oneshot::channel();
+    PENDING_PROMPTS.lock().unwrap_or_else(|e| e.into_inner()).insert(request_id.clone(), tx);
+    emitter(&prompt);
+
+    let answer = tokio::time::timeout(PROMPT_TIMEOUT, rx).await;
+    PENDING_PROMPTS.lock().unwrap_or_else(|e| e.into_inner()).remove(&request_id);
+
+    match answer {
+        Ok(Ok(Some(responses))) => Ok(responses),
+        Ok(Ok(None)) | Ok(Err(_)) => Err(ProviderError::AuthenticationFailed(
+            "Interactive authentication cancelled".to_string()
+        )),
+        Err(_) => Err(ProviderError::AuthenticationFailed(
+            "Timed out waiting for interactive authentication".to_string()
+        )),
+    }
+}
+
+/// Outcome of a single authentication attempt
+enum Step {
+    Done,
+    /// Accepted, but the server requires more methods
+    Partial(MethodSet),
+    Rejected,
+}
+
+impl From<AuthResult> for Step {
+    fn from(result: AuthResult) -> Self {
+        match result {
+            AuthResult::Success => Step::Done,
+            AuthResult::Failure { remaining_methods, partial_success: true } => Step::Partial(remaining_methods),
+            AuthResult::Failure { .. } => Step::Rejected,
+        }
+    }
+}
+
+/// Expand a leading `~/` to the home directory
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    if let Some(stripped) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(stripped);
+        }
+    }
+    PathBuf::from(path)
+}
+
+/// Authenticate `handle` with every method allowed by `opts` until the server accepts
+pub async fn authenticate<H: Handler>(
+    handle: &mut Handle<H>,
+    opts: &SshAuthOptions,
+) -> Result<(), ProviderError> {
+    let auth_err = |e: russh::Error| ProviderError::AuthenticationFailed(format!("SSH authentication error: {}", e));
+
+    // "none" tells us which methods the server accepts
+    let mut methods = match handle.authenticate_none(&opts.username).await.map_err(auth_err)? {
+        AuthResult::Success => return Ok(()),
+        AuthResult::Failure { remaining_methods, .. } => remaining_methods,
+    };
+    let mut last_error: Option<String> = None;
+
+    if methods.contains(&MethodKind::PublicKey) && !opts.identity_files.is_empty() {
+        match authenticate_identities(handle, opts, &opts.identity_files, &mut last_error).await? {
+            Step::Done => return Ok(()),
+            Step::Partial(next) => methods = next,
+            Step::Rejected => {}
+        }
+    }
+
+    let password = opts.password.as_ref().filter(|p| !p.expose_secret().is_empty());
+    if let Some(password) = password {
+        if methods.contains(&MethodKind::Password) {
+            let result = handle.authenticate_password(&opts.username, password.expose_secret()).await
+                .map_err(auth_err)?;
+            match Step::from(result) {
+                Step::Done => return Ok(()),
+                Step::Partial(next) => methods = next,
+                Step::Rejected => {}
+            }
+        }
+    }
+
+    if opts.use_agent && methods.contains(&MethodKind::PublicKey) {
+        let rsa_hash = handle.best_supported_rsa_hash().await.ok().flatten().flatten();
+        match authenticate_agent(handle, &opts.username, rsa_hash).await {
+            Some(Step::Done) => return Ok(()),
+            Some(Step::Partial(next)) => methods = next,
+            Some(Step::Rejected) | None => {}
+        }
+    }
+
+    if methods.contains(&MethodKind::PublicKey) && opts.identity_files.is_empty() && password.is_none() {
+        let defaults: Vec<String> = DEFAULT_IDENTITIES.iter()
+            .filter(|p| expand_home(p).exists())
+            .map(|p| p.to_string())
+            .collect();
+        match authenticate_identities(handle, opts, &defaults, &mut last_error).await? {
+            Step::Done => return Ok(()),
+            Step::Partial(next) => methods = next,
+            Step::Rejected => {}
+        }
+    }
+
+    if opts.keyboard_interactive && methods.contains(&MethodKind::KeyboardInteractive) {
+        match authenticate_keyboard_interactive(handle, opts).await? {
+            Step::Done => return Ok(()),
+            Step::Partial(next) => methods = next,
+            Step::Rejected => {}
+        }
+    }
+
+    let offered: Vec<&str> = methods.iter().map(<&str>::from).collect();
+    let mut message = format!(
+        "Authentication rejected by server (server accepts: {})",
+        if offered.is_empty() { "none".to_string() } else { offered.join(", ") }
+    );
+    if let Some(e) = last_error {
+        message.push_str(&format!("; {}", e));
+    }
+    Err(ProviderError::AuthenticationFailed(message))
+}
+
+/// Try identity files in order (certificate first when one matches)
+async fn authenticate_identities<H: Handler>(
+    handle: &mut Handle<H>,
+    opts: &SshAuthOptions,
+    identities: &[String],
+    last_error: &mut Option<String>,
+) -> Result<Step, ProviderError> {
+    if identities.is_empty() {
+        return Ok(Step::Rejected);
+    }
+    let auth_err = |e: russh::Error| ProviderError::AuthenticationFailed(format!("Key authentication failed: {}", e));
+    let rsa_hash = handle.best_supported_rsa_hash().await.ok().flatten().flatten();
+
+    let explicit_cert = match &opts.certificate_path {
+        Some(path) => Some(keys::load_openssh_certificate(expand_home(path)).map_err(|e| {
+            ProviderError::AuthenticationFailed(format!("Failed to load certificate {}: {}", path, e))
+        })?),
+        None => None,
+    };
+
+    let passphrase = opts.key_passphrase.as_ref().map(|s| s.expose_secret().to_string());
+
+    for identity in identities {
+        let path = expand_home(identity);
+        tracing::info!("SSH: Trying identity {}", path.display());
+
+        let key = match keys::load_secret_key(&path, passphrase.as_deref()) {
+            Ok(key) => Arc::new(key),
+            Err(e) => {
+                tracing::warn!("SSH: Skipping identity {}: {}", path.display(), e);
+                *last_error = Some(format!("failed to load key {}: {}", identity, e));
+                continue;
+            }
+        };
+
+        if let Some(cert) = matching_certificate(&path, &key, explicit_cert.as_ref()) {
+            tracing::info!("SSH: Offering certificate {} for {}", cert.key_id(), path.display());
+            let result = handle.authenticate_openssh_cert(&opts.username, key.clone(), cert).await
+                .map_err(auth_err)?;
+            match Step::from(result) {
+                Step::Rejected => {}
+                step => return Ok(step),
+            }
+        }
+
+        let hash = if key.algorithm().is_rsa() { rsa_hash } else { None };
+        let result = handle.authenticate_publickey(&opts.username, PrivateKeyWithHashAlg::new(key, hash)).await
+            .map_err(auth_err)?;
+        match Step::from(result) {
+            Step::Rejected => {}
+            step => return Ok(step),
+        }
+    }
+
+    Ok(Step::Rejected)
+}
+
+/// Find the certificate for `key`: the explicitly configured one, or `<key>-cert.pub`
+fn matching_certificate(key_path: &Path, key: &PrivateKey, explicit: Option<&Certificate>) -> Option<Certificate> {
+    let key_data = key.public_key().key_data().clone();
+
+    if let Some(cert) = explicit.filter(|c| *c.public_key() == key_data) {
+        return Some(cert.clone());
+    }
+
+    let mut cert_path = key_path.as_os_str().to_owned();
+    cert_path.push("-cert.pub");
+    let cert_path = PathBuf::from(cert_path);
+    if !cert_path.exists() {
+        return None;
+    }
+    match keys::load_openssh_certificate(&cert_path) {
+        Ok(cert) => (*cert.public_key() == key_data).then_some(cert),
+        Err(e) => {
+            tracing::warn!("SSH: Ignoring certificate {}: {}", cert_path.display(), e);
+            None
+        }
+    }
+}
+
+/// Offer every identity held by the local ssh-agent.
+/// Returns `None` when no agent is reachable or it holds no accepted key.
+async fn authenticate_agent<H: Handler>(
+    handle: &mut Handle<H>,
+    username: &str,
+    rsa_hash: Option<HashAlg>,
+) -> Option<Step> {
+    #[cfg(unix)]
+    let agent = AgentClient::connect_env().await.map(|a| a.dynamic());
+    #[cfg(windows)]
+    let agent = AgentClient::connect_named_pipe(
+        std::env::var("SSH_AUTH_SOCK").unwrap_or_else(|_| r"\\.\pipe\openssh-ssh-agent".to_string())
+    ).await.map(|a| a.dynamic());
+
+    let mut agent = match agent {
+        Ok(agent) => agent,
+        Err(e) => {
+            tracing::debug!("SSH: No ssh-agent available: {}", e);
+            return None;
+        }
+    };
+
+    let identities = match agent.request_identities().await {
+        Ok(ids) => ids,
+        Err(e) => {
+            tracing::warn!("SSH: Failed to list ssh-agent identities: {}", e);
+            return None;
+        }
+    };
+    tracing::info!("SSH: ssh-agent offers {} identities", identities.len());
+
+    for key in identities {
+        let hash = if key.algorithm().is_rsa() { rsa_hash } else { None };
+        // Boxed as `Send` explicitly: rustc cannot prove the agent signer future
+        // is Send for all lifetimes when it is awaited inside `async_trait` methods
+        let attempt: Pin<Box<dyn Future<Output = _> + Send + '_>> =
+            Box::pin(handle.authenticate_publickey_with(username, key, hash, &mut agent));
+        match attempt.await {
+            Ok(result) => match Step::from(result) {
+                Step::Rejected => {}
+                step => return Some(step),
+            },
+            Err(e) => tracing::warn!("SSH: ssh-agent signing failed: {}", e),
+        }
+    }
+    None
+}
+
+/// Run keyboard-interactive rounds. A lone hidden "Password:" question is answered
+/// with the configured password; anything else (OTP codes, PINs) goes to the user.
+async fn authenticate_keyboard_interactive<H: Handler>(
+    handle: &mut Handle<H>,
+    opts: &SshAuthOptions,
+) -> Result<Step, ProviderError> {
+    let auth_err = |e: russh::Error| ProviderError::AuthenticationFailed(format!("Interactive authentication failed: {}", e));
+    let mut password_used = false;
+
+    let mut response = handle.authenticate_keyboard_interactive_start(&opts.username, None).await
+        .map_err(auth_err)?;
+
+    for _ in 0..MAX_PROMPT_ROUNDS {
+        let (name, instructions, prompts) = match response {
+            KeyboardInteractiveAuthResponse::Success => return Ok(Step::Done),
+            KeyboardInteractiveAuthResponse::Failure { remaining_methods, partial_success } => {
+                return Ok(if partial_success { Step::Partial(remaining_methods) } else { Step::Rejected });
+            }
+            KeyboardInteractiveAuthResponse::InfoRequest { name, instructions, prompts } => (name, instructions, prompts),
+        };
+
+        let password = opts.password.as_ref().filter(|p| !p.expose_secret().is_empty());
+        let answers = if prompts.is_empty() {
+            Vec::new()
+        } else if let (false, Some(password), [only]) = (password_used, password, prompts.as_slice()) {
+            if !only.echo && only.prompt.to_lowercase().contains("password") {
+                password_used = true;
+                vec![password.expose_secret().to_string()]
+            } else {
+                ask_user(build_prompt(opts, name, instructions, &prompts)).await?
+            }
+        } else {
+            ask_user(build_prompt(opts, name, instructions, &prompts)).await?
+        };
+
+        if answers.len() != prompts.len() {
+            return Err(ProviderError::AuthenticationFailed(format!(
+                "Expected {} answers for interactive authentication, got {}",
+                prompts.len(), answers.len()
+            )));
+        }
+
+        response = handle.authenticate_keyboard_interactive_respond(answers).await
+            .map_err(auth_err)?;
+    }
+
+    Err(ProviderError::AuthenticationFailed(
+        "Too many interactive authentication rounds".to_string()
+    ))
+}
+
+fn build_prompt(
+    opts: &SshAuthOptions,
+    name: String,
+    instructions: String,
+    prompts: &[russh::client::Prompt],
+) -> KeyboardInteractivePrompt {
+    KeyboardInteractivePrompt {
+        request_id: uuid::Uuid::new_v4().to_string(),
+        label: opts.label.clone(),
+        name,
+        instructions,
+        prompts: prompts.iter().map(|p| KeyboardInteractiveField {
+            prompt: p.prompt.clone(),
+            echo: p.echo,
+        }).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_home() {
+        assert_eq!(expand_home("/etc/ssh/key"), PathBuf::from("/etc/ssh/key"));
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(expand_home("~/.ssh/id_ed25519"), home.join(".ssh/id_ed25519"));
+        }
+    }
+
+    #[test]
+    fn test_respond_to_unknown_prompt() {
+        assert!(respond_to_prompt("missing", Some(vec![])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_prompt_roundtrip() {
+        set_prompt_emitter(Box::new(|prompt| {
+            let id = prompt.request_id.clone();
+            let answers = prompt.prompts.iter().map(|p| format!("answer:{}", p.prompt)).collect();
+            std::thread::spawn(move || {
+                // The prompt is registered before the emitter runs
+                let _ = respond_to_prompt(&id, Some(answers));
+            });
+        }));
+        let prompt = KeyboardInteractivePrompt {
+            request_id: "test-roundtrip".to_string(),
+            label: "user@host".to_string(),
+            name: String::new(),
+            instructions: String::new(),
+            prompts: vec![KeyboardInteractiveField { prompt: "Verification code:".to_string(), echo: false }],
+        };
+        let answers = ask_user(prompt).await.unwrap();
+        assert_eq!(answers, vec!["answer:Verification code:".to_string()]);
+    }
+}
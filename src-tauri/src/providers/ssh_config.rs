@@ -0,0 +1,372 @@
+//! OpenSSH client configuration (`~/.ssh/config`)
+//!
+//! Resolves a host alias the way `ssh <alias>` does: `Host` blocks are matched
+//! in file order (wildcards and `!negation` supported), the first value seen for
+//! a keyword wins, and `IdentityFile` / `CertificateFile` accumulate. `Include`
+//! is followed (relative paths are resolved against `~/.ssh`). `Match` blocks
+//! other than `Match all` cannot be evaluated here and are skipped.
+//!
+//! Only the keywords AeroFTP acts on are kept: `HostName`, `User`, `Port`,
+//! `IdentityFile`, `CertificateFile`, `IdentitiesOnly`, `ProxyJump` and `ProxyCommand`.
+
+use std::path::{Path, PathBuf};
+
+use super::glob::wildcard_match;
+
+/// Nesting limit for `Include`, mirroring OpenSSH's
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Settings resolved for one host alias
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SshHostConfig {
+    /// The alias that was looked up
+    pub alias: String,
+    /// Real host to connect to (defaults to the alias)
+    pub host_name: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// `IdentityFile` entries with `~` and `%` tokens expanded
+    pub identity_files: Vec<String>,
+    pub certificate_files: Vec<String>,
+    /// `IdentitiesOnly yes`: do not offer ssh-agent keys
+    pub identities_only: bool,
+    /// Raw `ProxyJump` value (`[user@]host[:port]`, comma-separated)
+    pub proxy_jump: Option<String>,
+    /// `ProxyCommand` with `%` tokens expanded
+    pub proxy_command: Option<String>,
+}
+
+/// One `keyword value` line together with the `Host` patterns it sits under
+#[derive(Debug, Clone)]
+struct Directive {
+    /// `None` applies to every host (top of file or `Match all`)
+    patterns: Option<Vec<String>>,
+    /// Inside a `Match` block that cannot be evaluated
+    skipped: bool,
+    keyword: String,
+    args: Vec<String>,
+}
+
+impl SshHostConfig {
+    /// Resolve `alias` against `~/.ssh/config`. Returns defaults when the file is missing.
+    pub fn resolve(alias: &str) -> Self {
+        let Some(ssh_dir) = dirs::home_dir().map(|h| h.join(".ssh")) else {
+            return Self::defaults(alias);
+        };
+        match std::fs::read_to_string(ssh_dir.join("config")) {
+            Ok(content) => Self::resolve_in(&content, alias, &ssh_dir),
+            Err(_) => Self::defaults(alias),
+        }
+    }
+
+    /// Resolve `alias` against config text; `Include` paths are relative to `base_dir`
+    pub fn resolve_in(content: &str, alias: &str, base_dir: &Path) -> Self {
+        let mut directives = Vec::new();
+        parse_into(content, base_dir, None, 0, &mut directives);
+
+        let mut cfg = Self::defaults(alias);
+        let mut host_name: Option<String> = None;
+        let mut proxy_jump: Option<String> = None;
+        let mut proxy_command: Option<String> = None;
+        let mut identities_only: Option<bool> = None;
+
+        for d in &directives {
+            if d.skipped || d.args.is_empty() {
+                continue;
+            }
+            if let Some(patterns) = &d.patterns {
+                if !host_matches(patterns, alias) {
+                    continue;
+                }
+            }
+            let value = d.args.join(" ");
+            match d.keyword.as_str() {
+                "hostname" => { host_name.get_or_insert(value); }
+                "user" => { cfg.user.get_or_insert(value); }
+                "port" if cfg.port.is_none() => cfg.port = value.parse().ok(),
+                "identityfile" => cfg.identity_files.push(value),
+                "certificatefile" => cfg.certificate_files.push(value),
+                "identitiesonly" => { identities_only.get_or_insert(value.eq_ignore_ascii_case("yes")); }
+                "proxyjump" => { proxy_jump.get_or_insert(value); }
+                "proxycommand" => { proxy_command.get_or_insert(value); }
+                _ => {}
+            }
+        }
+
+        cfg.host_name = host_name
+            .map(|h| expand_tokens(&h, &cfg, alias))
+            .unwrap_or_else(|| alias.to_string());
+        cfg.identities_only = identities_only.unwrap_or(false);
+        // "none" explicitly disables a value set by a broader block
+        cfg.proxy_jump = proxy_jump.filter(|v| !v.eq_ignore_ascii_case("none"));
+        cfg.proxy_command = proxy_command
+            .filter(|v| !v.eq_ignore_ascii_case("none"))
+            .map(|v| expand_tokens(&v, &cfg, alias));
+        cfg.identity_files = cfg.identity_files.iter()
+            .filter(|v| !v.eq_ignore_ascii_case("none"))
+            .map(|v| expand_tokens(v, &cfg, alias))
+            .collect();
+        cfg.certificate_files = cfg.certificate_files.iter()
+            .filter(|v| !v.eq_ignore_ascii_case("none"))
+            .map(|v| expand_tokens(v, &cfg, alias))
+            .collect();
+        cfg
+    }
+
+    fn defaults(alias: &str) -> Self {
+        Self {
+            alias: alias.to_string(),
+            host_name: alias.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Local login name, used when neither the profile nor the config sets `User`
+pub(crate) fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "root".to_string())
+}
+
+/// Parse config text, appending directives. `inherited` carries the enclosing
+/// `Host` patterns into included files.
+fn parse_into(
+    content: &str,
+    base_dir: &Path,
+    inherited: Option<Vec<String>>,
+    depth: usize,
+    out: &mut Vec<Directive>,
+) {
+    let mut patterns = inherited;
+    let mut skipped = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((keyword, args)) = split_line(line) else { continue };
+
+        match keyword.as_str() {
+            "host" => {
+                patterns = Some(args);
+                skipped = false;
+            }
+            "match" => {
+                skipped = !(args.len() == 1 && args[0].eq_ignore_ascii_case("all"));
+                patterns = None;
+            }
+            "include" if !skipped => {
+                if depth >= MAX_INCLUDE_DEPTH {
+                    tracing::warn!("ssh config: Include nested too deeply, ignoring");
+                    continue;
+                }
+                for arg in &args {
+                    for path in expand_include(arg, base_dir) {
+                        if let Ok(included) = std::fs::read_to_string(&path) {
+                            parse_into(&included, base_dir, patterns.clone(), depth + 1, out);
+                        }
+                    }
+                }
+            }
+            _ => out.push(Directive {
+                patterns: patterns.clone(),
+                skipped,
+                keyword,
+                args,
+            }),
+        }
+    }
+}
+
+/// Split `Keyword value...` or `Keyword=value...` into a lowercase keyword and
+/// its arguments (double-quoted arguments may contain spaces)
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let split_at = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let keyword = line[..split_at].to_lowercase();
+    let rest = line[split_at..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim_start();
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+    for c in rest.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        args.push(current);
+    }
+    Some((keyword, args))
+}
+
+/// Expand an `Include` argument into existing files (`*` / `?` allowed in the file name)
+fn expand_include(arg: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let path = if let Some(stripped) = arg.strip_prefix("~/") {
+        match dirs::home_dir() {
+            Some(home) => home.join(stripped),
+            None => return Vec::new(),
+        }
+    } else if Path::new(arg).is_absolute() {
+        PathBuf::from(arg)
+    } else {
+        base_dir.join(arg)
+    };
+
+    let file_pattern = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+    if !file_pattern.contains(['*', '?']) {
+        return vec![path];
+    }
+    let Some(dir) = path.parent() else { return Vec::new() };
+    let mut matches: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries.flatten()
+                .filter(|e| wildcard_match(&file_pattern, &e.file_name().to_string_lossy()))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    matches.sort();
+    matches
+}
+
+/// OpenSSH host matching: any positive pattern must match and no `!pattern` may match
+fn host_matches(patterns: &[String], host: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(&negated.to_lowercase(), &host.to_lowercase()) {
+                return false;
+            }
+        } else if wildcard_match(&pattern.to_lowercase(), &host.to_lowercase()) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Expand `~/` and the `%` tokens OpenSSH supports in paths and commands
+fn expand_tokens(value: &str, cfg: &SshHostConfig, alias: &str) -> String {
+    let home = dirs::home_dir().map(|h| h.to_string_lossy().to_string()).unwrap_or_default();
+    let value = match value.strip_prefix("~/") {
+        Some(stripped) if !home.is_empty() => format!("{}/{}", home, stripped),
+        _ => value.to_string(),
+    };
+
+    let host = if cfg.host_name.is_empty() { alias } else { cfg.host_name.as_str() };
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('h') => out.push_str(host),
+            Some('n') => out.push_str(alias),
+            Some('p') => out.push_str(&cfg.port.unwrap_or(22).to_string()),
+            Some('r') => out.push_str(cfg.user.as_deref().unwrap_or(&local_user())),
+            Some('u') => out.push_str(&local_user()),
+            Some('d') => out.push_str(&home),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+# Jump-host setup
+Host bastion
+    HostName bastion.example.com
+    User jump
+    Port 2222
+
+Host prod-*  !prod-legacy
+    User deploy
+    ProxyJump bastion
+    IdentityFile ~/.ssh/prod_ed25519
+
+Host prod-db
+    HostName 10.0.0.5
+    User ignored-because-first-wins
+
+Host legacy
+    HostName=old.example.com
+    ProxyCommand ssh -W %h:%p bastion
+
+Host *
+    IdentityFile ~/.ssh/id_ed25519
+    User fallback
+"#;
+
+    fn resolve(alias: &str) -> SshHostConfig {
+        SshHostConfig::resolve_in(CONFIG, alias, Path::new("/nonexistent"))
+    }
+
+    #[test]
+    fn test_first_value_wins_and_identities_accumulate() {
+        let cfg = resolve("prod-db");
+        assert_eq!(cfg.host_name, "10.0.0.5");
+        assert_eq!(cfg.user.as_deref(), Some("deploy"));
+        assert_eq!(cfg.proxy_jump.as_deref(), Some("bastion"));
+        assert_eq!(cfg.identity_files.len(), 2);
+        assert!(cfg.identity_files[0].ends_with(".ssh/prod_ed25519"));
+        assert!(cfg.identity_files[1].ends_with(".ssh/id_ed25519"));
+    }
+
+    #[test]
+    fn test_negated_pattern() {
+        let cfg = resolve("prod-legacy");
+        assert_eq!(cfg.host_name, "prod-legacy");
+        assert_eq!(cfg.user.as_deref(), Some("fallback"));
+        assert!(cfg.proxy_jump.is_none());
+    }
+
+    #[test]
+    fn test_port_and_equals_syntax() {
+        assert_eq!(resolve("bastion").port, Some(2222));
+        let legacy = resolve("legacy");
+        assert_eq!(legacy.host_name, "old.example.com");
+        assert_eq!(legacy.proxy_command.as_deref(), Some("ssh -W old.example.com:22 bastion"));
+    }
+
+    #[test]
+    fn test_unknown_host_gets_defaults() {
+        let cfg = SshHostConfig::resolve_in("", "example.org", Path::new("/nonexistent"));
+        assert_eq!(cfg.host_name, "example.org");
+        assert!(cfg.port.is_none());
+        assert!(cfg.identity_files.is_empty());
+    }
+
+    #[test]
+    fn test_quoted_arguments() {
+        let (keyword, args) = split_line(r#"IdentityFile "/path with spaces/key""#).unwrap();
+        assert_eq!(keyword, "identityfile");
+        assert_eq!(args, vec!["/path with spaces/key".to_string()]);
+    }
+}
@@ -0,0 +1,443 @@
+//! File operations over SSH exec channels, for servers that refuse the SFTP subsystem
+//!
+//! Files move with `scp` or the rsync protocol; listings, renames and deletes
+//! run ordinary shell commands on the server (`find`, `mv`, `rm`, ...).
+
+use super::sftp::{format_permissions, parse_find_output};
+use super::ssh_pool::{shell_quote, ExecOutput, SshConnection};
+use super::{rsync, scp, PosixAttributes, ProviderError, RemoteEntry, SshTransferMode, Xattrs};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Output cap for small commands (`pwd`, `mkdir`, ...)
+const MAX_COMMAND_OUTPUT: usize = 64 * 1024;
+
+/// Output cap for directory listings with `find`
+const MAX_LIST_OUTPUT: usize = 16 * 1024 * 1024;
+
+const FIND_FORMAT: &str = "'%y\\t%s\\t%T@\\t%m\\t%p\\0'";
+
+/// Tool used to move file contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecTransfer {
+    Scp,
+    Rsync,
+}
+
+impl ExecTransfer {
+    pub fn name(self) -> &'static str {
+        match self {
+            ExecTransfer::Scp => "scp",
+            ExecTransfer::Rsync => "rsync",
+        }
+    }
+}
+
+pub struct ExecTransport {
+    conn: Arc<SshConnection>,
+    transfer: ExecTransfer,
+}
+
+/// Map a failed command's stderr to the closest provider error
+pub(super) fn command_error(output: &ExecOutput) -> ProviderError {
+    let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if message.contains("No such file") {
+        ProviderError::NotFound(message)
+    } else if message.contains("Permission denied") {
+        ProviderError::PermissionDenied(message)
+    } else if message.is_empty() {
+        ProviderError::ServerError(format!("Command failed with status {:?}", output.exit_status))
+    } else {
+        ProviderError::ServerError(message)
+    }
+}
+
+/// Error for a failed `find -printf`. BSD and busybox `find` reject `-printf`
+/// ("unknown primary", "unrecognized: -printf"); say so instead of passing that on.
+fn find_error(output: &ExecOutput) -> ProviderError {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("-printf") {
+        return ProviderError::NotSupported(
+            "Browsing in scp mode needs GNU find (-printf), which this server lacks. Use the rsync transfer mode instead".to_string(),
+        );
+    }
+    command_error(output)
+}
+
+fn entry_from_rsync(path: String, entry: rsync::RsyncEntry) -> RemoteEntry {
+    let is_dir = entry.is_dir();
+    RemoteEntry {
+        name: path.rsplit('/').next().unwrap_or(&path).to_string(),
+        path,
+        is_dir,
+        size: if is_dir { 0 } else { entry.size },
+        modified: chrono::DateTime::from_timestamp(entry.mtime, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string()),
+        permissions: Some(format_permissions(entry.mode, is_dir)),
+        owner: None,
+        group: None,
+        is_symlink: entry.is_symlink(),
+        link_target: entry.link_target,
+        mime_type: None,
+        metadata: Default::default(),
+        content_hash: None,
+    }
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+/// GNU `stat -c '%f %u %g %X %Y'`: raw mode in hex, uid, gid, atime, mtime
+fn parse_stat_output(output: &str) -> Option<PosixAttributes> {
+    let mut fields = output.split_whitespace();
+    let mode = u32::from_str_radix(fields.next()?, 16).ok()?;
+    let mut number = || fields.next().and_then(|f| f.parse::<u32>().ok());
+    Some(PosixAttributes {
+        mode: Some(mode & 0o7777),
+        uid: number(),
+        gid: number(),
+        atime: number(),
+        mtime: number(),
+    })
+}
+
+/// Shell commands applying `attrs` to `path` (`chmod`, `chown`, GNU `touch -d @`)
+fn attributes_command(path: &str, attrs: &PosixAttributes) -> Option<String> {
+    let quoted = shell_quote(path);
+    let mut commands = Vec::new();
+    if let Some(mode) = attrs.mode {
+        commands.push(format!("chmod {:o} -- {}", mode & 0o7777, quoted));
+    }
+    let owner = match (attrs.uid, attrs.gid) {
+        (Some(uid), Some(gid)) => Some(format!("{}:{}", uid, gid)),
+        (Some(uid), None) => Some(uid.to_string()),
+        (None, Some(gid)) => Some(format!(":{}", gid)),
+        (None, None) => None,
+    };
+    if let Some(owner) = owner {
+        commands.push(format!("chown {} -- {}", owner, quoted));
+    }
+    if let Some(atime) = attrs.atime {
+        commands.push(format!("touch -c -a -d @{} -- {}", atime, quoted));
+    }
+    if let Some(mtime) = attrs.mtime {
+        commands.push(format!("touch -c -m -d @{} -- {}", mtime, quoted));
+    }
+    if commands.is_empty() { None } else { Some(commands.join(" && ")) }
+}
+
+/// `getfattr -d` lists the `user.*` namespace only, which is all an
+/// unprivileged account can usually set on the other side
+pub(super) fn getfattr_command(path: &str) -> String {
+    format!("getfattr --absolute-names -d -e base64 -- {}", shell_quote(path))
+}
+
+/// Parse `getfattr -d -e base64` output (`name=0s<base64>`, `name` for empty values)
+pub(super) fn parse_getfattr_output(output: &[u8]) -> Xattrs {
+    let mut xattrs = Xattrs::new();
+    for line in String::from_utf8_lossy(output).lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, value) = line.split_once('=').unwrap_or((line, ""));
+        let value = if let Some(encoded) = value.strip_prefix("0s") {
+            BASE64.decode(encoded).unwrap_or_default()
+        } else if let Some(encoded) = value.strip_prefix("0x") {
+            hex::decode(encoded).unwrap_or_default()
+        } else {
+            value.trim_matches('"').as_bytes().to_vec()
+        };
+        xattrs.insert(name.to_string(), value);
+    }
+    xattrs
+}
+
+pub(super) fn setfattr_command(path: &str, xattrs: &Xattrs) -> Option<String> {
+    let quoted = shell_quote(path);
+    let commands: Vec<String> = xattrs.iter()
+        .map(|(name, value)| if value.is_empty() {
+            format!("setfattr -n {} -- {}", shell_quote(name), quoted)
+        } else {
+            format!("setfattr -n {} -v 0s{} -- {}", shell_quote(name), BASE64.encode(value), quoted)
+        })
+        .collect();
+    if commands.is_empty() { None } else { Some(commands.join(" && ")) }
+}
+
+impl ExecTransport {
+    /// Use the requested tool, or in `Auto` mode rsync when the server has it and scp otherwise
+    pub async fn open(conn: Arc<SshConnection>, mode: SshTransferMode) -> Result<Self, ProviderError> {
+        let transfer = match mode {
+            SshTransferMode::Scp => ExecTransfer::Scp,
+            SshTransferMode::Rsync => ExecTransfer::Rsync,
+            SshTransferMode::Auto | SshTransferMode::Sftp => {
+                let probe = conn.exec_output("command -v rsync; command -v scp", 4096).await?;
+                let found = String::from_utf8_lossy(&probe.stdout).to_string();
+                if found.lines().any(|l| l.trim_end().ends_with("rsync")) {
+                    ExecTransfer::Rsync
+                } else if found.lines().any(|l| l.trim_end().ends_with("scp")) {
+                    ExecTransfer::Scp
+                } else {
+                    return Err(ProviderError::ConnectionFailed(
+                        "Server refused the SFTP subsystem and has neither rsync nor scp".to_string(),
+                    ));
+                }
+            }
+        };
+        Ok(Self { conn, transfer })
+    }
+
+    pub fn transfer(&self) -> ExecTransfer {
+        self.transfer
+    }
+
+    async fn run(&self, command: &str) -> Result<ExecOutput, ProviderError> {
+        let output = self.conn.exec_output(command, MAX_COMMAND_OUTPUT).await?;
+        if output.success() { Ok(output) } else { Err(command_error(&output)) }
+    }
+
+    pub async fn home(&self) -> Result<String, ProviderError> {
+        let output = self.run("pwd").await?;
+        let home = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(if home.starts_with('/') { home } else { "/".to_string() })
+    }
+
+    pub async fn list(&self, path: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
+        if self.transfer == ExecTransfer::Rsync {
+            let entries = rsync::list(&self.conn, path).await?;
+            return Ok(entries.into_iter().map(|e| entry_from_rsync(join_path(path, &e.name), e)).collect());
+        }
+        // GNU find; scp-only servers without it cannot be browsed
+        let command = format!("find {} -mindepth 1 -maxdepth 1 -printf {}", shell_quote(path), FIND_FORMAT);
+        let output = self.conn.exec_output(&command, MAX_LIST_OUTPUT).await?;
+        if !output.success() {
+            return Err(find_error(&output));
+        }
+        Ok(parse_find_output(&output.stdout, usize::MAX))
+    }
+
+    pub async fn stat(&self, path: &str) -> Result<RemoteEntry, ProviderError> {
+        if self.transfer == ExecTransfer::Rsync {
+            return Ok(entry_from_rsync(path.to_string(), rsync::stat(&self.conn, path).await?));
+        }
+        let command = format!("find {} -maxdepth 0 -printf {}", shell_quote(path), FIND_FORMAT);
+        let output = self.conn.exec_output(&command, MAX_COMMAND_OUTPUT).await?;
+        if !output.success() {
+            return Err(find_error(&output));
+        }
+        parse_find_output(&output.stdout, 1).pop()
+            .ok_or_else(|| ProviderError::NotFound(path.to_string()))
+    }
+
+    pub async fn exists(&self, path: &str) -> Result<bool, ProviderError> {
+        let quoted = shell_quote(path);
+        let output = self.conn.exec_output(&format!("test -e {} || test -L {}", quoted, quoted), 0).await?;
+        Ok(output.success())
+    }
+
+    pub async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &str,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        match self.transfer {
+            ExecTransfer::Rsync => {
+                let stats = rsync::download(&self.conn, remote_path, Path::new(local_path), on_progress).await?;
+                tracing::info!("SFTP: rsync download complete: {} bytes sent, {} bytes reused", stats.literal, stats.matched);
+            }
+            ExecTransfer::Scp => {
+                // Like rsync: a failed transfer never leaves a truncated file at `local_path`
+                let partial = rsync::partial_path(Path::new(local_path));
+                let mut local_file = tokio::fs::File::create(&partial).await
+                    .map_err(|e| ProviderError::TransferFailed(format!("Failed to create local file: {}", e)))?;
+                let info = match scp::download(&self.conn, remote_path, &mut local_file, on_progress).await {
+                    Ok(info) => info,
+                    Err(e) => {
+                        drop(local_file);
+                        let _ = tokio::fs::remove_file(&partial).await;
+                        return Err(e);
+                    }
+                };
+                if let Some(secs) = info.mtime.and_then(|t| u64::try_from(t).ok()) {
+                    let local_file = local_file.into_std().await;
+                    let _ = local_file.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+                }
+                tokio::fs::rename(&partial, local_path).await
+                    .map_err(|e| ProviderError::TransferFailed(format!("Failed to move download into place: {}", e)))?;
+                tracing::info!("SFTP: scp download complete: {} bytes", info.size);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn upload(
+        &self,
+        local_path: &str,
+        remote_path: &str,
+        on_progress: Option<Box<dyn Fn(u64, u64) + Send>>,
+    ) -> Result<(), ProviderError> {
+        match self.transfer {
+            ExecTransfer::Rsync => {
+                let stats = rsync::upload(&self.conn, Path::new(local_path), remote_path, on_progress).await?;
+                tracing::info!("SFTP: rsync upload complete: {} bytes sent, {} bytes reused", stats.literal, stats.matched);
+            }
+            ExecTransfer::Scp => {
+                let mut local_file = tokio::fs::File::open(local_path).await
+                    .map_err(|e| ProviderError::TransferFailed(format!("Failed to open local file: {}", e)))?;
+                let metadata = local_file.metadata().await
+                    .map_err(|e| ProviderError::TransferFailed(format!("Failed to read local file: {}", e)))?;
+                #[cfg(unix)]
+                let mode = std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o777;
+                #[cfg(not(unix))]
+                let mode = 0o644;
+                let info = scp::ScpFileInfo { size: metadata.len(), mode, mtime: None };
+                scp::upload(&self.conn, remote_path, &mut local_file, info, on_progress).await?;
+                tracing::info!("SFTP: scp upload complete: {} bytes", info.size);
+            }
+        }
+        Ok(())
+    }
+
+    /// Whole file contents, refused when larger than `limit`
+    pub async fn read(&self, path: &str, limit: u64) -> Result<Vec<u8>, ProviderError> {
+        let cap = usize::try_from(limit).unwrap_or(usize::MAX).saturating_add(1);
+        let output = self.conn.exec_output(&format!("cat -- {}", shell_quote(path)), cap).await?;
+        if !output.success() {
+            return Err(command_error(&output));
+        }
+        if output.stdout.len() as u64 > limit {
+            return Err(ProviderError::TransferFailed(format!(
+                "File too large for in-memory download (over {:.0} MB)",
+                limit as f64 / 1_048_576.0,
+            )));
+        }
+        Ok(output.stdout)
+    }
+
+    pub async fn read_range(&self, path: &str, offset: u64, len: u64) -> Result<Vec<u8>, ProviderError> {
+        let command = format!("tail -c +{} -- {} | head -c {}", offset + 1, shell_quote(path), len);
+        let output = self.conn.exec_output(&command, len as usize).await?;
+        if !output.success() {
+            return Err(command_error(&output));
+        }
+        Ok(output.stdout)
+    }
+
+    pub async fn mkdir(&self, path: &str) -> Result<(), ProviderError> {
+        self.run(&format!("mkdir -- {}", shell_quote(path))).await.map(drop)
+    }
+
+    pub async fn delete(&self, path: &str) -> Result<(), ProviderError> {
+        self.run(&format!("rm -- {}", shell_quote(path))).await.map(drop)
+    }
+
+    pub async fn rmdir(&self, path: &str) -> Result<(), ProviderError> {
+        self.run(&format!("rmdir -- {}", shell_quote(path))).await.map(drop)
+    }
+
+    pub async fn rename(&self, from: &str, to: &str) -> Result<(), ProviderError> {
+        self.run(&format!("mv -- {} {}", shell_quote(from), shell_quote(to))).await.map(drop)
+    }
+
+    pub async fn chmod(&self, path: &str, mode: u32) -> Result<(), ProviderError> {
+        self.run(&format!("chmod {:o} -- {}", mode & 0o7777, shell_quote(path))).await.map(drop)
+    }
+
+    pub async fn read_link(&self, path: &str) -> Result<String, ProviderError> {
+        let output = self.run(&format!("readlink -- {}", shell_quote(path))).await?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim_end_matches('\n').to_string())
+    }
+
+    pub async fn symlink(&self, target: &str, link_path: &str) -> Result<(), ProviderError> {
+        self.run(&format!("ln -s -- {} {}", shell_quote(target), shell_quote(link_path))).await.map(drop)
+    }
+
+    pub async fn hardlink(&self, existing: &str, link_path: &str) -> Result<(), ProviderError> {
+        self.run(&format!("ln -- {} {}", shell_quote(existing), shell_quote(link_path))).await.map(drop)
+    }
+
+    pub async fn get_attributes(&self, path: &str) -> Result<PosixAttributes, ProviderError> {
+        let output = self.run(&format!("stat -L -c '%f %u %g %X %Y' -- {}", shell_quote(path))).await?;
+        parse_stat_output(&String::from_utf8_lossy(&output.stdout))
+            .ok_or_else(|| ProviderError::ServerError(format!("Unexpected stat output for {}", path)))
+    }
+
+    pub async fn set_attributes(&self, path: &str, attrs: &PosixAttributes) -> Result<(), ProviderError> {
+        match attributes_command(path, attrs) {
+            Some(command) => self.run(&command).await.map(drop),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn get_xattrs(&self, path: &str) -> Result<Xattrs, ProviderError> {
+        let output = self.run(&getfattr_command(path)).await?;
+        Ok(parse_getfattr_output(&output.stdout))
+    }
+
+    pub async fn set_xattrs(&self, path: &str, xattrs: &Xattrs) -> Result<(), ProviderError> {
+        match setfattr_command(path, xattrs) {
+            Some(command) => self.run(&command).await.map(drop),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_from_rsync() {
+        let entry = rsync::RsyncEntry {
+            name: "current".to_string(),
+            size: 7,
+            mtime: 1_700_000_000,
+            mode: 0o120777,
+            link_target: Some("releases/42".to_string()),
+        };
+        let remote = entry_from_rsync(join_path("/srv/app/", &entry.name), entry);
+        assert_eq!(remote.path, "/srv/app/current");
+        assert_eq!(remote.name, "current");
+        assert!(remote.is_symlink && !remote.is_dir);
+        assert_eq!(remote.link_target.as_deref(), Some("releases/42"));
+        assert_eq!(remote.modified.as_deref(), Some("2023-11-14 22:13:20"));
+    }
+
+    #[test]
+    fn test_attribute_commands() {
+        let attrs = parse_stat_output("81ed 1000 100 1700000001 1700000000\n").unwrap();
+        assert_eq!(attrs, PosixAttributes {
+            mode: Some(0o755), uid: Some(1000), gid: Some(100), atime: Some(1700000001), mtime: Some(1700000000),
+        });
+        let command = attributes_command("/srv/a b", &PosixAttributes { mode: Some(0o644), gid: Some(33), mtime: Some(5), ..Default::default() });
+        assert_eq!(command.as_deref(), Some("chmod 644 -- '/srv/a b' && chown :33 -- '/srv/a b' && touch -c -m -d @5 -- '/srv/a b'"));
+        assert_eq!(attributes_command("/x", &PosixAttributes::default()), None);
+    }
+
+    #[test]
+    fn test_getfattr_round_trip() {
+        let output = b"# file: /srv/app/current\nuser.origin=0saHR0cHM6Ly9leGFtcGxlLmNvbQ==\nuser.flag\nuser.hex=0x00ff\n\n";
+        let xattrs = parse_getfattr_output(output);
+        assert_eq!(xattrs["user.origin"], b"https://example.com");
+        assert_eq!(xattrs["user.flag"], b"");
+        assert_eq!(xattrs["user.hex"], [0x00, 0xff]);
+        let command = setfattr_command("/srv/f", &xattrs).unwrap();
+        assert!(command.starts_with("setfattr -n 'user.flag' -- '/srv/f' && setfattr -n 'user.hex' -v 0sAP8= -- '/srv/f'"));
+    }
+
+    #[test]
+    fn test_command_error() {
+        let output = |stderr: &str| ExecOutput { exit_status: Some(1), stdout: Vec::new(), stderr: stderr.as_bytes().to_vec() };
+        assert!(matches!(command_error(&output("rm: cannot remove 'x': No such file or directory\n")), ProviderError::NotFound(_)));
+        assert!(matches!(command_error(&output("mkdir: cannot create directory 'y': Permission denied")), ProviderError::PermissionDenied(_)));
+        assert!(matches!(command_error(&output("")), ProviderError::ServerError(m) if m.contains("Some(1)")));
+
+        // BSD and busybox find
+        assert!(matches!(find_error(&output("find: -printf: unknown primary or operator\n")), ProviderError::NotSupported(m) if m.contains("GNU find")));
+        assert!(matches!(find_error(&output("find: unrecognized: -printf\n")), ProviderError::NotSupported(_)));
+        assert!(matches!(find_error(&output("find: '/x': No such file or directory\n")), ProviderError::NotFound(_)));
+    }
+}
@@ -0,0 +1,447 @@
+//! Shared SSH connections (OpenSSH ControlMaster-style multiplexing)
+//!
+//! SFTP browsing, remote shells, exec channels and port forwards to the same
+//! `user@host:port` with the same credentials and route (jump hosts, proxy
+//! command, proxy) share one authenticated SSH session, so opening a terminal
+//! on a host that is already being browsed does not log in (or ask for a TOTP
+//! code) a second time. The pool only holds weak references: a connection
+//! closes when its last user drops it.
+//!
+//! The first user's transport settings (timeouts, compression) apply to the
+//! shared connection. Concurrent requests for the same key wait for the first
+//! login instead of starting their own. Channels on a shared connection run
+//! concurrently; only setting up a remote forward takes the handle exclusively.
+
+use super::ssh_auth::{self, SshAuthOptions};
+use super::ssh_tunnel::{self, SshEndpoint};
+use super::ProviderError;
+use russh::client::{Config, Handle, Handler, Msg, Session};
+use russh::keys::{self, known_hosts, PublicKey};
+use russh::{Channel, ChannelId, ChannelMsg, CryptoVec};
+use russh_sftp::client::{RawSftpSession, SftpSession};
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Weak};
+use tokio::sync::{mpsc, Mutex, RwLock};
+
+/// One slot per pool key; the async lock serializes logins to the same key
+type PoolSlot = Arc<Mutex<Weak<SshConnection>>>;
+
+static POOL: LazyLock<std::sync::Mutex<HashMap<String, PoolSlot>>> =
+    LazyLock::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// Receivers for server-initiated `forwarded-tcpip` channels, keyed by the
+/// (address, port) the server listens on
+type ForwardRegistry = std::sync::Mutex<HashMap<(String, u32), mpsc::UnboundedSender<Channel<Msg>>>>;
+
+/// SSH client handler shared by every pooled connection and its jump hosts
+pub struct PooledSshHandler {
+    host: String,
+    port: u16,
+    forwards: Arc<ForwardRegistry>,
+}
+
+impl Handler for PooledSshHandler {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &PublicKey,
+    ) -> Result<bool, Self::Error> {
+        match known_hosts::check_known_hosts(&self.host, self.port, server_public_key) {
+            Ok(true) => {
+                tracing::info!("SSH: Host key verified for {}", self.host);
+                Ok(true)
+            }
+            Ok(false) => {
+                // SEC-P1-06: Host not in known_hosts — reject here.
+                // Frontend must call sftp_check_host_key + sftp_accept_host_key first.
+                tracing::warn!(
+                    "SSH: Host key for {} not pre-approved via TOFU dialog — rejecting",
+                    self.host
+                );
+                Ok(false)
+            }
+            Err(keys::Error::KeyChanged { line }) => {
+                tracing::error!(
+                    "SSH: REJECTING connection to {} - host key changed at known_hosts line {} (possible MITM attack)",
+                    self.host, line
+                );
+                Ok(false)
+            }
+            Err(e) => {
+                // SEC: Reject on unknown errors — do not silently accept.
+                tracing::error!(
+                    "SSH: REJECTING connection to {} - known_hosts verification error: {}",
+                    self.host, e
+                );
+                Ok(false)
+            }
+        }
+    }
+
+    async fn server_channel_open_forwarded_tcpip(
+        &mut self,
+        channel: Channel<Msg>,
+        connected_address: &str,
+        connected_port: u32,
+        _originator_address: &str,
+        _originator_port: u32,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        let forwards = self.forwards.lock().unwrap_or_else(|e| e.into_inner());
+        match forwards.get(&(connected_address.to_string(), connected_port)) {
+            Some(tx) => { let _ = tx.send(channel); }
+            None => tracing::warn!(
+                "SSH: unexpected forwarded connection to {}:{}", connected_address, connected_port
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// An authenticated SSH session that hands out channels
+pub struct SshConnection {
+    key: String,
+    /// Shared for channel operations, exclusive only for `tcpip-forward`
+    handle: RwLock<Handle<PooledSshHandler>>,
+    /// ProxyJump hosts carrying the connection (kept alive with it)
+    _jump_handles: Vec<Handle<PooledSshHandler>>,
+    forwards: Arc<ForwardRegistry>,
+}
+
+impl SshConnection {
+    /// `user@host:port`
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub async fn is_closed(&self) -> bool {
+        self.handle.read().await.is_closed()
+    }
+
+    /// Session channel for a shell or a command
+    pub async fn open_session(&self) -> Result<Channel<Msg>, ProviderError> {
+        self.handle.read().await.channel_open_session().await
+            .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to open session channel: {}", e)))
+    }
+
+    /// SFTP subsystem on a new channel
+    pub async fn open_sftp(&self) -> Result<SftpSession, ProviderError> {
+        let channel = self.open_session().await?;
+        channel.request_subsystem(true, "sftp").await
+            .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to request SFTP subsystem: {}", e)))?;
+        SftpSession::new(channel.into_stream()).await
+            .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to create SFTP session: {}", e)))
+    }
+
+    /// SFTP subsystem on a new channel, without the high-level file API, for
+    /// issuing many READ/WRITE requests at once. `timeout_secs` bounds each request.
+    pub async fn open_raw_sftp(&self, timeout_secs: u64) -> Result<RawSftpSession, ProviderError> {
+        let channel = self.open_session().await?;
+        channel.request_subsystem(true, "sftp").await
+            .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to request SFTP subsystem: {}", e)))?;
+        let raw = RawSftpSession::new(channel.into_stream());
+        raw.set_timeout(timeout_secs).await;
+        raw.init().await
+            .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to create SFTP session: {}", e)))?;
+        Ok(raw)
+    }
+
+    /// Start `command` on a new session channel
+    pub async fn open_exec(&self, command: &str) -> Result<Channel<Msg>, ProviderError> {
+        let channel = self.open_session().await?;
+        channel.exec(true, command).await
+            .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to run remote command: {}", e)))?;
+        Ok(channel)
+    }
+
+    /// Run `command` to completion and collect its output. Output beyond
+    /// `max_output` bytes per stream is discarded.
+    pub async fn exec_output(&self, command: &str, max_output: usize) -> Result<ExecOutput, ProviderError> {
+        self.exec_with_input(command, &[], max_output).await
+    }
+
+    /// Like `exec_output`, feeding `input` to the command's stdin (then EOF)
+    pub async fn exec_with_input(&self, command: &str, input: &[u8], max_output: usize) -> Result<ExecOutput, ProviderError> {
+        let mut channel = self.open_exec(command).await?;
+        if !input.is_empty() {
+            channel.data(input).await
+                .map_err(|e| ProviderError::NetworkError(format!("Failed to send command input: {}", e)))?;
+        }
+        channel.eof().await
+            .map_err(|e| ProviderError::NetworkError(format!("Failed to close command input: {}", e)))?;
+        let mut output = ExecOutput::default();
+        while let Some(msg) = channel.wait().await {
+            match msg {
+                ChannelMsg::Data { data } => append_capped(&mut output.stdout, &data, max_output),
+                ChannelMsg::ExtendedData { data, .. } => append_capped(&mut output.stderr, &data, max_output),
+                ChannelMsg::ExitStatus { exit_status } => output.exit_status = Some(exit_status),
+                ChannelMsg::Close => break,
+                _ => {}
+            }
+        }
+        Ok(output)
+    }
+
+    /// `direct-tcpip` channel to `host:port`, as seen from the server
+    pub async fn open_direct_tcpip(
+        &self,
+        host: &str,
+        port: u16,
+        originator_address: &str,
+        originator_port: u16,
+    ) -> Result<Channel<Msg>, ProviderError> {
+        self.handle.read().await
+            .channel_open_direct_tcpip(host, port as u32, originator_address, originator_port as u32)
+            .await
+            .map_err(|e| ProviderError::ConnectionFailed(format!("Server could not connect to {}:{}: {}", host, port, e)))
+    }
+
+    /// Send data on a channel whose read half is owned elsewhere
+    pub async fn data(&self, channel_id: ChannelId, data: &[u8]) -> Result<(), ProviderError> {
+        self.handle.read().await.data(channel_id, CryptoVec::from(data)).await
+            .map_err(|_| ProviderError::NetworkError("SSH write error".to_string()))
+    }
+
+    /// Ask the server to listen on `address:port` (0 = server's choice).
+    /// Returns the bound port and the incoming connections; the forward is
+    /// cancelled when the returned value is dropped.
+    pub async fn forward_remote(self: &Arc<Self>, address: &str, port: u16) -> Result<RemoteForward, ProviderError> {
+        let bound = self.handle.write().await.tcpip_forward(address, port as u32).await
+            .map_err(|e| ProviderError::PermissionDenied(format!("Server refused to listen on {}:{}: {}", address, port, e)))?;
+        let bound = if port == 0 { bound as u16 } else { port };
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.forwards.lock().unwrap_or_else(|e| e.into_inner())
+            .insert((address.to_string(), bound as u32), tx);
+        Ok(RemoteForward { conn: self.clone(), address: address.to_string(), port: bound, incoming: rx })
+    }
+}
+
+/// Result of `SshConnection::exec_output`
+#[derive(Debug, Default)]
+pub struct ExecOutput {
+    /// `None` when the server closed the channel without reporting one
+    pub exit_status: Option<u32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl ExecOutput {
+    pub fn success(&self) -> bool {
+        self.exit_status == Some(0)
+    }
+}
+
+fn append_capped(buf: &mut Vec<u8>, data: &[u8], max: usize) {
+    let room = max.saturating_sub(buf.len());
+    buf.extend_from_slice(&data[..data.len().min(room)]);
+}
+
+/// Quote `value` as a single POSIX shell word
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// A server-side listener created by `SshConnection::forward_remote`
+pub struct RemoteForward {
+    conn: Arc<SshConnection>,
+    address: String,
+    port: u16,
+    incoming: mpsc::UnboundedReceiver<Channel<Msg>>,
+}
+
+impl RemoteForward {
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Next forwarded connection; `None` once the SSH connection is gone
+    pub async fn accept(&mut self) -> Option<Channel<Msg>> {
+        self.incoming.recv().await
+    }
+}
+
+impl Drop for RemoteForward {
+    fn drop(&mut self) {
+        self.conn.forwards.lock().unwrap_or_else(|e| e.into_inner())
+            .remove(&(self.address.clone(), self.port as u32));
+        let conn = self.conn.clone();
+        let address = std::mem::take(&mut self.address);
+        let port = self.port as u32;
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let _ = conn.handle.read().await.cancel_tcpip_forward(address, port).await;
+            });
+        }
+    }
+}
+
+/// Feed a length-prefixed field into the pool key digest
+fn hash_field(hasher: &mut Sha256, value: &[u8]) {
+    hasher.update((value.len() as u64).to_le_bytes());
+    hasher.update(value);
+}
+
+fn hash_auth(hasher: &mut Sha256, auth: &SshAuthOptions) {
+    hash_field(hasher, auth.username.as_bytes());
+    hash_field(hasher, auth.password.as_ref().map_or(&b""[..], |p| p.expose_secret().as_bytes()));
+    hash_field(hasher, auth.identity_files.join("\n").as_bytes());
+    hash_field(hasher, auth.certificate_path.as_deref().unwrap_or("").as_bytes());
+    hash_field(hasher, &[auth.use_agent as u8, auth.keyboard_interactive as u8]);
+}
+
+/// Pool key: `user@host:port` plus a digest of the credentials and the route.
+/// Profiles reaching the same account with another key or password, or through
+/// other jump hosts or proxies, get their own connection.
+fn pool_key(endpoint: &SshEndpoint, auth: &SshAuthOptions) -> String {
+    let mut hasher = Sha256::new();
+    hash_auth(&mut hasher, auth);
+    let route = &endpoint.route;
+    for hop in &route.jumps {
+        hash_field(&mut hasher, format!("{}:{}", hop.host, hop.port).as_bytes());
+        hash_auth(&mut hasher, &hop.auth);
+    }
+    hash_field(&mut hasher, route.proxy_command.as_deref().unwrap_or("").as_bytes());
+    if let Some(proxy) = &route.proxy {
+        hash_field(&mut hasher, format!("{:?}://{}:{}", proxy.kind, proxy.host, proxy.port).as_bytes());
+        hash_field(&mut hasher, proxy.username.as_deref().unwrap_or("").as_bytes());
+    }
+    let digest = hasher.finalize();
+    format!(
+        "{}@{}:{}#{}",
+        endpoint.username, endpoint.host, endpoint.port, hex::encode(&digest[..8])
+    )
+}
+
+/// Prune slots whose connection is gone (slots being connected are kept)
+fn prune(pool: &mut HashMap<String, PoolSlot>) {
+    pool.retain(|_, slot| slot.try_lock().map_or(true, |weak| weak.strong_count() > 0));
+}
+
+/// Get the shared connection for the endpoint's `user@host:port` with these
+/// credentials and route, connecting and authenticating if there is none yet.
+/// `config` applies only when a new connection is made.
+pub async fn connect(
+    endpoint: &SshEndpoint,
+    auth: &SshAuthOptions,
+    config: Config,
+) -> Result<Arc<SshConnection>, ProviderError> {
+    let key = format!("{}@{}:{}", endpoint.username, endpoint.host, endpoint.port);
+    let slot = {
+        let mut pool = POOL.lock().unwrap_or_else(|e| e.into_inner());
+        prune(&mut pool);
+        pool.entry(pool_key(endpoint, auth)).or_default().clone()
+    };
+    let mut slot = slot.lock().await;
+    if let Some(conn) = slot.upgrade() {
+        if !conn.is_closed().await {
+            tracing::info!("SSH: Reusing connection to {}", key);
+            return Ok(conn);
+        }
+    }
+
+    let forwards = Arc::new(ForwardRegistry::default());
+    let make_handler = |host: &str, port: u16| PooledSshHandler {
+        host: host.to_string(),
+        port,
+        forwards: forwards.clone(),
+    };
+    let (mut handle, jump_handles) = ssh_tunnel::connect(
+        Arc::new(config), &endpoint.host, endpoint.port, &endpoint.route, make_handler,
+    ).await?;
+
+    tracing::info!("SSH: Connection to {} established, authenticating...", key);
+    // Authenticate: configured keys → password → agent → default keys → keyboard-interactive
+    ssh_auth::authenticate(&mut handle, auth).await?;
+
+    let conn = Arc::new(SshConnection {
+        key,
+        handle: RwLock::new(handle),
+        _jump_handles: jump_handles,
+        forwards,
+    });
+    *slot = Arc::downgrade(&conn);
+    Ok(conn)
+}
+
+/// `user@host:port` of the connections currently open
+pub fn open_connections() -> Vec<String> {
+    let pool = POOL.lock().unwrap_or_else(|e| e.into_inner());
+    let mut keys: Vec<String> = pool.iter()
+        .filter(|(_, slot)| slot.try_lock().map_or(true, |weak| weak.strong_count() > 0))
+        .map(|(key, _)| key.rsplit_once('#').map_or(key.as_str(), |(label, _)| label).to_string())
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_prune_keeps_live_and_connecting_slots() {
+        let mut pool: HashMap<String, PoolSlot> = HashMap::new();
+        pool.insert("dead@a:22".to_string(), Arc::new(Mutex::new(Weak::new())));
+        let connecting: PoolSlot = Arc::new(Mutex::new(Weak::new()));
+        let _guard = connecting.lock().await;
+        pool.insert("busy@b:22".to_string(), connecting.clone());
+
+        prune(&mut pool);
+        assert_eq!(pool.keys().collect::<Vec<_>>(), vec!["busy@b:22"]);
+    }
+
+    fn auth(username: &str, identity: &str) -> SshAuthOptions {
+        SshAuthOptions {
+            username: username.to_string(),
+            password: None,
+            identity_files: vec![identity.to_string()],
+            key_passphrase: None,
+            certificate_path: None,
+            use_agent: false,
+            keyboard_interactive: false,
+            label: format!("{}@example.com", username),
+        }
+    }
+
+    fn endpoint(jumps: Vec<ssh_tunnel::JumpHop>) -> SshEndpoint {
+        SshEndpoint {
+            host: "example.com".to_string(),
+            port: 22,
+            username: "deploy".to_string(),
+            identity_files: Vec::new(),
+            certificate_path: None,
+            identities_only: false,
+            route: ssh_tunnel::SshRoute { jumps, ..Default::default() },
+        }
+    }
+
+    #[test]
+    fn test_pool_key_separates_credentials_and_routes() {
+        let base = pool_key(&endpoint(Vec::new()), &auth("deploy", "~/.ssh/id_a"));
+        assert!(base.starts_with("deploy@example.com:22#"));
+        assert_eq!(base, pool_key(&endpoint(Vec::new()), &auth("deploy", "~/.ssh/id_a")));
+
+        // Another key for the same account
+        assert_ne!(base, pool_key(&endpoint(Vec::new()), &auth("deploy", "~/.ssh/id_b")));
+
+        // A password instead of the key
+        let mut with_password = auth("deploy", "~/.ssh/id_a");
+        with_password.password = Some("hunter2".to_string().into());
+        assert_ne!(base, pool_key(&endpoint(Vec::new()), &with_password));
+
+        // Same credentials, reached through a jump host
+        let hop = ssh_tunnel::JumpHop { host: "bastion".to_string(), port: 22, auth: auth("jump", "~/.ssh/id_a") };
+        assert_ne!(base, pool_key(&endpoint(vec![hop]), &auth("deploy", "~/.ssh/id_a")));
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/srv/data"), "'/srv/data'");
+        assert_eq!(shell_quote("it's $HOME"), "'it'\\''s $HOME'");
+        assert_eq!(shell_quote(""), "''");
+    }
+}
@@ -0,0 +1,348 @@
+//! SSH transport setup: direct TCP, `ProxyCommand`, or `ProxyJump` chains
+//!
+//! Jump hosts are chained with `direct-tcpip` channels: each hop is connected
+//! and authenticated, then asked to open a channel to the next hop, and the SSH
+//! handshake for the next hop runs over that channel. The hop handles must
+//! stay alive for as long as the final connection is used. The first TCP
+//! connection may itself go through an HTTP or SOCKS5 proxy.
+
+use super::ssh_auth::{self, SshAuthOptions};
+use super::ssh_config::{local_user, SshHostConfig};
+use super::proxy::{self, ProxyConfig};
+use super::ProviderError;
+use russh::client::{self, Config, Handle, Handler};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+
+/// Upper bound on jump hosts, guarding against runaway configs
+const MAX_JUMP_HOPS: usize = 8;
+
+/// One intermediate host in a `ProxyJump` chain
+pub struct JumpHop {
+    pub host: String,
+    pub port: u16,
+    pub auth: SshAuthOptions,
+}
+
+/// How to reach the target SSH server
+#[derive(Default)]
+pub struct SshRoute {
+    /// Jump hosts, outermost first
+    pub jumps: Vec<JumpHop>,
+    /// Command whose stdin/stdout carry the connection (ignored when jumps are set, as in OpenSSH)
+    pub proxy_command: Option<String>,
+    /// HTTP/SOCKS5 proxy for the first TCP connection (unused with `proxy_command`)
+    pub proxy: Option<ProxyConfig>,
+}
+
+impl SshRoute {
+    /// Build the route described by a resolved `~/.ssh/config` entry.
+    /// Each jump host is itself resolved through the config for its
+    /// `HostName`, `User`, `Port` and identities.
+    pub fn from_host_config(cfg: &SshHostConfig) -> Result<Self, ProviderError> {
+        let mut jumps = Vec::new();
+        if let Some(spec) = &cfg.proxy_jump {
+            for hop in spec.split(',').map(str::trim).filter(|h| !h.is_empty()) {
+                jumps.push(parse_jump(hop)?);
+            }
+        }
+        if jumps.len() > MAX_JUMP_HOPS {
+            return Err(ProviderError::InvalidConfig(format!(
+                "ProxyJump chain has {} hops (maximum {})", jumps.len(), MAX_JUMP_HOPS
+            )));
+        }
+        Ok(Self {
+            jumps,
+            proxy_command: cfg.proxy_command.clone(),
+            proxy: None,
+        })
+    }
+}
+
+/// Where and as whom to connect, after merging profile settings with `~/.ssh/config`
+pub struct SshEndpoint {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// `IdentityFile` entries from the config (profile keys go first)
+    pub identity_files: Vec<String>,
+    pub certificate_path: Option<String>,
+    /// `IdentitiesOnly yes` was set
+    pub identities_only: bool,
+    pub route: SshRoute,
+}
+
+impl SshEndpoint {
+    /// Resolve a profile's host (possibly an ssh config alias). Like `ssh -p`/`-l`,
+    /// values set in the profile win, except that the default port 22 yields to a
+    /// configured `Port` and an empty username to a configured `User`.
+    pub fn resolve(
+        host: &str,
+        port: u16,
+        username: &str,
+        use_ssh_config: bool,
+        proxy: Option<ProxyConfig>,
+    ) -> Result<Self, ProviderError> {
+        if !use_ssh_config {
+            return Ok(Self {
+                host: host.to_string(),
+                port,
+                username: username.to_string(),
+                identity_files: Vec::new(),
+                certificate_path: None,
+                identities_only: false,
+                route: SshRoute { proxy, ..SshRoute::default() },
+            });
+        }
+
+        let cfg = SshHostConfig::resolve(host);
+        if cfg.host_name != host {
+            tracing::info!("SSH: {} resolved to {} via ~/.ssh/config", host, cfg.host_name);
+        }
+        let route = SshRoute { proxy, ..SshRoute::from_host_config(&cfg)? };
+        Ok(Self {
+            port: if port == 22 { cfg.port.unwrap_or(22) } else { port },
+            username: if username.is_empty() {
+                cfg.user.clone().unwrap_or_else(local_user)
+            } else {
+                username.to_string()
+            },
+            identity_files: cfg.identity_files,
+            certificate_path: cfg.certificate_files.into_iter().next(),
+            identities_only: cfg.identities_only,
+            host: cfg.host_name,
+            route,
+        })
+    }
+}
+
+/// Parse a `[user@]host[:port]` (or `ssh://[user@]host[:port]`) jump spec
+fn parse_jump(spec: &str) -> Result<JumpHop, ProviderError> {
+    parse_jump_with(spec, SshHostConfig::resolve)
+}
+
+/// [`parse_jump`] with the hop's host config looked up by `resolve`
+fn parse_jump_with(spec: &str, resolve: impl Fn(&str) -> SshHostConfig) -> Result<JumpHop, ProviderError> {
+    let spec = spec.strip_prefix("ssh://").unwrap_or(spec);
+    let (user, host_port) = match spec.rsplit_once('@') {
+        Some((user, rest)) => (Some(user.to_string()), rest),
+        None => (None, spec),
+    };
+
+    // [v6addr]:port, host:port or bare host
+    let (alias, port) = if let Some(rest) = host_port.strip_prefix('[') {
+        let (addr, after) = rest.split_once(']')
+            .ok_or_else(|| ProviderError::InvalidConfig(format!("Invalid ProxyJump host: {}", spec)))?;
+        (addr.to_string(), after.strip_prefix(':').map(str::to_string))
+    } else {
+        match host_port.split_once(':') {
+            Some((h, p)) => (h.to_string(), Some(p.to_string())),
+            None => (host_port.to_string(), None),
+        }
+    };
+    if alias.is_empty() {
+        return Err(ProviderError::InvalidConfig(format!("Invalid ProxyJump host: {}", spec)));
+    }
+    let port = port.map(|p| p.parse::<u16>()
+        .map_err(|_| ProviderError::InvalidConfig(format!("Invalid ProxyJump port: {}", p))))
+        .transpose()?;
+
+    let hop_cfg = resolve(&alias);
+    let username = user.or_else(|| hop_cfg.user.clone()).unwrap_or_else(local_user);
+    Ok(JumpHop {
+        port: port.or(hop_cfg.port).unwrap_or(22),
+        auth: SshAuthOptions {
+            label: format!("{}@{}", username, hop_cfg.host_name),
+            username,
+            // Never forward the target's password to intermediate hosts
+            password: None,
+            identity_files: hop_cfg.identity_files.clone(),
+            key_passphrase: None,
+            certificate_path: hop_cfg.certificate_files.first().cloned(),
+            use_agent: !hop_cfg.identities_only,
+            keyboard_interactive: true,
+        },
+        host: hop_cfg.host_name,
+    })
+}
+
+/// Connect to `host:port` along `route`. Jump hosts are authenticated here;
+/// authenticating the returned target handle is left to the caller.
+/// Returns the target handle and the jump handles that keep the tunnel open.
+pub async fn connect<H, F>(
+    config: Arc<Config>,
+    host: &str,
+    port: u16,
+    route: &SshRoute,
+    make_handler: F,
+) -> Result<(Handle<H>, Vec<Handle<H>>), ProviderError>
+where
+    H: Handler + Send + 'static,
+    H::Error: std::fmt::Display,
+    F: Fn(&str, u16) -> H,
+{
+    let connect_err = |target: &str, e: &dyn std::fmt::Display| {
+        ProviderError::ConnectionFailed(format!("SSH connection to {} failed: {}", target, e))
+    };
+
+    // First server on the path: the outermost jump host, or the target itself
+    let (first_host, first_port) = match route.jumps.first() {
+        Some(hop) => (hop.host.as_str(), hop.port),
+        None => (host, port),
+    };
+    let first_label = format!("{}:{}", first_host, first_port);
+
+    let mut current = if let (Some(command), true) = (&route.proxy_command, route.jumps.is_empty()) {
+        tracing::info!("SSH: Connecting to {} via ProxyCommand", first_label);
+        let stream = ProxyCommandStream::spawn(command)?;
+        client::connect_stream(config.clone(), stream, make_handler(first_host, first_port)).await
+            .map_err(|e| connect_err(&first_label, &e))?
+    } else {
+        let socket = proxy::connect_tcp(route.proxy.as_ref(), first_host, first_port).await
+            .map_err(|e| connect_err(&first_label, &e))?;
+        if config.nodelay {
+            let _ = socket.set_nodelay(true);
+        }
+        client::connect_stream(config.clone(), socket, make_handler(first_host, first_port)).await
+            .map_err(|e| connect_err(&first_label, &e))?
+    };
+
+    let mut jump_handles = Vec::with_capacity(route.jumps.len());
+    for (idx, hop) in route.jumps.iter().enumerate() {
+        ssh_auth::authenticate(&mut current, &hop.auth).await.map_err(|e| match e {
+            ProviderError::AuthenticationFailed(msg) => ProviderError::AuthenticationFailed(
+                format!("Jump host {}: {}", hop.host, msg)
+            ),
+            other => other,
+        })?;
+
+        let (next_host, next_port) = match route.jumps.get(idx + 1) {
+            Some(next) => (next.host.as_str(), next.port),
+            None => (host, port),
+        };
+        let next_label = format!("{}:{}", next_host, next_port);
+        tracing::info!("SSH: Tunnelling through {} to {}", hop.host, next_label);
+
+        let channel = current.channel_open_direct_tcpip(next_host, next_port as u32, "127.0.0.1", 0).await
+            .map_err(|e| ProviderError::ConnectionFailed(format!(
+                "Jump host {} could not open a tunnel to {}: {}", hop.host, next_label, e
+            )))?;
+        let next = client::connect_stream(config.clone(), channel.into_stream(), make_handler(next_host, next_port)).await
+            .map_err(|e| connect_err(&next_label, &e))?;
+
+        jump_handles.push(std::mem::replace(&mut current, next));
+    }
+
+    Ok((current, jump_handles))
+}
+
+/// A `ProxyCommand` child process used as the SSH transport (stdout = read, stdin = write).
+/// The process is killed when the stream is dropped.
+struct ProxyCommandStream {
+    _child: Child,
+    stdout: ChildStdout,
+    stdin: ChildStdin,
+}
+
+impl ProxyCommandStream {
+    fn spawn(command: &str) -> Result<Self, ProviderError> {
+        #[cfg(unix)]
+        let mut cmd = {
+            let mut c = tokio::process::Command::new("sh");
+            c.arg("-c").arg(command);
+            c
+        };
+        #[cfg(windows)]
+        let mut cmd = {
+            let mut c = tokio::process::Command::new("cmd");
+            c.arg("/C").arg(command);
+            c
+        };
+
+        let mut child = cmd
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| ProviderError::ConnectionFailed(format!("Failed to start ProxyCommand: {}", e)))?;
+
+        let stdout = child.stdout.take()
+            .ok_or_else(|| ProviderError::ConnectionFailed("ProxyCommand has no stdout".to_string()))?;
+        let stdin = child.stdin.take()
+            .ok_or_else(|| ProviderError::ConnectionFailed("ProxyCommand has no stdin".to_string()))?;
+        Ok(Self { _child: child, stdout, stdin })
+    }
+}
+
+impl AsyncRead for ProxyCommandStream {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stdout).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ProxyCommandStream {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.stdin).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stdin).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stdin).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jump_spec() {
+        // A fixture config instead of the developer's ~/.ssh/config
+        let config = "Host bastion\n  HostName bastion.internal\n  User jumper\n  Port 2222\n  IdentitiesOnly yes\n";
+        let dir = std::path::Path::new("/nonexistent");
+        let parse = |spec: &str| parse_jump_with(spec, |alias| SshHostConfig::resolve_in(config, alias, dir));
+
+        let hop = parse("ops@jump.example.com:2200").unwrap();
+        assert_eq!(hop.host, "jump.example.com");
+        assert_eq!(hop.auth.username, "ops");
+        assert_eq!(hop.port, 2200);
+        assert!(hop.auth.password.is_none());
+
+        let hop = parse("ssh://admin@[2001:db8::1]:22").unwrap();
+        assert_eq!(hop.host, "2001:db8::1");
+        assert_eq!(hop.auth.username, "admin");
+
+        // Alias, user and port come from the config unless the spec gives them
+        let hop = parse("bastion").unwrap();
+        assert_eq!(hop.host, "bastion.internal");
+        assert_eq!(hop.auth.username, "jumper");
+        assert_eq!(hop.port, 2222);
+        assert!(!hop.auth.use_agent);
+        let hop = parse("root@bastion:22").unwrap();
+        assert_eq!(hop.auth.username, "root");
+        assert_eq!(hop.port, 22);
+
+        assert!(parse("user@host:notaport").is_err());
+        assert!(parse("user@").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_proxy_command_stream() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        #[cfg(unix)]
+        {
+            let mut stream = ProxyCommandStream::spawn("cat").unwrap();
+            stream.write_all(b"SSH-2.0-test\r\n").await.unwrap();
+            let mut buf = [0u8; 14];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"SSH-2.0-test\r\n");
+        }
+    }
+}
@@ -0,0 +1,898 @@
+//! OpenStack Swift Provider
+//!
+//! Implements StorageProvider for OpenStack Swift object storage (OVH Public Cloud,
+//! Infomaniak, self-hosted clusters) using Keystone v3 password authentication.
+//!
+//! - Objects above 1 GB are uploaded as Static Large Objects: segments go to
+//!   `<container>_segments`, then a manifest is written under the object name
+//! - Versions are read from the archive container configured on the container
+//!   (`X-History-Location` or `X-Versions-Location`)
+//! - Server-side copy via `X-Copy-From`
+//!
+//! ## Limitations (documented)
+//! - SW-001: Only password authentication (no application credentials / tokens)
+//! - SW-002: Dynamic Large Objects are read but never created
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use md5::{Md5, Digest};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, debug};
+
+use super::{
+    StorageProvider, ProviderType, ProviderError, RemoteEntry, StorageInfo, FileVersion,
+    ContentHash, HashAlgorithm, sanitize_api_error, HttpRetryConfig, send_with_retry, read_full_async,
+};
+use super::types::SwiftConfig;
+
+/// SW-003: Objects above this size are uploaded as Static Large Objects (1 GB)
+const SLO_THRESHOLD: u64 = 1024 * 1024 * 1024;
+
+/// SW-003: Segment size for Static Large Objects (100 MB)
+const SEGMENT_SIZE: usize = 100 * 1024 * 1024;
+
+/// Re-authenticate when the Keystone token expires within this window
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 300;
+
+/// Maximum entries per container listing page (Swift default cap)
+const LIST_PAGE_SIZE: usize = 10_000;
+
+/// Content type Swift clients use for pseudo-directory marker objects
+const DIRECTORY_CONTENT_TYPE: &str = "application/directory";
+
+/// Entry of a JSON container listing: either an object or a pseudo-directory
+#[derive(Debug, Deserialize)]
+struct SwiftObject {
+    name: Option<String>,
+    subdir: Option<String>,
+    #[serde(default)]
+    bytes: u64,
+    last_modified: Option<String>,
+    content_type: Option<String>,
+    /// MD5 of the content, except for large objects where it hashes the segment ETags
+    hash: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeystoneResponse {
+    token: KeystoneToken,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeystoneToken {
+    expires_at: Option<String>,
+    #[serde(default)]
+    catalog: Vec<CatalogService>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogService {
+    #[serde(rename = "type")]
+    service_type: String,
+    #[serde(default)]
+    endpoints: Vec<CatalogEndpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogEndpoint {
+    interface: String,
+    region: Option<String>,
+    region_id: Option<String>,
+    url: String,
+}
+
+/// Percent-encode an object name, keeping `/` separators
+fn encode_name(name: &str) -> String {
+    name.split('/')
+        .map(|part| urlencoding::encode(part).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Prefix under which versioned containers archive old copies of `name`:
+/// the name length as three hex digits, the name, then `/`
+fn version_prefix(name: &str) -> String {
+    format!("{:03x}{}/", name.len(), name)
+}
+
+/// Pick the public object-store endpoint from a Keystone catalog
+fn object_store_url(catalog: &[CatalogService], region: Option<&str>) -> Option<String> {
+    catalog.iter()
+        .filter(|service| service.service_type == "object-store")
+        .flat_map(|service| service.endpoints.iter())
+        .filter(|ep| ep.interface == "public")
+        .find(|ep| match region {
+            Some(r) => ep.region.as_deref() == Some(r) || ep.region_id.as_deref() == Some(r),
+            None => true,
+        })
+        .map(|ep| ep.url.trim_end_matches('/').to_string())
+}
+
+/// OpenStack Swift Provider
+pub struct SwiftProvider {
+    config: SwiftConfig,
+    client: reqwest::Client,
+    connected: bool,
+    current_prefix: String,
+    /// Keystone token (`X-Subject-Token`)
+    token: String,
+    token_expires: Option<chrono::DateTime<chrono::Utc>>,
+    /// Account storage URL from the service catalog
+    storage_url: String,
+}
+
+impl SwiftProvider {
+    pub fn new(config: SwiftConfig) -> Self {
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
+            .connect_timeout(std::time::Duration::from_secs(30))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+
+        Self {
+            config,
+            client,
+            connected: false,
+            current_prefix: String::new(),
+            token: String::new(),
+            token_expires: None,
+            storage_url: String::new(),
+        }
+    }
+
+    /// Obtain a project-scoped Keystone v3 token and the object-store endpoint
+    async fn authenticate(&mut self) -> Result<(), ProviderError> {
+        let body = serde_json::json!({
+            "auth": {
+                "identity": {
+                    "methods": ["password"],
+                    "password": {
+                        "user": {
+                            "name": self.config.username,
+                            "domain": { "name": self.config.user_domain },
+                            "password": self.config.password.expose_secret(),
+                        }
+                    }
+                },
+                "scope": {
+                    "project": {
+                        "name": self.config.project,
+                        "domain": { "name": self.config.project_domain },
+                    }
+                }
+            }
+        });
+
+        let resp = self.client.post(format!("{}/auth/tokens", self.config.auth_url))
+            .json(&body)
+            .send().await
+            .map_err(|e| ProviderError::ConnectionFailed(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::AuthenticationFailed(
+                format!("Keystone auth failed (HTTP {}): {}", status.as_u16(), sanitize_api_error(&body))
+            ));
+        }
+
+        let token = resp.headers().get("x-subject-token")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| ProviderError::AuthenticationFailed("Keystone returned no token".to_string()))?;
+        let keystone: KeystoneResponse = resp.json().await
+            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+
+        self.storage_url = object_store_url(&keystone.token.catalog, self.config.region.as_deref())
+            .ok_or_else(|| ProviderError::InvalidConfig(match self.config.region {
+                Some(ref r) => format!("No public object-store endpoint in region '{}'", r),
+                None => "No public object-store endpoint in the service catalog".to_string(),
+            }))?;
+        self.token_expires = keystone.token.expires_at
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
+            .map(|t| t.with_timezone(&chrono::Utc));
+        self.token = token;
+        debug!("Swift token obtained, storage URL: {}", self.storage_url);
+        Ok(())
+    }
+
+    /// Refresh the Keystone token shortly before it expires
+    async fn ensure_token(&mut self) -> Result<(), ProviderError> {
+        if !self.connected {
+            return Err(ProviderError::NotConnected);
+        }
+        let expiring = self.token_expires.is_some_and(|exp| {
+            exp - chrono::Utc::now() < chrono::Duration::seconds(TOKEN_REFRESH_MARGIN_SECS)
+        });
+        if expiring {
+            debug!("Swift token about to expire, re-authenticating");
+            self.authenticate().await?;
+        }
+        Ok(())
+    }
+
+    fn container_url(&self, container: &str) -> String {
+        format!("{}/{}", self.storage_url, urlencoding::encode(container))
+    }
+
+    fn object_url(&self, container: &str, name: &str) -> String {
+        format!("{}/{}", self.container_url(container), encode_name(name))
+    }
+
+    fn segments_container(&self) -> String {
+        format!("{}_segments", self.config.container)
+    }
+
+    /// Send an authenticated request with retry for transient errors (429/5xx).
+    /// Only byte bodies can be retried; streaming uploads use `self.client` directly.
+    async fn send(&self, builder: reqwest::RequestBuilder) -> Result<reqwest::Response, ProviderError> {
+        let request = builder
+            .header("X-Auth-Token", &self.token)
+            .build()
+            .map_err(|e| ProviderError::NetworkError(format!("Failed to build request: {}", e)))?;
+        send_with_retry(&self.client, request, &HttpRetryConfig::default())
+            .await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))
+    }
+
+    fn resolve_object(&self, path: &str) -> String {
+        if path == "." || path.is_empty() {
+            self.current_prefix.clone()
+        } else if path.starts_with('/') {
+            path.trim_start_matches('/').to_string()
+        } else if self.current_prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}{}", self.current_prefix, path)
+        }
+    }
+
+    /// List a container, following `marker` pagination.
+    /// With `delimited`, objects below the next `/` collapse into `subdir` entries.
+    async fn list_objects(&self, container: &str, prefix: &str, delimited: bool) -> Result<Vec<SwiftObject>, ProviderError> {
+        let mut all = Vec::new();
+        let mut marker: Option<String> = None;
+
+        loop {
+            let mut url = format!("{}?format=json&limit={}&prefix={}",
+                self.container_url(container), LIST_PAGE_SIZE, urlencoding::encode(prefix));
+            if delimited {
+                url.push_str("&delimiter=/");
+            }
+            if let Some(ref m) = marker {
+                url.push_str(&format!("&marker={}", urlencoding::encode(m)));
+            }
+
+            let resp = self.send(self.client.get(&url)).await?;
+            if resp.status().as_u16() == 404 {
+                return Err(ProviderError::NotFound(format!("Container '{}'", container)));
+            }
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(ProviderError::ServerError(
+                    format!("List objects failed (HTTP {}): {}", status.as_u16(), sanitize_api_error(&body))
+                ));
+            }
+            // 204 No Content: empty container
+            if resp.status().as_u16() == 204 {
+                break;
+            }
+
+            let page: Vec<SwiftObject> = resp.json().await
+                .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+            let count = page.len();
+            marker = page.last().and_then(|o| o.name.clone().or_else(|| o.subdir.clone()));
+            all.extend(page);
+
+            if count < LIST_PAGE_SIZE || marker.is_none() {
+                break;
+            }
+        }
+
+        Ok(all)
+    }
+
+    /// Stream an object URL to a local file
+    async fn download_url(&self, url: &str, local_path: &str, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        let resp = self.send(self.client.get(url)).await?;
+
+        if resp.status().as_u16() == 404 {
+            return Err(ProviderError::NotFound(url.to_string()));
+        }
+        if !resp.status().is_success() {
+            return Err(ProviderError::TransferFailed(format!("Download failed: {}", resp.status())));
+        }
+
+        let total_size = resp.content_length().unwrap_or(0);
+        let mut stream = resp.bytes_stream();
+        let mut file = tokio::fs::File::create(local_path).await
+            .map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+
+        let mut bytes_received: u64 = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+            file.write_all(&chunk).await
+                .map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+            bytes_received += chunk.len() as u64;
+            if let Some(ref cb) = progress {
+                cb(bytes_received, total_size);
+            }
+        }
+        file.flush().await
+            .map_err(|e| ProviderError::TransferFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// HEAD an object. Only a 404 means it is missing; auth and server
+    /// errors are reported as such.
+    async fn head_object(&self, name: &str, path: &str) -> Result<reqwest::Response, ProviderError> {
+        let resp = self.send(self.client.head(self.object_url(&self.config.container, name))).await?;
+        match resp.status().as_u16() {
+            200..=299 => Ok(resp),
+            404 => Err(ProviderError::NotFound(path.to_string())),
+            401 => Err(ProviderError::AuthenticationFailed(format!("HEAD {}: {}", path, resp.status()))),
+            403 => Err(ProviderError::PermissionDenied(path.to_string())),
+            _ => Err(ProviderError::ServerError(format!("HEAD {} failed: {}", path, resp.status()))),
+        }
+    }
+
+    /// Copy an object inside the account. With `keep_manifest`, a Static Large
+    /// Object source is copied as a manifest referencing the same segments
+    /// instead of being concatenated into a plain object (limited to 5 GB).
+    async fn copy_object(&self, src_container: &str, src: &str, dest: &str, keep_manifest: bool) -> Result<(), ProviderError> {
+        let mut url = self.object_url(&self.config.container, dest);
+        if keep_manifest {
+            url.push_str("?multipart-manifest=get");
+        }
+        let copy_from = format!("/{}/{}", urlencoding::encode(src_container), encode_name(src));
+
+        let resp = self.send(
+            self.client.put(&url)
+                .header("X-Copy-From", copy_from)
+                .header(CONTENT_LENGTH, "0")
+        ).await?;
+
+        match resp.status().as_u16() {
+            200..=299 => Ok(()),
+            404 => Err(ProviderError::NotFound(src.to_string())),
+            _ => {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                Err(ProviderError::ServerError(
+                    format!("Copy failed (HTTP {}): {}", status.as_u16(), sanitize_api_error(&body))
+                ))
+            }
+        }
+    }
+
+    /// Delete one object. With `with_segments`, a Static Large Object manifest also
+    /// removes its segments (the parameter is ignored for plain objects).
+    async fn delete_object(&self, name: &str, with_segments: bool) -> Result<(), ProviderError> {
+        let mut url = self.object_url(&self.config.container, name);
+        if with_segments {
+            url.push_str("?multipart-manifest=delete");
+        }
+
+        let resp = self.send(self.client.delete(&url)).await?;
+        match resp.status().as_u16() {
+            200..=299 => Ok(()),
+            404 => Err(ProviderError::NotFound(name.to_string())),
+            _ => {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                Err(ProviderError::ServerError(
+                    format!("Delete failed (HTTP {}): {}", status.as_u16(), sanitize_api_error(&body))
+                ))
+            }
+        }
+    }
+
+    /// Archive container of a versioned container, if versioning is enabled
+    async fn archive_container(&self) -> Result<Option<String>, ProviderError> {
+        let resp = self.send(self.client.head(self.container_url(&self.config.container))).await?;
+        if !resp.status().is_success() {
+            return Err(ProviderError::ServerError(format!("Container HEAD failed: {}", resp.status())));
+        }
+        Ok(["x-history-location", "x-versions-location"].iter()
+            .find_map(|h| resp.headers().get(*h))
+            .and_then(|v| v.to_str().ok())
+            .map(|v| urlencoding::decode(v).map(|d| d.into_owned()).unwrap_or_else(|_| v.to_string())))
+    }
+
+    async fn require_archive(&self) -> Result<String, ProviderError> {
+        self.archive_container().await?.ok_or_else(|| ProviderError::NotSupported(
+            format!("Versioning is not enabled on container '{}'", self.config.container)
+        ))
+    }
+
+    /// Single PUT upload (objects up to `SLO_THRESHOLD`)
+    async fn upload_single(&self, local_path: &str, url: &str, file_len: u64, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        let file = tokio::fs::File::open(local_path).await
+            .map_err(ProviderError::IoError)?;
+        let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
+
+        // Streaming bodies are not retryable at this level — the caller can retry the entire upload
+        let resp = self.client.put(url)
+            .header("X-Auth-Token", &self.token)
+            .header(CONTENT_LENGTH, file_len)
+            .body(body)
+            .send().await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::TransferFailed(format!("Upload failed: {}", sanitize_api_error(&body))));
+        }
+
+        if let Some(ref cb) = progress {
+            cb(file_len, file_len);
+        }
+        Ok(())
+    }
+
+    /// SW-003: Static Large Object upload. Each segment is sent with its MD5 as
+    /// ETag so Swift verifies it; the manifest then lists the segments in order.
+    async fn upload_slo(&self, local_path: &str, name: &str, file_len: u64, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        let segments = self.segments_container();
+        let resp = self.send(self.client.put(self.container_url(&segments)).header(CONTENT_LENGTH, "0")).await?;
+        if !resp.status().is_success() {
+            return Err(ProviderError::TransferFailed(
+                format!("Cannot create segment container '{}': {}", segments, resp.status())
+            ));
+        }
+
+        // Same layout as python-swiftclient: <name>/slo/<timestamp>/<size>/<segment size>/<index>
+        let segment_prefix = format!("{}/slo/{}/{}/{}",
+            name, chrono::Utc::now().timestamp(), file_len, SEGMENT_SIZE);
+
+        let mut file = tokio::fs::File::open(local_path).await
+            .map_err(ProviderError::IoError)?;
+        let mut manifest = Vec::new();
+        let mut bytes_uploaded: u64 = 0;
+
+        loop {
+            let mut buf = vec![0u8; SEGMENT_SIZE];
+            let filled = read_full_async(&mut file, &mut buf).await?;
+            if filled == 0 {
+                break;
+            }
+            buf.truncate(filled);
+
+            let etag = hex::encode(Md5::digest(&buf));
+            let segment_name = format!("{}/{:08}", segment_prefix, manifest.len());
+            let resp = self.send(
+                self.client.put(self.object_url(&segments, &segment_name))
+                    .header("ETag", &etag)
+                    .body(buf)
+            ).await?;
+            if !resp.status().is_success() {
+                let body = resp.text().await.unwrap_or_default();
+                return Err(ProviderError::TransferFailed(
+                    format!("Segment upload failed: {}", sanitize_api_error(&body))
+                ));
+            }
+
+            manifest.push(serde_json::json!({
+                "path": format!("/{}/{}", segments, segment_name),
+                "etag": etag,
+                "size_bytes": filled,
+            }));
+            bytes_uploaded += filled as u64;
+            if let Some(ref cb) = progress {
+                cb(bytes_uploaded, file_len);
+            }
+        }
+
+        let url = format!("{}?multipart-manifest=put", self.object_url(&self.config.container, name));
+        let resp = self.send(
+            self.client.put(&url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(serde_json::Value::Array(manifest).to_string())
+        ).await?;
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::TransferFailed(
+                format!("Manifest upload failed: {}", sanitize_api_error(&body))
+            ));
+        }
+
+        debug!("SLO upload complete: {} ({} bytes)", name, file_len);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageProvider for SwiftProvider {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+
+    fn provider_type(&self) -> ProviderType { ProviderType::Swift }
+
+    fn display_name(&self) -> String {
+        format!("Swift:{}/{}", self.config.project, self.config.container)
+    }
+
+    fn is_connected(&self) -> bool { self.connected }
+
+    async fn connect(&mut self) -> Result<(), ProviderError> {
+        if self.config.container.is_empty() {
+            return Err(ProviderError::InvalidConfig("Swift container name is empty".to_string()));
+        }
+        info!("Swift connect: auth='{}', project='{}', container='{}'",
+            self.config.auth_url, self.config.project, self.config.container);
+
+        self.authenticate().await?;
+
+        let resp = self.send(self.client.head(self.container_url(&self.config.container))).await
+            .map_err(|e| ProviderError::ConnectionFailed(e.to_string()))?;
+        match resp.status().as_u16() {
+            200..=299 => {}
+            404 => return Err(ProviderError::NotFound(format!("Container '{}'", self.config.container))),
+            code => return Err(ProviderError::ConnectionFailed(format!("Container check failed: HTTP {}", code))),
+        }
+
+        self.connected = true;
+        self.current_prefix = String::new();
+        info!("Connected to Swift: {}/{}", self.storage_url, self.config.container);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ProviderError> {
+        self.connected = false;
+        self.token.clear();
+        Ok(())
+    }
+
+    async fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, ProviderError> {
+        self.ensure_token().await?;
+        let prefix = self.resolve_object(path);
+        let prefix = if prefix.is_empty() || prefix.ends_with('/') { prefix } else { format!("{}/", prefix) };
+
+        let objects = self.list_objects(&self.config.container, &prefix, true).await?;
+
+        Ok(objects.into_iter().filter_map(|obj| {
+            let (full, is_dir) = match (obj.subdir, obj.name) {
+                (Some(subdir), _) => (subdir, true),
+                (None, Some(name)) => {
+                    let is_dir = obj.content_type.as_deref() == Some(DIRECTORY_CONTENT_TYPE);
+                    (name, is_dir)
+                }
+                (None, None) => return None,
+            };
+            let full = full.trim_end_matches('/').to_string();
+            let name = full.strip_prefix(&prefix).unwrap_or(&full).to_string();
+            // The directory marker of the listed prefix itself
+            if name.is_empty() {
+                return None;
+            }
+            // Objects above the SLO threshold are manifests, whose hash is not a content MD5.
+            // Other tools segment smaller objects too and listings don't say which are
+            // manifests, so the hash is weak (`stat` checks the manifest headers)
+            let content_hash = obj.hash.as_deref()
+                .filter(|_| !is_dir && obj.bytes <= SLO_THRESHOLD)
+                .and_then(|h| ContentHash::hex(HashAlgorithm::Md5, h))
+                .map(ContentHash::weak);
+            Some(RemoteEntry {
+                name,
+                path: format!("/{}", full),
+                is_dir,
+                size: if is_dir { 0 } else { obj.bytes },
+                modified: obj.last_modified,
+                permissions: None, owner: None, group: None,
+                is_symlink: false, link_target: None,
+                mime_type: obj.content_type.filter(|_| !is_dir),
+                metadata: Default::default(),
+                content_hash,
+            })
+        }).collect())
+    }
+
+    async fn cd(&mut self, path: &str) -> Result<(), ProviderError> {
+        if path == ".." {
+            return self.cd_up().await;
+        }
+        if path == "/" {
+            self.current_prefix = String::new();
+            return Ok(());
+        }
+
+        let new_prefix = if path.starts_with('/') {
+            path.trim_start_matches('/').to_string()
+        } else if self.current_prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}{}/", self.current_prefix, path.trim_end_matches('/'))
+        };
+
+        self.current_prefix = if new_prefix.ends_with('/') || new_prefix.is_empty() {
+            new_prefix
+        } else {
+            format!("{}/", new_prefix)
+        };
+
+        Ok(())
+    }
+
+    async fn cd_up(&mut self) -> Result<(), ProviderError> {
+        let trimmed = self.current_prefix.trim_end_matches('/');
+        self.current_prefix = match trimmed.rfind('/') {
+            Some(pos) => format!("{}/", &trimmed[..pos]),
+            None => String::new(),
+        };
+        Ok(())
+    }
+
+    async fn pwd(&mut self) -> Result<String, ProviderError> {
+        if self.current_prefix.is_empty() {
+            Ok("/".to_string())
+        } else {
+            Ok(format!("/{}", self.current_prefix.trim_end_matches('/')))
+        }
+    }
+
+    async fn download(&mut self, remote_path: &str, local_path: &str, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_object(remote_path);
+        let url = self.object_url(&self.config.container, &name);
+        self.download_url(&url, local_path, progress).await
+    }
+
+    async fn download_to_bytes(&mut self, remote_path: &str) -> Result<Vec<u8>, ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_object(remote_path);
+        let resp = self.send(self.client.get(self.object_url(&self.config.container, &name))).await?;
+
+        if resp.status().as_u16() == 404 {
+            return Err(ProviderError::NotFound(remote_path.to_string()));
+        }
+        if !resp.status().is_success() {
+            return Err(ProviderError::TransferFailed(format!("Download failed: {}", resp.status())));
+        }
+
+        super::response_bytes_with_limit(resp, super::MAX_DOWNLOAD_TO_BYTES).await
+    }
+
+    /// - Files <= 1 GB: single PUT (streaming)
+    /// - Files > 1 GB: Static Large Object (100 MB segments + manifest)
+    async fn upload(&mut self, local_path: &str, remote_path: &str, progress: Option<Box<dyn Fn(u64, u64) + Send>>) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_object(remote_path);
+        let file_len = tokio::fs::metadata(local_path).await
+            .map_err(ProviderError::IoError)?
+            .len();
+
+        if file_len > SLO_THRESHOLD {
+            self.upload_slo(local_path, &name, file_len, progress).await
+        } else {
+            let url = self.object_url(&self.config.container, &name);
+            self.upload_single(local_path, &url, file_len, progress).await
+        }
+    }
+
+    /// Create a pseudo-directory marker so empty folders survive listing
+    async fn mkdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let name = format!("{}/", self.resolve_object(path).trim_end_matches('/'));
+        let resp = self.send(
+            self.client.put(self.object_url(&self.config.container, &name))
+                .header(CONTENT_TYPE, DIRECTORY_CONTENT_TYPE)
+                .header(CONTENT_LENGTH, "0")
+        ).await?;
+
+        if !resp.status().is_success() {
+            return Err(ProviderError::Other(format!("mkdir failed: {}", resp.status())));
+        }
+        Ok(())
+    }
+
+    async fn delete(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_object(path);
+        self.delete_object(&name, true).await
+    }
+
+    async fn rmdir(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.rmdir_recursive(path).await
+    }
+
+    /// Delete every object under the prefix, including directory markers
+    async fn rmdir_recursive(&mut self, path: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let prefix = format!("{}/", self.resolve_object(path).trim_end_matches('/'));
+        let objects = self.list_objects(&self.config.container, &prefix, false).await?;
+
+        for name in objects.into_iter().filter_map(|o| o.name) {
+            match self.delete_object(&name, true).await {
+                Ok(()) | Err(ProviderError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Rename via server-side copy + delete. Large objects are moved as their
+    /// manifest, so the segments are kept for the new name.
+    async fn rename(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let from_name = self.resolve_object(from);
+        let to_name = self.resolve_object(to);
+
+        self.copy_object(&self.config.container, &from_name, &to_name, true).await?;
+        self.delete_object(&from_name, false).await
+    }
+
+    async fn stat(&mut self, path: &str) -> Result<RemoteEntry, ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_object(path);
+        let resp = self.head_object(&name, path).await?;
+
+        let headers = resp.headers();
+        let header = |key: &str| headers.get(key).and_then(|v| v.to_str().ok()).map(str::to_string);
+        let mime_type = header("content-type");
+        let is_dir = mime_type.as_deref() == Some(DIRECTORY_CONTENT_TYPE);
+        let is_large = headers.contains_key("x-static-large-object")
+            || headers.contains_key("x-object-manifest");
+        let content_hash = header("etag")
+            .filter(|_| !is_dir && !is_large)
+            .and_then(|e| ContentHash::hex(HashAlgorithm::Md5, e.trim_matches('"')));
+
+        let name_only = name.trim_end_matches('/').rsplit('/').next().unwrap_or(&name).to_string();
+        Ok(RemoteEntry {
+            name: name_only,
+            path: format!("/{}", name.trim_end_matches('/')),
+            is_dir,
+            size: header("content-length").and_then(|s| s.parse().ok()).unwrap_or(0),
+            modified: header("last-modified"),
+            permissions: None, owner: None, group: None,
+            is_symlink: false, link_target: None,
+            mime_type: mime_type.filter(|_| !is_dir),
+            metadata: Default::default(),
+            content_hash,
+        })
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool, ProviderError> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(ProviderError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn size(&mut self, path: &str) -> Result<u64, ProviderError> {
+        Ok(self.stat(path).await?.size)
+    }
+
+    async fn keep_alive(&mut self) -> Result<(), ProviderError> {
+        self.ensure_token().await
+    }
+
+    async fn server_info(&mut self) -> Result<String, ProviderError> {
+        Ok(format!("OpenStack Swift: {}/{}", self.storage_url, self.config.container))
+    }
+
+    /// Account usage; `total` is 0 when no account quota is set
+    async fn storage_info(&mut self) -> Result<StorageInfo, ProviderError> {
+        self.ensure_token().await?;
+        let resp = self.send(self.client.head(&self.storage_url)).await?;
+        if !resp.status().is_success() {
+            return Err(ProviderError::ServerError(format!("Account HEAD failed: {}", resp.status())));
+        }
+
+        let header = |key: &str| resp.headers().get(key)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.parse::<u64>().ok());
+        let used = header("x-account-bytes-used").unwrap_or(0);
+        let total = header("x-account-meta-quota-bytes").unwrap_or(0);
+        Ok(StorageInfo { used, total, free: total.saturating_sub(used) })
+    }
+
+    fn supports_server_copy(&self) -> bool { true }
+
+    async fn server_copy(&mut self, from: &str, to: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let from_name = self.resolve_object(from);
+        let to_name = self.resolve_object(to);
+        // A full copy, so neither object depends on the other's segments
+        self.copy_object(&self.config.container, &from_name, &to_name, false).await
+    }
+
+    fn supports_checksum(&self) -> bool { true }
+
+    /// The ETag of plain objects is their MD5; Static Large Objects have none
+    async fn checksum(&mut self, path: &str) -> Result<std::collections::HashMap<String, String>, ProviderError> {
+        self.ensure_token().await?;
+        let name = self.resolve_object(path);
+        let resp = self.head_object(&name, path).await?;
+
+        let mut checksums = std::collections::HashMap::new();
+        let is_large = resp.headers().contains_key("x-static-large-object")
+            || resp.headers().contains_key("x-object-manifest");
+        if let Some(etag) = resp.headers().get("etag").and_then(|v| v.to_str().ok()) {
+            if !is_large {
+                checksums.insert("md5".to_string(), etag.trim_matches('"').to_string());
+            }
+        }
+        Ok(checksums)
+    }
+
+    fn transfer_optimization_hints(&self) -> super::TransferOptimizationHints {
+        super::TransferOptimizationHints {
+            supports_multipart: true,
+            multipart_threshold: SLO_THRESHOLD,
+            multipart_part_size: SEGMENT_SIZE as u64,
+            supports_server_checksum: true,
+            preferred_checksum_algo: Some("md5".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn supports_versions(&self) -> bool { true }
+
+    /// Archived copies, newest first. The version id is the archive timestamp.
+    async fn list_versions(&mut self, path: &str) -> Result<Vec<FileVersion>, ProviderError> {
+        self.ensure_token().await?;
+        let archive = self.require_archive().await?;
+        let name = self.resolve_object(path);
+        let prefix = version_prefix(&name);
+
+        let mut versions: Vec<FileVersion> = self.list_objects(&archive, &prefix, false).await?
+            .into_iter()
+            .filter_map(|obj| {
+                let id = obj.name?.strip_prefix(&prefix)?.to_string();
+                Some(FileVersion { id, modified: obj.last_modified, size: obj.bytes, modified_by: None })
+            })
+            .collect();
+        versions.reverse();
+        Ok(versions)
+    }
+
+    async fn download_version(&mut self, path: &str, version_id: &str, local_path: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let archive = self.require_archive().await?;
+        let name = self.resolve_object(path);
+        let url = self.object_url(&archive, &format!("{}{}", version_prefix(&name), version_id));
+        self.download_url(&url, local_path, None).await
+    }
+
+    /// Copy the archived version over the current object (which gets archived in turn)
+    async fn restore_version(&mut self, path: &str, version_id: &str) -> Result<(), ProviderError> {
+        self.ensure_token().await?;
+        let archive = self.require_archive().await?;
+        let name = self.resolve_object(path);
+        let archived = format!("{}{}", version_prefix(&name), version_id);
+        self.copy_object(&archive, &archived, &name, false).await?;
+        info!("Restored '{}' to version '{}'", name, version_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_endpoint_selection() {
+        let catalog: Vec<CatalogService> = serde_json::from_str(r#"[
+            {"type": "identity", "endpoints": [{"interface": "public", "region": "GRA", "url": "https://auth.example/v3"}]},
+            {"type": "object-store", "endpoints": [
+                {"interface": "internal", "region": "GRA", "url": "http://10.0.0.1/v1/AUTH_p"},
+                {"interface": "public", "region": "GRA", "url": "https://storage.gra.example/v1/AUTH_p"},
+                {"interface": "public", "region_id": "SBG", "region": null, "url": "https://storage.sbg.example/v1/AUTH_p/"}
+            ]}
+        ]"#).unwrap();
+
+        assert_eq!(object_store_url(&catalog, None).as_deref(), Some("https://storage.gra.example/v1/AUTH_p"));
+        assert_eq!(object_store_url(&catalog, Some("SBG")).as_deref(), Some("https://storage.sbg.example/v1/AUTH_p"));
+        assert_eq!(object_store_url(&catalog, Some("BHS")), None);
+    }
+
+    #[test]
+    fn test_object_naming() {
+        assert_eq!(encode_name("photos/2024 trip/a#1.jpg"), "photos/2024%20trip/a%231.jpg");
+        assert_eq!(version_prefix("docs/report.pdf"), "00fdocs/report.pdf/");
+    }
+}
@@ -0,0 +1,566 @@
+//! TLS trust options for FTPS and WebDAV
+//!
+//! Besides `verify_cert`, a profile may set in `extra`:
+//! - `ca_cert`: PEM bundle of additional trusted CA certificates (private CAs)
+//! - `client_cert`: credential store account holding a client identity for
+//!   mutual TLS, a JSON object with either `cert` + `key` (PEM) or `pkcs12`
+//!   (base64) + `password`
+//! - `cert_pin`: `sha256/<base64>` SPKI hash or `SHA256:<hex>` certificate fingerprint
+//!
+//! Certificates no CA vouches for can be approved once (trust on first use):
+//! the fingerprint is saved to `known_tls_hosts` and later connections to that
+//! host:port accept exactly that certificate, like SSH's `known_hosts`.
+
+use super::proxy::{self, ProxyConfig};
+use super::ProviderError;
+use base64::Engine;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::WebPkiSupportedAlgorithms;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// A pinned server certificate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertPin {
+    /// SHA-256 of the whole DER certificate
+    Certificate([u8; 32]),
+    /// SHA-256 of the SubjectPublicKeyInfo, which survives certificate renewal with the same key
+    Spki([u8; 32]),
+}
+
+impl CertPin {
+    /// Parse `SHA256:<hex>` (colons optional) or `sha256/<base64>`
+    pub fn parse(value: &str) -> Result<Self, ProviderError> {
+        let value = value.trim();
+        let invalid = || ProviderError::InvalidConfig(format!("Invalid certificate pin: {}", value));
+        let digest = |bytes: Vec<u8>| <[u8; 32]>::try_from(bytes).map_err(|_| invalid());
+
+        if let Some(b64) = value.strip_prefix("sha256/") {
+            let bytes = base64::engine::general_purpose::STANDARD.decode(b64).map_err(|_| invalid())?;
+            return Ok(Self::Spki(digest(bytes)?));
+        }
+        let hex_part = value.get(..7)
+            .filter(|p| p.eq_ignore_ascii_case("sha256:"))
+            .map_or(value, |_| &value[7..]);
+        let bytes = hex::decode(hex_part.replace(':', "")).map_err(|_| invalid())?;
+        Ok(Self::Certificate(digest(bytes)?))
+    }
+
+    /// Pin a certificate as a whole
+    pub fn for_certificate(der: &[u8]) -> Self {
+        Self::Certificate(Sha256::digest(der).into())
+    }
+
+    /// Whether the DER certificate `der` satisfies this pin
+    pub fn matches(&self, der: &[u8]) -> bool {
+        match self {
+            Self::Certificate(hash) => Sha256::digest(der).as_slice() == hash,
+            Self::Spki(hash) => spki_sha256(der).is_some_and(|h| &h == hash),
+        }
+    }
+}
+
+impl std::fmt::Display for CertPin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Certificate(hash) => write!(f, "SHA256:{}", colon_hex(hash)),
+            Self::Spki(hash) => write!(f, "sha256/{}", base64::engine::general_purpose::STANDARD.encode(hash)),
+        }
+    }
+}
+
+/// `AB:CD:...` rendering used by browsers and `openssl x509 -fingerprint`
+fn colon_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":")
+}
+
+/// SHA-256 of a certificate's SubjectPublicKeyInfo
+pub fn spki_sha256(der: &[u8]) -> Option<[u8; 32]> {
+    let cert = CertificateDer::from(der);
+    let parsed = webpki::EndEntityCert::try_from(&cert).ok()?;
+    Some(Sha256::digest(parsed.subject_public_key_info().as_ref()).into())
+}
+
+/// Client certificate and key for mutual TLS
+#[derive(Clone)]
+pub enum ClientIdentity {
+    /// PEM certificate chain and private key
+    Pem { cert: String, key: SecretString },
+    /// PKCS#12 archive (FTPS only: the WebDAV TLS stack cannot read it)
+    Pkcs12 { der: Vec<u8>, password: SecretString },
+}
+
+impl std::fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pem { .. } => f.write_str("ClientIdentity::Pem"),
+            Self::Pkcs12 { .. } => f.write_str("ClientIdentity::Pkcs12"),
+        }
+    }
+}
+
+impl ClientIdentity {
+    /// Parse the JSON stored in the credential vault
+    fn from_json(value: &str) -> Result<Self, ProviderError> {
+        #[derive(serde::Deserialize)]
+        struct Stored {
+            cert: Option<String>,
+            key: Option<String>,
+            pkcs12: Option<String>,
+            password: Option<String>,
+        }
+        let stored: Stored = serde_json::from_str(value)
+            .map_err(|e| ProviderError::InvalidConfig(format!("Invalid client certificate entry: {}", e)))?;
+        match stored {
+            Stored { cert: Some(cert), key: Some(key), .. } => Ok(Self::Pem { cert, key: key.into() }),
+            Stored { pkcs12: Some(p12), password, .. } => {
+                let der = base64::engine::general_purpose::STANDARD.decode(p12.trim())
+                    .map_err(|_| ProviderError::InvalidConfig("Client certificate PKCS#12 is not valid base64".to_string()))?;
+                Ok(Self::Pkcs12 { der, password: password.unwrap_or_default().into() })
+            }
+            _ => Err(ProviderError::InvalidConfig(
+                "Client certificate entry needs `cert` and `key`, or `pkcs12`".to_string(),
+            )),
+        }
+    }
+
+    /// Identity for native-tls (FTPS). PEM keys must be PKCS#8.
+    pub fn to_native(&self) -> Result<native_tls::Identity, ProviderError> {
+        match self {
+            Self::Pem { cert, key } => native_tls::Identity::from_pkcs8(cert.as_bytes(), key.expose_secret().as_bytes()),
+            Self::Pkcs12 { der, password } => native_tls::Identity::from_pkcs12(der, password.expose_secret()),
+        }
+        .map_err(|e| ProviderError::InvalidConfig(format!("Invalid client certificate: {}", e)))
+    }
+
+    /// Certificate chain and key for rustls (WebDAV)
+    fn to_rustls(&self) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), ProviderError> {
+        let Self::Pem { cert, key } = self else {
+            return Err(ProviderError::InvalidConfig(
+                "PKCS#12 client certificates are only supported for FTPS; store a PEM certificate and key instead".to_string(),
+            ));
+        };
+        let invalid = |e: rustls::pki_types::pem::Error| ProviderError::InvalidConfig(format!("Invalid client certificate: {}", e));
+        let chain = CertificateDer::pem_slice_iter(cert.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid)?;
+        let key = PrivateKeyDer::from_pem_slice(key.expose_secret().as_bytes()).map_err(invalid)?;
+        Ok((chain, key))
+    }
+}
+
+/// Certificate trust settings of one profile
+#[derive(Debug, Clone, Default)]
+pub struct TlsTrust {
+    /// Additional trusted CA certificates (DER)
+    pub ca_certs: Vec<Vec<u8>>,
+    /// Client identity presented for mutual TLS
+    pub client_identity: Option<ClientIdentity>,
+    /// Server certificate pin, overriding CA validation
+    pub pin: Option<CertPin>,
+}
+
+impl TlsTrust {
+    /// Read `ca_cert`, `client_cert` and `cert_pin` from a profile `extra` map
+    pub fn from_extra(extra: &HashMap<String, String>) -> Result<Self, ProviderError> {
+        let setting = |key: &str| extra.get(key).map(|v| v.trim()).filter(|v| !v.is_empty());
+
+        let ca_certs = setting("ca_cert").map(parse_ca_certs).transpose()?.unwrap_or_default();
+
+        let client_identity = match setting("client_cert") {
+            Some(account) => {
+                let store = crate::credential_store::CredentialStore::from_cache()
+                    .ok_or_else(|| ProviderError::InvalidConfig("Credential vault is locked".to_string()))?;
+                let value = store.get(account)
+                    .map_err(|e| ProviderError::InvalidConfig(format!("Client certificate '{}' not found: {}", account, e)))?;
+                Some(ClientIdentity::from_json(&value)?)
+            }
+            None => None,
+        };
+
+        let pin = setting("cert_pin").map(CertPin::parse).transpose()?;
+
+        Ok(Self { ca_certs, client_identity, pin })
+    }
+
+    /// The profile's pin, or the certificate approved for `host:port`
+    pub fn effective_pin(&self, host: &str, port: u16) -> Option<CertPin> {
+        self.pin.or_else(|| known_certificate(host, port))
+    }
+
+    /// Add the extra CAs and the client identity to a native-tls connector
+    pub fn configure_native(&self, builder: &mut native_tls::TlsConnectorBuilder) -> Result<(), ProviderError> {
+        for der in &self.ca_certs {
+            let cert = native_tls::Certificate::from_der(der)
+                .map_err(|e| ProviderError::InvalidConfig(format!("Invalid CA certificate: {}", e)))?;
+            builder.add_root_certificate(cert);
+        }
+        if let Some(identity) = &self.client_identity {
+            builder.identity(identity.to_native()?);
+        }
+        Ok(())
+    }
+
+    /// Apply trust settings to a reqwest client talking to `host:port`.
+    /// A pin replaces CA validation with an exact certificate match.
+    pub fn configure_reqwest(
+        &self,
+        builder: reqwest::ClientBuilder,
+        host: &str,
+        port: u16,
+        verify_cert: bool,
+    ) -> Result<reqwest::ClientBuilder, ProviderError> {
+        let pin = if verify_cert { self.effective_pin(host, port) } else { None };
+        if let Some(pin) = pin {
+            return Ok(builder.tls_backend_preconfigured(self.pinned_rustls_config(pin)?));
+        }
+
+        let mut builder = builder.danger_accept_invalid_certs(!verify_cert);
+        for der in &self.ca_certs {
+            let cert = reqwest::Certificate::from_der(der)
+                .map_err(|e| ProviderError::InvalidConfig(format!("Invalid CA certificate: {}", e)))?;
+            builder = builder.add_root_certificate(cert);
+        }
+        if let Some(identity) = &self.client_identity {
+            let (chain, key) = identity.to_rustls()?;
+            let mut pem = String::new();
+            for cert in &chain {
+                pem.push_str(&to_pem("CERTIFICATE", cert.as_ref()));
+            }
+            pem.push_str(&to_pem(key_pem_label(&key), key.secret_der()));
+            let identity = reqwest::Identity::from_pem(pem.as_bytes())
+                .map_err(|e| ProviderError::InvalidConfig(format!("Invalid client certificate: {}", e)))?;
+            builder = builder.identity(identity);
+        }
+        Ok(builder)
+    }
+
+    fn pinned_rustls_config(&self, pin: CertPin) -> Result<rustls::ClientConfig, ProviderError> {
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let verifier = PinnedCertVerifier { pin, algorithms: provider.signature_verification_algorithms };
+        let builder = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| ProviderError::Other(format!("TLS setup failed: {}", e)))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier));
+        match &self.client_identity {
+            Some(identity) => {
+                let (chain, key) = identity.to_rustls()?;
+                builder.with_client_auth_cert(chain, key)
+                    .map_err(|e| ProviderError::InvalidConfig(format!("Invalid client certificate: {}", e)))
+            }
+            None => Ok(builder.with_no_client_auth()),
+        }
+    }
+}
+
+/// DER certificates of a `ca_cert` PEM bundle
+pub fn parse_ca_certs(pem: &str) -> Result<Vec<Vec<u8>>, ProviderError> {
+    let certs = CertificateDer::pem_slice_iter(pem.as_bytes())
+        .map(|c| c.map(|c| c.to_vec()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ProviderError::InvalidConfig(format!("Invalid CA certificate: {}", e)))?;
+    if certs.is_empty() {
+        return Err(ProviderError::InvalidConfig("CA certificate contains no PEM certificate".to_string()));
+    }
+    Ok(certs)
+}
+
+fn key_pem_label(key: &PrivateKeyDer<'_>) -> &'static str {
+    match key {
+        PrivateKeyDer::Pkcs1(_) => "RSA PRIVATE KEY",
+        PrivateKeyDer::Sec1(_) => "EC PRIVATE KEY",
+        _ => "PRIVATE KEY",
+    }
+}
+
+fn to_pem(label: &str, der: &[u8]) -> String {
+    let b64 = base64::engine::general_purpose::STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in b64.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+/// Accepts exactly the pinned certificate, whatever its issuer or names.
+/// Handshake signatures are still checked, so the server must hold the key.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    pin: CertPin,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if self.pin.matches(end_entity) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!(
+                "certificate {} does not match the pinned certificate",
+                CertPin::for_certificate(end_entity)
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+// ============ Trust on first use ============
+
+fn known_hosts_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("aeroftp").join("known_tls_hosts"))
+}
+
+/// `host:port` key of a known_tls_hosts line (IPv6 literals bracketed)
+fn host_entry(host: &str, port: u16) -> String {
+    let host = host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// Parse known_tls_hosts content into (host:port, pin) pairs
+fn parse_known_hosts(content: &str) -> Vec<(String, CertPin)> {
+    content.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| {
+            let (host, pin) = l.split_once(char::is_whitespace)?;
+            Some((host.to_string(), CertPin::parse(pin).ok()?))
+        })
+        .collect()
+}
+
+/// The certificate approved for `host:port`, if any
+pub fn known_certificate(host: &str, port: u16) -> Option<CertPin> {
+    let content = std::fs::read_to_string(known_hosts_path()?).ok()?;
+    let entry = host_entry(host, port);
+    parse_known_hosts(&content).into_iter().find(|(h, _)| *h == entry).map(|(_, pin)| pin)
+}
+
+/// Approve `der` for `host:port`, replacing any earlier certificate
+pub fn learn_certificate(host: &str, port: u16, der: &[u8]) -> Result<(), ProviderError> {
+    update_known_hosts(host, port, Some(CertPin::for_certificate(der)))
+}
+
+/// Forget the certificate approved for `host:port`
+pub fn forget_certificate(host: &str, port: u16) -> Result<(), ProviderError> {
+    update_known_hosts(host, port, None)
+}
+
+fn update_known_hosts(host: &str, port: u16, pin: Option<CertPin>) -> Result<(), ProviderError> {
+    let path = known_hosts_path()
+        .ok_or_else(|| ProviderError::Other("No configuration directory".to_string()))?;
+    let entry = host_entry(host, port);
+    let mut entries: Vec<(String, CertPin)> = std::fs::read_to_string(&path)
+        .map(|c| parse_known_hosts(&c))
+        .unwrap_or_default();
+    entries.retain(|(h, _)| *h != entry);
+    if let Some(pin) = pin {
+        entries.push((entry, pin));
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let content: String = entries.iter().map(|(h, p)| format!("{} {}\n", h, p)).collect();
+    let temp_path = path.with_extension("tmp");
+    std::fs::write(&temp_path, content)?;
+    std::fs::rename(&temp_path, &path)?;
+    Ok(())
+}
+
+// ============ Certificate probe ============
+
+/// How the TLS handshake is reached when probing a server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsProbe {
+    /// TLS from the first byte (HTTPS, implicit FTPS)
+    Direct,
+    /// Plain FTP greeting, then `AUTH TLS`
+    FtpAuthTls,
+}
+
+/// Read one (possibly multi-line) FTP reply and return its code
+async fn read_ftp_reply<R: tokio::io::AsyncBufRead + Unpin>(reader: &mut R) -> Result<u16, ProviderError> {
+    let mut line = String::new();
+    let mut code: Option<String> = None;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Err(ProviderError::ConnectionFailed("Connection closed by server".to_string()));
+        }
+        let prefix = line.get(..3).unwrap_or("");
+        let code = code.get_or_insert_with(|| prefix.to_string());
+        // Multi-line replies end with "<code> " after "<code>-" lines
+        if line.get(..3) == Some(code.as_str()) && line.as_bytes().get(3) != Some(&b'-') {
+            return code.parse().map_err(|_| ProviderError::ParseError(format!("Bad FTP reply: {}", line.trim())));
+        }
+    }
+}
+
+/// Complete a TLS handshake with `host:port` and return the server certificate (DER).
+/// With `verify` off any certificate is accepted: the caller compares it itself.
+/// `ca_certs` are trusted in addition to the system roots, like the profile's `ca_cert`.
+pub async fn fetch_certificate(
+    proxy: Option<&ProxyConfig>,
+    host: &str,
+    port: u16,
+    probe: TlsProbe,
+    verify: bool,
+    ca_certs: &[Vec<u8>],
+) -> Result<Vec<u8>, ProviderError> {
+    let domain = host.trim_start_matches('[').trim_end_matches(']');
+    let mut tcp = proxy::connect_tcp(proxy, domain, port)
+        .await
+        .map_err(|e| ProviderError::ConnectionFailed(e.to_string()))?;
+
+    if probe == TlsProbe::FtpAuthTls {
+        let mut reader = BufReader::new(&mut tcp);
+        let greeting = read_ftp_reply(&mut reader).await?;
+        if greeting != 220 {
+            return Err(ProviderError::ConnectionFailed(format!("Unexpected FTP greeting {}", greeting)));
+        }
+        reader.get_mut().write_all(b"AUTH TLS\r\n").await?;
+        let reply = read_ftp_reply(&mut reader).await?;
+        if reply != 234 {
+            return Err(ProviderError::NotSupported(format!("Server refused AUTH TLS ({})", reply)));
+        }
+    }
+
+    let mut connector = suppaftp::async_native_tls::TlsConnector::new()
+        .danger_accept_invalid_certs(!verify)
+        .danger_accept_invalid_hostnames(!verify);
+    for der in ca_certs {
+        let cert = native_tls::Certificate::from_der(der)
+            .map_err(|e| ProviderError::InvalidConfig(format!("Invalid CA certificate: {}", e)))?;
+        connector = connector.add_root_certificate(cert);
+    }
+    let tls = connector.connect(domain, tcp)
+        .await
+        .map_err(|e| ProviderError::ConnectionFailed(format!("TLS handshake failed: {}", e)))?;
+    let cert = tls.peer_certificate()
+        .map_err(|e| ProviderError::ConnectionFailed(e.to_string()))?
+        .ok_or_else(|| ProviderError::ConnectionFailed("Server sent no certificate".to_string()))?;
+    cert.to_der().map_err(|e| ProviderError::ParseError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBizCCATGgAwIBAgIUJmV2k2Gf3r9dY3K00JuEHwkawKwwCgYIKoZIzj0EAwIw
+GjEYMBYGA1UEAwwPbmFzLmV4YW1wbGUubGFuMCAXDTI2MTAxODE1MzUzNFoYDzIx
+MjYwOTI0MTUzNTM0WjAaMRgwFgYDVQQDDA9uYXMuZXhhbXBsZS5sYW4wWTATBgcq
+hkjOPQIBBggqhkjOPQMBBwNCAAQf/jJK6bpgS5Nrhl1y9JSlz02Q1S8qdiXU0R78
+UCLRyHvBCl1vaj0PioKTxMx9SWO9w7zYufDT8YzB8ulTqTaUo1MwUTAdBgNVHQ4E
+FgQUzZ1cTpiUTAeylLmaQP/fxmyV8vowHwYDVR0jBBgwFoAUzZ1cTpiUTAeylLma
+QP/fxmyV8vowDwYDVR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNIADBFAiEAxwe+
+ZKikbfvm5JtbEALKUfhlZoMYPnOhNoP8ilcIiMcCIHiwyqZjVFQMN0yvdnYgT973
+TDVC3qlem3P/QJjUdKKw
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_cert_pins() {
+        let der = CertificateDer::from_pem_slice(CERT_PEM.as_bytes()).unwrap();
+        let fingerprint = "SHA256:FB:11:04:E8:0A:03:EA:99:B2:B5:DB:EE:B9:22:54:07:9C:60:BB:0D:3F:B2:C0:CB:AB:81:84:A6:9D:A7:C2:0B";
+
+        let pin = CertPin::for_certificate(&der);
+        assert_eq!(pin.to_string(), fingerprint);
+        assert_eq!(CertPin::parse(&fingerprint.to_lowercase().replace(':', "")[6..]).unwrap(), pin);
+
+        let spki = CertPin::parse("sha256/esT9HF/tl3sxMYKoPFBHPvu6FxQpX6c/YPNoR+rgdDg=").unwrap();
+        assert!(spki.matches(&der));
+        assert!(pin.matches(&der));
+        assert!(!CertPin::Certificate([0; 32]).matches(&der));
+        assert!(CertPin::parse("sha256/AAAA").is_err());
+    }
+
+    #[test]
+    fn test_pinned_cert_verifier() {
+        let der = CertificateDer::from_pem_slice(CERT_PEM.as_bytes()).unwrap();
+        let verifier = |pin| PinnedCertVerifier {
+            pin,
+            algorithms: rustls::crypto::aws_lc_rs::default_provider().signature_verification_algorithms,
+        };
+        let verify = |pin| {
+            // Name and validity are not checked: only the pin decides
+            let name = ServerName::try_from("other.example.com").unwrap();
+            verifier(pin).verify_server_cert(&der, &[], &name, &[], UnixTime::now())
+        };
+
+        assert!(verify(CertPin::for_certificate(&der)).is_ok());
+        assert!(verify(CertPin::parse("sha256/esT9HF/tl3sxMYKoPFBHPvu6FxQpX6c/YPNoR+rgdDg=").unwrap()).is_ok());
+        let err = verify(CertPin::Certificate([0; 32])).unwrap_err();
+        assert!(err.to_string().contains("SHA256:FB:11:04"), "{}", err);
+        assert!(verify(CertPin::Spki([0; 32])).is_err());
+    }
+
+    #[test]
+    fn test_parse_ca_certs() {
+        let certs = parse_ca_certs(&format!("{}{}", CERT_PEM, CERT_PEM)).unwrap();
+        assert_eq!(certs.len(), 2);
+        assert_eq!(certs[0], CertificateDer::from_pem_slice(CERT_PEM.as_bytes()).unwrap().to_vec());
+        assert!(parse_ca_certs("not a certificate").is_err());
+    }
+
+    #[test]
+    fn test_known_hosts_entries() {
+        assert_eq!(host_entry("NAS.lan", 990), "nas.lan:990");
+        assert_eq!(host_entry("[2001:db8::1]", 443), "[2001:db8::1]:443");
+
+        let content = "# approved\nnas.lan:990 SHA256:00\nnas.lan:21 SHA256:FB1104E80A03EA99B2B5DBEEB92254079C60BB0D3FB2C0CBAB8184A69DA7C20B\n";
+        let entries = parse_known_hosts(content);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "nas.lan:21");
+    }
+
+    #[tokio::test]
+    async fn test_ftp_reply_parsing() {
+        let mut reader = BufReader::new(&b"220-Welcome\r\n220-to the NAS\r\n220 Ready\r\n234 AUTH TLS OK\r\n"[..]);
+        assert_eq!(read_ftp_reply(&mut reader).await.unwrap(), 220);
+        assert_eq!(read_ftp_reply(&mut reader).await.unwrap(), 234);
+        assert!(read_ftp_reply(&mut reader).await.is_err());
+    }
+}
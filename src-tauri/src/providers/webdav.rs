@@ -155,6 +155,10 @@ mod webdav_methods {
     pub fn unlock() -> Method {
         Method::from_bytes(b"UNLOCK").unwrap()
     }
+
+    pub fn report() -> Method {
+        Method::from_bytes(b"REPORT").unwrap()
+    }
 }
 
 /// WebDAV Storage Provider
@@ -179,8 +183,15 @@ impl WebDavProvider {
                 config.url
             );
         }
-        let client = Client::builder()
-            .danger_accept_invalid_certs(!config.verify_cert)
+        let endpoint = url::Url::parse(&config.url)
+            .map_err(|e| ProviderError::InvalidConfig(format!("Invalid WebDAV URL: {e}")))?;
+        let builder = config.tls.configure_reqwest(
+            super::proxy::http_client_builder(config.proxy.as_ref()),
+            endpoint.host_str().unwrap_or_default(),
+            endpoint.port_or_known_default().unwrap_or(443),
+            config.verify_cert,
+        )?;
+        let client = builder
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .map_err(|e| ProviderError::ConnectionFailed(format!("HTTP client init failed: {e}")))?;
@@ -395,6 +406,7 @@ impl WebDavProvider {
                                 link_target: None,
                                 mime_type,
                                 metadata,
+                                content_hash: None,
                             });
                         }
                         "resourcetype" if in_resourcetype => {
@@ -527,6 +539,135 @@ impl WebDavProvider {
     }
 }
 
+/// Result of one RFC 6578 sync-collection REPORT
+#[derive(Debug, Default)]
+struct SyncCollectionPage {
+    changes: Vec<super::ChangeEntry>,
+    sync_token: Option<String>,
+    /// Server answered 507 on the collection itself: more changes follow
+    truncated: bool,
+}
+
+/// Parse a sync-collection multistatus. `root_path` is the URL path of the
+/// configured endpoint; hrefs are made relative to it.
+fn parse_sync_collection(xml: &str, root_path: &str) -> SyncCollectionPage {
+    let mut page = SyncCollectionPage::default();
+    let root = root_path.trim_end_matches('/');
+
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut in_response = false;
+    let mut in_propstat = false;
+    let mut in_resourcetype = false;
+    let mut current_tag: Option<String> = None;
+    let mut href = String::new();
+    let mut status = String::new();
+    let mut size = String::new();
+    let mut modified = String::new();
+    let mut is_collection = false;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Err(e) => {
+                tracing::warn!("[WebDAV] sync-collection parse error at position {}: {}", reader.error_position(), e);
+                break;
+            }
+            Ok(Event::Eof) => break,
+            Ok(Event::Start(ref e)) => {
+                let local = local_name(e.name().as_ref());
+                match local.as_str() {
+                    "response" => {
+                        in_response = true;
+                        href.clear();
+                        status.clear();
+                        size.clear();
+                        modified.clear();
+                        is_collection = false;
+                    }
+                    "propstat" if in_response => in_propstat = true,
+                    "resourcetype" if in_response => in_resourcetype = true,
+                    "collection" if in_resourcetype => is_collection = true,
+                    // Only the response-level status tells whether the member is gone
+                    "status" if in_response && !in_propstat => current_tag = Some(local),
+                    "href" | "getcontentlength" | "getlastmodified" if in_response => current_tag = Some(local),
+                    "sync-token" if !in_response => current_tag = Some(local),
+                    _ => {}
+                }
+            }
+            Ok(Event::Empty(ref e)) => {
+                let local = local_name(e.name().as_ref());
+                if local == "collection" && in_resourcetype {
+                    is_collection = true;
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                let local = local_name(e.name().as_ref());
+                match local.as_str() {
+                    "response" if in_response => {
+                        in_response = false;
+                        let decoded = urlencoding::decode(&href)
+                            .map(|d| d.into_owned())
+                            .unwrap_or_else(|_| href.clone());
+                        // Hrefs may be absolute URLs or absolute paths
+                        let path_part = match decoded.find("://") {
+                            Some(i) => decoded[i + 3..].find('/').map(|j| decoded[i + 3 + j..].to_string()).unwrap_or_default(),
+                            None => decoded,
+                        };
+                        let relative = path_part.strip_prefix(root).unwrap_or(&path_part).trim_matches('/');
+                        let code = status.split_whitespace().nth(1).unwrap_or("");
+                        if relative.is_empty() {
+                            if code == "507" {
+                                page.truncated = true;
+                            }
+                            continue;
+                        }
+                        let removed = code == "404";
+                        let path = format!("/{}", relative);
+                        page.changes.push(super::ChangeEntry {
+                            file_id: path.clone(),
+                            name: relative.rsplit('/').next().unwrap_or(relative).to_string(),
+                            change_type: if removed { "deleted" } else { "modified" }.to_string(),
+                            mime_type: None,
+                            timestamp: (!modified.is_empty()).then(|| modified.clone()),
+                            removed,
+                            is_dir: is_collection || href.ends_with('/'),
+                            size: if removed || is_collection { None } else { size.parse().ok() },
+                            parent_id: None,
+                            path: Some(path),
+                        });
+                    }
+                    "propstat" => in_propstat = false,
+                    "resourcetype" => in_resourcetype = false,
+                    _ => {
+                        if current_tag.as_deref() == Some(local.as_str()) {
+                            current_tag = None;
+                        }
+                    }
+                }
+            }
+            Ok(Event::Text(ref e)) => {
+                if let Some(ref tag) = current_tag {
+                    let text = String::from_utf8_lossy(e.as_ref()).trim().to_string();
+                    match tag.as_str() {
+                        "href" => href = text,
+                        "status" => status = text,
+                        "getcontentlength" => size = text,
+                        "getlastmodified" => modified = text,
+                        "sync-token" => page.sync_token = Some(text),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    page
+}
+
 /// Strip namespace prefix from an XML element name, returning an owned String.
 /// e.g. "d:response" -> "response", "DAV:href" -> "href", "response" -> "response"
 fn local_name(raw: &[u8]) -> String {
@@ -1033,6 +1174,7 @@ impl StorageProvider for WebDavProvider {
                     link_target: None,
                     mime_type,
                     metadata: Default::default(),
+                    content_hash: None,
                 })
             }
             StatusCode::NOT_FOUND => {
@@ -1177,6 +1319,99 @@ impl StorageProvider for WebDavProvider {
         Ok(super::StorageInfo { used, total, free })
     }
 
+    fn supports_change_tracking(&self) -> bool {
+        true
+    }
+
+    async fn get_change_token(&mut self) -> Result<String, ProviderError> {
+        if !self.connected {
+            return Err(ProviderError::NotConnected);
+        }
+
+        // RFC 6578: an empty sync-token in the REPORT would return every member,
+        // so read the current token as a plain property instead
+        let response = self.request(webdav_methods::propfind(), "/")
+            .header("Depth", "0")
+            .header("Content-Type", "application/xml")
+            .body(r#"<?xml version="1.0" encoding="utf-8"?>
+                <d:propfind xmlns:d="DAV:">
+                    <d:prop>
+                        <d:sync-token/>
+                    </d:prop>
+                </d:propfind>"#)
+            .send()
+            .await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() && response.status() != StatusCode::MULTI_STATUS {
+            return Err(ProviderError::NotSupported("sync-collection".to_string()));
+        }
+
+        let xml = response.text().await
+            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+        self.extract_xml_properties(&xml)
+            .remove("sync-token")
+            .ok_or_else(|| ProviderError::NotSupported("sync-collection".to_string()))
+    }
+
+    async fn list_changes(&mut self, page_token: &str) -> Result<(Vec<super::ChangeEntry>, String), ProviderError> {
+        if !self.connected {
+            return Err(ProviderError::NotConnected);
+        }
+
+        let root_path = extract_uri_path(&self.build_url("/"));
+        let mut changes = Vec::new();
+        let mut token = page_token.to_string();
+
+        loop {
+            let body = format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+                <d:sync-collection xmlns:d="DAV:">
+                    <d:sync-token>{}</d:sync-token>
+                    <d:sync-level>infinite</d:sync-level>
+                    <d:prop>
+                        <d:resourcetype/>
+                        <d:getcontentlength/>
+                        <d:getlastmodified/>
+                    </d:prop>
+                </d:sync-collection>"#,
+                quick_xml::escape::escape(token.as_str())
+            );
+            let response = self.request(webdav_methods::report(), "/")
+                .header("Depth", "0")
+                .header("Content-Type", "application/xml")
+                .body(body)
+                .send()
+                .await
+                .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+            match response.status() {
+                StatusCode::MULTI_STATUS | StatusCode::OK => {}
+                // valid-sync-token precondition failed: the token expired
+                StatusCode::FORBIDDEN | StatusCode::CONFLICT => {
+                    return Err(ProviderError::InvalidConfig("WebDAV sync token is no longer valid".to_string()));
+                }
+                status => {
+                    let text = response.text().await.unwrap_or_default();
+                    return Err(ProviderError::ServerError(format!(
+                        "sync-collection failed ({}): {}", status, sanitize_api_error(&text)
+                    )));
+                }
+            }
+
+            let xml = response.text().await
+                .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+            let page = parse_sync_collection(&xml, &root_path);
+            changes.extend(page.changes);
+            let next = page.sync_token
+                .ok_or_else(|| ProviderError::ParseError("sync-collection response without sync-token".to_string()))?;
+            if !page.truncated || next == token {
+                return Ok((changes, next));
+            }
+            token = next;
+        }
+    }
+
     fn supports_locking(&self) -> bool {
         true
     }
@@ -1295,6 +1530,8 @@ mod tests {
             password: secrecy::SecretString::from("pass".to_string()),
             initial_path: None,
             verify_cert: true,
+            proxy: None,
+            tls: Default::default(),
         }
     }
 
@@ -1400,4 +1637,57 @@ mod tests {
         assert_eq!(entries[1].name, "subdir");
         assert!(entries[1].is_dir);
     }
+
+    #[test]
+    fn test_parse_sync_collection() {
+        let xml = r#"<?xml version="1.0"?>
+        <d:multistatus xmlns:d="DAV:">
+            <d:response>
+                <d:href>/dav/docs/report%20final.txt</d:href>
+                <d:propstat>
+                    <d:prop>
+                        <d:resourcetype/>
+                        <d:getcontentlength>2048</d:getcontentlength>
+                        <d:getlastmodified>Tue, 02 Jan 2024 10:00:00 GMT</d:getlastmodified>
+                    </d:prop>
+                    <d:status>HTTP/1.1 200 OK</d:status>
+                </d:propstat>
+            </d:response>
+            <d:response>
+                <d:href>https://example.com/dav/photos/</d:href>
+                <d:propstat>
+                    <d:prop>
+                        <d:resourcetype><d:collection/></d:resourcetype>
+                    </d:prop>
+                    <d:status>HTTP/1.1 200 OK</d:status>
+                </d:propstat>
+            </d:response>
+            <d:response>
+                <d:href>/dav/old.txt</d:href>
+                <d:status>HTTP/1.1 404 Not Found</d:status>
+            </d:response>
+            <d:response>
+                <d:href>/dav/</d:href>
+                <d:status>HTTP/1.1 507 Insufficient Storage</d:status>
+            </d:response>
+            <d:sync-token>http://example.com/ns/sync/1234</d:sync-token>
+        </d:multistatus>"#;
+
+        let page = parse_sync_collection(xml, "/dav");
+        assert_eq!(page.sync_token.as_deref(), Some("http://example.com/ns/sync/1234"));
+        assert!(page.truncated);
+        assert_eq!(page.changes.len(), 3);
+
+        assert_eq!(page.changes[0].path.as_deref(), Some("/docs/report final.txt"));
+        assert_eq!(page.changes[0].name, "report final.txt");
+        assert_eq!(page.changes[0].size, Some(2048));
+        assert!(!page.changes[0].removed);
+
+        assert_eq!(page.changes[1].path.as_deref(), Some("/photos"));
+        assert!(page.changes[1].is_dir);
+        assert_eq!(page.changes[1].size, None);
+
+        assert_eq!(page.changes[2].path.as_deref(), Some("/old.txt"));
+        assert!(page.changes[2].removed);
+    }
 }
@@ -27,6 +27,10 @@ pub struct ZohoWorkdriveConfig {
     pub client_secret: String,
     /// Zoho region: "us", "eu", "in", "au", "jp", "uk", "ca", "sa"
     pub region: String,
+    /// Outbound proxy (`None` connects directly)
+    pub proxy: Option<super::proxy::ProxyConfig>,
+    /// Account whose tokens to use (email); `None` takes a fresh sign-in
+    pub account: Option<String>,
 }
 
 impl ZohoWorkdriveConfig {
@@ -35,6 +39,8 @@ impl ZohoWorkdriveConfig {
             client_id: client_id.to_string(),
             client_secret: client_secret.to_string(),
             region: region.to_string(),
+            account: None,
+            proxy: super::proxy::global(),
         }
     }
 
@@ -240,7 +246,7 @@ impl ZohoWorkdriveProvider {
             ACCEPT,
             HeaderValue::from_static("application/vnd.api+json"),
         );
-        let client = reqwest::Client::builder()
+        let client = super::proxy::http_client_builder(config.proxy.as_ref())
             .default_headers(default_headers)
             .timeout(std::time::Duration::from_secs(30))
             .build()
@@ -264,6 +270,7 @@ impl ZohoWorkdriveProvider {
     /// Get OAuth config for token operations
     fn oauth_config(&self) -> OAuthConfig {
         OAuthConfig::zoho(&self.config.client_id, &self.config.client_secret, &self.config.region)
+            .with_account(self.config.account.as_deref())
     }
 
     /// Get authorization header with valid token
@@ -462,7 +469,7 @@ impl ZohoWorkdriveProvider {
 
     /// Check if authenticated
     pub fn is_authenticated(&self) -> bool {
-        self.oauth_manager.has_tokens(OAuthProvider::ZohoWorkdrive)
+        self.oauth_manager.has_tokens(OAuthProvider::ZohoWorkdrive, self.config.account.as_deref())
     }
 
     /// Discover team ID and privatespace root folder
@@ -815,6 +822,7 @@ impl ZohoWorkdriveProvider {
             link_target: None,
             mime_type: None,
             metadata,
+            content_hash: None,
         }
     }
 
@@ -840,6 +848,7 @@ impl ZohoWorkdriveProvider {
                 link_target: None,
                 mime_type: None,
                 metadata,
+                content_hash: None,
             });
         }
 
@@ -962,6 +971,7 @@ impl StorageProvider for ZohoWorkdriveProvider {
 
         self.connected = true;
         self.current_path = "/".to_string();
+        self.config.account = self.oauth_manager.bind_account(&self.oauth_config(), self.account_email.as_deref());
         info!("Connected to Zoho WorkDrive ({})", self.account_email.as_deref().unwrap_or("unknown"));
         Ok(())
     }
@@ -2,6 +2,7 @@
 // Uses portable-pty for cross-platform support (Linux/macOS/Windows)
 // Supports multiple concurrent sessions (one per terminal tab)
 
+use crate::terminal_recording::{self, SessionRecorder};
 use portable_pty::{native_pty_system, CommandBuilder, PtyPair, PtySize};
 use std::collections::HashMap;
 use std::io::{Read, Write};
@@ -16,6 +17,8 @@ const MAX_PTY_SESSIONS: usize = 20;
 pub struct PtySession {
     pub pair: Option<PtyPair>,
     pub writer: Option<Box<dyn Write + Send>>,
+    /// asciicast recorder when the session is being recorded
+    pub recorder: Option<Arc<SessionRecorder>>,
 }
 
 /// Manager holding multiple PTY sessions keyed by session ID
@@ -51,8 +54,9 @@ pub fn create_pty_state() -> PtyState {
 
 /// Spawn a new shell in the PTY. Returns session info including session ID.
 /// Enforces a maximum of MAX_PTY_SESSIONS concurrent sessions.
+/// With `record` (or the "record all sessions" setting) the session is saved as an asciicast file.
 #[tauri::command]
-pub fn spawn_shell(app: AppHandle, pty_state: State<'_, PtyState>, cwd: Option<String>) -> Result<String, String> {
+pub fn spawn_shell(app: AppHandle, pty_state: State<'_, PtyState>, cwd: Option<String>, record: Option<bool>) -> Result<String, String> {
     // Check session limit before allocating resources
     {
         let manager = pty_state.lock().map_err(|_| "Lock error")?;
@@ -142,10 +146,17 @@ pub fn spawn_shell(app: AppHandle, pty_state: State<'_, PtyState>, cwd: Option<S
     let mut manager = pty_state.lock().map_err(|_| "Lock error")?;
     let session_id = manager.next_session_id();
 
+    let recorder = if terminal_recording::should_record(record) {
+        Some(SessionRecorder::create(&session_id, &format!("Local shell ({})", shell), 80, 24)?)
+    } else {
+        None
+    };
+
     // Store in state
     manager.sessions.insert(session_id.clone(), PtySession {
         pair: Some(pair),
         writer: Some(writer),
+        recorder: recorder.clone(),
     });
 
     // Spawn a thread to read output from the PTY and emit it to the frontend
@@ -159,6 +170,9 @@ pub fn spawn_shell(app: AppHandle, pty_state: State<'_, PtyState>, cwd: Option<S
                 Ok(0) => break, // EOF
                 Ok(n) => {
                     let output = String::from_utf8_lossy(&buffer[..n]).to_string();
+                    if let Some(recorder) = &recorder {
+                        recorder.output(&output);
+                    }
                     let _ = app_clone.emit(&event_name, output);
                 }
                 Err(_) => break, // Error or closed
@@ -178,6 +192,10 @@ pub fn pty_write(pty_state: State<'_, PtyState>, data: String, session_id: Strin
     let session = manager.sessions.get_mut(&session_id)
         .ok_or_else(|| format!("PTY session not found: {}", session_id))?;
 
+    if let Some(recorder) = &session.recorder {
+        recorder.input(&data);
+    }
+
     if let Some(ref mut writer) = session.writer {
         writer
             .write_all(data.as_bytes())
@@ -207,6 +225,9 @@ pub fn pty_resize(pty_state: State<'_, PtyState>, rows: u16, cols: u16, session_
                 pixel_height: 0,
             })
             .map_err(|e| format!("Resize error: {}", e))?;
+        if let Some(recorder) = &session.recorder {
+            recorder.resize(cols as u32, rows as u32);
+        }
         Ok(())
     } else {
         Err("No active PTY pair".to_string())
@@ -9,11 +9,9 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 use tracing::info;
 
-use crate::session_manager::MultiProviderState;
-use crate::providers::{
-    StorageProvider, ProviderFactory, ProviderType,
-    ProviderConfig, RemoteEntry,
-};
+use crate::session_manager::{MultiProviderState, TransferRoute};
+use crate::provider_commands::ProviderConnectionParams;
+use crate::providers::{StorageProvider, ProviderFactory, RemoteEntry};
 
 // ============ Request/Response Types ============
 
@@ -21,32 +19,18 @@ use crate::providers::{
 pub struct SessionConnectionParams {
     /// Unique session ID (from frontend activeSessionId)
     pub session_id: String,
-    /// Protocol type: "ftp", "ftps", "webdav", "s3", "googledrive", "dropbox", "onedrive"
-    pub protocol: String,
-    /// Host/URL (FTP server, WebDAV URL, or S3 endpoint)
-    pub server: String,
-    /// Port (optional, defaults based on protocol)
-    pub port: Option<u16>,
-    /// Username or Access Key ID
-    pub username: String,
-    /// Password or Secret Access Key  
-    pub password: String,
-    /// Initial remote path to navigate to
-    pub initial_path: Option<String>,
-    /// S3 bucket name
-    pub bucket: Option<String>,
-    /// S3/cloud region
-    pub region: Option<String>,
-    /// Custom endpoint for S3-compatible services
-    pub endpoint: Option<String>,
-    /// Use path-style URLs for S3
-    pub path_style: Option<bool>,
+    /// Protocol, server, credentials and per-protocol options, as for `provider_connect`
+    #[serde(flatten)]
+    pub connection: ProviderConnectionParams,
     /// OAuth client ID (for OAuth providers)
     pub client_id: Option<String>,
     /// OAuth client secret (for OAuth providers)
     pub client_secret: Option<String>,
     /// Display name override
     pub display_name: Option<String>,
+    /// OAuth account (email) of the saved profile, so several accounts of the
+    /// same provider can be open in parallel sessions
+    pub account: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,9 +57,9 @@ pub async fn session_connect(
     state: State<'_, MultiProviderState>,
     params: SessionConnectionParams,
 ) -> Result<SessionInfoResponse, String> {
-    info!("Creating session {} for {} provider", params.session_id, params.protocol);
+    info!("Creating session {} for {} provider", params.session_id, params.connection.protocol);
     
-    let protocol_lower = params.protocol.to_lowercase();
+    let protocol_lower = params.connection.protocol.to_lowercase();
     let is_oauth = matches!(
         protocol_lower.as_str(),
         "googledrive" | "google_drive" | "dropbox" | "onedrive"
@@ -86,50 +70,15 @@ pub async fn session_connect(
         return session_connect_oauth(state, params).await;
     }
 
-    // Build provider config for non-OAuth providers
-    let provider_type = match protocol_lower.as_str() {
-        "ftp" => ProviderType::Ftp,
-        "ftps" => ProviderType::Ftps,
-        "sftp" => ProviderType::Sftp,
-        "webdav" => ProviderType::WebDav,
-        "s3" => ProviderType::S3,
-        "filelu" => ProviderType::FileLu,
-        other => return Err(format!("Unknown protocol: {}", other)),
-    };
-
-    let mut extra = std::collections::HashMap::new();
-    
-    // Add S3-specific options
-    if provider_type == ProviderType::S3 {
-        if let Some(ref bucket) = params.bucket {
-            extra.insert("bucket".to_string(), bucket.clone());
-        } else {
-            return Err("S3 requires a bucket name".to_string());
-        }
-        if let Some(ref region) = params.region {
-            extra.insert("region".to_string(), region.clone());
-        } else {
-            extra.insert("region".to_string(), "us-east-1".to_string());
-        }
-        if let Some(ref endpoint) = params.endpoint {
-            extra.insert("endpoint".to_string(), endpoint.clone());
-        }
-        if params.path_style.unwrap_or(false) {
-            extra.insert("path_style".to_string(), "true".to_string());
-        }
+    if !matches!(protocol_lower.as_str(), "ftp" | "ftps" | "sftp" | "webdav" | "s3" | "filelu") {
+        return Err(format!("Unknown protocol: {}", protocol_lower));
     }
 
-    let config = ProviderConfig {
-        name: params.display_name.clone()
-            .unwrap_or_else(|| format!("{}@{}", params.username, params.server)),
-        provider_type,
-        host: params.server.clone(),
-        port: params.port,
-        username: Some(params.username.clone()),
-        password: Some(params.password.clone()),
-        initial_path: params.initial_path.clone(),
-        extra,
-    };
+    // Same options (FTP data mode, TLS trust, proxy, encryption...) as a main connection
+    let mut config = params.connection.to_provider_config()?;
+    if let Some(ref name) = params.display_name {
+        config.name = name.clone();
+    }
 
     // Create and connect provider
     let mut provider = ProviderFactory::create(&config)
@@ -172,31 +121,34 @@ async fn session_connect_oauth(
     let client_secret = params.client_secret.as_ref()
         .ok_or("OAuth requires client_secret")?;
 
-    let protocol_lower = params.protocol.to_lowercase();
+    let protocol_lower = params.connection.protocol.to_lowercase();
+    let proxy = crate::providers::proxy::from_setting(
+        params.connection.proxy.as_deref(), params.connection.no_proxy.as_deref(),
+    ).map_err(|e| e.to_string())?;
     
     let provider: Box<dyn StorageProvider> = match protocol_lower.as_str() {
         "googledrive" | "google_drive" => {
-            let config = GoogleDriveConfig::new(client_id, client_secret);
+            let config = GoogleDriveConfig { proxy, account: params.account.clone(), ..GoogleDriveConfig::new(client_id, client_secret) };
             let mut p = GoogleDriveProvider::new(config);
             p.connect().await
                 .map_err(|e| format!("Google Drive connection failed: {}", e))?;
             Box::new(p)
         }
         "dropbox" => {
-            let config = DropboxConfig::new(client_id, client_secret);
+            let config = DropboxConfig { proxy, account: params.account.clone(), ..DropboxConfig::new(client_id, client_secret) };
             let mut p = DropboxProvider::new(config);
             p.connect().await
                 .map_err(|e| format!("Dropbox connection failed: {}", e))?;
             Box::new(p)
         }
         "onedrive" => {
-            let config = OneDriveConfig::new(client_id, client_secret);
+            let config = OneDriveConfig { proxy, account: params.account.clone(), ..OneDriveConfig::new(client_id, client_secret) };
             let mut p = OneDriveProvider::new(config);
             p.connect().await
                 .map_err(|e| format!("OneDrive connection failed: {}", e))?;
             Box::new(p)
         }
-        _ => return Err(format!("Unknown OAuth provider: {}", params.protocol)),
+        _ => return Err(format!("Unknown OAuth provider: {}", params.connection.protocol)),
     };
 
     // Create session
@@ -439,6 +391,28 @@ pub async fn session_upload(
     Ok(())
 }
 
+/// Copy a file between two sessions: server-to-server (FXP) for FTP pairs
+/// when both servers allow it, otherwise relayed through this machine.
+/// Returns the route taken ("fxp" or "relay").
+#[tauri::command]
+pub async fn session_transfer(
+    state: State<'_, MultiProviderState>,
+    source_session_id: String,
+    source_path: String,
+    dest_session_id: String,
+    dest_path: String,
+    fxp: Option<bool>,
+) -> Result<TransferRoute, String> {
+    state.transfer_between(
+        &source_session_id,
+        &source_path,
+        &dest_session_id,
+        &dest_path,
+        fxp.unwrap_or(true),
+    ).await
+        .map_err(|e| format!("Failed to transfer: {}", e))
+}
+
 /// Create share link in a session
 #[tauri::command]
 pub async fn session_create_share_link(
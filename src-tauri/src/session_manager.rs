@@ -5,8 +5,10 @@
 //!
 //! Each session has a unique ID that corresponds to the frontend's activeSessionId.
 
+use serde::Serialize;
 use std::collections::HashMap;
-use tokio::sync::RwLock;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
 use tracing::{info, warn};
 
 use crate::providers::{StorageProvider, ProviderConfig, ProviderError, RemoteEntry};
@@ -29,15 +31,30 @@ pub struct SessionInfo {
     pub last_activity: std::time::Instant,
 }
 
+/// A provider shared between the session map and the operation using it.
+/// Each session has its own lock, so a long transfer on one session does not
+/// block the map or any other session.
+pub type SharedProvider = Arc<Mutex<Box<dyn StorageProvider>>>;
+
 /// A session wrapping a provider with its metadata
 pub struct ProviderSession {
     pub info: SessionInfo,
-    pub provider: Box<dyn StorageProvider>,
+    pub provider: SharedProvider,
     /// Provider configuration (for reconnection/serialization)
     #[allow(dead_code)]
     pub config: Option<ProviderConfig>,
 }
 
+/// How a transfer between two sessions was carried out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferRoute {
+    /// Server-to-server FTP (FXP): data never touched this machine
+    Fxp,
+    /// Downloaded from the source and uploaded to the destination
+    Relay,
+}
+
 /// Multi-session state manager
 /// 
 /// Replaces the single-provider ProviderState with a HashMap that can
@@ -78,7 +95,7 @@ impl MultiProviderState {
 
         let session = ProviderSession {
             info: info.clone(),
-            provider,
+            provider: Arc::new(Mutex::new(provider)),
             config,
         };
 
@@ -120,22 +137,35 @@ impl MultiProviderState {
         sessions.get(session_id).map(|s| s.info.clone())
     }
 
+    /// Look up a session, record activity and hand out its provider.
+    /// The map lock is released before the caller talks to the provider.
+    async fn provider_for(&self, session_id: &str) -> Result<SharedProvider, ProviderError> {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.get_mut(session_id)
+            .ok_or(ProviderError::NotConnected)?;
+        session.info.last_activity = std::time::Instant::now();
+        Ok(session.provider.clone())
+    }
+
     /// Close and remove a session
     pub async fn close_session(&self, session_id: &str) -> Result<(), ProviderError> {
         let mut sessions = self.sessions.write().await;
         
-        if let Some(mut session) = sessions.remove(session_id) {
+        if let Some(session) = sessions.remove(session_id) {
             info!("Closing session {} ({})", session_id, session.info.display_name);
             
-            // Disconnect the provider
-            if let Err(e) = session.provider.disconnect().await {
-                warn!("Error disconnecting session {}: {}", session_id, e);
+            // If this was the active session, clear or switch to another
+            {
+                let mut active = self.active_session_id.write().await;
+                if active.as_ref() == Some(&session_id.to_string()) {
+                    *active = sessions.keys().next().cloned();
+                }
             }
+            drop(sessions);
             
-            // If this was the active session, clear or switch to another
-            let mut active = self.active_session_id.write().await;
-            if active.as_ref() == Some(&session_id.to_string()) {
-                *active = sessions.keys().next().cloned();
+            // Disconnect the provider (waits for an operation still using it)
+            if let Err(e) = session.provider.lock().await.disconnect().await {
+                warn!("Error disconnecting session {}: {}", session_id, e);
             }
             
             Ok(())
@@ -203,11 +233,11 @@ impl MultiProviderState {
     /// Close all sessions (cleanup on app shutdown)
     #[allow(dead_code)]
     pub async fn close_all_sessions(&self) {
-        let mut sessions = self.sessions.write().await;
+        let drained: Vec<(String, ProviderSession)> = self.sessions.write().await.drain().collect();
         
-        for (id, mut session) in sessions.drain() {
+        for (id, session) in drained {
             info!("Closing session {} on shutdown", id);
-            if let Err(e) = session.provider.disconnect().await {
+            if let Err(e) = session.provider.lock().await.disconnect().await {
                 warn!("Error disconnecting session {}: {}", id, e);
             }
         }
@@ -242,12 +272,9 @@ impl MultiProviderState {
     ) -> Result<Vec<RemoteEntry>, ProviderError> {
         let sid = self.resolve_session_id(session_id).await?;
         
-        let mut sessions = self.sessions.write().await;
-        let session = sessions.get_mut(&sid)
-            .ok_or(ProviderError::NotConnected)?;
-        
-        session.info.last_activity = std::time::Instant::now();
-        session.provider.list(path).await
+        let provider = self.provider_for(&sid).await?;
+        let result = provider.lock().await.list(path).await;
+        result
     }
 
     /// Change directory in a session
@@ -258,15 +285,16 @@ impl MultiProviderState {
     ) -> Result<String, ProviderError> {
         let sid = self.resolve_session_id(session_id).await?;
         
-        let mut sessions = self.sessions.write().await;
-        let session = sessions.get_mut(&sid)
-            .ok_or(ProviderError::NotConnected)?;
-        
-        session.info.last_activity = std::time::Instant::now();
-        session.provider.cd(path).await?;
+        let provider = self.provider_for(&sid).await?;
+        let new_path = {
+            let mut provider = provider.lock().await;
+            provider.cd(path).await?;
+            provider.pwd().await?
+        };
         
-        let new_path = session.provider.pwd().await?;
-        session.info.current_path = new_path.clone();
+        if let Some(session) = self.sessions.write().await.get_mut(&sid) {
+            session.info.current_path = new_path.clone();
+        }
         
         Ok(new_path)
     }
@@ -275,11 +303,13 @@ impl MultiProviderState {
     pub async fn pwd(&self, session_id: Option<&str>) -> Result<String, ProviderError> {
         let sid = self.resolve_session_id(session_id).await?;
         
-        let mut sessions = self.sessions.write().await;
-        let session = sessions.get_mut(&sid)
+        let provider = self.sessions.read().await
+            .get(&sid)
+            .map(|s| s.provider.clone())
             .ok_or(ProviderError::NotConnected)?;
         
-        session.provider.pwd().await
+        let result = provider.lock().await.pwd().await;
+        result
     }
 
     /// Create directory in a session
@@ -290,12 +320,9 @@ impl MultiProviderState {
     ) -> Result<(), ProviderError> {
         let sid = self.resolve_session_id(session_id).await?;
         
-        let mut sessions = self.sessions.write().await;
-        let session = sessions.get_mut(&sid)
-            .ok_or(ProviderError::NotConnected)?;
-        
-        session.info.last_activity = std::time::Instant::now();
-        session.provider.mkdir(path).await
+        let provider = self.provider_for(&sid).await?;
+        let result = provider.lock().await.mkdir(path).await;
+        result
     }
 
     /// Delete file/folder in a session
@@ -306,12 +333,9 @@ impl MultiProviderState {
     ) -> Result<(), ProviderError> {
         let sid = self.resolve_session_id(session_id).await?;
         
-        let mut sessions = self.sessions.write().await;
-        let session = sessions.get_mut(&sid)
-            .ok_or(ProviderError::NotConnected)?;
-        
-        session.info.last_activity = std::time::Instant::now();
-        session.provider.delete(path).await
+        let provider = self.provider_for(&sid).await?;
+        let result = provider.lock().await.delete(path).await;
+        result
     }
 
     /// Rename file/folder in a session
@@ -323,12 +347,9 @@ impl MultiProviderState {
     ) -> Result<(), ProviderError> {
         let sid = self.resolve_session_id(session_id).await?;
         
-        let mut sessions = self.sessions.write().await;
-        let session = sessions.get_mut(&sid)
-            .ok_or(ProviderError::NotConnected)?;
-        
-        session.info.last_activity = std::time::Instant::now();
-        session.provider.rename(from, to).await
+        let provider = self.provider_for(&sid).await?;
+        let result = provider.lock().await.rename(from, to).await;
+        result
     }
 
     /// Download file
@@ -340,13 +361,10 @@ impl MultiProviderState {
     ) -> Result<(), ProviderError> {
         let sid = self.resolve_session_id(session_id).await?;
         
-        let mut sessions = self.sessions.write().await;
-        let session = sessions.get_mut(&sid)
-            .ok_or(ProviderError::NotConnected)?;
-        
-        session.info.last_activity = std::time::Instant::now();
+        let provider = self.provider_for(&sid).await?;
         // No progress callback for now - can be added later
-        session.provider.download(remote_path, local_path, None).await
+        let result = provider.lock().await.download(remote_path, local_path, None).await;
+        result
     }
 
     /// Upload file
@@ -358,13 +376,10 @@ impl MultiProviderState {
     ) -> Result<(), ProviderError> {
         let sid = self.resolve_session_id(session_id).await?;
         
-        let mut sessions = self.sessions.write().await;
-        let session = sessions.get_mut(&sid)
-            .ok_or(ProviderError::NotConnected)?;
-        
-        session.info.last_activity = std::time::Instant::now();
+        let provider = self.provider_for(&sid).await?;
         // No progress callback for now - can be added later
-        session.provider.upload(local_path, remote_path, None).await
+        let result = provider.lock().await.upload(local_path, remote_path, None).await;
+        result
     }
 
     /// Create share link
@@ -375,18 +390,90 @@ impl MultiProviderState {
     ) -> Result<String, ProviderError> {
         let sid = self.resolve_session_id(session_id).await?;
         
-        let mut sessions = self.sessions.write().await;
-        let session = sessions.get_mut(&sid)
-            .ok_or(ProviderError::NotConnected)?;
+        let provider = self.provider_for(&sid).await?;
+        let mut provider = provider.lock().await;
         
-        if !session.provider.supports_share_links() {
+        if !provider.supports_share_links() {
             return Err(ProviderError::Other(format!(
                 "{} does not support share links",
-                session.info.protocol
+                provider.provider_type()
             )));
         }
         
-        session.provider.create_share_link(path, None).await
+        provider.create_share_link(path, None).await
+    }
+
+    /// Copy a file from one session to another. Between two FTP sessions FXP is
+    /// tried first (unless `allow_fxp` is false); when a server refuses it, or
+    /// for any other provider pair, the file is relayed through a temp file.
+    pub async fn transfer_between(
+        &self,
+        source_session: &str,
+        source_path: &str,
+        dest_session: &str,
+        dest_path: &str,
+        allow_fxp: bool,
+    ) -> Result<TransferRoute, ProviderError> {
+        if source_session == dest_session {
+            return Err(ProviderError::InvalidConfig(
+                "Source and destination must be different sessions".to_string()
+            ));
+        }
+
+        // Only the provider handles are taken from the map; its lock is
+        // released before the (possibly long) transfer starts
+        let (source, dest) = {
+            let mut sessions = self.sessions.write().await;
+            if !sessions.contains_key(source_session) || !sessions.contains_key(dest_session) {
+                return Err(ProviderError::NotConnected);
+            }
+            let now = std::time::Instant::now();
+            let mut handle = |id: &str| {
+                let session = sessions.get_mut(id).expect("checked above");
+                session.info.last_activity = now;
+                session.provider.clone()
+            };
+            (handle(source_session), handle(dest_session))
+        };
+
+        // Lock both sessions in a fixed order so two opposite transfers cannot deadlock
+        let (mut source, mut dest) = if source_session < dest_session {
+            let s = source.lock_owned().await;
+            (s, dest.lock_owned().await)
+        } else {
+            let d = dest.lock_owned().await;
+            (source.lock_owned().await, d)
+        };
+
+        if allow_fxp {
+            use crate::providers::FtpProvider;
+            let src_ftp = source.as_any_mut().downcast_mut::<FtpProvider>();
+            let dst_ftp = dest.as_any_mut().downcast_mut::<FtpProvider>();
+            if let (Some(src_ftp), Some(dst_ftp)) = (src_ftp, dst_ftp) {
+                match crate::providers::ftp::fxp_transfer(src_ftp, source_path, dst_ftp, dest_path).await {
+                    Ok(()) => {
+                        info!("FXP {}:{} -> {}:{}", source_session, source_path, dest_session, dest_path);
+                        return Ok(TransferRoute::Fxp);
+                    }
+                    Err(ProviderError::NotSupported(reason)) => {
+                        warn!("{}; relaying through this machine instead", reason);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        let temp_path = std::env::temp_dir().join(format!("aeroftp_relay_{}", uuid::Uuid::new_v4()));
+        let temp = temp_path.to_string_lossy().to_string();
+        let result = async {
+            source.download(source_path, &temp, None).await?;
+            dest.upload(&temp, dest_path, None).await
+        }.await;
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        result?;
+
+        info!("Relayed {}:{} -> {}:{}", source_session, source_path, dest_session, dest_path);
+        Ok(TransferRoute::Relay)
     }
 }
 
@@ -412,4 +499,17 @@ mod tests {
         state.close_all_sessions().await;
         assert_eq!(state.session_count().await, 0);
     }
+
+    #[tokio::test]
+    async fn test_transfer_between_requires_two_sessions() {
+        let state = MultiProviderState::new();
+        assert!(matches!(
+            state.transfer_between("a", "/f", "a", "/g", true).await,
+            Err(ProviderError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            state.transfer_between("a", "/f", "b", "/g", true).await,
+            Err(ProviderError::NotConnected)
+        ));
+    }
 }
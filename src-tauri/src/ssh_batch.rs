@@ -0,0 +1,338 @@
+//! Batch command execution over SSH
+//!
+//! Runs one command, or a script fed to an interpreter on stdin, on many
+//! saved SSH hosts in parallel over exec channels. Each host's stdout, stderr
+//! and exit status are collected into a report (emitted per host as
+//! `ssh-batch-progress` while the batch runs) that can be exported as JSON or CSV.
+//!
+//! Connections go through the shared SSH pool, so hosts that are already open
+//! in the file browser or a terminal are not logged into again.
+
+use crate::credential_store::CredentialStore;
+use crate::ssh_shell::{self, SshLogin};
+use russh::client::Config;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Hosts contacted at the same time unless the job says otherwise
+const DEFAULT_CONCURRENCY: usize = 8;
+const MAX_CONCURRENCY: usize = 64;
+
+/// Per-host limit for connecting and running the command
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// Output kept per stream and host
+const MAX_OUTPUT: usize = 1024 * 1024;
+
+/// Interpreter used for scripts when none is given
+const DEFAULT_INTERPRETER: &str = "sh -s";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchHost {
+    /// Saved profile; its vault credentials are used when no password is given
+    #[serde(default)]
+    pub profile_id: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub host: String,
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    pub username: String,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub private_key_path: Option<String>,
+    #[serde(default)]
+    pub key_passphrase: Option<String>,
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Apply `~/.ssh/config` (default true)
+    #[serde(default)]
+    pub ssh_config: Option<bool>,
+}
+
+fn default_ssh_port() -> u16 {
+    22
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchJob {
+    /// Echoed in progress events so the frontend can tell batches apart
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Command line to run (exclusive with `script`)
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Script body, sent on stdin to `interpreter`
+    #[serde(default)]
+    pub script: Option<String>,
+    /// Defaults to `sh -s`; e.g. `bash -s`, `python3 -`
+    #[serde(default)]
+    pub interpreter: Option<String>,
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl BatchJob {
+    /// Remote command line and the bytes to send on its stdin
+    fn resolve(&self) -> Result<(String, Vec<u8>), String> {
+        let command = self.command.as_deref().map(str::trim).filter(|c| !c.is_empty());
+        let script = self.script.as_deref().filter(|s| !s.trim().is_empty());
+        match (command, script) {
+            (Some(command), None) => Ok((command.to_string(), Vec::new())),
+            (None, Some(script)) => {
+                let interpreter = self.interpreter.as_deref().map(str::trim).filter(|i| !i.is_empty());
+                Ok((interpreter.unwrap_or(DEFAULT_INTERPRETER).to_string(), script.as_bytes().to_vec()))
+            }
+            (Some(_), Some(_)) => Err("Give either a command or a script, not both".to_string()),
+            (None, None) => Err("Nothing to run".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostResult {
+    pub name: String,
+    /// `user@host:port`
+    pub target: String,
+    pub exit_status: Option<u32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Connection, authentication or timeout error (the command did not complete)
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+impl HostResult {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none() && self.exit_status == Some(0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub id: Option<String>,
+    pub command: String,
+    pub started: String,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// In the order the hosts were given
+    pub results: Vec<HostResult>,
+}
+
+#[derive(Serialize)]
+struct BatchProgress<'a> {
+    id: Option<&'a str>,
+    completed: usize,
+    total: usize,
+    result: &'a HostResult,
+}
+
+/// Password saved in the credential vault for a server profile
+fn vault_password(profile_id: &str) -> Option<String> {
+    let store = CredentialStore::from_cache()?;
+    let saved = store.get(&format!("server_{}", profile_id)).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&saved).ok()?;
+    value.get("password")?.as_str().filter(|p| !p.is_empty()).map(str::to_string)
+}
+
+async fn run_host(host: BatchHost, command: Arc<String>, input: Arc<Vec<u8>>, timeout: Duration) -> HostResult {
+    let started = Instant::now();
+    let target = format!("{}@{}:{}", host.username, host.host, host.port);
+    let name = host.name.clone().unwrap_or_else(|| host.host.clone());
+    let password = host.password.filter(|p| !p.is_empty())
+        .or_else(|| host.profile_id.as_deref().and_then(vault_password));
+    let login = SshLogin::new(
+        host.host, host.port, host.username, password, host.private_key_path, host.key_passphrase, host.proxy,
+        host.ssh_config,
+    );
+    let config = Config {
+        inactivity_timeout: Some(Duration::from_secs(120)),
+        keepalive_interval: Some(Duration::from_secs(15)),
+        keepalive_max: 3,
+        ..Default::default()
+    };
+
+    let run = async {
+        let conn = ssh_shell::connect_authenticated(&login, config).await?;
+        conn.exec_with_input(&command, &input, MAX_OUTPUT).await
+    };
+    let (exit_status, stdout, stderr, error) = match tokio::time::timeout(timeout, run).await {
+        Ok(Ok(output)) => (
+            output.exit_status,
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+            None,
+        ),
+        Ok(Err(e)) => (None, String::new(), String::new(), Some(e.to_string())),
+        Err(_) => (None, String::new(), String::new(), Some(format!("Timed out after {}s", timeout.as_secs()))),
+    };
+
+    HostResult {
+        name,
+        target,
+        exit_status,
+        stdout,
+        stderr,
+        error,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+/// Quote a CSV field when needed (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn report_to_csv(report: &BatchReport) -> String {
+    let mut csv = String::from("name,target,exit_status,error,duration_ms,stdout,stderr\n");
+    for r in &report.results {
+        let fields = [
+            csv_field(&r.name),
+            csv_field(&r.target),
+            r.exit_status.map(|s| s.to_string()).unwrap_or_default(),
+            csv_field(r.error.as_deref().unwrap_or("")),
+            r.duration_ms.to_string(),
+            csv_field(&r.stdout),
+            csv_field(&r.stderr),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Run a command or script on every host, at most `job.concurrency` at a time
+#[tauri::command]
+pub async fn ssh_batch_run(app: AppHandle, hosts: Vec<BatchHost>, job: BatchJob) -> Result<BatchReport, String> {
+    let (command, input) = job.resolve()?;
+    if hosts.is_empty() {
+        return Err("No hosts selected".to_string());
+    }
+    let concurrency = job.concurrency.unwrap_or(DEFAULT_CONCURRENCY).clamp(1, MAX_CONCURRENCY);
+    let timeout = Duration::from_secs(job.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1));
+    let started = chrono::Utc::now().to_rfc3339();
+    let total = hosts.len();
+    tracing::info!("SSH Batch: running {:?} on {} hosts ({} at a time)", command, total, concurrency);
+
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(concurrency));
+    let command = Arc::new(command);
+    let input = Arc::new(input);
+    let mut join_set = tokio::task::JoinSet::new();
+    for (index, host) in hosts.into_iter().enumerate() {
+        let sem = semaphore.clone();
+        let (command, input) = (command.clone(), input.clone());
+        join_set.spawn(async move {
+            let _permit = sem.acquire().await;
+            (index, run_host(host, command, input, timeout).await)
+        });
+    }
+
+    let mut results: Vec<(usize, HostResult)> = Vec::with_capacity(total);
+    while let Some(joined) = join_set.join_next().await {
+        let Ok((index, result)) = joined else {
+            continue;
+        };
+        let _ = app.emit("ssh-batch-progress", BatchProgress {
+            id: job.id.as_deref(),
+            completed: results.len() + 1,
+            total,
+            result: &result,
+        });
+        results.push((index, result));
+    }
+    results.sort_by_key(|(index, _)| *index);
+    let results: Vec<HostResult> = results.into_iter().map(|(_, r)| r).collect();
+
+    let succeeded = results.iter().filter(|r| r.succeeded()).count();
+    tracing::info!("SSH Batch: {} of {} hosts succeeded", succeeded, total);
+    Ok(BatchReport {
+        id: job.id,
+        command: command.to_string(),
+        started,
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+    })
+}
+
+/// Save a batch report as `json` or `csv`
+#[tauri::command]
+pub async fn ssh_batch_export(report: BatchReport, path: String, format: String) -> Result<(), String> {
+    let data = match format.as_str() {
+        "json" => serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?,
+        "csv" => report_to_csv(&report),
+        other => return Err(format!("Unsupported export format: {}", other)),
+    };
+    std::fs::write(&path, data).map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(command: Option<&str>, script: Option<&str>) -> BatchJob {
+        BatchJob {
+            id: None,
+            command: command.map(str::to_string),
+            script: script.map(str::to_string),
+            interpreter: None,
+            concurrency: None,
+            timeout_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_job() {
+        assert_eq!(job(Some(" uptime "), None).resolve().unwrap(), ("uptime".to_string(), Vec::new()));
+        assert_eq!(
+            job(None, Some("apt-get update\n")).resolve().unwrap(),
+            ("sh -s".to_string(), b"apt-get update\n".to_vec())
+        );
+        assert!(job(Some("uptime"), Some("id")).resolve().is_err());
+        assert!(job(Some("  "), None).resolve().is_err());
+    }
+
+    #[test]
+    fn test_report_to_csv() {
+        let report = BatchReport {
+            id: None,
+            command: "uname".to_string(),
+            started: String::new(),
+            succeeded: 1,
+            failed: 1,
+            results: vec![
+                HostResult {
+                    name: "web1".to_string(),
+                    target: "root@web1:22".to_string(),
+                    exit_status: Some(0),
+                    stdout: "Linux\n".to_string(),
+                    stderr: String::new(),
+                    error: None,
+                    duration_ms: 120,
+                },
+                HostResult {
+                    name: "web2".to_string(),
+                    target: "root@web2:22".to_string(),
+                    exit_status: None,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    error: Some("Authentication failed: \"root\"".to_string()),
+                    duration_ms: 30,
+                },
+            ],
+        };
+        let csv = report_to_csv(&report);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[1], "web1,root@web1:22,0,,120,\"Linux");
+        assert_eq!(lines[3], "web2,root@web2:22,,\"Authentication failed: \"\"root\"\"\",30,,");
+    }
+}
@@ -1,71 +1,99 @@
 //! SSH Remote Shell module
 //!
 //! Opens interactive shell sessions over SSH to remote servers.
-//! Reuses the same russh library and authentication flow as the SFTP provider.
-//! Each shell session has its own SSH connection.
+//! Reuses the same russh library, `~/.ssh/config` resolution and authentication
+//! chain as the SFTP provider.
+//! Sessions to a `user@host:port` that is already connected (SFTP browsing,
+//! another shell, a tunnel) run as extra channels on that connection.
 
-use russh::client::{self, Config, Handle, Handler, Msg};
-use russh::keys::{self, known_hosts, PrivateKeyWithHashAlg, PublicKey};
-use russh::client::AuthResult;
-use russh::{Channel, ChannelId, ChannelMsg, CryptoVec};
+use crate::providers::ssh_auth::SshAuthOptions;
+use crate::providers::ssh_pool::{self, SshConnection};
+use crate::providers::ssh_tunnel::SshEndpoint;
+use crate::providers::ProviderError;
+use crate::terminal_recording::{self, SessionRecorder};
+use russh::client::{Config, Msg};
+use secrecy::SecretString;
+use russh::{Channel, ChannelId, ChannelMsg};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{oneshot, Mutex};
 use tauri::{AppHandle, Emitter, State};
 
-/// SSH handler for shell connections (key verification reuses known_hosts)
-struct ShellSshHandler {
-    host: String,
-    port: u16,
+/// Connection details shared by shell sessions and tunnels
+#[derive(Clone)]
+pub(crate) struct SshLogin {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: Option<SecretString>,
+    pub private_key_path: Option<String>,
+    pub key_passphrase: Option<SecretString>,
+    /// Proxy setting as sent by the frontend (`None` follows the global setting)
+    pub proxy: Option<String>,
+    /// Apply `~/.ssh/config` (the profile's `ssh_config` flag, on by default)
+    pub use_ssh_config: bool,
 }
 
-impl ShellSshHandler {
-    fn new(host: &str, port: u16) -> Self {
-        Self { host: host.to_string(), port }
+impl SshLogin {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        host: String,
+        port: u16,
+        username: String,
+        password: Option<String>,
+        private_key_path: Option<String>,
+        key_passphrase: Option<String>,
+        proxy: Option<String>,
+        ssh_config: Option<bool>,
+    ) -> Self {
+        Self {
+            host,
+            port,
+            username,
+            password: password.filter(|p| !p.is_empty()).map(SecretString::from),
+            private_key_path: private_key_path.filter(|p| !p.is_empty()),
+            key_passphrase: key_passphrase.filter(|p| !p.is_empty()).map(SecretString::from),
+            proxy,
+            use_ssh_config: ssh_config.unwrap_or(true),
+        }
     }
 }
 
-impl Handler for ShellSshHandler {
-    type Error = russh::Error;
+/// Resolve `~/.ssh/config` aliases and ProxyJump/ProxyCommand routes as `ssh <host>` would
+/// (unless the profile turned `ssh_config` off),
+/// then reuse the pooled connection to that `user@host:port` or connect and
+/// authenticate with the same chain as SFTP:
+/// configured keys → password → agent → default keys → keyboard-interactive.
+pub(crate) async fn connect_authenticated(
+    login: &SshLogin,
+    config: Config,
+) -> Result<Arc<SshConnection>, ProviderError> {
+    let proxy = crate::providers::proxy::from_setting(login.proxy.as_deref(), None)?;
+    let endpoint = SshEndpoint::resolve(&login.host, login.port, &login.username, login.use_ssh_config, proxy)?;
 
-    async fn check_server_key(
-        &mut self,
-        server_public_key: &PublicKey,
-    ) -> Result<bool, Self::Error> {
-        match known_hosts::check_known_hosts(&self.host, self.port, server_public_key) {
-            Ok(true) => Ok(true),
-            Ok(false) => {
-                // SEC-P1-06: Host not in known_hosts — reject here.
-                // Frontend must call sftp_check_host_key + sftp_accept_host_key first.
-                tracing::warn!(
-                    "SSH Shell: Host key for {} not pre-approved via TOFU dialog — rejecting",
-                    self.host
-                );
-                Ok(false)
-            }
-            Err(keys::Error::KeyChanged { line }) => {
-                tracing::error!(
-                    "SSH Shell: REJECTING {} - host key changed at line {} (possible MITM)",
-                    self.host, line
-                );
-                Ok(false)
-            }
-            Err(e) => {
-                // SEC: Reject on unknown errors — do not silently accept.
-                tracing::error!(
-                    "SSH Shell: REJECTING {} - known_hosts verification error: {}",
-                    self.host, e
-                );
-                Ok(false)
-            }
-        }
-    }
+    let mut identity_files: Vec<String> = login.private_key_path.iter().cloned().collect();
+    identity_files.extend(endpoint.identity_files.iter().cloned());
+    let auth = SshAuthOptions {
+        label: format!("{}@{}", endpoint.username, endpoint.host),
+        username: endpoint.username.clone(),
+        password: login.password.clone(),
+        identity_files,
+        key_passphrase: login.key_passphrase.clone(),
+        certificate_path: endpoint.certificate_path.clone(),
+        use_agent: !endpoint.identities_only,
+        keyboard_interactive: true,
+    };
+    ssh_pool::connect(&endpoint, &auth, config).await
 }
 
-/// An SSH shell session with handle for writing and channel ID
+/// An SSH shell session: its (possibly shared) connection and channel ID
 struct SshShellSession {
-    handle: Handle<ShellSshHandler>,
+    conn: Arc<SshConnection>,
     channel_id: ChannelId,
+    /// Tells the read task to close the channel
+    close: Option<oneshot::Sender<()>>,
+    /// asciicast recorder when the session is being recorded
+    recorder: Option<Arc<SessionRecorder>>,
 }
 
 /// Global state for SSH shell sessions
@@ -86,7 +114,8 @@ pub fn create_ssh_shell_state() -> SshShellManager {
     Arc::new(Mutex::new(SshShellState::default()))
 }
 
-/// Open an SSH shell session to a remote server
+/// Open an SSH shell session to a remote server. With `record` (or the
+/// "record all sessions" setting) the session is saved as an asciicast file.
 #[tauri::command]
 #[allow(clippy::too_many_arguments)]
 pub async fn ssh_shell_open(
@@ -98,6 +127,9 @@ pub async fn ssh_shell_open(
     password: Option<String>,
     private_key_path: Option<String>,
     key_passphrase: Option<String>,
+    proxy: Option<String>,
+    ssh_config: Option<bool>,
+    record: Option<bool>,
 ) -> Result<String, String> {
     let config = Config {
         inactivity_timeout: Some(std::time::Duration::from_secs(120)),
@@ -106,49 +138,13 @@ pub async fn ssh_shell_open(
         ..Default::default()
     };
 
-    let addr = format!("{}:{}", host, port);
-    let mut handle = client::connect(
-        Arc::new(config),
-        &addr,
-        ShellSshHandler::new(&host, port),
-    )
-    .await
-    .map_err(|e| format!("SSH connect failed: {}", e))?;
-
-    // Authenticate
-    let mut authenticated = false;
-
-    // Try key-based auth first
-    if let Some(ref key_path) = private_key_path {
-        let path = std::path::Path::new(key_path);
-        if path.exists() {
-            let passphrase = key_passphrase.as_deref();
-            if let Ok(key_data) = std::fs::read_to_string(path) {
-                if let Ok(key) = keys::decode_secret_key(&key_data, passphrase) {
-                    let key_pair = PrivateKeyWithHashAlg::new(Arc::new(key), None);
-                    if let Ok(AuthResult::Success) = handle.authenticate_publickey(&username, key_pair).await {
-                        authenticated = true;
-                    }
-                }
-            }
-        }
-    }
-
-    // Try password auth
-    if !authenticated {
-        if let Some(ref pw) = password {
-            if let Ok(AuthResult::Success) = handle.authenticate_password(&username, pw).await {
-                authenticated = true;
-            }
-        }
-    }
-
-    if !authenticated {
-        return Err("SSH authentication failed".to_string());
-    }
+    let login = SshLogin::new(host, port, username, password, private_key_path, key_passphrase, proxy, ssh_config);
+    let conn = connect_authenticated(&login, config)
+        .await
+        .map_err(|e| format!("SSH connect failed: {}", e))?;
 
     // Open shell channel with PTY
-    let channel = handle.channel_open_session().await
+    let channel = conn.open_session().await
         .map_err(|e| format!("Open session: {}", e))?;
 
     channel.request_pty(true, "xterm-256color", 80, 24, 0, 0, &[])
@@ -159,6 +155,7 @@ pub async fn ssh_shell_open(
         .map_err(|e| format!("Request shell: {}", e))?;
 
     let channel_id = channel.id();
+    tracing::info!("SSH Shell: shell channel opened on {}", conn.key());
 
     // Generate session ID
     let session_id = {
@@ -168,24 +165,33 @@ pub async fn ssh_shell_open(
         id
     };
 
+    let recorder = if terminal_recording::should_record(record) {
+        Some(SessionRecorder::create(&session_id, &format!("SSH {}", conn.key()), 80, 24)?)
+    } else {
+        None
+    };
+
     let event_name = format!("pty-output-{}", session_id);
     let close_event = format!("ssh-shell-closed-{}", session_id);
 
-    // Store handle + channel_id for writing
+    // Store connection + channel_id for writing
+    let (close_tx, close_rx) = oneshot::channel();
     {
         let mut mgr = state.lock().await;
         mgr.sessions.insert(session_id.clone(), SshShellSession {
-            handle,
+            conn,
             channel_id,
+            close: Some(close_tx),
+            recorder: recorder.clone(),
         });
     }
 
-    // Spawn read task — channel is consumed here, writing goes through Handle.data()
+    // Spawn read task — channel is consumed here, writing goes through the connection handle
     let app_clone = app.clone();
     let session_id_clone = session_id.clone();
     let state_clone = state.inner().clone();
     tokio::spawn(async move {
-        read_channel(channel, &app_clone, &event_name).await;
+        read_channel(channel, close_rx, &app_clone, &event_name, recorder.as_deref()).await;
         let _ = app_clone.emit(&close_event, "closed");
         let mut mgr = state_clone.lock().await;
         mgr.sessions.remove(&session_id_clone);
@@ -194,16 +200,28 @@ pub async fn ssh_shell_open(
     Ok(format!("SSH shell opened [session:{}]", session_id))
 }
 
-/// Read loop for an SSH shell channel
-async fn read_channel(mut channel: Channel<Msg>, app: &AppHandle, event_name: &str) {
+/// Read loop for an SSH shell channel; closes the channel when asked to
+async fn read_channel(
+    mut channel: Channel<Msg>,
+    mut close: oneshot::Receiver<()>,
+    app: &AppHandle,
+    event_name: &str,
+    recorder: Option<&SessionRecorder>,
+) {
     loop {
-        match channel.wait().await {
-            Some(ChannelMsg::Data { data }) => {
-                let text = String::from_utf8_lossy(&data).to_string();
-                let _ = app.emit(event_name, text);
+        let msg = tokio::select! {
+            msg = channel.wait() => msg,
+            _ = &mut close => {
+                let _ = channel.close().await;
+                break;
             }
-            Some(ChannelMsg::ExtendedData { data, .. }) => {
+        };
+        match msg {
+            Some(ChannelMsg::Data { data }) | Some(ChannelMsg::ExtendedData { data, .. }) => {
                 let text = String::from_utf8_lossy(&data).to_string();
+                if let Some(recorder) = recorder {
+                    recorder.output(&text);
+                }
                 let _ = app.emit(event_name, text);
             }
             Some(ChannelMsg::Eof) | Some(ChannelMsg::Close) | None => {
@@ -225,10 +243,13 @@ pub async fn ssh_shell_write(
     let session = mgr.sessions.get(&session_id)
         .ok_or("SSH shell session not found")?;
 
-    let cv = CryptoVec::from(data.as_bytes());
-    session.handle.data(session.channel_id, cv)
+    if let Some(recorder) = &session.recorder {
+        recorder.input(&data);
+    }
+
+    session.conn.data(session.channel_id, data.as_bytes())
         .await
-        .map_err(|_| "SSH write error".to_string())?;
+        .map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -240,14 +261,19 @@ pub async fn ssh_shell_write(
 /// if the server doesn't support it through the current API.
 #[tauri::command]
 pub async fn ssh_shell_resize(
-    _state: State<'_, SshShellManager>,
-    _session_id: String,
-    _cols: u32,
-    _rows: u32,
+    state: State<'_, SshShellManager>,
+    session_id: String,
+    cols: u32,
+    rows: u32,
 ) -> Result<(), String> {
     // window_change is only available on Channel/ChannelWriteHalf which is consumed by the read task.
     // This is a known limitation — the terminal will use the initial 80x24 size.
     // A future refactor could use Channel::split() if russh exposes ChannelWriteHalf publicly.
+    // The recording still follows the local terminal, so it replays at the size the user saw.
+    let mgr = state.lock().await;
+    if let Some(recorder) = mgr.sessions.get(&session_id).and_then(|s| s.recorder.as_ref()) {
+        recorder.resize(cols, rows);
+    }
     Ok(())
 }
 
@@ -258,8 +284,17 @@ pub async fn ssh_shell_close(
     session_id: String,
 ) -> Result<(), String> {
     let mut mgr = state.lock().await;
-    if let Some(session) = mgr.sessions.remove(&session_id) {
-        let _ = session.handle.disconnect(russh::Disconnect::ByApplication, "", "en").await;
+    if let Some(mut session) = mgr.sessions.remove(&session_id) {
+        // Close only our channel; the connection closes once its other users are gone
+        if let Some(close) = session.close.take() {
+            let _ = close.send(());
+        }
     }
     Ok(())
 }
+
+/// SSH connections currently shared between SFTP, shells and tunnels (`user@host:port`)
+#[tauri::command]
+pub async fn ssh_shared_connections() -> Result<Vec<String>, String> {
+    Ok(ssh_pool::open_connections())
+}
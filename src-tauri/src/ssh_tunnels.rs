@@ -0,0 +1,737 @@
+//! SSH port forwarding manager
+//!
+//! Local (`-L`), remote (`-R`) and dynamic SOCKS5 (`-D`) forwards over the same
+//! russh connection setup as the SSH shell (`~/.ssh/config`, jump hosts,
+//! authentication chain, known_hosts check).
+//!
+//! Tunnel definitions are saved per profile in `ssh_tunnels.json`. Each running
+//! tunnel has a supervisor task that reconnects with exponential backoff when
+//! the connection drops; tunnels, shells and SFTP sessions to the same
+//! `user@host:port` share one SSH connection (see `ssh_pool`). Traffic
+//! counters are kept per tunnel and state changes are emitted as
+//! `ssh-tunnel-status` events.
+
+use crate::providers::ssh_pool::SshConnection;
+use crate::providers::ProviderError;
+use crate::ssh_shell::{self, SshLogin};
+use russh::client::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{info, warn};
+
+/// First and longest wait between reconnection attempts
+const RECONNECT_MIN_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// How often a running tunnel checks whether its SSH connection is still up
+const CONNECTION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Relay buffer size per direction
+const RELAY_BUFFER_SIZE: usize = 32 * 1024;
+
+// SOCKS5 (RFC 1928)
+const SOCKS_VERSION: u8 = 0x05;
+const SOCKS_NO_AUTH: u8 = 0x00;
+const SOCKS_NO_ACCEPTABLE_METHOD: u8 = 0xFF;
+const SOCKS_CMD_CONNECT: u8 = 0x01;
+const SOCKS_ATYP_IPV4: u8 = 0x01;
+const SOCKS_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS_ATYP_IPV6: u8 = 0x04;
+const SOCKS_REPLY_SUCCEEDED: u8 = 0x00;
+const SOCKS_REPLY_HOST_UNREACHABLE: u8 = 0x04;
+const SOCKS_REPLY_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const SOCKS_REPLY_ADDRESS_NOT_SUPPORTED: u8 = 0x08;
+
+/// Forwarding direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelKind {
+    /// `-L`: listen locally, connect to the target from the SSH server
+    Local,
+    /// `-R`: listen on the SSH server, connect to the target from this machine
+    Remote,
+    /// `-D`: local SOCKS5 proxy, every destination reached from the SSH server
+    Dynamic,
+}
+
+/// A saved tunnel definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TunnelSpec {
+    /// Assigned on first save
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub kind: TunnelKind,
+    /// Listening address (local for `-L`/`-D`, on the server for `-R`)
+    #[serde(default)]
+    pub bind_host: String,
+    /// Listening port; 0 lets the OS (or the server for `-R`) choose
+    pub bind_port: u16,
+    /// Destination host (unused for dynamic tunnels)
+    #[serde(default)]
+    pub target_host: String,
+    #[serde(default)]
+    pub target_port: u16,
+    /// Reconnect when the SSH connection drops
+    #[serde(default = "default_true")]
+    pub auto_reconnect: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl TunnelSpec {
+    /// Fill in default bind addresses and reject incomplete definitions
+    fn normalize(mut self) -> Result<Self, String> {
+        if self.bind_host.trim().is_empty() {
+            // Loopback only, as OpenSSH does without GatewayPorts
+            self.bind_host = match self.kind {
+                TunnelKind::Remote => "localhost".to_string(),
+                _ => "127.0.0.1".to_string(),
+            };
+        }
+        self.bind_host = self.bind_host.trim().to_string();
+        self.target_host = self.target_host.trim().to_string();
+        if self.kind != TunnelKind::Dynamic && (self.target_host.is_empty() || self.target_port == 0) {
+            return Err("Tunnel target host and port are required".to_string());
+        }
+        if self.kind == TunnelKind::Dynamic {
+            self.target_host.clear();
+            self.target_port = 0;
+        }
+        if self.id.is_empty() {
+            self.id = uuid::Uuid::new_v4().to_string();
+        }
+        if self.name.trim().is_empty() {
+            self.name = self.describe();
+        }
+        Ok(self)
+    }
+
+    /// OpenSSH-style summary, e.g. `L 127.0.0.1:5432 → db:5432`
+    fn describe(&self) -> String {
+        match self.kind {
+            TunnelKind::Local => format!("L {}:{} → {}:{}", self.bind_host, self.bind_port, self.target_host, self.target_port),
+            TunnelKind::Remote => format!("R {}:{} → {}:{}", self.bind_host, self.bind_port, self.target_host, self.target_port),
+            TunnelKind::Dynamic => format!("D {}:{}", self.bind_host, self.bind_port),
+        }
+    }
+}
+
+// ============ Persistence ============
+
+/// Saved tunnels keyed by profile id
+type SavedTunnels = BTreeMap<String, Vec<TunnelSpec>>;
+
+/// Serializes the load-modify-save cycles on `ssh_tunnels.json`
+static SAVED_TUNNELS_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// `~/.config/aeroftp/ssh_tunnels.json`, never relative to the working directory
+fn tunnels_path() -> Result<PathBuf, String> {
+    dirs::config_dir()
+        .map(|dir| dir.join("aeroftp").join("ssh_tunnels.json"))
+        .ok_or_else(|| "Cannot determine the config directory".to_string())
+}
+
+fn load_saved_tunnels() -> Result<SavedTunnels, String> {
+    Ok(std::fs::read_to_string(tunnels_path()?)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default())
+}
+
+fn save_tunnels(all: &SavedTunnels) -> Result<(), String> {
+    let path = tunnels_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(all).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// Change the saved tunnels while holding the lock, so concurrent edits are not lost
+fn update_saved_tunnels<T>(f: impl FnOnce(&mut SavedTunnels) -> T) -> Result<T, String> {
+    let _guard = SAVED_TUNNELS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut all = load_saved_tunnels()?;
+    let result = f(&mut all);
+    save_tunnels(&all)?;
+    Ok(result)
+}
+
+// ============ Runtime state ============
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelState {
+    Connecting,
+    Running,
+    Reconnecting,
+    Failed,
+    Stopped,
+}
+
+/// Live traffic counters of one tunnel
+#[derive(Default)]
+struct TunnelStats {
+    /// Bytes from local clients (or the local target for `-R`) into the tunnel
+    bytes_sent: AtomicU64,
+    /// Bytes from the tunnel back to the local side
+    bytes_received: AtomicU64,
+    connections: AtomicU64,
+    active_connections: AtomicU64,
+}
+
+/// Snapshot returned to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct TunnelStatus {
+    pub profile_id: String,
+    pub tunnel: TunnelSpec,
+    pub state: TunnelState,
+    /// Actual listening port (differs from `bind_port` when it was 0)
+    pub bound_port: Option<u16>,
+    pub error: Option<String>,
+    pub reconnects: u32,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub connections: u64,
+    pub active_connections: u64,
+}
+
+struct RuntimeInfo {
+    state: TunnelState,
+    bound_port: Option<u16>,
+    error: Option<String>,
+    reconnects: u32,
+}
+
+/// Shared between the manager and the supervisor task of a tunnel
+struct TunnelRuntime {
+    profile_id: String,
+    spec: TunnelSpec,
+    stats: Arc<TunnelStats>,
+    info: std::sync::Mutex<RuntimeInfo>,
+    app: AppHandle,
+}
+
+impl TunnelRuntime {
+    fn snapshot(&self) -> TunnelStatus {
+        let info = self.info.lock().unwrap_or_else(|e| e.into_inner());
+        TunnelStatus {
+            profile_id: self.profile_id.clone(),
+            tunnel: self.spec.clone(),
+            state: info.state,
+            bound_port: info.bound_port,
+            error: info.error.clone(),
+            reconnects: info.reconnects,
+            bytes_sent: self.stats.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.stats.bytes_received.load(Ordering::Relaxed),
+            connections: self.stats.connections.load(Ordering::Relaxed),
+            active_connections: self.stats.active_connections.load(Ordering::Relaxed),
+        }
+    }
+
+    fn state(&self) -> TunnelState {
+        self.info.lock().unwrap_or_else(|e| e.into_inner()).state
+    }
+
+    fn update(&self, f: impl FnOnce(&mut RuntimeInfo)) {
+        f(&mut self.info.lock().unwrap_or_else(|e| e.into_inner()));
+        let _ = self.app.emit("ssh-tunnel-status", self.snapshot());
+    }
+
+    fn set_state(&self, state: TunnelState, error: Option<String>) {
+        self.update(|info| {
+            info.state = state;
+            info.error = error;
+            if state != TunnelState::Running {
+                info.bound_port = None;
+            }
+        });
+    }
+
+    fn set_running(&self, bound_port: u16) {
+        self.update(|info| {
+            info.state = TunnelState::Running;
+            info.bound_port = Some(bound_port);
+            info.error = None;
+        });
+    }
+}
+
+struct ActiveTunnel {
+    runtime: Arc<TunnelRuntime>,
+    task: JoinHandle<()>,
+}
+
+/// Global state for running tunnels
+#[derive(Default)]
+pub struct SshTunnelState {
+    /// Running tunnels keyed by tunnel id
+    active: HashMap<String, ActiveTunnel>,
+}
+
+pub type SshTunnelManager = Arc<Mutex<SshTunnelState>>;
+
+pub fn create_ssh_tunnel_state() -> SshTunnelManager {
+    Arc::new(Mutex::new(SshTunnelState::default()))
+}
+
+// ============ SSH connection ============
+
+fn tunnel_ssh_config() -> Config {
+    Config {
+        // Idle tunnels stay open; dead connections are detected by keepalives
+        inactivity_timeout: None,
+        keepalive_interval: Some(Duration::from_secs(15)),
+        keepalive_max: 3,
+        nodelay: true,
+        ..Default::default()
+    }
+}
+
+/// Why a tunnel run ended
+enum RunEnd {
+    /// Connection lost or failed; worth retrying
+    Retry(String),
+    /// Configuration or authentication problem; retrying will not help
+    Fatal(String),
+}
+
+/// Copy one direction, counting bytes as they pass
+async fn pump<R, W>(mut reader: R, mut writer: W, counter: &AtomicU64) -> std::io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = vec![0u8; RELAY_BUFFER_SIZE];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return writer.shutdown().await;
+        }
+        writer.write_all(&buf[..n]).await?;
+        counter.fetch_add(n as u64, Ordering::Relaxed);
+    }
+}
+
+/// Relay between a local socket and an SSH channel until both sides close
+async fn relay<L, R>(local: L, remote: R, stats: &TunnelStats)
+where
+    L: AsyncRead + AsyncWrite,
+    R: AsyncRead + AsyncWrite,
+{
+    stats.connections.fetch_add(1, Ordering::Relaxed);
+    stats.active_connections.fetch_add(1, Ordering::Relaxed);
+    let (local_read, local_write) = tokio::io::split(local);
+    let (remote_read, remote_write) = tokio::io::split(remote);
+    let _ = tokio::join!(
+        pump(local_read, remote_write, &stats.bytes_sent),
+        pump(remote_read, local_write, &stats.bytes_received),
+    );
+    stats.active_connections.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Read a SOCKS5 greeting and CONNECT request, returning the requested destination.
+/// Only the "no authentication" method is offered: the listener is local.
+async fn socks5_accept<S>(stream: &mut S) -> std::io::Result<(String, u16)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg.to_string());
+
+    let mut greeting = [0u8; 2];
+    stream.read_exact(&mut greeting).await?;
+    if greeting[0] != SOCKS_VERSION {
+        return Err(invalid("not a SOCKS5 client"));
+    }
+    let mut methods = vec![0u8; greeting[1] as usize];
+    stream.read_exact(&mut methods).await?;
+    if !methods.contains(&SOCKS_NO_AUTH) {
+        stream.write_all(&[SOCKS_VERSION, SOCKS_NO_ACCEPTABLE_METHOD]).await?;
+        return Err(invalid("SOCKS5 client requires authentication"));
+    }
+    stream.write_all(&[SOCKS_VERSION, SOCKS_NO_AUTH]).await?;
+
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await?;
+    if request[0] != SOCKS_VERSION {
+        return Err(invalid("not a SOCKS5 request"));
+    }
+    if request[1] != SOCKS_CMD_CONNECT {
+        socks5_reply(stream, SOCKS_REPLY_COMMAND_NOT_SUPPORTED).await?;
+        return Err(invalid("only SOCKS5 CONNECT is supported"));
+    }
+    let host = match request[3] {
+        SOCKS_ATYP_IPV4 => {
+            let mut addr = [0u8; 4];
+            stream.read_exact(&mut addr).await?;
+            Ipv4Addr::from(addr).to_string()
+        }
+        SOCKS_ATYP_IPV6 => {
+            let mut addr = [0u8; 16];
+            stream.read_exact(&mut addr).await?;
+            Ipv6Addr::from(addr).to_string()
+        }
+        SOCKS_ATYP_DOMAIN => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await?;
+            let mut name = vec![0u8; len[0] as usize];
+            stream.read_exact(&mut name).await?;
+            String::from_utf8(name).map_err(|_| invalid("invalid SOCKS5 host name"))?
+        }
+        _ => {
+            socks5_reply(stream, SOCKS_REPLY_ADDRESS_NOT_SUPPORTED).await?;
+            return Err(invalid("unsupported SOCKS5 address type"));
+        }
+    };
+    let mut port = [0u8; 2];
+    stream.read_exact(&mut port).await?;
+    Ok((host, u16::from_be_bytes(port)))
+}
+
+/// Send a SOCKS5 reply (the bound address is not meaningful through SSH)
+async fn socks5_reply<S: AsyncWrite + Unpin>(stream: &mut S, code: u8) -> std::io::Result<()> {
+    stream.write_all(&[SOCKS_VERSION, code, 0, SOCKS_ATYP_IPV4, 0, 0, 0, 0, 0, 0]).await
+}
+
+/// Serve one client of a local or dynamic tunnel
+async fn serve_client(
+    mut socket: TcpStream,
+    peer: std::net::SocketAddr,
+    conn: Arc<SshConnection>,
+    spec: TunnelSpec,
+    stats: Arc<TunnelStats>,
+) {
+    let _ = socket.set_nodelay(true);
+    let (host, port) = match spec.kind {
+        TunnelKind::Dynamic => match socks5_accept(&mut socket).await {
+            Ok(target) => target,
+            Err(e) => {
+                warn!("SSH Tunnel: SOCKS5 handshake from {} failed: {}", peer, e);
+                return;
+            }
+        },
+        _ => (spec.target_host.clone(), spec.target_port),
+    };
+
+    let channel = match conn.open_direct_tcpip(&host, port, &peer.ip().to_string(), peer.port()).await {
+        Ok(channel) => channel,
+        Err(e) => {
+            warn!("SSH Tunnel: {}", e);
+            if spec.kind == TunnelKind::Dynamic {
+                let _ = socks5_reply(&mut socket, SOCKS_REPLY_HOST_UNREACHABLE).await;
+            }
+            return;
+        }
+    };
+    if spec.kind == TunnelKind::Dynamic && socks5_reply(&mut socket, SOCKS_REPLY_SUCCEEDED).await.is_err() {
+        return;
+    }
+    relay(socket, channel.into_stream(), &stats).await;
+}
+
+/// Resolves once the SSH connection has closed
+async fn wait_closed(conn: &SshConnection) {
+    while !conn.is_closed().await {
+        tokio::time::sleep(CONNECTION_POLL_INTERVAL).await;
+    }
+}
+
+/// Connect, set up the forward and serve it until the connection drops.
+/// Client connections run in a `JoinSet` owned by the accept loop, so they are
+/// aborted with it when the connection drops or the tunnel is stopped.
+async fn run_once(login: &SshLogin, runtime: &TunnelRuntime) -> RunEnd {
+    let spec = &runtime.spec;
+    let conn = match ssh_shell::connect_authenticated(login, tunnel_ssh_config()).await {
+        Ok(conn) => conn,
+        Err(e @ ProviderError::AuthenticationFailed(_)) => return RunEnd::Fatal(e.to_string()),
+        Err(e) => return RunEnd::Retry(e.to_string()),
+    };
+
+    match spec.kind {
+        TunnelKind::Remote => {
+            let mut forward = match conn.forward_remote(&spec.bind_host, spec.bind_port).await {
+                Ok(forward) => forward,
+                Err(e) => return RunEnd::Fatal(e.to_string()),
+            };
+            runtime.set_running(forward.port());
+            info!("SSH Tunnel: {} listening on server port {}", spec.describe(), forward.port());
+
+            let accept = async {
+                let mut clients = JoinSet::new();
+                loop {
+                    tokio::select! {
+                        channel = forward.accept() => {
+                            let Some(channel) = channel else { break };
+                            let (host, port) = (spec.target_host.clone(), spec.target_port);
+                            let stats = runtime.stats.clone();
+                            clients.spawn(async move {
+                                match TcpStream::connect((host.as_str(), port)).await {
+                                    Ok(socket) => relay(socket, channel.into_stream(), &stats).await,
+                                    Err(e) => warn!("SSH Tunnel: forwarded connection to {}:{} failed: {}", host, port, e),
+                                }
+                            });
+                        }
+                        Some(_) = clients.join_next() => {}
+                    }
+                }
+            };
+            tokio::select! {
+                _ = accept => {}
+                _ = wait_closed(&conn) => {}
+            }
+        }
+        TunnelKind::Local | TunnelKind::Dynamic => {
+            let listener = match TcpListener::bind((spec.bind_host.as_str(), spec.bind_port)).await {
+                Ok(listener) => listener,
+                Err(e) => return RunEnd::Fatal(format!("Cannot listen on {}:{}: {}", spec.bind_host, spec.bind_port, e)),
+            };
+            let bound_port = listener.local_addr().map(|a| a.port()).unwrap_or(spec.bind_port);
+            runtime.set_running(bound_port);
+            info!("SSH Tunnel: {} listening on local port {}", spec.describe(), bound_port);
+
+            let accept = async {
+                let mut clients = JoinSet::new();
+                loop {
+                    tokio::select! {
+                        accepted = listener.accept() => match accepted {
+                            Ok((socket, peer)) => {
+                                clients.spawn(serve_client(socket, peer, conn.clone(), spec.clone(), runtime.stats.clone()));
+                            }
+                            Err(e) => {
+                                warn!("SSH Tunnel: accept failed: {}", e);
+                                tokio::time::sleep(Duration::from_millis(100)).await;
+                            }
+                        },
+                        Some(_) = clients.join_next() => {}
+                    }
+                }
+            };
+            tokio::select! {
+                _ = accept => {}
+                _ = wait_closed(&conn) => {}
+            }
+        }
+    }
+    RunEnd::Retry("SSH connection lost".to_string())
+}
+
+/// Run a tunnel until it is stopped, reconnecting with exponential backoff
+async fn supervise(login: SshLogin, runtime: Arc<TunnelRuntime>) {
+    let mut delay = RECONNECT_MIN_DELAY;
+    loop {
+        runtime.set_state(TunnelState::Connecting, None);
+        let end = run_once(&login, &runtime).await;
+        let was_running = runtime.state() == TunnelState::Running;
+        let error = match end {
+            RunEnd::Fatal(e) => {
+                warn!("SSH Tunnel {}: {}", runtime.spec.describe(), e);
+                runtime.set_state(TunnelState::Failed, Some(e));
+                return;
+            }
+            RunEnd::Retry(e) => e,
+        };
+        if !runtime.spec.auto_reconnect {
+            runtime.set_state(TunnelState::Failed, Some(error));
+            return;
+        }
+        if was_running {
+            delay = RECONNECT_MIN_DELAY;
+        }
+        warn!("SSH Tunnel {}: {} — reconnecting in {}s", runtime.spec.describe(), error, delay.as_secs());
+        runtime.update(|info| {
+            info.state = TunnelState::Reconnecting;
+            info.error = Some(error);
+            info.bound_port = None;
+            info.reconnects += 1;
+        });
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+    }
+}
+
+// ============ Commands ============
+
+/// Tunnels saved for a profile
+#[tauri::command]
+pub async fn ssh_tunnel_list_saved(profile_id: String) -> Result<Vec<TunnelSpec>, String> {
+    Ok(load_saved_tunnels()?.remove(&profile_id).unwrap_or_default())
+}
+
+/// Add or update a saved tunnel; returns it with its id and defaults filled in
+#[tauri::command]
+pub async fn ssh_tunnel_save(profile_id: String, tunnel: TunnelSpec) -> Result<TunnelSpec, String> {
+    let tunnel = tunnel.normalize()?;
+    update_saved_tunnels(|all| {
+        let list = all.entry(profile_id).or_default();
+        match list.iter_mut().find(|t| t.id == tunnel.id) {
+            Some(existing) => *existing = tunnel.clone(),
+            None => list.push(tunnel.clone()),
+        }
+    })?;
+    Ok(tunnel)
+}
+
+/// Delete a saved tunnel, stopping it if it is running
+#[tauri::command]
+pub async fn ssh_tunnel_delete(
+    state: State<'_, SshTunnelManager>,
+    profile_id: String,
+    tunnel_id: String,
+) -> Result<(), String> {
+    stop_tunnel(&state, &tunnel_id).await;
+    update_saved_tunnels(|all| {
+        if let Some(list) = all.get_mut(&profile_id) {
+            list.retain(|t| t.id != tunnel_id);
+            if list.is_empty() {
+                all.remove(&profile_id);
+            }
+        }
+    })
+}
+
+/// Start a saved tunnel of a profile with the profile's SSH credentials
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ssh_tunnel_start(
+    app: AppHandle,
+    state: State<'_, SshTunnelManager>,
+    profile_id: String,
+    tunnel_id: String,
+    host: String,
+    port: u16,
+    username: String,
+    password: Option<String>,
+    private_key_path: Option<String>,
+    key_passphrase: Option<String>,
+    proxy: Option<String>,
+    ssh_config: Option<bool>,
+) -> Result<TunnelStatus, String> {
+    let spec = load_saved_tunnels()?
+        .remove(&profile_id)
+        .and_then(|list| list.into_iter().find(|t| t.id == tunnel_id))
+        .ok_or("Tunnel not found")?;
+
+    let mut mgr = state.lock().await;
+    if let Some(active) = mgr.active.get(&tunnel_id) {
+        if !active.task.is_finished() {
+            return Ok(active.runtime.snapshot());
+        }
+    }
+
+    let runtime = Arc::new(TunnelRuntime {
+        profile_id,
+        spec,
+        stats: Arc::new(TunnelStats::default()),
+        info: std::sync::Mutex::new(RuntimeInfo {
+            state: TunnelState::Connecting,
+            bound_port: None,
+            error: None,
+            reconnects: 0,
+        }),
+        app,
+    });
+    let login = SshLogin::new(host, port, username, password, private_key_path, key_passphrase, proxy, ssh_config);
+    let task = tokio::spawn(supervise(login, runtime.clone()));
+    let status = runtime.snapshot();
+    mgr.active.insert(tunnel_id, ActiveTunnel { runtime, task });
+    Ok(status)
+}
+
+async fn stop_tunnel(state: &SshTunnelManager, tunnel_id: &str) -> Option<TunnelStatus> {
+    let active = state.lock().await.active.remove(tunnel_id)?;
+    // Aborting the supervisor drops the listener, the client connections' JoinSet
+    // (aborting every relay) and with them all references to the shared connection
+    active.task.abort();
+    let _ = active.task.await;
+    active.runtime.set_state(TunnelState::Stopped, None);
+    Some(active.runtime.snapshot())
+}
+
+/// Stop a running tunnel
+#[tauri::command]
+pub async fn ssh_tunnel_stop(
+    state: State<'_, SshTunnelManager>,
+    tunnel_id: String,
+) -> Result<(), String> {
+    stop_tunnel(&state, &tunnel_id).await;
+    Ok(())
+}
+
+/// State and traffic counters of running tunnels (optionally of one profile)
+#[tauri::command]
+pub async fn ssh_tunnel_status(
+    state: State<'_, SshTunnelManager>,
+    profile_id: Option<String>,
+) -> Result<Vec<TunnelStatus>, String> {
+    let mgr = state.lock().await;
+    let mut list: Vec<TunnelStatus> = mgr.active.values()
+        .filter(|a| profile_id.as_ref().map_or(true, |p| &a.runtime.profile_id == p))
+        .map(|a| a.runtime.snapshot())
+        .collect();
+    list.sort_by(|a, b| a.tunnel.name.cmp(&b.tunnel.name));
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tunnel_spec_normalize() {
+        let spec: TunnelSpec = serde_json::from_str(
+            r#"{"kind":"local","bind_port":15432,"target_host":" db.internal ","target_port":5432}"#
+        ).unwrap();
+        let spec = spec.normalize().unwrap();
+        assert!(!spec.id.is_empty());
+        assert!(spec.auto_reconnect);
+        assert_eq!(spec.bind_host, "127.0.0.1");
+        assert_eq!(spec.target_host, "db.internal");
+        assert_eq!(spec.name, "L 127.0.0.1:15432 → db.internal:5432");
+
+        let remote: TunnelSpec = serde_json::from_str(r#"{"kind":"remote","bind_port":8080}"#).unwrap();
+        assert!(remote.normalize().is_err());
+
+        let dynamic: TunnelSpec = serde_json::from_str(r#"{"kind":"dynamic","bind_port":1080,"target_port":22}"#).unwrap();
+        let dynamic = dynamic.normalize().unwrap();
+        assert_eq!(dynamic.target_port, 0);
+        assert_eq!(dynamic.describe(), "D 127.0.0.1:1080");
+    }
+
+    #[tokio::test]
+    async fn test_socks5_handshake() {
+        let (mut client, mut server) = tokio::io::duplex(256);
+        let proxy = tokio::spawn(async move { socks5_accept(&mut server).await.unwrap() });
+
+        client.write_all(&[5, 2, 0x02, 0x00]).await.unwrap();
+        let mut choice = [0u8; 2];
+        client.read_exact(&mut choice).await.unwrap();
+        assert_eq!(choice, [5, 0]);
+
+        let mut request = vec![5, 1, 0, SOCKS_ATYP_DOMAIN, 11];
+        request.extend_from_slice(b"db.internal");
+        request.extend_from_slice(&5432u16.to_be_bytes());
+        client.write_all(&request).await.unwrap();
+        assert_eq!(proxy.await.unwrap(), ("db.internal".to_string(), 5432));
+
+        let (mut client, mut server) = tokio::io::duplex(256);
+        let proxy = tokio::spawn(async move { socks5_accept(&mut server).await });
+        client.write_all(&[5, 1, 0, 5, 2, 0, SOCKS_ATYP_IPV4, 10, 0, 0, 1, 0, 80]).await.unwrap();
+        let mut reply = [0u8; 12];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply[3], SOCKS_REPLY_COMMAND_NOT_SUPPORTED);
+        assert!(proxy.await.unwrap().is_err());
+    }
+}
@@ -43,6 +43,16 @@ pub struct FileInfo {
     pub modified: Option<DateTime<Utc>>,
     pub is_dir: bool,
     pub checksum: Option<String>,
+    /// Algorithm of `checksum`; `None` is the SHA-256 of local scans
+    #[serde(default)]
+    pub checksum_algorithm: Option<crate::providers::HashAlgorithm>,
+    /// `checksum` comes from an ETag that may not be a digest of the content:
+    /// a match proves equality, a mismatch proves nothing
+    #[serde(default)]
+    pub checksum_weak: bool,
+    /// Target of a symbolic link scanned with `SymlinkMode::CopyAsLink`
+    #[serde(default)]
+    pub link_target: Option<String>,
 }
 
 /// Result of comparing a single file/directory
@@ -70,6 +80,9 @@ pub struct CompareOptions {
     pub exclude_patterns: Vec<String>,
     /// Direction of comparison
     pub direction: SyncDirection,
+    /// How symbolic links are scanned on both sides
+    #[serde(default)]
+    pub symlink_mode: SymlinkMode,
 }
 
 impl Default for CompareOptions {
@@ -89,10 +102,25 @@ impl Default for CompareOptions {
                 "target".to_string(),
             ],
             direction: SyncDirection::Bidirectional,
+            symlink_mode: SymlinkMode::default(),
         }
     }
 }
 
+/// How symbolic links are handled when scanning for sync
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkMode {
+    /// Sync what the link points to; links leaving the sync root or looping
+    /// back into their own ancestors are skipped
+    Follow,
+    /// Recreate the link itself on the other side (e.g. a `current/` release link)
+    CopyAsLink,
+    /// Leave links out of the comparison (H22: no exfiltration through links)
+    #[default]
+    Skip,
+}
+
 /// Direction of synchronization
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -206,6 +234,32 @@ pub fn compare_timestamps(local: Option<DateTime<Utc>>, remote: Option<DateTime<
     }
 }
 
+/// Resolve a remote symlink `target` found in `dir` to an absolute path,
+/// collapsing `.` and `..` without touching the server
+pub fn resolve_link_path(dir: &str, target: &str) -> String {
+    let joined = if target.starts_with('/') {
+        target.to_string()
+    } else {
+        format!("{}/{}", dir.trim_end_matches('/'), target)
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for part in joined.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
+/// Algorithm of a file's checksum, SHA-256 unless stated otherwise
+fn checksum_algorithm(file: &FileInfo) -> crate::providers::HashAlgorithm {
+    file.checksum_algorithm.unwrap_or(crate::providers::HashAlgorithm::Sha256)
+}
+
 /// Compare a single file pair and determine status
 pub fn compare_file_pair(
     local: Option<&FileInfo>,
@@ -219,18 +273,28 @@ pub fn compare_file_pair(
         (Some(l), Some(r)) => {
             // Both exist - compare attributes
 
+            // Links copied as links are equal when they point to the same place
+            if l.link_target.is_some() || r.link_target.is_some() {
+                if l.link_target == r.link_target {
+                    return SyncStatus::Identical;
+                }
+                return compare_timestamps(l.modified, r.modified).unwrap_or(SyncStatus::Conflict);
+            }
+
             // ──── Checksum Comparison (when enabled) ────
-            // Local file checksums are computed via SHA-256 in get_local_files_recursive
-            // when options.compare_checksum is true. Remote checksums are provider-dependent
-            // and may not always be available.
-            if options.compare_checksum {
+            // Local file checksums are computed via SHA-256 in get_local_files_recursive,
+            // or with the provider's own algorithm when a listing carries content hashes.
+            // Hashes of different algorithms cannot be compared.
+            if options.compare_checksum && checksum_algorithm(l) == checksum_algorithm(r) {
                 match (&l.checksum, &r.checksum) {
                     (Some(l_hash), Some(r_hash)) => {
                         if l_hash == r_hash {
                             return SyncStatus::Identical;
                         }
-                        // Hashes differ - determine which is newer by timestamp
-                        if options.compare_timestamp {
+                        if l.checksum_weak || r.checksum_weak {
+                            // Not necessarily a content hash: fall through to size/timestamp
+                        } else if options.compare_timestamp {
+                            // Hashes differ - determine which is newer by timestamp
                             return compare_timestamps(l.modified, r.modified)
                                 .unwrap_or(SyncStatus::Conflict);
                         } else {
@@ -363,7 +427,7 @@ pub fn generate_sync_reason(
                     parts.push(format!("local: {} bytes, remote: {} bytes", l.size, r.size));
                 }
                 if let (Some(lc), Some(rc)) = (&l.checksum, &r.checksum) {
-                    if lc != rc {
+                    if lc != rc && checksum_algorithm(l) == checksum_algorithm(r) {
                         parts.push("checksums differ".to_string());
                     }
                 }
@@ -1170,6 +1234,9 @@ pub struct SyncProfile {
     /// Compression mode for transfers
     #[serde(default)]
     pub compression_mode: crate::transfer_pool::CompressionMode,
+    /// How symbolic links are scanned and transferred
+    #[serde(default)]
+    pub symlink_mode: SymlinkMode,
 }
 
 fn default_parallel_streams() -> u8 {
@@ -1196,6 +1263,7 @@ impl SyncProfile {
             delete_orphans: true,
             parallel_streams: 3,
             compression_mode: crate::transfer_pool::CompressionMode::Off,
+            symlink_mode: SymlinkMode::Skip,
         }
     }
 
@@ -1218,6 +1286,7 @@ impl SyncProfile {
             delete_orphans: false,
             parallel_streams: 3,
             compression_mode: crate::transfer_pool::CompressionMode::Off,
+            symlink_mode: SymlinkMode::Skip,
         }
     }
 
@@ -1243,6 +1312,7 @@ impl SyncProfile {
             delete_orphans: false,
             parallel_streams: 1,
             compression_mode: crate::transfer_pool::CompressionMode::Off,
+            symlink_mode: SymlinkMode::Skip,
         }
     }
 
@@ -1410,6 +1480,8 @@ pub struct SyncTemplateProfile {
     pub parallel_streams: u8,
     #[serde(default)]
     pub compression_mode: crate::transfer_pool::CompressionMode,
+    #[serde(default)]
+    pub symlink_mode: SymlinkMode,
 }
 
 /// Export current sync config as a template
@@ -1442,6 +1514,7 @@ pub fn export_sync_template(
             delete_orphans: profile.delete_orphans,
             parallel_streams: profile.parallel_streams,
             compression_mode: profile.compression_mode.clone(),
+            symlink_mode: profile.symlink_mode,
         },
         exclude_patterns: exclude_patterns.to_vec(),
         schedule: schedule.cloned(),
@@ -1708,6 +1781,9 @@ mod tests {
             modified: Some(Utc::now()),
             is_dir: false,
             checksum: None,
+            checksum_algorithm: None,
+            checksum_weak: false,
+            link_target: None,
         };
 
         let options = CompareOptions::default();
@@ -1716,6 +1792,78 @@ mod tests {
         assert_eq!(status, SyncStatus::LocalOnly);
     }
 
+    #[test]
+    fn test_resolve_link_path() {
+        assert_eq!(resolve_link_path("/srv/app", "releases/42"), "/srv/app/releases/42");
+        assert_eq!(resolve_link_path("/srv/app/releases/42", "../../shared/./logs"), "/srv/app/shared/logs");
+        assert_eq!(resolve_link_path("/srv/app", "/etc/nginx/"), "/etc/nginx");
+        assert_eq!(resolve_link_path("/", "../.."), "/");
+    }
+
+    #[test]
+    fn test_compare_file_pair_links() {
+        let now = Utc::now();
+        let link = |target: &str, modified: DateTime<Utc>| FileInfo {
+            name: "current".to_string(),
+            path: "/srv/app/current".to_string(),
+            size: 0,
+            modified: Some(modified),
+            is_dir: false,
+            checksum: None,
+            checksum_algorithm: None,
+            checksum_weak: false,
+            link_target: Some(target.to_string()),
+        };
+        let options = CompareOptions::default();
+
+        let status = compare_file_pair(Some(&link("releases/42", now)), Some(&link("releases/42", now - chrono::Duration::hours(1))), &options);
+        assert_eq!(status, SyncStatus::Identical);
+        let status = compare_file_pair(Some(&link("releases/43", now)), Some(&link("releases/42", now - chrono::Duration::hours(1))), &options);
+        assert_eq!(status, SyncStatus::LocalNewer);
+        let status = compare_file_pair(Some(&link("releases/43", now)), Some(&link("releases/42", now)), &options);
+        assert_eq!(status, SyncStatus::Conflict);
+    }
+
+    #[test]
+    fn test_compare_file_pair_checksum_algorithms() {
+        use crate::providers::HashAlgorithm;
+        let now = Utc::now();
+        let file = |hash: &str, algorithm: Option<HashAlgorithm>, modified: DateTime<Utc>| FileInfo {
+            name: "report.pdf".to_string(),
+            path: "/docs/report.pdf".to_string(),
+            size: 1024,
+            modified: Some(modified),
+            is_dir: false,
+            checksum: Some(hash.to_string()),
+            checksum_algorithm: algorithm,
+            checksum_weak: false,
+            link_target: None,
+        };
+        let options = CompareOptions { compare_checksum: true, ..Default::default() };
+        let older = now - chrono::Duration::hours(1);
+
+        // Same provider hash: identical despite the timestamps
+        let status = compare_file_pair(Some(&file("ab12", Some(HashAlgorithm::QuickXor), now)), Some(&file("ab12", Some(HashAlgorithm::QuickXor), older)), &options);
+        assert_eq!(status, SyncStatus::Identical);
+        // Local SHA-256 scans leave the algorithm unset
+        let status = compare_file_pair(Some(&file("ab12", None, now)), Some(&file("ab12", Some(HashAlgorithm::Sha256), older)), &options);
+        assert_eq!(status, SyncStatus::Identical);
+        // Hashes of different algorithms are not compared
+        let status = compare_file_pair(Some(&file("ab12", None, now)), Some(&file("ab12", Some(HashAlgorithm::Md5), older)), &options);
+        assert_eq!(status, SyncStatus::LocalNewer);
+
+        // A differing strong hash is a conflict, a differing ETag-derived one proves nothing
+        let md5 = Some(HashAlgorithm::Md5);
+        let status = compare_file_pair(Some(&file("ab12", md5, now)), Some(&file("cd34", md5, now)), &options);
+        assert_eq!(status, SyncStatus::Conflict);
+        let etag = FileInfo { checksum_weak: true, ..file("cd34", md5, now) };
+        let status = compare_file_pair(Some(&file("ab12", md5, now)), Some(&etag), &options);
+        assert_eq!(status, SyncStatus::Identical);
+        let etag = FileInfo { checksum_weak: true, ..file("ab12", md5, older) };
+        let status = compare_file_pair(Some(&file("ab12", md5, now)), Some(&etag), &options);
+        assert_eq!(status, SyncStatus::Identical);
+    }
+
     #[test]
     fn test_classify_sync_error_network() {
         let err = classify_sync_error("Connection refused by remote host", Some("test.txt"));
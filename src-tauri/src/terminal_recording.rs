@@ -0,0 +1,373 @@
+//! Terminal session recording and replay
+//!
+//! Local PTY shells and SSH shells can be recorded to asciicast v2 files
+//! (`~/.config/aeroftp/recordings/*.cast`): a JSON header line followed by one
+//! `[elapsed, code, data]` line per event. Output (`o`), typed input (`i`) and
+//! resizes (`r`) are recorded, so a recording shows both what was shown and
+//! what was typed. Files are written event by event and are owner-only.
+//!
+//! Replay emits the recorded output on a `pty-output-{id}` event channel, the
+//! same one live terminals use. Recordings can be moved into an AeroVault.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+use tokio::task::JoinHandle;
+
+/// Pauses longer than this are shortened during replay
+const DEFAULT_MAX_IDLE_SECS: f64 = 2.0;
+
+// ============ Settings ============
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordingSettings {
+    /// Record every terminal session, whatever the frontend asks for
+    #[serde(default)]
+    pub record_all: bool,
+}
+
+/// `~/.config/aeroftp`. There is no fallback: recordings contain typed input and
+/// must not end up in the working directory.
+fn app_config_dir() -> Result<PathBuf, String> {
+    dirs::config_dir()
+        .map(|dir| dir.join("aeroftp"))
+        .ok_or_else(|| "Cannot determine the config directory".to_string())
+}
+
+fn settings_path() -> Result<PathBuf, String> {
+    Ok(app_config_dir()?.join("terminal_recording.json"))
+}
+
+fn load_settings() -> RecordingSettings {
+    settings_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn recordings_dir() -> Result<PathBuf, String> {
+    Ok(app_config_dir()?.join("recordings"))
+}
+
+/// Resolve a recording file name inside the recordings directory
+fn recording_path(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') || !name.ends_with(".cast") {
+        return Err(format!("Invalid recording name: {}", name));
+    }
+    Ok(recordings_dir()?.join(name))
+}
+
+/// Whether a new session should be recorded (`requested` comes from the frontend)
+pub fn should_record(requested: Option<bool>) -> bool {
+    requested == Some(true) || load_settings().record_all
+}
+
+// ============ Recording ============
+
+/// asciicast v2 header
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CastHeader {
+    pub version: u32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+}
+
+/// Writes one terminal session to an asciicast v2 file
+pub struct SessionRecorder {
+    file: Mutex<File>,
+    started: Instant,
+    name: String,
+}
+
+impl SessionRecorder {
+    /// Start a recording; the file name is derived from the session id and start time
+    pub fn create(session_id: &str, title: &str, width: u32, height: u32) -> Result<Arc<Self>, String> {
+        let dir = recordings_dir()?;
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create recordings folder: {}", e))?;
+        let _ = crate::credential_store::ensure_secure_permissions(&dir);
+
+        let now = chrono::Utc::now();
+        let name = format!("{}-{}.cast", now.format("%Y%m%d-%H%M%S"), session_id);
+        let path = dir.join(&name);
+        let mut file = File::create(&path).map_err(|e| format!("Cannot create recording: {}", e))?;
+        let _ = crate::credential_store::ensure_secure_permissions(&path);
+
+        let header = CastHeader {
+            version: 2,
+            width,
+            height,
+            timestamp: Some(now.timestamp()),
+            title: Some(title.to_string()),
+            env: HashMap::from([("TERM".to_string(), "xterm-256color".to_string())]),
+        };
+        let line = serde_json::to_string(&header).map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| format!("Cannot write recording: {}", e))?;
+
+        tracing::info!("Terminal: recording session {} to {}", session_id, name);
+        Ok(Arc::new(Self { file: Mutex::new(file), started: Instant::now(), name }))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn output(&self, data: &str) {
+        self.event("o", data);
+    }
+
+    pub fn input(&self, data: &str) {
+        self.event("i", data);
+    }
+
+    pub fn resize(&self, cols: u32, rows: u32) {
+        self.event("r", &format!("{}x{}", cols, rows));
+    }
+
+    fn event(&self, code: &str, data: &str) {
+        let elapsed = (self.started.elapsed().as_secs_f64() * 1e6).round() / 1e6;
+        let Ok(line) = serde_json::to_string(&(elapsed, code, data)) else {
+            return;
+        };
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(file, "{}", line) {
+            tracing::warn!("Terminal: cannot write recording {}: {}", self.name, e);
+        }
+    }
+}
+
+// ============ Reading ============
+
+/// One recorded event: seconds since start, event code, data
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastEvent {
+    pub time: f64,
+    pub code: String,
+    pub data: String,
+}
+
+/// Parse an asciicast v2 recording. Malformed event lines (e.g. a line cut
+/// short when the app was killed) are skipped.
+pub fn parse_cast(content: &str) -> Result<(CastHeader, Vec<CastEvent>), String> {
+    let mut lines = content.lines();
+    let header: CastHeader = lines.next()
+        .ok_or("Empty recording")
+        .and_then(|line| serde_json::from_str(line).map_err(|_| "Invalid asciicast header"))?;
+    if header.version != 2 {
+        return Err(format!("Unsupported asciicast version {}", header.version));
+    }
+    let events = lines
+        .filter_map(|line| serde_json::from_str::<(f64, String, String)>(line).ok())
+        .map(|(time, code, data)| CastEvent { time, code, data })
+        .collect();
+    Ok((header, events))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingInfo {
+    pub name: String,
+    pub size: u64,
+    pub title: Option<String>,
+    /// Unix time the session started
+    pub timestamp: Option<i64>,
+    pub width: u32,
+    pub height: u32,
+    /// Seconds between the start and the last event
+    pub duration: f64,
+}
+
+// ============ Replay ============
+
+/// Running replays keyed by replay id
+pub struct TerminalReplayState {
+    replays: HashMap<String, JoinHandle<()>>,
+    next_id: u64,
+}
+
+pub type TerminalReplayManager = Arc<tokio::sync::Mutex<TerminalReplayState>>;
+
+pub fn create_terminal_replay_state() -> TerminalReplayManager {
+    Arc::new(tokio::sync::Mutex::new(TerminalReplayState { replays: HashMap::new(), next_id: 1 }))
+}
+
+/// Delays between output events, scaled by `speed` and with pauses capped at `max_idle`
+fn replay_schedule(events: &[CastEvent], speed: f64, max_idle: f64) -> Vec<(Duration, &str)> {
+    let mut previous = 0.0;
+    events.iter()
+        .filter(|event| event.code == "o")
+        .map(|event| {
+            let gap = (event.time - previous).clamp(0.0, max_idle) / speed;
+            previous = event.time;
+            (Duration::from_secs_f64(gap), event.data.as_str())
+        })
+        .collect()
+}
+
+// ============ Commands ============
+
+#[tauri::command]
+pub async fn terminal_recording_get_settings() -> Result<RecordingSettings, String> {
+    Ok(load_settings())
+}
+
+#[tauri::command]
+pub async fn terminal_recording_set_settings(settings: RecordingSettings) -> Result<(), String> {
+    let path = settings_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// Saved recordings, newest first
+#[tauri::command]
+pub async fn terminal_recordings_list() -> Result<Vec<RecordingInfo>, String> {
+    let Ok(entries) = std::fs::read_dir(recordings_dir()?) else {
+        return Ok(Vec::new());
+    };
+    let mut recordings: Vec<RecordingInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".cast") {
+                return None;
+            }
+            let content = std::fs::read_to_string(entry.path()).ok()?;
+            let (header, events) = parse_cast(&content).ok()?;
+            Some(RecordingInfo {
+                name,
+                size: content.len() as u64,
+                title: header.title,
+                timestamp: header.timestamp,
+                width: header.width,
+                height: header.height,
+                duration: events.last().map(|e| e.time).unwrap_or(0.0),
+            })
+        })
+        .collect();
+    recordings.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(recordings)
+}
+
+#[tauri::command]
+pub async fn terminal_recording_delete(name: String) -> Result<(), String> {
+    std::fs::remove_file(recording_path(&name)?).map_err(|e| format!("Cannot delete recording: {}", e))
+}
+
+/// Add a recording to an AeroVault v2 and remove the plain file unless `keep_plain`
+#[tauri::command]
+pub async fn terminal_recording_to_vault(
+    name: String,
+    vault_path: String,
+    password: String,
+    keep_plain: Option<bool>,
+) -> Result<(), String> {
+    let path = recording_path(&name)?;
+    crate::aerovault_v2::vault_v2_add_files(vault_path, password, vec![path.to_string_lossy().to_string()]).await?;
+    if !keep_plain.unwrap_or(false) {
+        std::fs::remove_file(&path).map_err(|e| format!("Recording stored, but the plain file could not be removed: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Replay a recording on `pty-output-{replay_id}`; `terminal-replay-finished-{replay_id}`
+/// is emitted at the end. Returns the replay id and the recorded terminal size.
+#[tauri::command]
+pub async fn terminal_recording_replay(
+    app: AppHandle,
+    state: State<'_, TerminalReplayManager>,
+    name: String,
+    speed: Option<f64>,
+    max_idle: Option<f64>,
+) -> Result<serde_json::Value, String> {
+    let content = std::fs::read_to_string(recording_path(&name)?)
+        .map_err(|e| format!("Cannot read recording: {}", e))?;
+    let (header, events) = parse_cast(&content)?;
+    let speed = speed.filter(|s| *s > 0.0).unwrap_or(1.0);
+    let max_idle = max_idle.filter(|m| *m > 0.0).unwrap_or(DEFAULT_MAX_IDLE_SECS);
+    let schedule: Vec<(Duration, String)> = replay_schedule(&events, speed, max_idle)
+        .into_iter()
+        .map(|(delay, data)| (delay, data.to_string()))
+        .collect();
+
+    let mut mgr = state.lock().await;
+    mgr.replays.retain(|_, task| !task.is_finished());
+    let replay_id = format!("replay-{}", mgr.next_id);
+    mgr.next_id += 1;
+
+    let output_event = format!("pty-output-{}", replay_id);
+    let finished_event = format!("terminal-replay-finished-{}", replay_id);
+    let task = tokio::spawn(async move {
+        for (delay, data) in schedule {
+            tokio::time::sleep(delay).await;
+            let _ = app.emit(&output_event, data);
+        }
+        let _ = app.emit(&finished_event, "finished");
+    });
+    mgr.replays.insert(replay_id.clone(), task);
+
+    Ok(serde_json::json!({
+        "replay_id": replay_id,
+        "width": header.width,
+        "height": header.height,
+    }))
+}
+
+#[tauri::command]
+pub async fn terminal_replay_stop(state: State<'_, TerminalReplayManager>, replay_id: String) -> Result<(), String> {
+    if let Some(task) = state.lock().await.replays.remove(&replay_id) {
+        task.abort();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cast() {
+        let content = concat!(
+            "{\"version\":2,\"width\":80,\"height\":24,\"timestamp\":1700000000,\"title\":\"ssh\"}\n",
+            "[0.25,\"o\",\"$ \"]\n",
+            "[1.5,\"i\",\"ls\\r\"]\n",
+            "[1.75,\"o\",\"file.txt\\r\\n\"]\n",
+            "[2.0,\"o\",\"trunc",
+        );
+        let (header, events) = parse_cast(content).unwrap();
+        assert_eq!((header.width, header.height), (80, 24));
+        assert_eq!(header.title.as_deref(), Some("ssh"));
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1], CastEvent { time: 1.5, code: "i".to_string(), data: "ls\r".to_string() });
+
+        assert!(parse_cast("{\"version\":1,\"width\":80,\"height\":24}").is_err());
+        assert!(parse_cast("").is_err());
+    }
+
+    #[test]
+    fn test_replay_schedule() {
+        let event = |time: f64, code: &str| CastEvent { time, code: code.to_string(), data: String::new() };
+        let events = vec![event(0.5, "o"), event(1.0, "i"), event(10.0, "o"), event(10.5, "o")];
+        let delays: Vec<f64> = replay_schedule(&events, 2.0, 2.0).iter().map(|(d, _)| d.as_secs_f64()).collect();
+        assert_eq!(delays, vec![0.25, 1.0, 0.25]);
+    }
+
+    #[test]
+    fn test_recording_path_rejects_traversal() {
+        assert!(recording_path("20260101-120000-pty-1.cast").is_ok());
+        assert!(recording_path("../secrets.cast").is_err());
+        assert!(recording_path("notes.txt").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! FTPS/WebDAV Server Certificate Verification (TOFU UX)
+//!
+//! Pre-check probe for TLS certificates no CA vouches for, mirroring the SSH
+//! host key flow. Returns the fingerprint to the frontend for user approval;
+//! approved certificates are pinned per host:port in `known_tls_hosts`.
+
+use crate::providers::tls::{self, CertPin, TlsProbe};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// Certificates awaiting user acceptance (host:port → (DER, timestamp))
+type PendingCerts = HashMap<String, (Vec<u8>, Instant)>;
+
+static PENDING_CERTS: LazyLock<Mutex<PendingCerts>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Maximum age for pending certificates before auto-cleanup (5 minutes)
+const PENDING_CERT_TTL: Duration = Duration::from_secs(300);
+
+/// Result of a certificate probe
+#[derive(Debug, Clone, Serialize)]
+pub struct TlsCertInfo {
+    /// "trusted" (valid CA chain) | "known" | "unknown" | "changed"
+    pub status: String,
+    /// SHA-256 certificate fingerprint: "SHA256:AB:CD:..."
+    pub fingerprint: String,
+    /// SHA-256 of the public key, usable as `cert_pin`: "sha256/base64..."
+    pub spki: Option<String>,
+    pub host: String,
+    pub port: u16,
+}
+
+/// Key for the PENDING_CERTS map
+fn pending_key(host: &str, port: u16) -> String {
+    format!("{}:{}", host, port)
+}
+
+/// Remove expired entries from PENDING_CERTS
+fn cleanup_expired(map: &mut PendingCerts) {
+    let now = Instant::now();
+    map.retain(|_, (_, ts)| now.duration_since(*ts) < PENDING_CERT_TTL);
+}
+
+/// Probe a server's TLS certificate without authenticating.
+/// `protocol` is "ftp" (explicit AUTH TLS), "ftps" (implicit) or "webdav".
+/// `ca_cert` is the profile's PEM bundle, so servers signed by a private CA count as trusted.
+#[tauri::command]
+pub async fn tls_check_certificate(
+    host: String,
+    port: u16,
+    protocol: String,
+    proxy: Option<String>,
+    ca_cert: Option<String>,
+) -> Result<TlsCertInfo, String> {
+    let probe = match protocol.as_str() {
+        "ftp" => TlsProbe::FtpAuthTls,
+        "ftps" | "webdav" | "https" => TlsProbe::Direct,
+        other => return Err(format!("Unsupported protocol for certificate check: {}", other)),
+    };
+    let proxy = crate::providers::proxy::from_setting(proxy.as_deref(), None).map_err(|e| e.to_string())?;
+    let proxy = proxy.as_ref().filter(|p| !p.bypasses(&host));
+    let ca_certs = match ca_cert.as_deref().map(str::trim).filter(|pem| !pem.is_empty()) {
+        Some(pem) => tls::parse_ca_certs(pem).map_err(|e| e.to_string())?,
+        None => Vec::new(),
+    };
+
+    // A certificate the system trusts needs no approval, unless a different one was approved
+    let verified = tls::fetch_certificate(proxy, &host, port, probe, true, &ca_certs).await;
+    let (der, ca_valid) = match verified {
+        Ok(der) => (der, true),
+        Err(_) => (
+            tls::fetch_certificate(proxy, &host, port, probe, false, &[]).await.map_err(|e| e.to_string())?,
+            false,
+        ),
+    };
+
+    let status = match tls::known_certificate(&host, port) {
+        Some(pin) if pin.matches(&der) => "known",
+        Some(_) => "changed",
+        None if ca_valid => "trusted",
+        None => "unknown",
+    };
+
+    if status == "unknown" || status == "changed" {
+        tracing::info!("TLS certificate probe: {} certificate for {}:{}", status, host, port);
+        let mut map = PENDING_CERTS.lock().unwrap_or_else(|e| e.into_inner());
+        cleanup_expired(&mut map);
+        map.insert(pending_key(&host, port), (der.clone(), Instant::now()));
+    }
+
+    Ok(TlsCertInfo {
+        status: status.to_string(),
+        fingerprint: CertPin::for_certificate(&der).to_string(),
+        spki: tls::spki_sha256(&der).map(|hash| CertPin::Spki(hash).to_string()),
+        host,
+        port,
+    })
+}
+
+/// Accept a pending certificate and pin it for host:port
+#[tauri::command]
+pub async fn tls_accept_certificate(host: String, port: u16) -> Result<(), String> {
+    let der = {
+        let mut map = PENDING_CERTS.lock().unwrap_or_else(|e| e.into_inner());
+        map.remove(&pending_key(&host, port))
+            .map(|(der, _)| der)
+            .ok_or_else(|| format!("No pending certificate for {}:{}", host, port))?
+    };
+
+    tls::learn_certificate(&host, port, &der)
+        .map_err(|e| format!("Failed to save certificate: {}", e))?;
+
+    tracing::info!("TLS certificate accepted and pinned for {}:{}", host, port);
+    Ok(())
+}
+
+/// Forget the certificate approved for host:port
+#[tauri::command]
+pub async fn tls_remove_certificate(host: String, port: u16) -> Result<(), String> {
+    tls::forget_certificate(&host, port)
+        .map_err(|e| format!("Failed to remove certificate: {}", e))?;
+    tracing::info!("Removed pinned TLS certificate for {}:{}", host, port);
+    Ok(())
+}
@@ -1,6 +1,10 @@
 //! Remote Vault support — download .aerovault files from remote servers,
 //! operate locally, and upload changes back.
 //!
+//! Providers that implement `read_range` can also open a remote vault in place:
+//! only the header and manifest are fetched to list it, and single entries are
+//! extracted by fetching just their chunk ranges.
+//!
 //! Security hardening (v2.2.4 audit remediation):
 //! - Symlink detection before zero-fill (RB-006, SEC-005)
 //! - Path canonicalization for starts_with check (RB-006)
@@ -9,9 +13,17 @@
 //! - Null byte validation (RB-013)
 //! - UTF-8 path handling without unwrap (RB-011)
 
+use std::io::Write;
 use std::path::PathBuf;
+use secrecy::SecretString;
+use secrecy::zeroize::Zeroize;
 use tauri::State;
+use crate::aerovault_v2::{VaultIndex, HEADER_SIZE, NONCE_SIZE, TAG_SIZE};
 use crate::provider_commands::ProviderState;
+use crate::providers::{ProviderError, StorageProvider};
+
+/// Bytes fetched per ranged read when extracting an entry (many chunks per round-trip)
+const RANGE_WINDOW: u64 = 8 * 1024 * 1024;
 
 /// Validate a path has no null bytes (defense-in-depth for C FFI providers).
 fn validate_no_null_bytes(path: &str) -> Result<(), String> {
@@ -21,6 +33,201 @@ fn validate_no_null_bytes(path: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Validate a remote vault path (extension, traversal, null bytes).
+fn validate_remote_vault_path(remote_path: &str) -> Result<(), String> {
+    validate_no_null_bytes(remote_path)?;
+
+    if !remote_path.ends_with(".aerovault") {
+        return Err("File must have .aerovault extension".into());
+    }
+
+    if remote_path.contains("..") {
+        return Err("Path traversal not allowed".into());
+    }
+    Ok(())
+}
+
+/// Map a ranged-read failure to a user-facing message.
+fn range_error(e: ProviderError) -> String {
+    match e {
+        ProviderError::NotSupported(_) => {
+            "This connection does not support ranged reads. Download the vault instead.".into()
+        }
+        other => format!("Ranged read failed: {}", other),
+    }
+}
+
+/// Fetch header + manifest of a remote vault and unlock it.
+async fn load_remote_index(
+    provider: &mut Box<dyn StorageProvider>,
+    remote_path: &str,
+    password: &SecretString,
+) -> Result<VaultIndex, String> {
+    let prefix_len = HEADER_SIZE as u64 + 4;
+    let prefix = provider.read_range(remote_path, 0, prefix_len)
+        .await
+        .map_err(range_error)?;
+    let manifest_len = VaultIndex::manifest_len(&prefix)?;
+
+    let mut manifest = provider.read_range(remote_path, prefix_len, manifest_len as u64)
+        .await
+        .map_err(range_error)?;
+    if manifest.len() < manifest_len {
+        return Err("Invalid vault: manifest truncated".into());
+    }
+
+    let index = VaultIndex::unlock(&prefix[..HEADER_SIZE], &manifest, password);
+    manifest.zeroize();
+    index
+}
+
+/// Unlock a remote vault and describe it in the same shape as `vault_v2_open`.
+async fn open_remote_vault(
+    provider: &mut Box<dyn StorageProvider>,
+    remote_path: &str,
+    password: &SecretString,
+) -> Result<serde_json::Value, String> {
+    let index = load_remote_index(provider, remote_path, password).await?;
+    let manifest = &index.manifest;
+
+    Ok(serde_json::json!({
+        "version": index.header.version,
+        "cascade_mode": index.header.flags.cascade_mode,
+        "chunk_size": index.header.chunk_size,
+        "created": manifest.created,
+        "modified": manifest.modified,
+        "description": manifest.description,
+        "file_count": manifest.entries.len(),
+        "files": manifest.entries.iter().map(|e| serde_json::json!({
+            "name": e.name,
+            "size": e.size,
+            "is_dir": e.is_dir,
+            "modified": e.modified,
+        })).collect::<Vec<_>>()
+    }))
+}
+
+/// Decrypt one entry of a remote vault to `dest_path`, fetching only its chunk range.
+async fn extract_remote_entry(
+    provider: &mut Box<dyn StorageProvider>,
+    remote_path: &str,
+    password: &SecretString,
+    entry_name: &str,
+    dest_path: &str,
+) -> Result<(), String> {
+    let index = load_remote_index(provider, remote_path, password).await?;
+    let entry = index.find_entry(entry_name)
+        .ok_or_else(|| format!("Entry '{}' not found in vault", entry_name))?
+        .clone();
+
+    if entry.is_dir {
+        std::fs::create_dir_all(dest_path)
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
+        return Ok(());
+    }
+
+    let (start, total_len) = index.entry_range(&entry);
+    let max_chunk = index.max_encrypted_chunk_len();
+
+    let out_file = std::fs::File::create(dest_path)
+        .map_err(|e| format!("Failed to create output: {}", e))?;
+    let mut writer = std::io::BufWriter::new(out_file);
+
+    let result: Result<(), String> = async {
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut fetched: u64 = 0;
+
+        for chunk_idx in 0..entry.chunk_count {
+            // Chunk format: length (4 bytes) + encrypted data
+            let mut needed = 4usize;
+            loop {
+                while buffer.len() < needed {
+                    if fetched >= total_len {
+                        return Err("Vault entry data truncated".to_string());
+                    }
+                    let window = RANGE_WINDOW.min(total_len - fetched);
+                    let data = provider.read_range(remote_path, start + fetched, window)
+                        .await
+                        .map_err(range_error)?;
+                    if data.is_empty() {
+                        return Err("Vault entry data truncated".to_string());
+                    }
+                    fetched += data.len() as u64;
+                    buffer.extend_from_slice(&data);
+                }
+                if needed > 4 {
+                    break;
+                }
+                let chunk_len = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as usize;
+                // Every chunk carries at least a nonce and a tag; anything shorter is corrupt
+                if chunk_len < NONCE_SIZE + TAG_SIZE || chunk_len > max_chunk {
+                    return Err(format!("Corrupt vault: invalid chunk length {} in chunk {}", chunk_len, chunk_idx));
+                }
+                needed = 4 + chunk_len;
+            }
+
+            // Decrypt chunk (chunk index verified via AAD)
+            let mut plaintext = index.decrypt_chunk(&buffer[4..needed], chunk_idx)?;
+            writer.write_all(&plaintext)
+                .map_err(|e| format!("Failed to write: {}", e))?;
+            plaintext.zeroize();
+
+            buffer[..needed].zeroize();
+            buffer.drain(..needed);
+        }
+
+        writer.flush()
+            .map_err(|e| format!("Failed to flush: {}", e))
+    }.await;
+
+    if let Err(e) = result {
+        drop(writer);
+        let _ = std::fs::remove_file(dest_path);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Open a remote .aerovault in place: reads only the header and manifest.
+/// Returns the same metadata shape as `vault_v2_open`.
+#[tauri::command]
+pub async fn vault_v2_open_remote(
+    state: State<'_, ProviderState>,
+    remote_path: String,
+    password: String,
+) -> Result<serde_json::Value, String> {
+    validate_remote_vault_path(&remote_path)?;
+    let pwd = SecretString::from(password);
+
+    let mut provider_guard = state.provider.lock().await;
+    let provider = provider_guard.as_mut()
+        .ok_or("No active connection. Connect to a server first.")?;
+
+    open_remote_vault(provider, &remote_path, &pwd).await
+}
+
+/// Extract a single entry from a remote .aerovault, fetching only its chunk range.
+#[tauri::command]
+pub async fn vault_v2_extract_entry_remote(
+    state: State<'_, ProviderState>,
+    remote_path: String,
+    password: String,
+    entry_name: String,
+    dest_path: String,
+) -> Result<String, String> {
+    validate_remote_vault_path(&remote_path)?;
+    validate_no_null_bytes(&dest_path)?;
+    let pwd = SecretString::from(password);
+
+    let mut provider_guard = state.provider.lock().await;
+    let provider = provider_guard.as_mut()
+        .ok_or("No active connection. Connect to a server first.")?;
+
+    extract_remote_entry(provider, &remote_path, &pwd, &entry_name, &dest_path).await?;
+    Ok(dest_path)
+}
+
 /// Download a remote .aerovault file to a temporary local path.
 /// Returns the temporary local file path.
 #[tauri::command]
@@ -179,3 +386,92 @@ pub fn vault_v2_cleanup_temp(local_path: String) -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::memory::MemoryProvider;
+
+    const PASSWORD: &str = "correct horse battery staple";
+
+    /// Build a vault holding one file of `content` and serve it at `/v.aerovault`.
+    /// Returns the provider, the scratch directory and the entry name.
+    async fn remote_vault(content: &[u8]) -> (Box<dyn StorageProvider>, tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join("v.aerovault").to_string_lossy().to_string();
+        let source = dir.path().join("notes.bin");
+        std::fs::write(&source, content).unwrap();
+
+        crate::aerovault_v2::vault_v2_create(vault_path.clone(), PASSWORD.into(), Some("test".into()), false)
+            .await
+            .unwrap();
+        crate::aerovault_v2::vault_v2_add_files(
+            vault_path.clone(),
+            PASSWORD.into(),
+            vec![source.to_string_lossy().to_string()],
+        )
+        .await
+        .unwrap();
+
+        let mut provider = MemoryProvider::default();
+        provider.files.insert("/v.aerovault".into(), std::fs::read(&vault_path).unwrap());
+        (Box::new(provider), dir, "notes.bin".into())
+    }
+
+    fn password() -> SecretString {
+        SecretString::from(PASSWORD.to_string())
+    }
+
+    fn content() -> Vec<u8> {
+        (0..300_000).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn test_open_remote_lists_entries() {
+        let data = content();
+        let (mut provider, _dir, name) = remote_vault(&data).await;
+
+        let info = open_remote_vault(&mut provider, "/v.aerovault", &password()).await.unwrap();
+        assert_eq!(info["description"], "test");
+        assert_eq!(info["file_count"], 1);
+        assert_eq!(info["files"][0]["name"], name.as_str());
+        assert_eq!(info["files"][0]["size"], data.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_open_remote_wrong_password_fails() {
+        let (mut provider, _dir, _) = remote_vault(b"secret").await;
+        let wrong = SecretString::from("wrong".to_string());
+        assert!(open_remote_vault(&mut provider, "/v.aerovault", &wrong).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_extract_remote_entry_roundtrip() {
+        let data = content();
+        let (mut provider, dir, name) = remote_vault(&data).await;
+        let dest = dir.path().join("out.bin").to_string_lossy().to_string();
+
+        extract_remote_entry(&mut provider, "/v.aerovault", &password(), &name, &dest).await.unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_extract_remote_rejects_zero_chunk_length() {
+        let (mut provider, dir, name) = remote_vault(&content()).await;
+
+        // Zero the length prefix of the entry's first chunk
+        let index = load_remote_index(&mut provider, "/v.aerovault", &password()).await.unwrap();
+        let entry = index.find_entry(&name).unwrap().clone();
+        let (start, _) = index.entry_range(&entry);
+        let memory = provider.as_any_mut().downcast_mut::<MemoryProvider>().unwrap();
+        let bytes = memory.files.get_mut("/v.aerovault").unwrap();
+        bytes[start as usize..start as usize + 4].copy_from_slice(&0u32.to_le_bytes());
+
+        let dest = dir.path().join("out.bin").to_string_lossy().to_string();
+        let err = extract_remote_entry(&mut provider, "/v.aerovault", &password(), &name, &dest)
+            .await
+            .unwrap_err();
+        assert!(err.contains("invalid chunk length 0"), "{}", err);
+        assert!(!std::path::Path::new(&dest).exists());
+    }
+}
@@ -296,6 +296,7 @@ const App: React.FC = () => {
     info: HostKeyInfo | null;
     host: string;
     port: number;
+    kind?: 'ssh' | 'tls';
     resolve: ((accepted: boolean) => void) | null;
   }>({ visible: false, info: null, host: '', port: 22, resolve: null });
   const [compressDialogState, setCompressDialogState] = useState<{ files: { name: string; path: string; size: number; isDir: boolean }[]; defaultName: string; outputDir: string } | null>(null);
@@ -1797,13 +1798,50 @@ const App: React.FC = () => {
     }
   };
 
+  // TOFU certificate check for WebDAV over HTTPS, same flow as SSH host keys
+  const checkWebdavCertificate = async (params: ConnectionParams): Promise<boolean> => {
+    // A pin or disabled verification already decides which certificate is accepted
+    if (params.options?.certPin || params.options?.verifyCert === false) return true;
+    let host = params.server;
+    let port = params.port || 443;
+    if (/^https?:\/\//i.test(params.server)) {
+      try {
+        const url = new URL(params.server);
+        if (url.protocol === 'http:') return true;
+        host = url.hostname;
+        port = url.port ? parseInt(url.port, 10) : 443;
+      } catch {
+        return true; // provider_connect reports the malformed URL
+      }
+    } else if (port === 80) {
+      return true;
+    }
+    try {
+      const cert = await invoke<{ status: string; fingerprint: string }>('tls_check_certificate', {
+        host, port, protocol: 'webdav', caCert: params.options?.caCert || null,
+      });
+      if (cert.status === 'trusted' || cert.status === 'known') return true;
+      const info: HostKeyInfo = { status: cert.status as HostKeyInfo['status'], fingerprint: cert.fingerprint, algorithm: 'X.509' };
+      return new Promise<boolean>((resolve) => {
+        setHostKeyDialog({ visible: true, info, host, port, kind: 'tls', resolve });
+      });
+    } catch (error) {
+      notify.error('Certificate check failed', String(error));
+      return false;
+    }
+  };
+
   const handleHostKeyAccept = async () => {
-    const { info, host, port, resolve } = hostKeyDialog;
+    const { info, host, port, kind, resolve } = hostKeyDialog;
     try {
-      if (info?.status === 'changed' && info.changed_line !== undefined) {
-        await invoke('sftp_remove_host_key', { host, port, line: info.changed_line });
+      if (kind === 'tls') {
+        await invoke('tls_accept_certificate', { host, port });
+      } else {
+        if (info?.status === 'changed' && info.changed_line !== undefined) {
+          await invoke('sftp_remove_host_key', { host, port, line: info.changed_line });
+        }
+        await invoke('sftp_accept_host_key', { host, port });
       }
-      await invoke('sftp_accept_host_key', { host, port });
       resolve?.(true);
     } catch (error) {
       notify.error('Failed to save host key', String(error));
@@ -1933,9 +1971,18 @@ const App: React.FC = () => {
           private_key_path: effectiveParams.options?.private_key_path || null,
           key_passphrase: effectiveParams.options?.key_passphrase || null,
           timeout: effectiveParams.options?.timeout || 30,
+          // SFTP transfer options
+          sftp_transfer_mode: effectiveParams.options?.sftpTransferMode || null,
+          sftp_pipeline_depth: effectiveParams.options?.sftpPipelineDepth || null,
+          sftp_block_size: effectiveParams.options?.sftpBlockSize || null,
+          sftp_preserve: effectiveParams.options?.sftpPreserve || null,
           // FTP/FTPS-specific options
           tls_mode: effectiveParams.options?.tlsMode || (protocol === 'ftps' ? 'implicit' : undefined),
           verify_cert: effectiveParams.options?.verifyCert !== undefined ? effectiveParams.options.verifyCert : true,
+          // FTPS/WebDAV TLS trust
+          ca_cert: effectiveParams.options?.caCert || null,
+          client_cert: effectiveParams.options?.clientCert || null,
+          cert_pin: effectiveParams.options?.certPin || null,
           // Filen-specific options
           two_factor_code: effectiveParams.options?.two_factor_code || null,
         };
@@ -1947,6 +1994,9 @@ const App: React.FC = () => {
           const accepted = await checkSftpHostKey(effectiveParams.server, effectiveParams.port || 22);
           if (!accepted) { setLoading(false); return; }
         }
+        if (protocol === 'webdav' && !(await checkWebdavCertificate(effectiveParams))) {
+          setLoading(false); return;
+        }
         const connHost = effectiveParams.server || getProviderHostFallback(protocol, effectiveParams.username);
         const { resolvedIp: connIp, connectingLogId } = await logConnectionSteps(connHost, effectiveParams.port || 443, protocol);
         await invoke('provider_connect', { params: providerParams });
@@ -2310,12 +2360,14 @@ const App: React.FC = () => {
             }
           }
 
+          const account = targetSession.connectionParams?.options?.oauthAccount;
           await invoke('oauth2_connect', {
             params: {
               provider: oauthProvider,
               client_id: clientId,
               client_secret: clientSecret,
               ...(region && { region }),
+              ...(account && { account }),
             }
           });
         }
@@ -2362,8 +2414,17 @@ const App: React.FC = () => {
           private_key_path: connectParams.options?.private_key_path || null,
           key_passphrase: connectParams.options?.key_passphrase || null,
           timeout: connectParams.options?.timeout || 30,
+          // SFTP transfer options
+          sftp_transfer_mode: connectParams.options?.sftpTransferMode || null,
+          sftp_pipeline_depth: connectParams.options?.sftpPipelineDepth || null,
+          sftp_block_size: connectParams.options?.sftpBlockSize || null,
+          sftp_preserve: connectParams.options?.sftpPreserve || null,
           tls_mode: connectParams.options?.tlsMode || (protocol === 'ftps' ? 'implicit' : undefined),
           verify_cert: connectParams.options?.verifyCert !== undefined ? connectParams.options.verifyCert : true,
+          // FTPS/WebDAV TLS trust
+          ca_cert: connectParams.options?.caCert || null,
+          client_cert: connectParams.options?.clientCert || null,
+          cert_pin: connectParams.options?.certPin || null,
           two_factor_code: connectParams.options?.two_factor_code || null,
         };
 
@@ -2373,6 +2434,9 @@ const App: React.FC = () => {
           const accepted = await checkSftpHostKey(connectParams.server, connectParams.port || 22);
           if (!accepted) throw new Error('Host key rejected by user');
         }
+        if (protocol === 'webdav' && !(await checkWebdavCertificate(connectParams))) {
+          throw new Error('Server certificate rejected by user');
+        }
         await invoke('provider_connect', { params: providerParams });
         if (targetSession.remotePath && targetSession.remotePath !== '/') {
           try { await invoke('provider_change_dir', { path: targetSession.remotePath }); } catch (e) { console.warn('Restore path failed', e); }
@@ -5842,6 +5906,7 @@ const App: React.FC = () => {
           info={hostKeyDialog.info}
           host={hostKeyDialog.host}
           port={hostKeyDialog.port}
+          kind={hostKeyDialog.kind}
           onAccept={handleHostKeyAccept}
           onReject={handleHostKeyReject}
         />
@@ -6197,9 +6262,18 @@ const App: React.FC = () => {
                       private_key_path: normalizedParams.options?.private_key_path || null,
                       key_passphrase: normalizedParams.options?.key_passphrase || null,
                       timeout: normalizedParams.options?.timeout || 30,
+                      // SFTP transfer options
+                      sftp_transfer_mode: normalizedParams.options?.sftpTransferMode || null,
+                      sftp_pipeline_depth: normalizedParams.options?.sftpPipelineDepth || null,
+                      sftp_block_size: normalizedParams.options?.sftpBlockSize || null,
+                      sftp_preserve: normalizedParams.options?.sftpPreserve || null,
                       // FTP/FTPS-specific options
                       tls_mode: normalizedParams.options?.tlsMode || (normalizedParams.protocol === 'ftps' ? 'implicit' : undefined),
                       verify_cert: normalizedParams.options?.verifyCert !== undefined ? normalizedParams.options.verifyCert : true,
+                      // FTPS/WebDAV TLS trust
+                      ca_cert: normalizedParams.options?.caCert || null,
+                      client_cert: normalizedParams.options?.clientCert || null,
+                      cert_pin: normalizedParams.options?.certPin || null,
                       // Filen-specific options
                       two_factor_code: normalizedParams.options?.two_factor_code || null,
                     };
@@ -6210,6 +6284,7 @@ const App: React.FC = () => {
                       const accepted = await checkSftpHostKey(normalizedParams.server, normalizedParams.port || 22);
                       if (!accepted) return;
                     }
+                    if (normalizedParams.protocol === 'webdav' && !(await checkWebdavCertificate(normalizedParams))) return;
                     const savedConnHost = normalizedParams.server || getProviderHostFallback(normalizedParams.protocol, normalizedParams.username);
                     const { resolvedIp: savedIp, connectingLogId: savedConnLogId } = await logConnectionSteps(savedConnHost, normalizedParams.port || 443, normalizedParams.protocol || 'ftp');
                     await invoke('provider_connect', { params: providerParams });
@@ -178,6 +178,8 @@ const SetupWizard: React.FC<{
             if (connExtra.client_id) params.client_id = connExtra.client_id;
             if (connExtra.client_secret) params.client_secret = connExtra.client_secret;
             if (connExtra.region) params.region = connExtra.region;
+            // Account the tokens were bound to on authorization
+            if (connExtra.account) params.account = connExtra.account;
         }
         if (OAUTH1_PROTOCOLS.includes(selectedProtocol)) {
             if (connExtra.consumer_key) params.consumer_key = connExtra.consumer_key;
@@ -220,14 +222,19 @@ const SetupWizard: React.FC<{
                     logger.error('client_id and client_secret are required');
                     return;
                 }
+                // Re-authorizing keeps the account AeroCloud was bound to
+                const account = connExtra.account || undefined;
                 // Full OAuth2 flow (opens browser, waits for callback)
                 await invoke('oauth2_full_auth', {
-                    params: { provider, client_id: clientId, client_secret: clientSecret, region }
+                    params: { provider, client_id: clientId, client_secret: clientSecret, region, account }
                 });
                 // Connect to verify and get account info
                 const result = await invoke<{ display_name: string; account_email: string | null }>('oauth2_connect', {
-                    params: { provider, client_id: clientId, client_secret: clientSecret, region }
+                    params: { provider, client_id: clientId, client_secret: clientSecret, region, account }
                 });
+                if (result.account_email) {
+                    setConnExtra(prev => ({ ...prev, account: result.account_email as string }));
+                }
                 setOauthAuthorized(true);
                 setOauthEmail(result.account_email || result.display_name);
             } else if (OAUTH1_PROTOCOLS.includes(selectedProtocol)) {
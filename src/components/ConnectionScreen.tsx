@@ -1159,7 +1159,7 @@ export const ConnectionScreen: React.FC<ConnectionScreenProps> = ({
                                                 protocol: protocol as ProviderType,
                                                 initialPath: '/',
                                                 localInitialPath: quickConnectDirs.localDir,
-                                                ...(extraOptions?.region && { options: { region: extraOptions.region } }),
+                                                ...(extraOptions && { options: extraOptions }),
                                             };
                                             const newServers = [...existingServers, newServer];
                                             await secureStoreAndClean('server_profiles', SERVERS_STORAGE_KEY, newServers).catch(() => { });
@@ -1987,21 +1987,6 @@ export const ConnectionScreen: React.FC<ConnectionScreenProps> = ({
                                             </div>
                                         </div>
 
-                                        <div className="bg-blue-50 dark:bg-blue-900/10 p-3 rounded-lg border border-blue-100 dark:border-blue-900/30 text-xs text-blue-800 dark:text-blue-200">
-                                            <p className="font-medium mb-1">{t('connection.megaRequirement')}</p>
-                                            <p className="opacity-80">
-                                                {t('connection.megaRequirementDesc')}
-                                                <a
-                                                    href="https://mega.io/cmd"
-                                                    target="_blank"
-                                                    rel="noopener noreferrer"
-                                                    className="block mt-1 underline hover:text-blue-600 dark:hover:text-blue-300"
-                                                >
-                                                    {t('connection.downloadMegacmd')}
-                                                </a>
-                                            </p>
-                                        </div>
-
                                         <div className="bg-red-50 dark:bg-red-900/10 p-3 rounded-lg border border-red-100 dark:border-red-900/30">
                                             <label className="flex items-center gap-3 cursor-pointer">
                                                 <input
@@ -14,6 +14,8 @@ interface HostKeyDialogProps {
     info: HostKeyInfo | null;
     host: string;
     port: number;
+    /** 'tls' shows the dialog for a server certificate instead of an SSH host key */
+    kind?: 'ssh' | 'tls';
     onAccept: () => void;
     onReject: () => void;
 }
@@ -23,6 +25,7 @@ export const HostKeyDialog: React.FC<HostKeyDialogProps> = ({
     info,
     host,
     port,
+    kind = 'ssh',
     onAccept,
     onReject,
 }) => {
@@ -31,6 +34,7 @@ export const HostKeyDialog: React.FC<HostKeyDialogProps> = ({
     if (!visible || !info || (info.status !== 'unknown' && info.status !== 'changed')) return null;
 
     const isChanged = info.status === 'changed';
+    const isTls = kind === 'tls';
 
     return (
         <div
@@ -58,13 +62,17 @@ export const HostKeyDialog: React.FC<HostKeyDialogProps> = ({
                             }
                         </div>
                         <h3 className="text-lg font-semibold text-gray-900 dark:text-white">
-                            {isChanged ? t('protocol.hostKeyChangedTitle') : t('protocol.hostKeyNewTitle')}
+                            {isTls
+                                ? (isChanged ? t('protocol.tlsCertChangedTitle') : t('protocol.tlsCertNewTitle'))
+                                : (isChanged ? t('protocol.hostKeyChangedTitle') : t('protocol.hostKeyNewTitle'))}
                         </h3>
                     </div>
 
                     {/* Body text */}
                     <p className="text-sm text-gray-600 dark:text-gray-400 leading-relaxed mb-4">
-                        {isChanged ? t('protocol.hostKeyChangedBody') : t('protocol.hostKeyNewBody')}
+                        {isTls
+                            ? (isChanged ? t('protocol.tlsCertChangedBody') : t('protocol.tlsCertNewBody'))
+                            : (isChanged ? t('protocol.hostKeyChangedBody') : t('protocol.hostKeyNewBody'))}
                     </p>
 
                     {/* MITM warning (key changed only) */}
@@ -115,7 +123,9 @@ export const HostKeyDialog: React.FC<HostKeyDialogProps> = ({
                                 : 'text-amber-600 dark:text-amber-400 hover:bg-amber-50 dark:hover:bg-amber-900/20'
                         }`}
                     >
-                        {isChanged ? t('protocol.hostKeyAcceptNewAction') : t('protocol.hostKeyTrustAction')}
+                        {isChanged
+                            ? (isTls ? t('protocol.tlsCertAcceptNewAction') : t('protocol.hostKeyAcceptNewAction'))
+                            : t('protocol.hostKeyTrustAction')}
                     </button>
                 </div>
             </div>
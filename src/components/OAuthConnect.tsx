@@ -3,7 +3,7 @@
  * Handles OAuth2 authentication for cloud providers (Google Drive, Dropbox, OneDrive)
  */
 
-import React, { useState, useEffect } from 'react';
+import React, { useState, useEffect, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { open } from '@tauri-apps/plugin-dialog';
 import { ExternalLink, LogIn, CheckCircle, AlertCircle, Loader2, Settings, FolderOpen, Save, LogOut, RefreshCw, Eye, EyeOff } from 'lucide-react';
@@ -14,7 +14,7 @@ import { logger } from '../utils/logger';
 
 interface OAuthConnectProps {
   provider: 'googledrive' | 'dropbox' | 'onedrive' | 'box' | 'pcloud' | 'zohoworkdrive';
-  onConnected: (displayName: string, extraOptions?: { region?: string }) => void;
+  onConnected: (displayName: string, extraOptions?: { region?: string; oauthAccount?: string }) => void;
   disabled?: boolean;
   initialLocalPath?: string;
   onLocalPathChange?: (path: string) => void;
@@ -148,8 +148,10 @@ export const OAuthConnect: React.FC<OAuthConnectProps> = ({
   onConnectionNameChange,
 }) => {
   const { t } = useI18n();
-  const { isAuthenticating, error, startAuth, connect, hasTokens, logout } = useOAuth2();
+  const { isAuthenticating, error, startAuth, connect, hasTokens, listAccounts, logout } = useOAuth2();
   const [hasExistingTokens, setHasExistingTokens] = useState(false);
+  const [accounts, setAccounts] = useState<string[]>([]);
+  const [selectedAccount, setSelectedAccount] = useState<string | undefined>(undefined);
   const [showCredentialsForm, setShowCredentialsForm] = useState(false);
   const [clientId, setClientId] = useState('');
   const [clientSecret, setClientSecret] = useState('');
@@ -185,16 +187,27 @@ export const OAuthConnect: React.FC<OAuthConnectProps> = ({
     }
   };
 
-  // Check for existing tokens on mount
+  // Check for signed-in accounts (or a sign-in not yet bound to one)
+  const checkTokens = useCallback(async () => {
+    setIsChecking(true);
+    const signedIn = await listAccounts(oauthProvider);
+    setAccounts(signedIn);
+    setSelectedAccount(signedIn[0]);
+    setHasExistingTokens(signedIn.length > 0 || await hasTokens(oauthProvider));
+    setIsChecking(false);
+  }, [oauthProvider, hasTokens, listAccounts]);
+
   useEffect(() => {
-    const checkTokens = async () => {
-      setIsChecking(true);
-      const exists = await hasTokens(oauthProvider);
-      setHasExistingTokens(exists);
-      setIsChecking(false);
-    };
     checkTokens();
-  }, [oauthProvider, hasTokens]);
+  }, [checkTokens]);
+
+  const connectedOptions = (account: string | null | undefined) => {
+    const options = {
+      ...(isZoho && { region: zohoRegion }),
+      ...(account && { oauthAccount: account }),
+    };
+    return Object.keys(options).length > 0 ? options : undefined;
+  };
 
   // Load saved credentials from secure credential store (fallback: localStorage for migration)
   // Reset credentials first when provider changes to avoid showing stale values
@@ -263,8 +276,8 @@ export const OAuthConnect: React.FC<OAuthConnectProps> = ({
       // The callback server in Rust handles this automatically
 
       // After successful auth, connect to the provider
-      const displayName = await connect(params);
-      onConnected(displayName, isZoho ? { region: zohoRegion } : undefined);
+      const result = await connect(params);
+      onConnected(result.display_name, connectedOptions(result.account_email));
     } catch (e) {
       console.error('OAuth error:', e);
     }
@@ -285,11 +298,12 @@ export const OAuthConnect: React.FC<OAuthConnectProps> = ({
         client_id: clientId,
         client_secret: clientSecret,
         ...(isZoho && { region: zohoRegion }),
+        ...(selectedAccount && { account: selectedAccount }),
       };
       logger.debug('[OAuthConnect] Calling oauth2_connect...');
-      const displayName = await connect(params);
-      logger.debug('[OAuthConnect] Connected, displayName:', displayName);
-      onConnected(displayName, isZoho ? { region: zohoRegion } : undefined);
+      const result = await connect(params);
+      logger.debug('[OAuthConnect] Connected, displayName:', result.display_name);
+      onConnected(result.display_name, connectedOptions(result.account_email ?? selectedAccount));
     } catch (e) {
       console.error('[OAuthConnect] Quick connect error:', e);
     }
@@ -298,9 +312,9 @@ export const OAuthConnect: React.FC<OAuthConnectProps> = ({
   const handleLogout = async () => {
     setIsLoggingOut(true);
     try {
-      await logout(oauthProvider);
-      setHasExistingTokens(false);
+      await logout(oauthProvider, selectedAccount);
       setWantsNewAccount(false);
+      await checkTokens();
     } catch (e) {
       console.error('Logout error:', e);
     } finally {
@@ -345,7 +359,19 @@ export const OAuthConnect: React.FC<OAuthConnectProps> = ({
                   {t('connection.active')}
                 </span>
               </div>
-              <span className="text-sm text-gray-500">{t('connection.oauth.previouslyAuthenticated')}</span>
+              {accounts.length > 1 ? (
+                <select
+                  value={selectedAccount}
+                  onChange={(e) => setSelectedAccount(e.target.value)}
+                  className="mt-1 w-full px-2 py-1 bg-gray-50 dark:bg-gray-700 border border-gray-300 dark:border-gray-600 rounded-lg text-sm"
+                >
+                  {accounts.map(account => (
+                    <option key={account} value={account}>{account}</option>
+                  ))}
+                </select>
+              ) : (
+                <span className="text-sm text-gray-500">{selectedAccount || t('connection.oauth.previouslyAuthenticated')}</span>
+              )}
             </div>
           </div>
         </div>
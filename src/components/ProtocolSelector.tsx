@@ -14,9 +14,10 @@ import {
     HardDrive,
     ChevronDown,
     ExternalLink,
-    Pencil
+    Pencil,
+    Gauge
 } from 'lucide-react';
-import { ProviderType, FtpTlsMode } from '../types';
+import { ProviderType, FtpTlsMode, SftpTransferMode } from '../types';
 import { useTranslation } from '../i18n';
 import { getProviderById, resolveS3Endpoint } from '../providers';
 import { BoxLogo, PCloudLogo, AzureLogo, FilenLogo, FourSharedLogo, ZohoWorkDriveLogo, InternxtLogo, KDriveLogo, JottacloudLogo, DrimeCloudLogo, FileLuLogo } from './ProviderLogos';
@@ -547,9 +548,17 @@ interface ProtocolFieldsProps {
         private_key_path?: string;
         key_passphrase?: string;
         timeout?: number;
+        sftpTransferMode?: SftpTransferMode;
+        sftpPipelineDepth?: number;
+        sftpBlockSize?: number;
+        sftpPreserve?: string;
         // FTP/FTPS-specific
         tlsMode?: FtpTlsMode;
         verifyCert?: boolean;
+        // WebDAV TLS trust
+        caCert?: string;
+        clientCert?: string;
+        certPin?: string;
     };
     onChange: (options: ProtocolFieldsProps['options']) => void;
     disabled?: boolean;
@@ -634,6 +643,92 @@ export const ProtocolFields: React.FC<ProtocolFieldsProps> = ({
                         className="w-24 px-4 py-2.5 bg-gray-50 dark:bg-gray-700 border border-gray-300 dark:border-gray-600 rounded-xl"
                     />
                 </div>
+                {/* Transfer: mode, request pipelining and preserved metadata */}
+                <details className="text-sm" open={!!(options.sftpTransferMode || options.sftpPipelineDepth || options.sftpBlockSize || options.sftpPreserve)}>
+                    <summary className="cursor-pointer font-medium text-gray-700 dark:text-gray-300 flex items-center gap-2">
+                        <Gauge size={14} />
+                        {t('protocol.sftpTransfer')}
+                    </summary>
+                    <div className="space-y-3 mt-2">
+                        <div>
+                            <label className="block text-sm font-medium mb-1.5">{t('protocol.sftpTransferMode')}</label>
+                            <select
+                                value={options.sftpTransferMode || 'auto'}
+                                onChange={(e) => onChange({ ...options, sftpTransferMode: e.target.value === 'auto' ? undefined : e.target.value as SftpTransferMode })}
+                                disabled={disabled}
+                                className="w-full px-4 py-2.5 bg-gray-50 dark:bg-gray-700 border border-gray-300 dark:border-gray-600 rounded-xl"
+                            >
+                                <option value="auto">{t('protocol.sftpModeAuto')}</option>
+                                <option value="sftp">SFTP</option>
+                                <option value="scp">SCP</option>
+                                <option value="rsync">rsync</option>
+                            </select>
+                        </div>
+                        <div className="flex gap-3">
+                            <div>
+                                <label className="block text-sm font-medium mb-1.5">{t('protocol.sftpPipelineDepth')}</label>
+                                <input
+                                    type="number"
+                                    value={options.sftpPipelineDepth || ''}
+                                    onChange={(e) => onChange({ ...options, sftpPipelineDepth: parseInt(e.target.value) || undefined })}
+                                    disabled={disabled}
+                                    min={1}
+                                    max={1024}
+                                    placeholder="64"
+                                    className="w-24 px-4 py-2.5 bg-gray-50 dark:bg-gray-700 border border-gray-300 dark:border-gray-600 rounded-xl"
+                                />
+                            </div>
+                            <div>
+                                <label className="block text-sm font-medium mb-1.5">{t('protocol.sftpBlockSize')}</label>
+                                <select
+                                    value={options.sftpBlockSize || 32768}
+                                    onChange={(e) => {
+                                        const size = parseInt(e.target.value);
+                                        onChange({ ...options, sftpBlockSize: size === 32768 ? undefined : size });
+                                    }}
+                                    disabled={disabled}
+                                    className="px-4 py-2.5 bg-gray-50 dark:bg-gray-700 border border-gray-300 dark:border-gray-600 rounded-xl"
+                                >
+                                    {[8192, 16384, 32768, 65536, 131072, 262144].map(size => (
+                                        <option key={size} value={size}>{size / 1024} KiB</option>
+                                    ))}
+                                </select>
+                            </div>
+                        </div>
+                        <p className="text-xs text-gray-500">{t('protocol.sftpPipelineHelp')}</p>
+                        <div>
+                            <label className="block text-sm font-medium mb-1.5">{t('protocol.sftpPreserve')}</label>
+                            <div className="flex flex-wrap gap-x-4 gap-y-1">
+                                {([
+                                    ['mode', t('protocol.sftpPreserveMode')],
+                                    ['times', t('protocol.sftpPreserveTimes')],
+                                    ['owner', t('protocol.sftpPreserveOwner')],
+                                    ['xattrs', t('protocol.sftpPreserveXattrs')],
+                                    ['hardlinks', t('protocol.sftpPreserveHardlinks')],
+                                ] as const).map(([item, label]) => {
+                                    const selected = (options.sftpPreserve || '').split(',').filter(Boolean);
+                                    return (
+                                        <label key={item} className="flex items-center gap-2 text-sm cursor-pointer">
+                                            <input
+                                                type="checkbox"
+                                                checked={selected.includes(item)}
+                                                onChange={(e) => {
+                                                    const next = e.target.checked
+                                                        ? [...selected, item]
+                                                        : selected.filter(s => s !== item);
+                                                    onChange({ ...options, sftpPreserve: next.join(',') || undefined });
+                                                }}
+                                                disabled={disabled}
+                                                className="rounded border-gray-300 text-blue-500 focus:ring-blue-500"
+                                            />
+                                            {label}
+                                        </label>
+                                    );
+                                })}
+                            </div>
+                        </div>
+                    </div>
+                </details>
             </div>
         );
     }
@@ -977,6 +1072,51 @@ export const ProtocolFields: React.FC<ProtocolFieldsProps> = ({
                         </span>
                     </div>
                 )}
+                {/* TLS trust: pin, private CA and client certificate */}
+                <details className="text-sm" open={!!(options.certPin || options.caCert || options.clientCert)}>
+                    <summary className="cursor-pointer font-medium text-gray-700 dark:text-gray-300 flex items-center gap-2">
+                        <ShieldCheck size={14} />
+                        {t('protocol.tlsTrust')}
+                    </summary>
+                    <div className="space-y-3 mt-2">
+                        <div>
+                            <label className="block text-sm font-medium mb-1.5">{t('protocol.certPin')}</label>
+                            <input
+                                type="text"
+                                value={options.certPin || ''}
+                                onChange={(e) => onChange({ ...options, certPin: e.target.value.trim() || undefined })}
+                                disabled={disabled}
+                                className="w-full px-4 py-2.5 bg-gray-50 dark:bg-gray-700 border border-gray-300 dark:border-gray-600 rounded-xl font-mono text-xs"
+                                placeholder="SHA256:AB:CD:… / sha256/…"
+                                spellCheck={false}
+                            />
+                            <p className="text-xs text-gray-500 mt-1">{t('protocol.certPinHelp')}</p>
+                        </div>
+                        <div>
+                            <label className="block text-sm font-medium mb-1.5">{t('protocol.caCert')}</label>
+                            <textarea
+                                value={options.caCert || ''}
+                                onChange={(e) => onChange({ ...options, caCert: e.target.value || undefined })}
+                                disabled={disabled}
+                                rows={3}
+                                className="w-full px-4 py-2.5 bg-gray-50 dark:bg-gray-700 border border-gray-300 dark:border-gray-600 rounded-xl font-mono text-xs"
+                                placeholder="-----BEGIN CERTIFICATE-----"
+                                spellCheck={false}
+                            />
+                        </div>
+                        <div>
+                            <label className="block text-sm font-medium mb-1.5">{t('protocol.clientCert')}</label>
+                            <input
+                                type="text"
+                                value={options.clientCert || ''}
+                                onChange={(e) => onChange({ ...options, clientCert: e.target.value.trim() || undefined })}
+                                disabled={disabled}
+                                className="w-full px-4 py-2.5 bg-gray-50 dark:bg-gray-700 border border-gray-300 dark:border-gray-600 rounded-xl"
+                            />
+                            <p className="text-xs text-gray-500 mt-1">{t('protocol.clientCertHelp')}</p>
+                        </div>
+                    </div>
+                </details>
                 {!isEditing && (providerConfig?.helpUrl || providerConfig?.signupUrl) && (
                     <div className="flex items-center gap-3 mt-1">
                         {providerConfig.signupUrl && (
@@ -281,15 +281,18 @@ export const SavedServers: React.FC<SavedServersProps> = ({
                         }
                     }
                 }
+                // Account this profile's tokens are bound to (unset until its first connection)
+                const account = server.options?.oauthAccount;
                 const params = {
                     provider: oauthProvider,
                     client_id: credentials.clientId,
                     client_secret: credentials.clientSecret,
                     ...(region && { region }),
+                    ...(account && { account }),
                 };
 
                 // Check if tokens already exist - if so, try to connect directly
-                const hasTokens = await invoke<boolean>('oauth2_has_tokens', { provider: oauthProvider });
+                const hasTokens = await invoke<boolean>('oauth2_has_tokens', { provider: oauthProvider, account });
 
                 if (!hasTokens) {
                     // No tokens - need full auth flow (opens browser)
@@ -314,10 +317,13 @@ export const SavedServers: React.FC<SavedServersProps> = ({
                     }
                 }
 
-                // Save account email to server profile if retrieved
+                // Save account email to server profile if retrieved; the tokens are
+                // now bound to that account, so later connections must name it
                 const updatedUsername = result.account_email || server.username;
+                const oauthAccount = result.account_email || account;
+                const updatedOptions = oauthAccount ? { ...server.options, oauthAccount } : server.options;
                 const updated = servers.map(s =>
-                    s.id === server.id ? { ...s, lastConnected: new Date().toISOString(), username: updatedUsername || s.username } : s
+                    s.id === server.id ? { ...s, lastConnected: new Date().toISOString(), username: updatedUsername || s.username, options: updatedOptions } : s
                 );
                 setServers(updated);
                 saveServers(updated);
@@ -328,6 +334,7 @@ export const SavedServers: React.FC<SavedServersProps> = ({
                     username: updatedUsername,
                     password: '',
                     protocol: server.protocol,
+                    options: updatedOptions,
                     displayName: server.name,
                     providerId: server.providerId,
                 }, server.initialPath, server.localInitialPath);
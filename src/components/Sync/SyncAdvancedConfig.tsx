@@ -10,11 +10,11 @@ import {
     ShieldCheck, RotateCcw, Gauge, Zap, Shrink, HardDrive,
     ArrowDownToLine, ArrowUpFromLine,
     FolderTree, FileDown, Undo2, Trash2, GitCompare, Settings2, Activity,
-    ChevronDown, FlaskConical
+    ChevronDown, FlaskConical, Link2
 } from 'lucide-react';
 import {
     CompareOptions, RetryPolicy, VerifyPolicy, CompressionMode,
-    SyncDirection, ProviderType, TransferOptimizationHints, isFtpProtocol
+    SymlinkMode, SyncDirection, ProviderType, TransferOptimizationHints, isFtpProtocol
 } from '../../types';
 import { useTranslation } from '../../i18n';
 import { BANDWIDTH_OPTIONS } from './syncConstants';
@@ -244,6 +244,21 @@ export const SyncAdvancedConfig: React.FC<SyncAdvancedConfigProps> = React.memo(
                             <option value="10">{t('syncPanel.retries', { count: '10' })}</option>
                         </select>
                     </label>
+                    <label className="flex items-center gap-1" title={t('syncPanel.symlinkModeHint')}>
+                        <Link2 size={12} className="text-sky-400" />
+                        <select
+                            className="sync-adv-select"
+                            value={options.symlink_mode || 'skip'}
+                            onChange={e => onOptionsChange({ ...options, symlink_mode: e.target.value as SymlinkMode })}
+                            disabled={disabled}
+                        >
+                            <option value="skip">{t('syncPanel.symlinkSkip')}</option>
+                            <option value="follow">{t('syncPanel.symlinkFollow')}</option>
+                            {isSftp && (
+                                <option value="copy_as_link">{t('syncPanel.symlinkCopyAsLink')}</option>
+                            )}
+                        </select>
+                    </label>
                 </div>
             </Section>
 
@@ -44,6 +44,7 @@ export function useSyncProfiles(isOpen: boolean): {
                 compare_checksum: profile.compare_checksum,
                 exclude_patterns: [...profile.exclude_patterns],
                 direction: profile.direction,
+                symlink_mode: profile.symlink_mode || 'skip',
             },
             retryPolicy: { ...profile.retry_policy },
             verifyPolicy: profile.verify_policy,
@@ -1145,7 +1145,7 @@ export const SyncPanel: React.FC<SyncPanelProps> = ({
             if (shouldUpload) {
                 const cmd = isProvider ? 'provider_upload_file' : 'upload_file';
                 const args = isProvider
-                    ? { localPath: localFilePath, remotePath: remoteFilePath }
+                    ? { localPath: localFilePath, remotePath: remoteFilePath, linkTarget: item.local_info?.link_target ?? null }
                     : { params: { local_path: localFilePath, remote_path: remoteFilePath } };
 
                 const result = await executeTransferWithRetry(cmd, args, item.relative_path);
@@ -1180,8 +1180,9 @@ export const SyncPanel: React.FC<SyncPanelProps> = ({
                 }
             } else if (shouldDownload) {
                 const cmd = isProvider ? 'provider_download_file' : 'download_file';
+                const linkTarget = isProvider ? item.remote_info?.link_target ?? null : null;
                 const args = isProvider
-                    ? { remotePath: remoteFilePath, localPath: localFilePath }
+                    ? { remotePath: remoteFilePath, localPath: localFilePath, linkTarget }
                     : { params: { remote_path: remoteFilePath, local_path: localFilePath } };
 
                 const result = await executeTransferWithRetry(cmd, args, item.relative_path);
@@ -1197,7 +1198,8 @@ export const SyncPanel: React.FC<SyncPanelProps> = ({
                     const expectedMtime = item.remote_info?.modified || null;
                     updateFileResult(item.relative_path, 'verifying');
 
-                    const vResult = await verifyDownload(localFilePath, expectedSize, expectedMtime);
+                    // Links are recreated, not downloaded: nothing to verify
+                    const vResult = linkTarget ? null : await verifyDownload(localFilePath, expectedSize, expectedMtime);
 
                     if (vResult && !vResult.passed) {
                         verifyFailed++;
@@ -18,6 +18,12 @@ interface OAuthConnectionParams {
   client_id: string;
   client_secret: string;
   region?: string;
+  account?: string;  // Account the tokens are bound to; omit for a new sign-in
+}
+
+export interface OAuth2ConnectResult {
+  display_name: string;
+  account_email: string | null;
 }
 
 interface UseOAuth2Return {
@@ -25,9 +31,10 @@ interface UseOAuth2Return {
   error: string | null;
   startAuth: (params: OAuthConnectionParams) => Promise<OAuthFlowStarted>;
   completeAuth: (params: OAuthConnectionParams, code: string, state: string) => Promise<void>;
-  connect: (params: OAuthConnectionParams) => Promise<string>;
-  hasTokens: (provider: OAuthProvider) => Promise<boolean>;
-  logout: (provider: OAuthProvider) => Promise<void>;
+  connect: (params: OAuthConnectionParams) => Promise<OAuth2ConnectResult>;
+  hasTokens: (provider: OAuthProvider, account?: string) => Promise<boolean>;
+  listAccounts: (provider: OAuthProvider) => Promise<string[]>;
+  logout: (provider: OAuthProvider, account?: string) => Promise<void>;
 }
 
 /**
@@ -79,11 +86,11 @@ export function useOAuth2(): UseOAuth2Return {
   /**
    * Connect to OAuth2 provider after authentication
    */
-  const connect = useCallback(async (params: OAuthConnectionParams): Promise<string> => {
+  const connect = useCallback(async (params: OAuthConnectionParams): Promise<OAuth2ConnectResult> => {
     try {
-      const result = await invoke<{ display_name: string; account_email: string | null }>('oauth2_connect', { params });
+      const result = await invoke<OAuth2ConnectResult>('oauth2_connect', { params });
       setIsAuthenticating(false);
-      return result.display_name;
+      return result;
     } catch (e) {
       const errorMsg = e instanceof Error ? e.message : String(e);
       setError(errorMsg);
@@ -93,11 +100,11 @@ export function useOAuth2(): UseOAuth2Return {
   }, []);
 
   /**
-   * Check if tokens exist for a provider
+   * Check if tokens exist for a provider account (no account: a pending sign-in)
    */
-  const hasTokens = useCallback(async (provider: OAuthProvider): Promise<boolean> => {
+  const hasTokens = useCallback(async (provider: OAuthProvider, account?: string): Promise<boolean> => {
     try {
-      return await invoke<boolean>('oauth2_has_tokens', { provider });
+      return await invoke<boolean>('oauth2_has_tokens', { provider, account });
     } catch (e) {
       console.error('Error checking tokens:', e);
       return false;
@@ -105,11 +112,23 @@ export function useOAuth2(): UseOAuth2Return {
   }, []);
 
   /**
-   * Logout from a provider (clear tokens)
+   * List the accounts signed in for a provider
+   */
+  const listAccounts = useCallback(async (provider: OAuthProvider): Promise<string[]> => {
+    try {
+      return await invoke<string[]>('oauth2_list_accounts', { provider });
+    } catch (e) {
+      console.error('Error listing accounts:', e);
+      return [];
+    }
+  }, []);
+
+  /**
+   * Logout from a provider account (clear its tokens only)
    */
-  const logout = useCallback(async (provider: OAuthProvider): Promise<void> => {
+  const logout = useCallback(async (provider: OAuthProvider, account?: string): Promise<void> => {
     try {
-      await invoke('oauth2_logout', { provider });
+      await invoke('oauth2_logout', { provider, account });
     } catch (e) {
       const errorMsg = e instanceof Error ? e.message : String(e);
       setError(errorMsg);
@@ -124,6 +143,7 @@ export function useOAuth2(): UseOAuth2Return {
     completeAuth,
     connect,
     hasTokens,
+    listAccounts,
     logout,
   };
 }
@@ -55,6 +55,9 @@ export const supportsNativeShareLink = (type: ProviderType): boolean => {
 // FTP/FTPS TLS encryption mode
 export type FtpTlsMode = 'none' | 'explicit' | 'implicit' | 'explicit_if_available';
 
+// SFTP provider transfer mode; 'auto' falls back to rsync or scp when the SFTP subsystem is refused
+export type SftpTransferMode = 'auto' | 'sftp' | 'scp' | 'rsync';
+
 // Provider-specific configuration options
 export interface ProviderOptions {
   // S3-specific
@@ -64,8 +67,10 @@ export interface ProviderOptions {
   accountId?: string; // Cloudflare R2 account ID (used to compute endpoint)
   pathStyle?: boolean;
 
-  // WebDAV-specific
-  // (no extra options needed, uses standard auth)
+  // WebDAV-specific (TLS trust)
+  caCert?: string;            // PEM bundle of additional trusted CAs
+  clientCert?: string;        // Credential vault account holding the client certificate
+  certPin?: string;           // "SHA256:<hex>" fingerprint or "sha256/<base64>" SPKI hash
 
   // FTP/FTPS-specific
   tlsMode?: FtpTlsMode;      // TLS encryption mode
@@ -75,10 +80,15 @@ export interface ProviderOptions {
   private_key_path?: string;  // Path to SSH private key
   key_passphrase?: string;    // Passphrase for encrypted keys
   timeout?: number;           // Connection timeout in seconds
+  sftpTransferMode?: SftpTransferMode; // Unset = auto (SFTP, then rsync or scp)
+  sftpPipelineDepth?: number; // READ/WRITE requests in flight (1-1024, default 64)
+  sftpBlockSize?: number;     // Bytes per request (default 32768)
+  sftpPreserve?: string;      // "mode,times,owner,xattrs,hardlinks" subset
 
   // OAuth-specific (for Google Drive, Dropbox, OneDrive)
   clientId?: string;
   clientSecret?: string;
+  oauthAccount?: string;      // Account the profile's tokens are bound to
 
   // MEGA-specific
   save_session?: boolean;
@@ -237,6 +247,8 @@ export type SyncAction =
   | 'ask_user'
   | 'keep_both';
 
+export type HashAlgorithm = 'md5' | 'sha1' | 'sha256' | 'dropbox_content_hash' | 'quick_xor';
+
 export interface FileInfo {
   name: string;
   path: string;
@@ -244,6 +256,9 @@ export interface FileInfo {
   modified: string | null;
   is_dir: boolean;
   checksum: string | null;
+  checksum_algorithm?: HashAlgorithm | null;  // null: SHA-256 from a local scan
+  checksum_weak?: boolean;  // ETag-derived: only a match is conclusive
+  link_target?: string | null;  // Set for links scanned with symlink_mode 'copy_as_link'
 }
 
 export interface FileComparison {
@@ -261,8 +276,11 @@ export interface CompareOptions {
   compare_checksum: boolean;
   exclude_patterns: string[];
   direction: SyncDirection;
+  symlink_mode?: SymlinkMode;
 }
 
+export type SymlinkMode = 'follow' | 'copy_as_link' | 'skip';
+
 export interface SyncIndexEntry {
   size: number;
   modified: string | null;
@@ -324,6 +342,7 @@ export interface SyncProfile {
   delete_orphans: boolean;
   parallel_streams: number;
   compression_mode: CompressionMode;
+  symlink_mode?: SymlinkMode;
 }
 
 // Phase 3A+: Sync Scheduler
@@ -397,6 +416,8 @@ export interface TransferOptimizationHints {
   preferred_checksum_algo: string | null;
   supports_compression: boolean;
   supports_delta_sync: boolean;
+  pipeline_depth: number;       // Read/write requests in flight per transfer (1 = one at a time)
+  pipeline_block_size: number;  // Bytes per pipelined request (0 = not applicable)
 }
 
 // Multi-Path Sync (#52)
@@ -429,6 +450,7 @@ export interface SyncTemplate {
     delete_orphans: boolean;
     parallel_streams: number;
     compression_mode: CompressionMode;
+    symlink_mode?: SymlinkMode;
   };
   exclude_patterns: string[];
   schedule: SyncSchedule | null;